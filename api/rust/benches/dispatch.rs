@@ -0,0 +1,58 @@
+//! Hot path benchmarks: building results and merging them
+//!
+//! Run with `cargo bench --bench dispatch`. Plain timing loops keep the
+//! crate free of a benchmarking dependency; compare runs on the same machine.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use volt_plugin_api::{PluginResult, ResultAction, ResultAggregator};
+
+const PLUGINS: usize = 8;
+const RESULTS_PER_PLUGIN: usize = 50;
+
+/// Results of one plugin, built the way typical plugins build them
+fn batch(plugin: usize) -> (String, Vec<PluginResult>) {
+    let results = (0..RESULTS_PER_PLUGIN)
+        .map(|i| {
+            let mut result = PluginResult::new(format!("result-{}", i), "Open Visual Studio Code")
+                .with_action(ResultAction::new("reveal", "Show in folder"));
+            result.subtitle = Some("Application".into());
+            result.icon = Some("💻".into());
+            result.score = (i * 7 % 100) as u32;
+            result
+        })
+        .collect();
+    (format!("plugin-{}", plugin), results)
+}
+
+/// Time `f` over enough iterations to smooth out noise
+fn bench(name: &str, mut f: impl FnMut()) {
+    for _ in 0..100 {
+        f();
+    }
+
+    let mut iterations = 0u32;
+    let started = Instant::now();
+    while started.elapsed() < Duration::from_secs(2) {
+        f();
+        iterations += 1;
+    }
+    println!("{:<24} {:>10.2?} / iteration", name, started.elapsed() / iterations);
+}
+
+fn main() {
+    let aggregator = ResultAggregator::new().with_max_results(20);
+
+    bench("build results", || {
+        black_box((0..PLUGINS).map(batch).collect::<Vec<_>>());
+    });
+
+    let batches: Vec<_> = (0..PLUGINS).map(batch).collect();
+    bench("merge", || {
+        black_box(aggregator.merge(black_box(batches.clone())));
+    });
+
+    bench("build and merge", || {
+        black_box(aggregator.merge((0..PLUGINS).map(batch).collect()));
+    });
+}
@@ -0,0 +1,164 @@
+/// Recent actions feed
+///
+/// Keeps the results the user executed across all plugins, newest first and
+/// deduplicated, so the home screen and a history plugin can offer them for
+/// one-keystroke repeat. The feed is persisted as JSON by `VoltPluginAPI`.
+use crate::result::PluginResult;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Maximum number of actions kept in the feed
+pub const MAX_RECENT_ACTIONS: usize = 200;
+
+/// An action the user executed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentAction {
+    /// Plugin that produced the result
+    pub plugin_id: String,
+    /// The executed result, replayed as-is to repeat the action
+    pub result: PluginResult,
+    /// Time of the last execution, in milliseconds since the Unix epoch
+    pub executed_at: u64,
+    /// How many times the action was executed
+    pub count: u32,
+}
+
+/// Deduplicated list of recent actions, newest first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecentActions {
+    actions: Vec<RecentAction>,
+}
+
+impl RecentActions {
+    /// Create an empty feed
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a feed from disk, or an empty feed if the file doesn't exist
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::new());
+        }
+
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read recent actions: {}", e))?;
+
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse recent actions: {}", e))
+    }
+
+    /// Save the feed to disk
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string(self)
+            .map_err(|e| format!("Failed to serialize recent actions: {}", e))?;
+
+        std::fs::write(path, content).map_err(|e| format!("Failed to write recent actions: {}", e))
+    }
+
+    /// Record an execution
+    ///
+    /// Executing the same result again (same plugin and result ID) moves it
+    /// to the front instead of adding a duplicate.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin that produced the result
+    /// * `result` - The executed result
+    /// * `executed_at` - Execution time, in milliseconds since the Unix epoch
+    pub fn record(&mut self, plugin_id: &str, result: &PluginResult, executed_at: u64) {
+        let count = match self
+            .actions
+            .iter()
+            .position(|action| action.plugin_id == plugin_id && action.result.id == result.id)
+        {
+            Some(index) => self.actions.remove(index).count + 1,
+            None => 1,
+        };
+
+        let mut result = result.clone();
+        result.plugin_id = Some(plugin_id.into());
+        result.shortcut = None;
+        result.cache_age_ms = None;
+
+        self.actions.insert(
+            0,
+            RecentAction {
+                plugin_id: plugin_id.to_string(),
+                result,
+                executed_at,
+                count,
+            },
+        );
+        self.actions.truncate(MAX_RECENT_ACTIONS);
+    }
+
+    /// Get the most recent actions, newest first
+    pub fn latest(&self, limit: usize) -> Vec<RecentAction> {
+        self.actions.iter().take(limit).cloned().collect()
+    }
+
+    /// Remove every action of a plugin (e.g., when it is uninstalled)
+    pub fn forget_plugin(&mut self, plugin_id: &str) {
+        self.actions.retain(|action| action.plugin_id != plugin_id);
+    }
+
+    /// Remove the actions executed at or after a time
+    ///
+    /// # Arguments
+    /// * `since` - Time in milliseconds since the Unix epoch
+    pub fn purge_since(&mut self, since: u64) {
+        self.actions.retain(|action| action.executed_at < since);
+    }
+
+    /// Remove all actions
+    pub fn clear(&mut self) {
+        self.actions.clear();
+    }
+}
+
+/// Current time in milliseconds since the Unix epoch
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_deduplicates_and_orders() {
+        let mut feed = RecentActions::new();
+        feed.record("calc", &PluginResult::new("sum", "4"), 1);
+        feed.record("files", &PluginResult::new("report", "report.pdf"), 2);
+        feed.record("calc", &PluginResult::new("sum", "4"), 3);
+
+        let latest = feed.latest(10);
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest[0].plugin_id, "calc");
+        assert_eq!(latest[0].executed_at, 3);
+        assert_eq!(latest[0].count, 2);
+        assert_eq!(latest[0].result.plugin_id.as_deref(), Some("calc"));
+        assert_eq!(latest[1].result.title, "report.pdf");
+        assert_eq!(feed.latest(1).len(), 1);
+
+        feed.purge_since(3);
+        assert_eq!(feed.latest(10).len(), 1);
+        assert_eq!(feed.latest(10)[0].plugin_id, "files");
+    }
+
+    #[test]
+    fn test_feed_is_capped() {
+        let mut feed = RecentActions::new();
+        for i in 0..(MAX_RECENT_ACTIONS as u64 + 10) {
+            feed.record("calc", &PluginResult::new(i.to_string(), "x"), i);
+        }
+
+        let latest = feed.latest(usize::MAX);
+        assert_eq!(latest.len(), MAX_RECENT_ACTIONS);
+        assert_eq!(latest[0].executed_at, MAX_RECENT_ACTIONS as u64 + 9);
+    }
+}
@@ -0,0 +1,661 @@
+/// Result aggregation across plugins
+///
+/// Merges the results returned by every plugin for a query into a single
+/// ranked list, and assigns the keyboard bindings the UI renders next to it.
+use crate::export::{self, ExportFormat};
+use crate::extensions::SendTarget;
+use crate::intents;
+use crate::no_results::NoResultsNote;
+use crate::open_with::{OPEN_WITH_ACTION, OPEN_WITH_LABEL};
+use crate::resources::VirtualPath;
+use crate::result::{KeyHint, PluginResult, ResultAction, ResultIntent};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Highest quick-select slot (Cmd/Ctrl+1 through Cmd/Ctrl+9)
+pub const MAX_QUICK_SELECT: u8 = 9;
+
+/// Keys handled by the launcher itself that plugins cannot rebind
+const RESERVED_KEYS: &[&str] = &["enter", "escape", "up", "down", "tab", "shift+tab"];
+
+/// Key combination bound to a quick-select slot
+pub fn quick_select_key(slot: u8) -> String {
+    format!("Mod+{}", slot)
+}
+
+/// How the score of a result served from cache decays with its age
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StalenessDecay {
+    /// Cached results rank exactly like fresh ones
+    None,
+    /// Multiply the score of every cached result by a fixed factor
+    Constant {
+        /// Multiplier in 0.0..=1.0
+        factor: f64,
+    },
+    /// Halve the score every `half_life`, never dropping below `floor`
+    Exponential {
+        /// Age at which the score is halved
+        half_life: Duration,
+        /// Minimum multiplier in 0.0..=1.0
+        floor: f64,
+    },
+}
+
+impl Default for StalenessDecay {
+    fn default() -> Self {
+        StalenessDecay::Exponential {
+            half_life: Duration::from_secs(600),
+            floor: 0.8,
+        }
+    }
+}
+
+impl StalenessDecay {
+    /// Get the score multiplier for a result of the given age
+    pub fn multiplier(&self, age: Duration) -> f64 {
+        match *self {
+            StalenessDecay::None => 1.0,
+            StalenessDecay::Constant { factor } => factor.clamp(0.0, 1.0),
+            StalenessDecay::Exponential { half_life, floor } => {
+                let half_lives = if half_life.is_zero() {
+                    f64::INFINITY
+                } else {
+                    age.as_secs_f64() / half_life.as_secs_f64()
+                };
+                0.5f64.powf(half_lives).max(floor.clamp(0.0, 1.0))
+            }
+        }
+    }
+
+    /// Apply the decay to a score
+    pub fn apply(&self, score: u32, age: Duration) -> u32 {
+        (score as f64 * self.multiplier(age)).round() as u32
+    }
+}
+
+/// Merges per-plugin result lists into the final ranked output
+#[derive(Debug, Clone, Default)]
+pub struct ResultAggregator {
+    /// Score decay applied to results served from cache
+    decay: StalenessDecay,
+    /// Results the host displays, applied to every plugin
+    max_results: Option<usize>,
+    /// Batch sizes declared by plugins, keyed by plugin ID
+    plugin_caps: HashMap<String, usize>,
+    /// "Send to" targets, with the ID of the plugin offering them
+    send_targets: Vec<(String, SendTarget)>,
+}
+
+impl ResultAggregator {
+    /// Create a new aggregator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use a custom decay function for cached results
+    pub fn with_decay(mut self, decay: StalenessDecay) -> Self {
+        self.decay = decay;
+        self
+    }
+
+    /// Keep at most `max_results` results of each plugin
+    ///
+    /// Pass the `QueryContext::max_results` sent to plugins.
+    pub fn with_max_results(mut self, max_results: usize) -> Self {
+        self.max_results = Some(max_results);
+        self
+    }
+
+    /// Cap the results of individual plugins
+    ///
+    /// # Arguments
+    /// * `caps` - Preferred batch size of each plugin, keyed by plugin ID
+    ///   (see `PluginRegistry::preferred_batch_sizes`)
+    pub fn with_plugin_caps(mut self, caps: impl IntoIterator<Item = (String, usize)>) -> Self {
+        self.plugin_caps.extend(caps);
+        self
+    }
+
+    /// Offer "send to" targets on compatible results
+    ///
+    /// # Arguments
+    /// * `targets` - Targets with the ID of the plugin offering them
+    ///   (see `PluginRegistry::send_targets`)
+    pub fn with_send_targets(mut self, targets: impl IntoIterator<Item = (String, SendTarget)>) -> Self {
+        self.send_targets.extend(targets);
+        self
+    }
+
+    /// Get the number of results kept for a plugin, if limited
+    pub fn cap_for(&self, plugin_id: &str) -> Option<usize> {
+        match (self.max_results, self.plugin_caps.get(plugin_id)) {
+            (Some(max), Some(cap)) => Some(max.min(*cap)),
+            (max, cap) => max.or(cap.copied()),
+        }
+    }
+
+    /// Merge results from several plugins
+    ///
+    /// Results are ranked by score (ties keep plugin order), tagged with the
+    /// producing plugin, and assigned conflict-free keyboard bindings. Results
+    /// served from cache have their score decayed according to their age.
+    /// Plugins returning more results than their cap only keep their best
+    /// ones, and results a plugin returned twice (see
+    /// `PluginResult::dedup_key`) are only listed once.
+    ///
+    /// # Arguments
+    /// * `batches` - Results of each plugin, keyed by plugin ID
+    pub fn merge(&self, batches: Vec<(String, Vec<PluginResult>)>) -> MergedResults {
+        let mut results: Vec<PluginResult> = batches
+            .into_iter()
+            .flat_map(|(plugin_id, results)| self.prepare_batch(plugin_id, results))
+            .collect();
+
+        results.sort_by_key(|result| std::cmp::Reverse(result.score));
+
+        Self::finish(results)
+    }
+
+    /// Merge fallback results, keeping the order of the providers
+    ///
+    /// Unlike `merge`, scores don't matter: results are listed provider by
+    /// provider, each provider's in the order it returned them.
+    ///
+    /// # Arguments
+    /// * `batches` - Fallback results of each provider, keyed by plugin ID, in
+    ///   display order
+    pub fn merge_fallbacks(&self, batches: Vec<(String, Vec<PluginResult>)>) -> MergedResults {
+        let results = batches
+            .into_iter()
+            .flat_map(|(plugin_id, results)| self.prepare_batch(plugin_id, results))
+            .collect();
+
+        Self::finish(results)
+    }
+
+    /// Replace one plugin's results in an already merged list
+    ///
+    /// Used when a plugin refreshes its results while they are on screen
+    /// (see `PluginRegistry::refresh_plugin`). The other plugins' results are
+    /// kept as they were merged, the new batch is prepared like in `merge`,
+    /// and the list is ranked again. Quick-select slots already shown stay
+    /// with their results where possible.
+    ///
+    /// # Arguments
+    /// * `current` - The results on screen
+    /// * `plugin_id` - Plugin whose results are replaced
+    /// * `results` - Its new results; empty to remove its rows
+    pub fn merge_refresh(&self, current: &MergedResults, plugin_id: String, results: Vec<PluginResult>) -> MergedResults {
+        let mut merged: Vec<PluginResult> = current
+            .results
+            .iter()
+            .filter(|result| result.plugin_id.as_deref() != Some(plugin_id.as_str()))
+            .cloned()
+            .collect();
+        let diagnostics = current
+            .diagnostics
+            .iter()
+            .filter(|note| note.plugin_id != plugin_id || results.is_empty())
+            .cloned()
+            .collect();
+        merged.extend(self.prepare_batch(plugin_id, results));
+
+        merged.sort_by_key(|result| std::cmp::Reverse(result.score));
+
+        let mut merged = Self::finish(merged);
+        merged.diagnostics = diagnostics;
+        merged
+    }
+
+    /// Assign keyboard bindings, sanitize the decorations and fill in the
+    /// accessibility metadata of ranked results
+    fn finish(mut results: Vec<PluginResult>) -> MergedResults {
+        Self::assign_shortcuts(&mut results);
+        for result in &mut results {
+            Self::sanitize_key_hints(&mut result.key_hints);
+            result.sanitize_decorations();
+            result.fill_accessibility();
+        }
+
+        MergedResults {
+            results,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Tag, decay and cap the results of one plugin
+    fn prepare_batch(&self, plugin_id: String, mut results: Vec<PluginResult>) -> Vec<PluginResult> {
+        // Shared by every result of the batch
+        let shared_id: Arc<str> = Arc::from(plugin_id.as_str());
+        for result in &mut results {
+            result.plugin_id = Some(shared_id.clone());
+            if let Some(age_ms) = result.cache_age_ms {
+                result.score = self.decay.apply(result.score, Duration::from_millis(age_ms));
+            }
+        }
+
+        let mut seen = HashSet::new();
+        results.retain(|result| seen.insert(result.dedup_key()));
+
+        if let Some(cap) = self.cap_for(&plugin_id).filter(|cap| results.len() > *cap) {
+            results.sort_by_key(|result| std::cmp::Reverse(result.score));
+            results.truncate(cap);
+        }
+
+        for result in &mut results {
+            Self::attach_open_with(result);
+            self.attach_send_targets(&plugin_id, result);
+        }
+
+        results
+    }
+
+    /// Add the "Open with…" action to results opening a file on disk
+    fn attach_open_with(result: &mut PluginResult) {
+        let opens_file = matches!(
+            &result.intent,
+            Some(ResultIntent::OpenFile { path }) if !VirtualPath::is_virtual(path)
+        );
+        if opens_file && !result.actions.iter().any(|action| action.id == OPEN_WITH_ACTION) {
+            result
+                .actions
+                .push(ResultAction::new(OPEN_WITH_ACTION, OPEN_WITH_LABEL));
+        }
+    }
+
+    /// Add the send targets accepting a result's intent as secondary actions
+    ///
+    /// Plugins aren't offered their own targets.
+    fn attach_send_targets(&self, plugin_id: &str, result: &mut PluginResult) {
+        let Some(kind) = result.intent.as_ref().map(|intent| intent.kind()) else {
+            return;
+        };
+
+        for (owner, target) in &self.send_targets {
+            if owner == plugin_id || !target.accepts(kind) {
+                continue;
+            }
+
+            let mut action = ResultAction::new(intents::send_action_id(owner, &target.id), &target.label);
+            action.icon = target.icon.clone();
+            result.actions.push(action);
+        }
+    }
+
+    /// Assign quick-select slots to the merged list
+    ///
+    /// Requested slots are honored in rank order, first claimant wins. The
+    /// remaining free slots then go to the highest ranked results without one.
+    fn assign_shortcuts(results: &mut [PluginResult]) {
+        let mut taken = [false; MAX_QUICK_SELECT as usize + 1];
+        let mut unassigned = Vec::new();
+
+        for (index, result) in results.iter_mut().enumerate() {
+            match result.shortcut.take() {
+                Some(slot) if (1..=MAX_QUICK_SELECT).contains(&slot) && !taken[slot as usize] => {
+                    taken[slot as usize] = true;
+                    result.shortcut = Some(slot);
+                }
+                _ => unassigned.push(index),
+            }
+        }
+
+        let mut free_slots = (1..=MAX_QUICK_SELECT).filter(|slot| !taken[*slot as usize]);
+        for index in unassigned {
+            match free_slots.next() {
+                Some(slot) => results[index].shortcut = Some(slot),
+                None => break,
+            }
+        }
+    }
+
+    /// Drop key hints that collide with launcher keys or with each other
+    fn sanitize_key_hints(hints: &mut Vec<KeyHint>) {
+        let mut seen = HashSet::new();
+        hints.retain(|hint| {
+            let key = hint.key.trim().to_ascii_lowercase();
+            let is_quick_select = key
+                .strip_prefix("mod+")
+                .and_then(|digit| digit.parse::<u8>().ok())
+                .is_some_and(|slot| (1..=MAX_QUICK_SELECT).contains(&slot));
+
+            !key.is_empty()
+                && !is_quick_select
+                && !RESERVED_KEYS.contains(&key.as_str())
+                && seen.insert(key)
+        });
+    }
+}
+
+/// Ranked results of a query, ready to be sent to the UI
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct MergedResults {
+    /// Results in display order
+    pub results: Vec<PluginResult>,
+    /// Why dev mode plugins returned no results, see `crate::no_results`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<NoResultsNote>,
+}
+
+/// Emitted when fresh results replace results previously served from cache
+///
+/// The UI uses this to animate the affected rows.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaleResultsRefreshed {
+    /// Plugin whose results were refreshed
+    pub plugin_id: String,
+    /// IDs of the results that are now fresh
+    pub result_ids: Vec<String>,
+}
+
+/// Where a key press should be delivered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActionRoute {
+    /// Plugin that owns the result
+    pub plugin_id: String,
+    /// Result the key press applies to
+    pub result_id: String,
+    /// Action requested, or None for the default action
+    pub action: Option<String>,
+}
+
+impl MergedResults {
+    /// Resolve a quick-select slot to the result bound to it
+    pub fn route_shortcut(&self, slot: u8) -> Option<ActionRoute> {
+        self.results
+            .iter()
+            .find(|result| result.shortcut == Some(slot))
+            .and_then(|result| Self::route(result, None))
+    }
+
+    /// Resolve a key press on the selected result to one of its key hints
+    ///
+    /// # Arguments
+    /// * `selected` - Index of the selected result
+    /// * `key` - Key combination pressed, e.g. "Shift+Enter"
+    pub fn route_key(&self, selected: usize, key: &str) -> Option<ActionRoute> {
+        let result = self.results.get(selected)?;
+        let hint = result
+            .key_hints
+            .iter()
+            .find(|hint| hint.key.trim().eq_ignore_ascii_case(key.trim()))?;
+
+        Self::route(result, Some(hint.action.clone()))
+    }
+
+    /// Resolve a secondary action chosen on a result
+    ///
+    /// # Arguments
+    /// * `selected` - Index of the result
+    /// * `action_id` - ID of one of the result's `actions`
+    pub fn route_action(&self, selected: usize, action_id: &str) -> Option<ActionRoute> {
+        let result = self.results.get(selected)?;
+        let action = result.actions.iter().find(|action| action.id == action_id)?;
+
+        Self::route(result, Some(action.id.clone()))
+    }
+
+    /// Compare against the previously displayed results
+    ///
+    /// Returns one event per plugin whose stale results have been replaced by
+    /// fresh ones in this merge.
+    pub fn refreshed_since(&self, previous: &MergedResults) -> Vec<StaleResultsRefreshed> {
+        let mut events: Vec<StaleResultsRefreshed> = Vec::new();
+
+        for result in self.results.iter().filter(|result| !result.is_stale()) {
+            let Some(plugin_id) = result.plugin_id.as_deref() else {
+                continue;
+            };
+
+            let was_stale = previous.results.iter().any(|old| {
+                old.is_stale() && old.id == result.id && old.plugin_id.as_deref() == Some(plugin_id)
+            });
+            if !was_stale {
+                continue;
+            }
+
+            match events.iter_mut().find(|event| event.plugin_id == plugin_id) {
+                Some(event) => event.result_ids.push(result.id.clone()),
+                None => events.push(StaleResultsRefreshed {
+                    plugin_id: plugin_id.to_string(),
+                    result_ids: vec![result.id.clone()],
+                }),
+            }
+        }
+
+        events
+    }
+
+    /// Export the results, see `crate::export`
+    ///
+    /// # Arguments
+    /// * `format` - Format of the export
+    /// * `selected` - Indices of the results the user picked, None for all
+    ///
+    /// # Returns
+    /// The rendered export, or Err if an index is out of range
+    pub fn export(&self, format: ExportFormat, selected: Option<&[usize]>) -> Result<String, String> {
+        let Some(selected) = selected else {
+            return export::render(&self.results, format);
+        };
+
+        let results = selected
+            .iter()
+            .map(|&index| {
+                self.results
+                    .get(index)
+                    .cloned()
+                    .ok_or_else(|| format!("No result at index {}", index))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        export::render(&results, format)
+    }
+
+    fn route(result: &PluginResult, action: Option<String>) -> Option<ActionRoute> {
+        Some(ActionRoute {
+            plugin_id: result.plugin_id.as_deref()?.to_string(),
+            result_id: result.id.clone(),
+            action,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(id: &str, score: u32) -> PluginResult {
+        let mut result = PluginResult::new(id, id.to_string());
+        result.score = score;
+        result
+    }
+
+    #[test]
+    fn test_shortcut_assignment_avoids_conflicts() {
+        let merged = ResultAggregator::new().merge(vec![
+            (
+                "apps".to_string(),
+                vec![result("firefox", 90).with_shortcut(3), result("files", 50)],
+            ),
+            (
+                "web".to_string(),
+                vec![result("search", 70).with_shortcut(3), result("bad", 60).with_shortcut(12)],
+            ),
+        ]);
+
+        let slots: Vec<_> = merged
+            .results
+            .iter()
+            .map(|r| (r.id.as_str(), r.shortcut))
+            .collect();
+        assert_eq!(
+            slots,
+            vec![
+                ("firefox", Some(3)),
+                ("search", Some(1)),
+                ("bad", Some(2)),
+                ("files", Some(4)),
+            ]
+        );
+
+        let route = merged.route_shortcut(1).unwrap();
+        assert_eq!(route.plugin_id, "web");
+        assert_eq!(route.result_id, "search");
+        assert!(merged.route_shortcut(9).is_none());
+    }
+
+    #[test]
+    fn test_only_nine_slots_are_assigned() {
+        let results = (0..12).map(|i| result(&format!("r{}", i), 100 - i)).collect();
+        let merged = ResultAggregator::new().merge(vec![("many".to_string(), results)]);
+
+        assert_eq!(merged.results[8].shortcut, Some(9));
+        assert!(merged.results[9..].iter().all(|r| r.shortcut.is_none()));
+    }
+
+    #[test]
+    fn test_plugin_caps_are_enforced_before_merge() {
+        let files = (0..20).map(|i| result(&format!("f{}", i), i)).collect();
+        // The duplicate of "a4" is dropped
+        let apps = (0..5)
+            .map(|i| result(&format!("a{}", i), 50 + i))
+            .chain([result("a4", 10)])
+            .collect();
+        let aggregator = ResultAggregator::new()
+            .with_max_results(8)
+            .with_plugin_caps([("files".to_string(), 3)]);
+
+        assert_eq!(aggregator.cap_for("files"), Some(3));
+        assert_eq!(aggregator.cap_for("apps"), Some(8));
+
+        let merged = aggregator.merge(vec![("files".to_string(), files), ("apps".to_string(), apps)]);
+        let ids: Vec<_> = merged.results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["a4", "a3", "a2", "a1", "a0", "f19", "f18", "f17"]);
+    }
+
+    #[test]
+    fn test_send_targets_are_attached_to_compatible_results() {
+        use crate::result::{IntentKind, ResultIntent};
+
+        let link = result("docs", 10).with_intent(ResultIntent::OpenUrl {
+            url: "https://volt.dev/docs".to_string(),
+        });
+        let aggregator = ResultAggregator::new().with_send_targets([
+            (
+                "slack".to_string(),
+                SendTarget::new("general", "Send to #general").accepting(IntentKind::OpenUrl),
+            ),
+            (
+                "notes".to_string(),
+                SendTarget::new("daily", "Append to daily note").accepting(IntentKind::CopyText),
+            ),
+            (
+                "web".to_string(),
+                SendTarget::new("bookmark", "Bookmark").accepting(IntentKind::OpenUrl),
+            ),
+        ]);
+
+        let merged = aggregator.merge(vec![
+            ("web".to_string(), vec![link]),
+            ("files".to_string(), vec![result("plain", 5)]),
+        ]);
+        let actions: Vec<_> = merged.results[0].actions.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(actions, vec!["send-to:slack:general"]);
+        assert!(merged.results[1].actions.is_empty());
+
+        let route = merged.route_action(0, "send-to:slack:general").unwrap();
+        assert_eq!(
+            intents::parse_send_action(route.action.as_deref().unwrap()),
+            Some(("slack", "general"))
+        );
+    }
+
+    #[test]
+    fn test_file_results_offer_open_with() {
+        let file = |id: &str, path: &str| {
+            result(id, 10).with_intent(ResultIntent::OpenFile {
+                path: path.to_string(),
+            })
+        };
+
+        let merged = ResultAggregator::new().merge(vec![(
+            "files".to_string(),
+            vec![
+                file("report", "/home/ada/report.pdf"),
+                file("archived", "voltres://archives/backup.zip/report.pdf"),
+            ],
+        )]);
+        let actions: Vec<_> = merged.results[0].actions.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(actions, vec![OPEN_WITH_ACTION]);
+        assert!(merged.results[1].actions.is_empty());
+        assert!(merged.route_action(0, OPEN_WITH_ACTION).is_some());
+    }
+
+    #[test]
+    fn test_key_hints_are_sanitized_and_routed() {
+        let hinted = result("doc", 10)
+            .with_key_hint(KeyHint::new("Shift+Enter", "reveal", "Show in folder"))
+            .with_key_hint(KeyHint::new("shift+enter", "other", "Duplicate"))
+            .with_key_hint(KeyHint::new("Mod+2", "steal", "Conflicts with quick-select"))
+            .with_key_hint(KeyHint::new("Escape", "close", "Reserved"))
+            .with_key_hint(KeyHint::new("Mod+C", "copy", "Copy path"));
+
+        let merged = ResultAggregator::new().merge(vec![("files".to_string(), vec![hinted])]);
+        let actions: Vec<_> = merged.results[0]
+            .key_hints
+            .iter()
+            .map(|hint| hint.action.as_str())
+            .collect();
+        assert_eq!(actions, vec!["reveal", "copy"]);
+        assert_eq!(merged.results[0].accessibility.as_ref().unwrap().label, "doc");
+
+        let route = merged.route_key(0, "mod+c").unwrap();
+        assert_eq!(route.action.as_deref(), Some("copy"));
+        assert!(merged.route_key(0, "Mod+2").is_none());
+        assert!(merged.route_key(1, "Mod+C").is_none());
+    }
+
+    #[test]
+    fn test_staleness_decay() {
+        let decay = StalenessDecay::default();
+        assert_eq!(decay.apply(100, Duration::ZERO), 100);
+        assert_eq!(decay.apply(100, Duration::from_secs(60)), 93);
+        assert_eq!(decay.apply(100, Duration::from_secs(86_400)), 80);
+        assert_eq!(StalenessDecay::Constant { factor: 0.5 }.apply(90, Duration::ZERO), 45);
+        assert_eq!(StalenessDecay::None.apply(90, Duration::from_secs(86_400)), 90);
+    }
+
+    #[test]
+    fn test_stale_results_rank_lower_and_refresh_is_reported() {
+        let mut cached = result("note", 100);
+        cached.cache_age_ms = Some(3_600_000);
+        let aggregator = ResultAggregator::new();
+
+        let before = aggregator.merge(vec![
+            ("notes".to_string(), vec![cached]),
+            ("files".to_string(), vec![result("doc", 90)]),
+        ]);
+        assert_eq!(before.results[0].id, "doc");
+        assert_eq!(before.results[1].score, 80);
+
+        let after = aggregator.merge(vec![
+            ("notes".to_string(), vec![result("note", 100)]),
+            ("files".to_string(), vec![result("doc", 90)]),
+        ]);
+        assert_eq!(after.results[0].id, "note");
+        assert_eq!(
+            after.refreshed_since(&before),
+            vec![StaleResultsRefreshed {
+                plugin_id: "notes".to_string(),
+                result_ids: vec!["note".to_string()],
+            }]
+        );
+        assert!(after.refreshed_since(&after).is_empty());
+    }
+}
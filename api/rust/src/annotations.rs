@@ -0,0 +1,124 @@
+/// Decoration of merged results by annotator plugins
+///
+/// After merging, plugins implementing `Annotator` may add tags and
+/// accessories to any result, including other plugins' (e.g., a git plugin
+/// adding a branch badge to folder results). Annotators run concurrently
+/// under a strict time budget; whatever isn't ready when it runs out is
+/// dropped, so a slow annotator never delays the results.
+use crate::result::{Accessory, PluginResult};
+use serde::Serialize;
+use std::time::Duration;
+
+/// Time annotators get to decorate a query's results
+pub const DEFAULT_ANNOTATION_BUDGET: Duration = Duration::from_millis(20);
+
+/// Decorations added to one result
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    /// Position of the result in the slice given to the annotator
+    pub index: usize,
+    /// Tags to add
+    pub tags: Vec<String>,
+    /// Accessories to add
+    pub accessories: Vec<Accessory>,
+}
+
+impl Annotation {
+    /// Create an empty annotation of the result at `index`
+    pub fn new(index: usize) -> Self {
+        Self {
+            index,
+            tags: Vec::new(),
+            accessories: Vec::new(),
+        }
+    }
+
+    /// Add a tag
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Add an accessory
+    pub fn with_accessory(mut self, accessory: Accessory) -> Self {
+        self.accessories.push(accessory);
+        self
+    }
+}
+
+/// Outcome of an annotation pass
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnnotationReport {
+    /// Annotators whose decorations were applied
+    pub annotated: Vec<String>,
+    /// Annotators that didn't answer within the budget
+    pub timed_out: Vec<String>,
+    /// Annotators that panicked
+    pub failed: Vec<String>,
+}
+
+/// Apply annotations to the results they target
+///
+/// Decorations come after the ones the result already has; annotations of
+/// indexes past the end are ignored. The results are sanitized afterwards,
+/// so annotators can't exceed `MAX_TAGS` or `MAX_ACCESSORIES`, and the
+/// decorations that were kept are added to the accessibility description.
+pub fn apply(results: &mut [PluginResult], annotations: Vec<Annotation>) {
+    let mut added = vec![Vec::new(); results.len()];
+    for annotation in annotations {
+        if let Some(result) = results.get_mut(annotation.index) {
+            added[annotation.index].extend(annotation.tags.iter().cloned());
+            added[annotation.index].extend(annotation.accessories.iter().map(Accessory::spoken_text));
+            result.tags.extend(annotation.tags);
+            result.accessories.extend(annotation.accessories);
+        }
+    }
+
+    for (result, added) in results.iter_mut().zip(added) {
+        result.sanitize_decorations();
+
+        let kept: Vec<String> = added
+            .into_iter()
+            .filter(|text| {
+                result.tags.contains(text)
+                    || result.accessories.iter().any(|accessory| accessory.spoken_text() == *text)
+            })
+            .collect();
+        result.extend_accessibility_description(kept);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_appends_and_sanitizes() {
+        let mut results = vec![
+            PluginResult::new("home", "Home").with_tag("folder"),
+            PluginResult::new("volt", "volt"),
+        ];
+        for result in &mut results {
+            result.fill_accessibility();
+        }
+
+        apply(
+            &mut results,
+            vec![
+                Annotation::new(1)
+                    .with_tag("main")
+                    .with_accessory(Accessory::badge("3 changes")),
+                Annotation::new(0).with_tag("Folder"),
+                Annotation::new(7).with_tag("ignored"),
+            ],
+        );
+
+        assert_eq!(results[0].tags, vec!["folder"]);
+        assert_eq!(results[1].tags, vec!["main"]);
+        assert_eq!(results[1].accessories, vec![Accessory::badge("3 changes")]);
+        let description = |result: &PluginResult| result.accessibility.clone().unwrap().description;
+        assert_eq!(description(&results[0]).as_deref(), Some("folder"));
+        assert_eq!(description(&results[1]).as_deref(), Some("main, 3 changes"));
+    }
+}
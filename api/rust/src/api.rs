@@ -4,8 +4,45 @@
 /// with Volt's features, including search, window management, settings, and more.
 // Note: These types are used in doc comments and future functionality
 // They are defined in commands/apps.rs and indexer/mod.rs
+use crate::actions::{RecentAction, RecentActions};
+use crate::api_trace::{ApiCall, ApiTrace, ApiTraceMode, ApiTraceSession, TracedBytes, TracedResponse};
+use crate::audit::{AuditEntry, AuditOutcome, AUDIT_FILE};
+use crate::backends::{PlatformBackend, ShareBackend};
+use crate::builder::{DirectoryLayout, VoltPluginAPIBuilder};
+use crate::clock::{Clock, SystemClock};
+use crate::elevation::{ElevationConfirmer, ElevationRequest};
+use crate::export::{ExportDestination, ExportFormat, EXPORTS_DIR};
+use crate::features::{FeatureSet, HostFeature};
+use crate::i18n::Messages;
+use crate::identity::{PluginIdentities, PluginRename, IDENTITY_FILE};
+use crate::index::{DocumentIndex, IndexBatch, IndexDoc, IndexHit};
+use crate::input::{ClipboardBackend, InputSynthesizer, TextInsertion};
+use crate::invalidation::{CacheEntry, DataChange, Dependency, InvalidationBus, PluginCacheLayer, PLUGIN_CACHE_LAYER};
+use crate::kv::{KvStore, KV_FILE};
+use crate::locks::{self, Recover};
+use crate::logging::Diagnostics;
+use crate::manifest::{PluginInfo, PluginManifest};
+use crate::matching::{self, Tokenizer};
+use crate::marketplace::{GcReport, RetentionPolicy, MARKETPLACE_CACHE_DIR};
+use crate::network::{HostPattern, NetworkGrantHandler, NetworkGrants, NetworkPolicy, NETWORK_GRANTS_FILE};
+use crate::notifications::{Notification, Notifier};
+use crate::patterns::{Regex, RegexCache};
+use crate::power::{PowerMode, PowerMonitor, PowerPolicy, PowerState};
+use crate::result::{CommandSpec, PluginResult};
+use crate::selection::{Selection, SelectionReader};
+use crate::spell::{Correction, SpellCorrector};
+use crate::stats::{UsageEvent, UsageReport, UsageStats, DEFAULT_WINDOWS, STATS_FILE};
+use crate::windows::{WindowEvent, WindowListener, WindowManager};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
 use std::path::PathBuf;
-use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+
+/// Suffix of the temporary files configurations are written to before
+/// being renamed into place
+static NEXT_PARTIAL_ID: AtomicU64 = AtomicU64::new(0);
 
 /// Main API interface provided to plugins
 ///
@@ -16,30 +53,245 @@ use std::sync::{Arc, RwLock};
 pub struct VoltPluginAPI {
     /// Internal state shared across all plugins
     state: Arc<RwLock<PluginAPIState>>,
+    /// Version of Volt reported to plugins
+    volt_version: Arc<str>,
 }
 
 /// Internal state for the plugin API
 struct PluginAPIState {
     /// Application data directory
     app_data_dir: PathBuf,
+    /// Trace session recording or replaying plugin API calls
+    api_trace: Option<Arc<Mutex<ApiTraceSession>>>,
+    /// System clipboard, installed by the host
+    clipboard: Option<Arc<dyn ClipboardBackend>>,
     /// Cache directory for plugins
     cache_dir: PathBuf,
+    /// Decides whether plugins may use capabilities
+    capability_broker: Arc<dyn CapabilityBroker>,
+    /// Source of the timestamps the API writes
+    clock: Arc<dyn Clock>,
     /// Configuration directory
     config_dir: PathBuf,
+    /// Where this instance's diagnostics and plugin logs go
+    diagnostics: Diagnostics,
+    /// Callbacks notified when a plugin configuration changes
+    config_listeners: Vec<ConfigListener>,
+    /// Last known modification time of each configuration file
+    config_mtimes: HashMap<PathBuf, SystemTime>,
+    /// Whether the configuration files have been scanned at least once
+    config_scanned: bool,
+    /// Optional host subsystems available at runtime
+    features: FeatureSet,
+    /// Types text into the focused application, installed by the host
+    input: Option<InputSynthesizer>,
+    /// Routes data changes to the cache entries depending on them
+    invalidation: InvalidationBus,
+    /// Strings of the user's locale, for built-in plugins
+    messages: Messages,
+    /// Reads the text selected in the focused application, installed by the host
+    selection_reader: Option<SelectionReader>,
+    /// Share sheet of the platform, installed by the host
+    share_sheet: Option<Arc<dyn ShareBackend>>,
+    /// Key-value store of each plugin, loaded from disk on first use
+    kv: HashMap<String, KvStore>,
+    /// Displays notifications sent by plugins, installed by the host
+    notifier: Option<Notifier>,
+    /// Asks the user to confirm elevated commands, installed by the host
+    elevation_confirmer: Option<ElevationConfirmer>,
+    /// Hosts users granted plugins beyond their manifest allowlists
+    network_grants: NetworkGrants,
+    /// Asks the user whether a plugin may reach a host outside its allowlist
+    network_grant_handler: Option<NetworkGrantHandler>,
+    /// What the marketplace package cache keeps
+    package_retention: RetentionPolicy,
+    /// Power supply of the machine, reported by the host
+    power_state: PowerState,
+    /// What throttles on battery
+    power_policy: PowerPolicy,
+    /// Callbacks notified when the power state changes
+    power_listeners: Vec<PowerListener>,
+    /// Whether privacy mode is on
+    privacy_mode: bool,
+    /// Callbacks notified when privacy mode is toggled
+    privacy_listeners: Vec<PrivacyListener>,
+    /// Recent actions feed, loaded from disk on first use
+    recent_actions: Option<RecentActions>,
+    /// Callbacks notified when a plugin asks for its results to be refreshed
+    refresh_listeners: Vec<RefreshListener>,
+    /// Patterns compiled by plugins
+    regexes: RegexCache,
+    /// Most recent log lines of each plugin, oldest first
+    recent_logs: HashMap<String, VecDeque<String>>,
+    /// Documents contributed by plugins to the central index
+    index: DocumentIndex,
+    /// Whether misspelled queries are corrected before dispatch
+    spell_correction: bool,
+    /// Tokenizer of this instance, or `None` for the installed one
+    tokenizer: Option<Arc<Tokenizer>>,
+    /// Local usage statistics of each plugin
+    usage: UsageStats,
+    /// Windows `my_stats` reports on, shortest first
+    stats_windows: Vec<Duration>,
+    /// Application windows, installed by the host
+    window_manager: Option<Arc<dyn WindowManager>>,
+    /// Callbacks notified of window events
+    window_listeners: Vec<WindowListener>,
+    /// Download client shared by all plugins, with the shared HTTP cache
+    #[cfg(feature = "download")]
+    downloader: crate::download::Downloader,
+    /// Preview fetcher shared by all plugins, rate limiting hosts
+    #[cfg(feature = "download")]
+    previews: crate::previews::PreviewService,
+    /// Supervisor of the tasks spawned by plugins
+    #[cfg(feature = "isolation")]
+    tasks: crate::runtime::TaskSupervisor,
+    /// State each plugin synchronizes across devices
+    #[cfg(feature = "sync")]
+    sync_scopes: HashMap<String, crate::sync::SyncScope>,
+}
+
+/// Number of log lines kept per plugin for diagnostics
+const MAX_LOG_LINES: usize = 500;
+
+impl Recover for PluginAPIState {
+    fn recover(&mut self) {
+        // Directories, listeners and features are only ever replaced whole;
+        // scan results and the actions feed are reloaded from disk
+        self.config_mtimes.clear();
+        self.config_scanned = false;
+        self.recent_actions = None;
+        self.kv.clear();
+    }
+}
+
+/// Callback invoked when a plugin configuration changes
+pub type ConfigListener = Arc<dyn Fn(&ConfigChange) + Send + Sync>;
+
+/// Callback invoked when privacy mode is toggled, with the new state
+pub type PrivacyListener = Arc<dyn Fn(bool) + Send + Sync>;
+
+/// Callback invoked when the power state changes, with the new state and mode
+pub type PowerListener = Arc<dyn Fn(&PowerState, PowerMode) + Send + Sync>;
+
+/// Callback invoked when a plugin asks for its results to be refreshed, with its ID
+pub type RefreshListener = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// A change to one of a plugin's configuration files
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigChange {
+    /// Plugin owning the configuration
+    pub plugin_id: String,
+    /// Name of the configuration file (without .json extension)
+    pub config_name: String,
+    /// The new configuration
+    pub config: serde_json::Value,
+    /// What caused the change
+    pub source: ConfigChangeSource,
+}
+
+/// Origin of a configuration change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigChangeSource {
+    /// Saved through `VoltPluginAPI::save_config` (e.g., by the settings UI)
+    Saved,
+    /// The file was edited on disk outside of Volt
+    External,
 }
 
 impl VoltPluginAPI {
     /// Create a new plugin API instance
+    ///
+    /// Uses the default components; see `builder` to replace them.
     pub fn new(app_data_dir: PathBuf) -> Self {
-        let cache_dir = app_data_dir.join("cache");
-        let config_dir = app_data_dir.join("config");
+        Self::assemble(DirectoryLayout::new(app_data_dir), VoltPluginAPIBuilder::default())
+    }
+
+    /// Start configuring a plugin API instance
+    pub fn builder() -> VoltPluginAPIBuilder {
+        VoltPluginAPIBuilder::default()
+    }
+
+    /// Create an instance with the components of a builder
+    pub(crate) fn assemble(layout: DirectoryLayout, builder: VoltPluginAPIBuilder) -> Self {
+        let DirectoryLayout {
+            app_data_dir,
+            cache_dir,
+            config_dir,
+        } = layout;
+        let usage = UsageStats::persistent(app_data_dir.join(STATS_FILE)).with_diagnostics(builder.diagnostics.clone());
+        let network_grants = NetworkGrants::new(app_data_dir.join(NETWORK_GRANTS_FILE));
+        let invalidation = InvalidationBus::new();
+        invalidation.register_layer(
+            PLUGIN_CACHE_LAYER,
+            Arc::new(PluginCacheLayer {
+                dir: cache_dir.join("plugins"),
+            }),
+        );
+        #[cfg(feature = "download")]
+        let downloader = crate::download::Downloader::with_config(&builder.http_client).with_http_cache(
+            crate::http_cache::HttpCache::new(cache_dir.join(crate::http_cache::HTTP_CACHE_DIR)),
+        )
+        .with_diagnostics(builder.diagnostics.clone());
+        #[cfg(feature = "download")]
+        if let Some(http_cache) = downloader.http_cache() {
+            invalidation.register_layer(crate::invalidation::HTTP_CACHE_LAYER, Arc::new(http_cache.clone()));
+        }
 
         Self {
             state: Arc::new(RwLock::new(PluginAPIState {
                 app_data_dir,
+                api_trace: None,
+                clipboard: None,
                 cache_dir,
+                capability_broker: builder.capability_broker.unwrap_or_else(|| Arc::new(ManifestCapabilities)),
+                clock: builder.clock.unwrap_or_else(|| Arc::new(SystemClock)),
                 config_dir,
+                diagnostics: builder.diagnostics,
+                config_listeners: Vec::new(),
+                config_mtimes: HashMap::new(),
+                config_scanned: false,
+                features: FeatureSet::empty(),
+                input: None,
+                invalidation,
+                messages: Messages::default(),
+                selection_reader: None,
+                share_sheet: None,
+                kv: HashMap::new(),
+                notifier: None,
+                elevation_confirmer: None,
+                network_grants,
+                network_grant_handler: None,
+                package_retention: RetentionPolicy::default(),
+                power_state: PowerState::default(),
+                power_policy: PowerPolicy::default(),
+                power_listeners: Vec::new(),
+                privacy_mode: false,
+                privacy_listeners: Vec::new(),
+                recent_actions: None,
+                refresh_listeners: Vec::new(),
+                regexes: RegexCache::default(),
+                recent_logs: HashMap::new(),
+                index: DocumentIndex::new(),
+                spell_correction: true,
+                tokenizer: builder.tokenizer,
+                usage,
+                stats_windows: DEFAULT_WINDOWS.to_vec(),
+                window_manager: None,
+                window_listeners: Vec::new(),
+                #[cfg(feature = "download")]
+                downloader,
+                #[cfg(feature = "download")]
+                previews: crate::previews::PreviewService::default(),
+                #[cfg(feature = "sync")]
+                sync_scopes: HashMap::new(),
+                #[cfg(feature = "isolation")]
+                tasks: crate::runtime::TaskSupervisor::new(),
             })),
+            volt_version: builder
+                .volt_version
+                .map(Arc::from)
+                .unwrap_or_else(|| Arc::from(env!("CARGO_PKG_VERSION"))),
         }
     }
 
@@ -50,7 +302,7 @@ impl VoltPluginAPI {
     ///
     /// # Returns
     /// Ok(()) if valid, Err with message if invalid
-    fn validate_plugin_id(plugin_id: &str) -> Result<(), String> {
+    pub(crate) fn validate_plugin_id(plugin_id: &str) -> Result<(), String> {
         // Check if empty
         if plugin_id.is_empty() {
             return Err("Plugin ID cannot be empty".to_string());
@@ -177,10 +429,7 @@ impl VoltPluginAPI {
         // Validate plugin_id to prevent path traversal
         Self::validate_plugin_id(plugin_id)?;
 
-        let state = self
-            .state
-            .read()
-            .map_err(|e| format!("Failed to acquire read lock: {}", e))?;
+        let state = locks::read(&self.state, "plugin API state");
 
         let plugin_dir = state.app_data_dir.join("plugins").join(plugin_id);
 
@@ -202,10 +451,7 @@ impl VoltPluginAPI {
         // Validate plugin_id to prevent path traversal
         Self::validate_plugin_id(plugin_id)?;
 
-        let state = self
-            .state
-            .read()
-            .map_err(|e| format!("Failed to acquire read lock: {}", e))?;
+        let state = locks::read(&self.state, "plugin API state");
 
         let cache_dir = state.cache_dir.join("plugins").join(plugin_id);
 
@@ -227,10 +473,7 @@ impl VoltPluginAPI {
         // Validate plugin_id to prevent path traversal
         Self::validate_plugin_id(plugin_id)?;
 
-        let state = self
-            .state
-            .read()
-            .map_err(|e| format!("Failed to acquire read lock: {}", e))?;
+        let state = locks::read(&self.state, "plugin API state");
 
         let config_dir = state.config_dir.join("plugins").join(plugin_id);
 
@@ -242,6 +485,84 @@ impl VoltPluginAPI {
         Ok(config_dir)
     }
 
+    /// Get the directory an installed plugin package lives in
+    ///
+    /// Packages are unpacked to `<app data>/extensions/<plugin_id>` and
+    /// contain the plugin's `manifest.json`, code and documentation.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    pub fn get_plugin_package_dir(&self, plugin_id: &str) -> Result<PathBuf, String> {
+        // Validate plugin_id to prevent path traversal
+        Self::validate_plugin_id(plugin_id)?;
+
+        let state = locks::read(&self.state, "plugin API state");
+        Ok(state.app_data_dir.join("extensions").join(plugin_id))
+    }
+
+    /// Resolve a file bundled in the plugin package's `assets/` directory
+    ///
+    /// Paths escaping the directory are rejected. With the
+    /// `compressed-assets` feature, a missing asset bundled as `<path>.gz` is
+    /// decompressed into the plugin's cache directory on first use.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    /// * `relative_path` - Path of the asset relative to `assets/`, e.g. "icons/logo.png"
+    ///
+    /// # Returns
+    /// Path of the asset file
+    pub fn asset(&self, plugin_id: &str, relative_path: &str) -> Result<PathBuf, String> {
+        let package_dir = self.get_plugin_package_dir(plugin_id)?;
+        let resolved = crate::assets::resolve_asset(&package_dir, relative_path);
+
+        #[cfg(feature = "compressed-assets")]
+        if resolved.is_err() {
+            let compressed = format!("{}.{}", relative_path, crate::assets::COMPRESSED_EXTENSION);
+            if let Ok(source) = crate::assets::resolve_asset(&package_dir, &compressed) {
+                let cache_dir = self.get_plugin_cache_dir(plugin_id)?;
+                return crate::assets::decompress_asset(&source, relative_path, &cache_dir);
+            }
+        }
+
+        resolved
+    }
+
+    /// Get an installed plugin's documentation and metadata
+    ///
+    /// Reads the manifest, readme and changelog shipped with the package,
+    /// without network access.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    pub fn plugin_info(&self, plugin_id: &str) -> Result<PluginInfo, String> {
+        let package_dir = self.get_plugin_package_dir(plugin_id)?;
+        if !package_dir.exists() {
+            return Err(format!("Plugin '{}' is not installed", plugin_id));
+        }
+
+        PluginInfo::from_package(&package_dir)
+    }
+
+    /// Open one of the plugin's SQLite databases, creating it if needed
+    ///
+    /// The database is stored as `<plugin data dir>/<name>.sqlite3` and
+    /// opened in WAL mode. Call `Database::migrate` before first use.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    /// * `name` - Name of the database, unique within the plugin
+    #[cfg(feature = "database")]
+    pub fn open_database(&self, plugin_id: &str, name: &str) -> Result<crate::database::Database, String> {
+        // Database names become file names
+        Self::validate_config_name(name)?;
+
+        let path = self
+            .get_plugin_data_dir(plugin_id)?
+            .join(format!("{}.sqlite3", name));
+        crate::database::Database::open(&path)
+    }
+
     // ========== Search Integration ==========
 
     /// Add search results from a plugin
@@ -266,6 +587,111 @@ impl VoltPluginAPI {
         Ok(())
     }
 
+    // ========== Index Contribution ==========
+
+    /// Add or replace documents in the central index
+    ///
+    /// Documents are stored in the plugin's own namespace; IDs only need to
+    /// be unique within the plugin.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    /// * `docs` - Documents to add or replace
+    ///
+    /// # Returns
+    /// The namespace's generation after the change
+    pub fn index_documents(&self, plugin_id: &str, docs: Vec<IndexDoc>) -> Result<u64, String> {
+        self.apply_index_batch(
+            plugin_id,
+            IndexBatch {
+                upserts: docs,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Remove documents from the central index
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    /// * `doc_ids` - IDs of the documents to remove; unknown IDs are ignored
+    pub fn remove_documents(&self, plugin_id: &str, doc_ids: Vec<String>) -> Result<u64, String> {
+        self.apply_index_batch(
+            plugin_id,
+            IndexBatch {
+                removals: doc_ids,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Apply a batch of index changes atomically
+    ///
+    /// Use this for incremental syncs: removals are applied first, then
+    /// upserts, and queries never observe a half-applied batch. Set
+    /// `replace_all` for a full resync.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    /// * `batch` - Changes to apply
+    pub fn apply_index_batch(&self, plugin_id: &str, batch: IndexBatch) -> Result<u64, String> {
+        Self::validate_plugin_id(plugin_id)?;
+
+        let generation = locks::write(&self.state, "plugin API state")
+            .index
+            .apply(plugin_id, batch)?;
+        self.publish_change(&DataChange::PluginReindexed(plugin_id.to_string()));
+        Ok(generation)
+    }
+
+    /// Remove every document a plugin contributed
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    pub fn clear_documents(&self, plugin_id: &str) {
+        locks::write(&self.state, "plugin API state")
+            .index
+            .remove_plugin(plugin_id);
+        self.publish_change(&DataChange::PluginReindexed(plugin_id.to_string()));
+    }
+
+    /// Search the central index
+    ///
+    /// Each hit names the plugin that contributed it; convert hits with
+    /// `IndexHit::into_result` and hand them to that plugin for execution.
+    ///
+    /// # Arguments
+    /// * `query` - Raw query
+    /// * `plugin_id` - Restrict the search to one plugin's documents
+    /// * `limit` - Maximum number of hits
+    pub fn search_index(&self, query: &str, plugin_id: Option<&str>, limit: usize) -> Vec<IndexHit> {
+        locks::read(&self.state, "plugin API state")
+            .index
+            .search(query, plugin_id, limit)
+    }
+
+    /// Open one of the plugin's full-text indexes, creating it if needed
+    ///
+    /// Indexes are stored in `<plugin data dir>/fulltext/<name>`.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    /// * `name` - Name of the index, unique within the plugin
+    /// * `schema` - Fields of the index
+    #[cfg(feature = "fulltext")]
+    pub fn open_fulltext_index(
+        &self,
+        plugin_id: &str,
+        name: &str,
+        schema: &crate::fulltext::FulltextSchema,
+    ) -> Result<crate::fulltext::FulltextIndex, String> {
+        // Index names become directory names
+        Self::validate_cache_key(name)?;
+
+        let index_dir = self.get_plugin_data_dir(plugin_id)?.join("fulltext").join(name);
+        crate::fulltext::FulltextIndex::open(&index_dir, schema)
+    }
+
     // ========== Configuration Management ==========
 
     /// Load plugin configuration from JSON file
@@ -281,20 +707,25 @@ impl VoltPluginAPI {
         plugin_id: &str,
         config_name: &str,
     ) -> Result<serde_json::Value, String> {
-        // Validate config_name to prevent path traversal
-        Self::validate_config_name(config_name)?;
+        let call = || ApiCall::LoadConfig {
+            config_name: config_name.to_string(),
+        };
+        self.traced(plugin_id, call, || {
+            // Validate config_name to prevent path traversal
+            Self::validate_config_name(config_name)?;
 
-        let config_dir = self.get_plugin_config_dir(plugin_id)?;
-        let config_path = config_dir.join(format!("{}.json", config_name));
+            let config_dir = self.get_plugin_config_dir(plugin_id)?;
+            let config_path = config_dir.join(format!("{}.json", config_name));
 
-        if !config_path.exists() {
-            return Ok(serde_json::json!({}));
-        }
+            if !config_path.exists() {
+                return Ok(serde_json::json!({}));
+            }
 
-        let content = std::fs::read_to_string(&config_path)
-            .map_err(|e| format!("Failed to read config: {}", e))?;
+            let content = std::fs::read_to_string(&config_path)
+                .map_err(|e| format!("Failed to read config: {}", e))?;
 
-        serde_json::from_str(&content).map_err(|e| format!("Failed to parse config: {}", e))
+            serde_json::from_str(&content).map_err(|e| format!("Failed to parse config: {}", e))
+        })
     }
 
     /// Save plugin configuration to JSON file
@@ -309,62 +740,368 @@ impl VoltPluginAPI {
         config_name: &str,
         config: &serde_json::Value,
     ) -> Result<(), String> {
-        // Validate config_name to prevent path traversal
-        Self::validate_config_name(config_name)?;
+        let call = || ApiCall::SaveConfig {
+            config_name: config_name.to_string(),
+        };
+        self.traced(plugin_id, call, || {
+            // Validate config_name to prevent path traversal
+            Self::validate_config_name(config_name)?;
+
+            let config_dir = self.get_plugin_config_dir(plugin_id)?;
+            let config_path = config_dir.join(format!("{}.json", config_name));
+
+            let content = serde_json::to_string_pretty(config)
+                .map_err(|e| format!("Failed to serialize config: {}", e))?;
+
+            // Write to a file of our own and rename it over the config, so
+            // concurrent loads and saves never see a partially written file
+            let partial = config_dir.join(format!(
+                "{}.json.{}-{}.partial",
+                config_name,
+                std::process::id(),
+                NEXT_PARTIAL_ID.fetch_add(1, Ordering::Relaxed)
+            ));
+            std::fs::write(&partial, content).map_err(|e| format!("Failed to write config: {}", e))?;
+            if let Err(e) = std::fs::rename(&partial, &config_path) {
+                let _ = std::fs::remove_file(&partial);
+                return Err(format!("Failed to write config: {}", e));
+            }
+
+            self.record_config_mtime(&config_path)?;
+            self.notify_config_changed(&ConfigChange {
+                plugin_id: plugin_id.to_string(),
+                config_name: config_name.to_string(),
+                config: config.clone(),
+                source: ConfigChangeSource::Saved,
+            });
+
+            Ok(())
+        })
+    }
 
-        let config_dir = self.get_plugin_config_dir(plugin_id)?;
-        let config_path = config_dir.join(format!("{}.json", config_name));
+    /// Subscribe to plugin configuration changes
+    ///
+    /// The listener is called after every successful `save_config` and for
+    /// every external edit found by `detect_config_changes`.
+    ///
+    /// # Arguments
+    /// * `listener` - Callback receiving each change
+    pub fn subscribe_config_changes(&self, listener: ConfigListener) -> Result<(), String> {
+        let mut state = locks::write(&self.state, "plugin API state");
+
+        state.config_listeners.push(listener);
+        Ok(())
+    }
+
+    /// Scan configuration files for edits made outside of Volt
+    ///
+    /// Call this from the host's file watcher (or periodically). The first
+    /// scan only records the current state; later scans notify subscribers
+    /// about every file created or modified since the previous scan.
+    ///
+    /// # Returns
+    /// The changes that were detected and dispatched
+    pub fn detect_config_changes(&self) -> Result<Vec<ConfigChange>, String> {
+        let plugins_dir = {
+            let state = locks::read(&self.state, "plugin API state");
+            state.config_dir.join("plugins")
+        };
+
+        let mut found = Vec::new();
+        if let Ok(plugin_dirs) = std::fs::read_dir(&plugins_dir) {
+            for plugin_dir in plugin_dirs.flatten() {
+                let plugin_id = plugin_dir.file_name().to_string_lossy().to_string();
+                if Self::validate_plugin_id(&plugin_id).is_err() {
+                    continue;
+                }
+
+                let Ok(files) = std::fs::read_dir(plugin_dir.path()) else {
+                    continue;
+                };
+                for file in files.flatten() {
+                    let path = file.path();
+                    let Some(config_name) = path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .and_then(|name| name.strip_suffix(".json"))
+                    else {
+                        continue;
+                    };
+                    if Self::validate_config_name(config_name).is_err() {
+                        continue;
+                    }
+                    if let Ok(modified) = file.metadata().and_then(|m| m.modified()) {
+                        found.push((plugin_id.clone(), config_name.to_string(), path, modified));
+                    }
+                }
+            }
+        }
+
+        let changed: Vec<(String, String)> = {
+            let mut state = locks::write(&self.state, "plugin API state");
+
+            let first_scan = !state.config_scanned;
+            state.config_scanned = true;
+
+            found
+                .into_iter()
+                .filter_map(|(plugin_id, config_name, path, modified)| {
+                    let previous = state.config_mtimes.insert(path, modified);
+                    let is_change = !first_scan && previous != Some(modified);
+                    is_change.then_some((plugin_id, config_name))
+                })
+                .collect()
+        };
+
+        let mut changes = Vec::new();
+        for (plugin_id, config_name) in changed {
+            match self.load_config(&plugin_id, &config_name) {
+                Ok(config) => {
+                    let change = ConfigChange {
+                        plugin_id,
+                        config_name,
+                        config,
+                        source: ConfigChangeSource::External,
+                    };
+                    self.notify_config_changed(&change);
+                    changes.push(change);
+                }
+                Err(e) => self.log(
+                    &plugin_id,
+                    LogLevel::Error,
+                    &format!("Ignoring external edit of '{}': {}", config_name, e),
+                ),
+            }
+        }
 
-        let content = serde_json::to_string_pretty(config)
-            .map_err(|e| format!("Failed to serialize config: {}", e))?;
+        Ok(changes)
+    }
+
+    /// Remember the modification time of a config file we wrote ourselves
+    fn record_config_mtime(&self, config_path: &std::path::Path) -> Result<(), String> {
+        let modified = std::fs::metadata(config_path)
+            .and_then(|m| m.modified())
+            .map_err(|e| format!("Failed to read config metadata: {}", e))?;
 
-        std::fs::write(&config_path, content)
-            .map_err(|e| format!("Failed to write config: {}", e))?;
+        let mut state = locks::write(&self.state, "plugin API state");
 
+        state.config_mtimes.insert(config_path.to_path_buf(), modified);
         Ok(())
     }
 
+    /// Dispatch a configuration change to all subscribers
+    ///
+    /// Cache entries depending on the configuration are invalidated first.
+    fn notify_config_changed(&self, change: &ConfigChange) {
+        self.publish_change(&DataChange::ConfigSaved {
+            plugin_id: change.plugin_id.clone(),
+            config_name: change.config_name.clone(),
+        });
+
+        // Clone the listeners so callbacks run without holding the lock
+        let listeners = locks::read(&self.state, "plugin API state")
+            .config_listeners
+            .clone();
+
+        for listener in listeners {
+            listener(change);
+        }
+    }
+
     // ========== Logging ==========
 
     /// Log a message from a plugin
     ///
+    /// The message is emitted through the API's diagnostics with the plugin
+    /// as source and kept for diagnostics bundles.
+    ///
     /// # Arguments
     /// * `plugin_id` - Unique identifier of the plugin
     /// * `level` - Log level (info, warn, error)
     /// * `message` - Message to log
     pub fn log(&self, plugin_id: &str, level: LogLevel, message: &str) {
-        match level {
-            LogLevel::Info => println!("[{}] INFO: {}", plugin_id, message),
-            LogLevel::Warn => println!("[{}] WARN: {}", plugin_id, message),
-            LogLevel::Error => eprintln!("[{}] ERROR: {}", plugin_id, message),
-            LogLevel::Debug => println!("[{}] DEBUG: {}", plugin_id, message),
+        self.diagnostics().emit(level, plugin_id, message);
+
+        let mut state = locks::write(&self.state, "plugin API state");
+        let now = state.clock.now_millis();
+        let lines = state.recent_logs.entry(plugin_id.to_string()).or_default();
+        if lines.len() == MAX_LOG_LINES {
+            lines.pop_front();
         }
+        lines.push_back(format!(
+            "{} {}: {}",
+            now,
+            level.as_str(),
+            message
+        ));
     }
 
-    // ========== Cache Management ==========
+    /// Get a plugin's most recent log lines, oldest first
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    /// * `limit` - Maximum number of lines to return
+    pub fn recent_logs(&self, plugin_id: &str, limit: usize) -> Vec<String> {
+        let state = locks::read(&self.state, "plugin API state");
+        state
+            .recent_logs
+            .get(plugin_id)
+            .map(|lines| {
+                let skip = lines.len().saturating_sub(limit);
+                lines.iter().skip(skip).cloned().collect()
+            })
+            .unwrap_or_default()
+    }
 
-    /// Read data from cache
+    // ========== Diagnostics ==========
+
+    /// Collect a plugin's diagnostics into a zip for bug reports
+    ///
+    /// The bundle contains version information, the plugin's configuration
+    /// files, its recent logs and last errors, and, when a registry is
+    /// given, the plugin's registry status. Secret configuration values are
+    /// redacted everywhere, and old log lines are dropped to respect
+    /// `diagnostics::MAX_BUNDLE_SIZE`.
     ///
     /// # Arguments
     /// * `plugin_id` - Unique identifier of the plugin
-    /// * `cache_key` - Key to identify the cached data
-    pub fn read_cache(&self, plugin_id: &str, cache_key: &str) -> Result<Vec<u8>, String> {
-        // Validate cache_key to prevent path traversal
-        Self::validate_cache_key(cache_key)?;
+    /// * `registry` - Registry the plugin is loaded in, if any
+    ///
+    /// # Returns
+    /// Path of the written zip, under `<app data>/diagnostics`
+    #[cfg(feature = "diagnostics")]
+    pub fn diagnostics_bundle(
+        &self,
+        plugin_id: &str,
+        registry: Option<&crate::registry::PluginRegistry>,
+    ) -> Result<PathBuf, String> {
+        use crate::diagnostics::{DiagnosticsBundle, Redactor, MAX_BUNDLE_SIZE};
+        use serde_json::json;
+
+        let mut bundle = DiagnosticsBundle::new();
+        let mut redactor = Redactor::new();
+
+        let platform = crate::platform::PlatformInfo::current();
+        let package_version = self.plugin_info(plugin_id).ok().map(|info| info.version);
+        bundle.add_json(
+            "version.json",
+            &json!({
+                "pluginId": plugin_id,
+                "pluginVersion": package_version,
+                "voltVersion": self.get_volt_version(),
+                "apiVersion": env!("CARGO_PKG_VERSION"),
+                "platform": platform.platform.map(|p| p.as_str()),
+                "osVersion": platform.os_version,
+            }),
+        )?;
 
-        let cache_dir = self.get_plugin_cache_dir(plugin_id)?;
-        let cache_path = cache_dir.join(cache_key);
+        let config_dir = self.get_plugin_config_dir(plugin_id)?;
+        let mut config_names: Vec<String> = std::fs::read_dir(&config_dir)
+            .map_err(|e| format!("Failed to read config directory: {}", e))?
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                name.strip_suffix(".json").map(str::to_string)
+            })
+            .collect();
+        config_names.sort();
+
+        for config_name in config_names {
+            let content = match self.load_config(plugin_id, &config_name) {
+                Ok(config) => redactor.redact_config(&config),
+                // Unparseable files may contain secrets we can't locate
+                Err(e) => json!({ "error": e }),
+            };
+            bundle.add_json(format!("config/{}.json", config_name), &content)?;
+        }
 
-        // Additional safety check: canonicalize and verify path is within cache_dir
-        if let Ok(canonical_cache_path) = cache_path.canonicalize() &&
-           let Ok(canonical_cache_dir) = cache_dir.canonicalize() &&
-           !canonical_cache_path.starts_with(&canonical_cache_dir) {
-            return Err("Cache path is outside plugin cache directory".to_string());
-        } else if !cache_path.exists() {
-            return Err("Cache entry not found".to_string());
+        let mut errors: Vec<String> = Vec::new();
+        if let Some(registry) = registry {
+            let entry = registry
+                .snapshot()?
+                .into_iter()
+                .find(|entry| entry.id == plugin_id);
+            let config_error = registry.last_config_error(plugin_id);
+            if let Some(e) = &config_error {
+                errors.push(format!("config: {}", e));
+            }
+            let entry = serde_json::to_value(entry)
+                .map_err(|e| format!("Failed to serialize diagnostics: {}", e))?;
+            bundle.add_json(
+                "registry.json",
+                &json!({ "entry": entry, "lastConfigError": config_error }),
+            )?;
         }
 
-        std::fs::read(&cache_path).map_err(|e| format!("Failed to read cache: {}", e))
+        let logs: Vec<String> = self
+            .recent_logs(plugin_id, MAX_LOG_LINES)
+            .iter()
+            .map(|line| redactor.redact_text(line))
+            .collect();
+        errors.extend(
+            logs.iter()
+                .filter(|line| line.contains(" ERROR: "))
+                .cloned(),
+        );
+        let errors: Vec<String> = errors.iter().map(|e| redactor.redact_text(e)).collect();
+        bundle.add_file("errors.txt", errors.join("\n"));
+
+        let budget = MAX_BUNDLE_SIZE.saturating_sub(bundle.size());
+        bundle.add_file(
+            "logs.txt",
+            crate::diagnostics::tail_within(&logs, budget).join("\n"),
+        );
+
+        let zip = bundle.to_zip()?;
+        let bundle_dir = self.get_app_data_dir()?.join("diagnostics");
+        std::fs::create_dir_all(&bundle_dir)
+            .map_err(|e| format!("Failed to create diagnostics directory: {}", e))?;
+
+        let bundle_path = bundle_dir.join(format!(
+            "{}-{}.zip",
+            plugin_id,
+            self.now_millis()
+        ));
+        std::fs::write(&bundle_path, zip)
+            .map_err(|e| format!("Failed to write diagnostics bundle: {}", e))?;
+
+        Ok(bundle_path)
+    }
+
+    // ========== Cache Management ==========
+
+    /// Read data from cache
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    /// * `cache_key` - Key to identify the cached data
+    pub fn read_cache(&self, plugin_id: &str, cache_key: &str) -> Result<Vec<u8>, String> {
+        let call = || ApiCall::ReadCache {
+            key: cache_key.to_string(),
+        };
+        self.traced(plugin_id, call, || {
+            // Validate cache_key to prevent path traversal
+            Self::validate_cache_key(cache_key)?;
+
+            let cache_dir = self.get_plugin_cache_dir(plugin_id)?;
+            let cache_path = cache_dir.join(cache_key);
+
+            // Additional safety check: canonicalize and verify path is within cache_dir
+            if let Ok(canonical_cache_path) = cache_path.canonicalize() &&
+               let Ok(canonical_cache_dir) = cache_dir.canonicalize() &&
+               !canonical_cache_path.starts_with(&canonical_cache_dir) {
+                return Err("Cache path is outside plugin cache directory".to_string());
+            } else if !cache_path.exists() {
+                self.record_usage(plugin_id, UsageEvent::CacheRead { hit: false });
+                return Err("Cache entry not found".to_string());
+            }
+            self.record_usage(plugin_id, UsageEvent::CacheRead { hit: true });
+
+            std::fs::read(&cache_path)
+                .map(TracedBytes)
+                .map_err(|e| format!("Failed to read cache: {}", e))
+        })
+        .map(|bytes| bytes.0)
     }
 
     /// Write data to cache
@@ -374,22 +1111,143 @@ impl VoltPluginAPI {
     /// * `cache_key` - Key to identify the cached data
     /// * `data` - Data to cache
     pub fn write_cache(&self, plugin_id: &str, cache_key: &str, data: &[u8]) -> Result<(), String> {
-        // Validate cache_key to prevent path traversal
-        Self::validate_cache_key(cache_key)?;
+        let call = || ApiCall::WriteCache {
+            key: cache_key.to_string(),
+        };
+        self.traced(plugin_id, call, || {
+            // Validate cache_key to prevent path traversal
+            Self::validate_cache_key(cache_key)?;
+
+            let cache_dir = self.get_plugin_cache_dir(plugin_id)?;
+            let cache_path = cache_dir.join(cache_key);
+
+            // Additional safety check: verify path would be within cache_dir
+            // Note: Can't canonicalize before file exists, so we check the parent
+            if let Some(parent) = cache_path.parent() &&
+               let Ok(canonical_parent) = parent.canonicalize() &&
+               let Ok(canonical_cache_dir) = cache_dir.canonicalize() &&
+               !canonical_parent.starts_with(&canonical_cache_dir) {
+                return Err("Cache path is outside plugin cache directory".to_string());
+            }
+
+            std::fs::write(&cache_path, data).map_err(|e| format!("Failed to write cache: {}", e))
+        })
+    }
+
+    /// Download a file into the plugin's cache
+    ///
+    /// Cached downloads are revalidated with the server instead of being
+    /// fetched again, and interrupted downloads resume where they stopped.
+    /// At most `download::DEFAULT_MAX_CONCURRENT_DOWNLOADS` downloads run at
+    /// once across all plugins; others wait for a slot. Responses go through
+    /// the HTTP cache shared by all plugins, so a URL another plugin fetched
+    /// recently is copied rather than downloaded. Plugins whose manifest
+    /// lists allowed hosts can only reach those, see `network_policy`.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    /// * `url` - URL to download
+    /// * `opts` - Download options (file name, checksum, progress callback)
+    #[cfg(feature = "download")]
+    pub async fn download(
+        &self,
+        plugin_id: &str,
+        url: &str,
+        opts: crate::download::DownloadOpts,
+    ) -> Result<crate::download::Downloaded, String> {
+        let file_name = match &opts.file_name {
+            Some(name) => name.clone(),
+            None => crate::download::file_name_from_url(url)
+                .ok_or_else(|| format!("Cannot derive a file name from {}", url))?,
+        };
+        // File names become cache keys
+        Self::validate_cache_key(&file_name)?;
 
         let cache_dir = self.get_plugin_cache_dir(plugin_id)?;
-        let cache_path = cache_dir.join(cache_key);
+        let call = ApiCall::Download {
+            url: url.to_string(),
+        };
+        let session = self.api_trace_session();
+        if let Some(response) = session.as_ref().and_then(|session| {
+            session
+                .lock()
+                .unwrap_or_else(|p| p.into_inner())
+                .replay(plugin_id, &call)
+        }) {
+            let bytes: TracedBytes = serde_json::from_value(response?)
+                .map_err(|e| format!("Failed to replay {}: {}", call, e))?;
+            let path = cache_dir.join(&file_name);
+            std::fs::write(&path, bytes.0)
+                .map_err(|e| format!("Failed to write download: {}", e))?;
+            return Ok(crate::download::Downloaded {
+                path,
+                source: crate::download::DownloadSource::Replayed,
+            });
+        }
+
+        let mut downloader = locks::read(&self.state, "plugin API state").downloader.clone();
+        if let Some(policy) = self.network_policy(plugin_id) {
+            downloader = downloader.with_network_policy(policy);
+        }
+
+        let result = downloader.download(&cache_dir, &file_name, url, &opts).await;
+        if let Some(session) = session {
+            let response = match &result {
+                Ok(downloaded) => match std::fs::read(&downloaded.path) {
+                    Ok(bytes) => TracedResponse::Ok(serde_json::json!(TracedBytes(bytes))),
+                    Err(e) => TracedResponse::Err(format!("Failed to read download: {}", e)),
+                },
+                Err(e) => TracedResponse::Err(e.clone()),
+            };
+            session
+                .lock()
+                .unwrap_or_else(|p| p.into_inner())
+                .record(plugin_id, call, response);
+        }
+        result
+    }
+
+    /// Get the title, description, favicon and OpenGraph image of a page
+    ///
+    /// Requires the `network` permission, and the page and images must be on
+    /// hosts the plugin may reach (see `network_policy`). Pages and images are cached in the
+    /// plugin's cache with size caps, and each host is fetched at most once
+    /// per `previews::DEFAULT_HOST_INTERVAL`; callers should show the result
+    /// without a preview when this fails.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    /// * `url` - The http(s) URL to preview
+    #[cfg(feature = "download")]
+    pub async fn request_url_preview(
+        &self,
+        plugin_id: &str,
+        url: &str,
+    ) -> Result<crate::previews::UrlPreview, String> {
+        self.require_capability(plugin_id, PluginCapability::Network)?;
 
-        // Additional safety check: verify path would be within cache_dir
-        // Note: Can't canonicalize before file exists, so we check the parent
-        if let Some(parent) = cache_path.parent() &&
-           let Ok(canonical_parent) = parent.canonicalize() &&
-           let Ok(canonical_cache_dir) = cache_dir.canonicalize() &&
-           !canonical_parent.starts_with(&canonical_cache_dir) {
-            return Err("Cache path is outside plugin cache directory".to_string());
+        let cache_dir = self.get_plugin_cache_dir(plugin_id)?;
+        let (mut downloader, previews) = {
+            let state = locks::read(&self.state, "plugin API state");
+            (state.downloader.clone(), state.previews.clone())
+        };
+        if let Some(policy) = self.network_policy(plugin_id) {
+            downloader = downloader.with_network_policy(policy);
         }
 
-        std::fs::write(&cache_path, data).map_err(|e| format!("Failed to write cache: {}", e))
+        previews.preview(&downloader, &cache_dir, url).await
+    }
+
+    /// Clear the HTTP cache shared by all plugins
+    ///
+    /// Files already downloaded into plugin caches are kept.
+    #[cfg(feature = "download")]
+    pub fn clear_http_cache(&self) -> Result<(), String> {
+        let downloader = locks::read(&self.state, "plugin API state").downloader.clone();
+        match downloader.http_cache() {
+            Some(cache) => cache.clear(),
+            None => Ok(()),
+        }
     }
 
     /// Clear plugin cache
@@ -409,84 +1267,1371 @@ impl VoltPluginAPI {
         Ok(())
     }
 
-    // ========== Application Information ==========
+    // ========== Cache Invalidation ==========
 
-    /// Get Volt's version
-    pub fn get_volt_version(&self) -> &str {
-        crate::core::constants::APP_VERSION
+    /// Declare what one of a plugin's cache entries depends on
+    ///
+    /// The entry is removed as soon as one of its dependencies changes, see
+    /// `invalidation`. Declarations replace earlier ones and are forgotten
+    /// once the entry is invalidated, so declare them on every write.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    /// * `cache_key` - Key of the entry, as given to `write_cache`
+    /// * `dependencies` - What the entry was derived from
+    pub fn cache_depends_on(
+        &self,
+        plugin_id: &str,
+        cache_key: &str,
+        dependencies: Vec<Dependency>,
+    ) -> Result<(), String> {
+        Self::validate_plugin_id(plugin_id)?;
+        Self::validate_cache_key(cache_key)?;
+
+        self.invalidation_bus().depend(
+            CacheEntry::new(PLUGIN_CACHE_LAYER, format!("{}/{}", plugin_id, cache_key)),
+            dependencies,
+        )
     }
 
-    /// Get application data directory
-    pub fn get_app_data_dir(&self) -> Result<PathBuf, String> {
-        let state = self
-            .state
-            .read()
-            .map_err(|e| format!("Failed to acquire read lock: {}", e))?;
+    /// Invalidate the cache entries depending on a change
+    ///
+    /// Configuration saves and index updates are published by the API;
+    /// the host publishes file changes from its file watcher.
+    ///
+    /// # Returns
+    /// Every entry invalidated
+    pub fn publish_change(&self, change: &DataChange) -> Vec<CacheEntry> {
+        self.invalidation_bus().publish(change)
+    }
 
-        Ok(state.app_data_dir.clone())
+    /// Get the bus routing data changes to cache entries
+    ///
+    /// Register the host's own cache layers on it.
+    pub fn invalidation_bus(&self) -> InvalidationBus {
+        locks::read(&self.state, "plugin API state").invalidation.clone()
     }
-}
 
-/// Log levels for plugin logging
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum LogLevel {
-    /// Informational messages
-    Info,
-    /// Warning messages
-    Warn,
-    /// Error messages
-    Error,
-    /// Debug messages
-    Debug,
-}
+    // ========== API Tracing ==========
 
-/// Plugin capabilities that can be requested
-///
-/// Plugins should declare which capabilities they need for security and transparency
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum PluginCapability {
-    /// Access to file system
-    FileSystem,
-    /// Network access
-    Network,
-    /// Access to system information
-    SystemInfo,
-    /// Ability to execute commands
-    ExecuteCommands,
-    /// Access to user's application data
-    ApplicationData,
-    /// Modify search results
-    ModifySearch,
-}
+    /// Start recording or replaying the API calls of plugins
+    ///
+    /// Replaces any running session. See `api_trace` for the calls that are
+    /// traced.
+    ///
+    /// # Arguments
+    /// * `mode` - Whether to record calls or answer them from a trace
+    pub fn start_api_trace(&self, mode: ApiTraceMode) {
+        locks::write(&self.state, "plugin API state").api_trace =
+            Some(Arc::new(Mutex::new(ApiTraceSession::new(mode))));
+    }
 
-impl PluginCapability {
-    /// Get human-readable description of the capability
-    pub fn description(&self) -> &str {
-        match self {
-            PluginCapability::FileSystem => "Read and write files on your computer",
-            PluginCapability::Network => "Access the internet and make network requests",
-            PluginCapability::SystemInfo => "Read system information (CPU, memory, etc.)",
-            PluginCapability::ExecuteCommands => "Execute programs and commands",
-            PluginCapability::ApplicationData => "Access your application data and history",
-            PluginCapability::ModifySearch => "Modify and add to search results",
-        }
+    /// Stop the running trace session
+    ///
+    /// # Returns
+    /// The recorded calls, or when replaying the calls of the trace that
+    /// were never made; None if no session was running
+    pub fn stop_api_trace(&self) -> Option<ApiTrace> {
+        let session = locks::write(&self.state, "plugin API state").api_trace.take()?;
+        // Calls still running may hold the session; what they record is dropped
+        let session = std::mem::replace(
+            &mut *session.lock().unwrap_or_else(|p| p.into_inner()),
+            ApiTraceSession::new(ApiTraceMode::Record),
+        );
+        Some(session.finish())
     }
 
-    /// Check if this capability is considered sensitive
-    pub fn is_sensitive(&self) -> bool {
-        matches!(
-            self,
-            PluginCapability::FileSystem
-                | PluginCapability::Network
-                | PluginCapability::ExecuteCommands
-                | PluginCapability::ApplicationData
-        )
+    /// Get the running trace session
+    fn api_trace_session(&self) -> Option<Arc<Mutex<ApiTraceSession>>> {
+        locks::read(&self.state, "plugin API state").api_trace.clone()
     }
-}
 
-#[cfg(test)]
-mod tests {
+    /// Run an API call, recording it or answering it from the trace
+    fn traced<T: serde::Serialize + serde::de::DeserializeOwned>(
+        &self,
+        plugin_id: &str,
+        call: impl FnOnce() -> ApiCall,
+        run: impl FnOnce() -> Result<T, String>,
+    ) -> Result<T, String> {
+        let Some(session) = self.api_trace_session() else {
+            return run();
+        };
+
+        let call = call();
+        let replayed = session
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .replay(plugin_id, &call);
+        if let Some(response) = replayed {
+            return serde_json::from_value(response?)
+                .map_err(|e| format!("Failed to replay {}: {}", call, e));
+        }
+
+        let result = run();
+        let response = match &result {
+            Ok(value) => match serde_json::to_value(value) {
+                Ok(value) => TracedResponse::Ok(value),
+                Err(e) => TracedResponse::Err(format!("Failed to record {}: {}", call, e)),
+            },
+            Err(e) => TracedResponse::Err(e.clone()),
+        };
+        session
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .record(plugin_id, call, response);
+        result
+    }
+
+    // ========== Marketplace Cache ==========
+
+    /// Get the directory downloaded marketplace packages are kept in
+    pub fn get_marketplace_cache_dir(&self) -> Result<PathBuf, String> {
+        let state = locks::read(&self.state, "plugin API state");
+        let cache_dir = state.cache_dir.join(MARKETPLACE_CACHE_DIR);
+
+        if !cache_dir.exists() {
+            std::fs::create_dir_all(&cache_dir)
+                .map_err(|e| format!("Failed to create marketplace cache directory: {}", e))?;
+        }
+
+        Ok(cache_dir)
+    }
+
+    /// Set what `gc_packages` keeps, including the quota of the package cache
+    pub fn set_package_retention(&self, policy: RetentionPolicy) {
+        locks::write(&self.state, "plugin API state").package_retention = policy;
+    }
+
+    /// Get what `gc_packages` keeps
+    pub fn package_retention(&self) -> RetentionPolicy {
+        locks::read(&self.state, "plugin API state").package_retention
+    }
+
+    /// Delete downloaded packages the retention policy doesn't keep
+    ///
+    /// The package of each installed plugin's current version is always
+    /// kept unless the cache is over quota.
+    ///
+    /// # Arguments
+    /// * `dry_run` - Only report what would be deleted
+    pub fn gc_packages(&self, dry_run: bool) -> Result<GcReport, String> {
+        let cache_dir = self.get_marketplace_cache_dir()?;
+        let policy = self.package_retention();
+        let extensions_dir = self.get_app_data_dir()?.join("extensions");
+
+        let mut installed = HashMap::new();
+        for entry in std::fs::read_dir(&extensions_dir).into_iter().flatten().flatten() {
+            if let Ok(manifest) = PluginManifest::from_file(&entry.path().join("manifest.json")) {
+                installed.insert(manifest.id, manifest.version);
+            }
+        }
+
+        crate::marketplace::gc(&cache_dir, &policy, &installed, dry_run)
+    }
+
+    // ========== Plugin Identity ==========
+
+    /// Carry a renamed plugin's data over to its new ID
+    ///
+    /// Call this before loading a plugin. If the manifest's UUID was last
+    /// installed under another ID, the plugin's data, cache and config
+    /// directories are moved to the new ID; the host then relabels the
+    /// registry's state with `PluginRegistry::rename_plugin`. A failed move
+    /// is rolled back and the old ID is kept, so the migration runs again on
+    /// the next start. Manifests without a UUID are left alone.
+    ///
+    /// # Arguments
+    /// * `manifest` - Manifest of the plugin about to load
+    ///
+    /// # Returns
+    /// The rename, if the plugin's ID changed
+    pub fn migrate_plugin_identity(&self, manifest: &PluginManifest) -> Result<Option<PluginRename>, String> {
+        let Some(uuid) = &manifest.uuid else {
+            return Ok(None);
+        };
+        crate::identity::check_uuid(uuid)?;
+        Self::validate_plugin_id(&manifest.id)?;
+
+        let (path, roots) = {
+            let state = locks::read(&self.state, "plugin API state");
+            (
+                state.app_data_dir.join(IDENTITY_FILE),
+                [
+                    state.app_data_dir.join("plugins"),
+                    state.cache_dir.join("plugins"),
+                    state.config_dir.join("plugins"),
+                ],
+            )
+        };
+        let mut identities = PluginIdentities::load(&path)?;
+        let Some(rename) = identities.record(uuid, &manifest.id) else {
+            return identities.save(&path).map(|()| None);
+        };
+        Self::validate_plugin_id(&rename.from)?;
+
+        let mut moved = Vec::new();
+        for root in &roots {
+            match crate::identity::relabel_dir(root, &rename.from, &rename.to) {
+                Ok(true) => moved.push(root),
+                Ok(false) => {}
+                Err(e) => {
+                    for root in moved {
+                        let _ = crate::identity::relabel_dir(root, &rename.to, &rename.from);
+                    }
+                    return Err(format!("Failed to migrate plugin '{}' to '{}': {}", rename.from, rename.to, e));
+                }
+            }
+        }
+        identities.save(&path)?;
+
+        // The cached store still points at the old directory
+        let usage = {
+            let mut state = locks::write(&self.state, "plugin API state");
+            state.kv.remove(&rename.from);
+            state.usage.clone()
+        };
+        usage.rename(&rename.from, &rename.to);
+        self.diagnostics().info(
+            "api",
+            &format!("Plugin '{}' was renamed to '{}', its data was moved", rename.from, rename.to),
+        );
+        Ok(Some(rename))
+    }
+
+    // ========== Permissions ==========
+
+    /// Check that an installed plugin declared a capability in its manifest
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    /// * `capability` - Capability the plugin is about to use
+    ///
+    /// Asks the capability broker, which grants what the manifest declares
+    /// unless the host installed another one.
+    pub fn require_capability(&self, plugin_id: &str, capability: PluginCapability) -> Result<(), String> {
+        let info = self.plugin_info(plugin_id)?;
+        let broker = locks::read(&self.state, "plugin API state").capability_broker.clone();
+        broker.check(&info, capability)
+    }
+
+    // ========== Network Allowlists ==========
+
+    /// Install the callback asking whether a plugin may reach a host outside its allowlist
+    ///
+    /// Without one, such hosts are refused. Approved hosts are remembered,
+    /// see `network_grants`.
+    pub fn set_network_grant_handler(&self, handler: NetworkGrantHandler) {
+        locks::write(&self.state, "plugin API state").network_grant_handler = Some(handler);
+    }
+
+    /// Get the hosts a plugin may connect to
+    ///
+    /// # Returns
+    /// The policy built from the manifest's `network.allow` and the hosts
+    /// the user granted, or None if the plugin isn't restricted by host
+    pub fn network_policy(&self, plugin_id: &str) -> Option<NetworkPolicy> {
+        let info = self.plugin_info(plugin_id).ok()?;
+        let allow = info.network_allow?;
+        // Invalid patterns are reported by manifest validation and match nothing
+        let patterns = allow.iter().filter_map(|pattern| HostPattern::parse(pattern).ok()).collect();
+
+        let state = locks::read(&self.state, "plugin API state");
+        let policy = NetworkPolicy::new(plugin_id, &info.name, patterns, state.network_grants.clone());
+        Some(match &state.network_grant_handler {
+            Some(handler) => policy.with_grant_handler(handler.clone()),
+            None => policy,
+        })
+    }
+
+    /// Get the hosts the user granted a plugin beyond its allowlist, sorted
+    pub fn network_grants(&self, plugin_id: &str) -> Vec<String> {
+        locks::read(&self.state, "plugin API state").network_grants.hosts(plugin_id)
+    }
+
+    /// Let a plugin connect to a host outside its allowlist
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    /// * `host` - The host, e.g. "cdn.example.com"
+    pub fn grant_network_host(&self, plugin_id: &str, host: &str) -> Result<(), String> {
+        Self::validate_plugin_id(plugin_id)?;
+        if !matches!(HostPattern::parse(host)?, HostPattern::Exact(_)) {
+            return Err(format!("Grant hosts one at a time, not '{}'", host));
+        }
+        let grants = locks::read(&self.state, "plugin API state").network_grants.clone();
+        grants.grant(plugin_id, host)
+    }
+
+    /// Withdraw a host granted to a plugin
+    ///
+    /// # Returns
+    /// Whether the host was granted
+    pub fn revoke_network_host(&self, plugin_id: &str, host: &str) -> Result<bool, String> {
+        let grants = locks::read(&self.state, "plugin API state").network_grants.clone();
+        grants.revoke(plugin_id, host)
+    }
+
+    // ========== Elevation ==========
+
+    /// Install the callback asking the user to confirm elevated commands
+    ///
+    /// Without one, `execute_elevated` refuses every request.
+    pub fn set_elevation_confirmer(&self, confirmer: ElevationConfirmer) {
+        locks::write(&self.state, "plugin API state").elevation_confirmer = Some(confirmer);
+    }
+
+    /// Run a command with administrator rights on behalf of a plugin
+    ///
+    /// Requires the `execute_commands` and `elevation` permissions. The user
+    /// confirms each invocation through the host's confirmer before the
+    /// platform prompt (UAC, pkexec, osascript) appears, and the request is
+    /// recorded in the audit trail whatever its outcome. Blocks until the
+    /// command exits.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    /// * `spec` - The program to run; it is started without a shell on Linux
+    ///   and quoted for the platform's elevation helper elsewhere
+    pub fn execute_elevated(&self, plugin_id: &str, spec: CommandSpec) -> Result<(), String> {
+        self.require_capability(plugin_id, PluginCapability::ExecuteCommands)?;
+        self.require_capability(plugin_id, PluginCapability::Elevation)?;
+        crate::intents::validate(&crate::result::ResultIntent::RunCommand(spec.clone()))?;
+
+        let confirmer = locks::read(&self.state, "plugin API state")
+            .elevation_confirmer
+            .clone()
+            .ok_or("Elevation is not available")?;
+        let request = ElevationRequest {
+            plugin_id: plugin_id.to_string(),
+            plugin_name: self.plugin_info(plugin_id)?.name,
+            command_line: crate::intents::command_line(&spec),
+            command: spec,
+        };
+
+        let approved = confirmer(&request);
+        let outcome = if approved {
+            crate::elevation::run_elevated(&request.command)
+        } else {
+            Err(format!("Running '{}' as administrator was declined", request.command_line))
+        };
+        self.audit(AuditEntry {
+            at_ms: self.now_millis(),
+            plugin_id: plugin_id.to_string(),
+            action: "execute_elevated".to_string(),
+            detail: request.command_line,
+            outcome: match (&outcome, approved) {
+                (_, false) => AuditOutcome::Declined,
+                (Ok(()), true) => AuditOutcome::Succeeded,
+                (Err(error), true) => AuditOutcome::Failed { error: error.clone() },
+            },
+        })?;
+
+        outcome
+    }
+
+    /// Get the audit trail of privileged plugin actions, oldest first
+    pub fn audit_trail(&self) -> Result<Vec<AuditEntry>, String> {
+        let path = locks::read(&self.state, "plugin API state").app_data_dir.join(AUDIT_FILE);
+        crate::audit::read(&path)
+    }
+
+    /// Record a privileged action in the audit trail
+    fn audit(&self, entry: AuditEntry) -> Result<(), String> {
+        let path = locks::read(&self.state, "plugin API state").app_data_dir.join(AUDIT_FILE);
+        crate::audit::append(&path, &entry)
+    }
+
+    // ========== macOS Automation ==========
+
+    /// Run an AppleScript on behalf of a plugin
+    ///
+    /// Requires the `execute_commands` permission. The source is checked
+    /// against the default `ScriptPolicy`, which refuses shell access.
+    ///
+    /// # Returns
+    /// The value of the script's last statement
+    #[cfg(feature = "macos")]
+    pub fn run_applescript(&self, plugin_id: &str, source: &str) -> Result<String, String> {
+        self.require_capability(plugin_id, PluginCapability::ExecuteCommands)?;
+
+        crate::macos::run_applescript(source, &crate::macos::ScriptPolicy::default())
+    }
+
+    /// Run a Shortcut from the Shortcuts app on behalf of a plugin
+    ///
+    /// Requires the `execute_commands` permission.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    /// * `name` - Name of the shortcut
+    /// * `input` - Text passed to the shortcut as input, if any
+    ///
+    /// # Returns
+    /// The shortcut's output as text
+    #[cfg(feature = "macos")]
+    pub fn run_shortcut(&self, plugin_id: &str, name: &str, input: Option<&str>) -> Result<String, String> {
+        self.require_capability(plugin_id, PluginCapability::ExecuteCommands)?;
+
+        crate::macos::run_shortcut(name, input, crate::macos::DEFAULT_TIMEOUT)
+    }
+
+    // ========== Recent Actions ==========
+
+    /// Record an executed result in the recent actions feed
+    ///
+    /// Called by the host after a plugin executed a result. The feed is
+    /// deduplicated by plugin and result ID and persisted immediately.
+    /// Nothing is recorded while privacy mode is on.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin that produced the result
+    /// * `result` - The executed result
+    pub fn record_action(&self, plugin_id: &str, result: &PluginResult) -> Result<(), String> {
+        Self::validate_plugin_id(plugin_id)?;
+
+        let mut state = locks::write(&self.state, "plugin API state");
+        if state.privacy_mode {
+            return Ok(());
+        }
+
+        let app_data_dir = state.app_data_dir.clone();
+        let path = app_data_dir.join("recent_actions.json");
+        if state.recent_actions.is_none() {
+            state.recent_actions = Some(RecentActions::load(&path)?);
+        }
+
+        let now = state.clock.now_millis();
+        let feed = state.recent_actions.get_or_insert_with(RecentActions::new);
+        feed.record(plugin_id, result, now);
+
+        std::fs::create_dir_all(&app_data_dir)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+        feed.save(&path)
+    }
+
+    /// Get the most recently executed actions across all plugins
+    ///
+    /// # Arguments
+    /// * `limit` - Maximum number of actions to return
+    ///
+    /// # Returns
+    /// Actions sorted newest first, one entry per distinct result
+    pub fn recent_actions(&self, limit: usize) -> Result<Vec<RecentAction>, String> {
+        let mut state = locks::write(&self.state, "plugin API state");
+
+        if state.recent_actions.is_none() {
+            let path = state.app_data_dir.join("recent_actions.json");
+            state.recent_actions = Some(RecentActions::load(&path)?);
+        }
+
+        Ok(state
+            .recent_actions
+            .as_ref()
+            .map(|feed| feed.latest(limit))
+            .unwrap_or_default())
+    }
+
+    // ========== Privacy ==========
+
+    /// Check if privacy mode is on
+    ///
+    /// While it is, plugins must not record anything about what the user
+    /// does: query history, frecency, analytics, clipboard history.
+    pub fn privacy_mode(&self) -> bool {
+        locks::read(&self.state, "plugin API state").privacy_mode
+    }
+
+    /// Turn privacy mode on or off
+    ///
+    /// Subscribers are notified when the mode actually changes.
+    pub fn set_privacy_mode(&self, enabled: bool) {
+        let listeners = {
+            let mut state = locks::write(&self.state, "plugin API state");
+            if state.privacy_mode == enabled {
+                return;
+            }
+            state.privacy_mode = enabled;
+            // Callbacks run without holding the lock
+            state.privacy_listeners.clone()
+        };
+
+        for listener in listeners {
+            listener(enabled);
+        }
+    }
+
+    /// Subscribe to privacy mode changes
+    ///
+    /// # Arguments
+    /// * `listener` - Callback receiving the new state
+    pub fn subscribe_privacy_changes(&self, listener: PrivacyListener) -> Result<(), String> {
+        let mut state = locks::write(&self.state, "plugin API state");
+
+        state.privacy_listeners.push(listener);
+        Ok(())
+    }
+
+    // ========== Live Results ==========
+
+    /// Ask the host to query a plugin again while its results are on screen
+    ///
+    /// For plugins whose data changes under the user's eyes (running
+    /// processes, downloads in progress). Only this plugin is queried again;
+    /// the host merges its new results into the current view and sends the
+    /// changed rows, see `PluginRegistry::refresh_plugin`. Nothing happens
+    /// when no results are shown.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin whose results changed
+    pub fn refresh_results(&self, plugin_id: &str) -> Result<(), String> {
+        Self::validate_plugin_id(plugin_id)?;
+        // Callbacks run without holding the lock
+        let listeners = locks::read(&self.state, "plugin API state").refresh_listeners.clone();
+
+        for listener in listeners {
+            listener(plugin_id);
+        }
+        Ok(())
+    }
+
+    /// Subscribe to refresh requests of plugins
+    ///
+    /// # Arguments
+    /// * `listener` - Callback receiving the ID of the plugin to query again
+    pub fn subscribe_refresh_requests(&self, listener: RefreshListener) -> Result<(), String> {
+        let mut state = locks::write(&self.state, "plugin API state");
+
+        state.refresh_listeners.push(listener);
+        Ok(())
+    }
+
+    // ========== Power ==========
+
+    /// Get the power supply of the machine, as last reported by the host
+    pub fn power_state(&self) -> PowerState {
+        locks::read(&self.state, "plugin API state").power_state
+    }
+
+    /// Get how much background work is throttled under the current policy
+    pub fn power_mode(&self) -> PowerMode {
+        let state = locks::read(&self.state, "plugin API state");
+        state.power_policy.mode(&state.power_state)
+    }
+
+    /// Report the power supply of the machine
+    ///
+    /// Subscribers are notified when the state actually changes.
+    pub fn set_power_state(&self, power: PowerState) {
+        let (mode, listeners) = {
+            let mut state = locks::write(&self.state, "plugin API state");
+            if state.power_state == power {
+                return;
+            }
+            state.power_state = power;
+            // Callbacks run without holding the lock
+            (state.power_policy.mode(&power), state.power_listeners.clone())
+        };
+
+        for listener in listeners {
+            listener(&power, mode);
+        }
+    }
+
+    /// Read the power supply with a monitor and report it
+    pub fn refresh_power_state(&self, monitor: &dyn PowerMonitor) -> Result<PowerState, String> {
+        let power = monitor.read()?;
+        self.set_power_state(power);
+        Ok(power)
+    }
+
+    /// Get what throttles on battery
+    pub fn power_policy(&self) -> PowerPolicy {
+        locks::read(&self.state, "plugin API state").power_policy
+    }
+
+    /// Change what throttles on battery
+    pub fn set_power_policy(&self, policy: PowerPolicy) {
+        locks::write(&self.state, "plugin API state").power_policy = policy;
+    }
+
+    /// Subscribe to power state changes
+    ///
+    /// # Arguments
+    /// * `listener` - Callback receiving the new state and mode
+    pub fn subscribe_power_changes(&self, listener: PowerListener) -> Result<(), String> {
+        let mut state = locks::write(&self.state, "plugin API state");
+
+        state.power_listeners.push(listener);
+        Ok(())
+    }
+
+    /// Forget everything the API recorded at or after a time
+    ///
+    /// Currently purges the recent actions feed. Use
+    /// `PluginRegistry::purge_recorded_since` to purge plugins' stores too.
+    ///
+    /// # Arguments
+    /// * `since` - Time in milliseconds since the Unix epoch
+    pub fn purge_recorded_since(&self, since: u64) -> Result<(), String> {
+        let mut state = locks::write(&self.state, "plugin API state");
+
+        let path = state.app_data_dir.join("recent_actions.json");
+        if state.recent_actions.is_none() {
+            state.recent_actions = Some(RecentActions::load(&path)?);
+        }
+
+        let feed = state.recent_actions.get_or_insert_with(RecentActions::new);
+        feed.purge_since(since);
+
+        if path.exists() {
+            feed.save(&path)?;
+        }
+        Ok(())
+    }
+
+    // ========== Key-Value Store ==========
+
+    /// Get a value from a plugin's key-value store
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    /// * `key` - The key
+    pub fn kv_get(&self, plugin_id: &str, key: &str) -> Result<Option<serde_json::Value>, String> {
+        let call = || ApiCall::KvGet {
+            key: key.to_string(),
+        };
+        self.traced(plugin_id, call, || {
+            self.with_kv(plugin_id, false, |store| Ok(store.get(key).cloned()))
+        })
+    }
+
+    /// Set a value in a plugin's key-value store
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    /// * `key` - The key
+    /// * `value` - The new value
+    pub fn kv_set(&self, plugin_id: &str, key: &str, value: serde_json::Value) -> Result<(), String> {
+        let call = || ApiCall::KvSet {
+            key: key.to_string(),
+        };
+        self.traced(plugin_id, call, || {
+            self.with_kv(plugin_id, true, |store| store.set(key, value))
+        })
+    }
+
+    /// Remove a key from a plugin's key-value store
+    ///
+    /// # Returns
+    /// The removed value, if the key existed
+    pub fn kv_remove(&self, plugin_id: &str, key: &str) -> Result<Option<serde_json::Value>, String> {
+        self.with_kv(plugin_id, true, |store| Ok(store.remove(key)))
+    }
+
+    /// Get the keys of a plugin's key-value store starting with a prefix, sorted
+    pub fn kv_keys(&self, plugin_id: &str, prefix: &str) -> Result<Vec<String>, String> {
+        self.with_kv(plugin_id, false, |store| Ok(store.keys(prefix)))
+    }
+
+    /// Run an operation on a plugin's store, saving it afterwards if `modify` is set
+    fn with_kv<T>(
+        &self,
+        plugin_id: &str,
+        modify: bool,
+        operation: impl FnOnce(&mut KvStore) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let path = self.get_plugin_data_dir(plugin_id)?.join(KV_FILE);
+        let mut state = locks::write(&self.state, "plugin API state");
+
+        let store = match state.kv.entry(plugin_id.to_string()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(KvStore::load(&path)?),
+        };
+
+        let output = operation(store)?;
+        if modify {
+            store.save(&path)?;
+        }
+        Ok(output)
+    }
+
+    // ========== Clipboard and Input ==========
+
+    /// Install the system clipboard
+    ///
+    /// Called by the host along with declaring `HostFeature::Clipboard` available.
+    pub fn set_clipboard_backend(&self, backend: Arc<dyn ClipboardBackend>) {
+        locks::write(&self.state, "plugin API state").clipboard = Some(backend);
+    }
+
+    /// Read the system clipboard as text
+    pub fn read_clipboard(&self) -> Result<String, String> {
+        self.clipboard()?.read_text()
+    }
+
+    /// Replace the system clipboard contents with text
+    pub fn write_clipboard(&self, text: &str) -> Result<(), String> {
+        self.clipboard()?.write_text(text)
+    }
+
+    /// Read the image on the system clipboard
+    ///
+    /// The image is decoded within `ImageLimits::CLIPBOARD`, downscaled and
+    /// re-encoded as PNG; images exceeding the limits are refused.
+    ///
+    /// # Returns
+    /// The image, or None if the clipboard holds none
+    #[cfg(feature = "imaging")]
+    pub fn read_clipboard_image(&self) -> Result<Option<crate::imaging::NormalizedImage>, String> {
+        let Some(bytes) = self.clipboard()?.read_image()? else {
+            return Ok(None);
+        };
+
+        crate::imaging::normalize(&bytes, &crate::imaging::ImageLimits::CLIPBOARD).map(Some)
+    }
+
+    fn clipboard(&self) -> Result<Arc<dyn ClipboardBackend>, String> {
+        let state = locks::read(&self.state, "plugin API state");
+        state.features.require(HostFeature::Clipboard)?;
+        state
+            .clipboard
+            .clone()
+            .ok_or_else(|| "Host feature 'clipboard' is not available".to_string())
+    }
+
+    /// Install the callback typing text into the focused application
+    ///
+    /// Called by the host along with declaring `HostFeature::InputSynthesis`
+    /// available.
+    pub fn set_input_synthesizer(&self, synthesizer: InputSynthesizer) {
+        locks::write(&self.state, "plugin API state").input = Some(synthesizer);
+    }
+
+    /// Type text into the application the user was in before opening Volt
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    /// * `insertion` - The text and where to leave the caret
+    pub fn insert_text(&self, plugin_id: &str, insertion: &TextInsertion) -> Result<(), String> {
+        Self::validate_plugin_id(plugin_id)?;
+
+        let synthesizer = {
+            let state = locks::read(&self.state, "plugin API state");
+            state.features.require(HostFeature::InputSynthesis)?;
+            state
+                .input
+                .clone()
+                .ok_or_else(|| "Host feature 'inputSynthesis' is not available".to_string())?
+        };
+
+        synthesizer(insertion)
+    }
+
+    /// Install the callback reading the text selected in the focused application
+    ///
+    /// Called by the host along with declaring `HostFeature::Selection`
+    /// available.
+    pub fn set_selection_reader(&self, reader: SelectionReader) {
+        locks::write(&self.state, "plugin API state").selection_reader = Some(reader);
+    }
+
+    /// Start the selection of a new launcher activation
+    ///
+    /// The host calls this each time the launcher opens and attaches the
+    /// result to the activation's queries with `QueryContext::with_selection`.
+    /// Only plugins declaring the `selection` permission can read it.
+    pub fn activation_selection(&self) -> Selection {
+        let reader = {
+            let state = locks::read(&self.state, "plugin API state");
+            state
+                .selection_reader
+                .clone()
+                .filter(|_| state.features.contains(HostFeature::Selection))
+        };
+
+        let api = self.clone();
+        Selection::new(reader, move |plugin_id| {
+            api.require_capability(plugin_id, PluginCapability::ReadSelection)
+                .is_ok()
+        })
+    }
+
+    // ========== Window Management ==========
+
+    /// Install the window manager
+    ///
+    /// Called by the host along with declaring `HostFeature::WindowManagement`
+    /// available.
+    pub fn set_window_manager(&self, manager: Arc<dyn WindowManager>) {
+        locks::write(&self.state, "plugin API state").window_manager = Some(manager);
+    }
+
+    /// Get the window manager
+    pub fn window_manager(&self) -> Result<Arc<dyn WindowManager>, String> {
+        let state = locks::read(&self.state, "plugin API state");
+        state.features.require(HostFeature::WindowManagement)?;
+        state
+            .window_manager
+            .clone()
+            .ok_or_else(|| "Host feature 'windowManagement' is not available".to_string())
+    }
+
+    /// Report a window event to the subscribed plugins
+    ///
+    /// Called by the host whenever a window opens, closes, changes or gains
+    /// focus.
+    pub fn notify_window_event(&self, event: &WindowEvent) {
+        // Callbacks run without holding the lock
+        let listeners = locks::read(&self.state, "plugin API state").window_listeners.clone();
+
+        for listener in listeners {
+            listener(event);
+        }
+    }
+
+    /// Subscribe to window events
+    ///
+    /// # Arguments
+    /// * `listener` - Callback receiving each event
+    pub fn subscribe_window_events(&self, listener: WindowListener) -> Result<(), String> {
+        let mut state = locks::write(&self.state, "plugin API state");
+
+        state.window_listeners.push(listener);
+        Ok(())
+    }
+
+    // ========== Notifications ==========
+
+    /// Install the callback displaying notifications
+    ///
+    /// Called by the host along with declaring `HostFeature::Notifications`
+    /// available.
+    pub fn set_notifier(&self, notifier: Notifier) {
+        locks::write(&self.state, "plugin API state").notifier = Some(notifier);
+    }
+
+    /// Show a desktop notification on behalf of a plugin
+    ///
+    /// Clicks on the notification's actions are delivered to the plugin's
+    /// `on_notification_action` hook.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    /// * `notification` - The notification; its `plugin_id` is overwritten
+    pub fn notify(&self, plugin_id: &str, mut notification: Notification) -> Result<(), String> {
+        Self::validate_plugin_id(plugin_id)?;
+
+        let notifier = {
+            let state = locks::read(&self.state, "plugin API state");
+            match &state.notifier {
+                Some(notifier) if state.features.contains(HostFeature::Notifications) => notifier.clone(),
+                _ => return Err("Notifications are not available".to_string()),
+            }
+        };
+
+        notification.plugin_id = plugin_id.to_string();
+        notifier(&notification)
+    }
+
+    // ========== Sharing and Export ==========
+
+    /// Install the share sheet
+    ///
+    /// Called by the host along with declaring `HostFeature::ShareSheet`
+    /// available.
+    pub fn set_share_backend(&self, backend: Arc<dyn ShareBackend>) {
+        locks::write(&self.state, "plugin API state").share_sheet = Some(backend);
+    }
+
+    /// Offer a file through the platform's share sheet
+    ///
+    /// # Arguments
+    /// * `path` - Absolute path of the file
+    pub fn share_file(&self, path: &std::path::Path) -> Result<(), String> {
+        let share_sheet = {
+            let state = locks::read(&self.state, "plugin API state");
+            state.features.require(HostFeature::ShareSheet)?;
+            state
+                .share_sheet
+                .clone()
+                .ok_or_else(|| "Host feature 'shareSheet' is not available".to_string())?
+        };
+        share_sheet.share(path)
+    }
+
+    /// Export results to a file or the share sheet, see `crate::export`
+    ///
+    /// Exports for the share sheet are written to the `exports` directory
+    /// of the cache first.
+    ///
+    /// # Arguments
+    /// * `results` - Results to export, in order: the merged list or the user's selection
+    /// * `format` - Format of the export
+    /// * `destination` - Where the export goes
+    ///
+    /// # Returns
+    /// The file the export was written to
+    pub fn export_results(
+        &self,
+        results: &[PluginResult],
+        format: ExportFormat,
+        destination: &ExportDestination,
+    ) -> Result<PathBuf, String> {
+        let content = crate::export::render(results, format)?;
+
+        let path = match destination {
+            ExportDestination::File { path } => path.clone(),
+            ExportDestination::ShareSheet => {
+                let dir = locks::read(&self.state, "plugin API state").cache_dir.join(EXPORTS_DIR);
+                std::fs::create_dir_all(&dir)
+                    .map_err(|e| format!("Failed to create exports directory: {}", e))?;
+                dir.join(format!("volt-results-{}.{}", self.now_millis(), format.extension()))
+            }
+        };
+        std::fs::write(&path, content).map_err(|e| format!("Failed to write export: {}", e))?;
+
+        if *destination == ExportDestination::ShareSheet {
+            self.share_file(&path)?;
+        }
+        Ok(path)
+    }
+
+    // ========== Synchronization ==========
+
+    /// Synchronize part of a plugin's state across the user's devices
+    ///
+    /// Replaces the plugin's previous scope. The host's `SyncEngine` picks
+    /// the scope up on its next run.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    /// * `scope` - Configurations and key-value prefixes to synchronize
+    #[cfg(feature = "sync")]
+    pub fn enable_sync(&self, plugin_id: &str, scope: crate::sync::SyncScope) -> Result<(), String> {
+        Self::validate_plugin_id(plugin_id)?;
+        for config_name in &scope.configs {
+            Self::validate_config_name(config_name)?;
+        }
+
+        locks::write(&self.state, "plugin API state")
+            .sync_scopes
+            .insert(plugin_id.to_string(), scope);
+        Ok(())
+    }
+
+    /// Stop synchronizing a plugin's state; what was uploaded stays on the backend
+    #[cfg(feature = "sync")]
+    pub fn disable_sync(&self, plugin_id: &str) {
+        locks::write(&self.state, "plugin API state").sync_scopes.remove(plugin_id);
+    }
+
+    /// Get the synchronized scope of every plugin that opted in, sorted by plugin
+    #[cfg(feature = "sync")]
+    pub fn sync_scopes(&self) -> Vec<(String, crate::sync::SyncScope)> {
+        let state = locks::read(&self.state, "plugin API state");
+        let mut scopes: Vec<_> = state
+            .sync_scopes
+            .iter()
+            .map(|(plugin_id, scope)| (plugin_id.clone(), scope.clone()))
+            .collect();
+        scopes.sort_by(|a, b| a.0.cmp(&b.0));
+        scopes
+    }
+
+    // ========== Localization ==========
+
+    /// Set the user's locale, e.g. "de-AT"
+    ///
+    /// Built-in plugins show their strings in it, falling back to English
+    /// for locales without translations.
+    pub fn set_locale(&self, locale: &str) {
+        locks::write(&self.state, "plugin API state").messages = Messages::for_locale(locale);
+    }
+
+    /// Get the strings of the user's locale
+    pub fn messages(&self) -> Messages {
+        locks::read(&self.state, "plugin API state").messages
+    }
+
+    // ========== Feature Detection ==========
+
+    /// Get the optional host subsystems available at runtime
+    ///
+    /// Plugins should check this before using clipboard, notifications,
+    /// window management, the indexer or secrets, and degrade gracefully
+    /// when a backend is absent.
+    pub fn features(&self) -> FeatureSet {
+        locks::read(&self.state, "plugin API state").features
+    }
+
+    /// Declare whether a host subsystem is available
+    ///
+    /// Called by the host once the corresponding backend is initialized.
+    ///
+    /// # Arguments
+    /// * `feature` - The subsystem
+    /// * `available` - Whether plugins can use it
+    pub fn set_feature_available(&self, feature: HostFeature, available: bool) -> Result<(), String> {
+        let mut state = locks::write(&self.state, "plugin API state");
+
+        if available {
+            state.features.insert(feature);
+        } else {
+            state.features.remove(feature);
+        }
+
+        Ok(())
+    }
+
+    /// Install every platform-facing subsystem of a backend
+    ///
+    /// Replaces the clipboard, notifier, window manager, input synthesizer,
+    /// selection reader and share sheet, and declares available exactly the
+    /// features the backend provides. See `backends::select` to pick a backend at startup.
+    pub fn install_backend(&self, backend: Arc<dyn PlatformBackend>) {
+        let clipboard = backend.clipboard();
+        let window_manager = backend.window_manager();
+        let notifier: Option<Notifier> = backend.notifications().map(|notifications| {
+            Arc::new(move |notification: &Notification| notifications.show(notification)) as Notifier
+        });
+        let input: Option<InputSynthesizer> = backend.input().map(|input| {
+            Arc::new(move |insertion: &TextInsertion| input.insert_text(insertion)) as InputSynthesizer
+        });
+        let selection_reader: Option<SelectionReader> = backend
+            .selection()
+            .map(|selection| Arc::new(move || selection.read_selection()) as SelectionReader);
+        let share_sheet = backend.share_sheet();
+
+        let mut state = locks::write(&self.state, "plugin API state");
+        for (feature, available) in [
+            (HostFeature::Clipboard, clipboard.is_some()),
+            (HostFeature::Notifications, notifier.is_some()),
+            (HostFeature::WindowManagement, window_manager.is_some()),
+            (HostFeature::InputSynthesis, input.is_some()),
+            (HostFeature::Selection, selection_reader.is_some()),
+            (HostFeature::ShareSheet, share_sheet.is_some()),
+        ] {
+            if available {
+                state.features.insert(feature);
+            } else {
+                state.features.remove(feature);
+            }
+        }
+        state.clipboard = clipboard;
+        state.window_manager = window_manager;
+        state.notifier = notifier;
+        state.input = input;
+        state.selection_reader = selection_reader;
+        state.share_sheet = share_sheet;
+        drop(state);
+
+        self.diagnostics().info("backends", &format!("Installed platform backend '{}'", backend.name()));
+    }
+
+    // ========== Regular Expressions ==========
+
+    /// Compile a regular expression, typically one typed by the user
+    ///
+    /// Matching takes time linear in the input whatever the pattern, and
+    /// patterns exceeding the size and nesting limits are rejected. Compiled
+    /// patterns are cached across plugins. See `patterns`.
+    ///
+    /// # Arguments
+    /// * `pattern` - Pattern in the syntax of the `regex` crate
+    pub fn compile_regex(&self, pattern: &str) -> Result<Regex, String> {
+        self.regex_cache().compile(pattern)
+    }
+
+    /// Get the cache of compiled patterns shared by plugins and the host
+    pub fn regex_cache(&self) -> RegexCache {
+        locks::read(&self.state, "plugin API state").regexes.clone()
+    }
+
+    /// Replace the cache of compiled patterns, e.g. to change its limits
+    pub fn set_regex_cache(&self, cache: RegexCache) {
+        locks::write(&self.state, "plugin API state").regexes = cache;
+    }
+
+    // ========== Spell Correction ==========
+
+    /// Enable or disable query spell correction (host setting, on by default)
+    pub fn set_spell_correction(&self, enabled: bool) {
+        locks::write(&self.state, "plugin API state").spell_correction = enabled;
+    }
+
+    /// Check if query spell correction is enabled
+    pub fn spell_correction_enabled(&self) -> bool {
+        locks::read(&self.state, "plugin API state").spell_correction
+    }
+
+    /// Correct a query with a corrector, if correction is enabled
+    ///
+    /// Run the corrected query through `Correction::context` and mark the
+    /// results with `Correction::mark`, so the UI can show what was typed.
+    ///
+    /// # Arguments
+    /// * `corrector` - The router's corrector or a plugin's own vocabulary
+    /// * `query` - Query as typed
+    ///
+    /// # Returns
+    /// None if correction is disabled or nothing was misspelled
+    pub fn correct_query(&self, corrector: &SpellCorrector, query: &str) -> Option<Correction> {
+        if !self.spell_correction_enabled() {
+            return None;
+        }
+        corrector.correct(query)
+    }
+
+    // ========== Usage Statistics ==========
+
+    /// Get the usage statistics shared with the registry
+    ///
+    /// The host hands them to `PluginRegistry::with_usage_stats` so queries
+    /// and executions are recorded, and saves them when shutting down.
+    pub fn usage_stats(&self) -> UsageStats {
+        locks::read(&self.state, "plugin API state").usage.clone()
+    }
+
+    /// Record a usage event at the API's current time
+    fn record_usage(&self, plugin_id: &str, event: UsageEvent) {
+        self.usage_stats().record_at(plugin_id, event, self.now_millis());
+    }
+
+    /// Get where the API's diagnostics go
+    ///
+    /// The host hands them to `PluginRegistry::with_diagnostics` so registry
+    /// diagnostics reach the same sink as the plugin logs.
+    pub fn diagnostics(&self) -> Diagnostics {
+        locks::read(&self.state, "plugin API state").diagnostics.clone()
+    }
+
+    /// Get the tokenizer text is matched with
+    ///
+    /// The one given to `VoltPluginAPIBuilder::with_tokenizer`, or else the
+    /// one installed with `matching::set_tokenizer`.
+    pub fn tokenizer(&self) -> Arc<Tokenizer> {
+        locks::read(&self.state, "plugin API state")
+            .tokenizer
+            .clone()
+            .unwrap_or_else(matching::tokenizer)
+    }
+
+    /// Get the clock timestamps written by the API are read from
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        locks::read(&self.state, "plugin API state").clock.clone()
+    }
+
+    /// Current time of the API's clock, in milliseconds since the Unix epoch
+    fn now_millis(&self) -> u64 {
+        self.clock().now_millis()
+    }
+
+    /// Set the windows `my_stats` reports on
+    ///
+    /// # Arguments
+    /// * `windows` - Window lengths, e.g. the last hour and the last week
+    pub fn set_stats_windows(&self, mut windows: Vec<Duration>) {
+        windows.sort();
+        windows.dedup();
+        locks::write(&self.state, "plugin API state").stats_windows = windows;
+    }
+
+    /// Get how a plugin performed on this device
+    ///
+    /// Meant for plugin authors tuning their plugin: query counts, how
+    /// often its results are picked, its latency, cache hit rate and errors.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    ///
+    /// # Returns
+    /// The statistics over each configured window, shortest first
+    pub fn my_stats(&self, plugin_id: &str) -> Result<UsageReport, String> {
+        Self::validate_plugin_id(plugin_id)?;
+
+        let (usage, windows) = {
+            let state = locks::read(&self.state, "plugin API state");
+            (state.usage.clone(), state.stats_windows.clone())
+        };
+        Ok(UsageReport {
+            plugin_id: plugin_id.to_string(),
+            windows: windows
+                .into_iter()
+                .map(|window| usage.stats_at(plugin_id, window, self.now_millis()))
+                .collect(),
+        })
+    }
+
+    // ========== Background Tasks ==========
+
+    /// Get the supervisor plugins spawn background tasks through
+    ///
+    /// The host sets each heavy plugin's `SandboxProfile` on it and hands it
+    /// to `PluginRegistry::with_tasks` so tasks stop when plugins are disabled.
+    #[cfg(feature = "isolation")]
+    pub fn tasks(&self) -> crate::runtime::TaskSupervisor {
+        locks::read(&self.state, "plugin API state").tasks.clone()
+    }
+
+    // ========== Application Information ==========
+
+    /// Get Volt's version
+    ///
+    /// The one given to `VoltPluginAPIBuilder::with_volt_version`, or else
+    /// this crate's version.
+    pub fn get_volt_version(&self) -> &str {
+        &self.volt_version
+    }
+
+    /// Get application data directory
+    pub fn get_app_data_dir(&self) -> Result<PathBuf, String> {
+        let state = locks::read(&self.state, "plugin API state");
+
+        Ok(state.app_data_dir.clone())
+    }
+
+    /// Poison the internal state lock by panicking while holding it
+    #[cfg(feature = "chaos")]
+    pub(crate) fn poison_state(&self) {
+        let state = self.state.clone();
+        let _ = std::thread::spawn(move || {
+            let _guard = state.write();
+            panic!("Injected fault: lock poisoning");
+        })
+        .join();
+    }
+}
+
+/// Log levels for plugin logging
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLevel {
+    /// Informational messages
+    Info,
+    /// Warning messages
+    Warn,
+    /// Error messages
+    Error,
+    /// Debug messages
+    Debug,
+}
+
+impl LogLevel {
+    /// Get the upper-case name used in log output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Info => "INFO",
+            LogLevel::Warn => "WARN",
+            LogLevel::Error => "ERROR",
+            LogLevel::Debug => "DEBUG",
+        }
+    }
+}
+
+/// Plugin capabilities that can be requested
+///
+/// Plugins should declare which capabilities they need for security and transparency
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PluginCapability {
+    /// Access to file system
+    FileSystem,
+    /// Network access
+    Network,
+    /// Access to system information
+    SystemInfo,
+    /// Ability to execute commands
+    ExecuteCommands,
+    /// Access to user's application data
+    ApplicationData,
+    /// Modify search results
+    ModifySearch,
+    /// Run commands with administrator rights
+    Elevation,
+    /// Read the text selected in the application the launcher was opened from
+    ReadSelection,
+}
+
+impl PluginCapability {
+    /// Get the permission name plugins declare in their manifest
+    pub fn permission(&self) -> &'static str {
+        match self {
+            PluginCapability::FileSystem => "filesystem",
+            PluginCapability::Network => "network",
+            PluginCapability::SystemInfo => "system_info",
+            PluginCapability::ExecuteCommands => "execute_commands",
+            PluginCapability::ApplicationData => "application_data",
+            PluginCapability::ModifySearch => "modify_search",
+            PluginCapability::Elevation => "elevation",
+            PluginCapability::ReadSelection => "selection",
+        }
+    }
+
+    /// Get human-readable description of the capability
+    pub fn description(&self) -> &str {
+        match self {
+            PluginCapability::FileSystem => "Read and write files on your computer",
+            PluginCapability::Network => "Access the internet and make network requests",
+            PluginCapability::SystemInfo => "Read system information (CPU, memory, etc.)",
+            PluginCapability::ExecuteCommands => "Execute programs and commands",
+            PluginCapability::ApplicationData => "Access your application data and history",
+            PluginCapability::ModifySearch => "Modify and add to search results",
+            PluginCapability::Elevation => "Run commands as administrator, after asking you each time",
+            PluginCapability::ReadSelection => "Read the text you selected before opening Volt",
+        }
+    }
+
+    /// Check if this capability is considered sensitive
+    pub fn is_sensitive(&self) -> bool {
+        matches!(
+            self,
+            PluginCapability::FileSystem
+                | PluginCapability::Network
+                | PluginCapability::ExecuteCommands
+                | PluginCapability::ApplicationData
+                | PluginCapability::Elevation
+                | PluginCapability::ReadSelection
+        )
+    }
+}
+
+/// Decides whether plugins may use capabilities
+///
+/// Hosts enforcing an admin policy on top of manifests install their own
+/// through `VoltPluginAPI::builder`.
+pub trait CapabilityBroker: Send + Sync {
+    /// Check that a plugin may use a capability
+    ///
+    /// # Arguments
+    /// * `plugin` - The installed plugin
+    /// * `capability` - Capability the plugin is about to use
+    ///
+    /// # Returns
+    /// Ok(()) if granted, Err with the reason given to the plugin otherwise
+    fn check(&self, plugin: &PluginInfo, capability: PluginCapability) -> Result<(), String>;
+}
+
+/// Grants plugins the capabilities declared in their manifest
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ManifestCapabilities;
+
+impl CapabilityBroker for ManifestCapabilities {
+    fn check(&self, plugin: &PluginInfo, capability: PluginCapability) -> Result<(), String> {
+        if plugin.capabilities.iter().any(|declared| declared == capability.permission()) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Plugin '{}' needs the '{}' permission: {}",
+                plugin.id,
+                capability.permission(),
+                capability.description()
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
     use super::*;
+    use proptest::prelude::*;
     use std::env;
 
     #[test]
@@ -506,6 +2651,40 @@ mod tests {
         let _ = std::fs::remove_dir_all(temp_dir);
     }
 
+    #[test]
+    fn test_renamed_plugins_keep_their_data() {
+        let temp_dir = env::temp_dir().join("volt_test_identity");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let api = VoltPluginAPI::new(temp_dir.clone());
+        let mut manifest = PluginManifest {
+            id: "clipboard".to_string(),
+            uuid: Some("0b7d2c4e-8f1a-4c3b-9e6d-5a2f1b8c7d90".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(api.migrate_plugin_identity(&manifest), Ok(None));
+        api.kv_set("clipboard", "pinned", serde_json::json!(3)).unwrap();
+        api.save_config("clipboard", "settings", &serde_json::json!({"max": 50}))
+            .unwrap();
+
+        manifest.id = "clipboard-history".to_string();
+        let rename = api.migrate_plugin_identity(&manifest).unwrap().unwrap();
+        assert_eq!((rename.from.as_str(), rename.to.as_str()), ("clipboard", "clipboard-history"));
+        assert_eq!(api.kv_get("clipboard-history", "pinned").unwrap(), Some(serde_json::json!(3)));
+        assert_eq!(
+            api.load_config("clipboard-history", "settings").unwrap(),
+            serde_json::json!({"max": 50})
+        );
+        assert_eq!(api.kv_get("clipboard", "pinned").unwrap(), None);
+        assert_eq!(api.migrate_plugin_identity(&manifest), Ok(None));
+
+        manifest.uuid = Some("not-a-uuid".to_string());
+        assert!(api.migrate_plugin_identity(&manifest).is_err());
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+
     #[test]
     fn test_config_save_load() {
         let temp_dir = env::temp_dir().join("volt_test_config");
@@ -526,6 +2705,258 @@ mod tests {
         let _ = std::fs::remove_dir_all(temp_dir);
     }
 
+    #[test]
+    fn test_config_change_notifications() {
+        let temp_dir = env::temp_dir().join("volt_test_config_changes");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let api = VoltPluginAPI::new(temp_dir.clone());
+
+        let changes = Arc::new(RwLock::new(Vec::new()));
+        let sink = changes.clone();
+        api.subscribe_config_changes(Arc::new(move |change: &ConfigChange| {
+            sink.write().unwrap().push(change.clone());
+        }))
+        .unwrap();
+
+        let config = serde_json::json!({"theme": "dark"});
+        api.save_config("test_plugin", "settings", &config).unwrap();
+        assert_eq!(api.detect_config_changes().unwrap(), Vec::new());
+
+        // Simulate an edit made by another program
+        let config_path = api
+            .get_plugin_config_dir("test_plugin")
+            .unwrap()
+            .join("settings.json");
+        std::fs::write(&config_path, r#"{"theme": "light"}"#).unwrap();
+        let file = std::fs::File::options().write(true).open(&config_path).unwrap();
+        file.set_modified(SystemTime::now() + std::time::Duration::from_secs(5))
+            .unwrap();
+
+        let detected = api.detect_config_changes().unwrap();
+        assert_eq!(detected.len(), 1);
+        assert_eq!(detected[0].source, ConfigChangeSource::External);
+
+        let changes = changes.read().unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].source, ConfigChangeSource::Saved);
+        assert_eq!(changes[0].config, config);
+        assert_eq!(changes[1].config, serde_json::json!({"theme": "light"}));
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn test_refresh_requests() {
+        let api = VoltPluginAPI::new(env::temp_dir().join("volt_test_refresh_requests"));
+        assert!(api.refresh_results("downloads").is_ok());
+
+        let requests = Arc::new(RwLock::new(Vec::new()));
+        let sink = requests.clone();
+        api.subscribe_refresh_requests(Arc::new(move |plugin_id: &str| {
+            sink.write().unwrap().push(plugin_id.to_string());
+        }))
+        .unwrap();
+
+        api.refresh_results("downloads").unwrap();
+        assert!(api.refresh_results("../downloads").is_err());
+        assert_eq!(*requests.read().unwrap(), vec!["downloads".to_string()]);
+    }
+
+    #[test]
+    fn test_feature_detection() {
+        let api = VoltPluginAPI::new(env::temp_dir().join("volt_test_features"));
+        assert_eq!(api.features(), FeatureSet::empty());
+
+        api.set_feature_available(HostFeature::Clipboard, true).unwrap();
+        api.set_feature_available(HostFeature::Notifications, true).unwrap();
+        api.set_feature_available(HostFeature::Notifications, false).unwrap();
+
+        let features = api.features();
+        assert!(features.contains(HostFeature::Clipboard));
+        assert!(!features.contains(HostFeature::Notifications));
+    }
+
+    #[test]
+    fn test_capabilities_are_enforced() {
+        let temp_dir = env::temp_dir().join("volt_test_capabilities");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let api = VoltPluginAPI::new(temp_dir.clone());
+
+        let package_dir = api.get_plugin_package_dir("automator").unwrap();
+        std::fs::create_dir_all(&package_dir).unwrap();
+        std::fs::write(
+            package_dir.join("manifest.json"),
+            r#"{"id": "automator", "name": "Automator", "version": "1.0.0", "permissions": ["execute_commands"]}"#,
+        )
+        .unwrap();
+
+        assert!(api
+            .require_capability("automator", PluginCapability::ExecuteCommands)
+            .is_ok());
+        assert!(api.require_capability("automator", PluginCapability::Network).is_err());
+        assert!(api
+            .require_capability("missing", PluginCapability::ExecuteCommands)
+            .is_err());
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn test_selection_requires_permission() {
+        let temp_dir = env::temp_dir().join("volt_test_selection");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let api = VoltPluginAPI::new(temp_dir.clone());
+        for (plugin_id, permissions) in [("dictionary", r#"["selection"]"#), ("clipboard", "[]")] {
+            let package_dir = api.get_plugin_package_dir(plugin_id).unwrap();
+            std::fs::create_dir_all(&package_dir).unwrap();
+            std::fs::write(
+                package_dir.join("manifest.json"),
+                format!(
+                    r#"{{"id": "{}", "name": "{}", "version": "1.0.0", "permissions": {}}}"#,
+                    plugin_id, plugin_id, permissions
+                ),
+            )
+            .unwrap();
+        }
+        api.set_selection_reader(Arc::new(|| Some("serendipity".to_string())));
+
+        let context = crate::plugin::QueryContext::new("define").with_selection(api.activation_selection());
+        assert_eq!(context.scoped_to("dictionary").selection(), None);
+
+        api.set_feature_available(HostFeature::Selection, true).unwrap();
+        let context = crate::plugin::QueryContext::new("define").with_selection(api.activation_selection());
+        assert_eq!(context.scoped_to("dictionary").selection().as_deref(), Some("serendipity"));
+        assert_eq!(context.scoped_to("clipboard").selection(), None);
+        assert_eq!(context.selection(), None);
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn test_elevated_commands_are_confirmed_and_audited() {
+        let temp_dir = env::temp_dir().join("volt_test_elevation");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let api = VoltPluginAPI::new(temp_dir.clone());
+
+        for (plugin_id, permissions) in [
+            ("hosts", r#"["execute_commands", "elevation"]"#),
+            ("runner", r#"["execute_commands"]"#),
+        ] {
+            let package_dir = api.get_plugin_package_dir(plugin_id).unwrap();
+            std::fs::create_dir_all(&package_dir).unwrap();
+            std::fs::write(
+                package_dir.join("manifest.json"),
+                format!(
+                    r#"{{"id": "{}", "name": "Hosts Editor", "version": "1.0.0", "permissions": {}}}"#,
+                    plugin_id, permissions
+                ),
+            )
+            .unwrap();
+        }
+
+        let spec = CommandSpec::new("systemctl").arg("restart").arg("nginx");
+        let error = api.execute_elevated("hosts", spec.clone()).unwrap_err();
+        assert_eq!(error, "Elevation is not available");
+        assert!(api.execute_elevated("runner", spec.clone()).unwrap_err().contains("'elevation' permission"));
+
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = requests.clone();
+        api.set_elevation_confirmer(Arc::new(move |request: &ElevationRequest| {
+            sink.lock().unwrap().push(request.clone());
+            false
+        }));
+        assert!(api.execute_elevated("hosts", spec).unwrap_err().ends_with("was declined"));
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests[0].plugin_name, "Hosts Editor");
+        assert_eq!(requests[0].command_line, "systemctl restart nginx");
+        let trail = api.audit_trail().unwrap();
+        assert_eq!(trail.len(), 1);
+        assert_eq!(trail[0].plugin_id, "hosts");
+        assert_eq!(trail[0].detail, "systemctl restart nginx");
+        assert_eq!(trail[0].outcome, AuditOutcome::Declined);
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn test_recent_actions_persist() {
+        let temp_dir = env::temp_dir().join("volt_test_recent_actions");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let api = VoltPluginAPI::new(temp_dir.clone());
+
+        api.record_action("calc", &PluginResult::new("sum", "4")).unwrap();
+        api.record_action("files", &PluginResult::new("report", "report.pdf")).unwrap();
+        api.record_action("calc", &PluginResult::new("sum", "4")).unwrap();
+
+        let reloaded = VoltPluginAPI::new(temp_dir.clone());
+        let actions = reloaded.recent_actions(10).unwrap();
+        assert_eq!(actions.len(), 2);
+        assert_eq!(actions[0].plugin_id, "calc");
+        assert_eq!(actions[0].count, 2);
+        assert!(actions[0].executed_at >= actions[1].executed_at);
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+
+    #[cfg(feature = "diagnostics")]
+    #[test]
+    fn test_diagnostics_bundle_redacts_secrets() {
+        let temp_dir = env::temp_dir().join("volt_test_diagnostics");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let api = VoltPluginAPI::new(temp_dir.clone());
+
+        api.save_config("weather", "settings", &serde_json::json!({"apiToken": "tok-9876", "city": "Oslo"}))
+            .unwrap();
+        api.log("weather", LogLevel::Error, "Request with tok-9876 was rejected");
+
+        let path = api.diagnostics_bundle("weather", None).unwrap();
+        assert!(path.starts_with(temp_dir.join("diagnostics")));
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let mut read = |name: &str| {
+            let mut content = String::new();
+            std::io::Read::read_to_string(&mut archive.by_name(name).unwrap(), &mut content).unwrap();
+            content
+        };
+
+        let config: serde_json::Value = serde_json::from_str(&read("config/settings.json")).unwrap();
+        assert_eq!(config, serde_json::json!({"apiToken": "[REDACTED]", "city": "Oslo"}));
+        assert!(read("logs.txt").ends_with("ERROR: Request with [REDACTED] was rejected"));
+        assert!(read("errors.txt").contains("[REDACTED]"));
+        assert!(read("version.json").contains("\"pluginId\": \"weather\""));
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn test_index_contribution() {
+        let api = VoltPluginAPI::new(env::temp_dir().join("volt_test_index"));
+
+        api.index_documents(
+            "notes",
+            vec![IndexDoc::new("1", "Standup notes"), IndexDoc::new("2", "Recipes")],
+        )
+        .unwrap();
+        api.index_documents("wiki", vec![IndexDoc::new("1", "Standup process")])
+            .unwrap();
+        api.remove_documents("notes", vec!["2".to_string()]).unwrap();
+
+        let hits = api.search_index("standup", None, 10);
+        assert_eq!(hits.len(), 2);
+        assert!(api.search_index("recipes", None, 10).is_empty());
+        assert_eq!(api.search_index("standup", Some("wiki"), 10)[0].plugin_id, "wiki");
+
+        api.clear_documents("wiki");
+        assert_eq!(api.search_index("standup", None, 10).len(), 1);
+        assert!(api.index_documents("../evil", Vec::new()).is_err());
+    }
+
     #[test]
     fn test_cache_operations() {
         let temp_dir = env::temp_dir().join("volt_test_cache");
@@ -543,4 +2974,175 @@ mod tests {
         // Cleanup
         let _ = std::fs::remove_dir_all(temp_dir);
     }
+
+    #[test]
+    fn test_config_save_invalidates_dependent_cache() {
+        use crate::invalidation::Dependency;
+
+        let temp_dir = env::temp_dir().join("volt_test_cache_invalidation");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let api = VoltPluginAPI::new(temp_dir.clone());
+
+        api.write_cache("test_plugin", "titles", b"cached").unwrap();
+        api.write_cache("test_plugin", "other", b"cached").unwrap();
+        api.cache_depends_on(
+            "test_plugin",
+            "titles",
+            vec![Dependency::Config {
+                plugin_id: "test_plugin".to_string(),
+                config_name: Some("settings".to_string()),
+            }],
+        )
+        .unwrap();
+        assert!(api.cache_depends_on("test_plugin", "../escape", Vec::new()).is_err());
+
+        api.save_config("test_plugin", "settings", &serde_json::json!({ "x": 1 }))
+            .unwrap();
+        assert!(api.read_cache("test_plugin", "titles").is_err());
+        assert!(api.read_cache("test_plugin", "other").is_ok());
+        assert!(api.invalidation_bus().is_empty());
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn test_export_results_to_share_sheet() {
+        struct RecordingShareSheet(std::sync::Mutex<Vec<PathBuf>>);
+
+        impl ShareBackend for RecordingShareSheet {
+            fn share(&self, path: &std::path::Path) -> Result<(), String> {
+                self.0.lock().unwrap().push(path.to_path_buf());
+                Ok(())
+            }
+        }
+
+        let temp_dir = env::temp_dir().join("volt_test_export_results");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let api = VoltPluginAPI::new(temp_dir.clone());
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let results = vec![PluginResult::new("1", "First"), PluginResult::new("2", "Second")];
+
+        let file = temp_dir.join("results.md");
+        let destination = ExportDestination::File { path: file.clone() };
+        assert_eq!(api.export_results(&results, ExportFormat::Markdown, &destination).unwrap(), file);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "- First\n- Second\n");
+
+        // The share sheet is a host feature
+        assert!(api.export_results(&results, ExportFormat::Csv, &ExportDestination::ShareSheet).is_err());
+
+        let share_sheet = Arc::new(RecordingShareSheet(std::sync::Mutex::new(Vec::new())));
+        api.set_share_backend(share_sheet.clone());
+        api.set_feature_available(HostFeature::ShareSheet, true).unwrap();
+        let shared = api.export_results(&results, ExportFormat::Csv, &ExportDestination::ShareSheet).unwrap();
+        assert_eq!(shared.extension().unwrap(), "csv");
+        assert_eq!(*share_sheet.0.lock().unwrap(), vec![shared]);
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+
+    #[cfg(feature = "compressed-assets")]
+    #[test]
+    fn test_compressed_assets_are_decompressed_lazily() {
+        use std::io::Write;
+
+        let temp_dir = env::temp_dir().join("volt_test_compressed_assets");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let api = VoltPluginAPI::new(temp_dir.clone());
+
+        let assets_dir = api.get_plugin_package_dir("dictionary").unwrap().join("assets");
+        std::fs::create_dir_all(&assets_dir).unwrap();
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"aardvark\nabacus\n").unwrap();
+        std::fs::write(assets_dir.join("words.txt.gz"), encoder.finish().unwrap()).unwrap();
+
+        let path = api.asset("dictionary", "words.txt").unwrap();
+        assert!(path.starts_with(api.get_plugin_cache_dir("dictionary").unwrap()));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "aardvark\nabacus\n");
+        assert_eq!(api.asset("dictionary", "words.txt").unwrap(), path);
+        assert!(api.asset("dictionary", "../manifest.json").is_err());
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+
+    /// Check that a validated name stays a single path component
+    fn is_single_component(name: &str) -> bool {
+        let mut components = std::path::Path::new(name).components();
+        matches!(components.next(), Some(std::path::Component::Normal(_))) && components.next().is_none()
+    }
+
+    proptest! {
+        #[test]
+        fn prop_valid_plugin_ids_are_accepted(plugin_id in "[A-Za-z0-9_-]{1,64}") {
+            prop_assert!(VoltPluginAPI::validate_plugin_id(&plugin_id).is_ok());
+        }
+
+        #[test]
+        fn prop_valid_names_are_single_components(name in ".{0,300}") {
+            if VoltPluginAPI::validate_plugin_id(&name).is_ok() {
+                prop_assert!(is_single_component(&name));
+            }
+            if VoltPluginAPI::validate_cache_key(&name).is_ok() {
+                prop_assert!(is_single_component(&name));
+            }
+            if VoltPluginAPI::validate_config_name(&name).is_ok() {
+                prop_assert!(is_single_component(&name));
+            }
+        }
+
+        #[test]
+        fn prop_path_separators_are_rejected(prefix in ".{0,16}", separator in "[/\\\\]", suffix in ".{0,16}") {
+            let name = format!("{}{}{}", prefix, separator, suffix);
+            prop_assert!(VoltPluginAPI::validate_plugin_id(&name).is_err());
+            prop_assert!(VoltPluginAPI::validate_cache_key(&name).is_err());
+            prop_assert!(VoltPluginAPI::validate_config_name(&name).is_err());
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn prop_config_round_trips_under_concurrent_access(
+            configs in prop::collection::vec(crate::fuzz::json_value(), 1..4)
+        ) {
+            let temp_dir = env::temp_dir().join(format!("volt_test_config_concurrent_{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&temp_dir);
+            let api = VoltPluginAPI::new(temp_dir.clone());
+
+            // Every load sees either no file or one complete save
+            let writers: Vec<_> = configs
+                .iter()
+                .cloned()
+                .map(|config| {
+                    let api = api.clone();
+                    std::thread::spawn(move || {
+                        for _ in 0..8 {
+                            api.save_config("test_plugin", "settings", &config).unwrap();
+                        }
+                    })
+                })
+                .collect();
+            let missing = serde_json::json!({});
+            for _ in 0..16 {
+                let loaded = api.load_config("test_plugin", "settings").unwrap();
+                prop_assert!(loaded == missing || configs.contains(&loaded));
+            }
+            for writer in writers {
+                writer.join().unwrap();
+            }
+
+            let loaded = api.load_config("test_plugin", "settings").unwrap();
+            prop_assert!(configs.contains(&loaded));
+            let leftovers = std::fs::read_dir(api.get_plugin_config_dir("test_plugin").unwrap())
+                .unwrap()
+                .filter(|entry| entry.as_ref().unwrap().file_name().to_string_lossy().ends_with(".partial"))
+                .count();
+            prop_assert_eq!(leftovers, 0);
+
+            let _ = std::fs::remove_dir_all(temp_dir);
+        }
+    }
 }
@@ -0,0 +1,310 @@
+/// Recording and replay of plugin API calls
+///
+/// What a plugin returns often depends on what the API hands it: saved
+/// configuration, cached data, downloaded files. To test such a plugin
+/// deterministically, run it once against the real API with
+/// `VoltPluginAPI::start_api_trace(ApiTraceMode::Record)`, save the calls
+/// it made with `ApiTrace::save`, and check the trace in next to the test.
+/// The test then starts the API in `ApiTraceMode::Replay`, and the calls
+/// are answered from the trace without touching the disk or the network.
+///
+/// ```json
+/// {
+///   "calls": [
+///     { "pluginId": "weather", "op": "loadConfig", "configName": "settings", "response": { "ok": { "city": "Lyon" } } },
+///     { "pluginId": "weather", "op": "download", "url": "https://example.com/lyon.json", "response": { "ok": "{\"temp\":21}" } },
+///     { "pluginId": "weather", "op": "readCache", "key": "units", "response": { "err": "Cache entry not found" } }
+///   ]
+/// }
+/// ```
+///
+/// Configuration reads and writes, cache reads and writes, key-value store
+/// reads and writes and downloads are traced. During replay, each call is
+/// answered by the first unused recorded call with the same plugin,
+/// operation and arguments, so repeated calls get their responses in the
+/// recorded order. A call missing from the trace fails rather than falling
+/// through to the real API. Values written by the plugin are not compared.
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::path::Path;
+
+/// An API call, with the arguments that identify it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum ApiCall {
+    /// `VoltPluginAPI::load_config`
+    #[serde(rename_all = "camelCase")]
+    LoadConfig { config_name: String },
+    /// `VoltPluginAPI::save_config`
+    #[serde(rename_all = "camelCase")]
+    SaveConfig { config_name: String },
+    /// `VoltPluginAPI::read_cache`
+    ReadCache { key: String },
+    /// `VoltPluginAPI::write_cache`
+    WriteCache { key: String },
+    /// `VoltPluginAPI::kv_get`
+    KvGet { key: String },
+    /// `VoltPluginAPI::kv_set`
+    KvSet { key: String },
+    /// `VoltPluginAPI::download`, answered with the downloaded file
+    Download { url: String },
+}
+
+impl fmt::Display for ApiCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiCall::LoadConfig { config_name } => write!(f, "load_config({})", config_name),
+            ApiCall::SaveConfig { config_name } => write!(f, "save_config({})", config_name),
+            ApiCall::ReadCache { key } => write!(f, "read_cache({})", key),
+            ApiCall::WriteCache { key } => write!(f, "write_cache({})", key),
+            ApiCall::KvGet { key } => write!(f, "kv_get({})", key),
+            ApiCall::KvSet { key } => write!(f, "kv_set({})", key),
+            ApiCall::Download { url } => write!(f, "download({})", url),
+        }
+    }
+}
+
+/// Outcome of a recorded call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TracedResponse {
+    /// The call succeeded with this value
+    Ok(serde_json::Value),
+    /// The call failed with this error
+    Err(String),
+}
+
+/// A call made by a plugin, with its response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TracedCall {
+    /// Plugin that made the call
+    pub plugin_id: String,
+    /// The call
+    #[serde(flatten)]
+    pub call: ApiCall,
+    /// What the API answered
+    pub response: TracedResponse,
+}
+
+/// A recorded sequence of API calls
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiTrace {
+    /// Calls in the order they were made
+    pub calls: Vec<TracedCall>,
+}
+
+impl ApiTrace {
+    /// Load a trace from a JSON file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read API trace {}: {}", path.display(), e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse API trace {}: {}", path.display(), e))
+    }
+
+    /// Save the trace to a JSON file
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize API trace: {}", e))?;
+        std::fs::write(path, content)
+            .map_err(|e| format!("Failed to write API trace {}: {}", path.display(), e))
+    }
+}
+
+/// What the API does with the calls of a trace session
+#[derive(Debug, Clone)]
+pub enum ApiTraceMode {
+    /// Run calls normally and record them
+    Record,
+    /// Answer calls from a trace instead of running them
+    Replay(ApiTrace),
+}
+
+/// A running trace session
+#[derive(Debug)]
+pub(crate) struct ApiTraceSession {
+    /// Calls recorded so far, or the trace being replayed
+    trace: ApiTrace,
+    /// Whether each call of the trace has been replayed, empty when recording
+    replayed: Option<Vec<bool>>,
+}
+
+impl ApiTraceSession {
+    /// Start a session
+    pub(crate) fn new(mode: ApiTraceMode) -> Self {
+        match mode {
+            ApiTraceMode::Record => Self {
+                trace: ApiTrace::default(),
+                replayed: None,
+            },
+            ApiTraceMode::Replay(trace) => Self {
+                replayed: Some(vec![false; trace.calls.len()]),
+                trace,
+            },
+        }
+    }
+
+    /// Answer a call from the trace
+    ///
+    /// # Returns
+    /// None when recording, so the call runs normally; otherwise the
+    /// recorded response, or an error if the trace has no such call left
+    pub(crate) fn replay(
+        &mut self,
+        plugin_id: &str,
+        call: &ApiCall,
+    ) -> Option<Result<serde_json::Value, String>> {
+        let replayed = self.replayed.as_mut()?;
+        let position = self.trace.calls.iter().enumerate().position(|(i, traced)| {
+            !replayed[i] && traced.plugin_id == plugin_id && traced.call == *call
+        });
+
+        Some(match position {
+            Some(i) => {
+                replayed[i] = true;
+                match &self.trace.calls[i].response {
+                    TracedResponse::Ok(value) => Ok(value.clone()),
+                    TracedResponse::Err(e) => Err(e.clone()),
+                }
+            }
+            None => Err(format!(
+                "Plugin '{}' made a call missing from the API trace: {}",
+                plugin_id, call
+            )),
+        })
+    }
+
+    /// Record a call and its response, unless replaying
+    pub(crate) fn record(&mut self, plugin_id: &str, call: ApiCall, response: TracedResponse) {
+        if self.replayed.is_none() {
+            self.trace.calls.push(TracedCall {
+                plugin_id: plugin_id.to_string(),
+                call,
+                response,
+            });
+        }
+    }
+
+    /// End the session
+    ///
+    /// # Returns
+    /// The recorded calls, or when replaying the calls that were never made
+    pub(crate) fn finish(self) -> ApiTrace {
+        match self.replayed {
+            None => self.trace,
+            Some(replayed) => ApiTrace {
+                calls: self
+                    .trace
+                    .calls
+                    .into_iter()
+                    .zip(replayed)
+                    .filter(|(_, replayed)| !replayed)
+                    .map(|(call, _)| call)
+                    .collect(),
+            },
+        }
+    }
+}
+
+/// Bytes as traced: a string when they are UTF-8, an array of bytes otherwise
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TracedBytes(pub Vec<u8>);
+
+impl Serialize for TracedBytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match std::str::from_utf8(&self.0) {
+            Ok(text) => serializer.serialize_str(text),
+            Err(_) => self.0.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TracedBytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Text(String),
+            Bytes(Vec<u8>),
+        }
+
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Text(text) => TracedBytes(text.into_bytes()),
+            Repr::Bytes(bytes) => TracedBytes(bytes),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::VoltPluginAPI;
+
+    #[test]
+    fn test_record_and_replay_api_calls() {
+        let temp_dir = std::env::temp_dir().join("volt_test_api_trace");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let api = VoltPluginAPI::new(temp_dir.clone());
+        api.save_config(
+            "weather",
+            "settings",
+            &serde_json::json!({ "city": "Lyon" }),
+        )
+        .unwrap();
+
+        api.start_api_trace(ApiTraceMode::Record);
+        assert_eq!(
+            api.load_config("weather", "settings").unwrap()["city"],
+            "Lyon"
+        );
+        assert!(api.read_cache("weather", "forecast").is_err());
+        api.write_cache("weather", "forecast", b"sunny").unwrap();
+        assert_eq!(api.read_cache("weather", "forecast").unwrap(), b"sunny");
+        api.kv_set("weather", "last", serde_json::json!("Lyon"))
+            .unwrap();
+        let trace = api.stop_api_trace().unwrap();
+        assert_eq!(trace.calls.len(), 5);
+        assert_eq!(trace.calls[3].response, TracedResponse::Ok("sunny".into()));
+
+        let path = temp_dir.join("trace.json");
+        trace.save(&path).unwrap();
+        let trace = ApiTrace::load(&path).unwrap();
+
+        // Replay answers from the trace, whatever is on disk now
+        api.clear_cache("weather").unwrap();
+        api.save_config(
+            "weather",
+            "settings",
+            &serde_json::json!({ "city": "Oslo" }),
+        )
+        .unwrap();
+        api.start_api_trace(ApiTraceMode::Replay(trace));
+        assert_eq!(
+            api.load_config("weather", "settings").unwrap()["city"],
+            "Lyon"
+        );
+        assert_eq!(
+            api.read_cache("weather", "forecast").unwrap_err(),
+            "Cache entry not found"
+        );
+        api.write_cache("weather", "forecast", b"rainy").unwrap();
+        assert_eq!(api.read_cache("weather", "forecast").unwrap(), b"sunny");
+        let missing = api.kv_get("weather", "last").unwrap_err();
+        assert!(missing.contains("kv_get(last)"), "{}", missing);
+
+        let unused = api.stop_api_trace().unwrap();
+        assert_eq!(unused.calls.len(), 1);
+        assert_eq!(
+            unused.calls[0].call,
+            ApiCall::KvSet {
+                key: "last".to_string()
+            }
+        );
+        // Replayed writes don't reach the disk either
+        assert!(api.read_cache("weather", "forecast").is_err());
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
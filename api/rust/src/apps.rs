@@ -0,0 +1,501 @@
+/// Application launching
+///
+/// Describes how to start an application found by an app-launcher plugin,
+/// including Windows specifics: Microsoft Store (UWP) apps activated by
+/// their AUMID, "run as administrator", and `.lnk` shortcut resolution.
+/// Launching requires the `ExecuteCommands` capability.
+use crate::result::{KeyHint, PluginResult, ResultAction};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Action ID of the "Run as administrator" key hint
+pub const RUN_AS_ADMIN_ACTION: &str = "run_as_admin";
+
+/// Metadata key holding the launch target of an app result
+const TARGET_META_KEY: &str = "app";
+
+/// Metadata key holding the targets of an app result's secondary actions
+const ACTIONS_META_KEY: &str = "appActions";
+
+/// Something that can be launched
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum AppTarget {
+    /// A classic executable
+    #[serde(rename_all = "camelCase")]
+    Executable {
+        /// Path of the executable
+        path: PathBuf,
+        /// Command-line arguments
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        args: Vec<String>,
+        /// Directory to start in
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        working_dir: Option<PathBuf>,
+    },
+    /// A Microsoft Store (UWP) app, Windows only
+    Store {
+        /// Application User Model ID, e.g. "Microsoft.WindowsCalculator_8wekyb3d8bbwe!App"
+        aumid: String,
+    },
+}
+
+impl AppTarget {
+    /// Create a target for an executable without arguments
+    pub fn executable(path: impl Into<PathBuf>) -> Self {
+        AppTarget::Executable {
+            path: path.into(),
+            args: Vec::new(),
+            working_dir: None,
+        }
+    }
+
+    /// Create a target for a Store app
+    pub fn store(aumid: impl Into<String>) -> Self {
+        AppTarget::Store { aumid: aumid.into() }
+    }
+
+    /// Check if this target can be started elevated
+    ///
+    /// Only executables on Windows can; Store apps never run elevated.
+    pub fn supports_elevation(&self) -> bool {
+        cfg!(target_os = "windows") && matches!(self, AppTarget::Executable { .. })
+    }
+
+    /// Build a search result launching this target
+    ///
+    /// The target is kept in the result's metadata, and a "Run as
+    /// administrator" key hint is added when elevation is supported.
+    pub fn to_result(&self, id: impl Into<String>, title: impl Into<Cow<'static, str>>) -> PluginResult {
+        let mut result = PluginResult::new(id, title).with_meta(
+            TARGET_META_KEY,
+            serde_json::to_value(self).unwrap_or_default(),
+        );
+
+        if self.supports_elevation() {
+            result = result.with_key_hint(KeyHint::new(
+                "Ctrl+Shift+Enter",
+                RUN_AS_ADMIN_ACTION,
+                "Run as administrator",
+            ));
+        }
+
+        result
+    }
+
+    /// Read the target stored by `to_result`
+    ///
+    /// # Returns
+    /// None if the result isn't an app result, Err if the target is malformed
+    pub fn from_result(result: &PluginResult) -> Option<Result<Self, String>> {
+        result.meta_as(TARGET_META_KEY)
+    }
+
+    /// Start the target
+    ///
+    /// # Arguments
+    /// * `elevated` - Run as administrator (Windows executables only)
+    pub fn launch(&self, elevated: bool) -> Result<(), String> {
+        if elevated && !self.supports_elevation() {
+            return Err("Running as administrator is not supported for this application".to_string());
+        }
+
+        let mut command = match self {
+            AppTarget::Executable { path, args, .. } if elevated => elevated_command(path, args),
+            AppTarget::Executable {
+                path,
+                args,
+                working_dir,
+            } => {
+                let mut command = Command::new(path);
+                command.args(args);
+                if let Some(dir) = working_dir {
+                    command.current_dir(dir);
+                }
+                command
+            }
+            AppTarget::Store { aumid } => {
+                if !cfg!(target_os = "windows") {
+                    return Err("Store apps can only be launched on Windows".to_string());
+                }
+                validate_aumid(aumid)?;
+
+                let mut command = Command::new("explorer.exe");
+                command.arg(format!("shell:AppsFolder\\{}", aumid));
+                command
+            }
+        };
+
+        command
+            .spawn()
+            .map(|_| ())
+            .map_err(|e| format!("Failed to launch application: {}", e))
+    }
+}
+
+/// Add a secondary action launching another target
+///
+/// Used for things like desktop actions ("New Private Window"); the
+/// target is kept in the result's metadata for `launch_result`.
+pub fn add_app_action(result: &mut PluginResult, action: ResultAction, target: &AppTarget) {
+    let mut targets = result
+        .remove_meta(ACTIONS_META_KEY)
+        .and_then(|value| match value {
+            serde_json::Value::Object(map) => Some(map),
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    targets.insert(action.id.clone(), serde_json::to_value(target).unwrap_or_default());
+    result.set_meta(ACTIONS_META_KEY, targets);
+    result.actions.push(action);
+}
+
+/// Launch the app stored in a result
+///
+/// Call this from `Plugin::execute` with the routed action.
+///
+/// # Arguments
+/// * `result` - Result built with `AppTarget::to_result`
+/// * `action` - Routed key hint or result action, None for the default action
+pub fn launch_result(result: &PluginResult, action: Option<&str>) -> Result<(), String> {
+    if let Some(action) = action.filter(|action| *action != RUN_AS_ADMIN_ACTION) {
+        let target = result
+            .meta(ACTIONS_META_KEY)
+            .and_then(|targets| targets.get(action))
+            .ok_or_else(|| format!("Unknown action '{}' on result '{}'", action, result.id))?;
+        let target: AppTarget = serde_json::from_value(target.clone())
+            .map_err(|e| format!("Invalid action target: {}", e))?;
+
+        return target.launch(false);
+    }
+
+    let target = AppTarget::from_result(result)
+        .ok_or_else(|| format!("Result '{}' is not an application", result.id))??;
+
+    target.launch(action == Some(RUN_AS_ADMIN_ACTION))
+}
+
+/// Check that an AUMID has the `PackageFamilyName!AppId` shape
+///
+/// The AUMID ends up in a shell command line, so anything unusual is refused.
+pub fn validate_aumid(aumid: &str) -> Result<(), String> {
+    let valid_part = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+    };
+
+    match aumid.split_once('!') {
+        Some((family, app)) if valid_part(family) && valid_part(app) => Ok(()),
+        _ => Err(format!("Invalid application user model ID: {}", aumid)),
+    }
+}
+
+/// Build a command starting an executable through UAC
+fn elevated_command(path: &Path, args: &[String]) -> Command {
+    let quote = |value: &str| format!("'{}'", value.replace('\'', "''"));
+
+    let mut script = format!(
+        "Start-Process -Verb RunAs -FilePath {}",
+        quote(&path.to_string_lossy())
+    );
+    if !args.is_empty() {
+        let args: Vec<String> = args.iter().map(|arg| quote(arg)).collect();
+        script.push_str(&format!(" -ArgumentList {}", args.join(",")));
+    }
+
+    let mut command = Command::new("powershell.exe");
+    command.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+    command
+}
+
+// ========== Shortcut Resolution ==========
+
+/// Size of the fixed Shell Link header
+const LINK_HEADER_SIZE: usize = 0x4C;
+
+/// Shell Link class identifier, as stored in the header
+const LINK_CLSID: [u8; 16] = [
+    0x01, 0x14, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+];
+
+const HAS_LINK_TARGET_ID_LIST: u32 = 1 << 0;
+const HAS_LINK_INFO: u32 = 1 << 1;
+const HAS_NAME: u32 = 1 << 2;
+const HAS_RELATIVE_PATH: u32 = 1 << 3;
+const HAS_WORKING_DIR: u32 = 1 << 4;
+const HAS_ARGUMENTS: u32 = 1 << 5;
+const HAS_ICON_LOCATION: u32 = 1 << 6;
+const IS_UNICODE: u32 = 1 << 7;
+
+/// LinkInfo flag set when the target is on a local volume
+const VOLUME_ID_AND_LOCAL_BASE_PATH: u32 = 1 << 0;
+
+/// Contents of a Windows `.lnk` shortcut
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShellLink {
+    /// Absolute path of the target, if it is a file
+    pub target: Option<PathBuf>,
+    /// Command-line arguments
+    pub arguments: Option<String>,
+    /// Directory to start in
+    pub working_dir: Option<PathBuf>,
+    /// Icon location, possibly with environment variables
+    pub icon_location: Option<String>,
+    /// Comment shown as the shortcut's tooltip
+    pub description: Option<String>,
+}
+
+impl ShellLink {
+    /// Read and parse a `.lnk` file
+    ///
+    /// Parsing is pure Rust, so shortcuts can be inspected on any platform.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("Failed to read shortcut: {}", e))?;
+        let mut link = Self::parse(&bytes)?;
+
+        // Relative targets are relative to the shortcut itself
+        let relative = link.target.as_ref().filter(|target| target.is_relative());
+        if let (Some(target), Some(dir)) = (relative, path.parent()) {
+            link.target = Some(dir.join(target));
+        }
+
+        Ok(link)
+    }
+
+    /// Parse the contents of a `.lnk` file
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        let invalid = || "Invalid shortcut file".to_string();
+
+        if read_u32(bytes, 0).ok_or_else(invalid)? as usize != LINK_HEADER_SIZE
+            || bytes.get(4..20) != Some(&LINK_CLSID[..])
+        {
+            return Err(invalid());
+        }
+        let flags = read_u32(bytes, 0x14).ok_or_else(invalid)?;
+        let mut offset = LINK_HEADER_SIZE;
+
+        if flags & HAS_LINK_TARGET_ID_LIST != 0 {
+            offset += 2 + read_u16(bytes, offset).ok_or_else(invalid)? as usize;
+        }
+
+        let mut link = ShellLink::default();
+
+        if flags & HAS_LINK_INFO != 0 {
+            let size = read_u32(bytes, offset).ok_or_else(invalid)? as usize;
+            let info = bytes.get(offset..offset + size).ok_or_else(invalid)?;
+            link.target = parse_link_info(info).map(PathBuf::from);
+            offset += size;
+        }
+
+        let unicode = flags & IS_UNICODE != 0;
+        let mut next_string = |flag: u32| -> Result<Option<String>, String> {
+            if flags & flag == 0 {
+                return Ok(None);
+            }
+            let (value, read) = read_counted_string(bytes, offset, unicode).ok_or_else(invalid)?;
+            offset += read;
+            Ok(Some(value))
+        };
+
+        link.description = next_string(HAS_NAME)?;
+        let relative_path = next_string(HAS_RELATIVE_PATH)?;
+        link.working_dir = next_string(HAS_WORKING_DIR)?.map(PathBuf::from);
+        link.arguments = next_string(HAS_ARGUMENTS)?;
+        link.icon_location = next_string(HAS_ICON_LOCATION)?;
+
+        if link.target.is_none() {
+            link.target = relative_path.map(PathBuf::from);
+        }
+
+        Ok(link)
+    }
+
+    /// Turn the shortcut into a launch target
+    ///
+    /// # Returns
+    /// None if the shortcut doesn't point to a file (e.g. shortcuts to
+    /// Store apps or control panel items)
+    pub fn into_target(self) -> Option<AppTarget> {
+        Some(AppTarget::Executable {
+            path: self.target?,
+            args: self
+                .arguments
+                .map(|arguments| split_arguments(&arguments))
+                .unwrap_or_default(),
+            working_dir: self.working_dir,
+        })
+    }
+}
+
+/// Extract the local target path from a LinkInfo structure
+fn parse_link_info(info: &[u8]) -> Option<String> {
+    let header_size = read_u32(info, 4)? as usize;
+    let flags = read_u32(info, 8)?;
+    if flags & VOLUME_ID_AND_LOCAL_BASE_PATH == 0 {
+        return None;
+    }
+
+    // Newer writers add Unicode offsets after the ANSI ones
+    if header_size >= 0x24 {
+        let base = read_utf16_z(info, read_u32(info, 0x1C)? as usize)?;
+        let suffix = read_utf16_z(info, read_u32(info, 0x20)? as usize).unwrap_or_default();
+        return Some(base + &suffix);
+    }
+
+    let base = read_ansi_z(info, read_u32(info, 0x10)? as usize)?;
+    let suffix = read_ansi_z(info, read_u32(info, 0x18)? as usize).unwrap_or_default();
+    Some(base + &suffix)
+}
+
+/// Split a command line into arguments, honoring double quotes
+pub(crate) fn split_arguments(command_line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_arg = false;
+
+    for c in command_line.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_arg = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_arg {
+                    args.push(std::mem::take(&mut current));
+                    has_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_arg = true;
+            }
+        }
+    }
+    if has_arg {
+        args.push(current);
+    }
+
+    args
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+/// Read a StringData entry, returning the string and the bytes consumed
+fn read_counted_string(bytes: &[u8], offset: usize, unicode: bool) -> Option<(String, usize)> {
+    let count = read_u16(bytes, offset)? as usize;
+    let start = offset + 2;
+
+    if unicode {
+        let units: Vec<u16> = (0..count)
+            .map(|i| read_u16(bytes, start + i * 2))
+            .collect::<Option<_>>()?;
+        Some((String::from_utf16_lossy(&units), 2 + count * 2))
+    } else {
+        let raw = bytes.get(start..start + count)?;
+        Some((String::from_utf8_lossy(raw).into_owned(), 2 + count))
+    }
+}
+
+fn read_ansi_z(bytes: &[u8], offset: usize) -> Option<String> {
+    let raw = bytes.get(offset..)?;
+    let end = raw.iter().position(|&b| b == 0)?;
+    Some(String::from_utf8_lossy(&raw[..end]).into_owned())
+}
+
+fn read_utf16_z(bytes: &[u8], offset: usize) -> Option<String> {
+    let mut units = Vec::new();
+    let mut position = offset;
+    loop {
+        match read_u16(bytes, position)? {
+            0 => break,
+            unit => units.push(unit),
+        }
+        position += 2;
+    }
+    Some(String::from_utf16_lossy(&units))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counted_utf16(value: &str) -> Vec<u8> {
+        let units: Vec<u16> = value.encode_utf16().collect();
+        let mut bytes = (units.len() as u16).to_le_bytes().to_vec();
+        bytes.extend(units.iter().flat_map(|unit| unit.to_le_bytes()));
+        bytes
+    }
+
+    /// Build a minimal shortcut with an ANSI LinkInfo and Unicode strings
+    fn shortcut(target: &str) -> Vec<u8> {
+        let mut bytes = vec![0u8; LINK_HEADER_SIZE];
+        bytes[0..4].copy_from_slice(&(LINK_HEADER_SIZE as u32).to_le_bytes());
+        bytes[4..20].copy_from_slice(&LINK_CLSID);
+        let flags = HAS_LINK_INFO | HAS_NAME | HAS_WORKING_DIR | HAS_ARGUMENTS | IS_UNICODE;
+        bytes[0x14..0x18].copy_from_slice(&flags.to_le_bytes());
+
+        let base_path_offset = 0x1C_u32;
+        let suffix_offset = base_path_offset + target.len() as u32 + 1;
+        let size = suffix_offset + 1;
+        for value in [size, 0x1C, VOLUME_ID_AND_LOCAL_BASE_PATH, 0, base_path_offset, 0, suffix_offset] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend(target.as_bytes());
+        bytes.extend([0, 0]);
+
+        bytes.extend(counted_utf16("Visual Studio Code"));
+        bytes.extend(counted_utf16("C:\\Users\\volt"));
+        bytes.extend(counted_utf16("--new-window \"C:\\My Projects\""));
+        bytes
+    }
+
+    #[test]
+    fn test_shortcut_resolution() {
+        let link = ShellLink::parse(&shortcut("C:\\Program Files\\Code\\Code.exe")).unwrap();
+        assert_eq!(link.description.as_deref(), Some("Visual Studio Code"));
+
+        assert_eq!(
+            link.into_target(),
+            Some(AppTarget::Executable {
+                path: PathBuf::from("C:\\Program Files\\Code\\Code.exe"),
+                args: vec!["--new-window".to_string(), "C:\\My Projects".to_string()],
+                working_dir: Some(PathBuf::from("C:\\Users\\volt")),
+            })
+        );
+
+        assert!(ShellLink::parse(b"not a shortcut").is_err());
+        assert!(ShellLink::parse(&shortcut("C:\\Code.exe")[..100]).is_err());
+    }
+
+    #[test]
+    fn test_app_results_round_trip() {
+        let target = AppTarget::store("Microsoft.WindowsCalculator_8wekyb3d8bbwe!App");
+        let result = target.to_result("calc", "Calculator");
+
+        assert_eq!(AppTarget::from_result(&result), Some(Ok(target)));
+        assert!(result.key_hints.is_empty());
+        assert!(AppTarget::from_result(&PluginResult::new("x", "x")).is_none());
+
+        let executable = AppTarget::executable("C:\\Windows\\regedit.exe").to_result("regedit", "Registry Editor");
+        assert_eq!(
+            executable.key_hints.iter().any(|hint| hint.action == RUN_AS_ADMIN_ACTION),
+            cfg!(target_os = "windows")
+        );
+
+        assert!(validate_aumid("Microsoft.WindowsCalculator_8wekyb3d8bbwe!App").is_ok());
+        assert!(validate_aumid("Calc!App & calc.exe").is_err());
+        assert!(validate_aumid("NoAppId").is_err());
+    }
+}
@@ -0,0 +1,171 @@
+/// Static assets bundled with plugin packages
+///
+/// Packages may ship an `assets/` directory next to `manifest.json` holding
+/// icon sets, datasets and templates. Plugins look files up through
+/// `VoltPluginAPI::asset`, which only resolves paths inside that directory.
+///
+/// Large datasets can be bundled gzip-compressed as `<name>.gz`. With the
+/// `compressed-assets` feature, asking for `<name>` decompresses the bundled
+/// file into the plugin's cache directory on first use.
+use std::path::{Path, PathBuf};
+
+/// Directory of a package holding its assets
+pub const ASSETS_DIR: &str = "assets";
+
+/// Extension of gzip-compressed assets
+pub const COMPRESSED_EXTENSION: &str = "gz";
+
+/// Longest accepted asset path, in bytes
+const MAX_ASSET_PATH_LEN: usize = 1024;
+
+/// Validate a relative asset path and convert it to a platform path
+///
+/// Both `/` and `\` separate components. Empty, `.` and `..` components,
+/// drive letters and absolute paths are rejected.
+pub fn validate_asset_path(relative_path: &str) -> Result<PathBuf, String> {
+    validate_package_path("Asset", relative_path)
+}
+
+/// Validate a path relative to a package directory
+///
+/// Shared by asset lookups and manifest entry points; `kind` prefixes the
+/// error messages.
+pub(crate) fn validate_package_path(kind: &str, relative_path: &str) -> Result<PathBuf, String> {
+    if relative_path.is_empty() {
+        return Err(format!("{} path cannot be empty", kind));
+    }
+
+    if relative_path.len() > MAX_ASSET_PATH_LEN {
+        return Err(format!("{} path too long (max {} characters)", kind, MAX_ASSET_PATH_LEN));
+    }
+
+    if relative_path.contains(':') || relative_path.contains('\0') {
+        return Err(format!("{} path cannot contain ':' or NUL characters", kind));
+    }
+
+    let mut path = PathBuf::new();
+    for component in relative_path.split(['/', '\\']) {
+        if component.is_empty() || component == "." || component == ".." {
+            return Err(format!("{} path must be relative and normalized: {}", kind, relative_path));
+        }
+        path.push(component);
+    }
+
+    Ok(path)
+}
+
+/// Resolve an asset inside an installed package
+///
+/// Symlinks are followed and the target must still be inside the package's
+/// assets directory.
+///
+/// # Arguments
+/// * `package_dir` - Directory of the installed package
+/// * `relative_path` - Path of the asset relative to `assets/`
+///
+/// # Returns
+/// Canonical path of the asset file
+pub fn resolve_asset(package_dir: &Path, relative_path: &str) -> Result<PathBuf, String> {
+    let relative = validate_asset_path(relative_path)?;
+    let assets_dir = package_dir.join(ASSETS_DIR);
+
+    let path = assets_dir.join(&relative);
+    if !path.is_file() {
+        return Err(format!("Asset not found: {}", relative_path));
+    }
+
+    let assets_dir = assets_dir
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve assets directory: {}", e))?;
+    let path = path
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve asset: {}", e))?;
+
+    if !path.starts_with(&assets_dir) {
+        return Err(format!("Asset escapes the package: {}", relative_path));
+    }
+
+    Ok(path)
+}
+
+/// Decompress a gzip-compressed asset into a cache directory
+///
+/// The decompressed copy is reused until the bundled file changes (e.g.,
+/// when the plugin is updated).
+///
+/// # Arguments
+/// * `compressed` - Resolved path of the `.gz` asset
+/// * `relative_path` - Path of the decompressed asset relative to `assets/`
+/// * `cache_dir` - The plugin's cache directory
+///
+/// # Returns
+/// Path of the decompressed file
+#[cfg(feature = "compressed-assets")]
+pub fn decompress_asset(compressed: &Path, relative_path: &str, cache_dir: &Path) -> Result<PathBuf, String> {
+    let target = cache_dir.join(ASSETS_DIR).join(validate_asset_path(relative_path)?);
+
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+    if let (Some(cached), Some(bundled)) = (modified(&target), modified(compressed))
+        && cached >= bundled
+    {
+        return Ok(target);
+    }
+
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create asset cache directory: {}", e))?;
+    }
+
+    let source =
+        std::fs::File::open(compressed).map_err(|e| format!("Failed to open compressed asset: {}", e))?;
+    let mut decoder = flate2::read::GzDecoder::new(std::io::BufReader::new(source));
+
+    // Decompress next to the target so a crash never leaves a truncated asset
+    let partial = target.with_extension("partial");
+    let mut output =
+        std::fs::File::create(&partial).map_err(|e| format!("Failed to create decompressed asset: {}", e))?;
+    std::io::copy(&mut decoder, &mut output).map_err(|e| {
+        let _ = std::fs::remove_file(&partial);
+        format!("Failed to decompress asset: {}", e)
+    })?;
+
+    std::fs::rename(&partial, &target).map_err(|e| format!("Failed to store decompressed asset: {}", e))?;
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asset_paths_are_validated() {
+        assert_eq!(validate_asset_path("icons/app.png").unwrap(), Path::new("icons").join("app.png"));
+        assert_eq!(validate_asset_path("icons\\app.png").unwrap(), Path::new("icons").join("app.png"));
+
+        for path in ["", "../manifest.json", "icons/../../secrets", "/etc/passwd", "C:\\Windows", "a//b", "./a"] {
+            assert!(validate_asset_path(path).is_err(), "{:?} should be rejected", path);
+        }
+    }
+
+    #[test]
+    fn test_assets_resolve_inside_package() {
+        let temp_dir = std::env::temp_dir().join("volt_test_assets");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(temp_dir.join("assets/data")).unwrap();
+        std::fs::write(temp_dir.join("assets/data/words.txt"), "volt").unwrap();
+        std::fs::write(temp_dir.join("manifest.json"), "{}").unwrap();
+
+        let path = resolve_asset(&temp_dir, "data/words.txt").unwrap();
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "volt");
+        assert!(resolve_asset(&temp_dir, "data").is_err());
+        assert!(resolve_asset(&temp_dir, "missing.txt").is_err());
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(temp_dir.join("manifest.json"), temp_dir.join("assets/link.json")).unwrap();
+            assert!(resolve_asset(&temp_dir, "link.json").unwrap_err().contains("escapes"));
+        }
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
@@ -0,0 +1,76 @@
+/// Audit trail of privileged plugin actions
+///
+/// Actions that can change the system beyond the plugin's own data, such as
+/// elevated commands, are appended to `audit.log` in the app data directory
+/// as JSON lines, whether they ran, failed or were declined. Unlike the
+/// recent actions feed, the trail is kept in privacy mode: it records what
+/// plugins did, not what the user searched for.
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+
+/// File holding the audit trail, in the app data directory
+pub const AUDIT_FILE: &str = "audit.log";
+
+/// One privileged action
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    /// When the action was requested, in milliseconds since the Unix epoch
+    pub at_ms: u64,
+    /// Plugin that requested the action
+    pub plugin_id: String,
+    /// Kind of action, e.g. "execute_elevated"
+    pub action: String,
+    /// What was acted on, e.g. the command line
+    pub detail: String,
+    /// How the request ended
+    pub outcome: AuditOutcome,
+}
+
+/// How a privileged action ended
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum AuditOutcome {
+    /// The user declined the action
+    Declined,
+    /// The action ran successfully
+    Succeeded,
+    /// The action ran but failed
+    Failed {
+        /// Why it failed
+        error: String,
+    },
+}
+
+/// Append an entry to an audit trail
+pub fn append(path: &Path, entry: &AuditEntry) -> Result<(), String> {
+    let mut line = serde_json::to_string(entry)
+        .map_err(|e| format!("Failed to serialize audit entry: {}", e))?;
+    line.push('\n');
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create audit directory: {}", e))?;
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| format!("Failed to write audit trail: {}", e))
+}
+
+/// Read an audit trail, oldest first
+///
+/// Lines that don't parse, e.g. one cut short by a crash, are skipped.
+pub fn read(path: &Path) -> Result<Vec<AuditEntry>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("Failed to read audit trail: {}", e)),
+    }
+}
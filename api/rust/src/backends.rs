@@ -0,0 +1,271 @@
+/// Platform backends
+///
+/// Clipboard, notifications, windows, text input, the selection and the
+/// share sheet are platform-facing: the desktop host implements them on
+/// top of the OS APIs.
+/// A `PlatformBackend` bundles one implementation of each, and
+/// `VoltPluginAPI::install_backend` installs them all at once, declaring
+/// available exactly the `HostFeature`s the backend provides.
+///
+/// Volt doesn't always run on a desktop. A remote plugin host on a server,
+/// CI or tests have no clipboard or windows, so the host picks a backend at
+/// startup with `select`, which falls back to `NoopBackend` when no
+/// candidate is available:
+///
+/// ```ignore
+/// let backend = backends::select(vec![Arc::new(DesktopBackend::new())]);
+/// api.install_backend(backend);
+/// ```
+use crate::input::{ClipboardBackend, TextInsertion};
+use crate::logging;
+use crate::notifications::Notification;
+use crate::windows::{WindowInfo, WindowManager};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Environment variable forcing headless mode when set to `1` or `true`
+pub const HEADLESS_ENV: &str = "VOLT_HEADLESS";
+
+/// Desktop notifications, implemented by the host
+pub trait NotificationBackend: Send + Sync {
+    /// Display a notification
+    fn show(&self, notification: &Notification) -> Result<(), String>;
+}
+
+/// Text input synthesis, implemented by the host
+pub trait InputBackend: Send + Sync {
+    /// Type text into the focused application
+    fn insert_text(&self, insertion: &TextInsertion) -> Result<(), String>;
+}
+
+/// Selected text of the focused application, implemented by the host
+pub trait SelectionBackend: Send + Sync {
+    /// Read the selected text, None if nothing is selected
+    fn read_selection(&self) -> Option<String>;
+}
+
+/// Share sheet of the platform, implemented by macOS and Windows hosts
+pub trait ShareBackend: Send + Sync {
+    /// Offer a file to the apps and services the user can share it with
+    fn share(&self, path: &Path) -> Result<(), String>;
+}
+
+/// Implementation of every platform-facing subsystem
+///
+/// Subsystems a backend doesn't provide return None and are declared
+/// unavailable when the backend is installed.
+pub trait PlatformBackend: Send + Sync {
+    /// Name of the backend, for logs and diagnostics
+    fn name(&self) -> &str;
+
+    /// Check if the backend can run in the current environment
+    ///
+    /// `select` skips unavailable backends, e.g. a desktop backend without a
+    /// display.
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    /// The system clipboard
+    fn clipboard(&self) -> Option<Arc<dyn ClipboardBackend>> {
+        None
+    }
+
+    /// Desktop notifications
+    fn notifications(&self) -> Option<Arc<dyn NotificationBackend>> {
+        None
+    }
+
+    /// Application windows
+    fn window_manager(&self) -> Option<Arc<dyn WindowManager>> {
+        None
+    }
+
+    /// Text input synthesis
+    fn input(&self) -> Option<Arc<dyn InputBackend>> {
+        None
+    }
+
+    /// Selected text of the focused application
+    fn selection(&self) -> Option<Arc<dyn SelectionBackend>> {
+        None
+    }
+
+    /// The share sheet
+    fn share_sheet(&self) -> Option<Arc<dyn ShareBackend>> {
+        None
+    }
+}
+
+/// Backend for environments without a desktop
+///
+/// Behaves sensibly rather than failing:
+/// - the clipboard is kept in memory, per backend
+/// - notifications are logged and dropped
+/// - there are no windows and nothing is ever selected
+/// - text input isn't provided, so plugins fall back to the clipboard
+/// - there is no share sheet; exports go to files
+#[derive(Clone, Default)]
+pub struct NoopBackend {
+    clipboard: Arc<MemoryClipboard>,
+}
+
+impl NoopBackend {
+    /// Create a backend with an empty clipboard
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl PlatformBackend for NoopBackend {
+    fn name(&self) -> &str {
+        "noop"
+    }
+
+    fn clipboard(&self) -> Option<Arc<dyn ClipboardBackend>> {
+        Some(self.clipboard.clone())
+    }
+
+    fn notifications(&self) -> Option<Arc<dyn NotificationBackend>> {
+        Some(Arc::new(self.clone()))
+    }
+
+    fn window_manager(&self) -> Option<Arc<dyn WindowManager>> {
+        Some(Arc::new(self.clone()))
+    }
+
+    fn selection(&self) -> Option<Arc<dyn SelectionBackend>> {
+        Some(Arc::new(self.clone()))
+    }
+}
+
+/// Clipboard of `NoopBackend`
+#[derive(Default)]
+struct MemoryClipboard(Mutex<String>);
+
+impl ClipboardBackend for MemoryClipboard {
+    fn read_text(&self) -> Result<String, String> {
+        Ok(self.0.lock().unwrap_or_else(|p| p.into_inner()).clone())
+    }
+
+    fn write_text(&self, text: &str) -> Result<(), String> {
+        *self.0.lock().unwrap_or_else(|p| p.into_inner()) = text.to_string();
+        Ok(())
+    }
+}
+
+impl NotificationBackend for NoopBackend {
+    fn show(&self, notification: &Notification) -> Result<(), String> {
+        logging::info(
+            &notification.plugin_id,
+            &format!("Notification: {}", notification.title),
+        );
+        Ok(())
+    }
+}
+
+impl WindowManager for NoopBackend {
+    fn list_windows(&self) -> Result<Vec<WindowInfo>, String> {
+        Ok(Vec::new())
+    }
+
+    fn focus(&self, window_id: &str) -> Result<(), String> {
+        Err(format!("Unknown window '{}'", window_id))
+    }
+
+    fn close(&self, window_id: &str) -> Result<(), String> {
+        Err(format!("Unknown window '{}'", window_id))
+    }
+}
+
+impl SelectionBackend for NoopBackend {
+    fn read_selection(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Check if Volt runs without a desktop
+///
+/// True when `VOLT_HEADLESS` is set to `1` or `true`, or on Linux when
+/// neither an X11 nor a Wayland display is set.
+pub fn is_headless() -> bool {
+    if let Ok(value) = std::env::var(HEADLESS_ENV) {
+        return value == "1" || value.eq_ignore_ascii_case("true");
+    }
+
+    cfg!(target_os = "linux")
+        && std::env::var_os("DISPLAY").is_none()
+        && std::env::var_os("WAYLAND_DISPLAY").is_none()
+}
+
+/// Pick the backend to install
+///
+/// # Arguments
+/// * `candidates` - Backends in order of preference
+///
+/// # Returns
+/// The first available candidate, or a `NoopBackend` if none is
+pub fn select(candidates: Vec<Arc<dyn PlatformBackend>>) -> Arc<dyn PlatformBackend> {
+    for candidate in candidates {
+        if candidate.is_available() {
+            return candidate;
+        }
+        logging::info(
+            "backends",
+            &format!("Platform backend '{}' is not available", candidate.name()),
+        );
+    }
+    Arc::new(NoopBackend::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::VoltPluginAPI;
+    use crate::features::HostFeature;
+
+    struct DesktopBackend;
+
+    impl PlatformBackend for DesktopBackend {
+        fn name(&self) -> &str {
+            "desktop"
+        }
+
+        fn is_available(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn test_noop_backend_runs_headless() {
+        let backend = select(vec![Arc::new(DesktopBackend)]);
+        assert_eq!(backend.name(), "noop");
+
+        let temp_dir = std::env::temp_dir().join("volt_test_backends");
+        let api = VoltPluginAPI::new(temp_dir.clone());
+        api.install_backend(backend);
+
+        let features = api.features();
+        assert!(features.contains(HostFeature::Clipboard));
+        assert!(features.contains(HostFeature::Notifications));
+        assert!(!features.contains(HostFeature::InputSynthesis));
+
+        api.write_clipboard("copied").unwrap();
+        assert_eq!(api.read_clipboard().unwrap(), "copied");
+        api.notify("timer", Notification::new("done", "Time's up"))
+            .unwrap();
+        assert!(
+            api.window_manager()
+                .unwrap()
+                .list_windows()
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            api.insert_text("snippets", &TextInsertion::new("hi"))
+                .is_err()
+        );
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
@@ -0,0 +1,184 @@
+//! `volt-plugin` command line tool
+//!
+//! Usage:
+//!   volt-plugin test <plugin> <fixture.yaml> [--junit <report.xml>]
+//!   volt-plugin replay <trace.json> <plugin>... [--timeout <ms>] [--realtime] [--json <report.json>]
+//!   volt-plugin validate <manifest.json>...
+//!
+//! `<plugin>` is a native library, a `.wasm` module, a plugin executable, or
+//! a `ws://`/`wss://` remote plugin host URL.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+use volt_plugin_api::manifest::{validate_file, Severity};
+use volt_plugin_api::replay::{replay, QueryTrace, ReplayOptions};
+use volt_plugin_api::testing::{run_fixture, Fixture, PluginSource};
+
+const USAGE: &str = "Usage: volt-plugin test <plugin> <fixture.yaml> [--junit <report.xml>]
+       volt-plugin replay <trace.json> <plugin>... [--timeout <ms>] [--realtime] [--json <report.json>]
+       volt-plugin validate <manifest.json>...";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.first().map(String::as_str) {
+        Some("test") => match run_test(&args[1..]) {
+            Ok(true) => ExitCode::SUCCESS,
+            Ok(false) => ExitCode::FAILURE,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                ExitCode::FAILURE
+            }
+        },
+        Some("replay") => match run_replay(&args[1..]) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                ExitCode::FAILURE
+            }
+        },
+        Some("validate") if args.len() > 1 => {
+            if run_validate(&args[1..]) {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Run a fixture, returning whether every case passed
+fn run_test(args: &[String]) -> Result<bool, String> {
+    let mut positional = Vec::new();
+    let mut junit_path = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--junit" => {
+                let path = iter.next().ok_or_else(|| "--junit requires a path".to_string())?;
+                junit_path = Some(PathBuf::from(path));
+            }
+            _ => positional.push(arg.as_str()),
+        }
+    }
+
+    let [source, fixture_path] = positional[..] else {
+        return Err(USAGE.to_string());
+    };
+
+    let fixture = Fixture::from_file(&PathBuf::from(fixture_path))?;
+    let runtime = runtime()?;
+
+    let report = runtime.block_on(async {
+        let plugin = PluginSource::parse(source)
+            .load(fixture.plugin.as_deref())
+            .await?;
+        Ok::<_, String>(run_fixture(plugin.as_ref(), &fixture).await)
+    })?;
+
+    println!("\nrunning {} tests", report.cases.len());
+    for case in &report.cases {
+        let status = if case.passed() { "ok" } else { "FAILED" };
+        println!("test {} ... {}", case.name, status);
+        for failure in &case.failures {
+            println!("    {}", failure);
+        }
+    }
+
+    let failed = report.failed();
+    println!(
+        "\ntest result: {}. {} passed; {} failed",
+        if failed == 0 { "ok" } else { "FAILED" },
+        report.cases.len() - failed,
+        failed
+    );
+
+    if let Some(path) = junit_path {
+        std::fs::write(&path, report.to_junit_xml())
+            .map_err(|e| format!("Failed to write JUnit report: {}", e))?;
+    }
+
+    Ok(failed == 0)
+}
+
+/// Replay a query trace against plugins and print per-plugin measurements
+fn run_replay(args: &[String]) -> Result<(), String> {
+    let mut positional = Vec::new();
+    let mut options = ReplayOptions::default();
+    let mut json_path = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--timeout" => {
+                let ms = iter.next().ok_or_else(|| "--timeout requires milliseconds".to_string())?;
+                let ms = ms.parse().map_err(|_| format!("Invalid timeout: {}", ms))?;
+                options.timeout = Duration::from_millis(ms);
+            }
+            "--realtime" => options.realtime = true,
+            "--json" => {
+                let path = iter.next().ok_or_else(|| "--json requires a path".to_string())?;
+                json_path = Some(PathBuf::from(path));
+            }
+            _ => positional.push(arg.as_str()),
+        }
+    }
+
+    let [trace_path, sources @ ..] = &positional[..] else {
+        return Err(USAGE.to_string());
+    };
+    if sources.is_empty() {
+        return Err(USAGE.to_string());
+    }
+
+    let trace = QueryTrace::from_file(&PathBuf::from(trace_path))?;
+    let report = runtime()?.block_on(async {
+        let mut plugins = Vec::with_capacity(sources.len());
+        for source in sources {
+            plugins.push(PluginSource::parse(source).load(None).await?);
+        }
+        Ok::<_, String>(replay(&plugins, &trace, &options).await)
+    })?;
+
+    print!("{}", report);
+
+    if let Some(path) = json_path {
+        let json = serde_json::to_string_pretty(&report)
+            .map_err(|e| format!("Failed to serialize replay report: {}", e))?;
+        std::fs::write(&path, json).map_err(|e| format!("Failed to write replay report: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Validate manifests and print their diagnostics, returning whether all are valid
+fn run_validate(paths: &[String]) -> bool {
+    let mut valid = true;
+
+    for path in paths {
+        let validation = validate_file(&PathBuf::from(path));
+        for diagnostic in &validation.diagnostics {
+            let severity = match diagnostic.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            };
+            println!("{}: {}: {}", path, severity, diagnostic);
+        }
+        valid &= validation.is_valid();
+    }
+
+    valid
+}
+
+fn runtime() -> Result<tokio::runtime::Runtime, String> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("Failed to start runtime: {}", e))
+}
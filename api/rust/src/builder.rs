@@ -0,0 +1,238 @@
+/// Configurable construction of the plugin API
+///
+/// `VoltPluginAPI::new` wires the default components: manifest-declared
+/// capabilities, the system clock, the default HTTP client and the
+/// directories below the application data directory. Hosts needing others,
+/// and tests pinning time, assemble the API with a builder instead:
+///
+/// ```ignore
+/// let clock = ManualClock::new(1_700_000_000_000);
+/// let api = VoltPluginAPI::builder()
+///     .with_app_data_dir(app_data_dir)
+///     .with_volt_version(APP_VERSION)
+///     .with_log_sink(Arc::new(|diagnostic: &Diagnostic| host_log(diagnostic)))
+///     .with_capability_broker(Arc::new(AdminPolicy::load()?))
+///     .with_clock(Arc::new(clock.clone()))
+///     .build()?;
+/// ```
+///
+/// The builder is `Send + Sync`, so it can be prepared on one thread and
+/// built on another. Components are fixed once the API is built, and belong
+/// to that instance only: several APIs built in one process don't share
+/// sinks, tokenizers or stores.
+use crate::api::{CapabilityBroker, VoltPluginAPI};
+use crate::clock::Clock;
+use crate::logging::{Diagnostic, Diagnostics, DiagnosticsSink};
+use crate::matching::Tokenizer;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Where the API keeps its files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryLayout {
+    /// Application data directory, holding the API's own stores
+    pub app_data_dir: PathBuf,
+    /// Cache directory for plugins
+    pub cache_dir: PathBuf,
+    /// Configuration directory
+    pub config_dir: PathBuf,
+}
+
+impl DirectoryLayout {
+    /// Keep everything below the application data directory
+    pub fn new(app_data_dir: PathBuf) -> Self {
+        Self {
+            cache_dir: app_data_dir.join("cache"),
+            config_dir: app_data_dir.join("config"),
+            app_data_dir,
+        }
+    }
+}
+
+/// Builder of `VoltPluginAPI` instances, see `VoltPluginAPI::builder`
+#[derive(Clone, Default)]
+pub struct VoltPluginAPIBuilder {
+    layout: Option<DirectoryLayout>,
+    log_sinks: Vec<Arc<dyn DiagnosticsSink>>,
+    quiet_logs: Option<bool>,
+    pub(crate) capability_broker: Option<Arc<dyn CapabilityBroker>>,
+    pub(crate) clock: Option<Arc<dyn Clock>>,
+    pub(crate) diagnostics: Diagnostics,
+    pub(crate) tokenizer: Option<Arc<Tokenizer>>,
+    pub(crate) volt_version: Option<String>,
+    #[cfg(feature = "download")]
+    pub(crate) http_client: crate::download::HttpClientConfig,
+}
+
+impl VoltPluginAPIBuilder {
+    /// Keep everything below an application data directory
+    pub fn with_app_data_dir(mut self, app_data_dir: PathBuf) -> Self {
+        self.layout = Some(DirectoryLayout::new(app_data_dir));
+        self
+    }
+
+    /// Place the API's directories individually
+    pub fn with_layout(mut self, layout: DirectoryLayout) -> Self {
+        self.layout = Some(layout);
+        self
+    }
+
+    /// Send diagnostics to a sink, in addition to the other sinks added
+    ///
+    /// The sinks receive the diagnostics of this instance only, see
+    /// `VoltPluginAPI::diagnostics`, which then ignore the process-wide
+    /// `logging::set_sink` and `logging::set_quiet`.
+    pub fn with_log_sink(mut self, sink: Arc<dyn DiagnosticsSink>) -> Self {
+        self.log_sinks.push(sink);
+        self
+    }
+
+    /// Suppress or restore this instance's console output
+    ///
+    /// Like `with_log_sink`, detaches the diagnostics from the process-wide
+    /// settings.
+    pub fn with_quiet_logs(mut self, quiet: bool) -> Self {
+        self.quiet_logs = Some(quiet);
+        self
+    }
+
+    /// Report a version of Volt other than this crate's
+    pub fn with_volt_version(mut self, version: impl Into<String>) -> Self {
+        self.volt_version = Some(version.into());
+        self
+    }
+
+    /// Decide plugin capabilities with a broker instead of the manifests
+    pub fn with_capability_broker(mut self, broker: Arc<dyn CapabilityBroker>) -> Self {
+        self.capability_broker = Some(broker);
+        self
+    }
+
+    /// Match text with a tokenizer instead of the one installed with
+    /// `matching::set_tokenizer`
+    pub fn with_tokenizer(mut self, tokenizer: Tokenizer) -> Self {
+        self.tokenizer = Some(Arc::new(tokenizer));
+        self
+    }
+
+    /// Read timestamps from a clock instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Configure the HTTP client shared by all plugins
+    #[cfg(feature = "download")]
+    pub fn with_http_client(mut self, config: crate::download::HttpClientConfig) -> Self {
+        self.http_client = config;
+        self
+    }
+
+    /// Create the configured API
+    ///
+    /// # Returns
+    /// The API, or an error if no directory was given
+    pub fn build(mut self) -> Result<VoltPluginAPI, String> {
+        let layout = self
+            .layout
+            .take()
+            .ok_or_else(|| "No application data directory given".to_string())?;
+        for dir in [&layout.app_data_dir, &layout.cache_dir, &layout.config_dir] {
+            if dir.as_os_str().is_empty() {
+                return Err("API directories cannot be empty".to_string());
+            }
+        }
+
+        self.diagnostics = match self.log_sinks.len() {
+            0 => Diagnostics::process(),
+            1 => Diagnostics::new(self.log_sinks[0].clone()),
+            _ => Diagnostics::new(Arc::new(FanOut(std::mem::take(&mut self.log_sinks)))),
+        };
+        if let Some(quiet) = self.quiet_logs {
+            self.diagnostics = std::mem::take(&mut self.diagnostics).with_quiet(quiet);
+        }
+        Ok(VoltPluginAPI::assemble(layout, self))
+    }
+}
+
+impl std::fmt::Debug for VoltPluginAPIBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VoltPluginAPIBuilder")
+            .field("layout", &self.layout)
+            .field("log_sinks", &self.log_sinks.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Sink handing diagnostics to several sinks
+struct FanOut(Vec<Arc<dyn DiagnosticsSink>>);
+
+impl DiagnosticsSink for FanOut {
+    fn emit(&self, diagnostic: &Diagnostic<'_>) {
+        for sink in &self.0 {
+            sink.emit(diagnostic);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::PluginCapability;
+    use crate::clock::ManualClock;
+    use crate::manifest::PluginInfo;
+    use std::time::Duration;
+
+    struct DenyAll;
+
+    impl CapabilityBroker for DenyAll {
+        fn check(&self, plugin: &PluginInfo, capability: PluginCapability) -> Result<(), String> {
+            Err(format!("{} may not use {:?}", plugin.id, capability))
+        }
+    }
+
+    #[test]
+    fn test_builder_overrides_components() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<VoltPluginAPIBuilder>();
+
+        assert!(VoltPluginAPI::builder().build().is_err());
+
+        let temp_dir = std::env::temp_dir().join("volt_test_api_builder");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let clock = ManualClock::new(1_000);
+        let api = VoltPluginAPI::builder()
+            .with_layout(DirectoryLayout {
+                cache_dir: temp_dir.join("elsewhere"),
+                ..DirectoryLayout::new(temp_dir.clone())
+            })
+            .with_capability_broker(Arc::new(DenyAll))
+            .with_clock(Arc::new(clock.clone()))
+            .build()
+            .unwrap();
+
+        clock.advance(Duration::from_secs(1));
+        api.log("test_plugin", crate::api::LogLevel::Info, "hello");
+        assert_eq!(
+            api.recent_logs("test_plugin", 1),
+            vec!["2000 INFO: hello".to_string()]
+        );
+
+        api.write_cache("test_plugin", "key", b"data").unwrap();
+        assert!(temp_dir.join("elsewhere").join("plugins").exists());
+
+        let package_dir = api.get_plugin_package_dir("test_plugin").unwrap();
+        std::fs::create_dir_all(&package_dir).unwrap();
+        std::fs::write(
+            package_dir.join("manifest.json"),
+            r#"{"id": "test_plugin", "name": "Test", "version": "1.0.0", "permissions": ["network"]}"#,
+        )
+        .unwrap();
+        let refused = api
+            .require_capability("test_plugin", PluginCapability::Network)
+            .unwrap_err();
+        assert!(refused.contains("may not use"), "{}", refused);
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
@@ -0,0 +1,14 @@
+/// Plugins shipped with the launcher
+///
+/// Built-in plugins implement `Plugin` like third-party ones and go through
+/// the same registry; the host registers the ones it wants at startup.
+pub mod color;
+pub mod dictionary;
+#[cfg(feature = "download")]
+pub mod finance;
+#[cfg(feature = "macros")]
+pub mod macros;
+pub mod snippets;
+pub mod system;
+pub mod timer;
+pub mod windows;
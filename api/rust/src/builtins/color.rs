@@ -0,0 +1,451 @@
+/// Color picker and converter
+///
+/// Typing a color as `#ff8800`, `#f80`, `rgb(255, 136, 0)` or
+/// `hsl(32, 100%, 50%)` shows it with a swatch and its other notations.
+/// Executing the result copies the hex notation; the action menu copies the
+/// RGB or HSL one. `color` alone lists the colors copied last, kept in the
+/// plugin's key-value store.
+///
+/// Swatches are small SVG files in the plugin's cache, used as the result's
+/// icon and as its preview.
+use crate::api::VoltPluginAPI;
+use crate::extensions::{Preview, Previewer};
+use crate::outcome::{ExecuteOutcome, Toast};
+use crate::plugin::{Plugin, QueryContext};
+use crate::result::{PluginResult, ResultAction};
+use async_trait::async_trait;
+
+/// Identifier of the color plugin
+pub const PLUGIN_ID: &str = "color";
+
+/// Keyword listing recent colors, or prefixing a color
+pub const KEYWORD: &str = "color";
+
+/// Key-value store key holding the recent colors, as hex notations
+pub const RECENT_KEY: &str = "recent";
+
+/// Action copying the hex notation
+pub const ACTION_COPY_HEX: &str = "copy-hex";
+
+/// Action copying the RGB notation
+pub const ACTION_COPY_RGB: &str = "copy-rgb";
+
+/// Action copying the HSL notation
+pub const ACTION_COPY_HSL: &str = "copy-hsl";
+
+/// Most recent colors kept
+const MAX_RECENT: usize = 10;
+
+/// Width and height of swatches, in pixels
+const SWATCH_SIZE: u32 = 128;
+
+/// An opaque sRGB color
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl Color {
+    /// Create a color from its RGB channels
+    pub fn new(red: u8, green: u8, blue: u8) -> Self {
+        Self { red, green, blue }
+    }
+
+    /// Parse a color in hex, `rgb()` or `hsl()` notation, ignoring case
+    ///
+    /// Channels of `rgb()` and the saturation and lightness of `hsl()` may be
+    /// separated by commas or spaces.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().to_ascii_lowercase();
+
+        if let Some(hex) = text.strip_prefix('#') {
+            return Self::parse_hex(hex);
+        }
+        if let Some(channels) = function_arguments(&text, "rgb") {
+            let [red, green, blue] = channels;
+            return Some(Self::new(channel(red)?, channel(green)?, channel(blue)?));
+        }
+        if let Some([hue, saturation, lightness]) = function_arguments(&text, "hsl") {
+            let hue: f64 = hue.strip_suffix("deg").unwrap_or(hue).parse().ok()?;
+            let saturation = percentage(saturation)?;
+            let lightness = percentage(lightness)?;
+            return hue
+                .is_finite()
+                .then(|| Self::from_hsl(hue, saturation, lightness));
+        }
+        None
+    }
+
+    fn parse_hex(hex: &str) -> Option<Self> {
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let digit = |index: usize| u8::from_str_radix(&hex[index..=index], 16).ok();
+        match hex.len() {
+            3 => Some(Self::new(digit(0)? * 17, digit(1)? * 17, digit(2)? * 17)),
+            6 => Some(Self::new(
+                u8::from_str_radix(&hex[0..2], 16).ok()?,
+                u8::from_str_radix(&hex[2..4], 16).ok()?,
+                u8::from_str_radix(&hex[4..6], 16).ok()?,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Create a color from its hue in degrees, and saturation and lightness from 0 to 1
+    pub fn from_hsl(hue: f64, saturation: f64, lightness: f64) -> Self {
+        let hue = hue.rem_euclid(360.0) / 60.0;
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        let second = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+        let (red, green, blue) = match hue as u32 {
+            0 => (chroma, second, 0.0),
+            1 => (second, chroma, 0.0),
+            2 => (0.0, chroma, second),
+            3 => (0.0, second, chroma),
+            4 => (second, 0.0, chroma),
+            _ => (chroma, 0.0, second),
+        };
+        let offset = lightness - chroma / 2.0;
+        let scale = |value: f64| ((value + offset) * 255.0).round().clamp(0.0, 255.0) as u8;
+        Self::new(scale(red), scale(green), scale(blue))
+    }
+
+    /// Hue in degrees, and saturation and lightness from 0 to 1
+    pub fn to_hsl(&self) -> (f64, f64, f64) {
+        let [red, green, blue] = [self.red, self.green, self.blue].map(|c| c as f64 / 255.0);
+        let max = red.max(green).max(blue);
+        let min = red.min(green).min(blue);
+        let delta = max - min;
+        let lightness = (max + min) / 2.0;
+        if delta == 0.0 {
+            return (0.0, 0.0, lightness);
+        }
+
+        let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+        let hue = if max == red {
+            ((green - blue) / delta).rem_euclid(6.0)
+        } else if max == green {
+            (blue - red) / delta + 2.0
+        } else {
+            (red - green) / delta + 4.0
+        };
+        (hue * 60.0, saturation, lightness)
+    }
+
+    /// Hex notation, e.g. `#ff8800`
+    pub fn hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+
+    /// RGB notation, e.g. `rgb(255, 136, 0)`
+    pub fn rgb(&self) -> String {
+        format!("rgb({}, {}, {})", self.red, self.green, self.blue)
+    }
+
+    /// HSL notation, e.g. `hsl(32, 100%, 50%)`
+    pub fn hsl(&self) -> String {
+        let (hue, saturation, lightness) = self.to_hsl();
+        format!(
+            "hsl({}, {}%, {}%)",
+            hue.round() as u32 % 360,
+            (saturation * 100.0).round(),
+            (lightness * 100.0).round()
+        )
+    }
+}
+
+/// Split `name(a, b, c)` or `name(a b c)` into its three arguments
+fn function_arguments<'a>(text: &'a str, name: &str) -> Option<[&'a str; 3]> {
+    let arguments = text
+        .strip_prefix(name)?
+        .trim_start()
+        .strip_prefix('(')?
+        .strip_suffix(')')?;
+    let mut arguments = arguments
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|argument| !argument.is_empty());
+    let parsed = [arguments.next()?, arguments.next()?, arguments.next()?];
+    arguments.next().is_none().then_some(parsed)
+}
+
+/// Parse an RGB channel, from 0 to 255 or as a percentage
+fn channel(text: &str) -> Option<u8> {
+    let value = match text.strip_suffix('%') {
+        Some(percent) => percent.parse::<f64>().ok()? / 100.0 * 255.0,
+        None => text.parse::<f64>().ok()?,
+    };
+    (0.0..=255.0).contains(&value).then(|| value.round() as u8)
+}
+
+/// Parse a percentage, `%` optional, as a fraction from 0 to 1
+fn percentage(text: &str) -> Option<f64> {
+    let value: f64 = text.strip_suffix('%').unwrap_or(text).parse().ok()?;
+    (0.0..=100.0).contains(&value).then_some(value / 100.0)
+}
+
+/// Built-in plugin showing and converting colors
+pub struct ColorPlugin {
+    api: VoltPluginAPI,
+}
+
+impl ColorPlugin {
+    /// Create the plugin
+    pub fn new(api: VoltPluginAPI) -> Self {
+        Self { api }
+    }
+
+    /// Get the colors copied last, most recent first
+    pub fn recent_colors(&self) -> Result<Vec<Color>, String> {
+        let hexes: Vec<String> = match self.api.kv_get(PLUGIN_ID, RECENT_KEY)? {
+            Some(value) => serde_json::from_value(value)
+                .map_err(|e| format!("Failed to parse recent colors: {}", e))?,
+            None => Vec::new(),
+        };
+        Ok(hexes.iter().filter_map(|hex| Color::parse(hex)).collect())
+    }
+
+    /// Move a color to the front of the recent colors
+    fn remember(&self, color: Color) -> Result<(), String> {
+        let mut recent = self.recent_colors()?;
+        recent.retain(|other| *other != color);
+        recent.insert(0, color);
+        recent.truncate(MAX_RECENT);
+
+        let hexes: Vec<String> = recent.iter().map(Color::hex).collect();
+        self.api
+            .kv_set(PLUGIN_ID, RECENT_KEY, serde_json::json!(hexes))
+    }
+
+    /// Copy a notation of a color result to the clipboard
+    ///
+    /// Call this from the host with the action routed by `MergedResults`;
+    /// `execute` copies the hex notation.
+    ///
+    /// # Arguments
+    /// * `result` - A result returned by this plugin
+    /// * `action` - `ACTION_COPY_HEX`, `ACTION_COPY_RGB` or `ACTION_COPY_HSL`
+    pub fn perform(&self, result: &PluginResult, action: &str) -> Result<ExecuteOutcome, String> {
+        let color = result
+            .meta_str("color")
+            .and_then(Color::parse)
+            .ok_or("Missing color")?;
+
+        let text = match action {
+            ACTION_COPY_HEX => color.hex(),
+            ACTION_COPY_RGB => color.rgb(),
+            ACTION_COPY_HSL => color.hsl(),
+            _ => return Err(format!("Unknown color action '{}'", action)),
+        };
+        self.api.write_clipboard(&text)?;
+        self.remember(color)?;
+
+        let messages = self.api.messages();
+        Ok(ExecuteOutcome::close().with_message(Toast::success(
+            messages.format("color.copied", &[("value", &text)]),
+        )))
+    }
+
+    /// Get the swatch of a color, writing it to the cache on first use
+    pub fn swatch(&self, color: Color) -> Result<String, String> {
+        let key = format!("swatch-{}.svg", &color.hex()[1..]);
+        let path = self.api.get_plugin_cache_dir(PLUGIN_ID)?.join(&key);
+        if !path.exists() {
+            let svg = format!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\">\
+                 <rect width=\"{size}\" height=\"{size}\" rx=\"{radius}\" fill=\"{hex}\"/></svg>",
+                size = SWATCH_SIZE,
+                radius = SWATCH_SIZE / 8,
+                hex = color.hex()
+            );
+            self.api.write_cache(PLUGIN_ID, &key, svg.as_bytes())?;
+        }
+        Ok(path.to_string_lossy().into_owned())
+    }
+
+    /// Result showing a color
+    fn color_result(&self, color: Color, recent: bool) -> PluginResult {
+        let messages = self.api.messages();
+        let mut result = PluginResult::new(color.hex(), color.hex())
+            .with_meta("color", color.hex())
+            .with_action(ResultAction::new(
+                ACTION_COPY_RGB,
+                messages.format("color.copy_as", &[("value", &color.rgb())]),
+            ))
+            .with_action(ResultAction::new(
+                ACTION_COPY_HSL,
+                messages.format("color.copy_as", &[("value", &color.hsl())]),
+            ));
+        result.subtitle = Some(if recent {
+            format!("{} · {}", messages.text("color.recent"), color.rgb()).into()
+        } else {
+            format!("{} · {}", color.rgb(), color.hsl()).into()
+        });
+        result.icon = Some(
+            self.swatch(color)
+                .unwrap_or_else(|_| "🎨".to_string())
+                .into(),
+        );
+        result
+    }
+}
+
+#[async_trait]
+impl Previewer for ColorPlugin {
+    async fn preview(&self, result: &PluginResult) -> Result<Preview, String> {
+        let color = result
+            .meta_str("color")
+            .and_then(Color::parse)
+            .ok_or("Result has no preview")?;
+
+        Ok(Preview::Image {
+            path: self.swatch(color)?,
+        })
+    }
+}
+
+#[async_trait]
+impl Plugin for ColorPlugin {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn id(&self) -> &str {
+        PLUGIN_ID
+    }
+
+    fn name(&self) -> &str {
+        self.api.messages().text("color.name")
+    }
+
+    fn description(&self) -> &str {
+        self.api.messages().text("color.description")
+    }
+
+    fn can_handle(&self, context: &QueryContext) -> bool {
+        let query = context.query.trim();
+        query.eq_ignore_ascii_case(KEYWORD) || parse_query(query).is_some()
+    }
+
+    async fn match_query(&self, context: &QueryContext) -> Result<Vec<PluginResult>, String> {
+        let query = context.query.trim();
+        if let Some(color) = parse_query(query) {
+            let mut result = self.color_result(color, false);
+            result.score = 100;
+            return Ok(vec![result]);
+        }
+        if !query.eq_ignore_ascii_case(KEYWORD) {
+            return Ok(Vec::new());
+        }
+
+        Ok(self
+            .recent_colors()?
+            .into_iter()
+            .enumerate()
+            .map(|(index, color)| {
+                let mut result = self.color_result(color, true);
+                result.score = 80u32.saturating_sub(index as u32);
+                result
+            })
+            .collect())
+    }
+
+    async fn execute(&self, result: &PluginResult) -> Result<ExecuteOutcome, String> {
+        self.perform(result, ACTION_COPY_HEX)
+    }
+
+    fn as_previewer(&self) -> Option<&dyn Previewer> {
+        Some(self)
+    }
+}
+
+/// Parse a query holding a color, optionally after the keyword
+fn parse_query(query: &str) -> Option<Color> {
+    let color = match query.split_once(char::is_whitespace) {
+        Some((keyword, color)) if keyword.eq_ignore_ascii_case(KEYWORD) => color,
+        _ => query,
+    };
+    Color::parse(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_convert() {
+        let orange = Color::new(255, 136, 0);
+        for notation in [
+            "#ff8800",
+            "#F80",
+            "rgb(255, 136, 0)",
+            "RGB(255 136 0)",
+            "rgb(100%, 53.3%, 0%)",
+            "hsl(32, 100%, 50%)",
+            "hsl(32deg 100% 50%)",
+        ] {
+            assert_eq!(Color::parse(notation), Some(orange), "{}", notation);
+        }
+        assert_eq!(orange.hex(), "#ff8800");
+        assert_eq!(orange.rgb(), "rgb(255, 136, 0)");
+        assert_eq!(orange.hsl(), "hsl(32, 100%, 50%)");
+        assert_eq!(Color::new(128, 128, 128).hsl(), "hsl(0, 0%, 50%)");
+
+        for invalid in [
+            "ff8800",
+            "#ff88",
+            "#gg8800",
+            "rgb(256, 0, 0)",
+            "rgb(1, 2)",
+            "hsl(0, 120%, 50%)",
+        ] {
+            assert_eq!(Color::parse(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_copy_remembers_recent_colors() {
+        let temp_dir = std::env::temp_dir().join("volt_test_color");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let api = VoltPluginAPI::new(temp_dir.clone());
+        api.install_backend(std::sync::Arc::new(crate::backends::NoopBackend::new()));
+        let plugin = ColorPlugin::new(api.clone());
+
+        let context = QueryContext::new("rgb(10, 20, 30)");
+        assert!(plugin.can_handle(&context));
+        assert!(!plugin.can_handle(&QueryContext::new("colors")));
+        let results = plugin.match_query(&context).await.unwrap();
+        assert_eq!(results[0].title, "#0a141e");
+        assert_eq!(
+            results[0].subtitle.as_deref(),
+            Some("rgb(10, 20, 30) · hsl(210, 50%, 8%)")
+        );
+        let Preview::Image { path } = plugin.preview(&results[0]).await.unwrap() else {
+            panic!("expected a swatch");
+        };
+        assert!(
+            std::fs::read_to_string(path)
+                .unwrap()
+                .contains("fill=\"#0a141e\"")
+        );
+
+        plugin.perform(&results[0], ACTION_COPY_HSL).unwrap();
+        assert_eq!(api.read_clipboard().unwrap(), "hsl(210, 50%, 8%)");
+        let orange = plugin
+            .match_query(&QueryContext::new("color #f80"))
+            .await
+            .unwrap();
+        plugin.execute(&orange[0]).await.unwrap();
+        assert_eq!(api.read_clipboard().unwrap(), "#ff8800");
+
+        let recent = plugin
+            .match_query(&QueryContext::new("color"))
+            .await
+            .unwrap();
+        let titles: Vec<&str> = recent.iter().map(|result| result.title.as_ref()).collect();
+        assert_eq!(titles, vec!["#ff8800", "#0a141e"]);
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
@@ -0,0 +1,672 @@
+/// Dictionary and thesaurus
+///
+/// `define <word>` answers with the word's senses, best first, and
+/// `syn <word>` lists its synonyms. Executing a sense copies its
+/// definition; the action menu copies the synonyms or every definition of
+/// the word, and the preview shows the whole entry as markdown. Unknown
+/// words offer the closest known ones instead.
+///
+/// The word list ships with the plugin's package as the `wordnet.tsv`
+/// asset, usually bundled gzip-compressed (see `crate::assets`), and is
+/// loaded on the first lookup. Each line holds one sense, in the order of
+/// the word's senses, as tab-separated fields:
+/// 1. the word
+/// 2. its part of speech
+/// 3. the definition
+/// 4. synonyms, comma-separated, `_` standing for spaces
+/// 5. examples, `|`-separated
+///
+/// ```text
+/// ephemeral\tadj\tlasting a very short time\ttransient,fleeting\tephemeral pleasures
+/// ```
+///
+/// Parts of speech are WordNet's `n`, `v`, `adj` and `adv`; the last two
+/// fields may be empty or left out, and lines starting with `#` are
+/// comments.
+use crate::api::VoltPluginAPI;
+use crate::extensions::{Preview, Previewer};
+use crate::i18n::Messages;
+use crate::locks::{self, Recover};
+use crate::outcome::{ExecuteOutcome, Toast};
+use crate::plugin::{Plugin, QueryContext};
+use crate::result::{PluginResult, ResultAction, ResultIntent};
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+/// Identifier of the dictionary plugin
+pub const PLUGIN_ID: &str = "dictionary";
+
+/// Asset of the plugin's package holding the word list
+pub const DATASET_ASSET: &str = "wordnet.tsv";
+
+/// Keyword looking up definitions
+pub const DEFINE_KEYWORD: &str = "define";
+
+/// Keyword looking up synonyms
+pub const SYNONYMS_KEYWORD: &str = "syn";
+
+/// Action copying the synonyms of a sense
+pub const ACTION_COPY_SYNONYMS: &str = "copy-synonyms";
+
+/// Action copying every definition of the word
+pub const ACTION_COPY_ENTRY: &str = "copy-entry";
+
+/// Most words offered for an unknown word
+const MAX_SUGGESTIONS: usize = 5;
+
+/// Grammatical category of a sense
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartOfSpeech {
+    /// `n`
+    Noun,
+    /// `v`
+    Verb,
+    /// `adj`, or WordNet's satellite adjective `s`
+    Adjective,
+    /// `adv`, or WordNet's `r`
+    Adverb,
+}
+
+impl PartOfSpeech {
+    /// Parse a WordNet part of speech
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.trim().to_lowercase().as_str() {
+            "n" | "noun" => Some(Self::Noun),
+            "v" | "verb" => Some(Self::Verb),
+            "a" | "s" | "adj" | "adjective" => Some(Self::Adjective),
+            "r" | "adv" | "adverb" => Some(Self::Adverb),
+            _ => None,
+        }
+    }
+
+    /// Name of the part of speech in the user's language
+    pub fn label(&self, messages: &Messages) -> &'static str {
+        messages.text(match self {
+            Self::Noun => "dictionary.noun",
+            Self::Verb => "dictionary.verb",
+            Self::Adjective => "dictionary.adjective",
+            Self::Adverb => "dictionary.adverb",
+        })
+    }
+}
+
+/// One meaning of a word
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sense {
+    /// Grammatical category
+    pub part_of_speech: PartOfSpeech,
+    /// What the word means in this sense
+    pub definition: String,
+    /// Words with the same meaning
+    pub synonyms: Vec<String>,
+    /// Sentences using the word in this sense
+    pub examples: Vec<String>,
+}
+
+/// A word and its senses, most common first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// The word as written in the word list
+    pub word: String,
+    /// Its senses
+    pub senses: Vec<Sense>,
+}
+
+impl Entry {
+    /// Every synonym of the word, without duplicates, in sense order
+    pub fn synonyms(&self) -> Vec<&str> {
+        let mut synonyms: Vec<&str> = Vec::new();
+        for synonym in self.senses.iter().flat_map(|sense| &sense.synonyms) {
+            if !synonyms.contains(&synonym.as_str()) {
+                synonyms.push(synonym);
+            }
+        }
+        synonyms
+    }
+
+    /// Plain-text form of every definition, one per line
+    pub fn to_text(&self, messages: &Messages) -> String {
+        self.senses
+            .iter()
+            .enumerate()
+            .map(|(index, sense)| {
+                format!(
+                    "{}. ({}) {}",
+                    index + 1,
+                    sense.part_of_speech.label(messages),
+                    sense.definition
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Markdown form of the entry, shown as the preview
+    pub fn to_markdown(&self, messages: &Messages) -> String {
+        let mut markdown = format!("# {}\n", self.word);
+
+        let mut part_of_speech = None;
+        for sense in &self.senses {
+            if part_of_speech != Some(sense.part_of_speech) {
+                part_of_speech = Some(sense.part_of_speech);
+                markdown.push_str(&format!("\n*{}*\n\n", sense.part_of_speech.label(messages)));
+            }
+            markdown.push_str(&format!("1. {}\n", sense.definition));
+            for example in &sense.examples {
+                markdown.push_str(&format!("   > {}\n", example));
+            }
+            if !sense.synonyms.is_empty() {
+                markdown.push_str(&format!(
+                    "   - **{}:** {}\n",
+                    messages.text("dictionary.synonyms"),
+                    sense.synonyms.join(", ")
+                ));
+            }
+        }
+        markdown
+    }
+}
+
+/// A word list, by lowercase word
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Dictionary {
+    entries: BTreeMap<String, Entry>,
+}
+
+impl Dictionary {
+    /// Parse a word list, see the module documentation for the format
+    ///
+    /// Malformed lines are skipped.
+    pub fn parse(text: &str) -> Self {
+        let mut entries: BTreeMap<String, Entry> = BTreeMap::new();
+
+        for line in text.lines() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            let (Some(word), Some(part_of_speech), Some(definition)) = (
+                fields.first().map(|word| word.trim()),
+                fields.get(1).and_then(|pos| PartOfSpeech::parse(pos)),
+                fields.get(2).map(|definition| definition.trim()),
+            ) else {
+                continue;
+            };
+            if word.is_empty() || definition.is_empty() {
+                continue;
+            }
+
+            let list = |index: usize, separator: char| -> Vec<String> {
+                fields
+                    .get(index)
+                    .into_iter()
+                    .flat_map(|field| field.split(separator))
+                    .map(|item| item.trim().replace('_', " "))
+                    .filter(|item| !item.is_empty())
+                    .collect()
+            };
+            let sense = Sense {
+                part_of_speech,
+                definition: definition.to_string(),
+                synonyms: list(3, ','),
+                examples: list(4, '|'),
+            };
+
+            entries
+                .entry(word.to_lowercase())
+                .or_insert_with(|| Entry {
+                    word: word.to_string(),
+                    senses: Vec::new(),
+                })
+                .senses
+                .push(sense);
+        }
+
+        Self { entries }
+    }
+
+    /// Load a word list from a file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read word list: {}", e))?;
+        Ok(Self::parse(&text))
+    }
+
+    /// Number of words
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the word list is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Look a word up, ignoring case
+    pub fn lookup(&self, word: &str) -> Option<&Entry> {
+        self.entries.get(&word.trim().to_lowercase())
+    }
+
+    /// Known words closest to an unknown one
+    ///
+    /// Words starting with `word` come first, then words sharing the
+    /// longest prefix with it.
+    pub fn suggestions(&self, word: &str, limit: usize) -> Vec<&Entry> {
+        let word = word.trim().to_lowercase();
+        let mut suggestions: Vec<&Entry> = self
+            .entries
+            .range(word.clone()..)
+            .take_while(|(key, _)| key.starts_with(&word))
+            .map(|(_, entry)| entry)
+            .take(limit)
+            .collect();
+
+        // Back off one character at a time until something matches
+        let mut prefix = word.as_str();
+        while suggestions.is_empty() && prefix.chars().count() > 2 {
+            prefix = &prefix[..prefix.char_indices().last().map_or(0, |(index, _)| index)];
+            suggestions = self
+                .entries
+                .range(prefix.to_string()..)
+                .take_while(|(key, _)| key.starts_with(prefix))
+                .map(|(_, entry)| entry)
+                .take(limit)
+                .collect();
+        }
+        suggestions
+    }
+}
+
+/// The word list, once loaded
+#[derive(Default)]
+struct Loaded(Option<Arc<Dictionary>>);
+
+impl Recover for Loaded {
+    fn recover(&mut self) {
+        // Reloaded from the asset on next use
+        self.0 = None;
+    }
+}
+
+/// What a query asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Lookup<'a> {
+    Define(&'a str),
+    Synonyms(&'a str),
+}
+
+/// Split a query into a lookup, if it is addressed to the plugin
+fn parse_lookup(query: &str) -> Option<Lookup<'_>> {
+    let (keyword, word) = query.trim().split_once(char::is_whitespace)?;
+    let word = word.trim();
+    if word.is_empty() {
+        return None;
+    }
+
+    if keyword.eq_ignore_ascii_case(DEFINE_KEYWORD) {
+        Some(Lookup::Define(word))
+    } else if keyword.eq_ignore_ascii_case(SYNONYMS_KEYWORD) {
+        Some(Lookup::Synonyms(word))
+    } else {
+        None
+    }
+}
+
+/// Built-in plugin answering definitions and synonyms from an offline word list
+pub struct DictionaryPlugin {
+    api: VoltPluginAPI,
+    dictionary: RwLock<Loaded>,
+}
+
+impl DictionaryPlugin {
+    /// Create the plugin; the word list is loaded on the first lookup
+    pub fn new(api: VoltPluginAPI) -> Self {
+        Self {
+            api,
+            dictionary: RwLock::new(Loaded::default()),
+        }
+    }
+
+    /// Get the word list, loading it from the package's assets on first use
+    pub fn dictionary(&self) -> Result<Arc<Dictionary>, String> {
+        if let Some(dictionary) = &locks::read(&self.dictionary, "dictionary").0 {
+            return Ok(dictionary.clone());
+        }
+
+        let path = self.api.asset(PLUGIN_ID, DATASET_ASSET)?;
+        let dictionary = Arc::new(Dictionary::load(&path)?);
+        locks::write(&self.dictionary, "dictionary").0 = Some(dictionary.clone());
+        Ok(dictionary)
+    }
+
+    /// Run a secondary action on a sense result
+    ///
+    /// Call this from the host with the action routed by `MergedResults`;
+    /// `execute` copies the definition.
+    ///
+    /// # Arguments
+    /// * `result` - A result returned by this plugin
+    /// * `action` - `ACTION_COPY_SYNONYMS` or `ACTION_COPY_ENTRY`
+    pub fn perform(&self, result: &PluginResult, action: &str) -> Result<ExecuteOutcome, String> {
+        let word = result.meta_str("word").ok_or("Missing word")?;
+        let dictionary = self.dictionary()?;
+        let entry = dictionary
+            .lookup(word)
+            .ok_or_else(|| format!("Unknown word '{}'", word))?;
+        let messages = self.api.messages();
+
+        let text = match action {
+            ACTION_COPY_SYNONYMS => {
+                let sense = result
+                    .meta_u64("sense")
+                    .and_then(|index| entry.senses.get(index as usize))
+                    .ok_or("Missing sense")?;
+                sense.synonyms.join(", ")
+            }
+            ACTION_COPY_ENTRY => entry.to_text(&messages),
+            _ => return Err(format!("Unknown dictionary action '{}'", action)),
+        };
+        self.api.write_clipboard(&text)?;
+        Ok(
+            ExecuteOutcome::close()
+                .with_message(Toast::success(messages.text("dictionary.copied"))),
+        )
+    }
+
+    /// Results for `define <word>`: the senses, or the closest words
+    fn define_results(&self, dictionary: &Dictionary, word: &str) -> Vec<PluginResult> {
+        let messages = self.api.messages();
+        let Some(entry) = dictionary.lookup(word) else {
+            return self.suggestion_results(dictionary, word, DEFINE_KEYWORD);
+        };
+
+        entry
+            .senses
+            .iter()
+            .enumerate()
+            .map(|(index, sense)| {
+                let mut result = PluginResult::new(
+                    format!("{}-{}", entry.word, index),
+                    sense.definition.clone(),
+                )
+                .with_meta("word", entry.word.clone())
+                .with_meta("sense", index as u64)
+                .with_intent(ResultIntent::CopyText {
+                    text: sense.definition.clone(),
+                });
+                if !sense.synonyms.is_empty() {
+                    result = result.with_action(ResultAction::new(
+                        ACTION_COPY_SYNONYMS,
+                        messages.text("dictionary.copy_synonyms"),
+                    ));
+                }
+                result = result.with_action(ResultAction::new(
+                    ACTION_COPY_ENTRY,
+                    messages.text("dictionary.copy_entry"),
+                ));
+                result.subtitle = Some(
+                    format!("{} · {}", entry.word, sense.part_of_speech.label(&messages)).into(),
+                );
+                result.icon = Some("📖".into());
+                // The first sense is the quick answer
+                result.score = 100u32.saturating_sub(index as u32 * 5);
+                result
+            })
+            .collect()
+    }
+
+    /// Results for `syn <word>`: one per synonym
+    fn synonym_results(&self, dictionary: &Dictionary, word: &str) -> Vec<PluginResult> {
+        let messages = self.api.messages();
+        let Some(entry) = dictionary.lookup(word) else {
+            return self.suggestion_results(dictionary, word, SYNONYMS_KEYWORD);
+        };
+
+        let synonym_of = messages.format("dictionary.synonym_of", &[("word", &entry.word)]);
+        entry
+            .synonyms()
+            .into_iter()
+            .enumerate()
+            .map(|(index, synonym)| {
+                let mut result = PluginResult::new(
+                    format!("{}-synonym-{}", entry.word, index),
+                    synonym.to_string(),
+                )
+                .with_meta("word", entry.word.clone())
+                .with_intent(ResultIntent::CopyText {
+                    text: synonym.to_string(),
+                });
+                result.subtitle = Some(synonym_of.clone().into());
+                result.icon = Some("📖".into());
+                result.score = 90u32.saturating_sub(index as u32);
+                result
+            })
+            .collect()
+    }
+
+    /// Results looking up the known words closest to an unknown one
+    fn suggestion_results(
+        &self,
+        dictionary: &Dictionary,
+        word: &str,
+        keyword: &str,
+    ) -> Vec<PluginResult> {
+        let messages = self.api.messages();
+        let suggestions = dictionary.suggestions(word, MAX_SUGGESTIONS);
+        if suggestions.is_empty() {
+            let mut result = PluginResult::new(
+                "not-found",
+                messages.format("dictionary.not_found", &[("word", word)]),
+            );
+            result.icon = Some("📖".into());
+            return vec![result];
+        }
+
+        suggestions
+            .into_iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let mut result = PluginResult::new(
+                    format!("suggestion-{}", entry.word),
+                    messages.format("dictionary.look_up", &[("word", &entry.word)]),
+                )
+                .with_meta("requery", format!("{} {}", keyword, entry.word));
+                result.subtitle = entry
+                    .senses
+                    .first()
+                    .map(|sense| sense.definition.clone().into());
+                result.icon = Some("📖".into());
+                result.score = 50u32.saturating_sub(index as u32);
+                result
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Previewer for DictionaryPlugin {
+    async fn preview(&self, result: &PluginResult) -> Result<Preview, String> {
+        let word = result.meta_str("word").ok_or("Result has no preview")?;
+        let dictionary = self.dictionary()?;
+        let entry = dictionary
+            .lookup(word)
+            .ok_or_else(|| format!("Unknown word '{}'", word))?;
+
+        Ok(Preview::Markdown {
+            markdown: entry.to_markdown(&self.api.messages()),
+        })
+    }
+}
+
+#[async_trait]
+impl Plugin for DictionaryPlugin {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn id(&self) -> &str {
+        PLUGIN_ID
+    }
+
+    fn name(&self) -> &str {
+        self.api.messages().text("dictionary.name")
+    }
+
+    fn description(&self) -> &str {
+        self.api.messages().text("dictionary.description")
+    }
+
+    fn can_handle(&self, context: &QueryContext) -> bool {
+        parse_lookup(&context.query).is_some()
+    }
+
+    async fn match_query(&self, context: &QueryContext) -> Result<Vec<PluginResult>, String> {
+        let Some(lookup) = parse_lookup(&context.query) else {
+            return Ok(Vec::new());
+        };
+        let dictionary = self.dictionary()?;
+
+        Ok(match lookup {
+            Lookup::Define(word) => self.define_results(&dictionary, word),
+            Lookup::Synonyms(word) => self.synonym_results(&dictionary, word),
+        })
+    }
+
+    async fn execute(&self, result: &PluginResult) -> Result<ExecuteOutcome, String> {
+        // Senses and synonyms carry a copy intent run by the host
+        match result.meta_str("requery") {
+            Some(query) => Ok(ExecuteOutcome::requery(query)),
+            None => Ok(ExecuteOutcome::keep_open()),
+        }
+    }
+
+    fn as_previewer(&self) -> Option<&dyn Previewer> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORDS: &str = "\
+# word\tpos\tdefinition\tsynonyms\texamples
+ephemeral\tadj\tlasting a very short time\ttransient,fleeting,short-lived\tephemeral pleasures|fame is ephemeral
+ephemeral\tn\tanything short-lived, as an insect that lives only for a day
+ephemeron\tn\tsomething ephemeral\t\t
+epic\tadj\tvery imposing or impressive\tlarge_scale
+broken line without enough fields
+";
+
+    /// API whose dictionary package bundles `WORDS`, compressed when supported
+    fn api_with_words(temp_dir: &Path) -> VoltPluginAPI {
+        let _ = std::fs::remove_dir_all(temp_dir);
+        let api = VoltPluginAPI::new(temp_dir.to_path_buf());
+        let assets = api
+            .get_plugin_package_dir(PLUGIN_ID)
+            .unwrap()
+            .join(crate::assets::ASSETS_DIR);
+        std::fs::create_dir_all(&assets).unwrap();
+
+        #[cfg(feature = "compressed-assets")]
+        {
+            use std::io::Write;
+            let file = std::fs::File::create(assets.join(format!("{}.gz", DATASET_ASSET))).unwrap();
+            let mut encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+            encoder.write_all(WORDS.as_bytes()).unwrap();
+            encoder.finish().unwrap();
+        }
+        #[cfg(not(feature = "compressed-assets"))]
+        std::fs::write(assets.join(DATASET_ASSET), WORDS).unwrap();
+
+        api
+    }
+
+    #[test]
+    fn test_parse_word_list() {
+        let dictionary = Dictionary::parse(WORDS);
+        assert_eq!(dictionary.len(), 3);
+
+        let entry = dictionary.lookup("Ephemeral").unwrap();
+        assert_eq!(entry.senses.len(), 2);
+        assert_eq!(entry.senses[0].part_of_speech, PartOfSpeech::Adjective);
+        assert_eq!(
+            entry.senses[0].examples,
+            vec!["ephemeral pleasures", "fame is ephemeral"]
+        );
+        assert_eq!(entry.senses[1].part_of_speech, PartOfSpeech::Noun);
+        assert_eq!(
+            dictionary.lookup("epic").unwrap().synonyms(),
+            vec!["large scale"]
+        );
+
+        let words = |word: &str| -> Vec<String> {
+            dictionary
+                .suggestions(word, 5)
+                .iter()
+                .map(|entry| entry.word.clone())
+                .collect()
+        };
+        assert_eq!(words("ephem"), vec!["ephemeral", "ephemeron"]);
+        assert_eq!(words("ephemerally"), vec!["ephemeral"]);
+        assert!(words("zebra").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_define_and_synonyms() {
+        let temp_dir = std::env::temp_dir().join("volt_test_dictionary");
+        let api = api_with_words(&temp_dir);
+        let plugin = DictionaryPlugin::new(api.clone());
+
+        let context = QueryContext::new("define ephemeral");
+        assert!(plugin.can_handle(&context));
+        assert!(!plugin.can_handle(&QueryContext::new("define ")));
+        let senses = plugin.match_query(&context).await.unwrap();
+        assert_eq!(senses.len(), 2);
+        assert_eq!(senses[0].title, "lasting a very short time");
+        assert_eq!(senses[0].subtitle.as_deref(), Some("ephemeral · adjective"));
+        assert_eq!(
+            senses[0].intent,
+            Some(ResultIntent::CopyText {
+                text: "lasting a very short time".to_string()
+            })
+        );
+        assert_eq!(senses[0].actions[0].id, ACTION_COPY_SYNONYMS);
+        assert_eq!(senses[1].actions[0].id, ACTION_COPY_ENTRY);
+
+        let Preview::Markdown { markdown } = plugin.preview(&senses[0]).await.unwrap() else {
+            panic!("expected a markdown preview");
+        };
+        assert!(
+            markdown.starts_with("# ephemeral\n\n*adjective*\n\n1. lasting a very short time\n")
+        );
+        assert!(markdown.contains("**Synonyms:** transient, fleeting, short-lived"));
+
+        let synonyms = plugin
+            .match_query(&QueryContext::new("syn ephemeral"))
+            .await
+            .unwrap();
+        let titles: Vec<&str> = synonyms
+            .iter()
+            .map(|result| result.title.as_ref())
+            .collect();
+        assert_eq!(titles, vec!["transient", "fleeting", "short-lived"]);
+
+        // Unknown words offer the closest known ones
+        let suggestions = plugin
+            .match_query(&QueryContext::new("define ephemer"))
+            .await
+            .unwrap();
+        assert_eq!(suggestions[0].title, "Look up ephemeral");
+        let outcome = plugin.execute(&suggestions[0]).await.unwrap();
+        assert_eq!(outcome, ExecuteOutcome::requery("define ephemeral"));
+
+        api.set_locale("de");
+        let senses = plugin.match_query(&context).await.unwrap();
+        assert_eq!(senses[0].subtitle.as_deref(), Some("ephemeral · Adjektiv"));
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
@@ -0,0 +1,1074 @@
+/// Currency conversions and stock quotes
+///
+/// Understands queries such as `100 usd to eur`, `€20 in gbp`, `$aapl` and
+/// `msft stock`; bare tickers (`aapl`) are answered for symbols on the
+/// user's watchlist. Answers copy the converted amount or price.
+///
+/// Rates and quotes are fetched with the download manager into the plugin's
+/// cache directory, so they survive restarts. Cached values are shown right
+/// away; once older than their maximum age they are refreshed in the
+/// background and shown as stale in the meantime (stale-while-revalidate).
+/// Offline, the last values stay in use and say so; without any cached
+/// value, an explicit "unavailable offline" result is shown instead of
+/// nothing. Providers and maximum ages are set on the plugin's settings page.
+use crate::actions::now_millis;
+use crate::api::VoltPluginAPI;
+use crate::download::DownloadOpts;
+use crate::extensions::SettingsProvider;
+use crate::i18n::Messages;
+use crate::outcome::Toast;
+use crate::plugin::{Plugin, QueryContext};
+use crate::result::{PluginResult, ResultIntent};
+use crate::settings::{SettingsField, SettingsPage, SettingsSection};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Identifier of the finance plugin
+pub const PLUGIN_ID: &str = "finance";
+
+/// Settings page, also the name of the plugin's configuration
+pub const SETTINGS_PAGE: &str = "finance";
+
+/// Cache file holding the latest currency rates
+pub const RATES_FILE: &str = "rates.json";
+
+/// Default rates provider, publishing the ECB reference rates
+pub const FRANKFURTER_URL: &str = "https://api.frankfurter.app/latest";
+
+/// Default quotes provider; `{symbol}` is replaced by the ticker
+pub const STOOQ_URL: &str = "https://stooq.com/q/l/?s={symbol}&f=sd2t2ohlcv&h&e=csv";
+
+/// Longest wait for a provider while the user is typing
+pub const FETCH_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Settings action refreshing the rates immediately
+pub const ACTION_REFRESH: &str = "refreshNow";
+
+const MINUTE_MS: u64 = 60 * 1000;
+const HOUR_MS: u64 = 60 * MINUTE_MS;
+
+/// Symbols typed instead of currency codes
+const CURRENCY_SYMBOLS: &[(&str, &str)] = &[("$", "USD"), ("€", "EUR"), ("£", "GBP"), ("¥", "JPY")];
+
+/// Where currency rates come from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RatesProvider {
+    /// ECB reference rates through frankfurter.app
+    Frankfurter,
+    /// URL answering in the Frankfurter format
+    Custom(String),
+}
+
+impl RatesProvider {
+    /// URL of the latest rates
+    pub fn url(&self) -> &str {
+        match self {
+            RatesProvider::Frankfurter => FRANKFURTER_URL,
+            RatesProvider::Custom(url) => url,
+        }
+    }
+}
+
+/// Where stock quotes come from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuoteProvider {
+    /// stooq.com CSV quotes, US listings unless the symbol has a suffix
+    Stooq,
+    /// URL template with `{symbol}`, answering `{"price": 1.0, "currency": "USD"}`
+    Custom(String),
+}
+
+impl QuoteProvider {
+    /// URL of a symbol's quote
+    pub fn url(&self, symbol: &str) -> String {
+        match self {
+            QuoteProvider::Stooq => {
+                let symbol = symbol.to_lowercase();
+                let listing = if symbol.contains('.') {
+                    symbol
+                } else {
+                    format!("{}.us", symbol)
+                };
+                STOOQ_URL.replace("{symbol}", &listing)
+            }
+            QuoteProvider::Custom(template) => template.replace("{symbol}", symbol),
+        }
+    }
+
+    /// Parse a provider's answer
+    pub fn parse(&self, symbol: &str, content: &str) -> Result<Quote, String> {
+        match self {
+            QuoteProvider::Stooq => parse_stooq_csv(symbol, content),
+            QuoteProvider::Custom(_) => {
+                #[derive(Deserialize)]
+                struct Answer {
+                    price: f64,
+                    currency: Option<String>,
+                }
+
+                let answer: Answer = serde_json::from_str(content)
+                    .map_err(|e| format!("Failed to parse quote: {}", e))?;
+                Ok(Quote {
+                    symbol: symbol.to_string(),
+                    price: answer.price,
+                    currency: answer.currency,
+                    date: None,
+                })
+            }
+        }
+    }
+}
+
+/// User settings of the plugin
+#[derive(Debug, Clone, PartialEq)]
+pub struct FinanceSettings {
+    /// Source of currency rates
+    pub rates_provider: RatesProvider,
+    /// Source of stock quotes
+    pub quote_provider: QuoteProvider,
+    /// Tickers answered without `$` or "stock", uppercase
+    pub watchlist: Vec<String>,
+    /// Age after which rates are refreshed, in milliseconds
+    pub rates_max_age_ms: u64,
+    /// Age after which quotes are refreshed, in milliseconds
+    pub quote_max_age_ms: u64,
+}
+
+impl Default for FinanceSettings {
+    fn default() -> Self {
+        Self {
+            rates_provider: RatesProvider::Frankfurter,
+            quote_provider: QuoteProvider::Stooq,
+            watchlist: Vec::new(),
+            rates_max_age_ms: 12 * HOUR_MS,
+            quote_max_age_ms: 15 * MINUTE_MS,
+        }
+    }
+}
+
+impl FinanceSettings {
+    /// Read the settings saved from the settings page
+    ///
+    /// Missing or invalid values keep their default.
+    pub fn from_config(config: &Value) -> Self {
+        let defaults = Self::default();
+        let text = |key: &str| {
+            config
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+        };
+        let number = |key: &str| config.get(key).and_then(Value::as_f64).filter(|n| *n > 0.0);
+
+        let rates_provider = match (text("ratesProvider"), text("ratesUrl")) {
+            (Some("custom"), Some(url)) => RatesProvider::Custom(url.to_string()),
+            _ => defaults.rates_provider,
+        };
+        let quote_provider = match (text("quoteProvider"), text("quoteUrl")) {
+            (Some("custom"), Some(url)) => QuoteProvider::Custom(url.to_string()),
+            _ => defaults.quote_provider,
+        };
+        let watchlist = text("watchlist")
+            .map(|list| {
+                list.split([',', ' '])
+                    .filter(|symbol| is_symbol(symbol))
+                    .map(str::to_uppercase)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            rates_provider,
+            quote_provider,
+            watchlist,
+            rates_max_age_ms: number("ratesMaxAgeHours")
+                .map_or(defaults.rates_max_age_ms, |h| (h * HOUR_MS as f64) as u64),
+            quote_max_age_ms: number("quoteMaxAgeMinutes")
+                .map_or(defaults.quote_max_age_ms, |m| (m * MINUTE_MS as f64) as u64),
+        }
+    }
+}
+
+/// Currency rates relative to a base currency
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Rates {
+    /// Currency every rate is relative to
+    pub base: String,
+    /// Day the rates were published, if known
+    #[serde(default)]
+    pub date: Option<String>,
+    /// Units of each currency per unit of `base`
+    pub rates: HashMap<String, f64>,
+}
+
+impl Rates {
+    /// Parse rates in the Frankfurter format
+    pub fn from_json(content: &str) -> Result<Self, String> {
+        serde_json::from_str(content).map_err(|e| format!("Failed to parse rates: {}", e))
+    }
+
+    /// Convert an amount between two currencies
+    ///
+    /// # Returns
+    /// The converted amount, or None if a currency is unknown
+    pub fn convert(&self, amount: f64, from: &str, to: &str) -> Option<f64> {
+        let rate = |code: &str| {
+            if code == self.base {
+                Some(1.0)
+            } else {
+                self.rates.get(code).copied().filter(|rate| *rate > 0.0)
+            }
+        };
+        Some(amount / rate(from)? * rate(to)?)
+    }
+}
+
+/// Latest price of a stock
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quote {
+    /// Ticker, uppercase
+    pub symbol: String,
+    /// Last price
+    pub price: f64,
+    /// Currency of the price, if known
+    pub currency: Option<String>,
+    /// Trading day of the price, if known
+    pub date: Option<String>,
+}
+
+/// Parse a stooq.com CSV quote (`Symbol,Date,Time,Open,High,Low,Close,Volume`)
+pub fn parse_stooq_csv(symbol: &str, content: &str) -> Result<Quote, String> {
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+    let header: Vec<&str> = lines.next().ok_or("Empty quote")?.split(',').collect();
+    let row: Vec<&str> = lines.next().ok_or("Empty quote")?.split(',').collect();
+    let column = |name: &str| {
+        let index = header
+            .iter()
+            .position(|column| column.trim().eq_ignore_ascii_case(name))?;
+        row.get(index).map(|value| value.trim())
+    };
+
+    let price = column("Close")
+        .and_then(|close| close.parse::<f64>().ok())
+        .ok_or_else(|| format!("No quote for '{}'", symbol))?;
+    Ok(Quote {
+        symbol: symbol.to_string(),
+        price,
+        currency: None,
+        date: column("Date")
+            .filter(|date| *date != "N/D")
+            .map(str::to_string),
+    })
+}
+
+/// What a query asks for
+#[derive(Debug, Clone, PartialEq)]
+pub enum FinanceQuery {
+    /// Convert an amount between currencies
+    Convert {
+        /// Amount in `from`
+        amount: f64,
+        /// Currency code of the amount
+        from: String,
+        /// Currency code to convert to
+        to: String,
+    },
+    /// Look up a stock
+    Quote {
+        /// Ticker, uppercase
+        symbol: String,
+    },
+}
+
+/// Parse a conversion or quote query
+///
+/// # Arguments
+/// * `query` - The query, e.g. `100 usd to eur` or `$aapl`
+/// * `watchlist` - Tickers answered without `$` or "stock"
+pub fn parse_query(query: &str, watchlist: &[String]) -> Option<FinanceQuery> {
+    let lower = query.trim().to_lowercase();
+    let words: Vec<&str> = lower.split_whitespace().collect();
+
+    match words.as_slice() {
+        [symbol]
+            if symbol.starts_with('$')
+                && is_symbol(&symbol[1..])
+                && !symbol[1..].starts_with(|c: char| c.is_ascii_digit()) =>
+        {
+            return Some(FinanceQuery::Quote {
+                symbol: symbol[1..].to_uppercase(),
+            });
+        }
+        [symbol, "stock"] | ["stock", symbol] if is_symbol(symbol) => {
+            return Some(FinanceQuery::Quote {
+                symbol: symbol.to_uppercase(),
+            });
+        }
+        [symbol]
+            if watchlist
+                .iter()
+                .any(|watched| watched.eq_ignore_ascii_case(symbol)) =>
+        {
+            return Some(FinanceQuery::Quote {
+                symbol: symbol.to_uppercase(),
+            });
+        }
+        _ => {}
+    }
+
+    // "<amount> <from> to|in <to>", the amount and its currency possibly
+    // glued together ("100usd", "$100")
+    let (source, target) = lower
+        .split_once(" to ")
+        .or_else(|| lower.split_once(" in "))?;
+    let to = currency_code(target.trim())?;
+    let source: String = source.split_whitespace().collect();
+
+    let digits_at = source.find(|c: char| c.is_ascii_digit() || c == '.');
+    let (amount, from) = match digits_at {
+        None => (1.0, currency_code(&source)?),
+        Some(start) => {
+            let end = source[start..]
+                .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+                .map_or(source.len(), |end| start + end);
+            let currency = if start > 0 {
+                &source[..start]
+            } else {
+                &source[end..]
+            };
+            if start > 0 && end < source.len() {
+                return None;
+            }
+            let amount = source[start..end].replace(',', "").parse::<f64>().ok()?;
+            (amount, currency_code(currency)?)
+        }
+    };
+
+    (from != to && amount.is_finite()).then_some(FinanceQuery::Convert { amount, from, to })
+}
+
+/// Resolve a typed currency code or symbol to an uppercase code
+fn currency_code(text: &str) -> Option<String> {
+    if let Some((_, code)) = CURRENCY_SYMBOLS.iter().find(|(symbol, _)| *symbol == text) {
+        return Some(code.to_string());
+    }
+    (text.len() == 3 && text.chars().all(|c| c.is_ascii_alphabetic())).then(|| text.to_uppercase())
+}
+
+/// Check if text looks like a ticker, e.g. "aapl" or "brk.b"
+fn is_symbol(text: &str) -> bool {
+    (1..=10).contains(&text.len())
+        && text.starts_with(|c: char| c.is_ascii_alphabetic())
+        && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '.')
+}
+
+/// Format an amount with a precision suited to its size
+pub fn format_amount(amount: f64) -> String {
+    if amount.abs() >= 1.0 || amount == 0.0 {
+        format!("{:.2}", amount)
+    } else {
+        format!("{:.4}", amount)
+    }
+}
+
+/// Format the age of a cached value, e.g. "5 min" or "3 h"
+fn format_age(age_ms: u64, messages: &Messages) -> String {
+    let (key, count) = match age_ms {
+        age if age < MINUTE_MS => return messages.text("finance.age_moments").to_string(),
+        age if age < HOUR_MS => ("finance.age_minutes", age / MINUTE_MS),
+        age if age < 48 * HOUR_MS => ("finance.age_hours", age / HOUR_MS),
+        age => ("finance.age_days", age / (24 * HOUR_MS)),
+    };
+    messages.format(key, &[("count", &count.to_string())])
+}
+
+/// A value and when it was fetched, in milliseconds since the Unix epoch
+#[derive(Debug, Clone, PartialEq)]
+struct Cached<T> {
+    value: T,
+    fetched_at: u64,
+}
+
+impl<T> Cached<T> {
+    fn age(&self, now: u64) -> u64 {
+        now.saturating_sub(self.fetched_at)
+    }
+}
+
+/// State shared with background refreshes
+struct FinanceState {
+    api: VoltPluginAPI,
+    settings: Mutex<FinanceSettings>,
+    rates: Mutex<Option<Cached<Rates>>>,
+    quotes: Mutex<HashMap<String, Cached<Quote>>>,
+    /// Keys of the refreshes running in the background
+    refreshing: Mutex<HashSet<String>>,
+    /// Keys whose latest refresh failed
+    offline: Mutex<HashSet<String>>,
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|p| p.into_inner())
+}
+
+impl FinanceState {
+    fn settings(&self) -> FinanceSettings {
+        lock(&self.settings).clone()
+    }
+
+    /// Download a file into the cache and read it
+    async fn fetch(&self, url: &str, file_name: &str) -> Result<(String, u64), String> {
+        let download = self
+            .api
+            .download(PLUGIN_ID, url, DownloadOpts::new().file_name(file_name));
+        let downloaded = tokio::time::timeout(FETCH_TIMEOUT, download)
+            .await
+            .map_err(|_| format!("No answer from {} after {}s", url, FETCH_TIMEOUT.as_secs()))??;
+
+        let content = std::fs::read_to_string(&downloaded.path)
+            .map_err(|e| format!("Failed to read download: {}", e))?;
+        Ok((content, now_millis()))
+    }
+
+    /// Read a file downloaded by an earlier session
+    fn read_cached_file(&self, file_name: &str) -> Option<(String, u64)> {
+        let path = self
+            .api
+            .get_plugin_cache_dir(PLUGIN_ID)
+            .ok()?
+            .join(file_name);
+        let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+        let fetched_at = modified
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+
+        Some((std::fs::read_to_string(&path).ok()?, fetched_at))
+    }
+
+    fn cached_rates(&self) -> Option<Cached<Rates>> {
+        let mut rates = lock(&self.rates);
+        if rates.is_none() {
+            let (content, fetched_at) = self.read_cached_file(RATES_FILE)?;
+            *rates = Some(Cached {
+                value: Rates::from_json(&content).ok()?,
+                fetched_at,
+            });
+        }
+        rates.clone()
+    }
+
+    async fn refresh_rates(&self) -> Result<Cached<Rates>, String> {
+        let provider = self.settings().rates_provider;
+        let outcome =
+            self.fetch(provider.url(), RATES_FILE)
+                .await
+                .and_then(|(content, fetched_at)| {
+                    Ok(Cached {
+                        value: Rates::from_json(&content)?,
+                        fetched_at,
+                    })
+                });
+
+        self.record_refresh("rates", outcome.is_ok());
+        let cached = outcome?;
+        *lock(&self.rates) = Some(cached.clone());
+        Ok(cached)
+    }
+
+    fn cached_quote(&self, symbol: &str) -> Option<Cached<Quote>> {
+        let mut quotes = lock(&self.quotes);
+        if !quotes.contains_key(symbol) {
+            let (content, fetched_at) = self.read_cached_file(&quote_file(symbol))?;
+            let value = self
+                .settings()
+                .quote_provider
+                .parse(symbol, &content)
+                .ok()?;
+            quotes.insert(symbol.to_string(), Cached { value, fetched_at });
+        }
+        quotes.get(symbol).cloned()
+    }
+
+    async fn refresh_quote(&self, symbol: &str) -> Result<Cached<Quote>, String> {
+        let provider = self.settings().quote_provider;
+        let outcome = self
+            .fetch(&provider.url(symbol), &quote_file(symbol))
+            .await
+            .and_then(|(content, fetched_at)| {
+                Ok(Cached {
+                    value: provider.parse(symbol, &content)?,
+                    fetched_at,
+                })
+            });
+
+        self.record_refresh(&format!("quote:{}", symbol), outcome.is_ok());
+        let cached = outcome?;
+        lock(&self.quotes).insert(symbol.to_string(), cached.clone());
+        Ok(cached)
+    }
+
+    fn record_refresh(&self, key: &str, succeeded: bool) {
+        let mut offline = lock(&self.offline);
+        if succeeded {
+            offline.remove(key);
+        } else {
+            offline.insert(key.to_string());
+        }
+    }
+
+    fn is_offline(&self, key: &str) -> bool {
+        lock(&self.offline).contains(key)
+    }
+}
+
+/// Cache file of a symbol's quote
+fn quote_file(symbol: &str) -> String {
+    format!("quote-{}.dat", symbol.to_lowercase().replace('.', "_"))
+}
+
+/// The finance plugin
+pub struct FinancePlugin {
+    state: Arc<FinanceState>,
+}
+
+impl FinancePlugin {
+    /// Create the plugin with default settings
+    pub fn new(api: VoltPluginAPI) -> Self {
+        Self {
+            state: Arc::new(FinanceState {
+                api,
+                settings: Mutex::new(FinanceSettings::default()),
+                rates: Mutex::new(None),
+                quotes: Mutex::new(HashMap::new()),
+                refreshing: Mutex::new(HashSet::new()),
+                offline: Mutex::new(HashSet::new()),
+            }),
+        }
+    }
+
+    /// Get the current settings
+    pub fn settings(&self) -> FinanceSettings {
+        self.state.settings()
+    }
+
+    /// Replace the settings, dropping values fetched from other providers
+    pub fn set_settings(&self, settings: FinanceSettings) {
+        let mut current = lock(&self.state.settings);
+        if current.rates_provider != settings.rates_provider {
+            *lock(&self.state.rates) = None;
+        }
+        if current.quote_provider != settings.quote_provider {
+            lock(&self.state.quotes).clear();
+        }
+        *current = settings;
+    }
+
+    /// Refresh a value in the background, unless a refresh is running
+    ///
+    /// Without a Tokio runtime the value simply stays stale.
+    fn revalidate(
+        &self,
+        key: String,
+        refresh: impl FnOnce(Arc<FinanceState>) -> tokio::task::JoinHandle<()>,
+    ) {
+        if tokio::runtime::Handle::try_current().is_err()
+            || !lock(&self.state.refreshing).insert(key)
+        {
+            return;
+        }
+        refresh(self.state.clone());
+    }
+
+    async fn convert_result(&self, amount: f64, from: &str, to: &str) -> PluginResult {
+        let messages = self.state.api.messages();
+        let now = now_millis();
+        let settings = self.settings();
+        let cached = match self.state.cached_rates() {
+            Some(cached) => {
+                if cached.age(now) > settings.rates_max_age_ms {
+                    self.revalidate("rates".to_string(), |state| {
+                        tokio::spawn(async move {
+                            let _ = state.refresh_rates().await;
+                            lock(&state.refreshing).remove("rates");
+                        })
+                    });
+                }
+                cached
+            }
+            None => match self.state.refresh_rates().await {
+                Ok(cached) => cached,
+                Err(e) => {
+                    let title = messages.text("finance.rates_unavailable");
+                    return unavailable_result(title.to_string(), &e, &messages);
+                }
+            },
+        };
+
+        let Some(converted) = cached.value.convert(amount, from, to) else {
+            let mut result = PluginResult::new(
+                "unknown-currency",
+                messages.format("finance.cannot_convert", &[("from", from), ("to", to)]),
+            );
+            result.subtitle = Some(messages.text("finance.unknown_currency").into());
+            result.icon = Some("💱".into());
+            result.score = 10;
+            return result;
+        };
+
+        let amount_text = format_amount(converted);
+        let mut subtitle = format!(
+            "{} {} = {} {}",
+            format_amount(amount),
+            from,
+            amount_text,
+            to
+        );
+        if let Some(date) = &cached.value.date {
+            subtitle.push_str(" · ");
+            subtitle.push_str(&messages.format("finance.rates_of", &[("date", date)]));
+        }
+        let mut result = PluginResult::new(
+            format!("convert-{}-{}", from, to),
+            format!("{} {}", amount_text, to),
+        )
+        .with_meta("from", from)
+        .with_meta("to", to);
+        result.subtitle = Some(
+            self.with_staleness(subtitle, "rates", &cached, settings.rates_max_age_ms, now)
+                .into(),
+        );
+        result.icon = Some("💱".into());
+        result.intent = Some(ResultIntent::CopyText { text: amount_text });
+        result.score = 100;
+        self.mark_stale(&mut result, &cached, settings.rates_max_age_ms, now);
+        result
+    }
+
+    async fn quote_result(&self, symbol: &str) -> PluginResult {
+        let messages = self.state.api.messages();
+        let now = now_millis();
+        let settings = self.settings();
+        let key = format!("quote:{}", symbol);
+        let cached = match self.state.cached_quote(symbol) {
+            Some(cached) => {
+                if cached.age(now) > settings.quote_max_age_ms {
+                    let symbol = symbol.to_string();
+                    self.revalidate(key.clone(), |state| {
+                        tokio::spawn(async move {
+                            let _ = state.refresh_quote(&symbol).await;
+                            lock(&state.refreshing).remove(&format!("quote:{}", symbol));
+                        })
+                    });
+                }
+                cached
+            }
+            None => match self.state.refresh_quote(symbol).await {
+                Ok(cached) => cached,
+                Err(e) => {
+                    let title = messages.format("finance.quote_unavailable", &[("symbol", symbol)]);
+                    return unavailable_result(title, &e, &messages);
+                }
+            },
+        };
+
+        let quote = &cached.value;
+        let price = format_amount(quote.price);
+        let title = match &quote.currency {
+            Some(currency) => format!("{} {} {}", quote.symbol, price, currency),
+            None => format!("{} {}", quote.symbol, price),
+        };
+        let subtitle = match &quote.date {
+            Some(date) => messages.format("finance.last_price_of", &[("date", date)]),
+            None => messages.text("finance.last_price").to_string(),
+        };
+        let mut result =
+            PluginResult::new(format!("quote-{}", symbol), title).with_meta("symbol", symbol);
+        result.subtitle = Some(
+            self.with_staleness(subtitle, &key, &cached, settings.quote_max_age_ms, now)
+                .into(),
+        );
+        result.icon = Some("📈".into());
+        result.intent = Some(ResultIntent::CopyText { text: price });
+        result.score = 90;
+        self.mark_stale(&mut result, &cached, settings.quote_max_age_ms, now);
+        result
+    }
+
+    /// Tell how old a stale value is and whether it's being refreshed
+    fn with_staleness<T>(
+        &self,
+        subtitle: String,
+        key: &str,
+        cached: &Cached<T>,
+        max_age_ms: u64,
+        now: u64,
+    ) -> String {
+        let age = cached.age(now);
+        if age <= max_age_ms {
+            return subtitle;
+        }
+
+        let messages = self.state.api.messages();
+        let key = if self.state.is_offline(key) {
+            "finance.stale_offline"
+        } else {
+            "finance.stale_updating"
+        };
+        let status = messages.format(key, &[("age", &format_age(age, &messages))]);
+        format!("{} · {}", subtitle, status)
+    }
+
+    fn mark_stale<T>(
+        &self,
+        result: &mut PluginResult,
+        cached: &Cached<T>,
+        max_age_ms: u64,
+        now: u64,
+    ) {
+        let age = cached.age(now);
+        if age > max_age_ms {
+            result.cache_age_ms = Some(age);
+        }
+    }
+}
+
+/// Result explaining that nothing is cached and the provider can't be reached
+fn unavailable_result(title: String, error: &str, messages: &Messages) -> PluginResult {
+    let mut result = PluginResult::new("unavailable", title);
+    result.subtitle = Some(messages.format("finance.nothing_cached", &[("error", error)]).into());
+    result.icon = Some("📡".into());
+    result.score = 10;
+    result
+}
+
+#[async_trait]
+impl Plugin for FinancePlugin {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn id(&self) -> &str {
+        PLUGIN_ID
+    }
+
+    fn name(&self) -> &str {
+        self.state.api.messages().text("finance.name")
+    }
+
+    fn description(&self) -> &str {
+        self.state.api.messages().text("finance.description")
+    }
+
+    fn initialize(&self) -> Result<(), String> {
+        let config = self.state.api.load_config(PLUGIN_ID, SETTINGS_PAGE)?;
+
+        self.set_settings(FinanceSettings::from_config(&config));
+        Ok(())
+    }
+
+    fn can_handle(&self, context: &QueryContext) -> bool {
+        parse_query(&context.query, &self.settings().watchlist).is_some()
+    }
+
+    async fn match_query(&self, context: &QueryContext) -> Result<Vec<PluginResult>, String> {
+        let result = match parse_query(&context.query, &self.settings().watchlist) {
+            Some(FinanceQuery::Convert { amount, from, to }) => {
+                self.convert_result(amount, &from, &to).await
+            }
+            Some(FinanceQuery::Quote { symbol }) => self.quote_result(&symbol).await,
+            None => return Ok(Vec::new()),
+        };
+        Ok(vec![result])
+    }
+
+    fn on_config_changed(&self, config_name: &str, config: &Value) -> Result<(), String> {
+        if config_name == SETTINGS_PAGE {
+            self.set_settings(FinanceSettings::from_config(config));
+        }
+        Ok(())
+    }
+
+    fn as_settings_provider(&self) -> Option<&dyn SettingsProvider> {
+        Some(self)
+    }
+}
+
+#[async_trait]
+impl SettingsProvider for FinancePlugin {
+    fn settings_pages(&self) -> Vec<SettingsPage> {
+        let messages = self.state.api.messages();
+        let currencies = SettingsSection::new("currencies", messages.text("finance.currencies"))
+            .with_field(SettingsField::select(
+                "ratesProvider",
+                messages.text("finance.rates_provider"),
+                "frankfurter",
+                [
+                    ("frankfurter", "ECB (frankfurter.app)"),
+                    ("custom", messages.text("finance.custom_url")),
+                ],
+            ))
+            .with_field(
+                SettingsField::text("ratesUrl", messages.text("finance.rates_url"), "")
+                    .with_description(messages.text("finance.rates_url_help"))
+                    .visible_when("ratesProvider", "custom"),
+            )
+            .with_field(SettingsField::number(
+                "ratesMaxAgeHours",
+                messages.text("finance.rates_max_age"),
+                12.0,
+                1.0,
+                168.0,
+            ))
+            .with_field(SettingsField::button(
+                "refresh",
+                messages.text("finance.refresh_now"),
+                ACTION_REFRESH,
+            ));
+        let stocks = SettingsSection::new("stocks", messages.text("finance.stocks"))
+            .with_field(SettingsField::select(
+                "quoteProvider",
+                messages.text("finance.quote_provider"),
+                "stooq",
+                [("stooq", "Stooq"), ("custom", messages.text("finance.custom_url"))],
+            ))
+            .with_field(
+                SettingsField::text("quoteUrl", messages.text("finance.quote_url"), "")
+                    .with_description(messages.text("finance.quote_url_help"))
+                    .visible_when("quoteProvider", "custom"),
+            )
+            .with_field(
+                SettingsField::text("watchlist", messages.text("finance.watchlist"), "")
+                    .with_description(messages.text("finance.watchlist_help")),
+            )
+            .with_field(SettingsField::number(
+                "quoteMaxAgeMinutes",
+                messages.text("finance.quote_max_age"),
+                15.0,
+                1.0,
+                1440.0,
+            ));
+
+        vec![SettingsPage::new(SETTINGS_PAGE, messages.text("finance.name"))
+            .with_section(currencies)
+            .with_section(stocks)]
+    }
+
+    async fn settings_action(
+        &self,
+        _page_id: &str,
+        action: &str,
+        _values: &Value,
+    ) -> Result<Option<Toast>, String> {
+        if action != ACTION_REFRESH {
+            return Err(format!("Unknown finance action '{}'", action));
+        }
+
+        let rates = self.state.refresh_rates().await?;
+        let messages = self.state.api.messages();
+        let message = match &rates.value.date {
+            Some(date) => messages.format("finance.rates_updated_on", &[("date", date)]),
+            None => messages.text("finance.rates_updated").to_string(),
+        };
+        Ok(Some(Toast::success(message)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    const RATES: &str =
+        r#"{"amount":1.0,"base":"EUR","date":"2024-05-02","rates":{"USD":1.0,"GBP":0.8}}"#;
+
+    /// HTTP server answering `/rates` and `/quote/<symbol>`
+    async fn serve() -> (String, tokio::task::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let mut buffer = vec![0; 4096];
+                let read = stream.read(&mut buffer).await.unwrap();
+                let request = String::from_utf8_lossy(&buffer[..read]).to_string();
+                let path = request
+                    .split_whitespace()
+                    .nth(1)
+                    .unwrap_or_default()
+                    .to_string();
+
+                let body = match path.strip_prefix("/quote/") {
+                    Some("AAPL") => r#"{"price":170.5,"currency":"USD"}"#.to_string(),
+                    Some(_) => "not found".to_string(),
+                    None => RATES.to_string(),
+                };
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+
+        (format!("http://{}", address), server)
+    }
+
+    fn custom_settings(base_url: &str) -> FinanceSettings {
+        FinanceSettings::from_config(&serde_json::json!({
+            "ratesProvider": "custom",
+            "ratesUrl": format!("{}/rates", base_url),
+            "quoteProvider": "custom",
+            "quoteUrl": format!("{}/quote/{{symbol}}", base_url),
+            "watchlist": "aapl, msft",
+        }))
+    }
+
+    #[test]
+    fn test_queries_are_parsed() {
+        let convert = |amount: f64, from: &str, to: &str| FinanceQuery::Convert {
+            amount,
+            from: from.to_string(),
+            to: to.to_string(),
+        };
+        assert_eq!(
+            parse_query("100 usd to eur", &[]),
+            Some(convert(100.0, "USD", "EUR"))
+        );
+        assert_eq!(
+            parse_query("1,250.5 GBP in usd", &[]),
+            Some(convert(1250.5, "GBP", "USD"))
+        );
+        assert_eq!(
+            parse_query("100usd to eur", &[]),
+            Some(convert(100.0, "USD", "EUR"))
+        );
+        assert_eq!(
+            parse_query("€20 in £", &[]),
+            Some(convert(20.0, "EUR", "GBP"))
+        );
+        assert_eq!(
+            parse_query("usd to jpy", &[]),
+            Some(convert(1.0, "USD", "JPY"))
+        );
+
+        let quote = |symbol: &str| {
+            Some(FinanceQuery::Quote {
+                symbol: symbol.to_string(),
+            })
+        };
+        assert_eq!(parse_query("$aapl", &[]), quote("AAPL"));
+        assert_eq!(parse_query("msft stock", &[]), quote("MSFT"));
+        assert_eq!(parse_query("aapl", &["AAPL".to_string()]), quote("AAPL"));
+
+        for query in [
+            "aapl",
+            "$100",
+            "100 usd",
+            "go to bed",
+            "100 usd to usd",
+            "10 dollars to eur",
+            "$5x to eur",
+        ] {
+            assert!(
+                parse_query(query, &[]).is_none(),
+                "{:?} should not parse",
+                query
+            );
+        }
+    }
+
+    #[test]
+    fn test_providers() {
+        let rates = Rates::from_json(RATES).unwrap();
+        assert_eq!(rates.convert(10.0, "EUR", "USD"), Some(10.0));
+        assert_eq!(rates.convert(8.0, "GBP", "USD"), Some(10.0));
+        assert_eq!(rates.convert(1.0, "EUR", "XYZ"), None);
+
+        let csv = "Symbol,Date,Time,Open,High,Low,Close,Volume\nAAPL.US,2024-05-01,22:00:09,169.58,172.71,169.11,169.3,50383147\n";
+        let quote = parse_stooq_csv("AAPL", csv).unwrap();
+        assert_eq!(quote.price, 169.3);
+        assert_eq!(quote.date.as_deref(), Some("2024-05-01"));
+        assert!(parse_stooq_csv("XYZ", "Symbol,Date,Close\nXYZ.US,N/D,N/D").is_err());
+        assert_eq!(
+            QuoteProvider::Stooq.url("AAPL"),
+            "https://stooq.com/q/l/?s=aapl.us&f=sd2t2ohlcv&h&e=csv"
+        );
+
+        let settings = FinanceSettings::from_config(
+            &serde_json::json!({"ratesProvider": "custom", "watchlist": "aapl, 12"}),
+        );
+        assert_eq!(settings.rates_provider, RatesProvider::Frankfurter);
+        assert_eq!(settings.watchlist, vec!["AAPL"]);
+        SettingsProvider::settings_pages(&FinancePlugin::new(VoltPluginAPI::new(
+            std::env::temp_dir(),
+        )))[0]
+            .validate()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_answers_are_cached_for_offline_use() {
+        let temp_dir = std::env::temp_dir().join("volt_test_finance_plugin");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let api = VoltPluginAPI::new(temp_dir.clone());
+        let (base_url, server) = serve().await;
+
+        let plugin = FinancePlugin::new(api.clone());
+        plugin.set_settings(custom_settings(&base_url));
+        let results = plugin
+            .match_query(&QueryContext::new("8 gbp to usd"))
+            .await
+            .unwrap();
+        assert_eq!(results[0].title, "10.00 USD");
+        assert_eq!(
+            results[0].intent,
+            Some(ResultIntent::CopyText {
+                text: "10.00".to_string()
+            })
+        );
+        assert_eq!(results[0].cache_age_ms, None);
+
+        assert!(plugin.can_handle(&QueryContext::new("aapl")));
+        let results = plugin
+            .match_query(&QueryContext::new("aapl"))
+            .await
+            .unwrap();
+        assert_eq!(results[0].title, "AAPL 170.50 USD");
+
+        // A restarted, offline launcher answers from the cache
+        server.abort();
+        let _ = server.await;
+        let plugin = FinancePlugin::new(api.clone());
+        plugin.set_settings(FinanceSettings {
+            rates_max_age_ms: 0,
+            ..custom_settings(&base_url)
+        });
+        std::thread::sleep(Duration::from_millis(5));
+        let results = plugin
+            .match_query(&QueryContext::new("8 gbp to usd"))
+            .await
+            .unwrap();
+        assert_eq!(results[0].title, "10.00 USD");
+        assert!(results[0].is_stale());
+
+        // Nothing was ever fetched for MSFT
+        let results = plugin
+            .match_query(&QueryContext::new("msft"))
+            .await
+            .unwrap();
+        assert_eq!(results[0].title, "Quote of MSFT unavailable offline");
+        assert!(results[0].intent.is_none());
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
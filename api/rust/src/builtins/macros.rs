@@ -0,0 +1,552 @@
+/// User-authored macros
+///
+/// A macro chains launcher actions under one result: open a few URLs, start
+/// a program, wait for it, and type a greeting. Users write macros as YAML
+/// lists in the plugin's config directory (`plugins/macros/*.yaml`), which
+/// is read again on every query, so edits apply right away:
+///
+/// ```yaml
+/// - name: Start standup
+///   keyword: standup
+///   steps:
+///     - openUrl: https://meet.example.com/standup
+///     - wait: 2000
+///     - run: { program: slack, args: ["--status", "In a meeting"] }
+///       onError: continue
+///     - type: "Joining now"
+/// ```
+///
+/// `macro <search>` fuzzy-searches macro names; typing a macro's keyword
+/// offers it directly. Steps run in order through the same validation as
+/// result intents (see `crate::intents`). A failing step stops the macro
+/// unless it says `onError: continue`. The preview panel shows a dry run:
+/// every step with the problems it would hit, without running anything.
+use crate::api::VoltPluginAPI;
+use crate::i18n::Messages;
+use crate::extensions::{Preview, Previewer};
+use crate::features::HostFeature;
+use crate::input::TextInsertion;
+use crate::intents;
+use crate::matching::fuzzy_score_with;
+use crate::outcome::{ExecuteOutcome, Toast};
+use crate::plugin::{Plugin, QueryContext};
+use crate::result::{CommandSpec, PluginResult, ResultIntent};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Identifier of the macros plugin
+pub const PLUGIN_ID: &str = "macros";
+
+/// Keyword listing and searching macros
+pub const KEYWORD: &str = "macro";
+
+/// Longest `wait` step, in milliseconds
+pub const MAX_WAIT_MS: u64 = 60_000;
+
+/// Most results returned for a search
+const MAX_RESULTS: usize = 20;
+
+/// A named sequence of steps
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Macro {
+    /// Name shown in results
+    pub name: String,
+    /// Word offering the macro directly when typed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyword: Option<String>,
+    /// Shown below the name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Steps, run in order
+    pub steps: Vec<MacroStep>,
+}
+
+/// One step of a macro
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MacroStep {
+    /// What the step does
+    #[serde(flatten)]
+    pub action: StepAction,
+    /// What happens when the step fails
+    #[serde(default)]
+    pub on_error: OnError,
+}
+
+/// What a macro step does
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StepAction {
+    /// Open a URL in the default browser
+    OpenUrl(String),
+    /// Open a file or folder with its default application
+    OpenFile(String),
+    /// Start a program, without a shell
+    Run(CommandSpec),
+    /// Copy text to the clipboard
+    Copy(String),
+    /// Type text into the application the user was in
+    Type(String),
+    /// Pause, in milliseconds
+    Wait(u64),
+}
+
+impl StepAction {
+    /// The intent performing the step, for steps that are intents
+    pub fn intent(&self) -> Option<ResultIntent> {
+        match self {
+            Self::OpenUrl(url) => Some(ResultIntent::OpenUrl { url: url.clone() }),
+            Self::OpenFile(path) => Some(ResultIntent::OpenFile { path: path.clone() }),
+            Self::Run(spec) => Some(ResultIntent::RunCommand(spec.clone())),
+            Self::Copy(text) => Some(ResultIntent::CopyText { text: text.clone() }),
+            Self::Type(_) | Self::Wait(_) => None,
+        }
+    }
+
+    /// One-line description of the step, in the language of `messages`
+    pub fn describe(&self, messages: &Messages) -> String {
+        let excerpt = |text: &str| {
+            let mut excerpt: String = text.chars().take(40).collect();
+            if excerpt.len() < text.len() {
+                excerpt.push('…');
+            }
+            excerpt
+        };
+
+        match self {
+            Self::OpenUrl(url) => messages.format("macros.step_open_url", &[("url", url)]),
+            Self::OpenFile(path) => messages.format("macros.step_open_file", &[("path", path)]),
+            Self::Run(spec) => {
+                let command = intents::command_line(spec);
+                messages.format("macros.step_run", &[("command", &command)])
+            }
+            Self::Copy(text) => messages.format("macros.step_copy", &[("text", &excerpt(text))]),
+            Self::Type(text) => messages.format("macros.step_type", &[("text", &excerpt(text))]),
+            Self::Wait(ms) if ms % 1000 == 0 => {
+                let count = (ms / 1000).to_string();
+                messages.format("macros.step_wait_seconds", &[("count", &count)])
+            }
+            Self::Wait(ms) => messages.format("macros.step_wait_ms", &[("count", &ms.to_string())]),
+        }
+    }
+}
+
+/// What happens when a step fails
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OnError {
+    /// Skip the remaining steps
+    #[default]
+    Stop,
+    /// Go on with the next step
+    Continue,
+}
+
+/// A step that failed while running a macro
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepFailure {
+    /// Zero-based index of the step
+    pub index: usize,
+    /// Why it failed
+    pub error: String,
+}
+
+/// How running a macro went
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MacroReport {
+    /// Number of steps that ran successfully
+    pub completed: usize,
+    /// Steps that failed, in order
+    pub failures: Vec<StepFailure>,
+    /// Whether a failing step stopped the macro
+    pub stopped: bool,
+}
+
+/// Built-in plugin running user-authored macros
+pub struct MacrosPlugin {
+    api: VoltPluginAPI,
+}
+
+impl MacrosPlugin {
+    /// Create the plugin
+    pub fn new(api: VoltPluginAPI) -> Self {
+        Self { api }
+    }
+
+    /// Get all macros, in file name order then file order
+    ///
+    /// Files that fail to parse are logged and skipped, as are macros whose
+    /// name is already taken.
+    pub fn macros(&self) -> Result<Vec<Macro>, String> {
+        let dir = self.api.get_plugin_config_dir(PLUGIN_ID)?;
+        let mut files: Vec<_> = std::fs::read_dir(&dir)
+            .map_err(|e| format!("Failed to read macros directory: {}", e))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "yaml" || extension == "yml")
+            })
+            .collect();
+        files.sort();
+
+        let mut macros: Vec<Macro> = Vec::new();
+        for path in files {
+            let parsed = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| {
+                    serde_yaml::from_str::<Vec<Macro>>(&content).map_err(|e| e.to_string())
+                });
+            let file_macros = match parsed {
+                Ok(file_macros) => file_macros,
+                Err(e) => {
+                    self.api.diagnostics().warn(
+                        PLUGIN_ID,
+                        &format!("Failed to load {}: {}", path.display(), e),
+                    );
+                    continue;
+                }
+            };
+
+            for found in file_macros {
+                if macros.iter().any(|known| known.name == found.name) {
+                    self.api.diagnostics().warn(
+                        PLUGIN_ID,
+                        &format!(
+                            "Skipping duplicate macro '{}' in {}",
+                            found.name,
+                            path.display()
+                        ),
+                    );
+                    continue;
+                }
+                macros.push(found);
+            }
+        }
+        Ok(macros)
+    }
+
+    /// Get a macro by name
+    pub fn find(&self, name: &str) -> Result<Option<Macro>, String> {
+        Ok(self.macros()?.into_iter().find(|found| found.name == name))
+    }
+
+    /// Find macros for a query, best first
+    ///
+    /// `macro <search>` fuzzy-matches names; any other query matches the
+    /// macros whose keyword is its first word.
+    pub fn search(&self, query: &str) -> Result<Vec<(Macro, u32)>, String> {
+        let query = query.trim();
+        let first_word = query.split_whitespace().next().unwrap_or_default();
+        let search = match query.split_once(char::is_whitespace) {
+            Some((keyword, search)) if keyword.eq_ignore_ascii_case(KEYWORD) => Some(search.trim()),
+            None if query.eq_ignore_ascii_case(KEYWORD) => Some(""),
+            _ => None,
+        };
+
+        let tokenizer = self.api.tokenizer();
+        let mut matches: Vec<(Macro, u32)> = self
+            .macros()?
+            .into_iter()
+            .filter_map(|found| {
+                let score = match search {
+                    Some("") => 0,
+                    Some(search) => fuzzy_score_with(&tokenizer, search, &found.name)?,
+                    None if found.keyword.as_deref().is_some_and(|keyword| {
+                        !keyword.is_empty() && keyword.eq_ignore_ascii_case(first_word)
+                    }) =>
+                    {
+                        100
+                    }
+                    None => return None,
+                };
+                Some((found, score))
+            })
+            .collect();
+
+        matches.sort_by(|(a, a_score), (b, b_score)| {
+            b_score.cmp(a_score).then_with(|| a.name.cmp(&b.name))
+        });
+        matches.truncate(MAX_RESULTS);
+        Ok(matches)
+    }
+
+    /// Check whether a step can run right now, without running it
+    pub fn check(&self, action: &StepAction) -> Result<(), String> {
+        match action {
+            StepAction::Type(_) => self.api.features().require(HostFeature::InputSynthesis),
+            StepAction::Wait(ms) if *ms > MAX_WAIT_MS => {
+                Err(format!("Waits are limited to {} ms", MAX_WAIT_MS))
+            }
+            StepAction::Wait(_) => Ok(()),
+            action => action
+                .intent()
+                .map_or(Ok(()), |intent| intents::validate(&intent)),
+        }
+    }
+
+    /// Run a macro's steps in order
+    pub async fn run(&self, steps: &[MacroStep]) -> MacroReport {
+        let mut report = MacroReport::default();
+
+        for (index, step) in steps.iter().enumerate() {
+            match self.run_step(&step.action).await {
+                Ok(()) => report.completed += 1,
+                Err(error) => {
+                    report.failures.push(StepFailure { index, error });
+                    if step.on_error == OnError::Stop {
+                        report.stopped = true;
+                        break;
+                    }
+                }
+            }
+        }
+
+        report
+    }
+
+    async fn run_step(&self, action: &StepAction) -> Result<(), String> {
+        self.check(action)?;
+
+        match action {
+            StepAction::Type(text) => self
+                .api
+                .insert_text(PLUGIN_ID, &TextInsertion::new(text.clone())),
+            StepAction::Wait(ms) => {
+                tokio::time::sleep(Duration::from_millis(*ms)).await;
+                Ok(())
+            }
+            action => match action.intent() {
+                Some(intent) => intents::perform(&intent),
+                None => Ok(()),
+            },
+        }
+    }
+
+    /// Markdown describing what running a macro would do
+    pub fn dry_run(&self, found: &Macro) -> String {
+        let messages = self.api.messages();
+        let mut markdown = format!("### {}\n\n", found.name);
+        if let Some(description) = &found.description {
+            markdown.push_str(&format!("{}\n\n", description));
+        }
+
+        for (index, step) in found.steps.iter().enumerate() {
+            markdown.push_str(&format!("{}. {}", index + 1, step.action.describe(&messages)));
+            if step.on_error == OnError::Continue {
+                let note = messages.text("macros.continues_on_error");
+                markdown.push_str(&format!(" *({})*", note));
+            }
+            if let Err(e) = self.check(&step.action) {
+                markdown.push_str(&format!("\n   ⚠️ {}", e));
+            }
+            markdown.push('\n');
+        }
+
+        if found.steps.is_empty() {
+            markdown.push_str(&format!("*{}*\n", messages.text("macros.no_steps")));
+        }
+        markdown
+    }
+}
+
+#[async_trait]
+impl Plugin for MacrosPlugin {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn id(&self) -> &str {
+        PLUGIN_ID
+    }
+
+    fn name(&self) -> &str {
+        self.api.messages().text("macros.name")
+    }
+
+    fn description(&self) -> &str {
+        self.api.messages().text("macros.description")
+    }
+
+    fn can_handle(&self, context: &QueryContext) -> bool {
+        !context.query.trim().is_empty()
+    }
+
+    async fn match_query(&self, context: &QueryContext) -> Result<Vec<PluginResult>, String> {
+        let messages = self.api.messages();
+        Ok(self
+            .search(&context.query)?
+            .into_iter()
+            .map(|(found, score)| {
+                let steps = messages.plural(
+                    found.steps.len() as u64,
+                    "macros.steps_one",
+                    "macros.steps_other",
+                );
+                let slug: String = found
+                    .name
+                    .to_lowercase()
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join("-");
+                let mut result = PluginResult::new(format!("macro-{}", slug), found.name.clone())
+                    .with_meta("macro", found.name);
+                result.subtitle = Some(found.description.unwrap_or(steps).into());
+                result.badge = Some(messages.text("macros.badge").to_string());
+                result.score = score;
+                result
+            })
+            .collect())
+    }
+
+    async fn execute(&self, result: &PluginResult) -> Result<ExecuteOutcome, String> {
+        let name = result.meta_str("macro").ok_or("Missing macro name")?;
+        let found = self
+            .find(name)?
+            .ok_or_else(|| format!("Macro '{}' not found", name))?;
+
+        let report = self.run(&found.steps).await;
+        let messages = self.api.messages();
+        let Some(failure) = report.failures.first() else {
+            return Ok(ExecuteOutcome::close());
+        };
+
+        if report.stopped {
+            let failure = report.failures.last().unwrap_or(failure);
+            return Ok(ExecuteOutcome::failed(messages.format(
+                "macros.stopped",
+                &[
+                    ("name", &found.name),
+                    ("step", &(failure.index + 1).to_string()),
+                    ("error", &failure.error),
+                ],
+            )));
+        }
+
+        Ok(ExecuteOutcome::close().with_message(Toast::warning(messages.format(
+            "macros.partly_failed",
+            &[
+                ("name", &found.name),
+                ("failed", &report.failures.len().to_string()),
+                ("total", &found.steps.len().to_string()),
+                ("step", &(failure.index + 1).to_string()),
+                ("error", &failure.error),
+            ],
+        ))))
+    }
+
+    fn as_previewer(&self) -> Option<&dyn Previewer> {
+        Some(self)
+    }
+}
+
+#[async_trait]
+impl Previewer for MacrosPlugin {
+    async fn preview(&self, result: &PluginResult) -> Result<Preview, String> {
+        let name = result.meta_str("macro").ok_or("Missing macro name")?;
+        let found = self
+            .find(name)?
+            .ok_or_else(|| format!("Macro '{}' not found", name))?;
+
+        Ok(Preview::Markdown {
+            markdown: self.dry_run(&found),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    const MACROS: &str = r#"
+- name: Start standup
+  keyword: standup
+  steps:
+    - openUrl: https://meet.example.com/standup
+    - wait: 2000
+    - run: { program: slack, args: ["--status", "In a meeting"] }
+      onError: continue
+    - type: "Joining now"
+
+- name: Greet
+  steps:
+    - type: "Hello"
+    - openFile: /does/not/exist
+      onError: continue
+    - wait: 10
+    - type: "there"
+
+- name: Broken
+  steps:
+    - type: "one"
+    - openFile: relative/path
+    - type: "never typed"
+"#;
+
+    #[tokio::test]
+    async fn test_macros_are_loaded_previewed_and_run() {
+        let temp_dir = std::env::temp_dir().join("volt_test_macros");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let api = VoltPluginAPI::new(temp_dir.clone());
+        let config_dir = api.get_plugin_config_dir(PLUGIN_ID).unwrap();
+        std::fs::write(config_dir.join("daily.yaml"), MACROS).unwrap();
+        std::fs::write(config_dir.join("zz-broken.yml"), "- name: [unclosed").unwrap();
+        std::fs::write(config_dir.join("notes.json"), "{}").unwrap();
+
+        let plugin = MacrosPlugin::new(api.clone());
+        assert_eq!(plugin.macros().unwrap().len(), 3);
+        let found = plugin.search("standup").unwrap();
+        assert_eq!(found[0].0.name, "Start standup");
+        assert_eq!(found[0].0.steps[2].on_error, OnError::Continue);
+        let results = plugin
+            .match_query(&QueryContext::new("macro greet"))
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].subtitle.as_deref(), Some("4 steps"));
+
+        // The dry run flags steps that can't run, and runs nothing
+        let preview = plugin.preview(&results[0]).await.unwrap();
+        let Preview::Markdown { markdown } = preview else {
+            panic!("expected a markdown preview");
+        };
+        assert!(markdown.contains("1. Type \"Hello\"\n   ⚠️ Host feature"));
+        assert!(
+            markdown.contains(
+                "2. Open file /does/not/exist *(continues on error)*\n   ⚠️ File not found"
+            )
+        );
+        assert!(markdown.contains("3. Wait 10 ms\n"));
+
+        let typed = Arc::new(Mutex::new(Vec::new()));
+        let sink = typed.clone();
+        api.set_input_synthesizer(Arc::new(move |insertion: &TextInsertion| {
+            sink.lock().unwrap().push(insertion.text.clone());
+            Ok(())
+        }));
+        api.set_feature_available(HostFeature::InputSynthesis, true)
+            .unwrap();
+
+        let outcome = plugin.execute(&results[0]).await.unwrap();
+        assert!(!outcome.is_error());
+        assert!(
+            outcome
+                .message
+                .unwrap()
+                .text
+                .contains("1 of 4 steps failed")
+        );
+        assert_eq!(*typed.lock().unwrap(), vec!["Hello", "there"]);
+
+        let broken = plugin.find("Broken").unwrap().unwrap();
+        let report = plugin.run(&broken.steps).await;
+        assert_eq!(report.completed, 1);
+        assert!(report.stopped);
+        assert_eq!(report.failures[0].index, 1);
+        assert_eq!(*typed.lock().unwrap(), vec!["Hello", "there", "one"]);
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
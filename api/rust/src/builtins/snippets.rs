@@ -0,0 +1,412 @@
+/// Text snippets
+///
+/// Users save snippets under a short trigger (`;sig`, `;addr`) and a name.
+/// Typing a trigger offers its snippet; `snip <search>` fuzzy-searches
+/// triggers and names. Executing a snippet expands its placeholders and
+/// types the text into the application the user was in, or copies it when
+/// the host can't synthesize input.
+///
+/// Placeholders:
+/// * `{date}` - Today's date, `YYYY-MM-DD`
+/// * `{time}` - The current time, `HH:MM`
+/// * `{clipboard}` - The clipboard's text
+/// * `{cursor}` - Where the caret is left after insertion
+///
+/// `{{` and `}}` insert literal braces; unknown placeholders are kept as-is.
+use crate::actions::now_millis;
+use crate::api::VoltPluginAPI;
+use crate::input::TextInsertion;
+pub use crate::matching::fuzzy_score;
+use crate::matching::fuzzy_score_with;
+use crate::outcome::{ExecuteOutcome, Toast};
+use crate::plugin::{Plugin, QueryContext};
+use crate::result::PluginResult;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// Identifier of the snippets plugin
+pub const PLUGIN_ID: &str = "snippets";
+
+/// Prefix of the key-value store keys holding snippets
+pub const KEY_PREFIX: &str = "snippet:";
+
+/// Keyword listing and searching snippets
+pub const KEYWORD: &str = "snip";
+
+/// Most results returned for a search
+const MAX_RESULTS: usize = 20;
+
+/// A saved snippet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snippet {
+    /// Text typed to insert the snippet, e.g. `;sig`
+    pub trigger: String,
+    /// Name shown in results
+    pub name: String,
+    /// Text with placeholders
+    pub body: String,
+}
+
+impl Snippet {
+    /// Create a snippet
+    pub fn new(trigger: impl Into<String>, name: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            trigger: trigger.into(),
+            name: name.into(),
+            body: body.into(),
+        }
+    }
+
+    /// Check if the body uses a placeholder
+    pub fn uses(&self, placeholder: &str) -> bool {
+        self.body.contains(&format!("{{{}}}", placeholder))
+    }
+}
+
+/// Values placeholders expand to
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Expansion {
+    /// Current time, in milliseconds since the Unix epoch
+    pub now: u64,
+    /// Offset of the user's time zone
+    pub utc_offset_minutes: i32,
+    /// The clipboard's text, if available
+    pub clipboard: Option<String>,
+}
+
+/// Expand the placeholders of a snippet body
+///
+/// # Returns
+/// The text to insert and where to leave the caret
+pub fn expand(body: &str, expansion: &Expansion) -> TextInsertion {
+    let (date, time) = local_date_time(expansion.now, expansion.utc_offset_minutes);
+    let mut insertion = TextInsertion::new(String::with_capacity(body.len()));
+    let mut rest = body;
+
+    while let Some(start) = rest.find(['{', '}']) {
+        insertion.text.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        for escape in ["{{", "}}"] {
+            if let Some(after) = rest.strip_prefix(escape) {
+                insertion.text.push_str(&escape[..1]);
+                rest = after;
+            }
+        }
+        if !rest.starts_with('{') {
+            if let Some(after) = rest.strip_prefix('}') {
+                insertion.text.push('}');
+                rest = after;
+            }
+            continue;
+        }
+
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+        match &rest[1..end] {
+            "date" => insertion.text.push_str(&date),
+            "time" => insertion.text.push_str(&time),
+            "clipboard" => insertion.text.push_str(expansion.clipboard.as_deref().unwrap_or_default()),
+            "cursor" if insertion.cursor.is_none() => insertion.cursor = Some(insertion.text.chars().count()),
+            "cursor" => {}
+            _ => insertion.text.push_str(&rest[..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    insertion.text.push_str(rest);
+
+    insertion
+}
+
+/// Local `YYYY-MM-DD` date and `HH:MM` time
+pub(crate) fn local_date_time(now: u64, utc_offset_minutes: i32) -> (String, String) {
+    let local_minutes = (now / 60_000) as i64 + i64::from(utc_offset_minutes);
+    let (days, minute_of_day) = (local_minutes.div_euclid(1440), local_minutes.rem_euclid(1440));
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (
+        format!("{:04}-{:02}-{:02}", year, month, day),
+        format!("{:02}:{:02}", minute_of_day / 60, minute_of_day % 60),
+    )
+}
+
+/// Built-in plugin inserting user-defined snippets
+pub struct SnippetsPlugin {
+    api: VoltPluginAPI,
+    utc_offset_minutes: i32,
+}
+
+impl SnippetsPlugin {
+    /// Create the plugin
+    pub fn new(api: VoltPluginAPI) -> Self {
+        Self {
+            api,
+            utc_offset_minutes: 0,
+        }
+    }
+
+    /// Set the offset of the user's time zone, used by `{date}` and `{time}`
+    pub fn with_utc_offset(mut self, minutes: i32) -> Self {
+        self.utc_offset_minutes = minutes;
+        self
+    }
+
+    /// Get all snippets, sorted by trigger
+    pub fn snippets(&self) -> Result<Vec<Snippet>, String> {
+        let mut snippets = Vec::new();
+        for key in self.api.kv_keys(PLUGIN_ID, KEY_PREFIX)? {
+            if let Some(value) = self.api.kv_get(PLUGIN_ID, &key)? {
+                snippets.push(
+                    serde_json::from_value(value).map_err(|e| format!("Failed to parse snippet '{}': {}", key, e))?,
+                );
+            }
+        }
+        Ok(snippets)
+    }
+
+    /// Get a snippet by trigger
+    pub fn snippet(&self, trigger: &str) -> Result<Option<Snippet>, String> {
+        match self.api.kv_get(PLUGIN_ID, &format!("{}{}", KEY_PREFIX, trigger))? {
+            Some(value) => serde_json::from_value(value)
+                .map(Some)
+                .map_err(|e| format!("Failed to parse snippet '{}': {}", trigger, e)),
+            None => Ok(None),
+        }
+    }
+
+    /// Save a snippet, replacing the one with the same trigger
+    pub fn save(&self, snippet: &Snippet) -> Result<(), String> {
+        if snippet.trigger.trim().is_empty() || snippet.trigger.chars().any(char::is_whitespace) {
+            return Err("Snippet trigger cannot be empty or contain spaces".to_string());
+        }
+
+        let value = serde_json::to_value(snippet).map_err(|e| format!("Failed to serialize snippet: {}", e))?;
+        self.api
+            .kv_set(PLUGIN_ID, &format!("{}{}", KEY_PREFIX, snippet.trigger), value)
+    }
+
+    /// Delete a snippet
+    pub fn remove(&self, trigger: &str) -> Result<(), String> {
+        self.api
+            .kv_remove(PLUGIN_ID, &format!("{}{}", KEY_PREFIX, trigger))?
+            .map(|_| ())
+            .ok_or_else(|| format!("Snippet '{}' not found", trigger))
+    }
+
+    /// Find snippets for a query, best first
+    ///
+    /// `snip <search>` fuzzy-matches triggers and names; any other query
+    /// matches the snippets whose trigger it is a prefix of.
+    pub fn search(&self, query: &str) -> Result<Vec<(Snippet, u32)>, String> {
+        let query = query.trim();
+        let search = match query.split_once(char::is_whitespace) {
+            Some((keyword, search)) if keyword.eq_ignore_ascii_case(KEYWORD) => Some(search.trim()),
+            None if query.eq_ignore_ascii_case(KEYWORD) => Some(""),
+            _ => None,
+        };
+
+        let tokenizer = self.api.tokenizer();
+        let mut matches: Vec<(Snippet, u32)> = self
+            .snippets()?
+            .into_iter()
+            .filter_map(|snippet| {
+                let score = match search {
+                    Some(search) => fuzzy_score_with(&tokenizer, search, &snippet.trigger)
+                        .max(fuzzy_score_with(&tokenizer, search, &snippet.name))?,
+                    None if !query.is_empty() && snippet.trigger.starts_with(query) => {
+                        100 + (query.len() * 10 / snippet.trigger.len()) as u32
+                    }
+                    None => return None,
+                };
+                Some((snippet, score))
+            })
+            .collect();
+
+        matches.sort_by(|(a, a_score), (b, b_score)| b_score.cmp(a_score).then_with(|| a.trigger.cmp(&b.trigger)));
+        matches.truncate(MAX_RESULTS);
+        Ok(matches)
+    }
+
+    /// Expand a snippet with the current date, time and clipboard
+    pub fn expand(&self, snippet: &Snippet) -> TextInsertion {
+        let clipboard = if snippet.uses("clipboard") {
+            self.api.read_clipboard().ok()
+        } else {
+            None
+        };
+
+        expand(
+            &snippet.body,
+            &Expansion {
+                now: now_millis(),
+                utc_offset_minutes: self.utc_offset_minutes,
+                clipboard,
+            },
+        )
+    }
+}
+
+#[async_trait]
+impl Plugin for SnippetsPlugin {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn id(&self) -> &str {
+        PLUGIN_ID
+    }
+
+    fn name(&self) -> &str {
+        self.api.messages().text("snippets.name")
+    }
+
+    fn description(&self) -> &str {
+        self.api.messages().text("snippets.description")
+    }
+
+    fn can_handle(&self, context: &QueryContext) -> bool {
+        !context.query.trim().is_empty()
+    }
+
+    async fn match_query(&self, context: &QueryContext) -> Result<Vec<PluginResult>, String> {
+        let messages = self.api.messages();
+        Ok(self
+            .search(&context.query)?
+            .into_iter()
+            .map(|(snippet, score)| {
+                let preview: String = snippet.body.lines().next().unwrap_or_default().chars().take(80).collect();
+                let mut result = PluginResult::new(format!("snippet-{}", snippet.trigger), snippet.name.clone())
+                    .with_meta("trigger", snippet.trigger.clone());
+                result.subtitle = Some(format!("{} · {}", snippet.trigger, preview).into());
+                result.badge = Some(messages.text("snippets.badge").to_string());
+                result.score = score;
+                result
+            })
+            .collect())
+    }
+
+    async fn execute(&self, result: &PluginResult) -> Result<ExecuteOutcome, String> {
+        let trigger = result.meta_str("trigger").ok_or("Missing snippet trigger")?;
+        let snippet = self
+            .snippet(trigger)?
+            .ok_or_else(|| format!("Snippet '{}' not found", trigger))?;
+
+        let insertion = self.expand(&snippet);
+        if self.api.insert_text(PLUGIN_ID, &insertion).is_ok() {
+            return Ok(ExecuteOutcome::close());
+        }
+
+        // Copy instead when the host can't type into other applications
+        self.api.write_clipboard(&insertion.text)?;
+        Ok(ExecuteOutcome::close().with_message(Toast::success(self.api.messages().text("snippets.copied"))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::HostFeature;
+    use crate::input::ClipboardBackend;
+    use std::sync::{Arc, Mutex};
+
+    /// 2024-02-29 23:30 UTC
+    const LEAP_NIGHT: u64 = 1_709_249_400_000;
+
+    #[derive(Default)]
+    struct Clipboard(Mutex<String>);
+
+    impl ClipboardBackend for Clipboard {
+        fn read_text(&self) -> Result<String, String> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+
+        fn write_text(&self, text: &str) -> Result<(), String> {
+            *self.0.lock().unwrap() = text.to_string();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_placeholders_expand() {
+        let expansion = Expansion {
+            now: LEAP_NIGHT,
+            utc_offset_minutes: 60,
+            clipboard: Some("https://volt.app".to_string()),
+        };
+
+        // One hour ahead of UTC it is already March
+        let insertion = expand("{date} {time}: see {clipboard} {unknown} {{braces}}", &expansion);
+        assert_eq!(insertion.text, "2024-03-01 00:30: see https://volt.app {unknown} {braces}");
+        assert_eq!(insertion.cursor, None);
+
+        let insertion = expand("<b>{cursor}</b>", &expansion);
+        assert_eq!(insertion.text, "<b></b>");
+        assert_eq!(insertion.caret_moves_left(), 4);
+        assert_eq!(expand("{time", &expansion).text, "{time");
+    }
+
+    #[test]
+    fn test_fuzzy_scores() {
+        assert!(fuzzy_score("sig", "Email signature").is_some());
+        assert!(fuzzy_score("es", "Email signature") > fuzzy_score("es", "addresses"));
+        assert!(fuzzy_score("xyz", "Email signature").is_none());
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_snippets_are_searched_and_inserted() {
+        let temp_dir = std::env::temp_dir().join("volt_test_snippets_plugin");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let api = VoltPluginAPI::new(temp_dir.clone());
+        api.set_feature_available(HostFeature::Clipboard, true).unwrap();
+        let clipboard = Arc::new(Clipboard::default());
+        api.set_clipboard_backend(clipboard.clone());
+
+        let plugin = SnippetsPlugin::new(api.clone());
+        plugin.save(&Snippet::new(";sig", "Email signature", "Regards,\nAda")).unwrap();
+        plugin.save(&Snippet::new(";link", "Link", "<a href=\"{clipboard}\">{cursor}</a>")).unwrap();
+        assert!(plugin.save(&Snippet::new("my sig", "Bad", "")).is_err());
+
+        let results = plugin.match_query(&QueryContext::new(";si")).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "Email signature");
+        assert_eq!(plugin.search("snip").unwrap().len(), 2);
+        assert_eq!(plugin.search("snip email").unwrap()[0].0.trigger, ";sig");
+
+        // Without input synthesis the expanded text is copied
+        clipboard.write_text("https://volt.app").unwrap();
+        let result = PluginResult::new("snippet-;link", "Link").with_meta("trigger", ";link");
+        plugin.execute(&result).await.unwrap();
+        assert_eq!(clipboard.read_text().unwrap(), "<a href=\"https://volt.app\"></a>");
+
+        let typed = Arc::new(Mutex::new(None));
+        let sink = typed.clone();
+        api.set_feature_available(HostFeature::InputSynthesis, true).unwrap();
+        api.set_input_synthesizer(Arc::new(move |insertion: &TextInsertion| {
+            *sink.lock().unwrap() = Some(insertion.clone());
+            Ok(())
+        }));
+        clipboard.write_text("https://volt.app").unwrap();
+        plugin.execute(&result).await.unwrap();
+        assert_eq!(typed.lock().unwrap().as_ref().unwrap().caret_moves_left(), 4);
+
+        plugin.remove(";link").unwrap();
+        assert!(plugin.remove(";link").is_err());
+        assert_eq!(plugin.snippets().unwrap().len(), 1);
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
@@ -0,0 +1,776 @@
+/// System settings and controls
+///
+/// Opens the panes of the OS settings app and runs common controls: Wi-Fi,
+/// Bluetooth, dark mode, volume, lock, sleep and emptying the trash. Typing
+/// a command's name or a word of it ("wifi", "dark", "trash") finds it, and
+/// `sys` lists every command. Sleeping and emptying the trash ask for
+/// confirmation: executing them shows a confirmation result first.
+///
+/// Commands are run through `SystemControls`. The default
+/// `PlatformControls` starts the platform's own tools directly, never
+/// through a shell, and only offers the commands whose tools are installed;
+/// e.g. Bluetooth needs `blueutil` on macOS, and Windows has no volume
+/// controls. Hosts can replace it to restrict commands or run them through
+/// native APIs.
+use crate::api::VoltPluginAPI;
+use crate::outcome::ExecuteOutcome;
+use crate::platform::Platform;
+use crate::plugin::{Plugin, QueryContext};
+use crate::preflight;
+use crate::result::{CommandSpec, PluginResult};
+use async_trait::async_trait;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, OnceLock};
+
+/// Identifier of the system plugin
+pub const PLUGIN_ID: &str = "system";
+
+/// Keyword listing every command
+pub const KEYWORD: &str = "sys";
+
+/// Prefix of the query confirming a destructive command, followed by its ID
+pub const CONFIRM_PREFIX: &str = "confirm:";
+
+/// A system setting pane or control
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SystemCommand {
+    DisplaySettings,
+    SoundSettings,
+    NetworkSettings,
+    BluetoothSettings,
+    NotificationSettings,
+    PrivacySettings,
+    KeyboardSettings,
+    PowerSettings,
+    ToggleWifi,
+    ToggleBluetooth,
+    ToggleDarkMode,
+    VolumeUp,
+    VolumeDown,
+    ToggleMute,
+    Lock,
+    Sleep,
+    EmptyTrash,
+}
+
+impl SystemCommand {
+    /// All commands, in the order they are listed
+    pub const ALL: [SystemCommand; 17] = [
+        SystemCommand::ToggleWifi,
+        SystemCommand::ToggleBluetooth,
+        SystemCommand::ToggleDarkMode,
+        SystemCommand::VolumeUp,
+        SystemCommand::VolumeDown,
+        SystemCommand::ToggleMute,
+        SystemCommand::Lock,
+        SystemCommand::Sleep,
+        SystemCommand::EmptyTrash,
+        SystemCommand::DisplaySettings,
+        SystemCommand::SoundSettings,
+        SystemCommand::NetworkSettings,
+        SystemCommand::BluetoothSettings,
+        SystemCommand::NotificationSettings,
+        SystemCommand::PrivacySettings,
+        SystemCommand::KeyboardSettings,
+        SystemCommand::PowerSettings,
+    ];
+
+    /// Get the identifier of the command, stored in its results
+    pub fn id(&self) -> &'static str {
+        match self {
+            SystemCommand::DisplaySettings => "display-settings",
+            SystemCommand::SoundSettings => "sound-settings",
+            SystemCommand::NetworkSettings => "network-settings",
+            SystemCommand::BluetoothSettings => "bluetooth-settings",
+            SystemCommand::NotificationSettings => "notification-settings",
+            SystemCommand::PrivacySettings => "privacy-settings",
+            SystemCommand::KeyboardSettings => "keyboard-settings",
+            SystemCommand::PowerSettings => "power-settings",
+            SystemCommand::ToggleWifi => "toggle-wifi",
+            SystemCommand::ToggleBluetooth => "toggle-bluetooth",
+            SystemCommand::ToggleDarkMode => "toggle-dark-mode",
+            SystemCommand::VolumeUp => "volume-up",
+            SystemCommand::VolumeDown => "volume-down",
+            SystemCommand::ToggleMute => "toggle-mute",
+            SystemCommand::Lock => "lock",
+            SystemCommand::Sleep => "sleep",
+            SystemCommand::EmptyTrash => "empty-trash",
+        }
+    }
+
+    /// Find a command by its identifier
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|command| command.id() == id)
+    }
+
+    /// Check if the command opens a settings pane
+    pub fn is_settings_pane(&self) -> bool {
+        self.settings_pane().is_some()
+    }
+
+    /// Check if the command needs confirmation before it runs
+    pub fn is_destructive(&self) -> bool {
+        matches!(self, SystemCommand::Sleep | SystemCommand::EmptyTrash)
+    }
+
+    /// Get how the command runs on a platform
+    ///
+    /// # Returns
+    /// None if the platform has no tool for the command
+    pub fn invocation(&self, platform: Platform) -> Option<Invocation> {
+        if let Some(pane) = self.settings_pane() {
+            let [macos, windows, linux] = pane;
+            return Some(Invocation::Run(match platform {
+                Platform::Macos => {
+                    CommandSpec::new("open").arg(format!("x-apple.systempreferences:{}", macos))
+                }
+                Platform::Windows => {
+                    CommandSpec::new("explorer.exe").arg(format!("ms-settings:{}", windows))
+                }
+                Platform::Linux => CommandSpec::new("gnome-control-center").arg(linux),
+            }));
+        }
+
+        let osascript = |script: &str| CommandSpec::new("osascript").arg("-e").arg(script);
+        let command = |args: &[&str]| {
+            args[1..]
+                .iter()
+                .fold(CommandSpec::new(args[0]), |spec, arg| spec.arg(*arg))
+        };
+        let toggle = |probe: &[&str], on_marker: &'static str, on: &[&str], off: &[&str]| {
+            Invocation::Toggle {
+                probe: command(probe),
+                on_marker,
+                on: command(on),
+                off: command(off),
+            }
+        };
+        const PERSONALIZE: &str =
+            r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize";
+        const COLOR_SCHEME: [&str; 3] =
+            ["gsettings", "org.gnome.desktop.interface", "color-scheme"];
+
+        Some(match (self, platform) {
+            (SystemCommand::ToggleWifi, Platform::Macos) => toggle(
+                &["networksetup", "-getairportpower", "en0"],
+                ": On",
+                &["networksetup", "-setairportpower", "en0", "on"],
+                &["networksetup", "-setairportpower", "en0", "off"],
+            ),
+            (SystemCommand::ToggleWifi, Platform::Linux) => toggle(
+                &["nmcli", "radio", "wifi"],
+                "enabled",
+                &["nmcli", "radio", "wifi", "on"],
+                &["nmcli", "radio", "wifi", "off"],
+            ),
+            (SystemCommand::ToggleBluetooth, Platform::Macos) => toggle(
+                &["blueutil", "--power"],
+                "1",
+                &["blueutil", "--power", "1"],
+                &["blueutil", "--power", "0"],
+            ),
+            (SystemCommand::ToggleBluetooth, Platform::Linux) => toggle(
+                &["bluetoothctl", "show"],
+                "Powered: yes",
+                &["bluetoothctl", "power", "on"],
+                &["bluetoothctl", "power", "off"],
+            ),
+            (SystemCommand::ToggleDarkMode, Platform::Macos) => Invocation::Run(osascript(
+                "tell application \"System Events\" to tell appearance preferences \
+                 to set dark mode to not dark mode",
+            )),
+            (SystemCommand::ToggleDarkMode, Platform::Windows) => toggle(
+                &["reg", "query", PERSONALIZE, "/v", "AppsUseLightTheme"],
+                "0x0",
+                &[
+                    "reg",
+                    "add",
+                    PERSONALIZE,
+                    "/v",
+                    "AppsUseLightTheme",
+                    "/t",
+                    "REG_DWORD",
+                    "/d",
+                    "0",
+                    "/f",
+                ],
+                &[
+                    "reg",
+                    "add",
+                    PERSONALIZE,
+                    "/v",
+                    "AppsUseLightTheme",
+                    "/t",
+                    "REG_DWORD",
+                    "/d",
+                    "1",
+                    "/f",
+                ],
+            ),
+            (SystemCommand::ToggleDarkMode, Platform::Linux) => {
+                let [program, schema, key] = COLOR_SCHEME;
+                toggle(
+                    &[program, "get", schema, key],
+                    "prefer-dark",
+                    &[program, "set", schema, key, "prefer-dark"],
+                    &[program, "set", schema, key, "default"],
+                )
+            }
+            (SystemCommand::VolumeUp, Platform::Macos) => Invocation::Run(osascript(
+                "set volume output volume (output volume of (get volume settings) + 10)",
+            )),
+            (SystemCommand::VolumeUp, Platform::Linux) => Invocation::Run(command(&[
+                "wpctl",
+                "set-volume",
+                "-l",
+                "1.0",
+                "@DEFAULT_AUDIO_SINK@",
+                "10%+",
+            ])),
+            (SystemCommand::VolumeDown, Platform::Macos) => Invocation::Run(osascript(
+                "set volume output volume (output volume of (get volume settings) - 10)",
+            )),
+            (SystemCommand::VolumeDown, Platform::Linux) => Invocation::Run(command(&[
+                "wpctl",
+                "set-volume",
+                "@DEFAULT_AUDIO_SINK@",
+                "10%-",
+            ])),
+            (SystemCommand::ToggleMute, Platform::Macos) => Invocation::Run(osascript(
+                "set volume output muted not (output muted of (get volume settings))",
+            )),
+            (SystemCommand::ToggleMute, Platform::Linux) => Invocation::Run(command(&[
+                "wpctl",
+                "set-mute",
+                "@DEFAULT_AUDIO_SINK@",
+                "toggle",
+            ])),
+            (SystemCommand::Lock, Platform::Macos) => {
+                Invocation::Run(command(&["pmset", "displaysleepnow"]))
+            }
+            (SystemCommand::Lock, Platform::Windows) => {
+                Invocation::Run(command(&["rundll32.exe", "user32.dll,LockWorkStation"]))
+            }
+            (SystemCommand::Lock, Platform::Linux) => {
+                Invocation::Run(command(&["loginctl", "lock-session"]))
+            }
+            (SystemCommand::Sleep, Platform::Macos) => {
+                Invocation::Run(command(&["pmset", "sleepnow"]))
+            }
+            (SystemCommand::Sleep, Platform::Windows) => Invocation::Run(command(&[
+                "rundll32.exe",
+                "powrprof.dll,SetSuspendState",
+                "0,1,0",
+            ])),
+            (SystemCommand::Sleep, Platform::Linux) => {
+                Invocation::Run(command(&["systemctl", "suspend"]))
+            }
+            (SystemCommand::EmptyTrash, Platform::Macos) => {
+                Invocation::Run(osascript("tell application \"Finder\" to empty trash"))
+            }
+            (SystemCommand::EmptyTrash, Platform::Windows) => Invocation::Run(command(&[
+                "powershell",
+                "-NoProfile",
+                "-Command",
+                "Clear-RecycleBin -Force",
+            ])),
+            (SystemCommand::EmptyTrash, Platform::Linux) => {
+                Invocation::Run(command(&["gio", "trash", "--empty"]))
+            }
+            _ => return None,
+        })
+    }
+
+    /// Get the pane of each platform's settings app, as macOS, Windows and
+    /// GNOME name it
+    fn settings_pane(&self) -> Option<[&'static str; 3]> {
+        Some(match self {
+            SystemCommand::DisplaySettings => {
+                ["com.apple.preference.displays", "display", "display"]
+            }
+            SystemCommand::SoundSettings => ["com.apple.preference.sound", "sound", "sound"],
+            SystemCommand::NetworkSettings => {
+                ["com.apple.preference.network", "network", "network"]
+            }
+            SystemCommand::BluetoothSettings => {
+                ["com.apple.preferences.Bluetooth", "bluetooth", "bluetooth"]
+            }
+            SystemCommand::NotificationSettings => [
+                "com.apple.preference.notifications",
+                "notifications",
+                "notifications",
+            ],
+            SystemCommand::PrivacySettings => [
+                "com.apple.preference.security?Privacy",
+                "privacy",
+                "privacy",
+            ],
+            SystemCommand::KeyboardSettings => {
+                ["com.apple.preference.keyboard", "keyboard", "keyboard"]
+            }
+            SystemCommand::PowerSettings => ["com.apple.preference.battery", "powersleep", "power"],
+            _ => return None,
+        })
+    }
+
+    /// Get the key of the command's title in the message catalogs
+    fn title_key(&self) -> &'static str {
+        match self {
+            SystemCommand::DisplaySettings => "system.display_settings",
+            SystemCommand::SoundSettings => "system.sound_settings",
+            SystemCommand::NetworkSettings => "system.network_settings",
+            SystemCommand::BluetoothSettings => "system.bluetooth_settings",
+            SystemCommand::NotificationSettings => "system.notification_settings",
+            SystemCommand::PrivacySettings => "system.privacy_settings",
+            SystemCommand::KeyboardSettings => "system.keyboard_settings",
+            SystemCommand::PowerSettings => "system.power_settings",
+            SystemCommand::ToggleWifi => "system.toggle_wifi",
+            SystemCommand::ToggleBluetooth => "system.toggle_bluetooth",
+            SystemCommand::ToggleDarkMode => "system.toggle_dark_mode",
+            SystemCommand::VolumeUp => "system.volume_up",
+            SystemCommand::VolumeDown => "system.volume_down",
+            SystemCommand::ToggleMute => "system.toggle_mute",
+            SystemCommand::Lock => "system.lock",
+            SystemCommand::Sleep => "system.sleep",
+            SystemCommand::EmptyTrash => "system.empty_trash",
+        }
+    }
+
+    /// Get English words the command is found by, besides its title
+    fn keywords(&self) -> &'static [&'static str] {
+        match self {
+            SystemCommand::DisplaySettings => &["screen", "monitor", "resolution"],
+            SystemCommand::SoundSettings => &["audio", "speakers", "microphone"],
+            SystemCommand::NetworkSettings => &["ethernet", "vpn", "proxy"],
+            SystemCommand::BluetoothSettings => &["devices", "pairing"],
+            SystemCommand::NotificationSettings => &["alerts", "focus"],
+            SystemCommand::PrivacySettings => &["security", "permissions"],
+            SystemCommand::KeyboardSettings => &["shortcuts", "layout", "input"],
+            SystemCommand::PowerSettings => &["battery", "energy"],
+            SystemCommand::ToggleWifi => &["wifi", "wireless", "wlan"],
+            SystemCommand::ToggleBluetooth => &["bt"],
+            SystemCommand::ToggleDarkMode => &["appearance", "theme", "light"],
+            SystemCommand::VolumeUp => &["louder", "sound"],
+            SystemCommand::VolumeDown => &["quieter", "sound"],
+            SystemCommand::ToggleMute => &["unmute", "silence", "sound"],
+            SystemCommand::Lock => &["screensaver"],
+            SystemCommand::Sleep => &["suspend", "standby"],
+            SystemCommand::EmptyTrash => &["recycle", "bin"],
+        }
+    }
+
+    /// Get the icon of the command's results
+    fn icon(&self) -> &'static str {
+        match self {
+            SystemCommand::ToggleWifi | SystemCommand::NetworkSettings => "📶",
+            SystemCommand::ToggleBluetooth | SystemCommand::BluetoothSettings => "🔵",
+            SystemCommand::ToggleDarkMode => "🌓",
+            SystemCommand::VolumeUp => "🔊",
+            SystemCommand::VolumeDown => "🔉",
+            SystemCommand::ToggleMute => "🔇",
+            SystemCommand::Lock => "🔒",
+            SystemCommand::Sleep => "💤",
+            SystemCommand::EmptyTrash => "🗑️",
+            _ => "⚙️",
+        }
+    }
+}
+
+/// Programs a command runs on a platform
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Invocation {
+    /// Start a program
+    Run(CommandSpec),
+    /// Flip a setting: run `off` if the output of `probe` contains
+    /// `on_marker`, `on` otherwise
+    Toggle {
+        probe: CommandSpec,
+        on_marker: &'static str,
+        on: CommandSpec,
+        off: CommandSpec,
+    },
+}
+
+impl Invocation {
+    /// Get the programs the invocation may start
+    pub fn programs(&self) -> Vec<&str> {
+        match self {
+            Invocation::Run(spec) => vec![spec.program.as_str()],
+            Invocation::Toggle { probe, on, off, .. } => {
+                vec![
+                    probe.program.as_str(),
+                    on.program.as_str(),
+                    off.program.as_str(),
+                ]
+            }
+        }
+    }
+
+    /// Run the invocation
+    pub fn run(&self) -> Result<(), String> {
+        let spec = match self {
+            Invocation::Run(spec) => spec,
+            Invocation::Toggle {
+                probe,
+                on_marker,
+                on,
+                off,
+            } => {
+                let output = Command::new(&probe.program)
+                    .args(&probe.args)
+                    .stdin(Stdio::null())
+                    .output()
+                    .map_err(|e| format!("Failed to run {}: {}", probe.program, e))?;
+                if !output.status.success() {
+                    return Err(format!(
+                        "{} failed: {}",
+                        probe.program,
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    ));
+                }
+                if String::from_utf8_lossy(&output.stdout).contains(on_marker) {
+                    off
+                } else {
+                    on
+                }
+            }
+        };
+
+        Command::new(&spec.program)
+            .args(&spec.args)
+            .stdin(Stdio::null())
+            .spawn()
+            .map(|_| ())
+            .map_err(|e| format!("Failed to start {}: {}", spec.program, e))
+    }
+}
+
+/// Runs system commands, implemented per platform
+pub trait SystemControls: Send + Sync {
+    /// Check if a command can run here
+    fn supports(&self, command: SystemCommand) -> bool;
+
+    /// Run a command
+    fn run(&self, command: SystemCommand) -> Result<(), String>;
+}
+
+/// Controls starting the tools of the current platform
+#[derive(Debug, Default)]
+pub struct PlatformControls {
+    /// Commands whose tools are installed, found on first use
+    supported: OnceLock<Vec<SystemCommand>>,
+}
+
+impl PlatformControls {
+    /// Create controls for the current platform
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SystemControls for PlatformControls {
+    fn supports(&self, command: SystemCommand) -> bool {
+        self.supported
+            .get_or_init(|| {
+                let Some(platform) = Platform::current() else {
+                    return Vec::new();
+                };
+                let search_path = std::env::var_os("PATH").unwrap_or_default();
+                SystemCommand::ALL
+                    .into_iter()
+                    .filter(|command| {
+                        command.invocation(platform).is_some_and(|invocation| {
+                            invocation.programs().iter().all(|program| {
+                                preflight::find_program(program, &search_path).is_some()
+                            })
+                        })
+                    })
+                    .collect()
+            })
+            .contains(&command)
+    }
+
+    fn run(&self, command: SystemCommand) -> Result<(), String> {
+        Platform::current()
+            .and_then(|platform| command.invocation(platform))
+            .ok_or_else(|| format!("'{}' is not available on this platform", command.id()))?
+            .run()
+    }
+}
+
+/// Built-in plugin opening system settings and running system controls
+pub struct SystemPlugin {
+    api: VoltPluginAPI,
+    controls: Arc<dyn SystemControls>,
+}
+
+impl SystemPlugin {
+    /// Create the plugin, running commands with the platform's tools
+    pub fn new(api: VoltPluginAPI) -> Self {
+        Self {
+            api,
+            controls: Arc::new(PlatformControls::new()),
+        }
+    }
+
+    /// Run commands through other controls
+    pub fn with_controls(mut self, controls: Arc<dyn SystemControls>) -> Self {
+        self.controls = controls;
+        self
+    }
+
+    /// Find the supported commands matching a query, best first
+    ///
+    /// `sys` alone lists every command. Otherwise a command matches when a
+    /// word of its title, or one of its keywords, starts with each word of
+    /// the query.
+    pub fn search(&self, query: &str) -> Vec<(SystemCommand, u32)> {
+        let query = query.trim().to_lowercase();
+        let search = match query.split_once(char::is_whitespace) {
+            Some((keyword, search)) if keyword == KEYWORD => search.trim(),
+            None if query == KEYWORD => "",
+            _ => query.as_str(),
+        };
+        let messages = self.api.messages();
+
+        let mut matches: Vec<(SystemCommand, u32)> = SystemCommand::ALL
+            .into_iter()
+            .enumerate()
+            .filter(|(_, command)| self.controls.supports(*command))
+            .filter_map(|(index, command)| {
+                let title = messages.text(command.title_key()).to_lowercase();
+                let words: Vec<&str> = title
+                    .split(|c: char| !c.is_alphanumeric())
+                    .chain(command.keywords().iter().copied())
+                    .filter(|word| !word.is_empty())
+                    .collect();
+                let matched = search.split_whitespace().all(|part| {
+                    words.iter().any(|word| word.starts_with(part)) || title.contains(part)
+                });
+                let exact = title == search;
+                matched.then(|| (command, 60 + 30 * exact as u32 - index as u32))
+            })
+            .collect();
+
+        matches.sort_by(|(_, a), (_, b)| b.cmp(a));
+        matches
+    }
+
+    /// Run the command of a result, asking for confirmation first if it is
+    /// destructive
+    pub fn perform(&self, result: &PluginResult) -> Result<ExecuteOutcome, String> {
+        let command = result
+            .meta_str("command")
+            .and_then(SystemCommand::from_id)
+            .ok_or("Missing system command")?;
+
+        if command.is_destructive() && result.meta_str("confirmed") != Some("true") {
+            return Ok(ExecuteOutcome::requery(format!(
+                "{} {}{}",
+                KEYWORD,
+                CONFIRM_PREFIX,
+                command.id()
+            )));
+        }
+        self.controls.run(command)?;
+        Ok(ExecuteOutcome::close())
+    }
+
+    /// Result running a command
+    fn command_result(&self, command: SystemCommand, score: u32) -> PluginResult {
+        let messages = self.api.messages();
+        let mut result = PluginResult::new(
+            format!("system-{}", command.id()),
+            messages.text(command.title_key()),
+        )
+        .with_meta("command", command.id());
+        result.subtitle = Some(
+            messages
+                .text(if command.is_settings_pane() {
+                    "system.settings"
+                } else {
+                    "system.control"
+                })
+                .into(),
+        );
+        result.icon = Some(command.icon().into());
+        result.score = score;
+        result
+    }
+
+    /// Result confirming a destructive command
+    fn confirmation_result(&self, command: SystemCommand) -> PluginResult {
+        let messages = self.api.messages();
+        let mut result = self
+            .command_result(command, 100)
+            .with_meta("confirmed", "true");
+        result.id = format!("system-confirm-{}", command.id());
+        result.title = messages
+            .format(
+                "system.confirm_title",
+                &[("command", messages.text(command.title_key()))],
+            )
+            .into();
+        result.subtitle = Some(messages.text("system.confirm").into());
+        result
+    }
+}
+
+/// Get the command a query confirms, if it is a confirmation query
+fn confirmed_command(query: &str) -> Option<SystemCommand> {
+    let (keyword, rest) = query.trim().split_once(char::is_whitespace)?;
+    if !keyword.eq_ignore_ascii_case(KEYWORD) {
+        return None;
+    }
+    SystemCommand::from_id(rest.trim().strip_prefix(CONFIRM_PREFIX)?)
+        .filter(SystemCommand::is_destructive)
+}
+
+#[async_trait]
+impl Plugin for SystemPlugin {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn id(&self) -> &str {
+        PLUGIN_ID
+    }
+
+    fn name(&self) -> &str {
+        self.api.messages().text("system.name")
+    }
+
+    fn description(&self) -> &str {
+        self.api.messages().text("system.description")
+    }
+
+    fn can_handle(&self, context: &QueryContext) -> bool {
+        let query = context.query.trim();
+        // Single letters would match half the commands
+        (query.chars().count() >= 2 && !self.search(query).is_empty())
+            || confirmed_command(query).is_some()
+    }
+
+    async fn match_query(&self, context: &QueryContext) -> Result<Vec<PluginResult>, String> {
+        if let Some(command) = confirmed_command(&context.query) {
+            return Ok(vec![self.confirmation_result(command)]);
+        }
+
+        Ok(self
+            .search(&context.query)
+            .into_iter()
+            .map(|(command, score)| self.command_result(command, score))
+            .collect())
+    }
+
+    async fn execute(&self, result: &PluginResult) -> Result<ExecuteOutcome, String> {
+        self.perform(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Controls of a platform without Bluetooth, recording what ran
+    #[derive(Default)]
+    struct FakeControls {
+        ran: Mutex<Vec<SystemCommand>>,
+    }
+
+    impl SystemControls for FakeControls {
+        fn supports(&self, command: SystemCommand) -> bool {
+            command != SystemCommand::ToggleBluetooth
+        }
+
+        fn run(&self, command: SystemCommand) -> Result<(), String> {
+            self.ran.lock().unwrap().push(command);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_system_commands_are_found_and_confirmed() {
+        let temp_dir = std::env::temp_dir().join("volt_test_system");
+        let controls = Arc::new(FakeControls::default());
+        let plugin =
+            SystemPlugin::new(VoltPluginAPI::new(temp_dir.clone())).with_controls(controls.clone());
+
+        let listed = plugin.match_query(&QueryContext::new("sys")).await.unwrap();
+        assert_eq!(listed.len(), SystemCommand::ALL.len() - 1);
+        // Bluetooth can't be toggled here, but its settings can be opened
+        let results = plugin
+            .match_query(&QueryContext::new("bluetooth"))
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "Bluetooth Settings");
+
+        let results = plugin
+            .match_query(&QueryContext::new("wifi"))
+            .await
+            .unwrap();
+        assert_eq!(results[0].title, "Toggle Wi-Fi");
+        let results = plugin
+            .match_query(&QueryContext::new("dark mode"))
+            .await
+            .unwrap();
+        assert_eq!(results[0].title, "Toggle Dark Mode");
+        assert!(!plugin.can_handle(&QueryContext::new("s")));
+        assert!(!plugin.can_handle(&QueryContext::new("2+2")));
+
+        plugin.execute(&results[0]).await.unwrap();
+
+        // Emptying the trash takes a confirmation
+        let results = plugin
+            .match_query(&QueryContext::new("trash"))
+            .await
+            .unwrap();
+        let outcome = plugin.execute(&results[0]).await.unwrap();
+        let requery = outcome.requery.unwrap();
+        assert_eq!(requery, "sys confirm:empty-trash");
+        assert!(plugin.can_handle(&QueryContext::new(requery.as_str())));
+        let confirmation = plugin
+            .match_query(&QueryContext::new(requery))
+            .await
+            .unwrap();
+        assert_eq!(confirmation[0].title, "Empty Trash?");
+        plugin.execute(&confirmation[0]).await.unwrap();
+
+        assert_eq!(
+            *controls.ran.lock().unwrap(),
+            vec![SystemCommand::ToggleDarkMode, SystemCommand::EmptyTrash]
+        );
+        // Only destructive commands are confirmed
+        assert!(!plugin.can_handle(&QueryContext::new("sys confirm:lock")));
+    }
+
+    #[test]
+    fn test_invocations_per_platform() {
+        for command in SystemCommand::ALL {
+            assert!(command.invocation(Platform::Linux).is_some());
+            assert!(command.invocation(Platform::Macos).is_some());
+        }
+        assert_eq!(
+            SystemCommand::Sleep.invocation(Platform::Linux),
+            Some(Invocation::Run(
+                CommandSpec::new("systemctl").arg("suspend")
+            ))
+        );
+        assert_eq!(
+            SystemCommand::SoundSettings.invocation(Platform::Windows),
+            Some(Invocation::Run(
+                CommandSpec::new("explorer.exe").arg("ms-settings:sound")
+            ))
+        );
+        assert_eq!(SystemCommand::VolumeUp.invocation(Platform::Windows), None);
+        assert!(matches!(
+            SystemCommand::ToggleWifi.invocation(Platform::Linux),
+            Some(Invocation::Toggle {
+                on_marker: "enabled",
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,699 @@
+/// Timers and reminders
+///
+/// Understands queries such as `timer 10m tea`, `timer 1h30m`,
+/// `remind me in 20 min to stretch` and `remind me at 5pm standup`.
+/// Pending timers are saved in the plugin's data directory, so they survive
+/// restarts; a timer that came due while the launcher was closed rings at
+/// the next tick. The host calls `TimerPlugin::fire_due` periodically,
+/// which sends a notification with snooze and dismiss actions for each
+/// timer that came due.
+///
+/// Active timers are listed on the home view (the empty query) and under
+/// `timer`; executing one of them cancels it.
+use crate::actions::now_millis;
+use crate::api::VoltPluginAPI;
+use crate::i18n::Messages;
+use crate::locks::{self, Recover};
+use crate::notifications::Notification;
+use crate::outcome::{ExecuteOutcome, Toast};
+use crate::plugin::{Plugin, QueryContext};
+use crate::result::PluginResult;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+/// Identifier of the timer plugin
+pub const PLUGIN_ID: &str = "timer";
+
+/// File of the plugin's data directory holding pending timers
+pub const TIMERS_FILE: &str = "timers.json";
+
+/// Notification action postponing a timer by `SNOOZE_MS`
+pub const ACTION_SNOOZE: &str = "snooze";
+
+/// Notification action dismissing a timer
+pub const ACTION_DISMISS: &str = "dismiss";
+
+/// How long snoozing postpones a timer, in milliseconds
+pub const SNOOZE_MS: u64 = 5 * 60 * 1000;
+
+const MINUTE_MS: u64 = 60 * 1000;
+const DAY_MS: u64 = 24 * 60 * MINUTE_MS;
+
+/// What a timer was created as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TimerKind {
+    /// Rings after a duration (`timer 10m`)
+    Timer,
+    /// Rings at a time of day or after a duration (`remind me ...`)
+    Reminder,
+}
+
+/// A pending or ringing timer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Timer {
+    /// Identifier of the timer
+    pub id: String,
+    /// What the timer is for; may be empty
+    #[serde(default)]
+    pub label: String,
+    /// What the timer was created as
+    pub kind: TimerKind,
+    /// When the timer rings, in milliseconds since the Unix epoch
+    pub due_at: u64,
+    /// Whether the timer rang and waits to be dismissed or snoozed
+    #[serde(default)]
+    pub fired: bool,
+}
+
+/// When a requested timer rings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Due {
+    /// After a number of milliseconds
+    After(u64),
+    /// At a time, in milliseconds since the Unix epoch
+    At(u64),
+}
+
+/// A timer parsed from a query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimerRequest {
+    /// What to create
+    pub kind: TimerKind,
+    /// When it rings
+    pub due: Due,
+    /// What the timer is for; may be empty
+    pub label: String,
+}
+
+impl TimerRequest {
+    /// Time the timer rings if started at `now`
+    pub fn due_at(&self, now: u64) -> u64 {
+        match self.due {
+            Due::After(delay) => now + delay,
+            Due::At(time) => time,
+        }
+    }
+}
+
+/// Parse a timer or reminder query
+///
+/// # Arguments
+/// * `query` - The query, e.g. `timer 10m tea` or `remind me at 5pm standup`
+/// * `now` - Current time, in milliseconds since the Unix epoch
+/// * `utc_offset_minutes` - Offset of the user's time zone, for times of day
+pub fn parse_request(query: &str, now: u64, utc_offset_minutes: i32) -> Option<TimerRequest> {
+    let words: Vec<&str> = query.split_whitespace().collect();
+    let lower: Vec<String> = words.iter().map(|word| word.to_lowercase()).collect();
+
+    match lower.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["timer", ..] => {
+            let (delay, used) = parse_duration_words(&lower[1..])?;
+            Some(TimerRequest {
+                kind: TimerKind::Timer,
+                due: Due::After(delay),
+                label: label(&words[1 + used..]),
+            })
+        }
+        ["remind", "me", "in", ..] => {
+            let (delay, used) = parse_duration_words(&lower[3..])?;
+            Some(TimerRequest {
+                kind: TimerKind::Reminder,
+                due: Due::After(delay),
+                label: label(&words[3 + used..]),
+            })
+        }
+        ["remind", "me", "at", ..] => {
+            let (minutes, used) = parse_time_words(&lower[3..])?;
+            Some(TimerRequest {
+                kind: TimerKind::Reminder,
+                due: Due::At(next_time_of_day(now, minutes, utc_offset_minutes)),
+                label: label(&words[3 + used..]),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Parse a compact duration such as `10m`, `1h30m` or `90s`
+///
+/// # Returns
+/// The duration in milliseconds, or None if it isn't a positive duration
+pub fn parse_duration(text: &str) -> Option<u64> {
+    let mut total = 0u64;
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let value: u64 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+
+        let unit_len = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_alphabetic()).len();
+        total = total.checked_add(value.checked_mul(unit_ms(&rest[..unit_len])?)?)?;
+        rest = &rest[unit_len..];
+    }
+
+    (total > 0).then_some(total)
+}
+
+/// Milliseconds in a duration unit; a missing unit means minutes
+fn unit_ms(unit: &str) -> Option<u64> {
+    match unit {
+        "" | "m" | "min" | "mins" | "minute" | "minutes" => Some(MINUTE_MS),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Some(60 * MINUTE_MS),
+        "s" | "sec" | "secs" | "second" | "seconds" => Some(1000),
+        _ => None,
+    }
+}
+
+/// Parse a duration at the start of lowercase words (`10m`, `10 min`, `1h 30m`)
+///
+/// # Returns
+/// The duration in milliseconds and the number of words it spans
+fn parse_duration_words(words: &[String]) -> Option<(u64, usize)> {
+    let mut total = 0;
+    let mut used = 0;
+
+    while used < words.len() {
+        let combined = words
+            .get(used + 1)
+            .filter(|unit| unit_ms(unit).is_some() && !unit.is_empty())
+            .map(|unit| format!("{}{}", words[used], unit));
+
+        if let Some(delay) = combined.as_deref().and_then(parse_duration) {
+            total += delay;
+            used += 2;
+        } else if let Some(delay) = parse_duration(&words[used]) {
+            total += delay;
+            used += 1;
+        } else {
+            break;
+        }
+    }
+
+    (total > 0).then_some((total, used))
+}
+
+/// Parse a time of day at the start of lowercase words (`5pm`, `5:30 pm`, `17:00`)
+///
+/// # Returns
+/// Minutes after midnight and the number of words the time spans
+fn parse_time_words(words: &[String]) -> Option<(u64, usize)> {
+    let first = words.first()?;
+    let (clock, meridiem, used) = if let Some(clock) = first.strip_suffix("am") {
+        (clock, Some(false), 1)
+    } else if let Some(clock) = first.strip_suffix("pm") {
+        (clock, Some(true), 1)
+    } else {
+        match words.get(1).map(String::as_str) {
+            Some("am") => (first.as_str(), Some(false), 2),
+            Some("pm") => (first.as_str(), Some(true), 2),
+            _ => (first.as_str(), None, 1),
+        }
+    };
+
+    let (hours, minutes) = match clock.split_once(':') {
+        Some((hours, minutes)) if minutes.len() == 2 => (hours.parse::<u64>().ok()?, minutes.parse::<u64>().ok()?),
+        Some(_) => return None,
+        None => (clock.parse::<u64>().ok()?, 0),
+    };
+    if minutes >= 60 {
+        return None;
+    }
+
+    let hours = match meridiem {
+        Some(_) if !(1..=12).contains(&hours) => return None,
+        Some(pm) => hours % 12 + if pm { 12 } else { 0 },
+        // A bare number is only a time with minutes ("17:00", not "17")
+        None if hours < 24 && clock.contains(':') => hours,
+        None => return None,
+    };
+
+    Some((hours * 60 + minutes, used))
+}
+
+/// Next occurrence of a local time of day after `now`
+fn next_time_of_day(now: u64, minutes: u64, utc_offset_minutes: i32) -> u64 {
+    let offset = i64::from(utc_offset_minutes) * MINUTE_MS as i64;
+    let local_now = (now as i64 + offset) as u64;
+
+    let mut local_due = local_now - local_now % DAY_MS + minutes * MINUTE_MS;
+    if local_due <= local_now {
+        local_due += DAY_MS;
+    }
+    (local_due as i64 - offset) as u64
+}
+
+/// Join the words following the time, dropping a leading "to"
+fn label(words: &[&str]) -> String {
+    let words = match words.first() {
+        Some(word) if word.eq_ignore_ascii_case("to") => &words[1..],
+        _ => words,
+    };
+    words.join(" ")
+}
+
+/// Format a remaining duration as `1h 5m`, `4m 30s` or `45s`
+pub fn format_remaining(ms: u64) -> String {
+    let seconds = ms.div_ceil(1000);
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+
+    match (hours, minutes) {
+        (0, 0) => format!("{}s", seconds),
+        (0, _) if seconds > 0 => format!("{}m {}s", minutes, seconds),
+        (0, _) => format!("{}m", minutes),
+        _ => format!("{}h {}m", hours, minutes),
+    }
+}
+
+/// Format a time as a local `HH:MM`
+fn format_time_of_day(time: u64, utc_offset_minutes: i32) -> String {
+    let local = (time as i64 + i64::from(utc_offset_minutes) * MINUTE_MS as i64) as u64;
+    let minutes = local % DAY_MS / MINUTE_MS;
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+#[derive(Default)]
+struct Timers(Vec<Timer>);
+
+impl Recover for Timers {
+    fn recover(&mut self) {
+        // Timers are user data; every change is saved as a whole
+    }
+}
+
+/// Built-in plugin managing timers and reminders
+pub struct TimerPlugin {
+    api: VoltPluginAPI,
+    timers: RwLock<Timers>,
+    utc_offset_minutes: i32,
+}
+
+impl TimerPlugin {
+    /// Create the plugin; pending timers are loaded by `initialize`
+    pub fn new(api: VoltPluginAPI) -> Self {
+        Self {
+            api,
+            timers: RwLock::new(Timers::default()),
+            utc_offset_minutes: 0,
+        }
+    }
+
+    /// Set the offset of the user's time zone, used for `remind me at`
+    pub fn with_utc_offset(mut self, minutes: i32) -> Self {
+        self.utc_offset_minutes = minutes;
+        self
+    }
+
+    /// Get all timers, soonest first
+    pub fn timers(&self) -> Vec<Timer> {
+        let mut timers = locks::read(&self.timers, "timers").0.clone();
+        timers.sort_by_key(|timer| timer.due_at);
+        timers
+    }
+
+    /// Start a timer
+    ///
+    /// # Arguments
+    /// * `request` - The parsed timer
+    /// * `now` - Current time, in milliseconds since the Unix epoch
+    pub fn start(&self, request: &TimerRequest, now: u64) -> Result<Timer, String> {
+        let mut timers = locks::write(&self.timers, "timers");
+
+        let mut id = now.to_string();
+        while timers.0.iter().any(|timer| timer.id == id) {
+            id.push('x');
+        }
+
+        let timer = Timer {
+            id,
+            label: request.label.clone(),
+            kind: request.kind,
+            due_at: request.due_at(now),
+            fired: false,
+        };
+        timers.0.push(timer.clone());
+        self.save(&timers.0)?;
+        Ok(timer)
+    }
+
+    /// Cancel a pending timer or dismiss a ringing one
+    pub fn cancel(&self, timer_id: &str) -> Result<(), String> {
+        let mut timers = locks::write(&self.timers, "timers");
+
+        let before = timers.0.len();
+        timers.0.retain(|timer| timer.id != timer_id);
+        if timers.0.len() == before {
+            return Err(format!("Timer '{}' not found", timer_id));
+        }
+        self.save(&timers.0)
+    }
+
+    /// Make a timer ring again `SNOOZE_MS` after `now`
+    pub fn snooze(&self, timer_id: &str, now: u64) -> Result<(), String> {
+        let mut timers = locks::write(&self.timers, "timers");
+
+        let timer = timers
+            .0
+            .iter_mut()
+            .find(|timer| timer.id == timer_id)
+            .ok_or_else(|| format!("Timer '{}' not found", timer_id))?;
+        timer.due_at = now + SNOOZE_MS;
+        timer.fired = false;
+        self.save(&timers.0)
+    }
+
+    /// Ring every timer that came due
+    ///
+    /// Call periodically (e.g., every second). Each due timer sends a
+    /// notification with snooze and dismiss actions and stays listed as
+    /// ringing until the user picks one.
+    ///
+    /// # Returns
+    /// The timers that rang
+    pub fn fire_due(&self, now: u64) -> Result<Vec<Timer>, String> {
+        let fired: Vec<Timer> = {
+            let mut timers = locks::write(&self.timers, "timers");
+            let mut fired = Vec::new();
+            for timer in timers.0.iter_mut().filter(|timer| !timer.fired && timer.due_at <= now) {
+                timer.fired = true;
+                fired.push(timer.clone());
+            }
+            if fired.is_empty() {
+                return Ok(fired);
+            }
+            self.save(&timers.0)?;
+            fired
+        };
+
+        let messages = self.api.messages();
+        for timer in &fired {
+            if let Err(e) = self.api.notify(PLUGIN_ID, notification(timer, &messages)) {
+                self.api.diagnostics().warn(PLUGIN_ID, &format!("Timer '{}' rang without a notification: {}", timer.id, e));
+            }
+        }
+        Ok(fired)
+    }
+
+    fn timers_path(&self) -> Result<PathBuf, String> {
+        Ok(self.api.get_plugin_data_dir(PLUGIN_ID)?.join(TIMERS_FILE))
+    }
+
+    fn save(&self, timers: &[Timer]) -> Result<(), String> {
+        let content =
+            serde_json::to_string(timers).map_err(|e| format!("Failed to serialize timers: {}", e))?;
+
+        std::fs::write(self.timers_path()?, content).map_err(|e| format!("Failed to write timers: {}", e))
+    }
+
+    /// Result listing a timer on the home view
+    fn timer_result(&self, timer: &Timer, now: u64) -> PluginResult {
+        let messages = self.api.messages();
+        let title = match (timer.label.is_empty(), timer.kind) {
+            (false, _) => timer.label.clone(),
+            (true, TimerKind::Timer) => messages.text("timer.timer").to_string(),
+            (true, TimerKind::Reminder) => messages.text("timer.reminder").to_string(),
+        };
+        let subtitle = if timer.fired {
+            messages.text("timer.ringing").to_string()
+        } else if timer.kind == TimerKind::Reminder && timer.due_at.saturating_sub(now) > 60 * MINUTE_MS {
+            let time = format_time_of_day(timer.due_at, self.utc_offset_minutes);
+            messages.format("timer.due_at", &[("time", &time)])
+        } else {
+            let duration = format_remaining(timer.due_at.saturating_sub(now));
+            messages.format("timer.due_in", &[("duration", &duration)])
+        };
+
+        let mut result = PluginResult::new(format!("timer-{}", timer.id), title)
+            .with_meta("action", "cancel")
+            .with_meta("timerId", timer.id.clone());
+        result.subtitle = Some(subtitle.into());
+        result.icon = Some("⏲️".into());
+        result.score = 50;
+        result
+    }
+
+    /// Result offering to start a parsed timer
+    fn start_result(&self, request: &TimerRequest, now: u64) -> PluginResult {
+        let key = match (request.kind, request.due, request.label.is_empty()) {
+            (TimerKind::Timer, Due::After(_), true) => "timer.start_in",
+            (TimerKind::Timer, Due::After(_), false) => "timer.start_in_labeled",
+            (TimerKind::Timer, Due::At(_), true) => "timer.start_at",
+            (TimerKind::Timer, Due::At(_), false) => "timer.start_at_labeled",
+            (TimerKind::Reminder, Due::After(_), true) => "timer.remind_in",
+            (TimerKind::Reminder, Due::After(_), false) => "timer.remind_in_labeled",
+            (TimerKind::Reminder, Due::At(_), true) => "timer.remind_at",
+            (TimerKind::Reminder, Due::At(_), false) => "timer.remind_at_labeled",
+        };
+        let duration = format_remaining(request.due_at(now).saturating_sub(now));
+        let time = format_time_of_day(request.due_at(now), self.utc_offset_minutes);
+        let title = self.api.messages().format(
+            key,
+            &[("duration", &duration), ("time", &time), ("label", &request.label)],
+        );
+
+        let mut result = PluginResult::new("start", title)
+            .with_meta("action", "start")
+            .with_meta("kind", serde_json::to_value(request.kind).unwrap_or_default())
+            .with_meta("label", request.label.clone());
+        result = match request.due {
+            Due::After(delay) => result.with_meta("delayMs", delay),
+            Due::At(time) => result.with_meta("dueAt", time),
+        };
+        result.icon = Some("⏲️".into());
+        result.score = 100;
+        result
+    }
+}
+
+/// Notification sent when a timer rings
+fn notification(timer: &Timer, messages: &Messages) -> Notification {
+    let title = match timer.kind {
+        TimerKind::Timer => messages.text("timer.done"),
+        TimerKind::Reminder => messages.text("timer.reminder"),
+    };
+    let mut notification = Notification::new(timer.id.clone(), title)
+        .with_action(ACTION_SNOOZE, messages.text("timer.snooze"))
+        .with_action(ACTION_DISMISS, messages.text("timer.dismiss"));
+    if !timer.label.is_empty() {
+        notification = notification.with_body(timer.label.clone());
+    }
+    notification
+}
+
+#[async_trait]
+impl Plugin for TimerPlugin {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn id(&self) -> &str {
+        PLUGIN_ID
+    }
+
+    fn name(&self) -> &str {
+        self.api.messages().text("timer.name")
+    }
+
+    fn description(&self) -> &str {
+        self.api.messages().text("timer.description")
+    }
+
+    fn initialize(&self) -> Result<(), String> {
+        let path = self.timers_path()?;
+        if !path.exists() {
+            return Ok(());
+        }
+
+        let content = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read timers: {}", e))?;
+        let timers: Vec<Timer> =
+            serde_json::from_str(&content).map_err(|e| format!("Failed to parse timers: {}", e))?;
+
+        locks::write(&self.timers, "timers").0 = timers;
+        Ok(())
+    }
+
+    fn can_handle(&self, context: &QueryContext) -> bool {
+        let query = context.query.trim().to_lowercase();
+        if query.is_empty() {
+            return !locks::read(&self.timers, "timers").0.is_empty();
+        }
+        query.starts_with("timer") || query.starts_with("remind me")
+    }
+
+    async fn match_query(&self, context: &QueryContext) -> Result<Vec<PluginResult>, String> {
+        let now = now_millis();
+
+        if let Some(request) = parse_request(&context.query, now, self.utc_offset_minutes) {
+            return Ok(vec![self.start_result(&request, now)]);
+        }
+
+        // The home view and a bare "timer" list the active timers
+        let query = context.query.trim().to_lowercase();
+        if query.is_empty() || query == "timer" || query == "timers" {
+            return Ok(self.timers().iter().map(|timer| self.timer_result(timer, now)).collect());
+        }
+        Ok(Vec::new())
+    }
+
+    async fn execute(&self, result: &PluginResult) -> Result<ExecuteOutcome, String> {
+        match result.meta_str("action") {
+            Some("start") => {
+                let kind = result
+                    .meta_as::<TimerKind>("kind")
+                    .ok_or("Missing timer kind")??;
+                let due = match (result.meta_u64("delayMs"), result.meta_u64("dueAt")) {
+                    (Some(delay), _) => Due::After(delay),
+                    (None, Some(time)) => Due::At(time),
+                    (None, None) => return Err("Missing timer due time".to_string()),
+                };
+                let request = TimerRequest {
+                    kind,
+                    due,
+                    label: result.meta_str("label").unwrap_or_default().to_string(),
+                };
+                self.start(&request, now_millis())?;
+                Ok(ExecuteOutcome::close())
+            }
+            Some("cancel") => {
+                self.cancel(result.meta_str("timerId").ok_or("Missing timer ID")?)?;
+                Ok(ExecuteOutcome::keep_open().with_message(Toast::info(self.api.messages().text("timer.cancelled"))))
+            }
+            _ => Err(format!("Unknown timer result '{}'", result.id)),
+        }
+    }
+
+    fn on_notification_action(&self, notification_id: &str, action_id: &str) -> Result<(), String> {
+        match action_id {
+            ACTION_SNOOZE => self.snooze(notification_id, now_millis()),
+            ACTION_DISMISS => self.cancel(notification_id),
+            _ => Err(format!("Unknown timer action '{}'", action_id)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::HostFeature;
+    use std::sync::{Arc, Mutex};
+
+    /// 2024-01-01 12:00 UTC
+    const NOON: u64 = 1_704_110_400_000;
+
+    #[test]
+    fn test_queries_are_parsed() {
+        let request = parse_request("timer 10m Tea", NOON, 0).unwrap();
+        assert_eq!(request.due, Due::After(10 * MINUTE_MS));
+        assert_eq!(request.label, "Tea");
+
+        assert_eq!(parse_request("timer 1h30m", NOON, 0).unwrap().due, Due::After(90 * MINUTE_MS));
+        assert_eq!(parse_request("timer 1 h 5 min pasta", NOON, 0).unwrap().label, "pasta");
+        assert_eq!(
+            parse_request("remind me in 20 minutes to stretch", NOON, 0).unwrap(),
+            TimerRequest {
+                kind: TimerKind::Reminder,
+                due: Due::After(20 * MINUTE_MS),
+                label: "stretch".to_string(),
+            }
+        );
+
+        // 5pm in UTC+2 is 15:00 UTC; 9am has passed and rings tomorrow
+        let request = parse_request("remind me at 5pm standup", NOON, 120).unwrap();
+        assert_eq!(request.due, Due::At(NOON + 3 * 60 * MINUTE_MS));
+        assert_eq!(request.label, "standup");
+        let tomorrow_morning = NOON + 21 * 60 * MINUTE_MS + 30 * MINUTE_MS;
+        assert_eq!(parse_request("remind me at 9:30 am", NOON, 0).unwrap().due, Due::At(tomorrow_morning));
+        let evening = NOON + 5 * 60 * MINUTE_MS;
+        assert_eq!(parse_request("remind me at 17:00", NOON, 0).unwrap().due, Due::At(evening));
+
+        for query in ["timer", "timer tea", "timer 0m", "remind me at 13pm", "remind me at 17", "reminder 5m"] {
+            assert!(parse_request(query, NOON, 0).is_none(), "{:?} should not parse", query);
+        }
+    }
+
+    #[test]
+    fn test_remaining_time_is_formatted() {
+        assert_eq!(format_remaining(45_000), "45s");
+        assert_eq!(format_remaining(270_000), "4m 30s");
+        assert_eq!(format_remaining(600_000), "10m");
+        assert_eq!(format_remaining(3_900_000), "1h 5m");
+    }
+
+    #[test]
+    fn test_timers_ring_persist_and_snooze() {
+        let temp_dir = std::env::temp_dir().join("volt_test_timer_plugin");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let api = VoltPluginAPI::new(temp_dir.clone());
+        api.set_feature_available(HostFeature::Notifications, true).unwrap();
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let sink = sent.clone();
+        api.set_notifier(Arc::new(move |notification: &Notification| {
+            sink.lock().unwrap().push(notification.clone());
+            Ok(())
+        }));
+
+        let plugin = TimerPlugin::new(api.clone());
+        plugin.initialize().unwrap();
+        let tea = plugin.start(&parse_request("timer 10m tea", NOON, 0).unwrap(), NOON).unwrap();
+        plugin.start(&parse_request("timer 1h", NOON, 0).unwrap(), NOON).unwrap();
+
+        // Pending timers survive a restart
+        let plugin = TimerPlugin::new(api);
+        plugin.initialize().unwrap();
+        assert_eq!(plugin.timers().len(), 2);
+        assert!(plugin.can_handle(&QueryContext::new("")));
+
+        assert!(plugin.fire_due(NOON + MINUTE_MS).unwrap().is_empty());
+        let fired = plugin.fire_due(NOON + 10 * MINUTE_MS).unwrap();
+        assert_eq!(fired.len(), 1);
+        assert!(plugin.fire_due(NOON + 11 * MINUTE_MS).unwrap().is_empty());
+
+        let notification = sent.lock().unwrap()[0].clone();
+        assert_eq!(notification.id, tea.id);
+        assert_eq!(notification.plugin_id, PLUGIN_ID);
+        assert_eq!(notification.body.as_deref(), Some("tea"));
+        assert_eq!(notification.actions.len(), 2);
+
+        plugin.snooze(&tea.id, NOON + 11 * MINUTE_MS).unwrap();
+        assert_eq!(plugin.fire_due(NOON + 16 * MINUTE_MS).unwrap().len(), 1);
+        plugin.on_notification_action(&tea.id, ACTION_DISMISS).unwrap();
+        assert_eq!(plugin.timers().len(), 1);
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn test_strings_follow_the_locale() {
+        let temp_dir = std::env::temp_dir().join("volt_test_timer_locale");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let api = VoltPluginAPI::new(temp_dir.clone());
+        let plugin = TimerPlugin::new(api.clone());
+        let request = parse_request("timer 10m tea", NOON, 0).unwrap();
+        assert_eq!(
+            plugin.start_result(&request, NOON).title,
+            "Start a timer for tea that rings in 10m"
+        );
+
+        api.set_locale("de_DE.UTF-8");
+        assert_eq!(plugin.name(), "Timer");
+        assert_eq!(
+            plugin.start_result(&request, NOON).title,
+            "Timer für tea starten, der in 10m klingelt"
+        );
+
+        // Every shown string comes from the catalogs
+        api.set_locale(crate::i18n::PSEUDO_LOCALE);
+        assert!(crate::i18n::is_pseudo_localized(plugin.name()));
+        let title = plugin.start_result(&request, NOON).title;
+        assert!(crate::i18n::is_pseudo_localized(&title));
+        assert!(title.contains("tea") && title.contains("10m"));
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
@@ -0,0 +1,473 @@
+/// Window switcher
+///
+/// `win` lists the open windows, most recently focused first, and
+/// `win <search>` fuzzy-matches window titles and application names.
+/// Executing a window focuses it; `Mod+W` closes it and the action menu
+/// moves it to another workspace. Previews captured by the host are used as
+/// result icons when the platform provides them; with the `imaging`
+/// feature they are checked and downscaled before being cached.
+///
+/// The window list is loaded from the host's `WindowManager` on first use
+/// and then kept current from window events, so it reflects windows opened
+/// or closed while the launcher is open without listing them again. This
+/// plugin is the reference consumer of `crate::windows`.
+use crate::api::VoltPluginAPI;
+#[cfg(feature = "imaging")]
+use crate::imaging::{self, ImageLimits};
+use crate::locks::{self, Recover};
+use crate::matching::fuzzy_score_with;
+use crate::outcome::{ExecuteOutcome, Toast};
+use crate::plugin::{Plugin, QueryContext};
+use crate::result::{KeyHint, PluginResult, ResultAction};
+use crate::windows::{self, WindowEvent, WindowInfo, WindowManager};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::{Arc, Once, RwLock};
+
+/// Identifier of the window switcher plugin
+pub const PLUGIN_ID: &str = "windows";
+
+/// Keyword listing and searching windows
+pub const KEYWORD: &str = "win";
+
+/// Key hint action closing a window
+pub const ACTION_CLOSE: &str = "close";
+
+/// Prefix of the actions moving a window, followed by the workspace index
+pub const MOVE_ACTION_PREFIX: &str = "move-to-workspace:";
+
+/// Directory of the plugin's cache holding window previews
+const THUMBNAILS_DIR: &str = "thumbnails";
+
+/// Most results returned for a search
+const MAX_RESULTS: usize = 20;
+
+/// The windows as last seen, and their cached previews
+#[derive(Default)]
+struct WindowList {
+    windows: Vec<WindowInfo>,
+    /// Whether `windows` was loaded from the window manager
+    loaded: bool,
+    /// Preview of each window, None if the platform has none
+    thumbnails: HashMap<String, Option<PathBuf>>,
+}
+
+impl Recover for WindowList {
+    fn recover(&mut self) {
+        // Everything is derived from the window manager
+        *self = Self::default();
+    }
+}
+
+impl WindowList {
+    /// Apply a window event, dropping the previews it made outdated
+    fn apply(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::Changed { window } => {
+                self.thumbnails.remove(&window.id);
+            }
+            WindowEvent::Closed { window_id } => {
+                if let Some(Some(path)) = self.thumbnails.remove(window_id) {
+                    let _ = std::fs::remove_file(path);
+                }
+            }
+            WindowEvent::Opened { .. } | WindowEvent::Focused { .. } => {}
+        }
+
+        if self.loaded {
+            windows::apply_event(&mut self.windows, event);
+        }
+    }
+}
+
+/// Built-in plugin switching between application windows
+pub struct WindowSwitcherPlugin {
+    api: VoltPluginAPI,
+    state: Arc<RwLock<WindowList>>,
+    subscribed: Once,
+}
+
+impl WindowSwitcherPlugin {
+    /// Create the plugin; it follows window events once initialized
+    pub fn new(api: VoltPluginAPI) -> Self {
+        Self {
+            api,
+            state: Arc::new(RwLock::new(WindowList::default())),
+            subscribed: Once::new(),
+        }
+    }
+
+    /// Get the open windows, most recently focused first
+    pub fn windows(&self) -> Result<Vec<WindowInfo>, String> {
+        {
+            let state = locks::read(&self.state, "window list");
+            if state.loaded {
+                return Ok(state.windows.clone());
+            }
+        }
+
+        let windows = self.api.window_manager()?.list_windows()?;
+        let mut state = locks::write(&self.state, "window list");
+        state.windows = windows.clone();
+        state.loaded = true;
+        Ok(windows)
+    }
+
+    /// Find windows for a search, best first
+    ///
+    /// An empty search lists every window in focus order. Otherwise titles
+    /// and application names are fuzzy-matched; among equal matches, the
+    /// most recently focused window wins.
+    pub fn search(&self, search: &str) -> Result<Vec<(WindowInfo, u32)>, String> {
+        let windows = self.windows()?;
+        let recency = |index: usize| MAX_RESULTS.saturating_sub(index) as u32;
+        let tokenizer = self.api.tokenizer();
+
+        let mut matches: Vec<(WindowInfo, u32)> = windows
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, window)| {
+                let score = if search.trim().is_empty() {
+                    0
+                } else {
+                    fuzzy_score_with(&tokenizer, search, &window.title)
+                        .max(fuzzy_score_with(&tokenizer, search, &window.app_name))?
+                        * 10
+                };
+                Some((window, score + recency(index)))
+            })
+            .collect();
+
+        matches.sort_by(|(_, a), (_, b)| b.cmp(a));
+        matches.truncate(MAX_RESULTS);
+        Ok(matches)
+    }
+
+    /// Run the default action or a routed action on a window result
+    ///
+    /// Call this from the host with the action routed by `MergedResults`;
+    /// `execute` runs the default action.
+    ///
+    /// # Arguments
+    /// * `result` - A result returned by this plugin
+    /// * `action` - `ACTION_CLOSE`, a workspace move, or None to focus the window
+    pub fn perform(
+        &self,
+        result: &PluginResult,
+        action: Option<&str>,
+    ) -> Result<ExecuteOutcome, String> {
+        let window_id = result.meta_str("windowId").ok_or("Missing window ID")?;
+        let manager = self.api.window_manager()?;
+        let messages = self.api.messages();
+
+        match action {
+            None => {
+                manager.focus(window_id)?;
+                Ok(ExecuteOutcome::close())
+            }
+            Some(ACTION_CLOSE) => {
+                manager.close(window_id)?;
+                locks::write(&self.state, "window list").apply(&WindowEvent::Closed {
+                    window_id: window_id.to_string(),
+                });
+                Ok(ExecuteOutcome::keep_open().with_message(Toast::info(messages.text("windows.closed"))))
+            }
+            Some(action) => {
+                let workspace: u32 = action
+                    .strip_prefix(MOVE_ACTION_PREFIX)
+                    .and_then(|index| index.parse().ok())
+                    .ok_or_else(|| format!("Unknown window action '{}'", action))?;
+                manager.move_to_workspace(window_id, workspace)?;
+                let number = (workspace + 1).to_string();
+                Ok(ExecuteOutcome::keep_open().with_message(Toast::info(
+                    messages.format("windows.moved", &[("number", &number)]),
+                )))
+            }
+        }
+    }
+
+    /// Get the preview of a window, capturing it on first use
+    fn thumbnail(&self, manager: &dyn WindowManager, window_id: &str) -> Option<PathBuf> {
+        if let Some(thumbnail) = locks::read(&self.state, "window list")
+            .thumbnails
+            .get(window_id)
+        {
+            return thumbnail.clone();
+        }
+
+        let thumbnail = manager.thumbnail(window_id).and_then(|png| {
+            #[cfg(feature = "imaging")]
+            let png = match imaging::normalize(&png, &ImageLimits::THUMBNAIL) {
+                Ok(image) => image.png,
+                Err(e) => {
+                    self.api.diagnostics().warn(PLUGIN_ID, &format!("Rejected window preview: {}", e));
+                    return None;
+                }
+            };
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            window_id.hash(&mut hasher);
+            let dir = self
+                .api
+                .get_plugin_cache_dir(PLUGIN_ID)
+                .ok()?
+                .join(THUMBNAILS_DIR);
+            let path = dir.join(format!("{:016x}.png", hasher.finish()));
+
+            match std::fs::create_dir_all(&dir).and_then(|()| std::fs::write(&path, png)) {
+                Ok(()) => Some(path),
+                Err(e) => {
+                    self.api.diagnostics().warn(PLUGIN_ID, &format!("Failed to save window preview: {}", e));
+                    None
+                }
+            }
+        });
+        locks::write(&self.state, "window list")
+            .thumbnails
+            .insert(window_id.to_string(), thumbnail.clone());
+        thumbnail
+    }
+
+    /// Result focusing a window
+    fn window_result(
+        &self,
+        manager: &dyn WindowManager,
+        window: &WindowInfo,
+        score: u32,
+    ) -> PluginResult {
+        let title = if window.title.trim().is_empty() {
+            window.app_name.clone()
+        } else {
+            window.title.clone()
+        };
+        let messages = self.api.messages();
+        let mut subtitle = window.app_name.clone();
+        if let Some(workspace) = window.workspace {
+            let number = (workspace + 1).to_string();
+            subtitle.push_str(" · ");
+            subtitle.push_str(&messages.format("windows.workspace", &[("number", &number)]));
+        }
+        if window.minimized {
+            subtitle.push_str(" · ");
+            subtitle.push_str(messages.text("windows.minimized"));
+        }
+
+        let mut result = PluginResult::new(format!("window-{}", window.id), title)
+            .with_meta("windowId", window.id.clone())
+            .with_key_hint(KeyHint::new(
+                "Mod+W",
+                ACTION_CLOSE,
+                messages.text("windows.close"),
+            ));
+        for workspace in
+            (0..manager.workspace_count()).filter(|index| window.workspace != Some(*index))
+        {
+            let number = (workspace + 1).to_string();
+            result = result.with_action(ResultAction::new(
+                format!("{}{}", MOVE_ACTION_PREFIX, workspace),
+                messages.format("windows.move_to", &[("number", &number)]),
+            ));
+        }
+        result.subtitle = Some(subtitle.into());
+        result.icon = Some(match self.thumbnail(manager, &window.id) {
+            Some(path) => path.to_string_lossy().into_owned().into(),
+            None => "🪟".into(),
+        });
+        result.score = score;
+        result
+    }
+}
+
+/// Split a query into the window search, if it is addressed to the plugin
+fn window_search(query: &str) -> Option<&str> {
+    let query = query.trim();
+    match query.split_once(char::is_whitespace) {
+        Some((keyword, search)) if keyword.eq_ignore_ascii_case(KEYWORD) => Some(search.trim()),
+        None if query.eq_ignore_ascii_case(KEYWORD) => Some(""),
+        _ => None,
+    }
+}
+
+#[async_trait]
+impl Plugin for WindowSwitcherPlugin {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn id(&self) -> &str {
+        PLUGIN_ID
+    }
+
+    fn name(&self) -> &str {
+        self.api.messages().text("windows.name")
+    }
+
+    fn description(&self) -> &str {
+        self.api.messages().text("windows.description")
+    }
+
+    fn initialize(&self) -> Result<(), String> {
+        let mut outcome = Ok(());
+        self.subscribed.call_once(|| {
+            let state = Arc::downgrade(&self.state);
+            outcome = self
+                .api
+                .subscribe_window_events(Arc::new(move |event: &WindowEvent| {
+                    if let Some(state) = state.upgrade() {
+                        locks::write(&state, "window list").apply(event);
+                    }
+                }));
+        });
+        outcome
+    }
+
+    fn can_handle(&self, context: &QueryContext) -> bool {
+        window_search(&context.query).is_some() && self.api.window_manager().is_ok()
+    }
+
+    async fn match_query(&self, context: &QueryContext) -> Result<Vec<PluginResult>, String> {
+        let Some(search) = window_search(&context.query) else {
+            return Ok(Vec::new());
+        };
+        let manager = self.api.window_manager()?;
+
+        Ok(self
+            .search(search)?
+            .iter()
+            .map(|(window, score)| self.window_result(manager.as_ref(), window, *score))
+            .collect())
+    }
+
+    async fn execute(&self, result: &PluginResult) -> Result<ExecuteOutcome, String> {
+        self.perform(result, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::HostFeature;
+    use std::sync::Mutex;
+
+    /// A 1x1 PNG
+    const PIXEL_PNG: &[u8] = &[
+        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52, 0x00, 0x00,
+        0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x02, 0x00, 0x00, 0x00, 0x90, 0x77, 0x53, 0xde, 0x00, 0x00, 0x00,
+        0x0c, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0xf8, 0xcf, 0xc0, 0x00, 0x00, 0x03, 0x01, 0x01, 0x00, 0xc9,
+        0xfe, 0x92, 0xef, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+    ];
+
+    #[derive(Default)]
+    struct FakeWindows {
+        windows: Mutex<Vec<WindowInfo>>,
+        lists: Mutex<usize>,
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl WindowManager for FakeWindows {
+        fn list_windows(&self) -> Result<Vec<WindowInfo>, String> {
+            *self.lists.lock().unwrap() += 1;
+            Ok(self.windows.lock().unwrap().clone())
+        }
+
+        fn focus(&self, window_id: &str) -> Result<(), String> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("focus {}", window_id));
+            Ok(())
+        }
+
+        fn close(&self, window_id: &str) -> Result<(), String> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("close {}", window_id));
+            Ok(())
+        }
+
+        fn workspace_count(&self) -> u32 {
+            2
+        }
+
+        fn move_to_workspace(&self, window_id: &str, workspace: u32) -> Result<(), String> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("move {} {}", window_id, workspace));
+            Ok(())
+        }
+
+        fn thumbnail(&self, window_id: &str) -> Option<Vec<u8>> {
+            (window_id == "1").then(|| PIXEL_PNG.to_vec())
+        }
+    }
+
+    fn window(id: &str, title: &str, app_name: &str) -> WindowInfo {
+        WindowInfo {
+            id: id.to_string(),
+            title: title.to_string(),
+            app_name: app_name.to_string(),
+            workspace: Some(0),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_windows_are_searched_and_followed() {
+        let temp_dir = std::env::temp_dir().join("volt_test_window_switcher");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let api = VoltPluginAPI::new(temp_dir.clone());
+        let manager = Arc::new(FakeWindows::default());
+        *manager.windows.lock().unwrap() = vec![
+            window("1", "volt-extensions – registry.rs", "Code"),
+            window("2", "Inbox", "Mail"),
+        ];
+        api.set_window_manager(manager.clone());
+
+        let plugin = WindowSwitcherPlugin::new(api.clone());
+        plugin.initialize().unwrap();
+        assert!(!plugin.can_handle(&QueryContext::new("win")));
+        api.set_feature_available(HostFeature::WindowManagement, true)
+            .unwrap();
+        assert!(plugin.can_handle(&QueryContext::new("win mail")));
+        assert!(!plugin.can_handle(&QueryContext::new("window")));
+
+        let results = plugin
+            .match_query(&QueryContext::new("win mail"))
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "Inbox");
+        assert_eq!(results[0].icon.as_deref(), Some("🪟"));
+        assert_eq!(results[0].actions[0].id, "move-to-workspace:1");
+
+        // New windows arrive through events, without listing again
+        api.notify_window_event(&WindowEvent::Opened {
+            window: window("3", "Release notes", "Mail"),
+        });
+        let results = plugin.match_query(&QueryContext::new("win")).await.unwrap();
+        let titles: Vec<&str> = results.iter().map(|result| result.title.as_ref()).collect();
+        assert_eq!(
+            titles,
+            vec!["Release notes", "volt-extensions – registry.rs", "Inbox"]
+        );
+        assert!(results[1].icon.as_deref().unwrap().ends_with(".png"));
+        assert_eq!(*manager.lists.lock().unwrap(), 1);
+
+        plugin.execute(&results[2]).await.unwrap();
+        plugin
+            .perform(&results[0], Some("move-to-workspace:1"))
+            .unwrap();
+        plugin.perform(&results[0], Some(ACTION_CLOSE)).unwrap();
+        assert!(plugin.perform(&results[0], Some("minimize")).is_err());
+        assert_eq!(
+            *manager.calls.lock().unwrap(),
+            vec!["focus 2", "move 3 1", "close 3"]
+        );
+        assert_eq!(plugin.windows().unwrap().len(), 2);
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
@@ -0,0 +1,360 @@
+/// Plugin bundles
+///
+/// Users group plugins into bundles ("Dev tools", "Writing") that are
+/// enabled and disabled together, and bind bundles to profiles so that
+/// switching profile swaps the active bundles. A plugin belonging to
+/// several bundles stays enabled as long as one of them is.
+///
+/// `BundleState` only computes which plugins change state; the registry
+/// applies the change and runs the plugins' lifecycle hooks.
+use crate::locks::Recover;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// A named group of plugins toggled together
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginBundle {
+    /// Identifier of the bundle
+    pub id: String,
+    /// Name shown to the user
+    pub name: String,
+    /// Member plugin IDs, in the order they are enabled
+    #[serde(default)]
+    pub plugins: Vec<String>,
+    /// Profiles the bundle is active in; empty if not bound to any profile
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<String>,
+}
+
+impl PluginBundle {
+    /// Create an empty bundle
+    pub fn new(id: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            plugins: Vec::new(),
+            profiles: Vec::new(),
+        }
+    }
+
+    /// Add a member plugin
+    pub fn with_plugin(mut self, plugin_id: impl Into<String>) -> Self {
+        self.plugins.push(plugin_id.into());
+        self
+    }
+
+    /// Activate the bundle in a profile
+    pub fn bound_to(mut self, profile: impl Into<String>) -> Self {
+        self.profiles.push(profile.into());
+        self
+    }
+}
+
+/// Plugins whose state changes, in the order their hooks must run
+///
+/// Plugins are disabled before any plugin is enabled, so a plugin leaving
+/// releases shared resources (hotkeys, keywords) before its replacement
+/// claims them. Disabling runs in reverse member order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct BundleTransition {
+    /// Plugins to disable, in order
+    pub disabled: Vec<String>,
+    /// Plugins to enable, in order
+    pub enabled: Vec<String>,
+}
+
+impl BundleTransition {
+    /// Check if nothing changes
+    pub fn is_empty(&self) -> bool {
+        self.disabled.is_empty() && self.enabled.is_empty()
+    }
+}
+
+/// Bundle definitions and the resulting plugin states, persisted by the host
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleState {
+    /// Defined bundles, keyed by ID
+    bundles: BTreeMap<String, PluginBundle>,
+    /// IDs of the enabled bundles
+    enabled_bundles: BTreeSet<String>,
+    /// Plugins disabled by the user, directly or through a bundle
+    disabled_plugins: BTreeSet<String>,
+}
+
+impl Recover for BundleState {
+    fn recover(&mut self) {
+        // Sets are only updated at the end of a transition; all state is user data
+    }
+}
+
+impl BundleState {
+    /// Create a state without bundles where every plugin is enabled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the state from disk, or an empty state if the file doesn't exist
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::new());
+        }
+
+        let content =
+            std::fs::read_to_string(path).map_err(|e| format!("Failed to read bundles: {}", e))?;
+
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse bundles: {}", e))
+    }
+
+    /// Save the state to disk
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content =
+            serde_json::to_string(self).map_err(|e| format!("Failed to serialize bundles: {}", e))?;
+
+        std::fs::write(path, content).map_err(|e| format!("Failed to write bundles: {}", e))
+    }
+
+    /// Get all bundles, sorted by ID
+    pub fn bundles(&self) -> Vec<&PluginBundle> {
+        self.bundles.values().collect()
+    }
+
+    /// Get a bundle
+    pub fn bundle(&self, bundle_id: &str) -> Option<&PluginBundle> {
+        self.bundles.get(bundle_id)
+    }
+
+    /// Check if a bundle is enabled
+    pub fn is_bundle_enabled(&self, bundle_id: &str) -> bool {
+        self.enabled_bundles.contains(bundle_id)
+    }
+
+    /// Check if the user left a plugin enabled
+    pub fn is_plugin_enabled(&self, plugin_id: &str) -> bool {
+        !self.disabled_plugins.contains(plugin_id)
+    }
+
+    /// Define or replace a bundle
+    ///
+    /// A new bundle starts enabled. Defining a bundle never changes the
+    /// state of its members.
+    pub fn define(&mut self, bundle: PluginBundle) -> Result<(), String> {
+        if bundle.id.trim().is_empty() {
+            return Err("Bundle ID cannot be empty".to_string());
+        }
+
+        if !self.bundles.contains_key(&bundle.id) {
+            self.enabled_bundles.insert(bundle.id.clone());
+        }
+        self.bundles.insert(bundle.id.clone(), bundle);
+        Ok(())
+    }
+
+    /// Delete a bundle, leaving its members in their current state
+    pub fn remove(&mut self, bundle_id: &str) -> Result<PluginBundle, String> {
+        self.enabled_bundles.remove(bundle_id);
+        self.bundles
+            .remove(bundle_id)
+            .ok_or_else(|| format!("Bundle '{}' not found", bundle_id))
+    }
+
+    /// Add a plugin to a bundle
+    pub fn add_member(&mut self, bundle_id: &str, plugin_id: &str) -> Result<(), String> {
+        let bundle = self.bundle_mut(bundle_id)?;
+        if !bundle.plugins.iter().any(|member| member == plugin_id) {
+            bundle.plugins.push(plugin_id.to_string());
+        }
+        Ok(())
+    }
+
+    /// Remove a plugin from a bundle
+    pub fn remove_member(&mut self, bundle_id: &str, plugin_id: &str) -> Result<(), String> {
+        self.bundle_mut(bundle_id)?
+            .plugins
+            .retain(|member| member != plugin_id);
+        Ok(())
+    }
+
+    /// Relabel a renamed plugin in every bundle and in the disabled set
+    pub fn rename_plugin(&mut self, from: &str, to: &str) {
+        for bundle in self.bundles.values_mut() {
+            if bundle.plugins.iter().any(|member| member == from) {
+                bundle.plugins.retain(|member| member != to);
+                for member in bundle.plugins.iter_mut().filter(|member| *member == from) {
+                    *member = to.to_string();
+                }
+            }
+        }
+        if self.disabled_plugins.remove(from) {
+            self.disabled_plugins.insert(to.to_string());
+        }
+    }
+
+    /// Enable or disable a bundle and, with it, its members
+    pub fn set_bundle_enabled(&mut self, bundle_id: &str, enabled: bool) -> Result<BundleTransition, String> {
+        self.bundle_mut(bundle_id)?;
+        Ok(self.transition(vec![(bundle_id.to_string(), enabled)]))
+    }
+
+    /// Switch to a profile
+    ///
+    /// Bundles bound to the profile are enabled and bundles bound only to
+    /// other profiles are disabled. Bundles without profiles are left alone.
+    pub fn apply_profile(&mut self, profile: &str) -> BundleTransition {
+        let changes = self
+            .bundles
+            .values()
+            .filter(|bundle| !bundle.profiles.is_empty())
+            .map(|bundle| (bundle.id.clone(), bundle.profiles.iter().any(|bound| bound == profile)))
+            .collect();
+
+        self.transition(changes)
+    }
+
+    /// Enable or disable a single plugin, regardless of its bundles
+    pub fn set_plugin_enabled(&mut self, plugin_id: &str, enabled: bool) -> BundleTransition {
+        let changed = if enabled {
+            self.disabled_plugins.remove(plugin_id)
+        } else {
+            self.disabled_plugins.insert(plugin_id.to_string())
+        };
+
+        match (changed, enabled) {
+            (false, _) => BundleTransition::default(),
+            (true, true) => BundleTransition {
+                disabled: Vec::new(),
+                enabled: vec![plugin_id.to_string()],
+            },
+            (true, false) => BundleTransition {
+                disabled: vec![plugin_id.to_string()],
+                enabled: Vec::new(),
+            },
+        }
+    }
+
+    /// Apply bundle toggles and work out which plugins change state
+    fn transition(&mut self, changes: Vec<(String, bool)>) -> BundleTransition {
+        for (bundle_id, enabled) in &changes {
+            if *enabled {
+                self.enabled_bundles.insert(bundle_id.clone());
+            } else {
+                self.enabled_bundles.remove(bundle_id);
+            }
+        }
+
+        // Members of the toggled bundles, in bundle then member order
+        let mut affected: Vec<&String> = Vec::new();
+        for (bundle_id, _) in &changes {
+            for member in self.bundles.get(bundle_id).map(|b| b.plugins.iter()).into_iter().flatten() {
+                if !affected.contains(&member) {
+                    affected.push(member);
+                }
+            }
+        }
+
+        let mut transition = BundleTransition::default();
+        for plugin_id in affected {
+            let wanted = self
+                .enabled_bundles
+                .iter()
+                .filter_map(|id| self.bundles.get(id))
+                .any(|bundle| bundle.plugins.contains(plugin_id));
+
+            match (wanted, self.disabled_plugins.contains(plugin_id)) {
+                (true, true) => transition.enabled.push(plugin_id.clone()),
+                (false, false) => transition.disabled.push(plugin_id.clone()),
+                _ => {}
+            }
+        }
+        transition.disabled.reverse();
+
+        for plugin_id in &transition.enabled {
+            self.disabled_plugins.remove(plugin_id);
+        }
+        self.disabled_plugins.extend(transition.disabled.iter().cloned());
+
+        transition
+    }
+
+    fn bundle_mut(&mut self, bundle_id: &str) -> Result<&mut PluginBundle, String> {
+        self.bundles
+            .get_mut(bundle_id)
+            .ok_or_else(|| format!("Bundle '{}' not found", bundle_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state() -> BundleState {
+        let mut state = BundleState::new();
+        state
+            .define(
+                PluginBundle::new("dev", "Dev tools")
+                    .with_plugin("github")
+                    .with_plugin("terminal")
+                    .with_plugin("docs")
+                    .bound_to("work"),
+            )
+            .unwrap();
+        state
+            .define(
+                PluginBundle::new("writing", "Writing")
+                    .with_plugin("docs")
+                    .with_plugin("thesaurus")
+                    .bound_to("home"),
+            )
+            .unwrap();
+        state
+    }
+
+    #[test]
+    fn test_renamed_plugins_keep_their_bundles() {
+        let mut state = state();
+        state.set_plugin_enabled("docs", false);
+
+        state.rename_plugin("docs", "docs-search");
+        assert_eq!(state.bundle("dev").unwrap().plugins, vec!["github", "terminal", "docs-search"]);
+        assert_eq!(state.bundle("writing").unwrap().plugins, vec!["docs-search", "thesaurus"]);
+        assert!(state.is_plugin_enabled("docs"));
+        assert!(!state.is_plugin_enabled("docs-search"));
+    }
+
+    #[test]
+    fn test_bundles_toggle_members() {
+        let mut state = state();
+
+        let transition = state.set_bundle_enabled("dev", false).unwrap();
+        // "docs" is still needed by the writing bundle
+        assert_eq!(transition.disabled, vec!["terminal", "github"]);
+        assert!(transition.enabled.is_empty());
+        assert!(!state.is_plugin_enabled("github"));
+        assert!(state.is_plugin_enabled("docs"));
+
+        let transition = state.set_bundle_enabled("dev", true).unwrap();
+        assert_eq!(transition.enabled, vec!["github", "terminal"]);
+        assert!(state.set_bundle_enabled("dev", true).unwrap().is_empty());
+        assert!(state.set_bundle_enabled("missing", true).is_err());
+    }
+
+    #[test]
+    fn test_profiles_swap_bundles() {
+        let mut state = state();
+
+        let transition = state.apply_profile("home");
+        assert_eq!(transition.disabled, vec!["terminal", "github"]);
+        assert!(state.is_bundle_enabled("writing"));
+
+        let transition = state.apply_profile("work");
+        assert_eq!(transition.disabled, vec!["thesaurus"]);
+        assert_eq!(transition.enabled, vec!["github", "terminal"]);
+
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(serde_json::from_str::<BundleState>(&json).unwrap(), state);
+    }
+}
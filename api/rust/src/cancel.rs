@@ -0,0 +1,190 @@
+/// Cooperative cancellation of queries
+///
+/// When the user keeps typing, answers to the previous keystroke are no
+/// longer wanted. The host attaches a `CancellationToken` to each query's
+/// `QueryContext` and cancels it when the query is superseded. Cancellation
+/// then propagates to every layer still working on the query:
+/// - `PluginRegistry::dispatch_query` stops waiting and drops the plugin
+///   futures
+/// - bridges to plugins running outside the launcher send a `cancel` message
+///   (see `protocol::methods::CANCEL`) for requests dropped before their
+///   response arrived, so the plugin host can stop working on them
+/// - long-running host functions, such as those called by WASM plugins, call
+///   `check` between units of work and bail out once the query is cancelled
+///
+/// ```ignore
+/// previous_query.cancel();
+/// let token = CancellationToken::new();
+/// let context = QueryContext::new(query).with_cancellation(token.clone());
+/// registry.dispatch_query(&context, &scheduler, &aggregator).await;
+/// ```
+use std::future::Future;
+use std::pin::{Pin, pin};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+/// Error returned for work stopped by cancellation
+pub const CANCELLED: &str = "Query cancelled";
+
+/// Shared flag telling everyone working on a query to stop
+///
+/// Cheap to clone; all clones share the same flag.
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<TokenInner>,
+}
+
+#[derive(Default)]
+struct TokenInner {
+    cancelled: AtomicBool,
+    /// Tasks waiting in `cancelled`
+    wakers: Mutex<Vec<Waker>>,
+}
+
+impl CancellationToken {
+    /// Create a token that isn't cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the work, waking every task waiting for it
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+
+        let wakers =
+            std::mem::take(&mut *self.inner.wakers.lock().unwrap_or_else(|p| p.into_inner()));
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    /// Cancel the work once a delay has passed
+    ///
+    /// Runtime-agnostic: the delay is waited for on a thread of its own.
+    pub fn cancel_after(&self, delay: Duration) {
+        let token = self.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(delay);
+            token.cancel();
+        });
+    }
+
+    /// Check if the work was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Fail once the work was cancelled
+    ///
+    /// Host functions call this between units of work so a cancelled query
+    /// stops burning CPU.
+    ///
+    /// # Returns
+    /// `Err(CANCELLED)` if the work was cancelled
+    pub fn check(&self) -> Result<(), String> {
+        if self.is_cancelled() {
+            return Err(CANCELLED.to_string());
+        }
+        Ok(())
+    }
+
+    /// Wait until the work is cancelled
+    pub fn cancelled(&self) -> Cancelled<'_> {
+        Cancelled { token: self }
+    }
+
+    /// Run a future until it completes or the work is cancelled
+    ///
+    /// The future is dropped as soon as the work is cancelled, which is how
+    /// bridges notice an abandoned request.
+    ///
+    /// # Returns
+    /// The output of the future, or None if the work was cancelled first
+    pub async fn run<F: Future>(&self, future: F) -> Option<F::Output> {
+        let mut future = pin!(future);
+        let mut cancelled = pin!(self.cancelled());
+
+        std::future::poll_fn(|cx| {
+            if cancelled.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(None);
+            }
+            future.as_mut().poll(cx).map(Some)
+        })
+        .await
+    }
+}
+
+impl std::fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+/// Two tokens are equal when they share the same flag
+impl PartialEq for CancellationToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+/// Future returned by `CancellationToken::cancelled`
+pub struct Cancelled<'a> {
+    token: &'a CancellationToken,
+}
+
+impl Future for Cancelled<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+
+        let mut wakers = self
+            .token
+            .inner
+            .wakers
+            .lock()
+            .unwrap_or_else(|p| p.into_inner());
+        if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+        drop(wakers);
+
+        // Cancelled while the waker was being registered
+        if self.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_cancel_stops_waiting_work() {
+        let token = CancellationToken::new();
+        assert!(token.check().is_ok());
+        assert_eq!(token.run(async { 42 }).await, Some(42));
+
+        let canceller = token.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            canceller.cancel();
+        });
+        assert_eq!(token.run(std::future::pending::<()>()).await, None);
+        assert_eq!(token.check().unwrap_err(), CANCELLED);
+
+        // Already cancelled work never starts
+        let mut started = false;
+        assert_eq!(token.run(async { started = true }).await, None);
+        assert!(!started);
+    }
+}
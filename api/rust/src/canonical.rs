@@ -0,0 +1,163 @@
+/// Canonical JSON serialization
+///
+/// The same result can reach the host through different backends: built
+/// from Rust structs, parsed from a TypeScript bridge, replayed from a trace.
+/// Their JSON differs in ways that don't change the content (key order,
+/// `1.0` against `1`, `null` against a missing field), so hashing it
+/// directly gives equal results different hashes. The canonical form
+/// removes those differences:
+///
+/// - object keys are sorted by their UTF-8 bytes
+/// - object members whose value is `null` are left out
+/// - integral numbers are written as integers (`1.0` becomes `1`, `-0.0`
+///   becomes `0`), others in their shortest round-tripping form
+/// - no whitespace is written
+///
+/// It is used for content hashes (`hash`), dedup keys
+/// (`PluginResult::dedup_key`), signature inputs (`PluginManifest::canonical_json`)
+/// and to compare the output of bridges in tests. Hashes are FNV-1a 128 and
+/// stable across platforms and releases, but not cryptographic: sign the
+/// canonical JSON itself, not its hash.
+use serde::Serialize;
+use serde_json::{Number, Value};
+
+/// Offset basis of 128-bit FNV-1a
+const FNV_OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+
+/// Prime of 128-bit FNV-1a
+const FNV_PRIME: u128 = 0x0000000001000000000000000000013b;
+
+/// Largest integer a double holds exactly
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+/// Serialize a value canonically
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, String> {
+    let value =
+        serde_json::to_value(value).map_err(|e| format!("Failed to serialize value: {}", e))?;
+    Ok(value_to_string(&value))
+}
+
+/// Write a JSON value canonically
+pub fn value_to_string(value: &Value) -> String {
+    let mut out = String::new();
+    write_value(value, &mut out);
+    out
+}
+
+/// Hash the canonical serialization of a value
+///
+/// # Returns
+/// The hash as 32 lowercase hex digits
+pub fn hash<T: Serialize + ?Sized>(value: &T) -> Result<String, String> {
+    to_string(value).map(|canonical| hash_str(&canonical))
+}
+
+/// Hash a string that is already canonical, see `hash`
+pub fn hash_str(canonical: &str) -> String {
+    let hash = canonical.bytes().fold(FNV_OFFSET, |hash, byte| {
+        (hash ^ u128::from(byte)).wrapping_mul(FNV_PRIME)
+    });
+    format!("{:032x}", hash)
+}
+
+fn write_value(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(value) => out.push_str(if *value { "true" } else { "false" }),
+        Value::Number(number) => write_number(number, out),
+        Value::String(text) => write_string(text, out),
+        Value::Array(items) => {
+            out.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_value(item, out);
+            }
+            out.push(']');
+        }
+        Value::Object(members) => {
+            let mut members: Vec<(&String, &Value)> = members
+                .iter()
+                .filter(|(_, value)| !value.is_null())
+                .collect();
+            members.sort_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+
+            out.push('{');
+            for (index, (key, value)) in members.into_iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_string(key, out);
+                out.push(':');
+                write_value(value, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_number(number: &Number, out: &mut String) {
+    if number.is_i64() || number.is_u64() {
+        out.push_str(&number.to_string());
+        return;
+    }
+
+    match number.as_f64() {
+        Some(float) if float.fract() == 0.0 && float.abs() <= MAX_SAFE_INTEGER => {
+            // Also turns -0.0 into 0
+            out.push_str(&(float as i64).to_string());
+        }
+        _ => out.push_str(&number.to_string()),
+    }
+}
+
+fn write_string(text: &str, out: &mut String) {
+    // Escaping strings can't fail
+    out.push_str(&serde_json::to_string(text).unwrap_or_default());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result::PluginResult;
+
+    #[test]
+    fn test_equal_content_serializes_identically() {
+        let a: Value =
+            serde_json::from_str(r#"{"b": 1.0, "a": [true, -0.0, 2.5], "c": null}"#).unwrap();
+        let b: Value = serde_json::from_str(r#"{"a":[true,0,2.5],"b":1}"#).unwrap();
+        assert_eq!(value_to_string(&a), r#"{"a":[true,0,2.5],"b":1}"#);
+        assert_eq!(hash(&a).unwrap(), hash(&b).unwrap());
+        assert_ne!(
+            hash(&a).unwrap(),
+            hash(&serde_json::json!({"b": 2})).unwrap()
+        );
+        assert_eq!(hash_str("").len(), 32);
+
+        // A result built in Rust and the same result sent by a bridge
+        let mut built = PluginResult::new("calc", "4");
+        built
+            .metadata
+            .insert("value".to_string(), serde_json::json!(4));
+        built
+            .metadata
+            .insert("expr".to_string(), serde_json::json!("2+2"));
+        let bridged: PluginResult = serde_json::from_str(
+            r#"{"title": "4", "id": "calc", "subtitle": null, "score": 0,
+                "data": {"expr": "2+2", "value": 4.0}}"#,
+        )
+        .unwrap();
+        assert_eq!(built.canonical_json(), bridged.canonical_json());
+        assert_eq!(built.content_hash(), bridged.content_hash());
+
+        // Ranking doesn't change what a result is
+        let ranked = PluginResult {
+            score: 90,
+            shortcut: Some(1),
+            ..bridged
+        };
+        assert_ne!(built.content_hash(), ranked.content_hash());
+        assert_eq!(built.dedup_key(), ranked.dedup_key());
+    }
+}
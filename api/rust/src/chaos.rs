@@ -0,0 +1,572 @@
+/// Fault injection for resilience testing
+///
+/// Wraps `VoltPluginAPI` and plugins so calls fail the way they do in the
+/// wild: IO errors, slow responses, poisoned locks and partially written
+/// files. Faults are drawn from a seeded generator, so a failing run can be
+/// replayed with the same seed.
+///
+/// Only compiled with the `chaos` feature; never enable it in release builds.
+use crate::api::VoltPluginAPI;
+use crate::extensions::{Annotator, Previewer, ResourceResolver, SendHandler, SettingsProvider, Suggester, UriHandler};
+use crate::feeds::DataFeed;
+use crate::keys::{KeyEvent, KeyResponse};
+use crate::outcome::ExecuteOutcome;
+use crate::plugin::{Plugin, QueryContext};
+use crate::protocol::NegotiatedProtocol;
+use crate::result::PluginResult;
+use async_trait::async_trait;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Kind of injected failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// The call fails as if the disk or network returned an error
+    IoError,
+    /// The call succeeds after an artificial delay
+    SlowResponse,
+    /// The API's internal lock is poisoned before the call
+    LockPoisoning,
+    /// Only part of the data is written before the call fails
+    PartialWrite,
+}
+
+impl Fault {
+    /// All fault kinds
+    pub const ALL: [Fault; 4] = [
+        Fault::IoError,
+        Fault::SlowResponse,
+        Fault::LockPoisoning,
+        Fault::PartialWrite,
+    ];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Probabilities and parameters of injected faults
+#[derive(Debug, Clone)]
+pub struct ChaosConfig {
+    /// Seed of the fault generator
+    pub seed: u64,
+    /// Probability of an IO error per call (0.0-1.0)
+    pub io_error_rate: f64,
+    /// Probability of a slow response per call (0.0-1.0)
+    pub slow_rate: f64,
+    /// Delay added to slow responses
+    pub slow_delay: Duration,
+    /// Probability of poisoning the API lock per call (0.0-1.0)
+    pub lock_poisoning_rate: f64,
+    /// Probability of a partial write per write call (0.0-1.0)
+    pub partial_write_rate: f64,
+}
+
+impl Default for ChaosConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0x5eed,
+            io_error_rate: 0.0,
+            slow_rate: 0.0,
+            slow_delay: Duration::from_millis(500),
+            lock_poisoning_rate: 0.0,
+            partial_write_rate: 0.0,
+        }
+    }
+}
+
+impl ChaosConfig {
+    /// Create a configuration injecting no faults
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            ..Self::default()
+        }
+    }
+
+    /// Set the probability of a fault
+    ///
+    /// # Arguments
+    /// * `fault` - Kind of fault
+    /// * `rate` - Probability per call, clamped to 0.0-1.0
+    pub fn with_rate(mut self, fault: Fault, rate: f64) -> Self {
+        let rate = rate.clamp(0.0, 1.0);
+        match fault {
+            Fault::IoError => self.io_error_rate = rate,
+            Fault::SlowResponse => self.slow_rate = rate,
+            Fault::LockPoisoning => self.lock_poisoning_rate = rate,
+            Fault::PartialWrite => self.partial_write_rate = rate,
+        }
+        self
+    }
+
+    /// Set the delay of slow responses
+    pub fn with_slow_delay(mut self, delay: Duration) -> Self {
+        self.slow_delay = delay;
+        self
+    }
+
+    fn rate(&self, fault: Fault) -> f64 {
+        match fault {
+            Fault::IoError => self.io_error_rate,
+            Fault::SlowResponse => self.slow_rate,
+            Fault::LockPoisoning => self.lock_poisoning_rate,
+            Fault::PartialWrite => self.partial_write_rate,
+        }
+    }
+}
+
+/// Seeded source of faults, shared by the API and plugin wrappers
+pub struct FaultInjector {
+    config: ChaosConfig,
+    rng: Mutex<u64>,
+    injected: [AtomicU64; 4],
+}
+
+impl FaultInjector {
+    /// Create an injector
+    pub fn new(config: ChaosConfig) -> Self {
+        // xorshift gets stuck on zero
+        let seed = config.seed.max(1);
+        Self {
+            config,
+            rng: Mutex::new(seed),
+            injected: Default::default(),
+        }
+    }
+
+    /// Get the configuration
+    pub fn config(&self) -> &ChaosConfig {
+        &self.config
+    }
+
+    /// Number of faults of a kind injected so far
+    pub fn injected(&self, fault: Fault) -> u64 {
+        self.injected[fault.index()].load(Ordering::Relaxed)
+    }
+
+    /// Decide whether to inject a fault on this call
+    pub fn roll(&self, fault: Fault) -> bool {
+        let rate = self.config.rate(fault);
+        if rate <= 0.0 {
+            return false;
+        }
+
+        let sample = {
+            let mut state = match self.rng.lock() {
+                Ok(state) => state,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+            *state ^= *state << 13;
+            *state ^= *state >> 7;
+            *state ^= *state << 17;
+            (*state >> 11) as f64 / (1u64 << 53) as f64
+        };
+
+        let hit = sample < rate;
+        if hit {
+            self.injected[fault.index()].fetch_add(1, Ordering::Relaxed);
+        }
+        hit
+    }
+
+    /// Roll for an IO error
+    fn io_error(&self, operation: &str) -> Result<(), String> {
+        if self.roll(Fault::IoError) {
+            return Err(format!("Failed to {}: injected IO error", operation));
+        }
+        Ok(())
+    }
+
+    /// Roll for a slow response and block for the configured delay
+    fn slow_blocking(&self) {
+        if self.roll(Fault::SlowResponse) {
+            std::thread::sleep(self.config.slow_delay);
+        }
+    }
+
+    /// Roll for a slow response and sleep for the configured delay
+    async fn slow(&self) {
+        if self.roll(Fault::SlowResponse) {
+            tokio::time::sleep(self.config.slow_delay).await;
+        }
+    }
+}
+
+// ========== API Wrapper ==========
+
+/// `VoltPluginAPI` wrapper injecting faults into file system calls
+pub struct ChaosApi {
+    api: VoltPluginAPI,
+    injector: FaultInjector,
+}
+
+impl ChaosApi {
+    /// Wrap an API instance
+    pub fn new(api: VoltPluginAPI, config: ChaosConfig) -> Self {
+        Self {
+            api,
+            injector: FaultInjector::new(config),
+        }
+    }
+
+    /// Get the wrapped API, without fault injection
+    pub fn inner(&self) -> &VoltPluginAPI {
+        &self.api
+    }
+
+    /// Get the fault injector
+    pub fn injector(&self) -> &FaultInjector {
+        &self.injector
+    }
+
+    /// Apply the faults common to every call
+    fn before_call(&self, operation: &str) -> Result<(), String> {
+        if self.injector.roll(Fault::LockPoisoning) {
+            self.api.poison_state();
+        }
+        self.injector.slow_blocking();
+        self.injector.io_error(operation)
+    }
+
+    /// See [`VoltPluginAPI::get_plugin_data_dir`]
+    pub fn get_plugin_data_dir(&self, plugin_id: &str) -> Result<PathBuf, String> {
+        self.before_call("create plugin directory")?;
+        self.api.get_plugin_data_dir(plugin_id)
+    }
+
+    /// See [`VoltPluginAPI::load_config`]
+    pub fn load_config(&self, plugin_id: &str, config_name: &str) -> Result<serde_json::Value, String> {
+        self.before_call("read config")?;
+        self.api.load_config(plugin_id, config_name)
+    }
+
+    /// See [`VoltPluginAPI::save_config`]
+    ///
+    /// A partial write leaves a truncated file behind, like a crash in the
+    /// middle of `std::fs::write` would.
+    pub fn save_config(
+        &self,
+        plugin_id: &str,
+        config_name: &str,
+        config: &serde_json::Value,
+    ) -> Result<(), String> {
+        self.before_call("write config")?;
+
+        if self.injector.roll(Fault::PartialWrite) {
+            let content = serde_json::to_string_pretty(config)
+                .map_err(|e| format!("Failed to serialize config: {}", e))?;
+            let config_path = self
+                .api
+                .get_plugin_config_dir(plugin_id)?
+                .join(format!("{}.json", config_name));
+            write_truncated(&config_path, content.as_bytes())?;
+            return Err("Failed to write config: injected partial write".to_string());
+        }
+
+        self.api.save_config(plugin_id, config_name, config)
+    }
+
+    /// See [`VoltPluginAPI::read_cache`]
+    pub fn read_cache(&self, plugin_id: &str, cache_key: &str) -> Result<Vec<u8>, String> {
+        self.before_call("read cache")?;
+        self.api.read_cache(plugin_id, cache_key)
+    }
+
+    /// See [`VoltPluginAPI::write_cache`]
+    pub fn write_cache(&self, plugin_id: &str, cache_key: &str, data: &[u8]) -> Result<(), String> {
+        self.before_call("write cache")?;
+
+        if self.injector.roll(Fault::PartialWrite) {
+            self.api
+                .write_cache(plugin_id, cache_key, &data[..data.len() / 2])?;
+            return Err("Failed to write cache: injected partial write".to_string());
+        }
+
+        self.api.write_cache(plugin_id, cache_key, data)
+    }
+
+    /// See [`VoltPluginAPI::clear_cache`]
+    pub fn clear_cache(&self, plugin_id: &str) -> Result<(), String> {
+        self.before_call("clear cache")?;
+        self.api.clear_cache(plugin_id)
+    }
+}
+
+fn write_truncated(path: &std::path::Path, content: &[u8]) -> Result<(), String> {
+    std::fs::write(path, &content[..content.len() / 2])
+        .map_err(|e| format!("Failed to write config: {}", e))
+}
+
+// ========== Plugin Wrapper ==========
+
+/// Plugin wrapper injecting faults into the dispatch path
+///
+/// Register it in place of the wrapped plugin to see how the host copes
+/// with a misbehaving plugin.
+pub struct ChaosPlugin {
+    plugin: Box<dyn Plugin>,
+    injector: FaultInjector,
+}
+
+impl ChaosPlugin {
+    /// Wrap a plugin
+    pub fn new(plugin: Box<dyn Plugin>, config: ChaosConfig) -> Self {
+        Self {
+            plugin,
+            injector: FaultInjector::new(config),
+        }
+    }
+
+    /// Get the fault injector
+    pub fn injector(&self) -> &FaultInjector {
+        &self.injector
+    }
+}
+
+#[async_trait]
+impl Plugin for ChaosPlugin {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self.plugin.as_any()
+    }
+
+    fn id(&self) -> &str {
+        self.plugin.id()
+    }
+
+    fn name(&self) -> &str {
+        self.plugin.name()
+    }
+
+    fn description(&self) -> &str {
+        self.plugin.description()
+    }
+
+    fn version(&self) -> Option<&str> {
+        self.plugin.version()
+    }
+
+    fn author(&self) -> Option<&str> {
+        self.plugin.author()
+    }
+
+    fn homepage(&self) -> Option<&str> {
+        self.plugin.homepage()
+    }
+
+    fn license(&self) -> Option<&str> {
+        self.plugin.license()
+    }
+
+    fn protocol(&self) -> Option<NegotiatedProtocol> {
+        self.plugin.protocol()
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.plugin.is_enabled()
+    }
+
+    fn initialize(&self) -> Result<(), String> {
+        self.injector.slow_blocking();
+        self.injector.io_error("initialize plugin")?;
+        self.plugin.initialize()
+    }
+
+    fn on_enabled(&self) -> Result<(), String> {
+        self.injector.io_error("enable plugin")?;
+        self.plugin.on_enabled()
+    }
+
+    fn on_disabled(&self) -> Result<(), String> {
+        self.injector.io_error("disable plugin")?;
+        self.plugin.on_disabled()
+    }
+
+    fn can_handle(&self, context: &QueryContext) -> bool {
+        self.plugin.can_handle(context)
+    }
+
+    async fn match_query(&self, context: &QueryContext) -> Result<Vec<PluginResult>, String> {
+        self.injector.slow().await;
+        self.injector.io_error("match query")?;
+        self.plugin.match_query(context).await
+    }
+
+    fn fallback_results(&self, context: &QueryContext) -> Vec<PluginResult> {
+        self.plugin.fallback_results(context)
+    }
+
+    fn preferred_batch_size(&self) -> Option<usize> {
+        self.plugin.preferred_batch_size()
+    }
+
+    async fn execute(&self, result: &PluginResult) -> Result<ExecuteOutcome, String> {
+        self.injector.slow().await;
+        self.injector.io_error("execute result")?;
+        self.plugin.execute(result).await
+    }
+
+    async fn on_key_event(&self, event: &KeyEvent, context: &QueryContext) -> Result<KeyResponse, String> {
+        self.injector.slow().await;
+        self.injector.io_error("handle key event")?;
+        self.plugin.on_key_event(event, context).await
+    }
+
+    fn on_config_changed(&self, config_name: &str, config: &serde_json::Value) -> Result<(), String> {
+        self.injector.slow_blocking();
+        self.injector.io_error("apply config")?;
+        self.plugin.on_config_changed(config_name, config)
+    }
+
+    fn data_feeds(&self) -> Vec<DataFeed> {
+        self.plugin.data_feeds()
+    }
+
+    fn on_feed_updated(&self, feed_id: &str, path: &std::path::Path) -> Result<(), String> {
+        self.injector.slow_blocking();
+        self.injector.io_error("apply feed update")?;
+        self.plugin.on_feed_updated(feed_id, path)
+    }
+
+    fn warm_up(&self, task: &crate::warmup::WarmupTask) -> Result<(), String> {
+        self.injector.slow_blocking();
+        self.injector.io_error("warm up")?;
+        self.plugin.warm_up(task)
+    }
+
+    fn on_notification_action(&self, notification_id: &str, action_id: &str) -> Result<(), String> {
+        self.injector.io_error("handle notification action")?;
+        self.plugin.on_notification_action(notification_id, action_id)
+    }
+
+    fn on_privacy_mode_changed(&self, enabled: bool) {
+        self.plugin.on_privacy_mode_changed(enabled)
+    }
+
+    fn purge_recorded_since(&self, since: u64) -> Result<(), String> {
+        self.injector.io_error("purge recorded data")?;
+        self.plugin.purge_recorded_since(since)
+    }
+
+    fn as_suggester(&self) -> Option<&dyn Suggester> {
+        self.plugin.as_suggester()
+    }
+
+    fn as_previewer(&self) -> Option<&dyn Previewer> {
+        self.plugin.as_previewer()
+    }
+
+    fn as_uri_handler(&self) -> Option<&dyn UriHandler> {
+        self.plugin.as_uri_handler()
+    }
+
+    fn as_send_handler(&self) -> Option<&dyn SendHandler> {
+        self.plugin.as_send_handler()
+    }
+
+    fn as_annotator(&self) -> Option<&dyn Annotator> {
+        self.plugin.as_annotator()
+    }
+
+    fn as_settings_provider(&self) -> Option<&dyn SettingsProvider> {
+        self.plugin.as_settings_provider()
+    }
+
+    fn as_resource_resolver(&self) -> Option<&dyn ResourceResolver> {
+        self.plugin.as_resource_resolver()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_faults_are_reproducible() {
+        let config = ChaosConfig::new(42).with_rate(Fault::IoError, 0.3);
+        let first = FaultInjector::new(config.clone());
+        let second = FaultInjector::new(config);
+
+        let a: Vec<bool> = (0..200).map(|_| first.roll(Fault::IoError)).collect();
+        let b: Vec<bool> = (0..200).map(|_| second.roll(Fault::IoError)).collect();
+        assert_eq!(a, b);
+
+        let hits = first.injected(Fault::IoError);
+        assert!((30..90).contains(&hits), "{} hits", hits);
+        assert!(!first.roll(Fault::PartialWrite));
+    }
+
+    #[test]
+    fn test_partial_write_leaves_truncated_config() {
+        let temp_dir = env::temp_dir().join("volt_test_chaos_partial");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let chaos = ChaosApi::new(
+            VoltPluginAPI::new(temp_dir.clone()),
+            ChaosConfig::new(1).with_rate(Fault::PartialWrite, 1.0),
+        );
+
+        let config = serde_json::json!({"theme": "dark", "fontSize": 14});
+        assert!(chaos.save_config("test_plugin", "settings", &config).is_err());
+
+        let error = chaos.inner().load_config("test_plugin", "settings").unwrap_err();
+        assert!(error.starts_with("Failed to parse config"));
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn test_lock_poisoning() {
+        let chaos = ChaosApi::new(
+            VoltPluginAPI::new(env::temp_dir().join("volt_test_chaos_poison")),
+            ChaosConfig::new(1).with_rate(Fault::LockPoisoning, 1.0),
+        );
+
+        // The API recovers the poisoned lock instead of failing forever
+        let before = crate::locks::poison_recoveries();
+        assert!(chaos.get_plugin_data_dir("test_plugin").is_ok());
+        assert!(chaos.get_plugin_data_dir("test_plugin").is_ok());
+        assert_eq!(chaos.injector().injected(Fault::LockPoisoning), 2);
+        assert!(crate::locks::poison_recoveries() >= before + 2);
+    }
+
+    #[tokio::test]
+    async fn test_plugin_dispatch_faults() {
+        struct Stub;
+
+        #[async_trait]
+        impl Plugin for Stub {
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+
+            fn id(&self) -> &str {
+                "stub"
+            }
+
+            fn name(&self) -> &str {
+                "Stub"
+            }
+
+            fn description(&self) -> &str {
+                "Returns nothing"
+            }
+
+            fn preferred_batch_size(&self) -> Option<usize> {
+                Some(3)
+            }
+        }
+
+        let plugin = ChaosPlugin::new(
+            Box::new(Stub),
+            ChaosConfig::new(7).with_rate(Fault::IoError, 1.0),
+        );
+        let error = plugin.match_query(&QueryContext::new("x")).await.unwrap_err();
+        assert_eq!(error, "Failed to match query: injected IO error");
+        assert_eq!(plugin.id(), "stub");
+        assert_eq!(plugin.preferred_batch_size(), Some(3));
+    }
+}
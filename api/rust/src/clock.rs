@@ -0,0 +1,59 @@
+/// Wall clock of the plugin API
+///
+/// Timestamps the API writes (usage statistics, recent actions, audit
+/// entries, log lines, export names) are read from a `Clock`, so hosts and
+/// tests can pin time with a `ManualClock` through
+/// `VoltPluginAPI::builder().with_clock(...)`.
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Source of the current time
+pub trait Clock: Send + Sync {
+    /// Current time in milliseconds since the Unix epoch
+    fn now_millis(&self) -> u64;
+}
+
+/// Reads the system clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> u64 {
+        crate::actions::now_millis()
+    }
+}
+
+/// Clock that only moves when told to
+///
+/// Cheap to clone; all clones share the same time.
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    millis: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    /// Create a clock stopped at `millis` since the Unix epoch
+    pub fn new(millis: u64) -> Self {
+        Self {
+            millis: Arc::new(AtomicU64::new(millis)),
+        }
+    }
+
+    /// Set the time, in milliseconds since the Unix epoch
+    pub fn set(&self, millis: u64) {
+        self.millis.store(millis, Ordering::SeqCst);
+    }
+
+    /// Move the time forward
+    pub fn advance(&self, by: Duration) {
+        self.millis
+            .fetch_add(by.as_millis() as u64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_millis(&self) -> u64 {
+        self.millis.load(Ordering::SeqCst)
+    }
+}
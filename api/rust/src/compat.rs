@@ -0,0 +1,348 @@
+/// Compatibility with plugins built against older API versions
+///
+/// Each breaking change of the `Plugin` trait bumps `CURRENT_API_VERSION`.
+/// Plugins built against a previous version keep working through adapters
+/// implementing the current trait on top of the old one, so an API bump
+/// doesn't break every compiled plugin at once.
+///
+/// Dynamic libraries and WASM modules declare the version they target with
+/// `declare_api_version!()`, which embeds `VERSION_MARKER` in the binary;
+/// `detect_api_version` finds it again without loading the plugin. Binaries
+/// without a marker predate versioning and target v1.
+use crate::outcome::ExecuteOutcome;
+use crate::plugin::{Plugin, QueryContext};
+use crate::result::PluginResult;
+use crate::watchdog::Watchdog;
+use async_trait::async_trait;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+/// API version implemented by this crate's `Plugin` trait
+pub const CURRENT_API_VERSION: u32 = 2;
+
+/// Oldest API version the host can still load through an adapter
+pub const MIN_SUPPORTED_API_VERSION: u32 = 1;
+
+/// Prefix of the version marker embedded in plugin binaries
+pub const VERSION_MARKER_PREFIX: &[u8] = b"volt-plugin-api-version=";
+
+/// Marker embedded by `declare_api_version!()`, NUL-terminated
+pub const VERSION_MARKER: &[u8] = b"volt-plugin-api-version=2\0";
+
+/// Declare the API version a dynamic or WASM plugin targets
+///
+/// Invoke once at the root of the plugin crate:
+///
+/// ```ignore
+/// volt_plugin_api::declare_api_version!();
+/// ```
+#[macro_export]
+macro_rules! declare_api_version {
+    () => {
+        #[used]
+        #[unsafe(no_mangle)]
+        pub static VOLT_PLUGIN_API_VERSION: [u8; $crate::compat::VERSION_MARKER.len()] = {
+            let mut marker = [0u8; $crate::compat::VERSION_MARKER.len()];
+            let mut i = 0;
+            while i < marker.len() {
+                marker[i] = $crate::compat::VERSION_MARKER[i];
+                i += 1;
+            }
+            marker
+        };
+    };
+}
+
+/// Version of the plugin API a plugin targets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ApiVersion(pub u32);
+
+impl ApiVersion {
+    /// Version implemented by this crate
+    pub const CURRENT: ApiVersion = ApiVersion(CURRENT_API_VERSION);
+
+    /// Check if the host can load plugins targeting this version
+    pub fn is_supported(&self) -> bool {
+        (MIN_SUPPORTED_API_VERSION..=CURRENT_API_VERSION).contains(&self.0)
+    }
+
+    /// Check if plugins targeting this version need an adapter
+    pub fn needs_adapter(&self) -> bool {
+        self.0 < CURRENT_API_VERSION
+    }
+
+    /// Check that the host can load plugins targeting this version
+    ///
+    /// # Returns
+    /// Ok(()) if supported, Err with a user-facing reason otherwise
+    pub fn check(&self) -> Result<(), String> {
+        if self.0 > CURRENT_API_VERSION {
+            return Err(format!(
+                "requires plugin API {}, but this version of Volt supports up to {}",
+                self, CURRENT_API_VERSION
+            ));
+        }
+        if self.0 < MIN_SUPPORTED_API_VERSION {
+            return Err(format!(
+                "targets plugin API {}, which is no longer supported (oldest supported: {})",
+                self, MIN_SUPPORTED_API_VERSION
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "v{}", self.0)
+    }
+}
+
+/// Find the API version declared in a plugin binary
+///
+/// # Returns
+/// The version of the first marker found, or None for binaries without one
+pub fn detect_api_version(binary: &[u8]) -> Option<ApiVersion> {
+    binary
+        .windows(VERSION_MARKER_PREFIX.len())
+        .enumerate()
+        .filter(|(_, window)| *window == VERSION_MARKER_PREFIX)
+        .find_map(|(start, _)| {
+            let digits: &[u8] = &binary[start + VERSION_MARKER_PREFIX.len()..];
+            let end = digits.iter().position(|byte| !byte.is_ascii_digit())?;
+            if end == 0 || digits[end] != 0 {
+                return None;
+            }
+            std::str::from_utf8(&digits[..end]).ok()?.parse().ok().map(ApiVersion)
+        })
+}
+
+/// Find the API version declared in a plugin file
+///
+/// # Returns
+/// The declared version, or v1 for binaries without a marker
+pub fn detect_api_version_of_file(path: &Path) -> Result<ApiVersion, String> {
+    let binary = std::fs::read(path).map_err(|e| format!("Failed to read plugin binary: {}", e))?;
+
+    Ok(detect_api_version(&binary).unwrap_or(ApiVersion(1)))
+}
+
+// ========== v1 ==========
+
+/// The plugin trait of API v1
+///
+/// v1 plugins matched on the raw query string, answered synchronously,
+/// couldn't report failures and always closed the window after executing.
+pub trait PluginV1: Send + Sync + 'static {
+    /// Unique identifier of the plugin
+    fn id(&self) -> &str;
+
+    /// Human-readable name
+    fn name(&self) -> &str;
+
+    /// Short description
+    fn description(&self) -> &str;
+
+    /// Check if this plugin should handle the query
+    fn can_handle(&self, query: &str) -> bool;
+
+    /// Generate results for the query
+    fn match_query(&self, query: &str) -> Vec<PluginResult>;
+
+    /// Execute the action for one of this plugin's results
+    fn execute(&self, result: &PluginResult);
+}
+
+/// Runs a v1 plugin as a current `Plugin`
+///
+/// Panics in the wrapped plugin are caught by the registry like those of
+/// any other plugin. With a watchdog, `match_query` and `execute` run on
+/// watched worker threads, so a v1 plugin blocking forever only fails its
+/// own calls (see `crate::watchdog`).
+pub struct V1Adapter<P: PluginV1> {
+    plugin: Arc<P>,
+    watchdog: Option<Watchdog>,
+}
+
+impl<P: PluginV1> V1Adapter<P> {
+    /// Wrap a v1 plugin
+    pub fn new(plugin: P) -> Self {
+        Self {
+            plugin: Arc::new(plugin),
+            watchdog: None,
+        }
+    }
+
+    /// Wrap a v1 plugin, running its calls under a watchdog
+    ///
+    /// Pass `PluginRegistry::watchdog`, so hangs show in the registry.
+    pub fn watched(plugin: P, watchdog: Watchdog) -> Self {
+        Self {
+            plugin: Arc::new(plugin),
+            watchdog: Some(watchdog),
+        }
+    }
+
+    /// Get the wrapped plugin
+    pub fn inner(&self) -> &P {
+        &self.plugin
+    }
+}
+
+#[async_trait]
+impl<P: PluginV1> Plugin for V1Adapter<P> {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn id(&self) -> &str {
+        self.plugin.id()
+    }
+
+    fn name(&self) -> &str {
+        self.plugin.name()
+    }
+
+    fn description(&self) -> &str {
+        self.plugin.description()
+    }
+
+    fn can_handle(&self, context: &QueryContext) -> bool {
+        self.plugin.can_handle(&context.query)
+    }
+
+    async fn match_query(&self, context: &QueryContext) -> Result<Vec<PluginResult>, String> {
+        let mut results = match &self.watchdog {
+            Some(watchdog) => {
+                let plugin = self.plugin.clone();
+                let query = context.query.clone();
+                watchdog
+                    .run(self.plugin.id(), "match_query", move || plugin.match_query(&query))
+                    .await?
+            }
+            None => self.plugin.match_query(&context.query),
+        };
+        // v1 plugins didn't know about the display limit
+        if let Some(max_results) = context.max_results {
+            results.truncate(max_results);
+        }
+        Ok(results)
+    }
+
+    async fn execute(&self, result: &PluginResult) -> Result<ExecuteOutcome, String> {
+        match &self.watchdog {
+            Some(watchdog) => {
+                let plugin = self.plugin.clone();
+                let result = result.clone();
+                watchdog
+                    .run(self.plugin.id(), "execute", move || plugin.execute(&result))
+                    .await?
+            }
+            None => self.plugin.execute(result),
+        }
+        Ok(ExecuteOutcome::default())
+    }
+}
+
+/// Wrap a v1 plugin for registration
+pub fn adapt_v1(plugin: impl PluginV1) -> Box<dyn Plugin + Send + Sync> {
+    Box::new(V1Adapter::new(plugin))
+}
+
+/// Wrap a v1 plugin for registration, running its calls under a watchdog
+pub fn adapt_v1_watched(plugin: impl PluginV1, watchdog: Watchdog) -> Box<dyn Plugin + Send + Sync> {
+    Box::new(V1Adapter::watched(plugin, watchdog))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct LegacyPlugin {
+        executed: AtomicUsize,
+    }
+
+    impl PluginV1 for LegacyPlugin {
+        fn id(&self) -> &str {
+            "legacy"
+        }
+
+        fn name(&self) -> &str {
+            "Legacy"
+        }
+
+        fn description(&self) -> &str {
+            "Built against API v1"
+        }
+
+        fn can_handle(&self, query: &str) -> bool {
+            query.starts_with("old")
+        }
+
+        fn match_query(&self, query: &str) -> Vec<PluginResult> {
+            (0..5).map(|i| PluginResult::new(i.to_string(), query.to_string())).collect()
+        }
+
+        fn execute(&self, _result: &PluginResult) {
+            self.executed.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_v1_adapter() {
+        let plugin = adapt_v1(LegacyPlugin::default());
+        assert_eq!(plugin.id(), "legacy");
+        assert!(plugin.can_handle(&QueryContext::new("old stuff")));
+        assert!(!plugin.can_handle(&QueryContext::new("new stuff")));
+
+        let results = plugin
+            .match_query(&QueryContext::new("old stuff").with_max_results(3))
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].title, "old stuff");
+
+        plugin.execute(&results[0]).await.unwrap();
+        let adapter = plugin.as_any().downcast_ref::<V1Adapter<LegacyPlugin>>().unwrap();
+        assert_eq!(adapter.inner().executed.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_detect_api_version() {
+        let mut binary = b"\0asm junk volt-plugin-api-version=x\0 more".to_vec();
+        assert_eq!(detect_api_version(&binary), None);
+
+        binary.extend_from_slice(VERSION_MARKER);
+        assert_eq!(detect_api_version(&binary), Some(ApiVersion::CURRENT));
+        assert_eq!(detect_api_version(b"volt-plugin-api-version=17\0"), Some(ApiVersion(17)));
+        assert_eq!(detect_api_version(b"volt-plugin-api-version=1"), None);
+
+        let marker = format!("volt-plugin-api-version={}\0", CURRENT_API_VERSION);
+        assert_eq!(VERSION_MARKER, marker.as_bytes());
+    }
+
+    mod declared {
+        crate::declare_api_version!();
+    }
+
+    #[test]
+    fn test_declared_marker_is_detected() {
+        assert_eq!(&declared::VOLT_PLUGIN_API_VERSION[..], VERSION_MARKER);
+        assert_eq!(
+            detect_api_version(&declared::VOLT_PLUGIN_API_VERSION),
+            Some(ApiVersion::CURRENT)
+        );
+    }
+
+    #[test]
+    fn test_version_support() {
+        assert!(ApiVersion(1).is_supported() && ApiVersion(1).needs_adapter());
+        assert!(ApiVersion::CURRENT.is_supported() && !ApiVersion::CURRENT.needs_adapter());
+        assert!(ApiVersion(0).check().unwrap_err().contains("no longer supported"));
+        assert!(ApiVersion(3).check().unwrap_err().contains("supports up to 2"));
+    }
+}
@@ -0,0 +1,161 @@
+/// Crash reports for out-of-process plugins
+///
+/// Bridges running plugins in a subprocess or WASM sandbox feed a
+/// `CrashRecorder` with the plugin's stderr and protocol traffic. When the
+/// plugin dies, the recorder turns that context into a `CrashReport`,
+/// which the registry stores under the plugin's data directory and exposes
+/// through its snapshot.
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+/// Number of stderr lines kept for a crash report
+pub const STDERR_TAIL_LINES: usize = 100;
+
+/// Number of protocol messages kept for a crash report
+pub const LAST_MESSAGES: usize = 20;
+
+/// Longest stderr line or protocol message kept, in bytes
+const MAX_ENTRY_LEN: usize = 2048;
+
+/// Why an external plugin stopped
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum CrashCause {
+    /// The plugin process exited
+    Exit {
+        /// Exit code, if the process exited normally
+        code: Option<i32>,
+        /// Signal that killed the process (Unix only)
+        signal: Option<i32>,
+    },
+    /// The WASM module trapped
+    Trap {
+        /// Trap reason reported by the runtime
+        reason: String,
+    },
+    /// The plugin stopped answering and was killed
+    Unresponsive,
+}
+
+/// Everything known about a plugin crash
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReport {
+    /// Plugin that crashed
+    pub plugin_id: String,
+    /// Time of the crash, in milliseconds since the Unix epoch
+    pub crashed_at: u64,
+    /// Why the plugin stopped
+    pub cause: CrashCause,
+    /// Last lines the plugin wrote to stderr, oldest first
+    pub stderr_tail: Vec<String>,
+    /// Last protocol messages exchanged, oldest first, prefixed with "->" (sent) or "<-" (received)
+    pub last_messages: Vec<String>,
+}
+
+impl CrashReport {
+    /// Write the report as JSON into a directory
+    ///
+    /// # Returns
+    /// Path of the written report
+    pub fn write_to(&self, dir: &Path) -> Result<PathBuf, String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create crash directory: {}", e))?;
+
+        let path = dir.join(format!("crash-{}.json", self.crashed_at));
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize crash report: {}", e))?;
+
+        std::fs::write(&path, content).map_err(|e| format!("Failed to write crash report: {}", e))?;
+        Ok(path)
+    }
+}
+
+/// Rolling context kept by a bridge while an external plugin runs
+#[derive(Debug, Clone, Default)]
+pub struct CrashRecorder {
+    stderr: VecDeque<String>,
+    messages: VecDeque<String>,
+}
+
+impl CrashRecorder {
+    /// Create an empty recorder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a line the plugin wrote to stderr
+    pub fn stderr_line(&mut self, line: &str) {
+        push_bounded(&mut self.stderr, line.trim_end().to_string(), STDERR_TAIL_LINES);
+    }
+
+    /// Record a protocol message sent to the plugin
+    pub fn sent(&mut self, message: &str) {
+        push_bounded(&mut self.messages, format!("-> {}", message), LAST_MESSAGES);
+    }
+
+    /// Record a protocol message received from the plugin
+    pub fn received(&mut self, message: &str) {
+        push_bounded(&mut self.messages, format!("<- {}", message), LAST_MESSAGES);
+    }
+
+    /// Build the report for a crash
+    pub fn report(&self, plugin_id: &str, cause: CrashCause) -> CrashReport {
+        CrashReport {
+            plugin_id: plugin_id.to_string(),
+            crashed_at: crate::actions::now_millis(),
+            cause,
+            stderr_tail: self.stderr.iter().cloned().collect(),
+            last_messages: self.messages.iter().cloned().collect(),
+        }
+    }
+}
+
+fn push_bounded(entries: &mut VecDeque<String>, mut entry: String, capacity: usize) {
+    if entry.len() > MAX_ENTRY_LEN {
+        let mut end = MAX_ENTRY_LEN;
+        while !entry.is_char_boundary(end) {
+            end -= 1;
+        }
+        entry.truncate(end);
+        entry.push('…');
+    }
+
+    if entries.len() == capacity {
+        entries.pop_front();
+    }
+    entries.push_back(entry);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recorder_keeps_recent_context() {
+        let mut recorder = CrashRecorder::new();
+        for i in 0..(STDERR_TAIL_LINES + 5) {
+            recorder.stderr_line(&format!("line {}\n", i));
+        }
+        recorder.sent(r#"{"method":"match_query"}"#);
+        recorder.received(&"x".repeat(MAX_ENTRY_LEN + 10));
+
+        let report = recorder.report(
+            "notes",
+            CrashCause::Exit {
+                code: Some(101),
+                signal: None,
+            },
+        );
+        assert_eq!(report.stderr_tail.len(), STDERR_TAIL_LINES);
+        assert_eq!(report.stderr_tail[0], "line 5");
+        assert_eq!(report.last_messages[0], r#"-> {"method":"match_query"}"#);
+        assert!(report.last_messages[1].ends_with('…'));
+
+        let dir = std::env::temp_dir().join("volt_test_crash_report");
+        let path = report.write_to(&dir).unwrap();
+        let written: CrashReport = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(written, report);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
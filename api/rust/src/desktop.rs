@@ -0,0 +1,564 @@
+/// Linux desktop entries
+///
+/// Parses freedesktop.org `.desktop` files into launch targets, including
+/// their desktop actions ("New Private Window") and Flatpak or Snap
+/// packaging, so app-launcher plugins start sandboxed apps through the
+/// right runtime.
+use crate::apps::{self, AppTarget};
+use crate::result::{PluginResult, ResultAction};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Group holding the main entry
+const MAIN_GROUP: &str = "Desktop Entry";
+
+/// Prefix of desktop action groups
+const ACTION_GROUP_PREFIX: &str = "Desktop Action ";
+
+/// Prefix of the result action IDs generated for desktop actions
+pub const DESKTOP_ACTION_PREFIX: &str = "desktop-action:";
+
+/// How an application is packaged
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Packaging {
+    /// Installed by the system package manager
+    Native,
+    /// Flatpak app, launched with `flatpak run`
+    Flatpak {
+        /// Flatpak application ID, e.g. "org.mozilla.firefox"
+        app_id: String,
+    },
+    /// Snap package, launched with `snap run`
+    Snap {
+        /// Snap name, e.g. "firefox"
+        name: String,
+    },
+}
+
+/// An additional action declared by a desktop entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DesktopAction {
+    /// Action identifier from the `Actions` key
+    pub id: String,
+    /// Human-readable name
+    pub name: String,
+    /// Command line, with field codes
+    pub exec: String,
+    /// Icon name or path
+    pub icon: Option<String>,
+}
+
+/// A parsed `.desktop` file of type Application
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DesktopEntry {
+    /// Desktop file ID, e.g. "firefox.desktop"
+    pub id: String,
+    /// Path of the `.desktop` file
+    pub path: PathBuf,
+    /// Application name
+    pub name: String,
+    /// Short description
+    pub comment: Option<String>,
+    /// Command line, with field codes
+    pub exec: String,
+    /// Icon name or path
+    pub icon: Option<String>,
+    /// Directory to start in
+    pub working_dir: Option<PathBuf>,
+    /// Hidden from menus (`NoDisplay` or `Hidden`)
+    pub hidden: bool,
+    /// Additional actions, in declaration order
+    pub actions: Vec<DesktopAction>,
+    /// How the application is packaged
+    pub packaging: Packaging,
+    /// MIME types the application opens, e.g. "text/plain"
+    pub mime_types: Vec<String>,
+}
+
+impl DesktopEntry {
+    /// Read and parse a `.desktop` file
+    ///
+    /// # Returns
+    /// None if the file isn't an application entry with a command line
+    pub fn open(path: &Path) -> Result<Option<Self>, String> {
+        let content =
+            std::fs::read_to_string(path).map_err(|e| format!("Failed to read desktop entry: {}", e))?;
+        Ok(Self::parse(&content, path))
+    }
+
+    /// Parse the contents of a `.desktop` file
+    pub fn parse(content: &str, path: &Path) -> Option<Self> {
+        let groups = parse_groups(content);
+        let main = groups.get(MAIN_GROUP)?;
+
+        if main.get("Type").map(String::as_str) != Some("Application") {
+            return None;
+        }
+
+        let actions = main
+            .get("Actions")
+            .map(|ids| split_list(ids))
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|id| {
+                let group = groups.get(&format!("{}{}", ACTION_GROUP_PREFIX, id))?;
+                Some(DesktopAction {
+                    name: group.get("Name")?.clone(),
+                    exec: group.get("Exec")?.clone(),
+                    icon: group.get("Icon").cloned(),
+                    id,
+                })
+            })
+            .collect();
+
+        let is_true = |key: &str| main.get(key).map(String::as_str) == Some("true");
+        let id = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        Some(Self {
+            packaging: detect_packaging(main, path, &id),
+            name: main.get("Name")?.clone(),
+            comment: main.get("Comment").cloned(),
+            exec: main.get("Exec")?.clone(),
+            icon: main.get("Icon").cloned(),
+            working_dir: main.get("Path").map(PathBuf::from),
+            hidden: is_true("NoDisplay") || is_true("Hidden"),
+            actions,
+            mime_types: main.get("MimeType").map(|types| split_list(types)).unwrap_or_default(),
+            path: path.to_path_buf(),
+            id,
+        })
+    }
+
+    /// Get the launch target of the entry's main command
+    pub fn target(&self) -> Result<AppTarget, String> {
+        self.target_for(&self.exec, None)
+    }
+
+    /// Get the launch target of one of the entry's actions
+    pub fn action_target(&self, action: &DesktopAction) -> Result<AppTarget, String> {
+        self.target_for(&action.exec, None)
+    }
+
+    /// Get the launch target opening a file with the application
+    pub fn open_target(&self, file: &Path) -> Result<AppTarget, String> {
+        self.target_for(&self.exec, Some(file))
+    }
+
+    /// Check if the application opens files of a MIME type
+    pub fn opens(&self, mime_type: &str) -> bool {
+        self.mime_types
+            .iter()
+            .any(|opened| opened.eq_ignore_ascii_case(mime_type))
+    }
+
+    /// Build a search result for the entry
+    ///
+    /// Desktop actions are offered as result actions, launched by
+    /// `apps::launch_result`.
+    pub fn to_result(&self) -> Result<PluginResult, String> {
+        let mut result = self.target()?.to_result(&self.id, self.name.clone());
+        result.subtitle = self.comment.clone().map(Into::into);
+        result.icon = self.icon.clone().map(Into::into);
+
+        for action in &self.actions {
+            let Ok(target) = self.action_target(action) else {
+                continue;
+            };
+
+            let mut result_action =
+                ResultAction::new(format!("{}{}", DESKTOP_ACTION_PREFIX, action.id), &action.name);
+            if let Some(icon) = &action.icon {
+                result_action = result_action.with_icon(icon);
+            }
+            apps::add_app_action(&mut result, result_action, &target);
+        }
+
+        Ok(result)
+    }
+
+    /// Expand a command line and route it through the packaging runtime
+    fn target_for(&self, exec: &str, file: Option<&Path>) -> Result<AppTarget, String> {
+        let mut args = expand_exec(exec, self, file)?;
+
+        let program = args.first().map(|program| program_name(program)).unwrap_or_default();
+        match &self.packaging {
+            Packaging::Flatpak { app_id } if program != "flatpak" => {
+                let command = format!("--command={}", args.remove(0));
+                args.splice(0..0, ["flatpak".to_string(), "run".to_string(), command, app_id.clone()]);
+            }
+            Packaging::Snap { name }
+                if !matches!(program.as_str(), "snap" | "env") && !args[0].starts_with("/snap/") =>
+            {
+                args.splice(0..1, ["snap".to_string(), "run".to_string(), name.clone()]);
+            }
+            _ => {}
+        }
+
+        let path = PathBuf::from(args.remove(0));
+        Ok(AppTarget::Executable {
+            path,
+            args,
+            working_dir: self.working_dir.clone(),
+        })
+    }
+}
+
+/// Get the directories applications are installed in, most important first
+///
+/// Follows `XDG_DATA_HOME` and `XDG_DATA_DIRS`, plus the Flatpak and Snap
+/// export directories, which aren't always listed there.
+pub fn application_dirs() -> Vec<PathBuf> {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home.as_ref().map(|home| home.join(".local/share")));
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+
+    let mut dirs: Vec<PathBuf> = data_home
+        .into_iter()
+        .chain(data_dirs.split(':').map(PathBuf::from))
+        .chain(home.map(|home| home.join(".local/share/flatpak/exports/share")))
+        .chain([
+            PathBuf::from("/var/lib/flatpak/exports/share"),
+            PathBuf::from("/var/lib/snapd/desktop"),
+        ])
+        .map(|dir| dir.join("applications"))
+        .collect();
+
+    let mut seen = HashSet::new();
+    dirs.retain(|dir| seen.insert(dir.clone()));
+    dirs
+}
+
+/// Enumerate the application entries in the given directories
+///
+/// Entries are identified by their file name; when several directories
+/// contain the same entry, the first one wins, as in menus.
+pub fn desktop_entries(dirs: &[PathBuf]) -> Vec<DesktopEntry> {
+    let mut seen = HashSet::new();
+    let mut entries = Vec::new();
+
+    for dir in dirs {
+        let Ok(read_dir) = std::fs::read_dir(dir) else {
+            continue;
+        };
+
+        let mut paths: Vec<PathBuf> = read_dir
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "desktop"))
+            .collect();
+        paths.sort();
+
+        let parsed = paths
+            .iter()
+            .filter_map(|path| DesktopEntry::open(path).ok().flatten());
+        for entry in parsed {
+            if seen.insert(entry.id.clone()) {
+                entries.push(entry);
+            }
+        }
+    }
+
+    entries
+}
+
+/// Split a desktop file into its groups of unescaped key/value pairs
+fn parse_groups(content: &str) -> HashMap<String, HashMap<String, String>> {
+    let mut groups: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let mut current: Option<String> = None;
+
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(group) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            current = Some(group.to_string());
+            continue;
+        }
+
+        let (Some(group), Some((key, value))) = (&current, line.split_once('=')) else {
+            continue;
+        };
+        groups
+            .entry(group.clone())
+            .or_default()
+            .entry(key.trim().to_string())
+            .or_insert_with(|| unescape_value(value.trim()));
+    }
+
+    groups
+}
+
+/// Resolve the escape sequences allowed in desktop entry values
+fn unescape_value(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => unescaped.push(' '),
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('r') => unescaped.push('\r'),
+            Some(other) => {
+                // Keep other escapes (e.g. `\;` in lists, `\"` in Exec) for later stages
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+/// Split a `;`-separated list value
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(';')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Split an Exec value into arguments and expand its field codes
+///
+/// File and URL codes (`%f`, `%U`, ...) are replaced by the file opened,
+/// which is appended if the command line has no such code, or dropped when
+/// the app starts without a file.
+fn expand_exec(exec: &str, entry: &DesktopEntry, file: Option<&Path>) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut file = file.map(|file| file.to_string_lossy().into_owned());
+
+    for arg in split_exec(exec)? {
+        match arg.as_str() {
+            "%f" | "%F" | "%u" | "%U" => args.extend(file.take()),
+            "%d" | "%D" | "%n" | "%N" | "%v" | "%m" => {}
+            "%i" => {
+                if let Some(icon) = &entry.icon {
+                    args.extend(["--icon".to_string(), icon.clone()]);
+                }
+            }
+            _ => args.push(expand_field_codes(&arg, entry)),
+        }
+    }
+
+    if args.is_empty() {
+        return Err(format!("Desktop entry '{}' has an empty command", entry.id));
+    }
+    args.extend(file);
+    Ok(args)
+}
+
+/// Expand field codes embedded in an argument
+fn expand_field_codes(arg: &str, entry: &DesktopEntry) -> String {
+    let mut expanded = String::with_capacity(arg.len());
+    let mut chars = arg.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => expanded.push('%'),
+            Some('c') => expanded.push_str(&entry.name),
+            Some('k') => expanded.push_str(&entry.path.to_string_lossy()),
+            _ => {}
+        }
+    }
+
+    expanded
+}
+
+/// Split an Exec value, honoring double quotes and their escapes
+fn split_exec(exec: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_arg = false;
+    let mut chars = exec.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_arg = true;
+            }
+            '\\' if in_quotes => match chars.next() {
+                Some(escaped) => current.push(escaped),
+                None => return Err("Unterminated escape in Exec".to_string()),
+            },
+            c if c.is_whitespace() && !in_quotes => {
+                if has_arg {
+                    args.push(std::mem::take(&mut current));
+                    has_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_arg = true;
+            }
+        }
+    }
+
+    if in_quotes {
+        return Err("Unterminated quote in Exec".to_string());
+    }
+    if has_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+/// Get the file name of a program path
+fn program_name(program: &str) -> String {
+    Path::new(program)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Work out how an entry is packaged from its keys and location
+fn detect_packaging(main: &HashMap<String, String>, path: &Path, id: &str) -> Packaging {
+    let location = path.to_string_lossy();
+    let stem = id.trim_end_matches(".desktop");
+
+    if let Some(app_id) = main.get("X-Flatpak") {
+        return Packaging::Flatpak {
+            app_id: app_id.clone(),
+        };
+    }
+    if location.contains("/flatpak/exports/") {
+        return Packaging::Flatpak {
+            app_id: stem.to_string(),
+        };
+    }
+
+    if let Some(name) = main.get("X-SnapInstanceName") {
+        return Packaging::Snap { name: name.clone() };
+    }
+    if location.starts_with("/var/lib/snapd/") {
+        // Snap desktop files are named `<snap>_<app>.desktop`
+        let name = stem.split('_').next().unwrap_or(stem);
+        return Packaging::Snap {
+            name: name.to_string(),
+        };
+    }
+
+    Packaging::Native
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIREFOX: &str = r#"
+[Desktop Entry]
+Type=Application
+Name=Firefox
+Comment=Browse the Web
+Exec=firefox %u
+Icon=firefox
+MimeType=text/html;application/pdf;
+Actions=new-window;new-private-window;
+
+# Localized names are ignored
+[Desktop Action new-window]
+Name=New Window
+Exec=firefox --new-window %u
+
+[Desktop Action new-private-window]
+Name=New Private Window
+Exec=firefox --private-window "%u" --class "Firefox \"Private\""
+"#;
+
+    #[test]
+    fn test_desktop_actions_become_result_actions() {
+        let entry = DesktopEntry::parse(FIREFOX, Path::new("/usr/share/applications/firefox.desktop")).unwrap();
+        assert_eq!(entry.packaging, Packaging::Native);
+        assert_eq!(entry.actions.len(), 2);
+
+        let private = entry.action_target(&entry.actions[1]).unwrap();
+        assert_eq!(
+            private,
+            AppTarget::Executable {
+                path: PathBuf::from("firefox"),
+                args: vec![
+                    "--private-window".to_string(),
+                    "--class".to_string(),
+                    "Firefox \"Private\"".to_string(),
+                ],
+                working_dir: None,
+            }
+        );
+
+        assert!(entry.opens("Application/PDF") && !entry.opens("text/plain"));
+        assert_eq!(
+            entry.open_target(Path::new("/home/ada/report.pdf")).unwrap(),
+            AppTarget::Executable {
+                path: PathBuf::from("firefox"),
+                args: vec!["/home/ada/report.pdf".to_string()],
+                working_dir: None,
+            }
+        );
+
+        let result = entry.to_result().unwrap();
+        assert_eq!(result.subtitle.as_deref(), Some("Browse the Web"));
+        let labels: Vec<_> = result.actions.iter().map(|action| action.label.as_str()).collect();
+        assert_eq!(labels, vec!["New Window", "New Private Window"]);
+        assert!(apps::launch_result(&result, Some("desktop-action:missing")).is_err());
+
+        let link = "[Desktop Entry]\nType=Link\nName=Docs\nURL=https://volt.dev";
+        assert!(DesktopEntry::parse(link, Path::new("docs.desktop")).is_none());
+    }
+
+    #[test]
+    fn test_sandboxed_apps_use_their_runtime() {
+        let flatpak = DesktopEntry::parse(
+            "[Desktop Entry]\nType=Application\nName=GIMP\nExec=gimp-2.10 %U\nX-Flatpak=org.gimp.GIMP",
+            Path::new("/var/lib/flatpak/exports/share/applications/org.gimp.GIMP.desktop"),
+        )
+        .unwrap();
+        assert_eq!(
+            flatpak.target().unwrap(),
+            AppTarget::Executable {
+                path: PathBuf::from("flatpak"),
+                args: vec!["run".to_string(), "--command=gimp-2.10".to_string(), "org.gimp.GIMP".to_string()],
+                working_dir: None,
+            }
+        );
+
+        let snap = DesktopEntry::parse(
+            "[Desktop Entry]\nType=Application\nName=Spotify\nExec=spotify %U",
+            Path::new("/var/lib/snapd/desktop/applications/spotify_spotify.desktop"),
+        )
+        .unwrap();
+        assert_eq!(
+            snap.packaging,
+            Packaging::Snap {
+                name: "spotify".to_string()
+            }
+        );
+        assert_eq!(
+            snap.target().unwrap(),
+            AppTarget::Executable {
+                path: PathBuf::from("snap"),
+                args: vec!["run".to_string(), "spotify".to_string()],
+                working_dir: None,
+            }
+        );
+    }
+}
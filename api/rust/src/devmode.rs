@@ -0,0 +1,438 @@
+/// Automatic reload of plugins under development
+///
+/// Plugins opt in with `"devMode": true` in their manifest. The host points
+/// a `DevWatcher` at their install directories and calls `scan` from its
+/// file watcher (or periodically). Once a plugin's files have been quiet for
+/// the debounce window, so a rebuild in progress isn't picked up halfway,
+/// the host's loader builds a fresh instance and it replaces the old one
+/// through `PluginRegistry::reload`.
+///
+/// Build outputs living outside the install directory, such as a library in
+/// a cargo `target` directory, are watched with `watch_artifact`. With
+/// `with_refresh`, the current query is replayed against the new build
+/// through `VoltPluginAPI::refresh_results`, so the results on screen
+/// reflect the change without typing again.
+use crate::api::VoltPluginAPI;
+use crate::locks;
+use crate::manifest::PluginManifest;
+use crate::plugin::Plugin;
+use crate::registry::PluginRegistry;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Default quiet period before a changed plugin is reloaded
+pub const DEFAULT_DEV_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Builds a plugin from its install directory and manifest
+///
+/// The host knows how to load native libraries, WASM modules and processes;
+/// the watcher only decides when.
+pub type PluginLoader =
+    Arc<dyn Fn(&Path, &PluginManifest) -> Result<Box<dyn Plugin + Send + Sync>, String> + Send + Sync>;
+
+/// Summary of a plugin directory's files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Fingerprint {
+    files: usize,
+    bytes: u64,
+    latest: Option<SystemTime>,
+}
+
+impl Fingerprint {
+    /// Summarize a plugin directory and the artifacts watched with it
+    fn of_all(dir: &Path, artifacts: &[PathBuf]) -> Self {
+        artifacts.iter().map(|artifact| Self::of(artifact)).fold(
+            Self::of(dir),
+            |total, fingerprint| Self {
+                files: total.files + fingerprint.files,
+                bytes: total.bytes + fingerprint.bytes,
+                latest: total.latest.max(fingerprint.latest),
+            },
+        )
+    }
+
+    /// Summarize a file, or every file below a directory, skipping hidden entries
+    fn of(path: &Path) -> Self {
+        let mut fingerprint = Self::default();
+        if let Ok(metadata) = std::fs::metadata(path)
+            && metadata.is_file()
+        {
+            fingerprint.add(&metadata);
+            return fingerprint;
+        }
+        let mut pending = vec![path.to_path_buf()];
+
+        while let Some(dir) = pending.pop() {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                if entry.file_name().to_string_lossy().starts_with('.') {
+                    continue;
+                }
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                if metadata.is_dir() {
+                    pending.push(entry.path());
+                    continue;
+                }
+
+                fingerprint.add(&metadata);
+            }
+        }
+
+        fingerprint
+    }
+
+    fn add(&mut self, metadata: &std::fs::Metadata) {
+        self.files += 1;
+        self.bytes += metadata.len();
+        if let Ok(modified) = metadata.modified() {
+            self.latest = self.latest.max(Some(modified));
+        }
+    }
+}
+
+/// A plugin directory being watched
+struct WatchedPlugin {
+    plugin_id: String,
+    /// Build outputs outside the plugin directory
+    artifacts: Vec<PathBuf>,
+    fingerprint: Fingerprint,
+    /// When the latest change not reloaded yet was seen
+    changed_at: Option<Instant>,
+}
+
+/// Reloads dev-mode plugins when their files change
+pub struct DevWatcher {
+    registry: PluginRegistry,
+    loader: PluginLoader,
+    debounce: Duration,
+    watched: RwLock<HashMap<PathBuf, WatchedPlugin>>,
+    /// Asked to replay the current query after a reload
+    api: Option<VoltPluginAPI>,
+}
+
+impl DevWatcher {
+    /// Create a watcher reloading plugins of a registry
+    ///
+    /// # Arguments
+    /// * `registry` - Registry the plugins are loaded into
+    /// * `loader` - Builds a fresh instance of a changed plugin
+    pub fn new(registry: PluginRegistry, loader: PluginLoader) -> Self {
+        Self {
+            registry,
+            loader,
+            debounce: DEFAULT_DEV_DEBOUNCE,
+            watched: RwLock::new(HashMap::new()),
+            api: None,
+        }
+    }
+
+    /// Use a different quiet period before reloading
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Replay the current query against reloaded plugins
+    ///
+    /// The watcher calls `VoltPluginAPI::refresh_results` for each reloaded
+    /// plugin; the host answers it like any refresh request.
+    pub fn with_refresh(mut self, api: VoltPluginAPI) -> Self {
+        self.api = Some(api);
+        self
+    }
+
+    /// Start watching an installed plugin
+    ///
+    /// # Arguments
+    /// * `plugin_dir` - Directory containing the plugin's `manifest.json`
+    ///
+    /// # Returns
+    /// Whether the plugin is watched; plugins not opting into dev mode aren't
+    pub fn watch(&self, plugin_dir: &Path) -> Result<bool, String> {
+        let manifest = PluginManifest::from_file(&plugin_dir.join("manifest.json"))?;
+        if !manifest.dev_mode {
+            return Ok(false);
+        }
+
+        locks::write(&self.watched, "dev watcher").insert(
+            plugin_dir.to_path_buf(),
+            WatchedPlugin {
+                plugin_id: manifest.id.clone(),
+                artifacts: Vec::new(),
+                fingerprint: Fingerprint::of(plugin_dir),
+                changed_at: None,
+            },
+        );
+        self.registry.diagnostics().info("dev", &format!("Watching plugin '{}' for changes", manifest.id));
+        Ok(true)
+    }
+
+    /// Also reload a watched plugin when a build output changes
+    ///
+    /// # Arguments
+    /// * `plugin_dir` - Directory of a plugin watched with `watch`
+    /// * `artifact` - File or directory the plugin is built into, e.g. the
+    ///   library in a cargo `target` directory its manifest's `main` points to
+    pub fn watch_artifact(&self, plugin_dir: &Path, artifact: &Path) -> Result<(), String> {
+        let mut watched = locks::write(&self.watched, "dev watcher");
+        let plugin = watched
+            .get_mut(plugin_dir)
+            .ok_or_else(|| format!("{} is not watched", plugin_dir.display()))?;
+
+        if !plugin.artifacts.iter().any(|watched| watched == artifact) {
+            plugin.artifacts.push(artifact.to_path_buf());
+            plugin.fingerprint = Fingerprint::of_all(plugin_dir, &plugin.artifacts);
+        }
+        Ok(())
+    }
+
+    /// Stop watching a plugin directory
+    pub fn unwatch(&self, plugin_dir: &Path) -> bool {
+        locks::write(&self.watched, "dev watcher")
+            .remove(plugin_dir)
+            .is_some()
+    }
+
+    /// Get the IDs of the watched plugins, sorted
+    pub fn watched_plugins(&self) -> Vec<String> {
+        let mut plugin_ids: Vec<String> = locks::read(&self.watched, "dev watcher")
+            .values()
+            .map(|watched| watched.plugin_id.clone())
+            .collect();
+        plugin_ids.sort();
+        plugin_ids
+    }
+
+    /// Look for changed plugins and reload those that settled
+    ///
+    /// Plugins failing to load keep running their previous build; the error
+    /// is logged and the plugin is retried after its next change.
+    ///
+    /// # Returns
+    /// The IDs of the plugins that were reloaded
+    pub fn scan(&self) -> Vec<String> {
+        self.scan_at(Instant::now())
+    }
+
+    fn scan_at(&self, now: Instant) -> Vec<String> {
+        let settled: Vec<PathBuf> = {
+            let mut watched = locks::write(&self.watched, "dev watcher");
+
+            watched
+                .iter_mut()
+                .filter_map(|(plugin_dir, plugin)| {
+                    let fingerprint = Fingerprint::of_all(plugin_dir, &plugin.artifacts);
+                    if fingerprint != plugin.fingerprint {
+                        plugin.fingerprint = fingerprint;
+                        plugin.changed_at = Some(now);
+                    }
+
+                    let changed_at = plugin.changed_at?;
+                    if now.saturating_duration_since(changed_at) < self.debounce {
+                        return None;
+                    }
+                    plugin.changed_at = None;
+                    Some(plugin_dir.clone())
+                })
+                .collect()
+        };
+
+        // Loading can take a while, so it runs without holding the lock
+        settled
+            .into_iter()
+            .filter_map(|plugin_dir| match self.reload(&plugin_dir) {
+                Ok(plugin_id) => Some(plugin_id),
+                Err(e) => {
+                    self.registry.diagnostics().warn("dev", &format!("Failed to reload {}: {}", plugin_dir.display(), e));
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Build a fresh instance of a plugin and swap it into the registry
+    fn reload(&self, plugin_dir: &Path) -> Result<String, String> {
+        let manifest = PluginManifest::from_file(&plugin_dir.join("manifest.json"))?;
+        let plugin = (self.loader)(plugin_dir, &manifest)?;
+        let plugin_id = plugin.id().to_string();
+
+        self.registry.reload(plugin)?;
+        if let Some(api) = &self.api
+            && let Err(e) = api.refresh_results(&plugin_id)
+        {
+            self.registry.diagnostics().warn("dev", &format!("Failed to replay the query for '{}': {}", plugin_id, e));
+        }
+        Ok(plugin_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::RegistryEvent;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    struct DevPlugin {
+        id: String,
+        unloads: Arc<AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl Plugin for DevPlugin {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn id(&self) -> &str {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            "Dev plugin"
+        }
+
+        fn description(&self) -> &str {
+            "Plugin under development"
+        }
+
+        fn on_disabled(&self) -> Result<(), String> {
+            self.unloads.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_changed_plugins_reload_after_debounce() {
+        let temp_dir = std::env::temp_dir().join("volt_test_devmode");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let plugin_dir = temp_dir.join("echo");
+        let released_dir = temp_dir.join("released");
+        std::fs::create_dir_all(&plugin_dir).unwrap();
+        std::fs::create_dir_all(&released_dir).unwrap();
+        std::fs::write(
+            plugin_dir.join("manifest.json"),
+            r#"{"id": "echo", "name": "Echo", "version": "0.1.0", "devMode": true}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            released_dir.join("manifest.json"),
+            r#"{"id": "released", "name": "Released", "version": "1.0.0"}"#,
+        )
+        .unwrap();
+        std::fs::write(plugin_dir.join("plugin.wasm"), b"build 1").unwrap();
+
+        let registry = PluginRegistry::new();
+        let manifest = PluginManifest::from_file(&plugin_dir.join("manifest.json")).unwrap();
+        registry
+            .register_with_manifest(Box::new(DevPlugin { id: "echo".to_string(), unloads: Default::default() }), &manifest)
+            .unwrap();
+        assert!(registry.snapshot().unwrap()[0].dev);
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        registry.subscribe(Arc::new(move |event: &RegistryEvent| sink.lock().unwrap().push(event.clone())));
+
+        let loads = Arc::new(AtomicUsize::new(0));
+        let counter = loads.clone();
+        let loader: PluginLoader = Arc::new(move |_: &Path, manifest: &PluginManifest| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(Box::new(DevPlugin {
+                id: manifest.id.clone(),
+                unloads: Default::default(),
+            }) as Box<dyn Plugin + Send + Sync>)
+        });
+        let watcher = DevWatcher::new(registry.clone(), loader).with_debounce(Duration::from_secs(1));
+
+        assert!(watcher.watch(&plugin_dir).unwrap());
+        assert!(!watcher.watch(&released_dir).unwrap());
+        assert_eq!(watcher.watched_plugins(), vec!["echo".to_string()]);
+
+        let start = Instant::now();
+        assert!(watcher.scan_at(start).is_empty());
+
+        std::fs::write(plugin_dir.join("plugin.wasm"), b"build 2, larger").unwrap();
+        assert!(watcher.scan_at(start).is_empty());
+        assert!(watcher.scan_at(start + Duration::from_millis(500)).is_empty());
+        assert_eq!(loads.load(Ordering::SeqCst), 0);
+
+        let reloaded = watcher.scan_at(start + Duration::from_secs(1));
+        assert_eq!(reloaded, vec!["echo".to_string()]);
+        assert!(watcher.scan_at(start + Duration::from_secs(5)).is_empty());
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            events.lock().unwrap().as_slice(),
+            &[RegistryEvent::PluginReloaded {
+                plugin_id: "echo".to_string()
+            }]
+        );
+
+        assert!(watcher.unwatch(&plugin_dir));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_rebuilt_artifact_reloads_and_replays_the_query() {
+        let temp_dir = std::env::temp_dir().join("volt_test_devmode_artifact");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let plugin_dir = temp_dir.join("plugins").join("echo");
+        let target_dir = temp_dir.join("target");
+        std::fs::create_dir_all(&plugin_dir).unwrap();
+        std::fs::create_dir_all(&target_dir).unwrap();
+        std::fs::write(
+            plugin_dir.join("manifest.json"),
+            r#"{"id": "echo", "name": "Echo", "version": "0.1.0", "devMode": true,
+                "main": "../../target/libecho.so"}"#,
+        )
+        .unwrap();
+        let artifact = target_dir.join("libecho.so");
+        std::fs::write(&artifact, b"build 1").unwrap();
+
+        let registry = PluginRegistry::new();
+        let unloads = Arc::new(AtomicUsize::new(0));
+        registry
+            .register(Box::new(DevPlugin {
+                id: "echo".to_string(),
+                unloads: unloads.clone(),
+            }))
+            .unwrap();
+
+        let api = VoltPluginAPI::new(temp_dir.join("data"));
+        let refreshed = Arc::new(Mutex::new(Vec::new()));
+        let sink = refreshed.clone();
+        api.subscribe_refresh_requests(Arc::new(move |plugin_id: &str| {
+            sink.lock().unwrap().push(plugin_id.to_string())
+        }))
+        .unwrap();
+
+        let loader: PluginLoader = Arc::new(|_: &Path, manifest: &PluginManifest| {
+            Ok(Box::new(DevPlugin {
+                id: manifest.id.clone(),
+                unloads: Default::default(),
+            }) as Box<dyn Plugin + Send + Sync>)
+        });
+        let watcher = DevWatcher::new(registry.clone(), loader)
+            .with_debounce(Duration::ZERO)
+            .with_refresh(api);
+        assert!(watcher.watch_artifact(&plugin_dir, &artifact).is_err());
+        assert!(watcher.watch(&plugin_dir).unwrap());
+        watcher.watch_artifact(&plugin_dir, &artifact).unwrap();
+
+        let start = Instant::now();
+        assert!(watcher.scan_at(start).is_empty());
+        std::fs::write(&artifact, b"build 2, larger").unwrap();
+        assert_eq!(watcher.scan_at(start), vec!["echo".to_string()]);
+
+        // The previous build was unloaded and the query replayed
+        assert_eq!(unloads.load(Ordering::SeqCst), 1);
+        assert_eq!(*refreshed.lock().unwrap(), vec!["echo".to_string()]);
+
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+}
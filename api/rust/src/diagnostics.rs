@@ -0,0 +1,215 @@
+/// Diagnostics bundles for bug reports
+///
+/// Collects everything needed to reproduce a plugin bug (recent logs,
+/// configuration, registry status, version information, last errors) into
+/// a single zip that users can attach to an issue. Secrets are redacted
+/// before anything is written, and the bundle is capped in size.
+use serde_json::Value;
+use std::io::{Cursor, Write};
+
+/// Maximum uncompressed size of a bundle
+pub const MAX_BUNDLE_SIZE: usize = 4 * 1024 * 1024;
+
+/// Replacement for redacted values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Configuration keys whose values are treated as secrets
+const SECRET_KEY_PARTS: &[&str] = &[
+    "password",
+    "passwd",
+    "secret",
+    "token",
+    "apikey",
+    "api_key",
+    "api-key",
+    "auth",
+    "credential",
+    "private_key",
+    "privatekey",
+    "cookie",
+    "session",
+];
+
+/// Secrets are only scrubbed from logs when long enough to be distinctive
+const MIN_SECRET_LEN: usize = 4;
+
+/// Check if a configuration key names a secret
+pub fn is_secret_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    SECRET_KEY_PARTS.iter().any(|part| key.contains(part))
+}
+
+/// Redaction pass applied to everything that goes into a bundle
+///
+/// Secret values found in configurations are remembered, so they are also
+/// scrubbed from logs and error messages that happen to echo them.
+#[derive(Debug, Default)]
+pub struct Redactor {
+    secrets: Vec<String>,
+}
+
+impl Redactor {
+    /// Create a redactor with no known secrets
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Redact the secret values of a configuration
+    pub fn redact_config(&mut self, config: &Value) -> Value {
+        match config {
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(key, value)| {
+                        let value = if is_secret_key(key) && !value.is_null() {
+                            self.remember(value);
+                            Value::String(REDACTED.to_string())
+                        } else {
+                            self.redact_config(value)
+                        };
+                        (key.clone(), value)
+                    })
+                    .collect(),
+            ),
+            Value::Array(items) => Value::Array(items.iter().map(|item| self.redact_config(item)).collect()),
+            other => other.clone(),
+        }
+    }
+
+    /// Scrub known secret values from free text
+    pub fn redact_text(&self, text: &str) -> String {
+        self.secrets
+            .iter()
+            .fold(text.to_string(), |text, secret| text.replace(secret.as_str(), REDACTED))
+    }
+
+    fn remember(&mut self, value: &Value) {
+        match value {
+            Value::String(secret) if secret.len() >= MIN_SECRET_LEN => {
+                self.secrets.push(secret.clone());
+            }
+            Value::Array(items) => items.iter().for_each(|item| self.remember(item)),
+            Value::Object(map) => map.values().for_each(|item| self.remember(item)),
+            _ => {}
+        }
+    }
+}
+
+/// Files collected for a bundle
+#[derive(Debug, Default)]
+pub struct DiagnosticsBundle {
+    files: Vec<(String, String)>,
+}
+
+impl DiagnosticsBundle {
+    /// Create an empty bundle
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file to the bundle
+    pub fn add_file(&mut self, name: impl Into<String>, content: impl Into<String>) {
+        self.files.push((name.into(), content.into()));
+    }
+
+    /// Add a JSON file to the bundle
+    pub fn add_json(&mut self, name: impl Into<String>, value: &Value) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(value)
+            .map_err(|e| format!("Failed to serialize diagnostics: {}", e))?;
+        self.add_file(name, content);
+        Ok(())
+    }
+
+    /// Total uncompressed size of the bundle
+    pub fn size(&self) -> usize {
+        self.files.iter().map(|(_, content)| content.len()).sum()
+    }
+
+    /// Write the bundle as a zip archive
+    ///
+    /// # Returns
+    /// The archive, or Err if the bundle exceeds `MAX_BUNDLE_SIZE`
+    pub fn to_zip(&self) -> Result<Vec<u8>, String> {
+        if self.size() > MAX_BUNDLE_SIZE {
+            return Err(format!(
+                "Diagnostics bundle too large ({} bytes, max {})",
+                self.size(),
+                MAX_BUNDLE_SIZE
+            ));
+        }
+
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+
+        for (name, content) in &self.files {
+            writer
+                .start_file(name.as_str(), options)
+                .map_err(|e| format!("Failed to write diagnostics bundle: {}", e))?;
+            writer
+                .write_all(content.as_bytes())
+                .map_err(|e| format!("Failed to write diagnostics bundle: {}", e))?;
+        }
+
+        writer
+            .finish()
+            .map(Cursor::into_inner)
+            .map_err(|e| format!("Failed to write diagnostics bundle: {}", e))
+    }
+}
+
+/// Keep the most recent log lines that fit in a byte budget
+pub fn tail_within(lines: &[String], budget: usize) -> Vec<String> {
+    let mut used = 0;
+    let mut kept: Vec<String> = lines
+        .iter()
+        .rev()
+        .take_while(|line| {
+            used += line.len() + 1;
+            used <= budget
+        })
+        .cloned()
+        .collect();
+    kept.reverse();
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redaction() {
+        let mut redactor = Redactor::new();
+        let config = serde_json::json!({
+            "apiKey": "sk-live-1234",
+            "accounts": [{"name": "work", "password": "hunter22"}],
+            "theme": "dark"
+        });
+
+        let redacted = redactor.redact_config(&config);
+        assert_eq!(redacted["apiKey"], REDACTED);
+        assert_eq!(redacted["accounts"][0]["password"], REDACTED);
+        assert_eq!(redacted["accounts"][0]["name"], "work");
+        assert_eq!(redacted["theme"], "dark");
+
+        assert_eq!(
+            redactor.redact_text("401 for key sk-live-1234 (user hunter22)"),
+            "401 for key [REDACTED] (user [REDACTED])"
+        );
+    }
+
+    #[test]
+    fn test_size_cap() {
+        let lines: Vec<String> = (0..10).map(|i| format!("line {}", i)).collect();
+        assert_eq!(tail_within(&lines, 14), vec!["line 8", "line 9"]);
+
+        let mut bundle = DiagnosticsBundle::new();
+        bundle.add_file("logs.txt", "x".repeat(MAX_BUNDLE_SIZE + 1));
+        assert!(bundle.to_zip().is_err());
+
+        let mut bundle = DiagnosticsBundle::new();
+        bundle.add_file("logs.txt", "hello");
+        let zip = bundle.to_zip().unwrap();
+        assert!(zip.starts_with(b"PK"));
+    }
+}
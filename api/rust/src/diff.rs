@@ -0,0 +1,368 @@
+/// Result list diff protocol for plugin bridges
+///
+/// Out-of-process plugins answer every keystroke, and results rarely change
+/// much between two keystrokes. Instead of re-sending the full list, the
+/// producing side sends a `ResultDiff` against the previous list, keyed by
+/// result ID, and the receiving side replays it.
+///
+/// Result IDs must be unique within a list; lists with duplicate IDs are sent
+/// as a full replacement.
+use crate::result::PluginResult;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// A single change to a result list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum DiffOp {
+    /// Replace the whole list (initial sync or resync)
+    Replace {
+        /// The new list
+        results: Vec<PluginResult>,
+    },
+    /// Remove the result with the given ID
+    Remove {
+        /// ID of the removed result
+        id: String,
+    },
+    /// Replace the result with the same ID
+    Update {
+        /// The new version of the result
+        result: PluginResult,
+    },
+    /// Append a new result
+    Add {
+        /// The added result
+        result: PluginResult,
+    },
+    /// Reorder the list; `order` lists every remaining ID exactly once
+    Reorder {
+        /// Result IDs in their new order
+        order: Vec<String>,
+    },
+}
+
+/// Changes turning the list at `base_version` into the list at `version`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResultDiff {
+    /// Version the diff applies to
+    pub base_version: u64,
+    /// Version produced by applying the diff
+    pub version: u64,
+    /// Operations, applied in order
+    pub ops: Vec<DiffOp>,
+}
+
+/// Producing side: computes diffs between successive result lists
+#[derive(Debug, Clone, Default)]
+pub struct DiffEncoder {
+    version: u64,
+    last: Option<Vec<PluginResult>>,
+}
+
+impl DiffEncoder {
+    /// Create an encoder; its first diff is a full replacement
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Force the next diff to be a full replacement
+    ///
+    /// Call this when the receiving side reports it is out of sync.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+
+    /// Compute the diff from the previously encoded list to `results`
+    pub fn encode(&mut self, results: &[PluginResult]) -> ResultDiff {
+        let ops = match &self.last {
+            Some(last) if has_unique_ids(results) => diff_lists(last, results),
+            _ => vec![DiffOp::Replace {
+                results: results.to_vec(),
+            }],
+        };
+
+        let base_version = self.version;
+        self.version += 1;
+        self.last = Some(results.to_vec());
+
+        ResultDiff {
+            base_version,
+            version: self.version,
+            ops,
+        }
+    }
+}
+
+/// Receiving side: rebuilds result lists from diffs
+#[derive(Debug, Clone, Default)]
+pub struct DiffDecoder {
+    version: u64,
+    results: Vec<PluginResult>,
+}
+
+impl DiffDecoder {
+    /// Create a decoder with an empty list
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the current list
+    pub fn results(&self) -> &[PluginResult] {
+        &self.results
+    }
+
+    /// Get the version of the current list
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Apply a diff
+    ///
+    /// The diff is applied atomically: on error the current list is left
+    /// untouched and the sender should be asked to resync.
+    ///
+    /// # Returns
+    /// The updated list, or Err if the diff doesn't apply to the current list
+    pub fn apply(&mut self, diff: &ResultDiff) -> Result<&[PluginResult], String> {
+        let is_replace = matches!(diff.ops.first(), Some(DiffOp::Replace { .. }));
+        if diff.base_version != self.version && !is_replace {
+            return Err(format!(
+                "Diff base version {} does not match current version {}",
+                diff.base_version, self.version
+            ));
+        }
+
+        let mut results = self.results.clone();
+        for op in &diff.ops {
+            apply_op(&mut results, op)?;
+        }
+
+        self.results = results;
+        self.version = diff.version;
+        Ok(&self.results)
+    }
+}
+
+fn has_unique_ids(results: &[PluginResult]) -> bool {
+    let mut seen = HashSet::new();
+    results.iter().all(|result| seen.insert(result.id.as_str()))
+}
+
+fn diff_lists(old: &[PluginResult], new: &[PluginResult]) -> Vec<DiffOp> {
+    let new_by_id: HashMap<&str, &PluginResult> =
+        new.iter().map(|result| (result.id.as_str(), result)).collect();
+    let old_ids: HashSet<&str> = old.iter().map(|result| result.id.as_str()).collect();
+
+    let mut ops = Vec::new();
+    let mut order: Vec<&str> = Vec::new();
+
+    for result in old {
+        match new_by_id.get(result.id.as_str()) {
+            None => ops.push(DiffOp::Remove {
+                id: result.id.clone(),
+            }),
+            Some(updated) => {
+                if *updated != result {
+                    ops.push(DiffOp::Update {
+                        result: (*updated).clone(),
+                    });
+                }
+                order.push(result.id.as_str());
+            }
+        }
+    }
+
+    for result in new.iter().filter(|result| !old_ids.contains(result.id.as_str())) {
+        ops.push(DiffOp::Add {
+            result: result.clone(),
+        });
+        order.push(result.id.as_str());
+    }
+
+    if new.iter().map(|result| result.id.as_str()).ne(order.iter().copied()) {
+        ops.push(DiffOp::Reorder {
+            order: new.iter().map(|result| result.id.clone()).collect(),
+        });
+    }
+
+    ops
+}
+
+fn apply_op(results: &mut Vec<PluginResult>, op: &DiffOp) -> Result<(), String> {
+    let position = |results: &[PluginResult], id: &str| results.iter().position(|r| r.id == id);
+
+    match op {
+        DiffOp::Replace { results: replacement } => {
+            *results = replacement.clone();
+        }
+        DiffOp::Remove { id } => {
+            let index = position(results, id).ok_or_else(|| format!("Cannot remove unknown result '{}'", id))?;
+            results.remove(index);
+        }
+        DiffOp::Update { result } => {
+            let index = position(results, &result.id)
+                .ok_or_else(|| format!("Cannot update unknown result '{}'", result.id))?;
+            results[index] = result.clone();
+        }
+        DiffOp::Add { result } => {
+            if position(results, &result.id).is_some() {
+                return Err(format!("Cannot add duplicate result '{}'", result.id));
+            }
+            results.push(result.clone());
+        }
+        DiffOp::Reorder { order } => {
+            if order.len() != results.len() {
+                return Err("Reorder must list every result exactly once".to_string());
+            }
+
+            let mut index_by_id: HashMap<&str, usize> = HashMap::new();
+            for (index, result) in results.iter().enumerate() {
+                index_by_id.insert(result.id.as_str(), index);
+            }
+
+            let mut indexes = Vec::with_capacity(order.len());
+            for id in order {
+                let index = index_by_id
+                    .remove(id.as_str())
+                    .ok_or_else(|| format!("Reorder references unknown or repeated result '{}'", id))?;
+                indexes.push(index);
+            }
+
+            // Move the results into place instead of copying them
+            let mut slots: Vec<Option<PluginResult>> = std::mem::take(results).into_iter().map(Some).collect();
+            *results = indexes.into_iter().filter_map(|index| slots[index].take()).collect();
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Small deterministic PRNG so fuzz runs are reproducible
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, bound: u64) -> u64 {
+            self.next() % bound.max(1)
+        }
+    }
+
+    fn result(id: &str, score: u32) -> PluginResult {
+        let mut result = PluginResult::new(id, format!("Result {}", id));
+        result.score = score;
+        result
+    }
+
+    fn random_list(rng: &mut XorShift) -> Vec<PluginResult> {
+        let mut ids: Vec<u64> = (0..12).filter(|_| rng.below(2) == 0).collect();
+        for i in (1..ids.len()).rev() {
+            ids.swap(i, rng.below(i as u64 + 1) as usize);
+        }
+        ids.iter()
+            .map(|id| result(&id.to_string(), rng.below(3) as u32))
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_ops() {
+        let mut encoder = DiffEncoder::new();
+        let first = vec![result("a", 1), result("b", 2), result("c", 3)];
+        let second = vec![result("c", 3), result("a", 5), result("d", 1)];
+
+        let initial = encoder.encode(&first);
+        assert!(matches!(initial.ops[..], [DiffOp::Replace { .. }]));
+
+        let diff = encoder.encode(&second);
+        assert_eq!(diff.base_version, 1);
+        assert_eq!(
+            diff.ops,
+            vec![
+                DiffOp::Update { result: result("a", 5) },
+                DiffOp::Remove { id: "b".to_string() },
+                DiffOp::Add { result: result("d", 1) },
+                DiffOp::Reorder {
+                    order: vec!["c".to_string(), "a".to_string(), "d".to_string()]
+                },
+            ]
+        );
+
+        let mut decoder = DiffDecoder::new();
+        decoder.apply(&initial).unwrap();
+        assert_eq!(decoder.apply(&diff).unwrap(), &second[..]);
+        assert_eq!(encoder.encode(&second).ops, Vec::new());
+    }
+
+    #[test]
+    fn test_out_of_sync_diff_is_rejected() {
+        let mut encoder = DiffEncoder::new();
+        let mut decoder = DiffDecoder::new();
+        encoder.encode(&[result("a", 1)]);
+        let missed = encoder.encode(&[result("a", 1), result("b", 1)]);
+
+        assert!(decoder.apply(&missed).is_err());
+        assert!(decoder.results().is_empty());
+
+        encoder.reset();
+        let resync = encoder.encode(&[result("b", 1)]);
+        assert_eq!(decoder.apply(&resync).unwrap(), &[result("b", 1)][..]);
+    }
+
+    #[test]
+    fn fuzz_encode_apply_round_trip() {
+        let mut rng = XorShift(0x5eed_1168);
+        let mut encoder = DiffEncoder::new();
+        let mut decoder = DiffDecoder::new();
+
+        for _ in 0..2_000 {
+            let list = random_list(&mut rng);
+            let diff = encoder.encode(&list);
+
+            // Diffs must survive the wire format too
+            let wire: ResultDiff = serde_json::from_str(&serde_json::to_string(&diff).unwrap()).unwrap();
+            assert_eq!(decoder.apply(&wire).unwrap(), &list[..]);
+        }
+    }
+
+    #[test]
+    fn fuzz_applier_never_panics_on_garbage() {
+        let mut rng = XorShift(0xbad_d1ff);
+        let mut decoder = DiffDecoder::new();
+
+        for _ in 0..5_000 {
+            let id = rng.below(6).to_string();
+            let op = match rng.below(5) {
+                0 => DiffOp::Remove { id },
+                1 => DiffOp::Update { result: result(&id, 1) },
+                2 => DiffOp::Add { result: result(&id, 1) },
+                3 => DiffOp::Reorder {
+                    order: (0..rng.below(6)).map(|_| rng.below(6).to_string()).collect(),
+                },
+                _ => DiffOp::Replace {
+                    results: random_list(&mut rng),
+                },
+            };
+            let before = decoder.results().to_vec();
+            let diff = ResultDiff {
+                base_version: decoder.version(),
+                version: decoder.version() + 1,
+                ops: vec![op],
+            };
+
+            if decoder.apply(&diff).is_err() {
+                assert_eq!(decoder.results(), &before[..]);
+            }
+        }
+    }
+}
@@ -0,0 +1,265 @@
+/// Discovery of installed plugin packages
+///
+/// `PluginRegistry::discover_and_register` walks a plugins directory, finds
+/// every package (a directory holding a `manifest.json`), and loads each one
+/// through the loader the host registered for its backend. The backend is
+/// inferred from the manifest's entry point: a shared library is native code,
+/// a `.wasm` file a WASM module, a script file runs in an interpreter, and
+/// anything else is started as a process. One broken package never stops the
+/// others from loading; each gets its own outcome in the returned list.
+use crate::devmode::PluginLoader;
+use crate::manifest::PluginManifest;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Entry point extensions of plugins loaded as native libraries
+const NATIVE_EXTENSIONS: &[&str] = &["dll", "so", "dylib"];
+
+/// Entry point extensions of plugins run by an interpreter
+const SCRIPT_EXTENSIONS: &[&str] = &["js", "mjs", "cjs", "ts", "py", "lua", "rb", "sh", "ps1"];
+
+/// How a plugin's code is loaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PluginBackend {
+    /// A shared library loaded into the launcher
+    Native,
+    /// A WASM module run in the sandbox
+    Wasm,
+    /// An executable speaking the plugin protocol over stdio
+    Process,
+    /// A script run by an interpreter
+    Script,
+}
+
+impl PluginBackend {
+    /// Infer the backend from a manifest's entry point
+    ///
+    /// # Returns
+    /// Err if the manifest has no entry point or it isn't a relative path
+    pub fn of(manifest: &PluginManifest) -> Result<Self, String> {
+        let main = manifest
+            .main
+            .as_deref()
+            .ok_or_else(|| format!("Plugin '{}' has no entry point", manifest.id))?;
+        crate::assets::validate_package_path("Entry point", main)?;
+        let extension = Path::new(main)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        Ok(match extension.as_str() {
+            extension if NATIVE_EXTENSIONS.contains(&extension) => Self::Native,
+            "wasm" => Self::Wasm,
+            extension if SCRIPT_EXTENSIONS.contains(&extension) => Self::Script,
+            _ => Self::Process,
+        })
+    }
+
+    /// Whether the entry point is a binary carrying its API version marker
+    pub fn is_binary(&self) -> bool {
+        matches!(self, Self::Native | Self::Wasm)
+    }
+}
+
+impl fmt::Display for PluginBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Native => "native",
+            Self::Wasm => "WASM",
+            Self::Process => "process",
+            Self::Script => "script",
+        })
+    }
+}
+
+/// Loaders the host provides, per backend
+///
+/// Packages whose backend has no loader are skipped.
+#[derive(Clone, Default)]
+pub struct PluginLoaders {
+    loaders: HashMap<PluginBackend, PluginLoader>,
+}
+
+impl PluginLoaders {
+    /// Create an empty set of loaders
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load plugins of a backend with a loader
+    pub fn with(mut self, backend: PluginBackend, loader: PluginLoader) -> Self {
+        self.loaders.insert(backend, loader);
+        self
+    }
+
+    /// Get the loader of a backend
+    pub fn get(&self, backend: PluginBackend) -> Option<&PluginLoader> {
+        self.loaders.get(&backend)
+    }
+}
+
+/// What happened to one discovered package
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveredPlugin {
+    /// Directory of the package
+    pub package_dir: PathBuf,
+    /// Plugin identifier, once the manifest was read
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plugin_id: Option<String>,
+    /// Backend the plugin was loaded with, once known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backend: Option<PluginBackend>,
+    /// How loading ended
+    pub outcome: DiscoveryOutcome,
+}
+
+/// How loading a discovered package ended
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum DiscoveryOutcome {
+    /// The plugin was loaded and registered
+    Registered,
+    /// The plugin was left out on purpose, e.g. it doesn't support this platform
+    Skipped {
+        /// User-facing reason
+        reason: String,
+    },
+    /// The package is broken or the plugin failed to load
+    Failed {
+        /// What went wrong
+        error: String,
+    },
+}
+
+impl DiscoveredPlugin {
+    /// Start the outcome of a package, assuming it registers
+    pub(crate) fn new(package_dir: &Path) -> Self {
+        Self {
+            package_dir: package_dir.to_path_buf(),
+            plugin_id: None,
+            backend: None,
+            outcome: DiscoveryOutcome::Registered,
+        }
+    }
+
+    /// Mark the package as skipped
+    pub(crate) fn skipped(mut self, reason: String) -> Self {
+        self.outcome = DiscoveryOutcome::Skipped { reason };
+        self
+    }
+
+    /// Mark the package as failed
+    pub(crate) fn failed(mut self, error: String) -> Self {
+        self.outcome = DiscoveryOutcome::Failed { error };
+        self
+    }
+
+    /// Whether the plugin was registered
+    pub fn is_registered(&self) -> bool {
+        self.outcome == DiscoveryOutcome::Registered
+    }
+}
+
+/// Find the package directories below a directory, sorted
+///
+/// A directory holding a `manifest.json` is a package and isn't searched
+/// further; hidden directories are ignored.
+///
+/// # Returns
+/// Err if the directory itself can't be read
+pub fn find_packages(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    std::fs::read_dir(dir).map_err(|e| format!("Failed to read plugins directory: {}", e))?;
+
+    let mut packages = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        if dir.join("manifest.json").is_file() {
+            packages.push(dir);
+            continue;
+        }
+
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                pending.push(entry.path());
+            }
+        }
+    }
+
+    packages.sort();
+    Ok(packages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(main: Option<&str>) -> PluginManifest {
+        PluginManifest {
+            id: "echo".to_string(),
+            main: main.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_backend_from_entry_point() {
+        assert_eq!(
+            PluginBackend::of(&manifest(Some("echo.DLL"))),
+            Ok(PluginBackend::Native)
+        );
+        assert_eq!(
+            PluginBackend::of(&manifest(Some("lib/libecho.so"))),
+            Ok(PluginBackend::Native)
+        );
+        assert_eq!(
+            PluginBackend::of(&manifest(Some("echo.wasm"))),
+            Ok(PluginBackend::Wasm)
+        );
+        assert_eq!(
+            PluginBackend::of(&manifest(Some("main.py"))),
+            Ok(PluginBackend::Script)
+        );
+        assert_eq!(
+            PluginBackend::of(&manifest(Some("bin/echo"))),
+            Ok(PluginBackend::Process)
+        );
+        assert_eq!(
+            PluginBackend::of(&manifest(Some("echo.exe"))),
+            Ok(PluginBackend::Process)
+        );
+        assert!(
+            PluginBackend::of(&manifest(None))
+                .unwrap_err()
+                .contains("no entry point")
+        );
+    }
+
+    #[test]
+    fn test_find_packages() {
+        let temp_dir = std::env::temp_dir().join("volt_test_discovery");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        for package in ["b", "vendor/a", "vendor/a/nested", ".trash/c"] {
+            std::fs::create_dir_all(temp_dir.join(package)).unwrap();
+            std::fs::write(temp_dir.join(package).join("manifest.json"), "{}").unwrap();
+        }
+        std::fs::create_dir_all(temp_dir.join("empty")).unwrap();
+
+        assert_eq!(
+            find_packages(&temp_dir).unwrap(),
+            vec![temp_dir.join("b"), temp_dir.join("vendor/a")]
+        );
+        assert!(find_packages(&temp_dir.join("missing")).is_err());
+
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+}
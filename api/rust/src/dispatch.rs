@@ -0,0 +1,648 @@
+/// Fair query dispatch
+///
+/// When a query matches many plugins, the host asks the `DispatchScheduler`
+/// which ones to run. Each plugin has a fixed number of concurrency slots,
+/// so a plugin still busy with earlier keystrokes can't pile up more work.
+/// Ordering is deficit round robin over historical latency: every query
+/// credits each matched plugin with one quantum, and a plugin runs once its
+/// credit covers its typical latency. Plugins faster than the quantum run on
+/// every query and are started first; plugins that are repeatedly slow yield
+/// on some queries, but their credit keeps growing so they always run
+/// eventually. Per-plugin metrics show how long plugins waited.
+///
+/// The `AdaptiveController` sizes each dispatch to the user's typing. While
+/// the user types fast, queries get a short deadline and few results, since
+/// they will be superseded anyway; once the user pauses, a deeper second
+/// pass with a longer deadline and more results upgrades the list. See
+/// `PluginRegistry::dispatch_adaptive`. Deep passes are speculative, so they
+/// can be turned off while saving power (see `crate::power`).
+///
+/// ```ignore
+/// let plan = scheduler.plan(&matched_ids);
+/// for slot in plan.ready {
+///     let plugin = registry.get(slot.plugin_id());
+///     spawn(async move {
+///         let results = plugin.match_query(&context).await;
+///         drop(slot); // records the latency and frees the slot
+///     });
+/// }
+/// ```
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::Poll;
+use std::time::{Duration, Instant};
+
+/// Weight of the newest sample in a plugin's latency estimate
+const LATENCY_SMOOTHING: f64 = 0.3;
+
+/// Error of plugins still answering at the deadline of an adaptive dispatch
+pub const DEADLINE_MISSED: &str = "Plugin missed the dispatch deadline";
+
+/// Settings of the dispatch scheduler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchedulerConfig {
+    /// Queries a plugin can answer at once
+    pub slots_per_plugin: usize,
+    /// Latency credited to each matched plugin per query
+    pub quantum: Duration,
+    /// Wait after which a deferred plugin counts as starved
+    pub starvation_threshold: Duration,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            slots_per_plugin: 1,
+            quantum: Duration::from_millis(100),
+            starvation_threshold: Duration::from_secs(2),
+        }
+    }
+}
+
+/// Why a matched plugin was not dispatched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Deferral {
+    /// All of the plugin's slots are busy
+    SlotsFull,
+    /// The plugin is slow and yields to the others on this query
+    Yielded,
+}
+
+/// Dispatch counters of a plugin
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DispatchMetrics {
+    /// Queries the plugin was dispatched
+    pub dispatched: u64,
+    /// Queries the plugin matched but was deferred
+    pub deferred: u64,
+    /// Dispatches that came after waiting longer than the starvation threshold
+    pub starved: u64,
+    /// Longest wait between being deferred and being dispatched
+    pub longest_wait_ms: u64,
+    /// Estimated latency of a query, None before the first one finished
+    pub latency_ms: Option<u64>,
+    /// Queries currently running
+    pub in_flight: usize,
+}
+
+/// Scheduling state of one plugin
+#[derive(Debug, Default)]
+struct Lane {
+    in_flight: usize,
+    latency: Option<f64>,
+    deficit: f64,
+    waiting_since: Option<Instant>,
+    metrics: DispatchMetrics,
+}
+
+impl Lane {
+    /// Record a finished query's latency in seconds
+    fn record_latency(&mut self, seconds: f64) {
+        let estimate = match self.latency {
+            Some(latency) => latency + LATENCY_SMOOTHING * (seconds - latency),
+            None => seconds,
+        };
+        self.latency = Some(estimate);
+        self.metrics.latency_ms = Some((estimate * 1000.0) as u64);
+    }
+}
+
+/// Plugins to run for a query
+#[derive(Debug, Default)]
+pub struct DispatchPlan {
+    /// Plugins to dispatch, fastest first, each holding a slot
+    pub ready: Vec<DispatchSlot>,
+    /// Plugins skipped on this query
+    pub deferred: Vec<(String, Deferral)>,
+}
+
+impl DispatchPlan {
+    /// Get the IDs of the plugins to dispatch, in order
+    pub fn ready_ids(&self) -> Vec<&str> {
+        self.ready.iter().map(DispatchSlot::plugin_id).collect()
+    }
+}
+
+/// A plugin's concurrency slot, held while it answers a query
+///
+/// Dropping the slot frees it and records the time since dispatch as the
+/// plugin's latency, so queries abandoned on timeout count against it.
+#[derive(Debug)]
+pub struct DispatchSlot {
+    plugin_id: String,
+    started: Instant,
+    lanes: Arc<Mutex<HashMap<String, Lane>>>,
+}
+
+impl DispatchSlot {
+    /// Get the ID of the plugin holding the slot
+    pub fn plugin_id(&self) -> &str {
+        &self.plugin_id
+    }
+}
+
+impl Drop for DispatchSlot {
+    fn drop(&mut self) {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let mut lanes = self.lanes.lock().unwrap_or_else(|p| p.into_inner());
+
+        if let Some(lane) = lanes.get_mut(&self.plugin_id) {
+            lane.in_flight = lane.in_flight.saturating_sub(1);
+            lane.metrics.in_flight = lane.in_flight;
+            lane.record_latency(elapsed);
+        }
+    }
+}
+
+/// Decides which matched plugins run for each query
+#[derive(Clone, Default)]
+pub struct DispatchScheduler {
+    config: SchedulerConfig,
+    lanes: Arc<Mutex<HashMap<String, Lane>>>,
+}
+
+impl DispatchScheduler {
+    /// Create a scheduler
+    pub fn new(config: SchedulerConfig) -> Self {
+        Self {
+            config,
+            lanes: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Plan the dispatch of a query to the plugins it matched
+    ///
+    /// # Arguments
+    /// * `plugin_ids` - Plugins that can handle the query
+    ///
+    /// # Returns
+    /// The plugins to run, holding their slots, and the ones deferred
+    pub fn plan<S: AsRef<str>>(&self, plugin_ids: &[S]) -> DispatchPlan {
+        let now = Instant::now();
+        let quantum = self.config.quantum.as_secs_f64();
+        let mut plan = DispatchPlan::default();
+        let mut ready = Vec::new();
+
+        let mut lanes = self.lanes.lock().unwrap_or_else(|p| p.into_inner());
+        for plugin_id in plugin_ids {
+            let plugin_id = plugin_id.as_ref();
+            let lane = lanes.entry(plugin_id.to_string()).or_default();
+
+            let deferral = if lane.in_flight >= self.config.slots_per_plugin.max(1) {
+                Some(Deferral::SlotsFull)
+            } else {
+                let cost = lane.latency.unwrap_or(0.0);
+                lane.deficit += quantum;
+                if lane.deficit >= cost {
+                    // Unused credit doesn't carry over, or a plugin that was
+                    // fast for a while could later run slow for many queries
+                    lane.deficit = (lane.deficit - cost).min(quantum);
+                    None
+                } else {
+                    Some(Deferral::Yielded)
+                }
+            };
+
+            match deferral {
+                Some(deferral) => {
+                    lane.metrics.deferred += 1;
+                    lane.waiting_since.get_or_insert(now);
+                    plan.deferred.push((plugin_id.to_string(), deferral));
+                }
+                None => {
+                    if let Some(since) = lane.waiting_since.take() {
+                        let waited = now.duration_since(since);
+                        lane.metrics.longest_wait_ms = lane.metrics.longest_wait_ms.max(waited.as_millis() as u64);
+                        if waited >= self.config.starvation_threshold {
+                            lane.metrics.starved += 1;
+                        }
+                    }
+                    lane.in_flight += 1;
+                    lane.metrics.in_flight = lane.in_flight;
+                    lane.metrics.dispatched += 1;
+                    ready.push((lane.latency.unwrap_or(0.0), plugin_id.to_string()));
+                }
+            }
+        }
+        drop(lanes);
+
+        ready.sort_by(|(a_latency, a_id), (b_latency, b_id)| a_latency.total_cmp(b_latency).then_with(|| a_id.cmp(b_id)));
+        plan.ready = ready
+            .into_iter()
+            .map(|(_, plugin_id)| DispatchSlot {
+                plugin_id,
+                started: now,
+                lanes: self.lanes.clone(),
+            })
+            .collect();
+
+        plan
+    }
+
+    /// Get the dispatch counters of a plugin
+    pub fn metrics(&self, plugin_id: &str) -> Option<DispatchMetrics> {
+        let lanes = self.lanes.lock().unwrap_or_else(|p| p.into_inner());
+        lanes.get(plugin_id).map(|lane| lane.metrics)
+    }
+
+    /// Get the dispatch counters of every plugin seen, sorted by plugin ID
+    pub fn all_metrics(&self) -> Vec<(String, DispatchMetrics)> {
+        let lanes = self.lanes.lock().unwrap_or_else(|p| p.into_inner());
+        let mut metrics: Vec<(String, DispatchMetrics)> = lanes
+            .iter()
+            .map(|(plugin_id, lane)| (plugin_id.clone(), lane.metrics))
+            .collect();
+        metrics.sort_by(|(a, _), (b, _)| a.cmp(b));
+        metrics
+    }
+
+    /// Forget a plugin's history, e.g. when it is unregistered or updated
+    pub fn remove(&self, plugin_id: &str) {
+        let mut lanes = self.lanes.lock().unwrap_or_else(|p| p.into_inner());
+        lanes.remove(plugin_id);
+    }
+}
+
+/// Tuning knobs of the adaptive controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdaptiveConfig {
+    /// Typing faster than one keystroke per this interval gets quick passes
+    pub fast_typing_interval: Duration,
+    /// Time without keystrokes after which a deep pass is due
+    pub pause: Duration,
+    /// Deadline of plugins on a quick pass
+    pub quick_deadline: Duration,
+    /// Deadline of plugins on a normal pass
+    pub normal_deadline: Duration,
+    /// Deadline of plugins on a deep pass
+    pub deep_deadline: Duration,
+    /// Results kept per plugin on a quick pass
+    pub quick_max_results: usize,
+    /// Results kept per plugin on a normal pass
+    pub normal_max_results: usize,
+    /// Results kept per plugin on a deep pass
+    pub deep_max_results: usize,
+}
+
+impl Default for AdaptiveConfig {
+    fn default() -> Self {
+        Self {
+            fast_typing_interval: Duration::from_millis(150),
+            pause: Duration::from_millis(400),
+            quick_deadline: Duration::from_millis(60),
+            normal_deadline: Duration::from_millis(250),
+            deep_deadline: Duration::from_millis(1500),
+            quick_max_results: 5,
+            normal_max_results: 10,
+            deep_max_results: 30,
+        }
+    }
+}
+
+/// Depth of a dispatch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DispatchPass {
+    /// The user is typing fast; answer quickly with few results
+    Quick,
+    /// The user is typing at a normal pace
+    Normal,
+    /// The user paused; take the time to find more results
+    Deep,
+}
+
+/// Deadline and result limit of a dispatch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DispatchBudget {
+    /// Depth of the dispatch
+    pub pass: DispatchPass,
+    /// Time plugins get to answer; later answers are left out
+    pub deadline: Duration,
+    /// Results kept per plugin
+    pub max_results: usize,
+}
+
+/// Counters of the adaptive controller, for calibrating perceived latency
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdaptiveMetrics {
+    /// Keystrokes seen
+    pub keystrokes: u64,
+    /// Smoothed interval between keystrokes, None before the second one
+    pub typing_interval_ms: Option<u64>,
+    /// Quick passes dispatched
+    pub quick_passes: u64,
+    /// Normal passes dispatched
+    pub normal_passes: u64,
+    /// Deep passes dispatched
+    pub deep_passes: u64,
+    /// Plugins left out of a pass because they missed its deadline
+    pub deadline_misses: u64,
+    /// Results deep passes added to the list of the pass before them
+    pub upgraded_results: u64,
+    /// Deep passes skipped because they were turned off
+    pub skipped_deep_passes: u64,
+}
+
+/// Typing state seen by the adaptive controller
+#[derive(Debug, Default)]
+struct TypingState {
+    last_keystroke: Option<Instant>,
+    interval: Option<f64>,
+    deep_pass_taken: bool,
+    deep_passes_off: bool,
+    last_results: usize,
+    metrics: AdaptiveMetrics,
+}
+
+/// Sizes dispatches to the user's typing speed
+///
+/// ```ignore
+/// let budget = controller.keystroke(Instant::now());
+/// show(registry.dispatch_adaptive(&context, &scheduler, &aggregator, &controller, budget).await);
+///
+/// // Later, on an idle timer, while the query is unchanged
+/// if let Some(budget) = controller.deep_pass_due(Instant::now()) {
+///     show(registry.dispatch_adaptive(&context, &scheduler, &aggregator, &controller, budget).await);
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct AdaptiveController {
+    config: AdaptiveConfig,
+    state: Arc<Mutex<TypingState>>,
+}
+
+impl AdaptiveController {
+    /// Create a controller
+    pub fn new(config: AdaptiveConfig) -> Self {
+        Self {
+            config,
+            state: Arc::new(Mutex::new(TypingState::default())),
+        }
+    }
+
+    /// Get the tuning knobs
+    pub fn config(&self) -> AdaptiveConfig {
+        self.config
+    }
+
+    /// Get the budget of the query of a keystroke
+    ///
+    /// # Arguments
+    /// * `at` - When the keystroke happened
+    pub fn keystroke(&self, at: Instant) -> DispatchBudget {
+        let mut state = self.state.lock().unwrap_or_else(|p| p.into_inner());
+        state.metrics.keystrokes += 1;
+        state.deep_pass_taken = false;
+
+        if let Some(last) = state.last_keystroke.replace(at) {
+            // A pause ends a burst of typing rather than slowing it down
+            let interval = at.saturating_duration_since(last).min(self.config.pause).as_secs_f64();
+            let estimate = match state.interval {
+                Some(estimate) => estimate + LATENCY_SMOOTHING * (interval - estimate),
+                None => interval,
+            };
+            state.interval = Some(estimate);
+            state.metrics.typing_interval_ms = Some((estimate * 1000.0) as u64);
+        }
+
+        let typing_fast = state
+            .interval
+            .is_some_and(|interval| interval < self.config.fast_typing_interval.as_secs_f64());
+        if typing_fast {
+            self.budget(DispatchPass::Quick)
+        } else {
+            self.budget(DispatchPass::Normal)
+        }
+    }
+
+    /// Get the budget of a deep pass, if one is due
+    ///
+    /// A deep pass is due once per keystroke, after the user paused for
+    /// `AdaptiveConfig::pause`, unless deep passes are turned off.
+    ///
+    /// # Arguments
+    /// * `now` - The current time
+    pub fn deep_pass_due(&self, now: Instant) -> Option<DispatchBudget> {
+        let mut state = self.state.lock().unwrap_or_else(|p| p.into_inner());
+        let last = state.last_keystroke?;
+        if state.deep_pass_taken || now.saturating_duration_since(last) < self.config.pause {
+            return None;
+        }
+        state.deep_pass_taken = true;
+        if state.deep_passes_off {
+            state.metrics.skipped_deep_passes += 1;
+            return None;
+        }
+        Some(self.budget(DispatchPass::Deep))
+    }
+
+    /// Turn deep passes on or off
+    pub fn set_deep_passes(&self, enabled: bool) {
+        self.state.lock().unwrap_or_else(|p| p.into_inner()).deep_passes_off = !enabled;
+    }
+
+    /// Get the budget of a pass
+    pub fn budget(&self, pass: DispatchPass) -> DispatchBudget {
+        let (deadline, max_results) = match pass {
+            DispatchPass::Quick => (self.config.quick_deadline, self.config.quick_max_results),
+            DispatchPass::Normal => (self.config.normal_deadline, self.config.normal_max_results),
+            DispatchPass::Deep => (self.config.deep_deadline, self.config.deep_max_results),
+        };
+        DispatchBudget {
+            pass,
+            deadline,
+            max_results,
+        }
+    }
+
+    /// Record a finished pass
+    ///
+    /// # Arguments
+    /// * `budget` - Budget of the pass
+    /// * `results` - Results the pass produced
+    /// * `missed` - Plugins that missed the deadline
+    pub fn record_pass(&self, budget: &DispatchBudget, results: usize, missed: usize) {
+        let mut state = self.state.lock().unwrap_or_else(|p| p.into_inner());
+        match budget.pass {
+            DispatchPass::Quick => state.metrics.quick_passes += 1,
+            DispatchPass::Normal => state.metrics.normal_passes += 1,
+            DispatchPass::Deep => {
+                state.metrics.deep_passes += 1;
+                state.metrics.upgraded_results += results.saturating_sub(state.last_results) as u64;
+            }
+        }
+        state.metrics.deadline_misses += missed as u64;
+        state.last_results = results;
+    }
+
+    /// Get the counters of the controller
+    pub fn metrics(&self) -> AdaptiveMetrics {
+        self.state.lock().unwrap_or_else(|p| p.into_inner()).metrics
+    }
+}
+
+/// Run futures concurrently on the current task, catching panics
+///
+/// Runtime-agnostic, so dispatch works on whatever executor the host uses.
+///
+/// # Returns
+/// The output of each future, in order, or None for futures that panicked
+pub(crate) async fn join_catching<F: Future>(futures: impl IntoIterator<Item = F>) -> Vec<Option<F::Output>> {
+    let mut futures: Vec<Option<Pin<Box<F>>>> = futures.into_iter().map(|future| Some(Box::pin(future))).collect();
+    let mut outputs: Vec<Option<F::Output>> = futures.iter().map(|_| None).collect();
+
+    std::future::poll_fn(|cx| {
+        for (slot, output) in futures.iter_mut().zip(outputs.iter_mut()) {
+            let Some(future) = slot else {
+                continue;
+            };
+            match panic::catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(cx))) {
+                Ok(Poll::Pending) => continue,
+                Ok(Poll::Ready(value)) => *output = Some(value),
+                Err(_) => {}
+            }
+            *slot = None;
+        }
+
+        if futures.iter().all(Option::is_none) {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    })
+    .await;
+
+    outputs
+}
+
+/// Await a future, catching panics
+///
+/// # Returns
+/// The future's output, or None if it panicked
+pub(crate) async fn catch_panic<F: Future>(future: F) -> Option<F::Output> {
+    join_catching([future]).await.pop().flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Record a query of the given latency for a plugin
+    fn run(scheduler: &DispatchScheduler, plugin_id: &str, latency: Duration) {
+        let mut plan = scheduler.plan(&[plugin_id]);
+        let mut slot = plan.ready.pop().expect("plugin should be dispatched");
+        slot.started -= latency;
+    }
+
+    #[test]
+    fn test_slots_limit_concurrent_queries() {
+        let scheduler = DispatchScheduler::new(SchedulerConfig::default());
+
+        let first = scheduler.plan(&["files"]);
+        assert_eq!(first.ready_ids(), vec!["files"]);
+        let second = scheduler.plan(&["files"]);
+        assert!(second.ready.is_empty());
+        assert_eq!(second.deferred, vec![("files".to_string(), Deferral::SlotsFull)]);
+
+        drop(first);
+        assert_eq!(scheduler.plan(&["files"]).ready_ids(), vec!["files"]);
+        assert_eq!(scheduler.metrics("files").unwrap().in_flight, 0);
+    }
+
+    #[test]
+    fn test_fast_plugins_go_first_and_slow_ones_yield() {
+        let scheduler = DispatchScheduler::new(SchedulerConfig {
+            quantum: Duration::from_millis(100),
+            ..Default::default()
+        });
+        run(&scheduler, "calculator", Duration::from_millis(5));
+        run(&scheduler, "jira", Duration::from_millis(350));
+
+        let mut jira_runs = 0;
+        for _ in 0..8 {
+            let plan = scheduler.plan(&["jira", "calculator"]);
+            assert_eq!(plan.ready_ids()[0], "calculator");
+            jira_runs += plan.ready_ids().contains(&"jira") as u32;
+            for mut slot in plan.ready {
+                if slot.plugin_id() == "jira" {
+                    slot.started -= Duration::from_millis(350);
+                }
+            }
+        }
+
+        // Jira costs three and a half quanta, so it runs about every fourth query
+        assert!((1..=3).contains(&jira_runs), "jira ran {} times", jira_runs);
+        let metrics = scheduler.metrics("jira").unwrap();
+        assert!(metrics.deferred >= 5);
+        assert!(metrics.latency_ms.unwrap() >= 300);
+        assert_eq!(scheduler.metrics("calculator").unwrap().deferred, 0);
+    }
+
+    #[test]
+    fn test_fast_typing_shrinks_budget_and_pauses_deepen_it() {
+        let controller = AdaptiveController::new(AdaptiveConfig::default());
+        let start = Instant::now();
+
+        assert_eq!(controller.keystroke(start).pass, DispatchPass::Normal);
+        let mut budget = controller.keystroke(start);
+        for i in 1..5 {
+            budget = controller.keystroke(start + Duration::from_millis(60 * i));
+        }
+        assert_eq!(budget.pass, DispatchPass::Quick);
+        assert_eq!(budget.max_results, 5);
+        controller.record_pass(&budget, 4, 1);
+
+        let typed = start + Duration::from_millis(240);
+        assert_eq!(controller.deep_pass_due(typed + Duration::from_millis(100)), None);
+        let deep = controller.deep_pass_due(typed + Duration::from_millis(500)).unwrap();
+        assert_eq!(deep.pass, DispatchPass::Deep);
+        assert!(deep.deadline > budget.deadline);
+        controller.record_pass(&deep, 12, 0);
+        assert_eq!(controller.deep_pass_due(typed + Duration::from_secs(2)), None);
+
+        let metrics = controller.metrics();
+        assert_eq!(metrics.keystrokes, 6);
+        assert_eq!(metrics.quick_passes, 1);
+        assert_eq!(metrics.deep_passes, 1);
+        assert_eq!(metrics.deadline_misses, 1);
+        assert_eq!(metrics.upgraded_results, 8);
+    }
+
+    #[test]
+    fn test_deep_passes_can_be_turned_off() {
+        let controller = AdaptiveController::new(AdaptiveConfig::default());
+        let start = Instant::now();
+        controller.set_deep_passes(false);
+        controller.keystroke(start);
+        assert_eq!(controller.deep_pass_due(start + Duration::from_secs(1)), None);
+        assert_eq!(controller.metrics().skipped_deep_passes, 1);
+
+        controller.set_deep_passes(true);
+        controller.keystroke(start + Duration::from_secs(2));
+        assert!(controller.deep_pass_due(start + Duration::from_secs(3)).is_some());
+    }
+
+    #[test]
+    fn test_starvation_is_measured() {
+        let scheduler = DispatchScheduler::new(SchedulerConfig {
+            starvation_threshold: Duration::ZERO,
+            ..Default::default()
+        });
+        run(&scheduler, "jira", Duration::from_secs(1));
+
+        while scheduler.plan(&["jira"]).ready.is_empty() {}
+        let metrics = scheduler.metrics("jira").unwrap();
+        assert_eq!(metrics.starved, 1);
+        assert_eq!(metrics.dispatched, 2);
+
+        scheduler.remove("jira");
+        assert!(scheduler.all_metrics().is_empty());
+    }
+}
@@ -0,0 +1,775 @@
+/// Download manager for plugin datasets
+///
+/// Plugins fetching datasets (emoji databases, currency rates, models) get
+/// robust downloads into their cache directory:
+/// - interrupted downloads resume from the partial file (`Range` + `If-Range`)
+/// - unchanged files are revalidated with `ETag` / `Last-Modified` instead
+///   of downloaded again
+/// - optional SHA-256 checksum validation
+/// - progress callbacks
+/// - a limit on concurrent downloads shared by all plugins
+/// - with an `HttpCache`, responses are shared by all plugins: a URL another
+///   plugin already downloaded is copied from the cache instead of fetched
+/// - with a `NetworkPolicy`, hosts outside the plugin's allowlist are
+///   refused, whether requested directly or reached through a redirect
+use crate::actions::now_millis;
+use crate::http_cache::{HttpCache, ResponseHeaders};
+use crate::logging::Diagnostics;
+use crate::network::NetworkPolicy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Semaphore;
+
+/// Default number of downloads running at the same time
+pub const DEFAULT_MAX_CONCURRENT_DOWNLOADS: usize = 4;
+
+/// Most redirects followed by a download
+pub const MAX_REDIRECTS: usize = 10;
+
+/// Callback receiving download progress
+pub type ProgressCallback = Arc<dyn Fn(&DownloadProgress) + Send + Sync>;
+
+/// Progress of a running download
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadProgress {
+    /// Bytes of the file on disk so far, including resumed bytes
+    pub downloaded: u64,
+    /// Total size, if announced by the server
+    pub total: Option<u64>,
+}
+
+/// Options of a download
+#[derive(Clone, Default)]
+pub struct DownloadOpts {
+    /// Name of the file in the plugin cache; defaults to the URL's last segment
+    pub file_name: Option<String>,
+    /// Expected SHA-256 of the file, hex encoded
+    pub sha256: Option<String>,
+    /// Download again even if a cached copy exists
+    pub force: bool,
+    /// Largest accepted file, in bytes
+    pub max_bytes: Option<u64>,
+    /// Called as data arrives
+    pub on_progress: Option<ProgressCallback>,
+}
+
+impl DownloadOpts {
+    /// Create default options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store the download under a specific cache file name
+    pub fn file_name(mut self, name: &str) -> Self {
+        self.file_name = Some(name.to_string());
+        self
+    }
+
+    /// Validate the download against a SHA-256 checksum
+    pub fn sha256(mut self, checksum: &str) -> Self {
+        self.sha256 = Some(checksum.to_lowercase());
+        self
+    }
+
+    /// Refuse files larger than `max_bytes`
+    pub fn max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Receive progress updates
+    pub fn on_progress(mut self, callback: ProgressCallback) -> Self {
+        self.on_progress = Some(callback);
+        self
+    }
+}
+
+/// How a download request was satisfied
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownloadSource {
+    /// The file was downloaded from scratch
+    Network,
+    /// A partial download was completed
+    Resumed,
+    /// The server confirmed the cached copy is current
+    Revalidated,
+    /// The file was copied from the HTTP cache shared by all plugins
+    HttpCache,
+    /// The file was written from an API trace, see `api_trace`
+    Replayed,
+}
+
+/// A completed download
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Downloaded {
+    /// Path of the file in the plugin cache
+    pub path: PathBuf,
+    /// How the file was obtained
+    pub source: DownloadSource,
+}
+
+/// Cache validators stored next to each download
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DownloadMeta {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl DownloadMeta {
+    fn load(path: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn save(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string(self)
+            .map_err(|e| format!("Failed to serialize download metadata: {}", e))?;
+        std::fs::write(path, content).map_err(|e| format!("Failed to write download metadata: {}", e))
+    }
+
+    fn has_validator(&self) -> bool {
+        self.etag.is_some() || self.last_modified.is_some()
+    }
+}
+
+/// Settings of the HTTP client shared by all plugins
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpClientConfig {
+    /// Most downloads running at the same time
+    pub max_concurrent: usize,
+    /// Limit on a whole request, None for no limit
+    pub timeout: Option<Duration>,
+    /// Limit on connecting to a host, None for no limit
+    pub connect_timeout: Option<Duration>,
+    /// `User-Agent` header sent with every request
+    pub user_agent: Option<String>,
+}
+
+impl Default for HttpClientConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent: DEFAULT_MAX_CONCURRENT_DOWNLOADS,
+            timeout: None,
+            connect_timeout: None,
+            user_agent: None,
+        }
+    }
+}
+
+/// Shared download client with a concurrency limit
+#[derive(Clone)]
+pub struct Downloader {
+    client: reqwest::Client,
+    permits: Arc<Semaphore>,
+    http_cache: Option<HttpCache>,
+    network_policy: Option<NetworkPolicy>,
+    diagnostics: Diagnostics,
+}
+
+impl Default for Downloader {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CONCURRENT_DOWNLOADS)
+    }
+}
+
+impl Downloader {
+    /// Create a downloader running at most `max_concurrent` downloads at once
+    pub fn new(max_concurrent: usize) -> Self {
+        Self::with_config(&HttpClientConfig {
+            max_concurrent,
+            ..Default::default()
+        })
+    }
+
+    /// Create a downloader with custom client settings
+    pub fn with_config(config: &HttpClientConfig) -> Self {
+        // Redirects are followed by `send`, which checks each hop's host
+        let mut client = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none());
+        if let Some(timeout) = config.timeout {
+            client = client.timeout(timeout);
+        }
+        if let Some(timeout) = config.connect_timeout {
+            client = client.connect_timeout(timeout);
+        }
+        if let Some(user_agent) = &config.user_agent {
+            client = client.user_agent(user_agent.clone());
+        }
+
+        Self {
+            client: client.build().unwrap_or_default(),
+            permits: Arc::new(Semaphore::new(config.max_concurrent.max(1))),
+            http_cache: None,
+            network_policy: None,
+            diagnostics: Diagnostics::process(),
+        }
+    }
+
+    /// Report failures through an instance's diagnostics
+    pub fn with_diagnostics(mut self, diagnostics: Diagnostics) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    /// Share responses through an HTTP cache
+    pub fn with_http_cache(mut self, cache: HttpCache) -> Self {
+        self.http_cache = Some(cache);
+        self
+    }
+
+    /// Get the HTTP cache responses are shared through
+    pub fn http_cache(&self) -> Option<&HttpCache> {
+        self.http_cache.as_ref()
+    }
+
+    /// Only connect to the hosts a plugin may reach
+    ///
+    /// The client, download slots and HTTP cache stay shared with the
+    /// downloader this one was cloned from.
+    pub fn with_network_policy(mut self, policy: NetworkPolicy) -> Self {
+        self.network_policy = Some(policy);
+        self
+    }
+
+    /// Check that the network policy allows a URL's host
+    fn check_host(&self, url: &reqwest::Url) -> Result<(), String> {
+        match &self.network_policy {
+            Some(policy) => policy.check(url.as_str(), url.host_str().unwrap_or_default()),
+            None => Ok(()),
+        }
+    }
+
+    /// Send a request, following redirects to allowed hosts
+    async fn send(&self, mut request: reqwest::Request) -> Result<reqwest::Response, String> {
+        let url = request.url().to_string();
+
+        for _ in 0..=MAX_REDIRECTS {
+            self.check_host(request.url())?;
+            let headers = request.headers().clone();
+            let response = self
+                .client
+                .execute(request)
+                .await
+                .map_err(|e| format!("Failed to download {}: {}", url, e))?;
+
+            let location = response
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|location| location.to_str().ok());
+            let next = match location {
+                Some(location)
+                    if response.status().is_redirection()
+                        && response.status() != reqwest::StatusCode::NOT_MODIFIED =>
+                {
+                    response
+                        .url()
+                        .join(location)
+                        .map_err(|e| format!("Failed to download {}: invalid redirect: {}", url, e))?
+                }
+                _ => return Ok(response),
+            };
+
+            request = reqwest::Request::new(reqwest::Method::GET, next);
+            *request.headers_mut() = headers;
+        }
+
+        Err(format!("Failed to download {}: too many redirects", url))
+    }
+
+    /// Download a URL into a directory
+    ///
+    /// Plugins normally go through `VoltPluginAPI::download`, which picks
+    /// the plugin's cache directory and validates the file name.
+    ///
+    /// # Arguments
+    /// * `dir` - Directory the file is stored in
+    /// * `file_name` - Name of the file in `dir`
+    /// * `url` - URL to download
+    /// * `opts` - Download options
+    pub async fn download(
+        &self,
+        dir: &Path,
+        file_name: &str,
+        url: &str,
+        opts: &DownloadOpts,
+    ) -> Result<Downloaded, String> {
+        let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+        self.check_host(&parsed)?;
+
+        let _permit = self
+            .permits
+            .acquire()
+            .await
+            .map_err(|e| format!("Failed to acquire download slot: {}", e))?;
+
+        let path = dir.join(file_name);
+        let part_path = dir.join(format!("{}.part", file_name));
+        let meta_path = dir.join(format!("{}.meta.json", file_name));
+        let meta = DownloadMeta::load(&meta_path).filter(|meta| meta.url == url);
+        let shared = self
+            .http_cache
+            .as_ref()
+            .filter(|_| !opts.force)
+            .and_then(|cache| cache.lookup(url));
+
+        if shared.as_ref().is_some_and(|shared| shared.is_fresh(now_millis()))
+            && let Ok(downloaded) = self.copy_shared(url, &path, &meta_path, opts)
+        {
+            return Ok(downloaded);
+        }
+
+        let mut request = self.client.get(url);
+        let mut resume_from = 0;
+        // Whether the request revalidates the shared copy rather than the plugin's
+        let mut revalidating_shared = false;
+
+        if let Some(meta) = &meta {
+            if !opts.force && path.exists() && meta.has_validator() {
+                request = with_validators(request, meta, false);
+            } else if let Ok(partial) = std::fs::metadata(&part_path)
+                && partial.len() > 0
+                && meta.has_validator()
+            {
+                resume_from = partial.len();
+                request = with_validators(request, meta, true)
+                    .header(reqwest::header::RANGE, format!("bytes={}-", resume_from));
+            }
+        }
+        if resume_from == 0
+            && !(meta.as_ref().is_some_and(DownloadMeta::has_validator) && path.exists())
+            && let Some(shared) = shared.as_ref().filter(|shared| shared.has_validator())
+        {
+            let shared_meta = DownloadMeta {
+                url: url.to_string(),
+                etag: shared.etag.clone(),
+                last_modified: shared.last_modified.clone(),
+            };
+            request = with_validators(request, &shared_meta, false);
+            revalidating_shared = true;
+        }
+
+        let request = request
+            .build()
+            .map_err(|e| format!("Failed to download {}: {}", url, e))?;
+        let mut response = self.send(request).await?;
+        let status = response.status();
+        let headers = ResponseHeaders::from_headers(response.headers());
+
+        if status == reqwest::StatusCode::NOT_MODIFIED && revalidating_shared {
+            if let Some(cache) = &self.http_cache {
+                cache.revalidated(url, &headers);
+            }
+            let mut downloaded = self.copy_shared(url, &path, &meta_path, opts)?;
+            downloaded.source = DownloadSource::Revalidated;
+            return Ok(downloaded);
+        }
+        if status == reqwest::StatusCode::NOT_MODIFIED {
+            if let Some(cache) = &self.http_cache {
+                cache.revalidated(url, &headers);
+            }
+            verify_checksum(&path, opts.sha256.as_deref())?;
+            return Ok(Downloaded {
+                path,
+                source: DownloadSource::Revalidated,
+            });
+        }
+        if !status.is_success() {
+            return Err(format!("Failed to download {}: HTTP {}", url, status));
+        }
+
+        // The server may ignore the range (or the file changed): start over
+        let resumed = resume_from > 0 && status == reqwest::StatusCode::PARTIAL_CONTENT;
+        if !resumed {
+            resume_from = 0;
+        }
+
+        let header = |name: reqwest::header::HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let new_meta = DownloadMeta {
+            url: url.to_string(),
+            etag: header(reqwest::header::ETAG),
+            last_modified: header(reqwest::header::LAST_MODIFIED),
+        };
+        // Saved before the body so an interrupted download can be resumed
+        new_meta.save(&meta_path)?;
+
+        let total = response.content_length().map(|length| length + resume_from);
+        if let (Some(total), Some(max_bytes)) = (total, opts.max_bytes)
+            && total > max_bytes
+        {
+            return Err(too_large(url, max_bytes));
+        }
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resumed)
+            .truncate(!resumed)
+            .open(&part_path)
+            .await
+            .map_err(|e| format!("Failed to open download file: {}", e))?;
+
+        let mut downloaded = resume_from;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| format!("Failed to download {}: {}", url, e))?
+        {
+            file.write_all(&chunk)
+                .await
+                .map_err(|e| format!("Failed to write download file: {}", e))?;
+            downloaded += chunk.len() as u64;
+            // Servers may not announce the size, or lie about it
+            if opts.max_bytes.is_some_and(|max_bytes| downloaded > max_bytes) {
+                drop(file);
+                let _ = std::fs::remove_file(&part_path);
+                return Err(too_large(url, opts.max_bytes.unwrap_or_default()));
+            }
+
+            if let Some(on_progress) = &opts.on_progress {
+                on_progress(&DownloadProgress { downloaded, total });
+            }
+        }
+        file.flush()
+            .await
+            .map_err(|e| format!("Failed to write download file: {}", e))?;
+        drop(file);
+
+        if let Err(e) = verify_checksum(&part_path, opts.sha256.as_deref()) {
+            let _ = std::fs::remove_file(&part_path);
+            return Err(e);
+        }
+
+        std::fs::rename(&part_path, &path).map_err(|e| format!("Failed to store download: {}", e))?;
+
+        if let Some(cache) = &self.http_cache
+            && let Err(e) = cache.store(url, &headers, &path)
+        {
+            self.diagnostics.warn("download", &format!("Failed to cache {}: {}", url, e));
+        }
+
+        Ok(Downloaded {
+            path,
+            source: if resumed {
+                DownloadSource::Resumed
+            } else {
+                DownloadSource::Network
+            },
+        })
+    }
+
+    /// Copy the shared response of a URL into a plugin's cache
+    fn copy_shared(
+        &self,
+        url: &str,
+        path: &Path,
+        meta_path: &Path,
+        opts: &DownloadOpts,
+    ) -> Result<Downloaded, String> {
+        let shared = self
+            .http_cache
+            .as_ref()
+            .and_then(|cache| cache.touch(url))
+            .ok_or_else(|| format!("{} is not cached", url))?;
+        if opts.max_bytes.is_some_and(|max_bytes| shared.size > max_bytes) {
+            return Err(too_large(url, opts.max_bytes.unwrap_or_default()));
+        }
+        verify_checksum(&shared.body, opts.sha256.as_deref())?;
+
+        std::fs::copy(&shared.body, path).map_err(|e| format!("Failed to store download: {}", e))?;
+        // Later downloads revalidate the plugin's copy like a network download
+        DownloadMeta {
+            url: url.to_string(),
+            etag: shared.etag,
+            last_modified: shared.last_modified,
+        }
+        .save(meta_path)?;
+
+        Ok(Downloaded {
+            path: path.to_path_buf(),
+            source: DownloadSource::HttpCache,
+        })
+    }
+}
+
+/// Add conditional request headers
+///
+/// For a resume, validators go in `If-Range` so a changed file is sent in
+/// full; otherwise they make the request conditional.
+fn with_validators(
+    request: reqwest::RequestBuilder,
+    meta: &DownloadMeta,
+    resume: bool,
+) -> reqwest::RequestBuilder {
+    use reqwest::header::{IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_RANGE};
+
+    match (&meta.etag, &meta.last_modified, resume) {
+        (Some(etag), _, true) => request.header(IF_RANGE, etag),
+        (None, Some(modified), true) => request.header(IF_RANGE, modified),
+        (etag, modified, false) => {
+            let request = match etag {
+                Some(etag) => request.header(IF_NONE_MATCH, etag),
+                None => request,
+            };
+            match modified {
+                Some(modified) => request.header(IF_MODIFIED_SINCE, modified),
+                None => request,
+            }
+        }
+        (None, None, true) => request,
+    }
+}
+
+fn too_large(url: &str, max_bytes: u64) -> String {
+    format!("Failed to download {}: larger than {} bytes", url, max_bytes)
+}
+
+/// Check a file against an expected SHA-256 checksum
+fn verify_checksum(path: &Path, expected: Option<&str>) -> Result<(), String> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+
+    let content = std::fs::read(path).map_err(|e| format!("Failed to read download: {}", e))?;
+    let actual: String = Sha256::digest(&content)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    if actual != expected.to_lowercase() {
+        return Err(format!(
+            "Checksum mismatch for {}: expected {}, got {}",
+            path.display(),
+            expected,
+            actual
+        ));
+    }
+
+    Ok(())
+}
+
+/// Derive a cache file name from a URL
+pub fn file_name_from_url(url: &str) -> Option<String> {
+    let path = url.split(['?', '#']).next()?;
+    let (_host, path) = path.split_once("://")?.1.split_once('/')?;
+    let name = path.rsplit('/').next()?;
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    const BODY: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    const ETAG: &str = "\"v1\"";
+
+    /// Minimal HTTP server honoring Range, If-Range and If-None-Match, and
+    /// redirecting `/redirect?to=<url>`
+    async fn serve(requests: Arc<Mutex<Vec<String>>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let mut buffer = vec![0; 4096];
+                let read = stream.read(&mut buffer).await.unwrap();
+                let request = String::from_utf8_lossy(&buffer[..read]).to_lowercase();
+                requests.lock().unwrap().push(request.clone());
+
+                let header = |name: &str| {
+                    request
+                        .lines()
+                        .find_map(|line| line.strip_prefix(&format!("{}: ", name)))
+                        .map(str::to_string)
+                };
+
+                let redirect = request
+                    .split_whitespace()
+                    .nth(1)
+                    .and_then(|path| path.strip_prefix("/redirect?to="));
+                let response = if let Some(target) = redirect {
+                    format!("HTTP/1.1 302 Found\r\nlocation: {}\r\ncontent-length: 0\r\n\r\n", target).into_bytes()
+                } else if header("if-none-match").as_deref() == Some(ETAG) {
+                    "HTTP/1.1 304 Not Modified\r\ncontent-length: 0\r\n\r\n".as_bytes().to_vec()
+                } else {
+                    let start = header("range")
+                        .filter(|_| header("if-range").as_deref() == Some(ETAG))
+                        .and_then(|range| range.strip_prefix("bytes=")?.strip_suffix('-')?.parse().ok())
+                        .unwrap_or(0usize);
+                    let status = if start > 0 { "206 Partial Content" } else { "200 OK" };
+                    let cache_control = if request.contains("no-cache") { "no-cache" } else { "max-age=60" };
+                    let mut response = format!(
+                        "HTTP/1.1 {}\r\netag: {}\r\ncache-control: {}\r\ncontent-length: {}\r\n\r\n",
+                        status,
+                        ETAG,
+                        cache_control,
+                        BODY.len() - start
+                    )
+                    .into_bytes();
+                    response.extend_from_slice(&BODY[start..]);
+                    response
+                };
+                let _ = tokio::io::AsyncWriteExt::write_all(&mut stream, &response).await;
+            }
+        });
+
+        format!("http://{}/data/emoji.json?v=2", address)
+    }
+
+    fn sha256_hex(data: &[u8]) -> String {
+        Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[tokio::test]
+    async fn test_download_revalidate_and_resume() {
+        let dir = std::env::temp_dir().join("volt_test_download");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let url = serve(requests.clone()).await;
+        assert_eq!(file_name_from_url(&url).as_deref(), Some("emoji.json"));
+
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        let sink = progress.clone();
+        let opts = DownloadOpts::new()
+            .sha256(&sha256_hex(BODY))
+            .on_progress(Arc::new(move |p: &DownloadProgress| sink.lock().unwrap().push(*p)));
+        let downloader = Downloader::new(2);
+
+        let first = downloader.download(&dir, "emoji.json", &url, &opts).await.unwrap();
+        assert_eq!(first.source, DownloadSource::Network);
+        assert_eq!(std::fs::read(&first.path).unwrap(), BODY);
+        assert_eq!(
+            progress.lock().unwrap().last(),
+            Some(&DownloadProgress {
+                downloaded: BODY.len() as u64,
+                total: Some(BODY.len() as u64)
+            })
+        );
+
+        let second = downloader.download(&dir, "emoji.json", &url, &opts).await.unwrap();
+        assert_eq!(second.source, DownloadSource::Revalidated);
+
+        // Simulate an interrupted download
+        std::fs::remove_file(&first.path).unwrap();
+        std::fs::write(dir.join("emoji.json.part"), &BODY[..10]).unwrap();
+        let resumed = downloader.download(&dir, "emoji.json", &url, &opts).await.unwrap();
+        assert_eq!(resumed.source, DownloadSource::Resumed);
+        assert_eq!(std::fs::read(&resumed.path).unwrap(), BODY);
+        assert!(requests.lock().unwrap()[2].contains("range: bytes=10-"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_checksum_mismatch_is_rejected() {
+        let dir = std::env::temp_dir().join("volt_test_download_checksum");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let url = serve(Arc::default()).await;
+        let opts = DownloadOpts::new().sha256(&sha256_hex(b"something else"));
+        let error = Downloader::default()
+            .download(&dir, "emoji.json", &url, &opts)
+            .await
+            .unwrap_err();
+
+        assert!(error.starts_with("Checksum mismatch"));
+        assert!(!dir.join("emoji.json").exists());
+        assert!(!dir.join("emoji.json.part").exists());
+
+        let opts = DownloadOpts::new().max_bytes(10);
+        let error = Downloader::default()
+            .download(&dir, "emoji.json", &url, &opts)
+            .await
+            .unwrap_err();
+        assert!(error.ends_with("larger than 10 bytes"));
+        assert!(!dir.join("emoji.json").exists());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_http_cache_is_shared_by_plugins() {
+        let dir = std::env::temp_dir().join("volt_test_download_shared");
+        let _ = std::fs::remove_dir_all(&dir);
+        let (weather, finance) = (dir.join("weather"), dir.join("finance"));
+        std::fs::create_dir_all(&weather).unwrap();
+        std::fs::create_dir_all(&finance).unwrap();
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let url = serve(requests.clone()).await;
+        let downloader = Downloader::default().with_http_cache(HttpCache::new(dir.join("http")));
+        let opts = DownloadOpts::new();
+
+        let first = downloader.download(&weather, "emoji.json", &url, &opts).await.unwrap();
+        assert_eq!(first.source, DownloadSource::Network);
+        let shared = downloader.download(&finance, "emoji.json", &url, &opts).await.unwrap();
+        assert_eq!(shared.source, DownloadSource::HttpCache);
+        assert_eq!(std::fs::read(&shared.path).unwrap(), BODY);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+
+        // Stale responses are revalidated, then copied
+        let url = url.replace("?v=2", "?no-cache");
+        downloader.download(&weather, "emoji.json", &url, &opts).await.unwrap();
+        let revalidated = downloader.download(&finance, "emoji.json", &url, &opts).await.unwrap();
+        assert_eq!(revalidated.source, DownloadSource::Revalidated);
+        assert_eq!(std::fs::read(&revalidated.path).unwrap(), BODY);
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert!(requests[2].contains("if-none-match"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_network_policy_checks_redirects() {
+        use crate::network::{HostPattern, NetworkGrants, NETWORK_GRANTS_FILE};
+
+        let dir = std::env::temp_dir().join("volt_test_download_policy");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let url = serve(requests.clone()).await;
+        let origin = url.split("/data").next().unwrap().to_string();
+        let port = origin.rsplit(':').next().unwrap();
+        let allow = vec![HostPattern::parse("127.0.0.1").unwrap()];
+        let grants = NetworkGrants::new(dir.join(NETWORK_GRANTS_FILE));
+        let downloader = Downloader::default().with_network_policy(NetworkPolicy::new("emoji", "Emoji", allow, grants));
+        let opts = DownloadOpts::new();
+
+        // Redirects within the allowlist are followed
+        let hop = format!("{}/redirect?to={}/data/emoji.json", origin, origin);
+        let downloaded = downloader.download(&dir, "emoji.json", &hop, &opts).await.unwrap();
+        assert_eq!(std::fs::read(&downloaded.path).unwrap(), BODY);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+
+        // Hosts outside it are refused before connecting, redirected to or not
+        let escape = format!("{}/redirect?to=http://localhost:{}/data/emoji.json", origin, port);
+        let error = downloader.download(&dir, "escape.json", &escape, &opts).await.unwrap_err();
+        assert!(error.contains("may not connect to localhost"), "{}", error);
+        let direct = format!("http://localhost:{}/data/emoji.json", port);
+        assert!(downloader.download(&dir, "direct.json", &direct, &opts).await.is_err());
+        assert_eq!(requests.lock().unwrap().len(), 3);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,196 @@
+/// Commands run with administrator rights
+///
+/// Some plugin actions need elevation, e.g. editing the hosts file or
+/// restarting a service. Plugins call `VoltPluginAPI::execute_elevated`,
+/// which requires the `execute_commands` and `elevation` permissions and
+/// asks the user through the host's `ElevationConfirmer` every time; the
+/// platform then asks again for credentials (UAC on Windows, `pkexec` on
+/// Linux, the administrator prompt of `osascript` on macOS). Every request
+/// is recorded in the audit trail (see `crate::audit`).
+use crate::platform::Platform;
+use crate::result::CommandSpec;
+use serde::Serialize;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+
+/// Callback installed by the host to confirm an elevation request
+///
+/// Returns whether the user allowed the command to run.
+pub type ElevationConfirmer = Arc<dyn Fn(&ElevationRequest) -> bool + Send + Sync>;
+
+/// An elevated command awaiting the user's confirmation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ElevationRequest {
+    /// Plugin asking for elevation
+    pub plugin_id: String,
+    /// Display name of the plugin
+    pub plugin_name: String,
+    /// The command to run
+    pub command: CommandSpec,
+    /// The command as a single line, as shown to the user
+    pub command_line: String,
+}
+
+/// Build the command running a program elevated on a platform
+///
+/// The program and arguments are quoted for the elevation helper, which
+/// on macOS and Windows goes through a command interpreter.
+pub fn elevated_command(spec: &CommandSpec, platform: Platform) -> Command {
+    let mut command = match platform {
+        Platform::Linux => {
+            let mut command = Command::new("pkexec");
+            command.arg(&spec.program).args(&spec.args);
+            command
+        }
+        Platform::Macos => {
+            let mut shell_line = posix_command_line(spec);
+            if let Some(dir) = &spec.working_dir {
+                shell_line = format!("cd {} && {}", posix_quote(dir), shell_line);
+            }
+            let script = format!(
+                "do shell script \"{}\" with administrator privileges",
+                shell_line.replace('\\', "\\\\").replace('"', "\\\"")
+            );
+            let mut command = Command::new("osascript");
+            command.arg("-e").arg(script);
+            command
+        }
+        Platform::Windows => {
+            let mut script = format!(
+                "$p = Start-Process -FilePath {} -Verb RunAs -Wait -PassThru",
+                powershell_quote(&spec.program)
+            );
+            if !spec.args.is_empty() {
+                let args: Vec<String> = spec.args.iter().map(|arg| windows_quote(arg)).collect();
+                script.push_str(&format!(
+                    " -ArgumentList {}",
+                    powershell_quote(&args.join(" "))
+                ));
+            }
+            if let Some(dir) = &spec.working_dir {
+                script.push_str(&format!(" -WorkingDirectory {}", powershell_quote(dir)));
+            }
+            script.push_str("; exit $p.ExitCode");
+
+            let mut command = Command::new("powershell.exe");
+            command.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+            command
+        }
+    };
+
+    // pkexec starts in root's home; the others were told above
+    if platform == Platform::Linux
+        && let Some(dir) = &spec.working_dir
+    {
+        command.current_dir(dir);
+    }
+    command
+}
+
+/// Run a command elevated and wait for it to finish
+///
+/// # Returns
+/// Err if the command couldn't start, the user cancelled the platform
+/// prompt, or the command exited with a failure
+pub fn run_elevated(spec: &CommandSpec) -> Result<(), String> {
+    let platform = Platform::current().ok_or("Elevation is not supported on this platform")?;
+    let mut command = elevated_command(spec, platform);
+
+    let status = command
+        .stdin(Stdio::null())
+        .status()
+        .map_err(|e| format!("Failed to start {:?}: {}", command.get_program(), e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("Elevated command exited with {}", status))
+    }
+}
+
+/// Render a command line for a POSIX shell
+fn posix_command_line(spec: &CommandSpec) -> String {
+    std::iter::once(&spec.program)
+        .chain(&spec.args)
+        .map(|part| posix_quote(part))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Quote a word for a POSIX shell
+fn posix_quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', "'\\''"))
+}
+
+/// Quote a string literal for PowerShell
+fn powershell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+/// Quote an argument for a Windows command line (`CommandLineToArgvW` rules)
+fn windows_quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        return arg.to_string();
+    }
+
+    let mut quoted = String::from("\"");
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            // Backslashes before a quote are doubled, and the quote escaped
+            '"' => {
+                quoted.push_str(&"\\".repeat(backslashes + 1));
+                backslashes = 0;
+            }
+            _ => backslashes = 0,
+        }
+        quoted.push(c);
+    }
+    // So are trailing ones, before the closing quote
+    quoted.push_str(&"\\".repeat(backslashes));
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command_parts(command: &Command) -> Vec<String> {
+        std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|part| part.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_elevated_commands_are_quoted() {
+        let spec = CommandSpec::new("systemctl")
+            .arg("restart")
+            .arg("it's here");
+
+        assert_eq!(
+            command_parts(&elevated_command(&spec, Platform::Linux)),
+            vec!["pkexec", "systemctl", "restart", "it's here"]
+        );
+        assert_eq!(
+            command_parts(&elevated_command(&spec, Platform::Macos)),
+            vec![
+                "osascript",
+                "-e",
+                r#"do shell script "'systemctl' 'restart' 'it'\\''s here'" with administrator privileges"#
+            ]
+        );
+
+        let spec = CommandSpec::new("sc.exe").arg("stop").arg(r#"My "Svc""#);
+        assert_eq!(
+            command_parts(&elevated_command(&spec, Platform::Windows))[4],
+            r#"$p = Start-Process -FilePath 'sc.exe' -Verb RunAs -Wait -PassThru -ArgumentList 'stop "My \"Svc\""'; exit $p.ExitCode"#
+        );
+        assert_eq!(
+            windows_quote(r"C:\Program Files\"),
+            r#""C:\Program Files\\""#
+        );
+    }
+}
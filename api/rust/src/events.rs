@@ -0,0 +1,938 @@
+/// Event bus between the host and plugins
+///
+/// Publishers send events to named topics, and each subscriber receives the
+/// events of its topics through a bounded queue of its own, so a slow plugin
+/// only ever delays itself. What happens when a subscriber's queue is full
+/// is chosen per topic (`OverflowPolicy`): the oldest queued event is dropped,
+/// or the publisher waits for room, at most `TopicConfig::block_timeout`,
+/// after which the event is dropped for that subscriber.
+///
+/// Topics delivered `AtLeastOnce` (by default the plugin lifecycle topics in
+/// `topics::LIFECYCLE`) never drop an event silently: their events are kept,
+/// and saved to disk by a `persistent` bus, until the subscriber acks them.
+/// A subscriber keeps at most `TopicConfig::capacity` unacked events; past
+/// that, `OverflowPolicy::Block` waits for an ack, and publishing fails if
+/// none comes in time.
+/// Events not acked within the ack timeout are delivered again, and events
+/// published while a subscriber is away wait for it to subscribe again.
+/// A plugin that was slow or restarting therefore can't miss that it was
+/// disabled.
+///
+/// Hosts forward the registry's events with `EventBus::attach`.
+use crate::actions::now_millis;
+use crate::api::VoltPluginAPI;
+use crate::locks;
+use crate::logging::Diagnostics;
+use crate::registry::{PluginRegistry, RegistryEvent};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// Events a subscriber queues per topic by default
+pub const DEFAULT_QUEUE_CAPACITY: usize = 256;
+
+/// Time a publisher waits for room in a full queue by default
+pub const DEFAULT_BLOCK_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// Time after which an event that wasn't acked is delivered again by default
+pub const DEFAULT_ACK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Topics of the events forwarded from the registry, see `EventBus::attach`
+pub mod topics {
+    /// A plugin was registered
+    pub const PLUGIN_REGISTERED: &str = "plugin.registered";
+    /// A plugin was unregistered
+    pub const PLUGIN_UNREGISTERED: &str = "plugin.unregistered";
+    /// A plugin was replaced by a fresh build
+    pub const PLUGIN_RELOADED: &str = "plugin.reloaded";
+    /// A plugin's state moved to its new ID
+    pub const PLUGIN_RENAMED: &str = "plugin.renamed";
+    /// The user enabled a plugin
+    pub const PLUGIN_ENABLED: &str = "plugin.enabled";
+    /// The user disabled a plugin
+    pub const PLUGIN_DISABLED: &str = "plugin.disabled";
+    /// An external plugin crashed
+    pub const PLUGIN_CRASHED: &str = "plugin.crashed";
+    /// Leftovers of a crashed session were cleaned up
+    pub const STALE_STATE_CLEANED: &str = "registry.cleaned";
+
+    /// Topics delivered at least once unless configured otherwise
+    pub const LIFECYCLE: &[&str] = &[
+        PLUGIN_REGISTERED,
+        PLUGIN_UNREGISTERED,
+        PLUGIN_RENAMED,
+        PLUGIN_ENABLED,
+        PLUGIN_DISABLED,
+    ];
+}
+
+/// What to do when a subscriber's queue is full
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OverflowPolicy {
+    /// Drop the oldest queued event to make room
+    #[default]
+    DropOldest,
+    /// Make the publisher wait for room, up to the topic's block timeout
+    Block,
+}
+
+/// Delivery guarantee of a topic
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Delivery {
+    /// Events may be dropped when a subscriber falls behind or is away
+    #[default]
+    AtMostOnce,
+    /// Events are kept until acked, and delivered again if they aren't
+    AtLeastOnce,
+}
+
+/// How the events of a topic are queued and delivered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TopicConfig {
+    /// Events each subscriber queues, or keeps unacked for `AtLeastOnce` topics
+    pub capacity: usize,
+    /// What to do when a subscriber's queue is full
+    pub overflow: OverflowPolicy,
+    /// Delivery guarantee
+    pub delivery: Delivery,
+    /// Time a publisher waits for room with `OverflowPolicy::Block`
+    pub block_timeout: Duration,
+}
+
+impl Default for TopicConfig {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_QUEUE_CAPACITY,
+            overflow: OverflowPolicy::DropOldest,
+            delivery: Delivery::AtMostOnce,
+            block_timeout: DEFAULT_BLOCK_TIMEOUT,
+        }
+    }
+}
+
+impl TopicConfig {
+    /// Configuration of a topic delivered at least once
+    pub fn critical() -> Self {
+        Self {
+            delivery: Delivery::AtLeastOnce,
+            ..Self::default()
+        }
+    }
+
+    /// Queue at most `capacity` events per subscriber
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Use an overflow policy
+    pub fn with_overflow(mut self, overflow: OverflowPolicy) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Wait at most `timeout` for room with `OverflowPolicy::Block`
+    pub fn with_block_timeout(mut self, timeout: Duration) -> Self {
+        self.block_timeout = timeout;
+        self
+    }
+}
+
+/// An event published on the bus
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BusEvent {
+    /// Identifier, increasing in publishing order; used to ack the event
+    pub id: u64,
+    /// Topic the event was published on
+    pub topic: String,
+    /// Content of the event
+    pub payload: Value,
+    /// Publishing time, in milliseconds since the Unix epoch
+    pub published_ms: u64,
+}
+
+/// Delivery counters of a topic
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicMetrics {
+    /// Events published
+    pub published: u64,
+    /// Events handed to subscribers, redeliveries included
+    pub delivered: u64,
+    /// Events a subscriber never received because its queue was full
+    pub dropped: u64,
+    /// Publications that had to wait for room in a queue
+    pub blocked: u64,
+    /// Events delivered again because they weren't acked in time
+    pub redelivered: u64,
+    /// Events waiting to be acked
+    pub unacked: u64,
+}
+
+/// Delivery counters of a subscriber
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriberMetrics {
+    /// Events waiting in the queue
+    pub queued: usize,
+    /// Events kept until acked, delivered or not
+    pub unacked: usize,
+    /// Events received
+    pub delivered: u64,
+    /// Events dropped because the queue was full
+    pub dropped: u64,
+}
+
+/// An event kept until acked
+#[derive(Debug, Clone)]
+struct Unacked {
+    event: BusEvent,
+    /// Last delivery, None until delivered
+    delivered_at: Option<Instant>,
+}
+
+/// Events waiting for a subscriber
+#[derive(Debug, Default)]
+struct Inbox {
+    queue: VecDeque<BusEvent>,
+    unacked: BTreeMap<u64, Unacked>,
+    delivered: u64,
+    dropped: u64,
+}
+
+/// A subscriber, kept while it is away so `AtLeastOnce` events reach it
+struct Subscriber {
+    topics: RwLock<HashSet<String>>,
+    inbox: Mutex<Inbox>,
+    /// Signalled when an event arrives
+    ready: Condvar,
+    /// Signalled when room is freed in the queue
+    room: Condvar,
+    /// Whether a `Subscription` is receiving the events
+    attached: AtomicBool,
+}
+
+impl Subscriber {
+    fn new() -> Self {
+        Self {
+            topics: RwLock::new(HashSet::new()),
+            inbox: Mutex::new(Inbox::default()),
+            ready: Condvar::new(),
+            room: Condvar::new(),
+            attached: AtomicBool::new(false),
+        }
+    }
+
+    fn inbox(&self) -> std::sync::MutexGuard<'_, Inbox> {
+        self.inbox.lock().unwrap_or_else(|p| p.into_inner())
+    }
+}
+
+/// State shared by every clone of a bus
+#[derive(Default)]
+struct Shared {
+    topics: RwLock<HashMap<String, TopicConfig>>,
+    subscribers: RwLock<HashMap<String, Arc<Subscriber>>>,
+    metrics: Mutex<HashMap<String, TopicMetrics>>,
+    next_id: AtomicU64,
+}
+
+/// Publishes events to the subscribers of their topic
+///
+/// Cheap to clone; all clones share the same topics and subscribers.
+#[derive(Clone)]
+pub struct EventBus {
+    shared: Arc<Shared>,
+    /// Directory unacked events are saved to, one file per subscriber
+    dir: Option<PathBuf>,
+    ack_timeout: Duration,
+    /// Where events that couldn't be saved are reported
+    diagnostics: Diagnostics,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    /// Create a bus keeping unacked events in memory only
+    pub fn new() -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                next_id: AtomicU64::new(1),
+                ..Shared::default()
+            }),
+            dir: None,
+            ack_timeout: DEFAULT_ACK_TIMEOUT,
+            diagnostics: Diagnostics::process(),
+        }
+    }
+
+    /// Create a bus saving unacked events to a directory
+    ///
+    /// Subscribers get the events saved by a previous session when they
+    /// subscribe again.
+    pub fn persistent(dir: PathBuf) -> Self {
+        Self {
+            dir: Some(dir),
+            ..Self::new()
+        }
+    }
+
+    /// Deliver events again when they weren't acked within `timeout`
+    pub fn with_ack_timeout(mut self, timeout: Duration) -> Self {
+        self.ack_timeout = timeout;
+        self
+    }
+
+    /// Report failures through an instance's diagnostics
+    pub fn with_diagnostics(mut self, diagnostics: Diagnostics) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    /// Set how the events of a topic are queued and delivered
+    pub fn configure_topic(&self, topic: &str, config: TopicConfig) {
+        locks::write(&self.shared.topics, "event bus topics").insert(topic.to_string(), config);
+    }
+
+    /// Get the configuration of a topic
+    ///
+    /// Unconfigured lifecycle topics are delivered at least once.
+    pub fn topic_config(&self, topic: &str) -> TopicConfig {
+        if let Some(config) = locks::read(&self.shared.topics, "event bus topics").get(topic) {
+            return *config;
+        }
+        if topics::LIFECYCLE.contains(&topic) {
+            TopicConfig::critical()
+        } else {
+            TopicConfig::default()
+        }
+    }
+
+    /// Subscribe to topics
+    ///
+    /// Subscribing again with the same ID replaces the topics, and picks up
+    /// the events kept while the subscriber was away.
+    ///
+    /// # Arguments
+    /// * `subscriber_id` - Subscriber, usually a plugin ID
+    /// * `topics` - Topics to receive events of
+    pub fn subscribe(&self, subscriber_id: &str, topics: &[&str]) -> Result<Subscription, String> {
+        VoltPluginAPI::validate_plugin_id(subscriber_id)?;
+
+        let (subscriber, created) = {
+            let mut subscribers = locks::write(&self.shared.subscribers, "event bus subscribers");
+            match subscribers.get(subscriber_id) {
+                Some(subscriber) => (subscriber.clone(), false),
+                None => {
+                    let subscriber = Arc::new(Subscriber::new());
+                    subscribers.insert(subscriber_id.to_string(), subscriber.clone());
+                    (subscriber, true)
+                }
+            }
+        };
+        if subscriber.attached.swap(true, Ordering::SeqCst) {
+            return Err(format!("'{}' is already subscribed", subscriber_id));
+        }
+        *locks::write(&subscriber.topics, "event bus subscriber topics") =
+            topics.iter().map(|topic| topic.to_string()).collect();
+
+        if created {
+            let saved = self.load(subscriber_id)?;
+            if let Some(last) = saved.last() {
+                self.shared.next_id.fetch_max(last.id + 1, Ordering::SeqCst);
+            }
+            let mut inbox = subscriber.inbox();
+            for event in saved {
+                self.record(&event.topic, |metrics| metrics.unacked += 1);
+                inbox.unacked.insert(
+                    event.id,
+                    Unacked {
+                        event,
+                        delivered_at: None,
+                    },
+                );
+            }
+        }
+
+        Ok(Subscription {
+            bus: self.clone(),
+            subscriber_id: subscriber_id.to_string(),
+            subscriber,
+        })
+    }
+
+    /// Forget a subscriber and the events kept for it
+    pub fn unsubscribe(&self, subscriber_id: &str) -> Result<(), String> {
+        let removed =
+            locks::write(&self.shared.subscribers, "event bus subscribers").remove(subscriber_id);
+        if let Some(subscriber) = removed {
+            subscriber.attached.store(false, Ordering::SeqCst);
+            let inbox = subscriber.inbox();
+            for unacked in inbox.unacked.values() {
+                self.record(&unacked.event.topic, |metrics| metrics.unacked -= 1);
+            }
+            subscriber.room.notify_all();
+        }
+        self.save(subscriber_id, &[])
+    }
+
+    /// Publish an event
+    ///
+    /// Returns once every subscriber of the topic has the event queued, or
+    /// had it dropped. With `OverflowPolicy::Block` this may wait up to the
+    /// topic's block timeout per subscriber with a full queue.
+    ///
+    /// # Returns
+    /// The ID of the event, or Err if the topic is empty or the event could
+    /// not be saved
+    pub fn publish(&self, topic: &str, payload: Value) -> Result<u64, String> {
+        if topic.trim().is_empty() {
+            return Err("Event topic cannot be empty".to_string());
+        }
+        let config = self.topic_config(topic);
+        let event = BusEvent {
+            id: self.shared.next_id.fetch_add(1, Ordering::SeqCst),
+            topic: topic.to_string(),
+            payload,
+            published_ms: now_millis(),
+        };
+        self.record(topic, |metrics| metrics.published += 1);
+
+        let subscribers: Vec<(String, Arc<Subscriber>)> =
+            locks::read(&self.shared.subscribers, "event bus subscribers")
+                .iter()
+                .filter(|(_, subscriber)| {
+                    locks::read(&subscriber.topics, "event bus subscriber topics").contains(topic)
+                })
+                .map(|(id, subscriber)| (id.clone(), subscriber.clone()))
+                .collect();
+
+        let mut saved = Ok(());
+        for (subscriber_id, subscriber) in subscribers {
+            let result = match config.delivery {
+                Delivery::AtLeastOnce => self.keep(&subscriber_id, &subscriber, &event, &config),
+                Delivery::AtMostOnce => {
+                    self.enqueue(&subscriber, &event, &config);
+                    Ok(())
+                }
+            };
+            if let Err(e) = result {
+                self.diagnostics.warn(
+                    "events",
+                    &format!(
+                        "Event {} for '{}' not saved: {}",
+                        event.id, subscriber_id, e
+                    ),
+                );
+                saved = Err(e);
+            }
+        }
+
+        saved.map(|()| event.id)
+    }
+
+    /// Get the delivery counters of a topic
+    pub fn metrics(&self, topic: &str) -> TopicMetrics {
+        self.shared
+            .metrics
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .get(topic)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Get the delivery counters of a subscriber
+    pub fn subscriber_metrics(&self, subscriber_id: &str) -> Option<SubscriberMetrics> {
+        let subscriber = locks::read(&self.shared.subscribers, "event bus subscribers")
+            .get(subscriber_id)
+            .cloned()?;
+        let inbox = subscriber.inbox();
+
+        Some(SubscriberMetrics {
+            queued: inbox.queue.len(),
+            unacked: inbox.unacked.len(),
+            delivered: inbox.delivered,
+            dropped: inbox.dropped,
+        })
+    }
+
+    /// Publish the registry's events on the `topics` topics
+    ///
+    /// The payload of each event is the serialized `RegistryEvent`.
+    pub fn attach(&self, registry: &PluginRegistry) {
+        let bus = self.clone();
+        let diagnostics = registry.diagnostics();
+        registry.subscribe(Arc::new(move |event: &RegistryEvent| {
+            let topic = match event {
+                RegistryEvent::PluginRegistered { .. } => topics::PLUGIN_REGISTERED,
+                RegistryEvent::PluginUnregistered { .. } => topics::PLUGIN_UNREGISTERED,
+                RegistryEvent::PluginReloaded { .. } => topics::PLUGIN_RELOADED,
+                RegistryEvent::PluginRenamed { .. } => topics::PLUGIN_RENAMED,
+                RegistryEvent::PluginEnabled { .. } => topics::PLUGIN_ENABLED,
+                RegistryEvent::PluginDisabled { .. } => topics::PLUGIN_DISABLED,
+                RegistryEvent::PluginCrashed { .. } => topics::PLUGIN_CRASHED,
+                RegistryEvent::StaleStateCleaned { .. } => topics::STALE_STATE_CLEANED,
+            };
+            let published = serde_json::to_value(event)
+                .map_err(|e| format!("Failed to serialize event: {}", e))
+                .and_then(|payload| bus.publish(topic, payload));
+            if let Err(e) = published {
+                diagnostics.warn("events", &format!("Failed to publish '{}': {}", topic, e));
+            }
+        }));
+    }
+
+    /// Keep an event until the subscriber acks it
+    ///
+    /// With the subscriber's unacked events at the topic's capacity,
+    /// `OverflowPolicy::Block` waits for an ack; the event is refused if
+    /// there's still no room.
+    fn keep(
+        &self,
+        subscriber_id: &str,
+        subscriber: &Subscriber,
+        event: &BusEvent,
+        config: &TopicConfig,
+    ) -> Result<(), String> {
+        let mut inbox = subscriber.inbox();
+        if inbox.unacked.len() >= config.capacity {
+            // Nobody would ack while the subscriber is away
+            if config.overflow == OverflowPolicy::Block && subscriber.attached.load(Ordering::SeqCst) {
+                self.record(&event.topic, |metrics| metrics.blocked += 1);
+                inbox = subscriber
+                    .room
+                    .wait_timeout_while(inbox, config.block_timeout, |inbox| {
+                        inbox.unacked.len() >= config.capacity
+                            && subscriber.attached.load(Ordering::SeqCst)
+                    })
+                    .unwrap_or_else(|p| p.into_inner())
+                    .0;
+            }
+            if inbox.unacked.len() >= config.capacity {
+                inbox.dropped += 1;
+                self.record(&event.topic, |metrics| metrics.dropped += 1);
+                return Err(format!(
+                    "'{}' has {} unacked events",
+                    subscriber_id,
+                    inbox.unacked.len()
+                ));
+            }
+        }
+
+        inbox.unacked.insert(
+            event.id,
+            Unacked {
+                event: event.clone(),
+                delivered_at: None,
+            },
+        );
+        self.record(&event.topic, |metrics| metrics.unacked += 1);
+        let saved = self.save_inbox(subscriber_id, &inbox);
+        drop(inbox);
+
+        subscriber.ready.notify_all();
+        saved
+    }
+
+    /// Queue an event, applying the topic's overflow policy
+    fn enqueue(&self, subscriber: &Subscriber, event: &BusEvent, config: &TopicConfig) {
+        // Nobody would receive it
+        if !subscriber.attached.load(Ordering::SeqCst) {
+            return;
+        }
+
+        let mut inbox = subscriber.inbox();
+        if inbox.queue.len() >= config.capacity {
+            match config.overflow {
+                OverflowPolicy::DropOldest => {
+                    if let Some(dropped) = inbox.queue.pop_front() {
+                        inbox.dropped += 1;
+                        self.record(&dropped.topic, |metrics| metrics.dropped += 1);
+                    }
+                }
+                OverflowPolicy::Block => {
+                    self.record(&event.topic, |metrics| metrics.blocked += 1);
+                    let (waited, _) = subscriber
+                        .room
+                        .wait_timeout_while(inbox, config.block_timeout, |inbox| {
+                            inbox.queue.len() >= config.capacity
+                                && subscriber.attached.load(Ordering::SeqCst)
+                        })
+                        .unwrap_or_else(|p| p.into_inner());
+                    inbox = waited;
+                    if inbox.queue.len() >= config.capacity
+                        || !subscriber.attached.load(Ordering::SeqCst)
+                    {
+                        inbox.dropped += 1;
+                        self.record(&event.topic, |metrics| metrics.dropped += 1);
+                        return;
+                    }
+                }
+            }
+        }
+
+        inbox.queue.push_back(event.clone());
+        drop(inbox);
+        subscriber.ready.notify_all();
+    }
+
+    fn record(&self, topic: &str, update: impl FnOnce(&mut TopicMetrics)) {
+        let mut metrics = self
+            .shared
+            .metrics
+            .lock()
+            .unwrap_or_else(|p| p.into_inner());
+        update(metrics.entry(topic.to_string()).or_default());
+    }
+
+    fn save_inbox(&self, subscriber_id: &str, inbox: &Inbox) -> Result<(), String> {
+        let events: Vec<&BusEvent> = inbox
+            .unacked
+            .values()
+            .map(|unacked| &unacked.event)
+            .collect();
+        self.save(subscriber_id, &events)
+    }
+
+    /// Save the unacked events of a subscriber, removing the file when none are left
+    fn save(&self, subscriber_id: &str, events: &[&BusEvent]) -> Result<(), String> {
+        let Some(path) = self.path(subscriber_id) else {
+            return Ok(());
+        };
+        if events.is_empty() {
+            return match std::fs::remove_file(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    Err(format!("Failed to remove saved events: {}", e))
+                }
+                _ => Ok(()),
+            };
+        }
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create event directory: {}", e))?;
+        }
+        let content =
+            serde_json::to_vec(events).map_err(|e| format!("Failed to serialize events: {}", e))?;
+        write_atomic(&path, &content)
+    }
+
+    /// Load the events a previous session saved for a subscriber
+    fn load(&self, subscriber_id: &str) -> Result<Vec<BusEvent>, String> {
+        let Some(path) = self.path(subscriber_id) else {
+            return Ok(Vec::new());
+        };
+        match std::fs::read(&path) {
+            Ok(content) => serde_json::from_slice(&content)
+                .map_err(|e| format!("Failed to parse saved events: {}", e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(format!("Failed to read saved events: {}", e)),
+        }
+    }
+
+    fn path(&self, subscriber_id: &str) -> Option<PathBuf> {
+        self.dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.json", subscriber_id)))
+    }
+}
+
+/// Write a file through a temporary file, so readers never see it half written
+fn write_atomic(path: &Path, content: &[u8]) -> Result<(), String> {
+    let temp = path.with_extension("tmp");
+    std::fs::write(&temp, content).map_err(|e| format!("Failed to save events: {}", e))?;
+    std::fs::rename(&temp, path).map_err(|e| format!("Failed to save events: {}", e))
+}
+
+/// Receiving end of a subscriber
+///
+/// Dropping it leaves the subscriber away: its queued events are dropped,
+/// and events of `AtLeastOnce` topics are kept until it subscribes again.
+pub struct Subscription {
+    bus: EventBus,
+    subscriber_id: String,
+    subscriber: Arc<Subscriber>,
+}
+
+impl Subscription {
+    /// Get the ID of the subscriber
+    pub fn subscriber_id(&self) -> &str {
+        &self.subscriber_id
+    }
+
+    /// Take the next event without waiting
+    pub fn try_recv(&self) -> Option<BusEvent> {
+        self.recv_timeout(Duration::ZERO)
+    }
+
+    /// Wait for the next event
+    ///
+    /// Events of `AtLeastOnce` topics come first, oldest first, including
+    /// those delivered before and not acked within the ack timeout. Ack them
+    /// once handled, see `ack`.
+    ///
+    /// # Returns
+    /// The event, or None if none arrived within `timeout`
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<BusEvent> {
+        let deadline = Instant::now() + timeout;
+        let mut inbox = self.subscriber.inbox();
+
+        loop {
+            if let Some(event) = self.next(&mut inbox) {
+                inbox.delivered += 1;
+                self.bus
+                    .record(&event.topic, |metrics| metrics.delivered += 1);
+                return Some(event);
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return None;
+            }
+            // Wake up in time for the next redelivery
+            let mut wait = deadline - now;
+            if let Some(due) = inbox
+                .unacked
+                .values()
+                .filter_map(|unacked| unacked.delivered_at)
+                .map(|delivered_at| {
+                    (delivered_at + self.bus.ack_timeout).saturating_duration_since(now)
+                })
+                .min()
+            {
+                wait = wait.min(due.max(Duration::from_millis(1)));
+            }
+            inbox = self
+                .subscriber
+                .ready
+                .wait_timeout(inbox, wait)
+                .unwrap_or_else(|p| p.into_inner())
+                .0;
+        }
+    }
+
+    /// Mark an event of an `AtLeastOnce` topic as handled
+    ///
+    /// # Returns
+    /// Whether the event was waiting to be acked, or Err if the remaining
+    /// events could not be saved
+    pub fn ack(&self, event_id: u64) -> Result<bool, String> {
+        let mut inbox = self.subscriber.inbox();
+        let Some(acked) = inbox.unacked.remove(&event_id) else {
+            return Ok(false);
+        };
+        self.bus
+            .record(&acked.event.topic, |metrics| metrics.unacked -= 1);
+        self.subscriber.room.notify_all();
+
+        self.bus
+            .save_inbox(&self.subscriber_id, &inbox)
+            .map(|()| true)
+    }
+
+    /// Take the next event to deliver
+    fn next(&self, inbox: &mut Inbox) -> Option<BusEvent> {
+        let now = Instant::now();
+        let ack_timeout = self.bus.ack_timeout;
+        let unacked = inbox.unacked.values_mut().find(|unacked| {
+            unacked
+                .delivered_at
+                .is_none_or(|delivered_at| now.duration_since(delivered_at) >= ack_timeout)
+        });
+        if let Some(unacked) = unacked {
+            if unacked.delivered_at.is_some() {
+                self.bus
+                    .record(&unacked.event.topic, |metrics| metrics.redelivered += 1);
+            }
+            unacked.delivered_at = Some(now);
+            return Some(unacked.event.clone());
+        }
+
+        let event = inbox.queue.pop_front()?;
+        self.subscriber.room.notify_all();
+        Some(event)
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.subscriber.attached.store(false, Ordering::SeqCst);
+        let mut inbox = self.subscriber.inbox();
+        inbox.queue.clear();
+        // Delivered but unacked events go out again on the next subscription
+        for unacked in inbox.unacked.values_mut() {
+            unacked.delivered_at = None;
+        }
+        drop(inbox);
+        self.subscriber.room.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_overflow_policies() {
+        let bus = EventBus::new();
+        bus.configure_topic("clipboard", TopicConfig::default().with_capacity(2));
+        bus.configure_topic(
+            "query",
+            TopicConfig::default()
+                .with_capacity(1)
+                .with_overflow(OverflowPolicy::Block)
+                .with_block_timeout(Duration::from_millis(10)),
+        );
+        let slow = bus.subscribe("slow", &["clipboard", "query"]).unwrap();
+        assert!(bus.subscribe("slow", &["clipboard"]).is_err());
+
+        for n in 0..3 {
+            bus.publish("clipboard", json!(n)).unwrap();
+        }
+        assert_eq!(slow.try_recv().unwrap().payload, json!(1));
+        assert_eq!(slow.try_recv().unwrap().payload, json!(2));
+        assert_eq!(bus.metrics("clipboard").dropped, 1);
+
+        // The publisher waits for room, and gives up on the slow subscriber
+        bus.publish("query", json!("a")).unwrap();
+        bus.publish("query", json!("b")).unwrap();
+        let query = bus.metrics("query");
+        assert_eq!((query.published, query.blocked, query.dropped), (2, 1, 1));
+
+        // ... but not when the subscriber catches up in time
+        let bus_clone = bus.clone();
+        let publisher = std::thread::spawn(move || {
+            bus_clone.configure_topic(
+                "query",
+                TopicConfig::default()
+                    .with_capacity(1)
+                    .with_overflow(OverflowPolicy::Block)
+                    .with_block_timeout(Duration::from_secs(5)),
+            );
+            bus_clone.publish("query", json!("c")).unwrap();
+        });
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(
+            slow.recv_timeout(Duration::from_secs(1)).unwrap().payload,
+            json!("a")
+        );
+        publisher.join().unwrap();
+        assert_eq!(slow.try_recv().unwrap().payload, json!("c"));
+        assert_eq!(
+            bus.subscriber_metrics("slow"),
+            Some(SubscriberMetrics {
+                queued: 0,
+                unacked: 0,
+                delivered: 4,
+                dropped: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn test_lifecycle_events_are_delivered_at_least_once() {
+        let temp_dir = std::env::temp_dir().join("volt_test_event_bus");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let bus =
+            EventBus::persistent(temp_dir.clone()).with_ack_timeout(Duration::from_millis(20));
+        let registry = PluginRegistry::new();
+        bus.attach(&registry);
+
+        let subscription = bus.subscribe("todo", &[topics::PLUGIN_DISABLED]).unwrap();
+        registry.set_plugin_enabled("todo", false);
+        let event = subscription.try_recv().unwrap();
+        assert_eq!(event.payload["pluginId"], "todo");
+
+        // Not acked in time: delivered again
+        assert!(subscription.try_recv().is_none());
+        let again = subscription.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(again.id, event.id);
+        assert_eq!(bus.metrics(topics::PLUGIN_DISABLED).redelivered, 1);
+        assert!(subscription.ack(event.id).unwrap());
+        assert!(!subscription.ack(event.id).unwrap());
+
+        // Published while away, kept across sessions until acked
+        drop(subscription);
+        registry.set_plugin_enabled("todo", true);
+        registry.set_plugin_enabled("todo", false);
+        let restarted = EventBus::persistent(temp_dir.clone());
+        let subscription = restarted
+            .subscribe("todo", &[topics::PLUGIN_DISABLED])
+            .unwrap();
+        let kept = subscription.try_recv().unwrap();
+        assert!(kept.id > event.id);
+        assert!(subscription.ack(kept.id).unwrap());
+        assert!(!temp_dir.join("todo.json").exists());
+
+        assert!(
+            bus.subscribe("../todo", &[topics::PLUGIN_DISABLED])
+                .is_err()
+        );
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn test_unacked_events_are_bounded() {
+        let temp_dir = std::env::temp_dir().join("volt_test_event_bus_unacked");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let bus = EventBus::persistent(temp_dir.clone());
+        bus.configure_topic("sync", TopicConfig::critical().with_capacity(2));
+        bus.configure_topic(
+            "jobs",
+            TopicConfig::critical()
+                .with_capacity(1)
+                .with_overflow(OverflowPolicy::Block)
+                .with_block_timeout(Duration::from_millis(10)),
+        );
+
+        // A subscriber that never acks
+        let stuck = bus.subscribe("stuck", &["sync", "jobs"]).unwrap();
+        bus.publish("sync", json!(1)).unwrap();
+        bus.publish("sync", json!(2)).unwrap();
+        assert!(bus.publish("sync", json!(3)).is_err());
+        assert_eq!(bus.metrics("sync").dropped, 1);
+        assert_eq!(bus.subscriber_metrics("stuck").unwrap().unacked, 2);
+        let saved: Vec<BusEvent> =
+            serde_json::from_slice(&std::fs::read(temp_dir.join("stuck.json")).unwrap()).unwrap();
+        assert_eq!(saved.len(), 2);
+
+        // The publisher waits for an ack, and gives up when none comes
+        let first = stuck.try_recv().unwrap();
+        assert!(bus.publish("jobs", json!("a")).is_err());
+        let jobs = bus.metrics("jobs");
+        assert_eq!((jobs.blocked, jobs.dropped), (1, 1));
+
+        // ... but not when the subscriber acks in time
+        let bus_clone = bus.clone();
+        let publisher = std::thread::spawn(move || {
+            bus_clone.configure_topic(
+                "jobs",
+                TopicConfig::critical()
+                    .with_capacity(2)
+                    .with_overflow(OverflowPolicy::Block)
+                    .with_block_timeout(Duration::from_secs(5)),
+            );
+            bus_clone.publish("jobs", json!("b")).unwrap();
+        });
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(stuck.ack(first.id).unwrap());
+        publisher.join().unwrap();
+        assert_eq!(bus.subscriber_metrics("stuck").unwrap().unacked, 2);
+
+        drop(stuck);
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
@@ -0,0 +1,219 @@
+/// Export of result lists
+///
+/// Users of search-like plugins sometimes want to keep what they found: the
+/// tickets matching a query, a list of files, a set of definitions. The
+/// merged results, or the ones the user picked, are rendered as JSON, CSV
+/// or Markdown with `MergedResults::export`, and
+/// `VoltPluginAPI::export_results` writes them to a file or hands them to
+/// the platform's share sheet.
+///
+/// Each result becomes one record with its title, subtitle, plugin, score
+/// and target: the path, URL, command line or text its intent acts on.
+use crate::intents::SendPayload;
+use crate::result::{PluginResult, ResultIntent};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Directory of the cache holding exports handed to the share sheet
+pub const EXPORTS_DIR: &str = "exports";
+
+/// File format of an export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportFormat {
+    /// Array of records, for other tools
+    Json,
+    /// One row per result, for spreadsheets
+    Csv,
+    /// Bulleted list with links, for notes
+    Markdown,
+}
+
+impl ExportFormat {
+    /// Get the file extension of the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Markdown => "md",
+        }
+    }
+}
+
+/// Where an export goes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ExportDestination {
+    /// A file chosen by the user
+    File {
+        /// Path of the file, replaced if it exists
+        path: PathBuf,
+    },
+    /// The platform's share sheet, see `HostFeature::ShareSheet`
+    ShareSheet,
+}
+
+/// A result as exported
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedResult {
+    /// Title of the result
+    pub title: String,
+    /// Subtitle of the result
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subtitle: Option<String>,
+    /// Plugin that produced the result
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plugin_id: Option<String>,
+    /// Relevance score
+    pub score: u32,
+    /// Path, URL, command line or text the result's intent acts on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+}
+
+impl ExportedResult {
+    /// Convert a result for export
+    pub fn from_result(result: &PluginResult) -> Self {
+        Self {
+            title: result.title.to_string(),
+            subtitle: result.subtitle.as_deref().map(str::to_string),
+            plugin_id: result.plugin_id.as_deref().map(str::to_string),
+            score: result.score,
+            target: SendPayload::from_result(result).map(|payload| payload.text),
+        }
+    }
+}
+
+/// Render results in an export format
+///
+/// # Arguments
+/// * `results` - Results to export, in order
+/// * `format` - Format of the export
+pub fn render(results: &[PluginResult], format: ExportFormat) -> Result<String, String> {
+    let records: Vec<ExportedResult> = results.iter().map(ExportedResult::from_result).collect();
+
+    match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&records)
+            .map_err(|e| format!("Failed to serialize results: {}", e)),
+        ExportFormat::Csv => {
+            let mut csv = String::from("title,subtitle,plugin,score,target\r\n");
+            for record in &records {
+                let fields = [
+                    csv_field(&record.title),
+                    csv_field(record.subtitle.as_deref().unwrap_or_default()),
+                    csv_field(record.plugin_id.as_deref().unwrap_or_default()),
+                    record.score.to_string(),
+                    csv_field(record.target.as_deref().unwrap_or_default()),
+                ];
+                csv.push_str(&fields.join(","));
+                csv.push_str("\r\n");
+            }
+            Ok(csv)
+        }
+        ExportFormat::Markdown => {
+            let mut markdown = String::new();
+            for (result, record) in results.iter().zip(&records) {
+                let title = markdown_text(&record.title);
+                match &result.intent {
+                    Some(ResultIntent::OpenUrl { url }) => {
+                        markdown.push_str(&format!("- [{}](<{}>)", title, url.replace('>', "%3E")))
+                    }
+                    _ => markdown.push_str(&format!("- {}", title)),
+                }
+                if let Some(subtitle) = &record.subtitle {
+                    markdown.push_str(&format!(" — {}", markdown_text(subtitle)));
+                }
+                if let Some(target) = record
+                    .target
+                    .as_ref()
+                    .filter(|_| !matches!(result.intent, Some(ResultIntent::OpenUrl { .. })))
+                {
+                    markdown.push_str(&format!(
+                        " `{}`",
+                        target.replace('`', "'").replace('\n', " ")
+                    ));
+                }
+                markdown.push('\n');
+            }
+            Ok(markdown)
+        }
+    }
+}
+
+/// Quote a CSV field if needed
+///
+/// Fields a spreadsheet would evaluate as a formula are prefixed with a
+/// quote, so exported results can't run code when opened.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// Escape text so Markdown shows it literally, on a single line
+fn markdown_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' | '\r' => escaped.push(' '),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results() -> Vec<PluginResult> {
+        let mut ticket =
+            PluginResult::new("VOLT-1", "Crash on *startup*").with_intent(ResultIntent::OpenUrl {
+                url: "https://tracker.example.com/VOLT-1".to_string(),
+            });
+        ticket.subtitle = Some("Open, assigned to Ada".into());
+        ticket.plugin_id = Some("jira".into());
+        ticket.score = 90;
+        let formula = PluginResult::new("sum", "=SUM(A1:A9)").with_intent(ResultIntent::CopyText {
+            text: "45, \"exact\"".to_string(),
+        });
+        vec![ticket, formula]
+    }
+
+    #[test]
+    fn test_export_formats() {
+        let results = results();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&results, ExportFormat::Json).unwrap()).unwrap();
+        assert_eq!(json[0]["pluginId"], "jira");
+        assert_eq!(json[0]["target"], "https://tracker.example.com/VOLT-1");
+        assert_eq!(json[1].get("subtitle"), None);
+
+        assert_eq!(
+            render(&results, ExportFormat::Csv).unwrap(),
+            "title,subtitle,plugin,score,target\r\n\
+             Crash on *startup*,\"Open, assigned to Ada\",jira,90,https://tracker.example.com/VOLT-1\r\n\
+             '=SUM(A1:A9),,,0,\"45, \"\"exact\"\"\"\r\n"
+        );
+
+        assert_eq!(
+            render(&results, ExportFormat::Markdown).unwrap(),
+            "- [Crash on \\*startup\\*](<https://tracker.example.com/VOLT-1>) — Open, assigned to Ada\n\
+             - =SUM(A1:A9) `45, \"exact\"`\n"
+        );
+    }
+}
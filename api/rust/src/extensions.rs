@@ -0,0 +1,309 @@
+/// Optional plugin capabilities
+///
+/// The core `Plugin` trait only grows through hooks with default
+/// implementations. Larger capabilities live in their own traits; a plugin
+/// opts in by implementing the trait and returning `Some(self)` from the
+/// matching `Plugin::as_*` accessor. New capabilities are added as new
+/// traits and accessors, so existing `impl Plugin` blocks keep compiling.
+///
+/// ```ignore
+/// impl Suggester for MyPlugin { ... }
+///
+/// impl Plugin for MyPlugin {
+///     fn as_suggester(&self) -> Option<&dyn Suggester> {
+///         Some(self)
+///     }
+///     ...
+/// }
+/// ```
+use crate::annotations::Annotation;
+use crate::intents::SendPayload;
+use crate::outcome::Toast;
+use crate::plugin::{Plugin, QueryContext};
+use crate::resources::{ResolvedResource, VirtualPath};
+use crate::result::{IntentKind, PluginResult};
+use crate::settings::SettingsPage;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// Completes partially typed queries
+pub trait Suggester: Send + Sync {
+    /// Suggest completions for the query, best first
+    ///
+    /// Called on every keystroke, so it must be cheap.
+    fn suggest(&self, context: &QueryContext) -> Vec<String>;
+}
+
+/// Renders a rich preview of a result in the side panel
+#[async_trait]
+pub trait Previewer: Send + Sync {
+    /// Build the preview of one of the plugin's results
+    async fn preview(&self, result: &PluginResult) -> Result<Preview, String>;
+}
+
+/// Handles `volt://` style deep links and custom URI schemes
+#[async_trait]
+pub trait UriHandler: Send + Sync {
+    /// URI schemes handled by the plugin, without "://" (e.g., "vscode")
+    fn schemes(&self) -> Vec<String>;
+
+    /// Handle a URI with one of the plugin's schemes
+    async fn handle_uri(&self, uri: &str) -> Result<(), String>;
+}
+
+/// Gives access to the plugin's virtual resources
+///
+/// Results of the plugin may then point at `voltres://<plugin id>/...`
+/// URIs instead of real files, see `crate::resources`.
+#[async_trait]
+pub trait ResourceResolver: Send + Sync {
+    /// Get the content of one of the plugin's resources
+    ///
+    /// Only called with paths whose `plugin_id` is the plugin's.
+    async fn resolve(&self, path: &VirtualPath) -> Result<ResolvedResource, String>;
+}
+
+/// Receives results other plugins send to it ("Send to Slack")
+///
+/// The aggregator offers the plugin's targets as secondary actions on
+/// every result whose intent kind they accept.
+#[async_trait]
+pub trait SendHandler: Send + Sync {
+    /// Targets offered by the plugin
+    fn send_targets(&self) -> Vec<SendTarget>;
+
+    /// Deliver a result sent to one of the plugin's targets
+    ///
+    /// # Arguments
+    /// * `target_id` - ID of the chosen target
+    /// * `payload` - The sent result, converted by the intents layer
+    async fn send(&self, target_id: &str, payload: &SendPayload) -> Result<(), String>;
+}
+
+/// Decorates merged results, its own and other plugins', with tags and
+/// accessories
+///
+/// Runs after every query under a strict time budget
+/// (`annotations::DEFAULT_ANNOTATION_BUDGET` by default); annotations
+/// arriving late are dropped.
+pub trait Annotator: Send + Sync {
+    /// Annotate the merged results, in display order
+    ///
+    /// Return an `Annotation` per result to decorate, indexed into `results`.
+    fn annotate(&self, results: &[PluginResult]) -> Vec<Annotation>;
+}
+
+/// Contributes settings pages richer than a flat form
+///
+/// Each page is saved as the plugin configuration named after its ID, and
+/// reaches the plugin through `Plugin::on_config_changed` like any other.
+#[async_trait]
+pub trait SettingsProvider: Send + Sync {
+    /// Pages shown in the plugin's settings, in display order
+    fn settings_pages(&self) -> Vec<SettingsPage>;
+
+    /// Handle a click on one of the pages' buttons
+    ///
+    /// # Arguments
+    /// * `page_id` - Page holding the button
+    /// * `action` - Action identifier of the button
+    /// * `values` - Unsaved values currently shown on the page
+    ///
+    /// # Returns
+    /// A message to show, e.g. "Connection successful"
+    async fn settings_action(
+        &self,
+        page_id: &str,
+        action: &str,
+        values: &serde_json::Value,
+    ) -> Result<Option<Toast>, String>;
+}
+
+/// A destination results can be sent to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SendTarget {
+    /// Identifier, unique within the plugin
+    pub id: String,
+    /// Action label, e.g. "Append to daily note"
+    pub label: String,
+    /// Icon path, URL, or emoji
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// Intent kinds the target can receive
+    pub accepts: Vec<IntentKind>,
+}
+
+impl SendTarget {
+    /// Create a target accepting nothing yet
+    pub fn new(id: impl Into<String>, label: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            label: label.into(),
+            icon: None,
+            accepts: Vec::new(),
+        }
+    }
+
+    /// Accept results with the given intent kind
+    pub fn accepting(mut self, kind: IntentKind) -> Self {
+        self.accepts.push(kind);
+        self
+    }
+
+    /// Check if the target can receive an intent kind
+    pub fn accepts(&self, kind: IntentKind) -> bool {
+        self.accepts.contains(&kind)
+    }
+}
+
+/// Content of a result preview
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Preview {
+    /// Plain text, shown in a monospace font
+    Text {
+        /// The text
+        text: String,
+    },
+    /// Markdown rendered by the host
+    Markdown {
+        /// The markdown source
+        markdown: String,
+    },
+    /// Image file on disk
+    Image {
+        /// Path to the image, or a `voltres://` URI
+        path: String,
+    },
+}
+
+mod private {
+    pub trait Sealed {}
+
+    impl<T: super::Plugin + ?Sized> Sealed for T {}
+}
+
+/// Queries over a plugin's optional capabilities
+///
+/// Implemented for every plugin and sealed, so methods can be added
+/// without breaking anyone.
+pub trait PluginExt: private::Sealed {
+    /// Names of the optional capabilities the plugin supports
+    fn extensions(&self) -> Vec<&'static str>;
+
+    /// Check if the plugin handles a URI scheme
+    fn handles_scheme(&self, scheme: &str) -> bool;
+}
+
+impl<T: Plugin + ?Sized> PluginExt for T {
+    fn extensions(&self) -> Vec<&'static str> {
+        let mut extensions = Vec::new();
+        if self.as_suggester().is_some() {
+            extensions.push("suggester");
+        }
+        if self.as_previewer().is_some() {
+            extensions.push("previewer");
+        }
+        if self.as_uri_handler().is_some() {
+            extensions.push("uriHandler");
+        }
+        if self.as_send_handler().is_some() {
+            extensions.push("sendHandler");
+        }
+        if self.as_annotator().is_some() {
+            extensions.push("annotator");
+        }
+        if self.as_settings_provider().is_some() {
+            extensions.push("settingsProvider");
+        }
+        if self.as_resource_resolver().is_some() {
+            extensions.push("resourceResolver");
+        }
+        extensions
+    }
+
+    fn handles_scheme(&self, scheme: &str) -> bool {
+        self.as_uri_handler().is_some_and(|handler| {
+            handler
+                .schemes()
+                .iter()
+                .any(|handled| handled.eq_ignore_ascii_case(scheme))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Notes;
+
+    impl Suggester for Notes {
+        fn suggest(&self, context: &QueryContext) -> Vec<String> {
+            vec![format!("{} meeting", context.query)]
+        }
+    }
+
+    #[async_trait]
+    impl UriHandler for Notes {
+        fn schemes(&self) -> Vec<String> {
+            vec!["notes".to_string()]
+        }
+
+        async fn handle_uri(&self, _uri: &str) -> Result<(), String> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl Plugin for Notes {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn id(&self) -> &str {
+            "notes"
+        }
+
+        fn name(&self) -> &str {
+            "Notes"
+        }
+
+        fn description(&self) -> &str {
+            "Searches notes"
+        }
+
+        fn as_suggester(&self) -> Option<&dyn Suggester> {
+            Some(self)
+        }
+
+        fn as_uri_handler(&self) -> Option<&dyn UriHandler> {
+            Some(self)
+        }
+    }
+
+    #[test]
+    fn test_extension_accessors() {
+        let plugin: Box<dyn Plugin> = Box::new(Notes);
+
+        let suggester = plugin.as_suggester().unwrap();
+        assert_eq!(suggester.suggest(&QueryContext::new("team")), vec!["team meeting"]);
+        assert!(plugin.as_previewer().is_none());
+
+        assert_eq!(plugin.extensions(), vec!["suggester", "uriHandler"]);
+        assert!(plugin.handles_scheme("NOTES"));
+        assert!(!plugin.handles_scheme("https"));
+    }
+
+    #[test]
+    fn test_preview_wire_format() {
+        let preview = Preview::Markdown {
+            markdown: "# Title".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(&preview).unwrap(),
+            serde_json::json!({"kind": "markdown", "markdown": "# Title"})
+        );
+    }
+}
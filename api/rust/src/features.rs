@@ -0,0 +1,135 @@
+/// Runtime feature detection
+///
+/// Optional host subsystems may be missing depending on the platform or how
+/// Volt was built. Plugins query the `FeatureSet` to adapt instead of failing.
+use serde::{Serialize, Serializer};
+
+/// Optional host subsystems a plugin can rely on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HostFeature {
+    /// Reading and writing the system clipboard
+    Clipboard,
+    /// Desktop notifications
+    Notifications,
+    /// Enumerating and controlling application windows
+    WindowManagement,
+    /// Volt's central file and application index
+    Indexer,
+    /// Secure credential storage
+    Secrets,
+    /// Typing text into the focused application
+    InputSynthesis,
+    /// Reading the text selected in the focused application
+    Selection,
+    /// Sharing files through the platform's share sheet
+    ShareSheet,
+}
+
+impl HostFeature {
+    /// All features, in declaration order
+    pub const ALL: [HostFeature; 8] = [
+        HostFeature::Clipboard,
+        HostFeature::Notifications,
+        HostFeature::WindowManagement,
+        HostFeature::Indexer,
+        HostFeature::Secrets,
+        HostFeature::InputSynthesis,
+        HostFeature::Selection,
+        HostFeature::ShareSheet,
+    ];
+
+    /// Get the identifier used in serialized feature sets
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HostFeature::Clipboard => "clipboard",
+            HostFeature::Notifications => "notifications",
+            HostFeature::WindowManagement => "windowManagement",
+            HostFeature::Indexer => "indexer",
+            HostFeature::Secrets => "secrets",
+            HostFeature::InputSynthesis => "inputSynthesis",
+            HostFeature::Selection => "selection",
+            HostFeature::ShareSheet => "shareSheet",
+        }
+    }
+
+    fn bit(&self) -> u32 {
+        1 << (*self as u32)
+    }
+}
+
+/// Set of host features available at runtime
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeatureSet {
+    bits: u32,
+}
+
+impl FeatureSet {
+    /// Create an empty feature set
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// Check if a feature is available
+    pub fn contains(&self, feature: HostFeature) -> bool {
+        self.bits & feature.bit() != 0
+    }
+
+    /// Mark a feature as available
+    pub fn insert(&mut self, feature: HostFeature) {
+        self.bits |= feature.bit();
+    }
+
+    /// Mark a feature as unavailable
+    pub fn remove(&mut self, feature: HostFeature) {
+        self.bits &= !feature.bit();
+    }
+
+    /// Builder-style variant of [`insert`](Self::insert)
+    pub fn with(mut self, feature: HostFeature) -> Self {
+        self.insert(feature);
+        self
+    }
+
+    /// Iterate over the available features
+    pub fn iter(&self) -> impl Iterator<Item = HostFeature> + '_ {
+        HostFeature::ALL
+            .into_iter()
+            .filter(move |feature| self.contains(*feature))
+    }
+
+    /// Fail with a descriptive error if a feature is unavailable
+    pub fn require(&self, feature: HostFeature) -> Result<(), String> {
+        if self.contains(feature) {
+            Ok(())
+        } else {
+            Err(format!("Host feature '{}' is not available", feature.as_str()))
+        }
+    }
+}
+
+impl Serialize for FeatureSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter().map(|feature| feature.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feature_set() {
+        let mut features = FeatureSet::empty()
+            .with(HostFeature::Clipboard)
+            .with(HostFeature::Secrets);
+
+        assert!(features.contains(HostFeature::Clipboard));
+        assert!(!features.contains(HostFeature::Indexer));
+        assert!(features.require(HostFeature::Secrets).is_ok());
+        assert!(features.require(HostFeature::Indexer).is_err());
+
+        features.remove(HostFeature::Clipboard);
+        assert_eq!(features.iter().collect::<Vec<_>>(), vec![HostFeature::Secrets]);
+        assert_eq!(serde_json::to_value(features).unwrap(), serde_json::json!(["secrets"]));
+    }
+}
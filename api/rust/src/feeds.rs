@@ -0,0 +1,458 @@
+/// Remote data feeds refreshed by the host
+///
+/// Built-in plugins ship data that goes stale (currency rates, emoji
+/// versions). Rather than downloading it themselves, they declare feeds
+/// through `Plugin::data_feeds`; the host's `FeedScheduler` refreshes them
+/// on a schedule with the download manager and hands the new file to
+/// `Plugin::on_feed_updated`.
+///
+/// A feed URL points to a small JSON index of the published versions:
+///
+/// ```json
+/// {
+///   "latest": "15.1",
+///   "versions": {
+///     "15.0": { "url": "https://data.volt.dev/emoji/15.0.json", "sha256": "..." },
+///     "15.1": { "url": "https://data.volt.dev/emoji/15.1.json", "sha256": "..." }
+///   }
+/// }
+/// ```
+///
+/// On battery, feeds are refreshed less often, and not at all on low
+/// battery, as set by the `PowerPolicy` (see `crate::power`).
+///
+/// A feed pinned to a version never moves past it. While offline, the last
+/// downloaded version stays in use, and before the first download the
+/// plugin's bundled asset is used instead.
+use crate::api::VoltPluginAPI;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Default time between two refreshes of a feed
+pub const DEFAULT_FEED_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Time before a failed refresh is attempted again
+pub const FEED_RETRY_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// A remote data feed declared by a plugin
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataFeed {
+    /// Identifier, unique within the plugin
+    pub id: String,
+    /// URL of the feed's version index
+    pub index_url: String,
+    /// Time between refreshes
+    pub interval: Duration,
+    /// Version to stay on instead of following "latest"
+    pub pinned_version: Option<String>,
+    /// Asset used until the feed has been downloaded (see `VoltPluginAPI::asset`)
+    pub fallback_asset: Option<String>,
+}
+
+impl DataFeed {
+    /// Declare a feed refreshed daily and following the latest version
+    pub fn new(id: impl Into<String>, index_url: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            index_url: index_url.into(),
+            interval: DEFAULT_FEED_INTERVAL,
+            pinned_version: None,
+            fallback_asset: None,
+        }
+    }
+
+    /// Refresh the feed at a different interval
+    pub fn every(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Stay on a specific version
+    pub fn pinned(mut self, version: impl Into<String>) -> Self {
+        self.pinned_version = Some(version.into());
+        self
+    }
+
+    /// Use a bundled asset until the feed has been downloaded
+    pub fn with_fallback(mut self, asset: impl Into<String>) -> Self {
+        self.fallback_asset = Some(asset.into());
+        self
+    }
+
+    /// Cache file holding the feed's version index
+    #[cfg(feature = "download")]
+    fn index_file(&self) -> String {
+        format!("feed-{}.index.json", self.id)
+    }
+
+    /// Cache file holding the refresh state
+    fn state_file(&self) -> String {
+        format!("feed-{}.state.json", self.id)
+    }
+
+    /// Cache file holding one version of the data
+    fn data_file(&self, version: &str) -> String {
+        format!("feed-{}-{}.data", self.id, version)
+    }
+}
+
+/// Published versions of a feed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedIndex {
+    /// Newest version
+    pub latest: String,
+    /// Every published version, keyed by version
+    pub versions: HashMap<String, FeedVersion>,
+}
+
+/// One published version of a feed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedVersion {
+    /// URL of the data file
+    pub url: String,
+    /// Expected SHA-256 of the data file, hex encoded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+impl FeedIndex {
+    /// Parse an index from JSON
+    pub fn from_json(content: &str) -> Result<Self, String> {
+        serde_json::from_str(content).map_err(|e| format!("Failed to parse feed index: {}", e))
+    }
+
+    /// Pick the version a feed should use
+    ///
+    /// # Returns
+    /// The pinned version if the feed is pinned, otherwise the latest one
+    pub fn select(&self, feed: &DataFeed) -> Result<(&str, &FeedVersion), String> {
+        let version = feed.pinned_version.as_deref().unwrap_or(&self.latest);
+
+        self.versions
+            .get_key_value(version)
+            .map(|(version, data)| (version.as_str(), data))
+            .ok_or_else(|| format!("Feed '{}' has no version '{}'", feed.id, version))
+    }
+}
+
+/// Refresh state of a feed, stored in the plugin's cache
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedState {
+    /// Version of the downloaded data
+    pub version: String,
+    /// Last successful refresh, in milliseconds since the Unix epoch
+    pub refreshed_at_ms: u64,
+}
+
+/// Load the refresh state of a feed
+///
+/// # Returns
+/// None if the feed was never downloaded
+pub fn feed_state(api: &VoltPluginAPI, plugin_id: &str, feed: &DataFeed) -> Option<FeedState> {
+    let content = api.read_cache(plugin_id, &feed.state_file()).ok()?;
+    serde_json::from_slice(&content).ok()
+}
+
+/// Get the file a plugin should read a feed's data from
+///
+/// # Arguments
+/// * `api` - API of the host
+/// * `plugin_id` - Plugin declaring the feed
+/// * `feed` - The feed
+///
+/// # Returns
+/// The downloaded data, or the bundled fallback if nothing was downloaded yet
+pub fn feed_path(api: &VoltPluginAPI, plugin_id: &str, feed: &DataFeed) -> Result<PathBuf, String> {
+    if let Some(state) = feed_state(api, plugin_id, feed) {
+        let path = api.get_plugin_cache_dir(plugin_id)?.join(feed.data_file(&state.version));
+        if path.is_file() {
+            return Ok(path);
+        }
+    }
+
+    match &feed.fallback_asset {
+        Some(asset) => api.asset(plugin_id, asset),
+        None => Err(format!("Feed '{}' has not been downloaded yet", feed.id)),
+    }
+}
+
+/// Outcome of refreshing one feed
+#[cfg(feature = "download")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedRefresh {
+    /// Plugin declaring the feed
+    pub plugin_id: String,
+    /// The refreshed feed
+    pub feed_id: String,
+    /// The newly installed version, None if the data was already current
+    pub outcome: Result<Option<String>, String>,
+}
+
+/// Refreshes the data feeds declared by registered plugins
+#[cfg(feature = "download")]
+#[derive(Clone)]
+pub struct FeedScheduler {
+    api: VoltPluginAPI,
+    registry: crate::registry::PluginRegistry,
+    /// Time of the last failed refresh of each (plugin, feed)
+    failures: std::sync::Arc<std::sync::Mutex<HashMap<(String, String), std::time::Instant>>>,
+}
+
+#[cfg(feature = "download")]
+impl FeedScheduler {
+    /// Create a scheduler for the plugins of a registry
+    pub fn new(api: VoltPluginAPI, registry: crate::registry::PluginRegistry) -> Self {
+        Self {
+            api,
+            registry,
+            failures: Default::default(),
+        }
+    }
+
+    /// Refresh the feeds whose interval has elapsed
+    ///
+    /// Feeds that failed to refresh are retried after `FEED_RETRY_INTERVAL`.
+    /// Intervals follow the power policy of the API.
+    pub async fn refresh_due(&self) -> Vec<FeedRefresh> {
+        let now = now_ms();
+        let mut refreshes = Vec::new();
+        let policy = self.api.power_policy();
+        let mode = self.api.power_mode();
+
+        for (plugin_id, feed) in self.registry.data_feeds() {
+            let key = (plugin_id.clone(), feed.id.clone());
+            let retry_pending = self
+                .failures
+                .lock()
+                .ok()
+                .and_then(|failures| failures.get(&key).copied())
+                .is_some_and(|failed_at| failed_at.elapsed() < FEED_RETRY_INTERVAL);
+            let Some(interval) = policy.refresh_interval(feed.interval, mode) else {
+                continue;
+            };
+            let due = feed_state(&self.api, &plugin_id, &feed).is_none_or(|state| {
+                now.saturating_sub(state.refreshed_at_ms) >= interval.as_millis() as u64
+            });
+            if retry_pending || !due {
+                continue;
+            }
+
+            let outcome = self.refresh(&plugin_id, &feed).await;
+            if let Ok(mut failures) = self.failures.lock() {
+                if outcome.is_err() {
+                    failures.insert(key, std::time::Instant::now());
+                } else {
+                    failures.remove(&key);
+                }
+            }
+
+            refreshes.push(FeedRefresh {
+                plugin_id,
+                feed_id: feed.id,
+                outcome,
+            });
+        }
+
+        refreshes
+    }
+
+    /// Refresh one feed now
+    ///
+    /// On failure the previously downloaded data is left untouched.
+    ///
+    /// # Returns
+    /// The newly installed version, None if the data was already current
+    pub async fn refresh(&self, plugin_id: &str, feed: &DataFeed) -> Result<Option<String>, String> {
+        use crate::download::DownloadOpts;
+
+        let index = self
+            .api
+            .download(plugin_id, &feed.index_url, DownloadOpts::new().file_name(&feed.index_file()))
+            .await?;
+        let index = std::fs::read_to_string(&index.path)
+            .map_err(|e| format!("Failed to read feed index: {}", e))
+            .and_then(|content| FeedIndex::from_json(&content))?;
+        let (version, published) = index.select(feed)?;
+
+        let cache_dir = self.api.get_plugin_cache_dir(plugin_id)?;
+        let previous = feed_state(&self.api, plugin_id, feed);
+        let current = previous.as_ref().is_some_and(|state| state.version == version)
+            && cache_dir.join(feed.data_file(version)).is_file();
+
+        let state = FeedState {
+            version: version.to_string(),
+            refreshed_at_ms: now_ms(),
+        };
+
+        if current {
+            self.save_state(plugin_id, feed, &state)?;
+            return Ok(None);
+        }
+
+        let mut opts = DownloadOpts::new().file_name(&feed.data_file(version));
+        if let Some(sha256) = &published.sha256 {
+            opts = opts.sha256(sha256);
+        }
+        let downloaded = self.api.download(plugin_id, &published.url, opts).await?;
+        self.save_state(plugin_id, feed, &state)?;
+
+        if let Some(previous) = previous.filter(|previous| previous.version != version) {
+            let _ = std::fs::remove_file(cache_dir.join(feed.data_file(&previous.version)));
+        }
+
+        self.registry
+            .notify_feed_updated(plugin_id, &feed.id, &downloaded.path)?;
+        Ok(Some(state.version))
+    }
+
+    /// Refresh due feeds forever, checking every `tick`
+    ///
+    /// Spawn this on the host runtime after plugins are initialized.
+    pub async fn run(self, tick: Duration) {
+        loop {
+            for refresh in self.refresh_due().await {
+                if let Err(e) = &refresh.outcome {
+                    self.api.diagnostics().warn(
+                        "feeds",
+                        &format!("Feed '{}' of plugin '{}' not refreshed: {}", refresh.feed_id, refresh.plugin_id, e),
+                    );
+                }
+            }
+            tokio::time::sleep(tick).await;
+        }
+    }
+
+    fn save_state(&self, plugin_id: &str, feed: &DataFeed, state: &FeedState) -> Result<(), String> {
+        let content =
+            serde_json::to_vec(state).map_err(|e| format!("Failed to serialize feed state: {}", e))?;
+        self.api.write_cache(plugin_id, &feed.state_file(), &content)
+    }
+}
+
+#[cfg(feature = "download")]
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feed_versions_are_selected() {
+        let index = FeedIndex::from_json(
+            r#"{"latest": "15.1", "versions": {
+                "15.0": {"url": "https://data.volt.dev/emoji/15.0.json"},
+                "15.1": {"url": "https://data.volt.dev/emoji/15.1.json", "sha256": "ab12"}
+            }}"#,
+        )
+        .unwrap();
+
+        let feed = DataFeed::new("emoji", "https://data.volt.dev/emoji/index.json");
+        assert_eq!(index.select(&feed).unwrap().0, "15.1");
+        assert_eq!(index.select(&feed.clone().pinned("15.0")).unwrap().0, "15.0");
+        assert!(index.select(&feed.pinned("14.0")).is_err());
+    }
+
+    #[test]
+    fn test_feed_falls_back_to_bundled_asset() {
+        let temp_dir = std::env::temp_dir().join("volt_test_feeds");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let api = VoltPluginAPI::new(temp_dir.clone());
+
+        let assets_dir = api.get_plugin_package_dir("emoji").unwrap().join("assets");
+        std::fs::create_dir_all(&assets_dir).unwrap();
+        std::fs::write(assets_dir.join("emoji.json"), "[]").unwrap();
+
+        let feed = DataFeed::new("emoji", "https://data.volt.dev/emoji/index.json");
+        assert!(feed_path(&api, "emoji", &feed).is_err());
+
+        let feed = feed.with_fallback("emoji.json");
+        assert!(feed_path(&api, "emoji", &feed).unwrap().ends_with("emoji.json"));
+
+        // Once downloaded, the cached version wins
+        let state = FeedState {
+            version: "15.1".to_string(),
+            refreshed_at_ms: 1,
+        };
+        api.write_cache("emoji", &feed.state_file(), &serde_json::to_vec(&state).unwrap())
+            .unwrap();
+        api.write_cache("emoji", &feed.data_file("15.1"), b"[1]").unwrap();
+        assert!(feed_path(&api, "emoji", &feed).unwrap().ends_with("feed-emoji-15.1.data"));
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+
+    #[cfg(feature = "download")]
+    #[tokio::test]
+    async fn test_offline_refresh_keeps_fallback() {
+        use crate::plugin::Plugin;
+        use crate::registry::PluginRegistry;
+
+        struct Rates;
+
+        #[async_trait::async_trait]
+        impl Plugin for Rates {
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+
+            fn id(&self) -> &str {
+                "rates"
+            }
+
+            fn name(&self) -> &str {
+                "Rates"
+            }
+
+            fn description(&self) -> &str {
+                "Currency rates"
+            }
+
+            fn data_feeds(&self) -> Vec<DataFeed> {
+                // Nothing listens on port 9
+                vec![DataFeed::new("rates", "http://127.0.0.1:9/index.json").with_fallback("rates.json")]
+            }
+        }
+
+        let temp_dir = std::env::temp_dir().join("volt_test_feeds_offline");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let api = VoltPluginAPI::new(temp_dir.clone());
+        let assets_dir = api.get_plugin_package_dir("rates").unwrap().join("assets");
+        std::fs::create_dir_all(&assets_dir).unwrap();
+        std::fs::write(assets_dir.join("rates.json"), "{}").unwrap();
+
+        let registry = PluginRegistry::new();
+        registry.register(Box::new(Rates)).unwrap();
+        let scheduler = FeedScheduler::new(api.clone(), registry.clone());
+
+        // Nothing is refreshed on low battery
+        let low_battery = crate::power::PowerState {
+            on_battery: true,
+            battery_percent: Some(5),
+            low_power_mode: false,
+        };
+        api.set_power_state(low_battery);
+        assert!(scheduler.refresh_due().await.is_empty());
+        api.set_power_state(Default::default());
+
+        let refreshes = scheduler.refresh_due().await;
+        assert_eq!(refreshes.len(), 1);
+        assert!(refreshes[0].outcome.is_err());
+
+        // The failure is not retried right away and the bundled data stays in use
+        assert!(scheduler.refresh_due().await.is_empty());
+        let feed = &registry.data_feeds()[0].1;
+        assert!(feed_path(&api, "rates", feed).unwrap().ends_with("rates.json"));
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
@@ -0,0 +1,496 @@
+/// Full-text search for plugins, backed by tantivy
+///
+/// Plugins indexing notes, emails or documents get a ready-made search
+/// engine instead of each embedding their own: per-plugin index directories,
+/// a small schema builder, incremental commits and a query API returning
+/// scored hits with highlighted snippets. Every index writer uses the same
+/// fixed memory budget, so plugins have predictable memory profiles.
+///
+/// Text is split into terms by the tokenizer of `crate::matching`, so
+/// full-text hits rank on the same words as fuzzy matches and suggestions.
+use crate::matching::{self, Tokenizer};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tantivy::collector::TopDocs;
+use tantivy::directory::MmapDirectory;
+use tantivy::query::QueryParser;
+use tantivy::schema::{Field, STORED, STRING, Schema, TEXT, Value};
+use tantivy::snippet::SnippetGenerator;
+use tantivy::tokenizer::{TextAnalyzer, Token, TokenStream};
+use tantivy::{Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, Term};
+
+/// Memory budget of each index writer, in bytes
+pub const WRITER_MEMORY_BUDGET: usize = 15_000_000;
+
+/// Name of the field holding document IDs
+pub const ID_FIELD: &str = "id";
+
+/// Maximum length of a snippet, in characters
+const SNIPPET_MAX_CHARS: usize = 160;
+
+/// Name text fields refer to their tokenizer by
+///
+/// Tantivy's default name: indexes created before the tokenizer was
+/// configurable keep their schema, and get the configured tokenizer.
+const TOKENIZER_NAME: &str = "default";
+
+/// Fields of a full-text index
+///
+/// Every schema has an `id` field used to replace and delete documents.
+#[derive(Debug, Clone, Default)]
+pub struct FulltextSchema {
+    text_fields: Vec<String>,
+    stored_fields: Vec<String>,
+    snippet_field: Option<String>,
+    tokenizer: Option<Tokenizer>,
+}
+
+impl FulltextSchema {
+    /// Create a schema with only the `id` field
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a searchable text field, stored so hits can return it
+    pub fn text_field(mut self, name: &str) -> Self {
+        self.text_fields.push(name.to_string());
+        self
+    }
+
+    /// Add a stored, non-searchable field (paths, URLs, timestamps, ...)
+    pub fn stored_field(mut self, name: &str) -> Self {
+        self.stored_fields.push(name.to_string());
+        self
+    }
+
+    /// Choose the text field snippets are taken from
+    ///
+    /// Defaults to the last text field, typically the body.
+    pub fn snippet_field(mut self, name: &str) -> Self {
+        self.snippet_field = Some(name.to_string());
+        self
+    }
+
+    /// Split text with a tokenizer instead of the one installed with
+    /// `matching::set_tokenizer`
+    pub fn tokenizer(mut self, tokenizer: Tokenizer) -> Self {
+        self.tokenizer = Some(tokenizer);
+        self
+    }
+
+    fn build(&self) -> Result<Schema, String> {
+        let mut builder = Schema::builder();
+        builder.add_text_field(ID_FIELD, STRING | STORED);
+
+        let mut seen = vec![ID_FIELD];
+        for name in self.text_fields.iter().chain(&self.stored_fields) {
+            if seen.contains(&name.as_str()) {
+                return Err(format!("Duplicate full-text field '{}'", name));
+            }
+            seen.push(name);
+        }
+        if self.text_fields.is_empty() {
+            return Err("A full-text schema needs at least one text field".to_string());
+        }
+        if let Some(snippet_field) = &self.snippet_field
+            && !self.text_fields.contains(snippet_field)
+        {
+            return Err(format!("Snippet field '{}' is not a text field", snippet_field));
+        }
+
+        for name in &self.text_fields {
+            builder.add_text_field(name, TEXT | STORED);
+        }
+        for name in &self.stored_fields {
+            builder.add_text_field(name, STORED);
+        }
+
+        Ok(builder.build())
+    }
+}
+
+/// A document to index
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FulltextDoc {
+    /// Unique identifier; indexing a document with an existing ID replaces it
+    pub id: String,
+    /// Field values by field name
+    pub fields: HashMap<String, String>,
+}
+
+impl FulltextDoc {
+    /// Create a document with no fields
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            fields: HashMap::new(),
+        }
+    }
+
+    /// Set a field value
+    pub fn with(mut self, field: &str, value: impl Into<String>) -> Self {
+        self.fields.insert(field.to_string(), value.into());
+        self
+    }
+}
+
+/// Excerpt of a matching document
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snippet {
+    /// Plain-text excerpt
+    pub text: String,
+    /// Byte ranges of `text` matching the query
+    pub highlights: Vec<Range<usize>>,
+}
+
+/// A document matching a query
+#[derive(Debug, Clone, PartialEq)]
+pub struct FulltextHit {
+    /// Document ID
+    pub id: String,
+    /// Relevance score (BM25), higher is better
+    pub score: f32,
+    /// Stored field values
+    pub fields: HashMap<String, String>,
+    /// Excerpt of the snippet field around the matches
+    pub snippet: Option<Snippet>,
+}
+
+/// A plugin's full-text index
+pub struct FulltextIndex {
+    index: Index,
+    reader: IndexReader,
+    writer: Mutex<IndexWriter>,
+    id_field: Field,
+    fields: HashMap<String, Field>,
+    text_fields: Vec<Field>,
+    snippet_field: Field,
+}
+
+impl FulltextIndex {
+    /// Open the index in a directory, creating it if needed
+    ///
+    /// Plugins normally go through `VoltPluginAPI::open_fulltext_index`,
+    /// which places the directory in the plugin's data directory.
+    ///
+    /// # Arguments
+    /// * `dir` - Index directory
+    /// * `schema` - Fields of the index; must match an existing index
+    pub fn open(dir: &Path, schema: &FulltextSchema) -> Result<Self, String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create index directory: {}", e))?;
+
+        let directory = MmapDirectory::open(dir).map_err(|e| format!("Failed to open index: {}", e))?;
+        let index = Index::open_or_create(directory, schema.build()?)
+            .map_err(|e| format!("Failed to open index: {}", e))?;
+        let tokenizer = match &schema.tokenizer {
+            Some(tokenizer) => Arc::new(tokenizer.clone()),
+            None => matching::tokenizer(),
+        };
+        index
+            .tokenizers()
+            .register(TOKENIZER_NAME, TextAnalyzer::from(TokenizerAdapter(tokenizer)));
+
+        let writer = index
+            .writer(WRITER_MEMORY_BUDGET)
+            .map_err(|e| format!("Failed to create index writer: {}", e))?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()
+            .map_err(|e| format!("Failed to create index reader: {}", e))?;
+
+        let tantivy_schema = index.schema();
+        let field = |name: &str| {
+            tantivy_schema
+                .get_field(name)
+                .map_err(|e| format!("Failed to resolve field '{}': {}", name, e))
+        };
+
+        let id_field = field(ID_FIELD)?;
+        let mut fields = HashMap::new();
+        for name in schema.text_fields.iter().chain(&schema.stored_fields) {
+            fields.insert(name.clone(), field(name)?);
+        }
+        let text_fields = schema
+            .text_fields
+            .iter()
+            .map(|name| fields[name])
+            .collect::<Vec<_>>();
+        let snippet_field = match &schema.snippet_field {
+            Some(name) => fields[name],
+            None => *text_fields.last().expect("schema has a text field"),
+        };
+
+        Ok(Self {
+            index,
+            reader,
+            writer: Mutex::new(writer),
+            id_field,
+            fields,
+            text_fields,
+            snippet_field,
+        })
+    }
+
+    /// Add or replace a document
+    ///
+    /// Changes become visible to searches after `commit`.
+    pub fn upsert(&self, doc: &FulltextDoc) -> Result<(), String> {
+        if doc.id.is_empty() {
+            return Err("Indexed documents must have an ID".to_string());
+        }
+
+        let mut document = TantivyDocument::default();
+        document.add_text(self.id_field, &doc.id);
+        for (name, value) in &doc.fields {
+            let field = self
+                .fields
+                .get(name)
+                .ok_or_else(|| format!("Unknown full-text field '{}'", name))?;
+            document.add_text(*field, value);
+        }
+
+        let writer = self.lock_writer()?;
+        writer.delete_term(Term::from_field_text(self.id_field, &doc.id));
+        writer
+            .add_document(document)
+            .map_err(|e| format!("Failed to index document: {}", e))?;
+        Ok(())
+    }
+
+    /// Delete a document
+    ///
+    /// Changes become visible to searches after `commit`.
+    pub fn delete(&self, id: &str) -> Result<(), String> {
+        self.lock_writer()?
+            .delete_term(Term::from_field_text(self.id_field, id));
+        Ok(())
+    }
+
+    /// Persist pending changes and make them visible to searches
+    pub fn commit(&self) -> Result<(), String> {
+        self.lock_writer()?
+            .commit()
+            .map_err(|e| format!("Failed to commit index: {}", e))?;
+
+        self.reader
+            .reload()
+            .map_err(|e| format!("Failed to reload index: {}", e))
+    }
+
+    /// Discard changes made since the last commit
+    pub fn rollback(&self) -> Result<(), String> {
+        self.lock_writer()?
+            .rollback()
+            .map(|_| ())
+            .map_err(|e| format!("Failed to roll back index: {}", e))
+    }
+
+    /// Number of committed documents
+    pub fn document_count(&self) -> u64 {
+        self.reader.searcher().num_docs()
+    }
+
+    /// Search the index
+    ///
+    /// The query uses tantivy's syntax (`"exact phrase"`, `title:rust`,
+    /// `-excluded`); all terms must match by default. Syntax errors are
+    /// tolerated rather than reported, since queries come from keystrokes.
+    ///
+    /// # Arguments
+    /// * `query` - Query text
+    /// * `limit` - Maximum number of hits
+    ///
+    /// # Returns
+    /// Hits sorted by score, best first
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<FulltextHit>, String> {
+        if query.trim().is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        let searcher = self.reader.searcher();
+        let mut parser = QueryParser::for_index(&self.index, self.text_fields.clone());
+        parser.set_conjunction_by_default();
+        let (query, _errors) = parser.parse_query_lenient(query);
+
+        let top_docs = searcher
+            .search(&query, &TopDocs::with_limit(limit))
+            .map_err(|e| format!("Failed to search index: {}", e))?;
+
+        let mut snippets = SnippetGenerator::create(&searcher, &*query, self.snippet_field)
+            .map_err(|e| format!("Failed to create snippets: {}", e))?;
+        snippets.set_max_num_chars(SNIPPET_MAX_CHARS);
+
+        let mut hits = Vec::with_capacity(top_docs.len());
+        for (score, address) in top_docs {
+            let document: TantivyDocument = searcher
+                .doc(address)
+                .map_err(|e| format!("Failed to read document: {}", e))?;
+
+            let text = |field: Field| {
+                document
+                    .get_first(field)
+                    .and_then(|value| value.as_str())
+                    .map(str::to_string)
+            };
+
+            let fields = self
+                .fields
+                .iter()
+                .filter_map(|(name, field)| text(*field).map(|value| (name.clone(), value)))
+                .collect();
+
+            let snippet = snippets.snippet_from_doc(&document);
+            let snippet = (!snippet.is_empty()).then(|| Snippet {
+                text: snippet.fragment().to_string(),
+                highlights: snippet.highlighted().to_vec(),
+            });
+
+            hits.push(FulltextHit {
+                id: text(self.id_field).unwrap_or_default(),
+                score,
+                fields,
+                snippet,
+            });
+        }
+
+        Ok(hits)
+    }
+
+    fn lock_writer(&self) -> Result<std::sync::MutexGuard<'_, IndexWriter>, String> {
+        self.writer
+            .lock()
+            .map_err(|e| format!("Failed to acquire index writer lock: {}", e))
+    }
+}
+
+/// Splits text for tantivy with a `Tokenizer`
+#[derive(Clone)]
+struct TokenizerAdapter(Arc<Tokenizer>);
+
+impl tantivy::tokenizer::Tokenizer for TokenizerAdapter {
+    type TokenStream<'a> = Tokens;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Tokens {
+        let tokens = self
+            .0
+            .tokens(text)
+            .into_iter()
+            .map(|token| Token {
+                offset_from: token.start,
+                offset_to: token.end,
+                position: token.position,
+                text: token.text,
+                position_length: 1,
+            })
+            .collect();
+        Tokens { tokens, next: 0 }
+    }
+}
+
+/// Words of a text, handed to tantivy one by one
+struct Tokens {
+    tokens: Vec<Token>,
+    next: usize,
+}
+
+impl TokenStream for Tokens {
+    fn advance(&mut self) -> bool {
+        if self.next == self.tokens.len() {
+            return false;
+        }
+        self.next += 1;
+        true
+    }
+
+    fn token(&self) -> &Token {
+        &self.tokens[self.next - 1]
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.tokens[self.next - 1]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> FulltextSchema {
+        FulltextSchema::new()
+            .text_field("title")
+            .text_field("body")
+            .stored_field("path")
+    }
+
+    #[test]
+    fn test_index_and_search() {
+        let dir = std::env::temp_dir().join("volt_test_fulltext");
+        let _ = std::fs::remove_dir_all(&dir);
+        let index = FulltextIndex::open(&dir, &schema().tokenizer(Tokenizer::for_locale("en"))).unwrap();
+
+        index
+            .upsert(
+                &FulltextDoc::new("n1")
+                    .with("title", "Release checklist")
+                    .with("body", "Tag the release, publish the crate and announce it")
+                    .with("path", "notes/release.md"),
+            )
+            .unwrap();
+        index
+            .upsert(&FulltextDoc::new("n2").with("title", "Groceries").with("body", "Eggs, milk"))
+            .unwrap();
+        assert!(index.search("release", 10).unwrap().is_empty());
+
+        index.commit().unwrap();
+        let hits = index.search("publish crate", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, "n1");
+        assert_eq!(hits[0].fields["path"], "notes/release.md");
+
+        let snippet = hits[0].snippet.as_ref().unwrap();
+        let highlighted: Vec<&str> = snippet
+            .highlights
+            .iter()
+            .map(|range| &snippet.text[range.clone()])
+            .collect();
+        assert_eq!(highlighted, vec!["publish", "crate"]);
+
+        // Words are split like everywhere else, and stop words are ignored
+        index
+            .upsert(&FulltextDoc::new("n3").with("title", "gitHubDesktop").with("body", "The app"))
+            .unwrap();
+        index.commit().unwrap();
+        assert_eq!(index.search("hub", 10).unwrap()[0].id, "n3");
+        assert!(index.search("the", 10).unwrap().is_empty());
+        index.delete("n3").unwrap();
+
+        // Replacing and deleting are incremental
+        index
+            .upsert(&FulltextDoc::new("n2").with("title", "Groceries").with("body", "Eggs, bread"))
+            .unwrap();
+        index.delete("n1").unwrap();
+        index.commit().unwrap();
+        assert_eq!(index.document_count(), 1);
+        assert!(index.search("milk", 10).unwrap().is_empty());
+        assert_eq!(index.search("bread", 10).unwrap()[0].id, "n2");
+
+        drop(index);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_schema_validation() {
+        assert!(FulltextSchema::new().build().is_err());
+        assert!(FulltextSchema::new().text_field("id").build().is_err());
+        assert!(FulltextSchema::new()
+            .text_field("title")
+            .stored_field("url")
+            .snippet_field("url")
+            .build()
+            .is_err());
+        assert!(schema().build().is_ok());
+    }
+}
@@ -0,0 +1,86 @@
+/// Fuzz targets for the parsers fed untrusted input
+///
+/// Each target takes arbitrary bytes, runs them through a parser and panics
+/// when one of the parser's invariants breaks. The cargo-fuzz harnesses in
+/// `fuzz/` call these functions, so the targets are versioned and type-checked
+/// with the crate; the property tests reuse them on generated inputs.
+use crate::manifest::PluginManifest;
+use crate::platform::{Platform, PlatformInfo};
+use crate::protocol::{RpcRequest, RpcResponse};
+
+/// Parse a `manifest.json`
+///
+/// A manifest that parses must survive a serialize/parse round trip
+/// unchanged, and platform checks must not panic on its version strings.
+pub fn manifest(data: &[u8]) {
+    let Ok(content) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(manifest) = PluginManifest::from_json(content) else {
+        return;
+    };
+
+    let encoded = serde_json::to_string(&manifest).expect("parsed manifest failed to serialize");
+    let decoded = PluginManifest::from_json(&encoded).expect("serialized manifest failed to parse");
+    assert_eq!(decoded, manifest, "manifest changed in a round trip");
+
+    for platform in [Platform::Windows, Platform::Macos, Platform::Linux] {
+        for os_version in [None, Some("10.0.19041".to_string())] {
+            let _ = manifest.check_platform(&PlatformInfo {
+                platform: Some(platform),
+                os_version,
+            });
+        }
+    }
+}
+
+/// Decode a message received by a bridge, as a request and as a response
+///
+/// A message that decodes must encode to a message that decodes to the same
+/// call, and a response must convert to a result without panicking.
+pub fn bridge_message(data: &[u8]) {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    if let Ok(request) = RpcRequest::decode(text) {
+        let encoded = serde_json::to_string(&request).expect("decoded request failed to encode");
+        let decoded = RpcRequest::decode(&encoded).expect("encoded request failed to decode");
+        assert_eq!(decoded.id, request.id, "request ID changed in a round trip");
+        assert_eq!(decoded.method, request.method, "request method changed in a round trip");
+    }
+
+    if let Ok(response) = RpcResponse::decode(text) {
+        let encoded = serde_json::to_string(&response).expect("decoded response failed to encode");
+        let decoded = RpcResponse::decode(&encoded).expect("encoded response failed to decode");
+        assert_eq!(decoded.id, response.id, "response ID changed in a round trip");
+        assert_eq!(
+            decoded.error.is_some(),
+            response.error.is_some(),
+            "response outcome changed in a round trip"
+        );
+        let _ = response.into_result();
+    }
+}
+
+/// Strategy generating JSON values without floats, which serde_json doesn't
+/// always round-trip bit for bit
+#[cfg(test)]
+pub(crate) fn json_value() -> impl proptest::strategy::Strategy<Value = serde_json::Value> {
+    use proptest::prelude::*;
+    use serde_json::Value;
+
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::from),
+        any::<i64>().prop_map(Value::from),
+        ".{0,16}".prop_map(Value::from),
+    ];
+    leaf.prop_recursive(3, 32, 4, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..4).prop_map(Value::from),
+            prop::collection::btree_map(".{0,8}", inner, 0..4)
+                .prop_map(|entries| Value::Object(entries.into_iter().collect())),
+        ]
+    })
+}
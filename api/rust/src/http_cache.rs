@@ -0,0 +1,594 @@
+/// HTTP cache shared by all plugins
+///
+/// Plugins fetching public resources (favicons, currency feeds, emoji
+/// databases) often ask for the same URLs. The download manager keeps every
+/// response it is allowed to store in this cache, below the global cache
+/// directory, and serves later requests for the same URL from it, whichever
+/// plugin asks:
+/// - responses are stored as the `Cache-Control` header allows: `no-store`
+///   and `private` responses never are, and `max-age` / `s-maxage` say how
+///   long a response is served without asking the server
+/// - stale responses with an `ETag` or `Last-Modified` are revalidated with
+///   a conditional request instead of downloaded again
+/// - bodies are content-addressed, so identical files fetched from several
+///   URLs of an origin are stored once
+/// - each origin has a size cap; past it, the least recently used responses
+///   of the origin are evicted
+///
+/// ```text
+/// <cache>/http/<origin>/entries/<sha256 of URL>.json
+/// <cache>/http/<origin>/blobs/<sha256 of body>
+/// ```
+use crate::actions::now_millis;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Directory of the global cache directory holding the HTTP cache
+pub const HTTP_CACHE_DIR: &str = "http";
+
+/// Default size cap of the responses of one origin, in bytes
+pub const DEFAULT_MAX_ORIGIN_BYTES: u64 = 32 * 1024 * 1024;
+
+/// Directives of a `Cache-Control` header that matter to the cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheControl {
+    /// `no-store`: the response must not be stored
+    pub no_store: bool,
+    /// `no-cache`: the response must be revalidated before each use
+    pub no_cache: bool,
+    /// `private`: the response is meant for a single user agent
+    pub private: bool,
+    /// `max-age`, in seconds
+    pub max_age: Option<u64>,
+    /// `s-maxage`, in seconds; overrides `max-age` for shared caches
+    pub s_maxage: Option<u64>,
+}
+
+impl CacheControl {
+    /// Parse a `Cache-Control` header
+    ///
+    /// Unknown directives are ignored.
+    pub fn parse(header: &str) -> Self {
+        let mut control = Self::default();
+        for directive in header.split(',') {
+            let (name, value) = match directive.split_once('=') {
+                Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+                None => (directive.trim(), None),
+            };
+            let seconds = value.and_then(|value| value.parse().ok());
+            match name.to_lowercase().as_str() {
+                "no-store" => control.no_store = true,
+                "no-cache" => control.no_cache = true,
+                "private" => control.private = true,
+                "max-age" => control.max_age = seconds,
+                "s-maxage" => control.s_maxage = seconds,
+                _ => {}
+            }
+        }
+        control
+    }
+
+    /// Check if a shared cache may store the response
+    pub fn is_storable(&self) -> bool {
+        !self.no_store && !self.private
+    }
+
+    /// How long the response is fresh, in seconds
+    ///
+    /// # Arguments
+    /// * `age` - Value of the response's `Age` header, time it already spent
+    ///   in upstream caches
+    pub fn freshness(&self, age: u64) -> u64 {
+        if self.no_cache {
+            return 0;
+        }
+        self.s_maxage
+            .or(self.max_age)
+            .unwrap_or(0)
+            .saturating_sub(age)
+    }
+}
+
+/// Headers of a response the cache needs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResponseHeaders {
+    /// `Cache-Control`
+    pub cache_control: CacheControl,
+    /// `Age`, in seconds
+    pub age: u64,
+    /// `ETag`
+    pub etag: Option<String>,
+    /// `Last-Modified`
+    pub last_modified: Option<String>,
+    /// `Content-Type`
+    pub content_type: Option<String>,
+}
+
+impl ResponseHeaders {
+    /// Read the cache headers of a response
+    pub fn from_headers(headers: &reqwest::header::HeaderMap) -> Self {
+        use reqwest::header::{AGE, CACHE_CONTROL, CONTENT_TYPE, ETAG, LAST_MODIFIED};
+
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            cache_control: header(CACHE_CONTROL)
+                .map(|value| CacheControl::parse(&value))
+                .unwrap_or_default(),
+            age: header(AGE)
+                .and_then(|value| value.trim().parse().ok())
+                .unwrap_or(0),
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+            content_type: header(CONTENT_TYPE),
+        }
+    }
+}
+
+/// A stored response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedResponse {
+    /// URL of the request
+    pub url: String,
+    /// SHA-256 of the body, hex encoded
+    pub sha256: String,
+    /// Size of the body, in bytes
+    pub size: u64,
+    /// `Content-Type` of the response
+    pub content_type: Option<String>,
+    /// `ETag` of the response
+    pub etag: Option<String>,
+    /// `Last-Modified` of the response
+    pub last_modified: Option<String>,
+    /// When the response was stored or last revalidated, in milliseconds
+    /// since the Unix epoch
+    pub validated_at: u64,
+    /// Seconds after `validated_at` the response is served without asking
+    /// the server
+    pub fresh_for: u64,
+    /// When the response was last served, in milliseconds since the Unix epoch
+    pub last_used: u64,
+    /// Path of the body in the cache
+    #[serde(skip)]
+    pub body: PathBuf,
+}
+
+impl CachedResponse {
+    /// Check if the response can be served without asking the server
+    pub fn is_fresh(&self, now: u64) -> bool {
+        now < self
+            .validated_at
+            .saturating_add(self.fresh_for.saturating_mul(1000))
+    }
+
+    /// Check if the response can be revalidated with a conditional request
+    pub fn has_validator(&self) -> bool {
+        self.etag.is_some() || self.last_modified.is_some()
+    }
+}
+
+/// Content-addressed HTTP cache with a size cap per origin
+///
+/// Cheap to clone; clones share the same directory.
+#[derive(Debug, Clone)]
+pub struct HttpCache {
+    dir: PathBuf,
+    max_origin_bytes: u64,
+    /// Serializes changes to the entries, evictions included
+    lock: Arc<Mutex<()>>,
+}
+
+impl HttpCache {
+    /// Create a cache stored in a directory
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            max_origin_bytes: DEFAULT_MAX_ORIGIN_BYTES,
+            lock: Arc::default(),
+        }
+    }
+
+    /// Set the size cap of the responses of one origin, in bytes
+    pub fn with_max_origin_bytes(mut self, max_origin_bytes: u64) -> Self {
+        self.max_origin_bytes = max_origin_bytes;
+        self
+    }
+
+    /// Get the directory of the cache
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Get the stored response of a URL, fresh or not
+    ///
+    /// # Returns
+    /// None if the URL isn't cached, or its body is gone
+    pub fn lookup(&self, url: &str) -> Option<CachedResponse> {
+        let origin_dir = self.origin_dir(url)?;
+        let response = read_entry(&entry_path(&origin_dir, url))?;
+        response.body.exists().then_some(response)
+    }
+
+    /// Serve a stored response, recording that it was used
+    ///
+    /// # Returns
+    /// The response, or None if the URL isn't cached
+    pub fn touch(&self, url: &str) -> Option<CachedResponse> {
+        let _guard = self.lock.lock().unwrap_or_else(|p| p.into_inner());
+        let mut response = self.lookup(url)?;
+        response.last_used = now_millis();
+
+        let origin_dir = self.origin_dir(url)?;
+        // Only affects eviction order
+        let _ = write_entry(&entry_path(&origin_dir, url), &response);
+        Some(response)
+    }
+
+    /// Store a response whose body was downloaded to a file
+    ///
+    /// # Arguments
+    /// * `url` - URL of the request
+    /// * `headers` - Headers of the response
+    /// * `file` - The downloaded body
+    ///
+    /// # Returns
+    /// Whether the response was stored; responses the headers forbid
+    /// storing, or larger than the origin's cap, aren't
+    pub fn store(&self, url: &str, headers: &ResponseHeaders, file: &Path) -> Result<bool, String> {
+        let Some(origin_dir) = self.origin_dir(url) else {
+            return Ok(false);
+        };
+        if !headers.cache_control.is_storable() {
+            self.remove(url);
+            return Ok(false);
+        }
+        let size = std::fs::metadata(file)
+            .map_err(|e| format!("Failed to read cached response: {}", e))?
+            .len();
+        if size > self.max_origin_bytes {
+            self.remove(url);
+            return Ok(false);
+        }
+
+        let content =
+            std::fs::read(file).map_err(|e| format!("Failed to read cached response: {}", e))?;
+        let sha256 = hex(&Sha256::digest(&content));
+        let blobs_dir = origin_dir.join("blobs");
+        std::fs::create_dir_all(&blobs_dir)
+            .map_err(|e| format!("Failed to create HTTP cache directory: {}", e))?;
+        std::fs::create_dir_all(origin_dir.join("entries"))
+            .map_err(|e| format!("Failed to create HTTP cache directory: {}", e))?;
+
+        let _guard = self.lock.lock().unwrap_or_else(|p| p.into_inner());
+        let body = blobs_dir.join(&sha256);
+        if !body.exists() {
+            write_atomic(&body, &content)?;
+        }
+
+        let now = now_millis();
+        let response = CachedResponse {
+            url: url.to_string(),
+            sha256,
+            size,
+            content_type: headers.content_type.clone(),
+            etag: headers.etag.clone(),
+            last_modified: headers.last_modified.clone(),
+            validated_at: now,
+            fresh_for: headers.cache_control.freshness(headers.age),
+            last_used: now,
+            body,
+        };
+        let path = entry_path(&origin_dir, url);
+        let replaced = read_entry(&path);
+        write_entry(&path, &response)?;
+        if let Some(replaced) = replaced
+            && replaced.sha256 != response.sha256
+        {
+            remove_unreferenced_blob(&origin_dir, &replaced.sha256);
+        }
+
+        self.evict(&origin_dir, url);
+        Ok(true)
+    }
+
+    /// Record that the server confirmed a stored response is current
+    ///
+    /// Call this on a `304 Not Modified` answering a conditional request.
+    ///
+    /// # Returns
+    /// The refreshed response, or None if the URL isn't cached
+    pub fn revalidated(&self, url: &str, headers: &ResponseHeaders) -> Option<CachedResponse> {
+        let _guard = self.lock.lock().unwrap_or_else(|p| p.into_inner());
+        let mut response = self.lookup(url)?;
+        let now = now_millis();
+        response.validated_at = now;
+        response.last_used = now;
+        // A 304 may carry updated headers
+        response.fresh_for = headers.cache_control.freshness(headers.age);
+        if headers.etag.is_some() {
+            response.etag = headers.etag.clone();
+        }
+        if headers.last_modified.is_some() {
+            response.last_modified = headers.last_modified.clone();
+        }
+
+        let origin_dir = self.origin_dir(url)?;
+        write_entry(&entry_path(&origin_dir, url), &response).ok()?;
+        Some(response)
+    }
+
+    /// Forget the stored response of a URL
+    pub fn remove(&self, url: &str) {
+        let Some(origin_dir) = self.origin_dir(url) else {
+            return;
+        };
+        let path = entry_path(&origin_dir, url);
+        if let Some(response) = read_entry(&path) {
+            let _ = std::fs::remove_file(&path);
+            remove_unreferenced_blob(&origin_dir, &response.sha256);
+        }
+    }
+
+    /// Get the size of the stored bodies of a URL's origin, in bytes
+    pub fn origin_size(&self, url: &str) -> u64 {
+        self.origin_dir(url)
+            .map(|origin_dir| blob_sizes(&origin_dir).values().sum())
+            .unwrap_or(0)
+    }
+
+    /// Delete every stored response
+    pub fn clear(&self) -> Result<(), String> {
+        let _guard = self.lock.lock().unwrap_or_else(|p| p.into_inner());
+        match std::fs::remove_dir_all(&self.dir) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("Failed to clear HTTP cache: {}", e))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Directory of the responses of a URL's origin
+    ///
+    /// # Returns
+    /// None if the URL isn't an http(s) URL
+    fn origin_dir(&self, url: &str) -> Option<PathBuf> {
+        let url = reqwest::Url::parse(url).ok()?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return None;
+        }
+        let host: String = url
+            .host_str()?
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let port = url.port_or_known_default()?;
+        Some(self.dir.join(format!("{}_{}_{}", url.scheme(), host, port)))
+    }
+
+    /// Evict the least recently used responses of an origin past its cap
+    ///
+    /// The response of `keep` was just stored and is evicted last.
+    fn evict(&self, origin_dir: &Path, keep: &str) {
+        let mut blobs = blob_sizes(origin_dir);
+        let mut total: u64 = blobs.values().sum();
+        if total <= self.max_origin_bytes {
+            return;
+        }
+
+        let mut entries: Vec<(PathBuf, CachedResponse)> =
+            std::fs::read_dir(origin_dir.join("entries"))
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter_map(|entry| Some((entry.path(), read_entry(&entry.path())?)))
+                .collect();
+        entries.sort_by_key(|(_, response)| (response.url == keep, response.last_used));
+
+        for (path, response) in entries {
+            if total <= self.max_origin_bytes {
+                break;
+            }
+            let _ = std::fs::remove_file(&path);
+            if remove_unreferenced_blob(origin_dir, &response.sha256) {
+                total -= blobs.remove(&response.sha256).unwrap_or(0);
+            }
+        }
+    }
+}
+
+/// Path of the entry of a URL
+fn entry_path(origin_dir: &Path, url: &str) -> PathBuf {
+    origin_dir
+        .join("entries")
+        .join(format!("{}.json", hex(&Sha256::digest(url.as_bytes()))))
+}
+
+fn read_entry(path: &Path) -> Option<CachedResponse> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let mut response: CachedResponse = serde_json::from_str(&content).ok()?;
+    response.body = path
+        .parent()?
+        .parent()?
+        .join("blobs")
+        .join(&response.sha256);
+    Some(response)
+}
+
+fn write_entry(path: &Path, response: &CachedResponse) -> Result<(), String> {
+    let content = serde_json::to_vec(response)
+        .map_err(|e| format!("Failed to serialize cached response: {}", e))?;
+    write_atomic(path, &content)
+}
+
+/// Write a file through a temporary file, so readers never see it half written
+fn write_atomic(path: &Path, content: &[u8]) -> Result<(), String> {
+    let temp = path.with_extension("tmp");
+    std::fs::write(&temp, content).map_err(|e| format!("Failed to write HTTP cache: {}", e))?;
+    std::fs::rename(&temp, path).map_err(|e| format!("Failed to write HTTP cache: {}", e))
+}
+
+/// Size of each body of an origin, by SHA-256
+fn blob_sizes(origin_dir: &Path) -> HashMap<String, u64> {
+    std::fs::read_dir(origin_dir.join("blobs"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let size = entry.metadata().ok()?.len();
+            Some((entry.file_name().to_string_lossy().to_string(), size))
+        })
+        .collect()
+}
+
+/// Delete a body no entry of its origin refers to anymore
+///
+/// # Returns
+/// Whether the body was deleted
+fn remove_unreferenced_blob(origin_dir: &Path, sha256: &str) -> bool {
+    let referenced = std::fs::read_dir(origin_dir.join("entries"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| read_entry(&entry.path()))
+        .any(|response| response.sha256 == sha256);
+
+    !referenced && std::fs::remove_file(origin_dir.join("blobs").join(sha256)).is_ok()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_control() {
+        let control = CacheControl::parse("public, max-age=600, s-maxage=\"60\"");
+        assert!(control.is_storable());
+        assert_eq!(control.freshness(0), 60);
+        assert_eq!(control.freshness(100), 0);
+
+        assert!(!CacheControl::parse("no-store").is_storable());
+        assert!(!CacheControl::parse("Private, max-age=60").is_storable());
+        assert_eq!(CacheControl::parse("no-cache, max-age=60").freshness(0), 0);
+        assert_eq!(CacheControl::default().freshness(0), 0);
+    }
+
+    #[test]
+    fn test_store_dedup_and_evict() {
+        let temp_dir = std::env::temp_dir().join("volt_test_http_cache");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(&temp_dir).unwrap();
+
+        let cache = HttpCache::new(temp_dir.join(HTTP_CACHE_DIR)).with_max_origin_bytes(250);
+        let body = |name: &str, byte: u8, size: usize| {
+            let path = temp_dir.join(name);
+            std::fs::write(&path, vec![byte; size]).unwrap();
+            path
+        };
+        let headers = ResponseHeaders {
+            cache_control: CacheControl::parse("max-age=60"),
+            etag: Some("\"a\"".to_string()),
+            ..ResponseHeaders::default()
+        };
+
+        let icon = body("icon", 1, 100);
+        assert!(
+            cache
+                .store("https://example.com/favicon.ico", &headers, &icon)
+                .unwrap()
+        );
+        assert!(
+            cache
+                .store("https://example.com/icon.ico?v=2", &headers, &icon)
+                .unwrap()
+        );
+        // Identical bodies are stored once
+        assert_eq!(cache.origin_size("https://example.com/"), 100);
+        let cached = cache.lookup("https://example.com/favicon.ico").unwrap();
+        assert!(cached.is_fresh(now_millis()));
+        assert_eq!(std::fs::read(&cached.body).unwrap(), vec![1; 100]);
+
+        // Origins have separate caps
+        assert!(
+            cache
+                .store("http://example.com/favicon.ico", &headers, &icon)
+                .unwrap()
+        );
+        assert_eq!(cache.origin_size("http://example.com/"), 100);
+
+        let private = ResponseHeaders {
+            cache_control: CacheControl::parse("private"),
+            ..ResponseHeaders::default()
+        };
+        assert!(
+            !cache
+                .store("https://example.com/me", &private, &icon)
+                .unwrap()
+        );
+        assert!(
+            !cache
+                .store("https://example.com/huge", &headers, &body("huge", 2, 300))
+                .unwrap()
+        );
+        assert!(cache.lookup("https://example.com/me").is_none());
+
+        // Past the cap, the least recently used go first
+        cache.touch("https://example.com/favicon.ico").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        assert!(
+            cache
+                .store(
+                    "https://example.com/feed.json",
+                    &headers,
+                    &body("feed", 3, 200)
+                )
+                .unwrap()
+        );
+        assert!(cache.lookup("https://example.com/feed.json").is_some());
+        assert!(cache.lookup("https://example.com/favicon.ico").is_none());
+        assert!(cache.lookup("https://example.com/icon.ico?v=2").is_none());
+        assert_eq!(cache.origin_size("https://example.com/"), 200);
+
+        let stale = ResponseHeaders {
+            cache_control: CacheControl::parse("no-cache"),
+            ..headers.clone()
+        };
+        assert!(
+            cache
+                .store(
+                    "https://example.com/feed.json",
+                    &stale,
+                    &body("feed", 3, 200)
+                )
+                .unwrap()
+        );
+        let cached = cache.lookup("https://example.com/feed.json").unwrap();
+        assert!(!cached.is_fresh(now_millis()));
+        let refreshed = cache
+            .revalidated("https://example.com/feed.json", &headers)
+            .unwrap();
+        assert!(refreshed.is_fresh(now_millis()));
+
+        cache.clear().unwrap();
+        assert!(cache.lookup("https://example.com/feed.json").is_none());
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
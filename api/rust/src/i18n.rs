@@ -0,0 +1,317 @@
+/// Translations of the built-in plugins' strings
+///
+/// Built-in plugins look up every string they show (names, result titles,
+/// subtitles, toasts, notifications, settings labels) by key in the
+/// catalogs shipped with the crate, through the `Messages` of the locale the
+/// host set with `VoltPluginAPI::set_locale`. Lookups fall back from a
+/// regional locale to its language (`de-AT` to `de`) and then to English,
+/// so a missing translation shows the English text rather than a key.
+/// Error messages and logs stay in English, like the rest of the crate.
+///
+/// The `qps-ploc` pseudo-locale rewrites the English strings with accented
+/// letters inside brackets (`[Ţíɱéŕš]`). Tests run plugins under it and
+/// check results with `is_pseudo_localized`; hard-coded strings stand out
+/// because they come back unbracketed.
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+mod de;
+mod en;
+mod es;
+mod fr;
+mod ja;
+
+/// Locale of the built-in strings as written in the source
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Pseudo-locale for catching unlocalized strings
+pub const PSEUDO_LOCALE: &str = "qps-ploc";
+
+/// Strings of one locale, by key
+struct Catalog {
+    locale: &'static str,
+    messages: &'static [(&'static str, &'static str)],
+}
+
+/// Catalogs shipped with the crate, English first
+const CATALOGS: &[Catalog] = &[
+    Catalog {
+        locale: "en",
+        messages: en::MESSAGES,
+    },
+    Catalog {
+        locale: "de",
+        messages: de::MESSAGES,
+    },
+    Catalog {
+        locale: "es",
+        messages: es::MESSAGES,
+    },
+    Catalog {
+        locale: "fr",
+        messages: fr::MESSAGES,
+    },
+    Catalog {
+        locale: "ja",
+        messages: ja::MESSAGES,
+    },
+];
+
+/// Locales with shipped translations, plus the pseudo-locale
+pub fn supported_locales() -> Vec<&'static str> {
+    CATALOGS
+        .iter()
+        .map(|catalog| catalog.locale)
+        .chain([PSEUDO_LOCALE])
+        .collect()
+}
+
+/// The strings of a locale
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Messages {
+    /// Index in `CATALOGS` of the best matching catalog
+    catalog: usize,
+    pseudo: bool,
+}
+
+impl Messages {
+    /// Get the strings of a locale
+    ///
+    /// Accepts BCP 47 tags and POSIX locale names (`pt-BR`, `de_AT.UTF-8`).
+    /// Unknown locales get English.
+    pub fn for_locale(locale: &str) -> Self {
+        let tag = locale
+            .split(['.', '@'])
+            .next()
+            .unwrap_or_default()
+            .replace('_', "-")
+            .to_ascii_lowercase();
+        if tag == PSEUDO_LOCALE {
+            return Self {
+                catalog: 0,
+                pseudo: true,
+            };
+        }
+
+        let language = tag.split('-').next().unwrap_or_default();
+        let catalog = [tag.as_str(), language]
+            .iter()
+            .find_map(|candidate| {
+                CATALOGS
+                    .iter()
+                    .position(|catalog| catalog.locale == *candidate)
+            })
+            .unwrap_or(0);
+        Self {
+            catalog,
+            pseudo: false,
+        }
+    }
+
+    /// Locale the strings are in, after fallback
+    pub fn locale(&self) -> &'static str {
+        if self.pseudo {
+            PSEUDO_LOCALE
+        } else {
+            CATALOGS[self.catalog].locale
+        }
+    }
+
+    /// Get a string without placeholders
+    ///
+    /// # Returns
+    /// The translation, the English text if there is none, or the key itself
+    /// for an unknown key
+    pub fn text(&self, key: &'static str) -> &'static str {
+        let text = lookup(&CATALOGS[self.catalog], key)
+            .or_else(|| lookup(&CATALOGS[0], key))
+            .unwrap_or(key);
+
+        if self.pseudo {
+            pseudo_static(text)
+        } else {
+            text
+        }
+    }
+
+    /// Get a string with its `{name}` placeholders replaced
+    ///
+    /// # Arguments
+    /// * `key` - Key of the string
+    /// * `args` - Value of each placeholder, inserted untranslated
+    pub fn format(&self, key: &'static str, args: &[(&str, &str)]) -> String {
+        let mut text = self.text(key).to_string();
+        for (name, value) in args {
+            text = text.replace(&format!("{{{}}}", name), value);
+        }
+        text
+    }
+
+    /// Get the singular or plural form of a string with a `{count}` placeholder
+    pub fn plural(&self, count: u64, one: &'static str, other: &'static str) -> String {
+        let singular = match self.locale() {
+            // No grammatical number
+            "ja" => false,
+            // Zero is singular
+            "fr" => count <= 1,
+            _ => count == 1,
+        };
+
+        self.format(
+            if singular { one } else { other },
+            &[("count", &count.to_string())],
+        )
+    }
+}
+
+/// Check that a string went through the pseudo-locale
+///
+/// Strings built entirely from one message are bracketed; anything else was
+/// hard-coded or assembled from unlocalized pieces.
+pub fn is_pseudo_localized(text: &str) -> bool {
+    text.starts_with('[') && text.ends_with(']')
+}
+
+fn lookup(catalog: &Catalog, key: &str) -> Option<&'static str> {
+    catalog
+        .messages
+        .iter()
+        .find(|(known, _)| *known == key)
+        .map(|(_, text)| *text)
+}
+
+/// Pseudo-localize a catalog string, once per string
+///
+/// The catalogs are finite, so leaking each rewritten string is bounded.
+fn pseudo_static(text: &'static str) -> &'static str {
+    static PSEUDO: OnceLock<Mutex<HashMap<&'static str, &'static str>>> = OnceLock::new();
+
+    let mut cache = PSEUDO
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|p| p.into_inner());
+    cache
+        .entry(text)
+        .or_insert_with(|| Box::leak(pseudo_localize(text).into_boxed_str()))
+}
+
+/// Rewrite a string with accented letters inside brackets, keeping placeholders
+pub fn pseudo_localize(text: &str) -> String {
+    let mut pseudo = String::with_capacity(text.len() * 2 + 2);
+    let mut in_placeholder = false;
+
+    pseudo.push('[');
+    for c in text.chars() {
+        match c {
+            '{' => in_placeholder = true,
+            '}' => in_placeholder = false,
+            _ => {}
+        }
+        pseudo.push(if in_placeholder { c } else { accented(c) });
+    }
+    pseudo.push(']');
+    pseudo
+}
+
+fn accented(c: char) -> char {
+    match c {
+        'a' => 'á',
+        'c' => 'ç',
+        'e' => 'é',
+        'g' => 'ĝ',
+        'h' => 'ĥ',
+        'i' => 'í',
+        'l' => 'ļ',
+        'm' => 'ɱ',
+        'n' => 'ñ',
+        'o' => 'ó',
+        'r' => 'ŕ',
+        's' => 'š',
+        't' => 'ţ',
+        'u' => 'ú',
+        'w' => 'ŵ',
+        'y' => 'ý',
+        'z' => 'ž',
+        'A' => 'Å',
+        'C' => 'Ç',
+        'E' => 'É',
+        'I' => 'Î',
+        'N' => 'Ñ',
+        'O' => 'Ö',
+        'R' => 'Ŕ',
+        'S' => 'Š',
+        'T' => 'Ţ',
+        'U' => 'Û',
+        'W' => 'Ŵ',
+        c => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Placeholders of a string, sorted
+    fn placeholders(text: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = text
+            .split('{')
+            .skip(1)
+            .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_catalogs_match_english() {
+        for catalog in &CATALOGS[1..] {
+            for (key, text) in catalog.messages {
+                let english = lookup(&CATALOGS[0], key).unwrap_or_else(|| {
+                    panic!("{}: '{}' is not an English key", catalog.locale, key)
+                });
+                assert_eq!(
+                    placeholders(text),
+                    placeholders(english),
+                    "{}: placeholders of '{}'",
+                    catalog.locale,
+                    key
+                );
+            }
+        }
+
+        let mut keys: Vec<&str> = en::MESSAGES.iter().map(|(key, _)| *key).collect();
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), en::MESSAGES.len(), "duplicate English keys");
+    }
+
+    #[test]
+    fn test_locale_fallback() {
+        assert_eq!(Messages::for_locale("de_AT.UTF-8").locale(), "de");
+        assert_eq!(Messages::for_locale("FR-ca").locale(), "fr");
+        assert_eq!(Messages::for_locale("pt-BR").locale(), "en");
+        assert_eq!(Messages::for_locale("").locale(), "en");
+
+        let german = Messages::for_locale("de");
+        assert_eq!(german.text("timer.name"), "Timer");
+        assert_eq!(german.text("no.such.key"), "no.such.key");
+        assert_eq!(
+            german.plural(1, "macros.steps_one", "macros.steps_other"),
+            "1 Schritt"
+        );
+        assert_eq!(
+            Messages::for_locale("fr").plural(0, "macros.steps_one", "macros.steps_other"),
+            "0 étape"
+        );
+
+        let pseudo = Messages::for_locale(PSEUDO_LOCALE);
+        let text = pseudo.format("windows.moved", &[("number", "2")]);
+        assert_eq!(text, "[Móvéd ţó ŵóŕkšpáçé 2]");
+        assert!(is_pseudo_localized(&text));
+        assert!(std::ptr::eq(
+            pseudo.text("timer.name"),
+            pseudo.text("timer.name")
+        ));
+        assert!(!is_pseudo_localized(Messages::default().text("timer.name")));
+    }
+}
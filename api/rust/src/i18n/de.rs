@@ -0,0 +1,206 @@
+/// German
+pub(super) const MESSAGES: &[(&str, &str)] = &[
+    // Colors
+    ("color.name", "Farben"),
+    ("color.description", "Farbumrechner mit Farbfeldern und zuletzt verwendeten Farben"),
+    ("color.copy_as", "{value} kopieren"),
+    ("color.copied", "{value} kopiert"),
+    ("color.recent", "Zuletzt verwendet"),
+    // Dictionary
+    ("dictionary.name", "Wörterbuch"),
+    (
+        "dictionary.description",
+        "Definitionen und Synonyme aus einer Offline-Wortliste",
+    ),
+    ("dictionary.noun", "Substantiv"),
+    ("dictionary.verb", "Verb"),
+    ("dictionary.adjective", "Adjektiv"),
+    ("dictionary.adverb", "Adverb"),
+    ("dictionary.synonyms", "Synonyme"),
+    ("dictionary.synonym_of", "Synonym für {word}"),
+    (
+        "dictionary.not_found",
+        "Keine Definition für „{word}“ gefunden",
+    ),
+    ("dictionary.look_up", "{word} nachschlagen"),
+    ("dictionary.copy_synonyms", "Synonyme kopieren"),
+    ("dictionary.copy_entry", "Alle Definitionen kopieren"),
+    ("dictionary.copied", "In die Zwischenablage kopiert"),
+    // Finance
+    ("finance.name", "Finanzen"),
+    ("finance.description", "Währungsumrechnung und Aktienkurse"),
+    (
+        "finance.cannot_convert",
+        "{from} kann nicht in {to} umgerechnet werden",
+    ),
+    ("finance.unknown_currency", "Unbekannte Währung"),
+    ("finance.rates_of", "Kurse vom {date}"),
+    ("finance.last_price", "Letzter Kurs"),
+    ("finance.last_price_of", "Letzter Kurs vom {date}"),
+    ("finance.stale_offline", "offline, Stand vor {age}"),
+    (
+        "finance.stale_updating",
+        "wird aktualisiert, Stand vor {age}",
+    ),
+    ("finance.age_moments", "wenigen Augenblicken"),
+    ("finance.age_minutes", "{count} Min."),
+    ("finance.age_hours", "{count} Std."),
+    ("finance.age_days", "{count} Tagen"),
+    (
+        "finance.rates_unavailable",
+        "Wechselkurse offline nicht verfügbar",
+    ),
+    (
+        "finance.quote_unavailable",
+        "Kurs von {symbol} offline nicht verfügbar",
+    ),
+    (
+        "finance.nothing_cached",
+        "Noch nichts zwischengespeichert: {error}",
+    ),
+    ("finance.currencies", "Währungen"),
+    ("finance.rates_provider", "Kursanbieter"),
+    ("finance.custom_url", "Eigene URL"),
+    ("finance.rates_url", "Kurs-URL"),
+    (
+        "finance.rates_url_help",
+        "Muss wie frankfurter.app/latest antworten",
+    ),
+    (
+        "finance.rates_max_age",
+        "Kurse aktualisieren nach (Stunden)",
+    ),
+    ("finance.refresh_now", "Jetzt aktualisieren"),
+    ("finance.stocks", "Aktien"),
+    ("finance.quote_provider", "Anbieter für Aktienkurse"),
+    ("finance.quote_url", "Aktienkurs-URL"),
+    (
+        "finance.quote_url_help",
+        "{symbol} wird durch das Tickersymbol ersetzt",
+    ),
+    ("finance.watchlist", "Beobachtungsliste"),
+    (
+        "finance.watchlist_help",
+        "Tickersymbole, die ohne $ beantwortet werden, z. B. AAPL, MSFT",
+    ),
+    (
+        "finance.quote_max_age",
+        "Aktienkurse aktualisieren nach (Minuten)",
+    ),
+    ("finance.rates_updated", "Kurse aktualisiert"),
+    ("finance.rates_updated_on", "Kurse aktualisiert ({date})"),
+    // Macros
+    ("macros.name", "Makros"),
+    (
+        "macros.description",
+        "Selbst definierte Abfolgen von Aktionen ausführen",
+    ),
+    ("macros.badge", "Makro"),
+    ("macros.steps_one", "{count} Schritt"),
+    ("macros.steps_other", "{count} Schritte"),
+    ("macros.step_open_url", "{url} öffnen"),
+    ("macros.step_open_file", "Datei {path} öffnen"),
+    ("macros.step_run", "`{command}` ausführen"),
+    ("macros.step_copy", "„{text}“ kopieren"),
+    ("macros.step_type", "„{text}“ tippen"),
+    ("macros.step_wait_seconds", "{count} s warten"),
+    ("macros.step_wait_ms", "{count} ms warten"),
+    ("macros.continues_on_error", "läuft bei Fehlern weiter"),
+    ("macros.no_steps", "Dieses Makro hat keine Schritte."),
+    (
+        "macros.stopped",
+        "Makro „{name}“ bei Schritt {step} abgebrochen: {error}",
+    ),
+    (
+        "macros.partly_failed",
+        "Makro „{name}“ beendet, aber {failed} von {total} Schritten sind fehlgeschlagen (zuerst Schritt {step}, {error})",
+    ),
+    // Snippets
+    ("snippets.name", "Textbausteine"),
+    (
+        "snippets.description",
+        "Gespeicherte Textbausteine mit Platzhaltern einfügen",
+    ),
+    ("snippets.badge", "Textbaustein"),
+    (
+        "snippets.copied",
+        "Textbaustein in die Zwischenablage kopiert",
+    ),
+    // System
+    ("system.name", "System"),
+    ("system.description", "Systemeinstellungen und -steuerung: WLAN, Bluetooth, Dunkelmodus, Lautstärke, Sperren und Ruhezustand"),
+    ("system.settings", "In den Systemeinstellungen öffnen"),
+    ("system.control", "Systemsteuerung"),
+    ("system.confirm", "Zum Bestätigen Eingabetaste drücken"),
+    ("system.confirm_title", "{command}?"),
+    ("system.display_settings", "Anzeigeeinstellungen"),
+    ("system.sound_settings", "Toneinstellungen"),
+    ("system.network_settings", "Netzwerkeinstellungen"),
+    ("system.bluetooth_settings", "Bluetooth-Einstellungen"),
+    ("system.notification_settings", "Mitteilungseinstellungen"),
+    ("system.privacy_settings", "Datenschutzeinstellungen"),
+    ("system.keyboard_settings", "Tastatureinstellungen"),
+    ("system.power_settings", "Energieeinstellungen"),
+    ("system.toggle_wifi", "WLAN ein/aus"),
+    ("system.toggle_bluetooth", "Bluetooth ein/aus"),
+    ("system.toggle_dark_mode", "Dunkelmodus ein/aus"),
+    ("system.volume_up", "Lauter"),
+    ("system.volume_down", "Leiser"),
+    ("system.toggle_mute", "Stummschalten ein/aus"),
+    ("system.lock", "Bildschirm sperren"),
+    ("system.sleep", "Ruhezustand"),
+    ("system.empty_trash", "Papierkorb leeren"),
+    // Timers
+    ("timer.name", "Timer"),
+    (
+        "timer.description",
+        "Timer und Erinnerungen mit Benachrichtigungen",
+    ),
+    ("timer.timer", "Timer"),
+    ("timer.reminder", "Erinnerung"),
+    ("timer.ringing", "Klingelt · Eingabe zum Beenden"),
+    ("timer.due_at", "Um {time} · Eingabe zum Abbrechen"),
+    (
+        "timer.due_in",
+        "Klingelt in {duration} · Eingabe zum Abbrechen",
+    ),
+    (
+        "timer.start_in",
+        "Timer starten, der in {duration} klingelt",
+    ),
+    (
+        "timer.start_in_labeled",
+        "Timer für {label} starten, der in {duration} klingelt",
+    ),
+    ("timer.start_at", "Timer starten, der um {time} klingelt"),
+    (
+        "timer.start_at_labeled",
+        "Timer für {label} starten, der um {time} klingelt",
+    ),
+    ("timer.remind_in", "Erinnere mich in {duration}"),
+    (
+        "timer.remind_in_labeled",
+        "Erinnere mich in {duration}: {label}",
+    ),
+    ("timer.remind_at", "Erinnere mich um {time}"),
+    (
+        "timer.remind_at_labeled",
+        "Erinnere mich um {time}: {label}",
+    ),
+    ("timer.done", "Timer abgelaufen"),
+    ("timer.snooze", "5 Min. schlummern"),
+    ("timer.dismiss", "Beenden"),
+    ("timer.cancelled", "Timer abgebrochen"),
+    // Windows
+    ("windows.name", "Fenster"),
+    (
+        "windows.description",
+        "Zwischen offenen Fenstern wechseln, sie schließen und verschieben",
+    ),
+    ("windows.workspace", "Arbeitsbereich {number}"),
+    ("windows.minimized", "Minimiert"),
+    ("windows.close", "Fenster schließen"),
+    ("windows.move_to", "In Arbeitsbereich {number} verschieben"),
+    ("windows.closed", "Fenster geschlossen"),
+    ("windows.moved", "In Arbeitsbereich {number} verschoben"),
+];
@@ -0,0 +1,182 @@
+/// English, the source strings of the built-in plugins
+pub(super) const MESSAGES: &[(&str, &str)] = &[
+    // Colors
+    ("color.name", "Colors"),
+    (
+        "color.description",
+        "Color converter with swatches and recent colors",
+    ),
+    ("color.copy_as", "Copy {value}"),
+    ("color.copied", "Copied {value}"),
+    ("color.recent", "Recent"),
+    // Dictionary
+    ("dictionary.name", "Dictionary"),
+    (
+        "dictionary.description",
+        "Definitions and synonyms from an offline word list",
+    ),
+    ("dictionary.noun", "noun"),
+    ("dictionary.verb", "verb"),
+    ("dictionary.adjective", "adjective"),
+    ("dictionary.adverb", "adverb"),
+    ("dictionary.synonyms", "Synonyms"),
+    ("dictionary.synonym_of", "Synonym of {word}"),
+    ("dictionary.not_found", "No definition found for \"{word}\""),
+    ("dictionary.look_up", "Look up {word}"),
+    ("dictionary.copy_synonyms", "Copy synonyms"),
+    ("dictionary.copy_entry", "Copy all definitions"),
+    ("dictionary.copied", "Copied to clipboard"),
+    // Finance
+    ("finance.name", "Finance"),
+    (
+        "finance.description",
+        "Currency conversions and stock quotes",
+    ),
+    ("finance.cannot_convert", "Can't convert {from} to {to}"),
+    ("finance.unknown_currency", "Unknown currency"),
+    ("finance.rates_of", "rates of {date}"),
+    ("finance.last_price", "Last price"),
+    ("finance.last_price_of", "Last price of {date}"),
+    ("finance.stale_offline", "offline, from {age} ago"),
+    ("finance.stale_updating", "updating, from {age} ago"),
+    ("finance.age_moments", "moments"),
+    ("finance.age_minutes", "{count} min"),
+    ("finance.age_hours", "{count} h"),
+    ("finance.age_days", "{count} days"),
+    (
+        "finance.rates_unavailable",
+        "Currency rates unavailable offline",
+    ),
+    (
+        "finance.quote_unavailable",
+        "Quote of {symbol} unavailable offline",
+    ),
+    ("finance.nothing_cached", "Nothing cached yet: {error}"),
+    ("finance.currencies", "Currencies"),
+    ("finance.rates_provider", "Rates provider"),
+    ("finance.custom_url", "Custom URL"),
+    ("finance.rates_url", "Rates URL"),
+    (
+        "finance.rates_url_help",
+        "Must answer like frankfurter.app/latest",
+    ),
+    ("finance.rates_max_age", "Refresh rates after (hours)"),
+    ("finance.refresh_now", "Refresh now"),
+    ("finance.stocks", "Stocks"),
+    ("finance.quote_provider", "Quotes provider"),
+    ("finance.quote_url", "Quote URL"),
+    (
+        "finance.quote_url_help",
+        "{symbol} is replaced by the ticker",
+    ),
+    ("finance.watchlist", "Watchlist"),
+    (
+        "finance.watchlist_help",
+        "Tickers answered without $, e.g. AAPL, MSFT",
+    ),
+    ("finance.quote_max_age", "Refresh quotes after (minutes)"),
+    ("finance.rates_updated", "Rates updated"),
+    ("finance.rates_updated_on", "Rates updated ({date})"),
+    // Macros
+    ("macros.name", "Macros"),
+    (
+        "macros.description",
+        "Run user-defined sequences of actions",
+    ),
+    ("macros.badge", "Macro"),
+    ("macros.steps_one", "{count} step"),
+    ("macros.steps_other", "{count} steps"),
+    ("macros.step_open_url", "Open {url}"),
+    ("macros.step_open_file", "Open file {path}"),
+    ("macros.step_run", "Run `{command}`"),
+    ("macros.step_copy", "Copy \"{text}\""),
+    ("macros.step_type", "Type \"{text}\""),
+    ("macros.step_wait_seconds", "Wait {count} s"),
+    ("macros.step_wait_ms", "Wait {count} ms"),
+    ("macros.continues_on_error", "continues on error"),
+    ("macros.no_steps", "This macro has no steps."),
+    (
+        "macros.stopped",
+        "Macro '{name}' stopped at step {step}: {error}",
+    ),
+    (
+        "macros.partly_failed",
+        "Macro '{name}' finished, but {failed} of {total} steps failed (first: step {step}, {error})",
+    ),
+    // Snippets
+    ("snippets.name", "Snippets"),
+    (
+        "snippets.description",
+        "Insert saved text snippets with placeholders",
+    ),
+    ("snippets.badge", "Snippet"),
+    ("snippets.copied", "Snippet copied to clipboard"),
+    // System
+    ("system.name", "System"),
+    ("system.description", "System settings and controls: Wi-Fi, Bluetooth, dark mode, volume, lock and sleep"),
+    ("system.settings", "Open in system settings"),
+    ("system.control", "System control"),
+    ("system.confirm", "Press Enter to confirm"),
+    ("system.confirm_title", "{command}?"),
+    ("system.display_settings", "Display Settings"),
+    ("system.sound_settings", "Sound Settings"),
+    ("system.network_settings", "Network Settings"),
+    ("system.bluetooth_settings", "Bluetooth Settings"),
+    ("system.notification_settings", "Notification Settings"),
+    ("system.privacy_settings", "Privacy Settings"),
+    ("system.keyboard_settings", "Keyboard Settings"),
+    ("system.power_settings", "Power Settings"),
+    ("system.toggle_wifi", "Toggle Wi-Fi"),
+    ("system.toggle_bluetooth", "Toggle Bluetooth"),
+    ("system.toggle_dark_mode", "Toggle Dark Mode"),
+    ("system.volume_up", "Volume Up"),
+    ("system.volume_down", "Volume Down"),
+    ("system.toggle_mute", "Toggle Mute"),
+    ("system.lock", "Lock Screen"),
+    ("system.sleep", "Sleep"),
+    ("system.empty_trash", "Empty Trash"),
+    // Timers
+    ("timer.name", "Timers"),
+    (
+        "timer.description",
+        "Timers and reminders with notifications",
+    ),
+    ("timer.timer", "Timer"),
+    ("timer.reminder", "Reminder"),
+    ("timer.ringing", "Ringing · Enter to dismiss"),
+    ("timer.due_at", "At {time} · Enter to cancel"),
+    ("timer.due_in", "Rings in {duration} · Enter to cancel"),
+    ("timer.start_in", "Start a timer that rings in {duration}"),
+    (
+        "timer.start_in_labeled",
+        "Start a timer for {label} that rings in {duration}",
+    ),
+    ("timer.start_at", "Start a timer that rings at {time}"),
+    (
+        "timer.start_at_labeled",
+        "Start a timer for {label} that rings at {time}",
+    ),
+    ("timer.remind_in", "Remind me in {duration}"),
+    (
+        "timer.remind_in_labeled",
+        "Remind me in {duration}: {label}",
+    ),
+    ("timer.remind_at", "Remind me at {time}"),
+    ("timer.remind_at_labeled", "Remind me at {time}: {label}"),
+    ("timer.done", "Timer done"),
+    ("timer.snooze", "Snooze 5 min"),
+    ("timer.dismiss", "Dismiss"),
+    ("timer.cancelled", "Timer cancelled"),
+    // Windows
+    ("windows.name", "Windows"),
+    (
+        "windows.description",
+        "Switch between, close and move open windows",
+    ),
+    ("windows.workspace", "Workspace {number}"),
+    ("windows.minimized", "Minimized"),
+    ("windows.close", "Close window"),
+    ("windows.move_to", "Move to workspace {number}"),
+    ("windows.closed", "Window closed"),
+    ("windows.moved", "Moved to workspace {number}"),
+];
@@ -0,0 +1,203 @@
+/// Spanish
+pub(super) const MESSAGES: &[(&str, &str)] = &[
+    // Colors
+    ("color.name", "Colores"),
+    ("color.description", "Conversor de colores con muestras y colores recientes"),
+    ("color.copy_as", "Copiar {value}"),
+    ("color.copied", "{value} copiado"),
+    ("color.recent", "Reciente"),
+    // Dictionary
+    ("dictionary.name", "Diccionario"),
+    (
+        "dictionary.description",
+        "Definiciones y sinónimos de una lista de palabras sin conexión",
+    ),
+    ("dictionary.noun", "sustantivo"),
+    ("dictionary.verb", "verbo"),
+    ("dictionary.adjective", "adjetivo"),
+    ("dictionary.adverb", "adverbio"),
+    ("dictionary.synonyms", "Sinónimos"),
+    ("dictionary.synonym_of", "Sinónimo de {word}"),
+    (
+        "dictionary.not_found",
+        "No se encontró ninguna definición de «{word}»",
+    ),
+    ("dictionary.look_up", "Buscar {word}"),
+    ("dictionary.copy_synonyms", "Copiar sinónimos"),
+    ("dictionary.copy_entry", "Copiar todas las definiciones"),
+    ("dictionary.copied", "Copiado al portapapeles"),
+    // Finance
+    ("finance.name", "Finanzas"),
+    (
+        "finance.description",
+        "Conversión de divisas y cotizaciones bursátiles",
+    ),
+    (
+        "finance.cannot_convert",
+        "No se puede convertir {from} a {to}",
+    ),
+    ("finance.unknown_currency", "Divisa desconocida"),
+    ("finance.rates_of", "tipos del {date}"),
+    ("finance.last_price", "Último precio"),
+    ("finance.last_price_of", "Último precio del {date}"),
+    ("finance.stale_offline", "sin conexión, de hace {age}"),
+    ("finance.stale_updating", "actualizando, de hace {age}"),
+    ("finance.age_moments", "unos instantes"),
+    ("finance.age_minutes", "{count} min"),
+    ("finance.age_hours", "{count} h"),
+    ("finance.age_days", "{count} días"),
+    (
+        "finance.rates_unavailable",
+        "Tipos de cambio no disponibles sin conexión",
+    ),
+    (
+        "finance.quote_unavailable",
+        "Cotización de {symbol} no disponible sin conexión",
+    ),
+    (
+        "finance.nothing_cached",
+        "Aún no hay nada en caché: {error}",
+    ),
+    ("finance.currencies", "Divisas"),
+    ("finance.rates_provider", "Proveedor de tipos"),
+    ("finance.custom_url", "URL personalizada"),
+    ("finance.rates_url", "URL de tipos"),
+    (
+        "finance.rates_url_help",
+        "Debe responder como frankfurter.app/latest",
+    ),
+    (
+        "finance.rates_max_age",
+        "Actualizar tipos después de (horas)",
+    ),
+    ("finance.refresh_now", "Actualizar ahora"),
+    ("finance.stocks", "Acciones"),
+    ("finance.quote_provider", "Proveedor de cotizaciones"),
+    ("finance.quote_url", "URL de cotizaciones"),
+    (
+        "finance.quote_url_help",
+        "{symbol} se sustituye por el símbolo bursátil",
+    ),
+    ("finance.watchlist", "Lista de seguimiento"),
+    (
+        "finance.watchlist_help",
+        "Símbolos que se responden sin $, p. ej. AAPL, MSFT",
+    ),
+    (
+        "finance.quote_max_age",
+        "Actualizar cotizaciones después de (minutos)",
+    ),
+    ("finance.rates_updated", "Tipos actualizados"),
+    ("finance.rates_updated_on", "Tipos actualizados ({date})"),
+    // Macros
+    ("macros.name", "Macros"),
+    (
+        "macros.description",
+        "Ejecutar secuencias de acciones definidas por el usuario",
+    ),
+    ("macros.badge", "Macro"),
+    ("macros.steps_one", "{count} paso"),
+    ("macros.steps_other", "{count} pasos"),
+    ("macros.step_open_url", "Abrir {url}"),
+    ("macros.step_open_file", "Abrir el archivo {path}"),
+    ("macros.step_run", "Ejecutar `{command}`"),
+    ("macros.step_copy", "Copiar «{text}»"),
+    ("macros.step_type", "Escribir «{text}»"),
+    ("macros.step_wait_seconds", "Esperar {count} s"),
+    ("macros.step_wait_ms", "Esperar {count} ms"),
+    ("macros.continues_on_error", "continúa si hay errores"),
+    ("macros.no_steps", "Esta macro no tiene pasos."),
+    (
+        "macros.stopped",
+        "La macro «{name}» se detuvo en el paso {step}: {error}",
+    ),
+    (
+        "macros.partly_failed",
+        "La macro «{name}» terminó, pero fallaron {failed} de {total} pasos (primero el paso {step}, {error})",
+    ),
+    // Snippets
+    ("snippets.name", "Fragmentos"),
+    (
+        "snippets.description",
+        "Insertar fragmentos de texto guardados con marcadores",
+    ),
+    ("snippets.badge", "Fragmento"),
+    ("snippets.copied", "Fragmento copiado al portapapeles"),
+    // System
+    ("system.name", "Sistema"),
+    ("system.description", "Ajustes y controles del sistema: Wi-Fi, Bluetooth, modo oscuro, volumen, bloqueo y suspensión"),
+    ("system.settings", "Abrir en los ajustes del sistema"),
+    ("system.control", "Control del sistema"),
+    ("system.confirm", "Pulsa Intro para confirmar"),
+    ("system.confirm_title", "¿{command}?"),
+    ("system.display_settings", "Ajustes de pantalla"),
+    ("system.sound_settings", "Ajustes de sonido"),
+    ("system.network_settings", "Ajustes de red"),
+    ("system.bluetooth_settings", "Ajustes de Bluetooth"),
+    ("system.notification_settings", "Ajustes de notificaciones"),
+    ("system.privacy_settings", "Ajustes de privacidad"),
+    ("system.keyboard_settings", "Ajustes de teclado"),
+    ("system.power_settings", "Ajustes de energía"),
+    ("system.toggle_wifi", "Activar o desactivar Wi-Fi"),
+    ("system.toggle_bluetooth", "Activar o desactivar Bluetooth"),
+    ("system.toggle_dark_mode", "Activar o desactivar modo oscuro"),
+    ("system.volume_up", "Subir volumen"),
+    ("system.volume_down", "Bajar volumen"),
+    ("system.toggle_mute", "Silenciar o activar sonido"),
+    ("system.lock", "Bloquear pantalla"),
+    ("system.sleep", "Suspender"),
+    ("system.empty_trash", "Vaciar papelera"),
+    // Timers
+    ("timer.name", "Temporizadores"),
+    (
+        "timer.description",
+        "Temporizadores y recordatorios con notificaciones",
+    ),
+    ("timer.timer", "Temporizador"),
+    ("timer.reminder", "Recordatorio"),
+    ("timer.ringing", "Sonando · Intro para descartar"),
+    ("timer.due_at", "A las {time} · Intro para cancelar"),
+    ("timer.due_in", "Suena en {duration} · Intro para cancelar"),
+    (
+        "timer.start_in",
+        "Iniciar un temporizador que suene en {duration}",
+    ),
+    (
+        "timer.start_in_labeled",
+        "Iniciar un temporizador para {label} que suene en {duration}",
+    ),
+    (
+        "timer.start_at",
+        "Iniciar un temporizador que suene a las {time}",
+    ),
+    (
+        "timer.start_at_labeled",
+        "Iniciar un temporizador para {label} que suene a las {time}",
+    ),
+    ("timer.remind_in", "Recuérdamelo en {duration}"),
+    (
+        "timer.remind_in_labeled",
+        "Recuérdame en {duration}: {label}",
+    ),
+    ("timer.remind_at", "Recuérdamelo a las {time}"),
+    (
+        "timer.remind_at_labeled",
+        "Recuérdame a las {time}: {label}",
+    ),
+    ("timer.done", "Temporizador terminado"),
+    ("timer.snooze", "Posponer 5 min"),
+    ("timer.dismiss", "Descartar"),
+    ("timer.cancelled", "Temporizador cancelado"),
+    // Windows
+    ("windows.name", "Ventanas"),
+    (
+        "windows.description",
+        "Cambiar entre ventanas abiertas, cerrarlas y moverlas",
+    ),
+    ("windows.workspace", "Escritorio {number}"),
+    ("windows.minimized", "Minimizada"),
+    ("windows.close", "Cerrar ventana"),
+    ("windows.move_to", "Mover al escritorio {number}"),
+    ("windows.closed", "Ventana cerrada"),
+    ("windows.moved", "Movida al escritorio {number}"),
+];
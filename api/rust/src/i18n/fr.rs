@@ -0,0 +1,200 @@
+/// French
+pub(super) const MESSAGES: &[(&str, &str)] = &[
+    // Colors
+    ("color.name", "Couleurs"),
+    ("color.description", "Convertisseur de couleurs avec aperçus et couleurs récentes"),
+    ("color.copy_as", "Copier {value}"),
+    ("color.copied", "{value} copié"),
+    ("color.recent", "Récente"),
+    // Dictionary
+    ("dictionary.name", "Dictionnaire"),
+    (
+        "dictionary.description",
+        "Définitions et synonymes d'une liste de mots hors ligne",
+    ),
+    ("dictionary.noun", "nom"),
+    ("dictionary.verb", "verbe"),
+    ("dictionary.adjective", "adjectif"),
+    ("dictionary.adverb", "adverbe"),
+    ("dictionary.synonyms", "Synonymes"),
+    ("dictionary.synonym_of", "Synonyme de {word}"),
+    (
+        "dictionary.not_found",
+        "Aucune définition trouvée pour « {word} »",
+    ),
+    ("dictionary.look_up", "Chercher {word}"),
+    ("dictionary.copy_synonyms", "Copier les synonymes"),
+    ("dictionary.copy_entry", "Copier toutes les définitions"),
+    ("dictionary.copied", "Copié dans le presse-papiers"),
+    // Finance
+    ("finance.name", "Finance"),
+    (
+        "finance.description",
+        "Conversions de devises et cours de bourse",
+    ),
+    (
+        "finance.cannot_convert",
+        "Impossible de convertir {from} en {to}",
+    ),
+    ("finance.unknown_currency", "Devise inconnue"),
+    ("finance.rates_of", "taux du {date}"),
+    ("finance.last_price", "Dernier cours"),
+    ("finance.last_price_of", "Dernier cours du {date}"),
+    ("finance.stale_offline", "hors ligne, il y a {age}"),
+    ("finance.stale_updating", "mise à jour, il y a {age}"),
+    ("finance.age_moments", "quelques instants"),
+    ("finance.age_minutes", "{count} min"),
+    ("finance.age_hours", "{count} h"),
+    ("finance.age_days", "{count} jours"),
+    (
+        "finance.rates_unavailable",
+        "Taux de change indisponibles hors ligne",
+    ),
+    (
+        "finance.quote_unavailable",
+        "Cours de {symbol} indisponible hors ligne",
+    ),
+    (
+        "finance.nothing_cached",
+        "Rien en cache pour l'instant : {error}",
+    ),
+    ("finance.currencies", "Devises"),
+    ("finance.rates_provider", "Fournisseur des taux"),
+    ("finance.custom_url", "URL personnalisée"),
+    ("finance.rates_url", "URL des taux"),
+    (
+        "finance.rates_url_help",
+        "Doit répondre comme frankfurter.app/latest",
+    ),
+    (
+        "finance.rates_max_age",
+        "Actualiser les taux après (heures)",
+    ),
+    ("finance.refresh_now", "Actualiser maintenant"),
+    ("finance.stocks", "Actions"),
+    ("finance.quote_provider", "Fournisseur des cours"),
+    ("finance.quote_url", "URL des cours"),
+    (
+        "finance.quote_url_help",
+        "{symbol} est remplacé par le symbole boursier",
+    ),
+    ("finance.watchlist", "Liste de suivi"),
+    (
+        "finance.watchlist_help",
+        "Symboles reconnus sans $, p. ex. AAPL, MSFT",
+    ),
+    (
+        "finance.quote_max_age",
+        "Actualiser les cours après (minutes)",
+    ),
+    ("finance.rates_updated", "Taux mis à jour"),
+    ("finance.rates_updated_on", "Taux mis à jour ({date})"),
+    // Macros
+    ("macros.name", "Macros"),
+    (
+        "macros.description",
+        "Exécuter des suites d'actions personnalisées",
+    ),
+    ("macros.badge", "Macro"),
+    ("macros.steps_one", "{count} étape"),
+    ("macros.steps_other", "{count} étapes"),
+    ("macros.step_open_url", "Ouvrir {url}"),
+    ("macros.step_open_file", "Ouvrir le fichier {path}"),
+    ("macros.step_run", "Exécuter `{command}`"),
+    ("macros.step_copy", "Copier « {text} »"),
+    ("macros.step_type", "Saisir « {text} »"),
+    ("macros.step_wait_seconds", "Attendre {count} s"),
+    ("macros.step_wait_ms", "Attendre {count} ms"),
+    ("macros.continues_on_error", "continue en cas d'erreur"),
+    ("macros.no_steps", "Cette macro n'a aucune étape."),
+    (
+        "macros.stopped",
+        "La macro « {name} » s'est arrêtée à l'étape {step} : {error}",
+    ),
+    (
+        "macros.partly_failed",
+        "La macro « {name} » est terminée, mais {failed} étapes sur {total} ont échoué (d'abord l'étape {step}, {error})",
+    ),
+    // Snippets
+    ("snippets.name", "Extraits"),
+    (
+        "snippets.description",
+        "Insérer des extraits de texte enregistrés avec des variables",
+    ),
+    ("snippets.badge", "Extrait"),
+    ("snippets.copied", "Extrait copié dans le presse-papiers"),
+    // System
+    ("system.name", "Système"),
+    ("system.description", "Réglages et commandes du système : Wi-Fi, Bluetooth, mode sombre, volume, verrouillage et veille"),
+    ("system.settings", "Ouvrir dans les réglages du système"),
+    ("system.control", "Commande du système"),
+    ("system.confirm", "Appuyez sur Entrée pour confirmer"),
+    ("system.confirm_title", "{command} ?"),
+    ("system.display_settings", "Réglages de l'écran"),
+    ("system.sound_settings", "Réglages du son"),
+    ("system.network_settings", "Réglages du réseau"),
+    ("system.bluetooth_settings", "Réglages Bluetooth"),
+    ("system.notification_settings", "Réglages des notifications"),
+    ("system.privacy_settings", "Réglages de confidentialité"),
+    ("system.keyboard_settings", "Réglages du clavier"),
+    ("system.power_settings", "Réglages d'énergie"),
+    ("system.toggle_wifi", "Activer ou désactiver le Wi-Fi"),
+    ("system.toggle_bluetooth", "Activer ou désactiver le Bluetooth"),
+    ("system.toggle_dark_mode", "Activer ou désactiver le mode sombre"),
+    ("system.volume_up", "Monter le volume"),
+    ("system.volume_down", "Baisser le volume"),
+    ("system.toggle_mute", "Couper ou rétablir le son"),
+    ("system.lock", "Verrouiller l'écran"),
+    ("system.sleep", "Suspendre"),
+    ("system.empty_trash", "Vider la corbeille"),
+    // Timers
+    ("timer.name", "Minuteurs"),
+    (
+        "timer.description",
+        "Minuteurs et rappels avec notifications",
+    ),
+    ("timer.timer", "Minuteur"),
+    ("timer.reminder", "Rappel"),
+    ("timer.ringing", "Sonne · Entrée pour arrêter"),
+    ("timer.due_at", "À {time} · Entrée pour annuler"),
+    (
+        "timer.due_in",
+        "Sonne dans {duration} · Entrée pour annuler",
+    ),
+    (
+        "timer.start_in",
+        "Lancer un minuteur qui sonne dans {duration}",
+    ),
+    (
+        "timer.start_in_labeled",
+        "Lancer un minuteur pour {label} qui sonne dans {duration}",
+    ),
+    ("timer.start_at", "Lancer un minuteur qui sonne à {time}"),
+    (
+        "timer.start_at_labeled",
+        "Lancer un minuteur pour {label} qui sonne à {time}",
+    ),
+    ("timer.remind_in", "Me le rappeler dans {duration}"),
+    (
+        "timer.remind_in_labeled",
+        "Me rappeler dans {duration} : {label}",
+    ),
+    ("timer.remind_at", "Me le rappeler à {time}"),
+    ("timer.remind_at_labeled", "Me rappeler à {time} : {label}"),
+    ("timer.done", "Minuteur terminé"),
+    ("timer.snooze", "Répéter dans 5 min"),
+    ("timer.dismiss", "Arrêter"),
+    ("timer.cancelled", "Minuteur annulé"),
+    // Windows
+    ("windows.name", "Fenêtres"),
+    (
+        "windows.description",
+        "Passer d'une fenêtre ouverte à l'autre, les fermer et les déplacer",
+    ),
+    ("windows.workspace", "Bureau {number}"),
+    ("windows.minimized", "Réduite"),
+    ("windows.close", "Fermer la fenêtre"),
+    ("windows.move_to", "Déplacer vers le bureau {number}"),
+    ("windows.closed", "Fenêtre fermée"),
+    ("windows.moved", "Déplacée vers le bureau {number}"),
+];
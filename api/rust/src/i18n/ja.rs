@@ -0,0 +1,173 @@
+/// Japanese
+pub(super) const MESSAGES: &[(&str, &str)] = &[
+    // Colors
+    ("color.name", "カラー"),
+    ("color.description", "色見本と最近の色を備えたカラー変換"),
+    ("color.copy_as", "{value} をコピー"),
+    ("color.copied", "{value} をコピーしました"),
+    ("color.recent", "最近"),
+    // Dictionary
+    ("dictionary.name", "辞書"),
+    (
+        "dictionary.description",
+        "オフライン単語リストによる定義と類義語",
+    ),
+    ("dictionary.noun", "名詞"),
+    ("dictionary.verb", "動詞"),
+    ("dictionary.adjective", "形容詞"),
+    ("dictionary.adverb", "副詞"),
+    ("dictionary.synonyms", "類義語"),
+    ("dictionary.synonym_of", "{word} の類義語"),
+    ("dictionary.not_found", "「{word}」の定義が見つかりません"),
+    ("dictionary.look_up", "{word} を調べる"),
+    ("dictionary.copy_synonyms", "類義語をコピー"),
+    ("dictionary.copy_entry", "すべての定義をコピー"),
+    ("dictionary.copied", "クリップボードにコピーしました"),
+    // Finance
+    ("finance.name", "ファイナンス"),
+    ("finance.description", "通貨換算と株価"),
+    ("finance.cannot_convert", "{from} を {to} に換算できません"),
+    ("finance.unknown_currency", "不明な通貨"),
+    ("finance.rates_of", "{date} のレート"),
+    ("finance.last_price", "最終価格"),
+    ("finance.last_price_of", "{date} の最終価格"),
+    ("finance.stale_offline", "オフライン、{age}前のデータ"),
+    ("finance.stale_updating", "更新中、{age}前のデータ"),
+    ("finance.age_moments", "少し"),
+    ("finance.age_minutes", "{count} 分"),
+    ("finance.age_hours", "{count} 時間"),
+    ("finance.age_days", "{count} 日"),
+    (
+        "finance.rates_unavailable",
+        "オフラインのため為替レートを取得できません",
+    ),
+    (
+        "finance.quote_unavailable",
+        "オフラインのため {symbol} の株価を取得できません",
+    ),
+    ("finance.nothing_cached", "キャッシュがありません: {error}"),
+    ("finance.currencies", "通貨"),
+    ("finance.rates_provider", "レートの提供元"),
+    ("finance.custom_url", "カスタム URL"),
+    ("finance.rates_url", "レートの URL"),
+    (
+        "finance.rates_url_help",
+        "frankfurter.app/latest と同じ形式で応答する必要があります",
+    ),
+    ("finance.rates_max_age", "レートの更新間隔（時間）"),
+    ("finance.refresh_now", "今すぐ更新"),
+    ("finance.stocks", "株式"),
+    ("finance.quote_provider", "株価の提供元"),
+    ("finance.quote_url", "株価の URL"),
+    (
+        "finance.quote_url_help",
+        "{symbol} はティッカーに置き換えられます",
+    ),
+    ("finance.watchlist", "ウォッチリスト"),
+    (
+        "finance.watchlist_help",
+        "$ なしで応答するティッカー（例: AAPL, MSFT）",
+    ),
+    ("finance.quote_max_age", "株価の更新間隔（分）"),
+    ("finance.rates_updated", "レートを更新しました"),
+    ("finance.rates_updated_on", "レートを更新しました（{date}）"),
+    // Macros
+    ("macros.name", "マクロ"),
+    ("macros.description", "ユーザー定義の一連の操作を実行"),
+    ("macros.badge", "マクロ"),
+    ("macros.steps_one", "{count} ステップ"),
+    ("macros.steps_other", "{count} ステップ"),
+    ("macros.step_open_url", "{url} を開く"),
+    ("macros.step_open_file", "ファイル {path} を開く"),
+    ("macros.step_run", "`{command}` を実行"),
+    ("macros.step_copy", "「{text}」をコピー"),
+    ("macros.step_type", "「{text}」を入力"),
+    ("macros.step_wait_seconds", "{count} 秒待つ"),
+    ("macros.step_wait_ms", "{count} ミリ秒待つ"),
+    ("macros.continues_on_error", "エラー時も続行"),
+    ("macros.no_steps", "このマクロにはステップがありません。"),
+    (
+        "macros.stopped",
+        "マクロ「{name}」はステップ {step} で停止しました: {error}",
+    ),
+    (
+        "macros.partly_failed",
+        "マクロ「{name}」は完了しましたが、{total} ステップ中 {failed} ステップが失敗しました（最初はステップ {step}: {error}）",
+    ),
+    // Snippets
+    ("snippets.name", "スニペット"),
+    (
+        "snippets.description",
+        "保存したテキストスニペットをプレースホルダー付きで挿入",
+    ),
+    ("snippets.badge", "スニペット"),
+    (
+        "snippets.copied",
+        "スニペットをクリップボードにコピーしました",
+    ),
+    // System
+    ("system.name", "システム"),
+    ("system.description", "システム設定と操作: Wi-Fi、Bluetooth、ダークモード、音量、ロック、スリープ"),
+    ("system.settings", "システム設定で開く"),
+    ("system.control", "システム操作"),
+    ("system.confirm", "Enter キーで確定"),
+    ("system.confirm_title", "{command}?"),
+    ("system.display_settings", "ディスプレイ設定"),
+    ("system.sound_settings", "サウンド設定"),
+    ("system.network_settings", "ネットワーク設定"),
+    ("system.bluetooth_settings", "Bluetooth 設定"),
+    ("system.notification_settings", "通知設定"),
+    ("system.privacy_settings", "プライバシー設定"),
+    ("system.keyboard_settings", "キーボード設定"),
+    ("system.power_settings", "電源設定"),
+    ("system.toggle_wifi", "Wi-Fi のオン/オフ"),
+    ("system.toggle_bluetooth", "Bluetooth のオン/オフ"),
+    ("system.toggle_dark_mode", "ダークモードのオン/オフ"),
+    ("system.volume_up", "音量を上げる"),
+    ("system.volume_down", "音量を下げる"),
+    ("system.toggle_mute", "ミュートのオン/オフ"),
+    ("system.lock", "画面をロック"),
+    ("system.sleep", "スリープ"),
+    ("system.empty_trash", "ゴミ箱を空にする"),
+    // Timers
+    ("timer.name", "タイマー"),
+    ("timer.description", "通知付きのタイマーとリマインダー"),
+    ("timer.timer", "タイマー"),
+    ("timer.reminder", "リマインダー"),
+    ("timer.ringing", "鳴っています · Enter で停止"),
+    ("timer.due_at", "{time} · Enter でキャンセル"),
+    ("timer.due_in", "あと {duration} · Enter でキャンセル"),
+    ("timer.start_in", "{duration} 後に鳴るタイマーを開始"),
+    (
+        "timer.start_in_labeled",
+        "{duration} 後に鳴る「{label}」のタイマーを開始",
+    ),
+    ("timer.start_at", "{time} に鳴るタイマーを開始"),
+    (
+        "timer.start_at_labeled",
+        "{time} に鳴る「{label}」のタイマーを開始",
+    ),
+    ("timer.remind_in", "{duration} 後にリマインド"),
+    (
+        "timer.remind_in_labeled",
+        "{duration} 後にリマインド: {label}",
+    ),
+    ("timer.remind_at", "{time} にリマインド"),
+    ("timer.remind_at_labeled", "{time} にリマインド: {label}"),
+    ("timer.done", "タイマー終了"),
+    ("timer.snooze", "5 分後に再通知"),
+    ("timer.dismiss", "停止"),
+    ("timer.cancelled", "タイマーをキャンセルしました"),
+    // Windows
+    ("windows.name", "ウィンドウ"),
+    (
+        "windows.description",
+        "開いているウィンドウの切り替え、終了、移動",
+    ),
+    ("windows.workspace", "ワークスペース {number}"),
+    ("windows.minimized", "最小化"),
+    ("windows.close", "ウィンドウを閉じる"),
+    ("windows.move_to", "ワークスペース {number} に移動"),
+    ("windows.closed", "ウィンドウを閉じました"),
+    ("windows.moved", "ワークスペース {number} に移動しました"),
+];
@@ -0,0 +1,196 @@
+/// Stable plugin identity across renames
+///
+/// Plugin IDs are chosen by authors and can change between releases, while
+/// everything the launcher keeps for a plugin (data, cache and config
+/// directories, bundle memberships, enabled state) is keyed by ID. Manifests
+/// therefore carry a `uuid` that never changes. The host remembers which ID
+/// each UUID was last installed under in `plugin-identities.json`; when a
+/// package shows up with a known UUID under a new ID, the plugin's state is
+/// relabeled to the new ID before it loads (see
+/// `VoltPluginAPI::migrate_plugin_identity` and `PluginRegistry::rename_plugin`).
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// File mapping plugin UUIDs to their IDs, in the app data directory
+pub const IDENTITY_FILE: &str = "plugin-identities.json";
+
+/// A plugin whose ID changed since it was last installed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginRename {
+    /// Stable identifier of the plugin
+    pub uuid: String,
+    /// ID the plugin's state was kept under
+    pub from: String,
+    /// ID the plugin now uses
+    pub to: String,
+}
+
+/// The ID each known plugin UUID was last installed under
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginIdentities {
+    ids: BTreeMap<String, String>,
+}
+
+impl PluginIdentities {
+    /// Load the identities from disk, or none if the file doesn't exist
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read plugin identities: {}", e))?;
+
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse plugin identities: {}", e))
+    }
+
+    /// Save the identities to disk
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize plugin identities: {}", e))?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+        }
+        std::fs::write(path, content)
+            .map_err(|e| format!("Failed to write plugin identities: {}", e))
+    }
+
+    /// Get the ID a UUID was last installed under
+    pub fn id_of(&self, uuid: &str) -> Option<&str> {
+        self.ids.get(&normalize(uuid)).map(String::as_str)
+    }
+
+    /// Remember the ID of a UUID
+    ///
+    /// # Returns
+    /// The rename, if the UUID was known under another ID
+    pub fn record(&mut self, uuid: &str, plugin_id: &str) -> Option<PluginRename> {
+        let uuid = normalize(uuid);
+        let previous = self.ids.insert(uuid.clone(), plugin_id.to_string())?;
+
+        (previous != plugin_id).then(|| PluginRename {
+            uuid,
+            from: previous,
+            to: plugin_id.to_string(),
+        })
+    }
+}
+
+/// Check that a manifest UUID is well formed, e.g. "0b7d2c4e-8f1a-4c3b-9e6d-5a2f1b8c7d90"
+pub fn check_uuid(uuid: &str) -> Result<(), String> {
+    let groups: Vec<&str> = uuid.split('-').collect();
+    let well_formed = groups.iter().map(|group| group.len()).eq([8, 4, 4, 4, 12])
+        && groups
+            .iter()
+            .all(|group| group.chars().all(|c| c.is_ascii_hexdigit()));
+
+    if well_formed {
+        Ok(())
+    } else {
+        Err(format!("Invalid plugin UUID '{}'", uuid))
+    }
+}
+
+/// UUIDs compare case-insensitively
+pub(crate) fn normalize(uuid: &str) -> String {
+    uuid.to_ascii_lowercase()
+}
+
+/// Move a plugin's directory below `root` from its old ID to its new one
+///
+/// Nothing happens if the old directory doesn't exist, so an interrupted
+/// migration can simply run again. An empty directory under the new ID,
+/// e.g. created by the new build before the migration ran, is replaced;
+/// one holding files is never overwritten.
+///
+/// # Returns
+/// Whether the directory was moved
+pub(crate) fn relabel_dir(root: &Path, from: &str, to: &str) -> Result<bool, String> {
+    let source = root.join(from);
+    let target = root.join(to);
+    if !source.exists() {
+        return Ok(false);
+    }
+
+    if target.exists() {
+        let empty = std::fs::read_dir(&target)
+            .map(|mut entries| entries.next().is_none())
+            .unwrap_or(false);
+        if !empty {
+            return Err(format!(
+                "Cannot move {} to {}: the target already holds data",
+                source.display(),
+                target.display()
+            ));
+        }
+        std::fs::remove_dir(&target)
+            .map_err(|e| format!("Failed to replace {}: {}", target.display(), e))?;
+    }
+
+    std::fs::rename(&source, &target).map_err(|e| {
+        format!(
+            "Failed to move {} to {}: {}",
+            source.display(),
+            target.display(),
+            e
+        )
+    })?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UUID: &str = "0b7d2c4e-8f1a-4c3b-9e6d-5a2f1b8c7d90";
+
+    #[test]
+    fn test_renames_are_detected() {
+        let mut identities = PluginIdentities::default();
+        assert_eq!(identities.record(UUID, "clipboard"), None);
+        assert_eq!(identities.record(UUID, "clipboard"), None);
+
+        assert_eq!(
+            identities.record(&UUID.to_uppercase(), "clipboard-history"),
+            Some(PluginRename {
+                uuid: UUID.to_string(),
+                from: "clipboard".to_string(),
+                to: "clipboard-history".to_string(),
+            })
+        );
+        assert_eq!(identities.id_of(UUID), Some("clipboard-history"));
+
+        assert!(check_uuid(UUID).is_ok());
+        assert!(check_uuid("0b7d2c4e8f1a4c3b9e6d5a2f1b8c7d90").is_err());
+        assert!(check_uuid("0b7d2c4e-8f1a-4c3b-9e6d-5a2f1b8c7dzz").is_err());
+    }
+
+    #[test]
+    fn test_relabel_dir() {
+        let root = std::env::temp_dir().join("volt_test_identity_relabel");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("old")).unwrap();
+        std::fs::write(root.join("old").join("kv.json"), "{}").unwrap();
+        std::fs::create_dir_all(root.join("new")).unwrap();
+
+        assert_eq!(relabel_dir(&root, "old", "new"), Ok(true));
+        assert!(root.join("new").join("kv.json").exists());
+        assert_eq!(relabel_dir(&root, "old", "new"), Ok(false));
+
+        std::fs::create_dir_all(root.join("other")).unwrap();
+        std::fs::write(root.join("other").join("kv.json"), "{}").unwrap();
+        assert!(
+            relabel_dir(&root, "other", "new")
+                .unwrap_err()
+                .contains("already holds data")
+        );
+        assert!(root.join("other").join("kv.json").exists());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
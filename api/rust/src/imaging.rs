@@ -0,0 +1,319 @@
+/// Bounded decoding of untrusted images
+///
+/// Window thumbnails, favicons, OpenGraph images and clipboard images come
+/// from sources the launcher doesn't control, and a few kilobytes of crafted
+/// PNG can claim billions of pixels. Every image the crate decodes goes
+/// through this module: the encoded size and format are checked first, the
+/// dimensions are read from the header and compared to a pixel cap before
+/// any pixel buffer is allocated, and decoders run under allocation limits.
+/// Decoded images are turned upright according to their EXIF orientation,
+/// downscaled right away to the size they are shown at, and re-encoded as
+/// PNG, so hosts only ever display small files this module produced.
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageReader};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+/// Image formats the crate can decode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Gif,
+    WebP,
+    Bmp,
+    Ico,
+}
+
+impl ImageFormat {
+    fn from_image(format: image::ImageFormat) -> Option<Self> {
+        match format {
+            image::ImageFormat::Png => Some(Self::Png),
+            image::ImageFormat::Jpeg => Some(Self::Jpeg),
+            image::ImageFormat::Gif => Some(Self::Gif),
+            image::ImageFormat::WebP => Some(Self::WebP),
+            image::ImageFormat::Bmp => Some(Self::Bmp),
+            image::ImageFormat::Ico => Some(Self::Ico),
+            _ => None,
+        }
+    }
+
+    fn to_image(self) -> image::ImageFormat {
+        match self {
+            Self::Png => image::ImageFormat::Png,
+            Self::Jpeg => image::ImageFormat::Jpeg,
+            Self::Gif => image::ImageFormat::Gif,
+            Self::WebP => image::ImageFormat::WebP,
+            Self::Bmp => image::ImageFormat::Bmp,
+            Self::Ico => image::ImageFormat::Ico,
+        }
+    }
+}
+
+/// What an image may be, depending on where it is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageLimits {
+    /// Largest encoded image, in bytes
+    pub max_bytes: usize,
+    /// Largest image, in pixels as declared by its header
+    pub max_pixels: u64,
+    /// Longest side of the decoded image; larger images are downscaled
+    pub max_side: u32,
+    /// Formats accepted
+    pub formats: &'static [ImageFormat],
+}
+
+impl ImageLimits {
+    /// Icons and favicons
+    pub const ICON: Self = Self {
+        max_bytes: 1024 * 1024,
+        max_pixels: 1024 * 1024,
+        max_side: 256,
+        formats: &[
+            ImageFormat::Png,
+            ImageFormat::Ico,
+            ImageFormat::Bmp,
+            ImageFormat::Gif,
+            ImageFormat::Jpeg,
+            ImageFormat::WebP,
+        ],
+    };
+
+    /// Window thumbnails and page preview images
+    pub const THUMBNAIL: Self = Self {
+        max_bytes: 8 * 1024 * 1024,
+        max_pixels: 4096 * 4096,
+        max_side: 1024,
+        formats: &[
+            ImageFormat::Png,
+            ImageFormat::Jpeg,
+            ImageFormat::WebP,
+            ImageFormat::Gif,
+        ],
+    };
+
+    /// Images copied to the clipboard, e.g. screenshots
+    pub const CLIPBOARD: Self = Self {
+        max_bytes: 64 * 1024 * 1024,
+        max_pixels: 8192 * 8192,
+        max_side: 2048,
+        formats: &[
+            ImageFormat::Png,
+            ImageFormat::Jpeg,
+            ImageFormat::WebP,
+            ImageFormat::Gif,
+            ImageFormat::Bmp,
+        ],
+    };
+
+    /// Limits enforced by the decoders while they run
+    fn decoder_limits(&self) -> image::Limits {
+        let side = u32::try_from(self.max_pixels).unwrap_or(u32::MAX);
+        let mut limits = image::Limits::default();
+        limits.max_image_width = Some(side);
+        limits.max_image_height = Some(side);
+        // Room for 16-bit RGBA, the widest pixels the formats above produce
+        limits.max_alloc = Some(self.max_pixels.saturating_mul(8));
+        limits
+    }
+}
+
+/// Format and dimensions of an encoded image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageInfo {
+    pub format: ImageFormat,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// An image decoded within limits and re-encoded as PNG
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizedImage {
+    /// The PNG file
+    pub png: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Check an encoded image against limits without decoding it
+///
+/// Only the header is read.
+pub fn probe(bytes: &[u8], limits: &ImageLimits) -> Result<ImageInfo, String> {
+    if bytes.len() > limits.max_bytes {
+        return Err(format!(
+            "Image is too large ({} bytes, at most {} allowed)",
+            bytes.len(),
+            limits.max_bytes
+        ));
+    }
+
+    let format = image::guess_format(bytes)
+        .ok()
+        .and_then(ImageFormat::from_image)
+        .filter(|format| limits.formats.contains(format))
+        .ok_or("Unsupported image format")?;
+
+    let (width, height) = ImageReader::with_format(Cursor::new(bytes), format.to_image())
+        .into_dimensions()
+        .map_err(|e| format!("Failed to read image header: {}", e))?;
+
+    let pixels = u64::from(width) * u64::from(height);
+    if pixels == 0 || pixels > limits.max_pixels {
+        return Err(format!(
+            "Image dimensions {}x{} are outside the allowed range",
+            width, height
+        ));
+    }
+
+    Ok(ImageInfo {
+        format,
+        width,
+        height,
+    })
+}
+
+/// Decode an image within limits
+///
+/// The image is turned upright according to its EXIF orientation and
+/// downscaled so its longest side is at most `limits.max_side`. Animated
+/// images yield their first frame.
+pub fn decode(bytes: &[u8], limits: &ImageLimits) -> Result<DynamicImage, String> {
+    let info = probe(bytes, limits)?;
+
+    let mut reader = ImageReader::with_format(Cursor::new(bytes), info.format.to_image());
+    reader.limits(limits.decoder_limits());
+    let mut decoder = reader
+        .into_decoder()
+        .map_err(|e| format!("Failed to decode image: {}", e))?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let mut image = DynamicImage::from_decoder(decoder)
+        .map_err(|e| format!("Failed to decode image: {}", e))?;
+
+    // Downscale first: the bounding box is square, so rotating afterwards
+    // keeps the image within it and works on fewer pixels
+    if image.width().max(image.height()) > limits.max_side {
+        image = image.thumbnail(limits.max_side, limits.max_side);
+    }
+    image.apply_orientation(orientation);
+    Ok(image)
+}
+
+/// Decode an image within limits and re-encode it as PNG
+pub fn normalize(bytes: &[u8], limits: &ImageLimits) -> Result<NormalizedImage, String> {
+    let image = decode(bytes, limits)?;
+
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode image: {}", e))?;
+
+    Ok(NormalizedImage {
+        png,
+        width: image.width(),
+        height: image.height(),
+    })
+}
+
+/// Replace an image file with its normalized PNG
+///
+/// The PNG is written next to the file with a `.png` extension and the
+/// original is removed. A file that fails the limits is removed as well, so
+/// it never gets displayed.
+///
+/// # Returns
+/// Path of the PNG
+pub fn normalize_file(path: &Path, limits: &ImageLimits) -> Result<PathBuf, String> {
+    let normalized = std::fs::metadata(path)
+        .map_err(|e| format!("Failed to read image: {}", e))
+        .and_then(|metadata| {
+            if metadata.len() > limits.max_bytes as u64 {
+                return Err(format!("Image is too large ({} bytes)", metadata.len()));
+            }
+            let bytes = std::fs::read(path).map_err(|e| format!("Failed to read image: {}", e))?;
+            normalize(&bytes, limits)
+        });
+
+    let target = path.with_extension("png");
+    let written = normalized.and_then(|image| {
+        std::fs::write(&target, image.png).map_err(|e| format!("Failed to write image: {}", e))
+    });
+    if written.is_err() || target != path {
+        let _ = std::fs::remove_file(path);
+    }
+
+    written.map(|()| target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    fn encode(width: u32, height: u32, format: image::ImageFormat) -> Vec<u8> {
+        let image = RgbImage::from_pixel(width, height, Rgb([200, 40, 40]));
+        let mut bytes = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut bytes), format)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_images_are_bounded() {
+        let png = encode(2000, 1000, image::ImageFormat::Png);
+        let info = probe(&png, &ImageLimits::THUMBNAIL).unwrap();
+        assert_eq!(
+            (info.format, info.width, info.height),
+            (ImageFormat::Png, 2000, 1000)
+        );
+
+        let thumbnail = normalize(&png, &ImageLimits::THUMBNAIL).unwrap();
+        assert_eq!((thumbnail.width, thumbnail.height), (1024, 512));
+        assert_eq!(
+            probe(&thumbnail.png, &ImageLimits::THUMBNAIL)
+                .unwrap()
+                .format,
+            ImageFormat::Png
+        );
+
+        // Rejected from the header, before any pixels are decoded
+        let tiny = ImageLimits {
+            max_pixels: 1000 * 1000,
+            ..ImageLimits::THUMBNAIL
+        };
+        assert!(decode(&png, &tiny).unwrap_err().contains("2000x1000"));
+
+        let bmp = encode(16, 16, image::ImageFormat::Bmp);
+        assert!(normalize(&bmp, &ImageLimits::ICON).is_ok());
+        assert_eq!(
+            normalize(&bmp, &ImageLimits::THUMBNAIL).unwrap_err(),
+            "Unsupported image format"
+        );
+        assert!(normalize(b"<svg/>", &ImageLimits::ICON).is_err());
+        assert!(normalize(&png[..png.len() / 2], &ImageLimits::THUMBNAIL).is_err());
+    }
+
+    #[test]
+    fn test_normalize_file() {
+        let dir = std::env::temp_dir().join("volt_test_imaging");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let favicon = dir.join("favicon.img");
+        std::fs::write(&favicon, encode(512, 512, image::ImageFormat::Bmp)).unwrap();
+        let png = normalize_file(&favicon, &ImageLimits::ICON).unwrap();
+        assert_eq!(png, dir.join("favicon.png"));
+        assert!(!favicon.exists());
+        let info = probe(&std::fs::read(&png).unwrap(), &ImageLimits::ICON).unwrap();
+        assert_eq!((info.width, info.height), (256, 256));
+
+        let bogus = dir.join("image.jpg");
+        std::fs::write(&bogus, b"not an image").unwrap();
+        assert!(normalize_file(&bogus, &ImageLimits::THUMBNAIL).is_err());
+        assert!(!bogus.exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
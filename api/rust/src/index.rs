@@ -0,0 +1,320 @@
+/// Central document index shared by plugins
+///
+/// Plugins contribute documents (notes, bookmarks, snippets, ...) to Volt's
+/// index instead of maintaining their own. Documents live in a namespace per
+/// plugin, and every hit carries the contributing plugin's ID so execution
+/// is routed back to it.
+use crate::result::PluginResult;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Maximum number of documents a single plugin may contribute
+pub const MAX_DOCUMENTS_PER_PLUGIN: usize = 100_000;
+
+/// A document contributed to the index
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexDoc {
+    /// Identifier, unique within the contributing plugin
+    pub id: String,
+    /// Title, matched with the highest weight and shown as the result title
+    pub title: String,
+    /// Secondary text shown under the title
+    #[serde(default)]
+    pub subtitle: Option<String>,
+    /// Extra terms the document should be found by
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// Searchable body text, not shown
+    #[serde(default)]
+    pub body: Option<String>,
+    /// Icon shown with the result
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Opaque data passed back to the plugin on execution
+    #[serde(default)]
+    pub metadata: serde_json::Map<String, serde_json::Value>,
+}
+
+impl IndexDoc {
+    /// Create a document with an ID and title
+    pub fn new(id: impl Into<String>, title: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            title: title.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Score the document against lower-cased query terms (0-100)
+    fn score(&self, terms: &[String]) -> u32 {
+        let title = self.title.to_lowercase();
+        let keywords: Vec<String> = self.keywords.iter().map(|k| k.to_lowercase()).collect();
+        let subtitle = self.subtitle.as_deref().unwrap_or_default().to_lowercase();
+        let body = self.body.as_deref().unwrap_or_default().to_lowercase();
+
+        let mut total = 0;
+        for term in terms {
+            let term_score = if title.starts_with(term.as_str()) {
+                100
+            } else if title.split_whitespace().any(|word| word.starts_with(term.as_str())) {
+                90
+            } else if keywords.iter().any(|keyword| keyword == term) {
+                80
+            } else if title.contains(term.as_str()) {
+                70
+            } else if keywords.iter().any(|keyword| keyword.starts_with(term.as_str())) {
+                60
+            } else if subtitle.contains(term.as_str()) {
+                50
+            } else if body.contains(term.as_str()) {
+                40
+            } else {
+                // Every term must match
+                return 0;
+            };
+            total += term_score;
+        }
+
+        total / terms.len().max(1) as u32
+    }
+}
+
+/// A set of changes applied atomically to a plugin's namespace
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexBatch {
+    /// Documents added or replaced (matched by ID)
+    #[serde(default)]
+    pub upserts: Vec<IndexDoc>,
+    /// IDs of documents removed
+    #[serde(default)]
+    pub removals: Vec<String>,
+    /// Remove every existing document before applying the batch
+    #[serde(default)]
+    pub replace_all: bool,
+}
+
+/// A document matching a query
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexHit {
+    /// Plugin that contributed the document
+    pub plugin_id: String,
+    /// The matching document
+    pub doc: IndexDoc,
+    /// Match score (0-100)
+    pub score: u32,
+}
+
+impl IndexHit {
+    /// Turn the hit into a result routed back to the contributing plugin
+    pub fn into_result(self) -> PluginResult {
+        let mut result = PluginResult::new(self.doc.id, self.doc.title);
+        result.subtitle = self.doc.subtitle.map(Into::into);
+        result.icon = self.doc.icon.map(Into::into);
+        result.score = self.score;
+        result.plugin_id = Some(self.plugin_id.into());
+        result.metadata = self.doc.metadata;
+        result
+    }
+}
+
+/// A plugin's documents
+#[derive(Debug, Clone, Default)]
+struct Namespace {
+    docs: HashMap<String, IndexDoc>,
+    /// Incremented by every applied batch
+    generation: u64,
+}
+
+/// In-memory index of documents contributed by plugins
+#[derive(Debug, Clone, Default)]
+pub struct DocumentIndex {
+    namespaces: HashMap<String, Namespace>,
+}
+
+impl DocumentIndex {
+    /// Create an empty index
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a batch of changes to a plugin's namespace
+    ///
+    /// Either the whole batch is applied or, on error, nothing is.
+    ///
+    /// # Returns
+    /// The namespace's new generation
+    pub fn apply(&mut self, plugin_id: &str, batch: IndexBatch) -> Result<u64, String> {
+        let namespace = self.namespaces.entry(plugin_id.to_string()).or_default();
+
+        let mut docs = if batch.replace_all {
+            HashMap::new()
+        } else {
+            namespace.docs.clone()
+        };
+        for id in &batch.removals {
+            docs.remove(id);
+        }
+        for doc in batch.upserts {
+            if doc.id.is_empty() {
+                return Err("Indexed documents must have an ID".to_string());
+            }
+            docs.insert(doc.id.clone(), doc);
+        }
+
+        if docs.len() > MAX_DOCUMENTS_PER_PLUGIN {
+            return Err(format!(
+                "Plugin '{}' exceeds the index limit of {} documents",
+                plugin_id, MAX_DOCUMENTS_PER_PLUGIN
+            ));
+        }
+
+        namespace.docs = docs;
+        namespace.generation += 1;
+        Ok(namespace.generation)
+    }
+
+    /// Remove a plugin's namespace entirely
+    pub fn remove_plugin(&mut self, plugin_id: &str) {
+        self.namespaces.remove(plugin_id);
+    }
+
+    /// Number of documents contributed by a plugin
+    pub fn document_count(&self, plugin_id: &str) -> usize {
+        self.namespaces
+            .get(plugin_id)
+            .map(|namespace| namespace.docs.len())
+            .unwrap_or(0)
+    }
+
+    /// Get a document by plugin and ID
+    pub fn get(&self, plugin_id: &str, doc_id: &str) -> Option<&IndexDoc> {
+        self.namespaces.get(plugin_id)?.docs.get(doc_id)
+    }
+
+    /// Search every namespace
+    ///
+    /// # Arguments
+    /// * `query` - Raw query; every whitespace-separated term must match
+    /// * `plugin_id` - Restrict the search to one plugin's namespace
+    /// * `limit` - Maximum number of hits
+    ///
+    /// # Returns
+    /// Hits sorted by score, best first
+    pub fn search(&self, query: &str, plugin_id: Option<&str>, limit: usize) -> Vec<IndexHit> {
+        let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let mut hits: Vec<IndexHit> = self
+            .namespaces
+            .iter()
+            .filter(|(id, _)| plugin_id.is_none_or(|wanted| wanted == id.as_str()))
+            .flat_map(|(id, namespace)| {
+                namespace.docs.values().filter_map(|doc| {
+                    let score = doc.score(&terms);
+                    (score > 0).then(|| IndexHit {
+                        plugin_id: id.clone(),
+                        doc: doc.clone(),
+                        score,
+                    })
+                })
+            })
+            .collect();
+
+        hits.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.doc.title.len().cmp(&b.doc.title.len()))
+                .then_with(|| a.plugin_id.cmp(&b.plugin_id))
+                .then_with(|| a.doc.id.cmp(&b.doc.id))
+        });
+        hits.truncate(limit);
+        hits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(id: &str, title: &str) -> IndexDoc {
+        IndexDoc::new(id, title)
+    }
+
+    #[test]
+    fn test_batches_are_namespaced() {
+        let mut index = DocumentIndex::new();
+        index
+            .apply(
+                "notes",
+                IndexBatch {
+                    upserts: vec![note("1", "Meeting notes"), note("2", "Groceries")],
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        index
+            .apply(
+                "bookmarks",
+                IndexBatch {
+                    upserts: vec![note("1", "Meeting room booking")],
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let generation = index
+            .apply(
+                "notes",
+                IndexBatch {
+                    removals: vec!["2".to_string()],
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(generation, 2);
+        assert_eq!(index.document_count("notes"), 1);
+        assert_eq!(index.document_count("bookmarks"), 1);
+
+        let failed = index.apply(
+            "notes",
+            IndexBatch {
+                upserts: vec![note("3", "Ok"), note("", "Missing ID")],
+                replace_all: true,
+                ..Default::default()
+            },
+        );
+        assert!(failed.is_err());
+        assert!(index.get("notes", "1").is_some());
+    }
+
+    #[test]
+    fn test_search_routes_back_to_plugin() {
+        let mut index = DocumentIndex::new();
+        let mut doc = note("q3", "Quarterly report");
+        doc.keywords = vec!["finance".to_string()];
+        doc.body = Some("Revenue grew in Q3".to_string());
+        index
+            .apply("docs", IndexBatch { upserts: vec![doc], ..Default::default() })
+            .unwrap();
+        index
+            .apply("notes", IndexBatch { upserts: vec![note("r", "Reading list")], ..Default::default() })
+            .unwrap();
+
+        let hits = index.search("rep", None, 10);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].plugin_id, "docs");
+
+        assert_eq!(index.search("finance revenue", None, 10).len(), 1);
+        assert!(index.search("report", Some("notes"), 10).is_empty());
+
+        let result = index.search("quarterly", None, 1).remove(0).into_result();
+        assert_eq!(result.plugin_id.as_deref(), Some("docs"));
+        assert_eq!(result.id, "q3");
+        assert_eq!(result.score, 100);
+    }
+}
@@ -0,0 +1,59 @@
+/// Clipboard access and text input synthesis
+///
+/// The host implements both on top of the platform's APIs and installs
+/// them on `VoltPluginAPI`; plugins go through `read_clipboard`,
+/// `write_clipboard` and `insert_text`, which fail when the host declared
+/// the matching `HostFeature` unavailable.
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// System clipboard, implemented by the host
+pub trait ClipboardBackend: Send + Sync {
+    /// Read the clipboard as text
+    fn read_text(&self) -> Result<String, String>;
+
+    /// Replace the clipboard contents with text
+    fn write_text(&self, text: &str) -> Result<(), String>;
+
+    /// Read the clipboard as an encoded image (PNG, JPEG, BMP...)
+    ///
+    /// None if the clipboard holds no image. Plugins get it through
+    /// `VoltPluginAPI::read_clipboard_image`, which decodes it within limits.
+    fn read_image(&self) -> Result<Option<Vec<u8>>, String> {
+        Ok(None)
+    }
+}
+
+/// Callback installed by the host to type text into the focused application
+///
+/// Called once the launcher window is hidden and focus is back on the
+/// application the user was in.
+pub type InputSynthesizer = Arc<dyn Fn(&TextInsertion) -> Result<(), String> + Send + Sync>;
+
+/// Text to type into the focused application
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextInsertion {
+    /// The text
+    pub text: String,
+    /// Where to leave the caret, in characters from the start of `text`;
+    /// None to leave it after the text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<usize>,
+}
+
+impl TextInsertion {
+    /// Insert text, leaving the caret after it
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            cursor: None,
+        }
+    }
+
+    /// Number of characters the caret moves left after typing the text
+    pub fn caret_moves_left(&self) -> usize {
+        let length = self.text.chars().count();
+        self.cursor.map_or(0, |cursor| length.saturating_sub(cursor))
+    }
+}
@@ -0,0 +1,348 @@
+/// Host execution of result intents
+///
+/// Performs the `ResultIntent` attached to a result, so opening files and
+/// URLs, starting programs and copying text behave the same for every
+/// plugin. Intents are validated before anything runs: only a few URL
+/// schemes are opened, files must exist, programs are never started
+/// through a shell, and `RunCommand` needs the `execute_commands`
+/// permission.
+///
+/// `OpenFile` also accepts a `voltres://` virtual path (see
+/// `crate::resources`). Only `PluginRegistry::execute_intent` can open
+/// those, since the owning plugin has to materialize the file first.
+///
+/// `Share` goes to the share sheet the host installed (see
+/// `crate::backends::ShareBackend`), which only macOS and Windows have.
+///
+/// File results can be opened with another application than the default
+/// one, see `crate::open_with`.
+///
+/// Also converts results into the payload delivered to "send to" targets
+/// (see `crate::extensions::SendHandler`).
+use crate::api::{PluginCapability, VoltPluginAPI};
+use crate::open_with::{self, OpenWithApp};
+use crate::resources::VirtualPath;
+use crate::result::{CommandSpec, IntentKind, PluginResult, ResultIntent};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// URL schemes `OpenUrl` may open
+pub const ALLOWED_URL_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+/// Largest text `CopyText` accepts, in bytes
+pub const MAX_COPY_SIZE: usize = 1024 * 1024;
+
+/// Prefix of the result action IDs generated for send targets
+pub const SEND_ACTION_PREFIX: &str = "send-to:";
+
+/// A result sent to another plugin's target
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SendPayload {
+    /// Plugin that produced the result
+    pub source_plugin_id: Option<String>,
+    /// Title of the sent result
+    pub title: String,
+    /// Kind of the result's intent
+    pub kind: IntentKind,
+    /// Plain-text form: the path, URL, command line or copied text
+    pub text: String,
+    /// The original intent
+    pub intent: ResultIntent,
+}
+
+impl SendPayload {
+    /// Convert a result for delivery to a send target
+    ///
+    /// # Returns
+    /// None if the result has no intent
+    pub fn from_result(result: &PluginResult) -> Option<Self> {
+        let intent = result.intent.clone()?;
+
+        let text = match &intent {
+            ResultIntent::OpenFile { path } => path.clone(),
+            ResultIntent::OpenUrl { url } => url.clone(),
+            ResultIntent::RunCommand(spec) => command_line(spec),
+            ResultIntent::CopyText { text } => text.clone(),
+            ResultIntent::Share { path } => path.clone(),
+        };
+
+        Some(Self {
+            source_plugin_id: result.plugin_id.as_deref().map(str::to_string),
+            title: result.title.to_string(),
+            kind: intent.kind(),
+            text,
+            intent,
+        })
+    }
+}
+
+/// Build the result action ID routing to a send target
+pub fn send_action_id(plugin_id: &str, target_id: &str) -> String {
+    format!("{}{}:{}", SEND_ACTION_PREFIX, plugin_id, target_id)
+}
+
+/// Split a routed action into the plugin ID and target ID of a send target
+///
+/// # Returns
+/// None if the action isn't a send action
+pub fn parse_send_action(action: &str) -> Option<(&str, &str)> {
+    action.strip_prefix(SEND_ACTION_PREFIX)?.split_once(':')
+}
+
+/// Execute a result's intent
+///
+/// Call this before `Plugin::execute`; results without an intent are left
+/// to the plugin.
+///
+/// # Arguments
+/// * `api` - API used to check the plugin's permissions
+/// * `result` - The executed result, tagged with its plugin ID
+///
+/// # Returns
+/// None if the result has no intent, otherwise the outcome of running it
+pub fn execute(api: &VoltPluginAPI, result: &PluginResult) -> Option<Result<(), String>> {
+    let intent = result.intent.as_ref()?;
+
+    Some(
+        check_permission(api, result, intent)
+            .and_then(|()| validate(intent))
+            .and_then(|()| match intent {
+                ResultIntent::Share { path } => api.share_file(Path::new(path)),
+                _ => run(intent),
+            }),
+    )
+}
+
+/// List the applications able to open a file result, the default one first
+///
+/// Backs the "Open with…" action the aggregator adds to file results.
+///
+/// # Returns
+/// Err if the result doesn't open a file on disk
+pub fn open_with_apps(result: &PluginResult) -> Result<Vec<OpenWithApp>, String> {
+    open_with::applications(&openable_file(result)?)
+}
+
+/// Open a file result with an application listed by `open_with_apps`
+///
+/// # Arguments
+/// * `result` - The result the "Open with…" action was chosen on
+/// * `app_id` - ID of the chosen application
+pub fn open_with(result: &PluginResult, app_id: &str) -> Result<(), String> {
+    open_with::open(&openable_file(result)?, app_id)
+}
+
+/// Get the file an `OpenFile` intent opens, once validated
+fn openable_file(result: &PluginResult) -> Result<std::path::PathBuf, String> {
+    match &result.intent {
+        Some(intent @ ResultIntent::OpenFile { path }) if !VirtualPath::is_virtual(path) => {
+            validate(intent)?;
+            Ok(path.into())
+        }
+        _ => Err(format!("Result '{}' doesn't open a file", result.id)),
+    }
+}
+
+/// Check that the producing plugin may perform an intent
+fn check_permission(api: &VoltPluginAPI, result: &PluginResult, intent: &ResultIntent) -> Result<(), String> {
+    if !matches!(intent, ResultIntent::RunCommand(_)) {
+        return Ok(());
+    }
+
+    let plugin_id = result
+        .plugin_id
+        .as_deref()
+        .ok_or_else(|| format!("Result '{}' has no plugin ID", result.id))?;
+    api.require_capability(plugin_id, PluginCapability::ExecuteCommands)
+}
+
+/// Check that an intent is safe to perform
+pub fn validate(intent: &ResultIntent) -> Result<(), String> {
+    match intent {
+        ResultIntent::OpenFile { path } if VirtualPath::is_virtual(path) => VirtualPath::parse(path).map(|_| ()),
+        ResultIntent::OpenFile { path } | ResultIntent::Share { path } => {
+            let path = Path::new(path);
+            if !path.is_absolute() {
+                return Err(format!("File path must be absolute: {}", path.display()));
+            }
+            if !path.exists() {
+                return Err(format!("File not found: {}", path.display()));
+            }
+            Ok(())
+        }
+        ResultIntent::OpenUrl { url } => {
+            let scheme = url
+                .split_once(':')
+                .map(|(scheme, _)| scheme.to_ascii_lowercase())
+                .unwrap_or_default();
+            if ALLOWED_URL_SCHEMES.contains(&scheme.as_str()) && !url.chars().any(char::is_control) {
+                Ok(())
+            } else {
+                Err(format!("URL cannot be opened: {}", url))
+            }
+        }
+        ResultIntent::RunCommand(spec) => {
+            if spec.program.trim().is_empty() {
+                return Err("Command program cannot be empty".to_string());
+            }
+            Ok(())
+        }
+        ResultIntent::CopyText { text } => {
+            if text.len() > MAX_COPY_SIZE {
+                return Err(format!("Text exceeds {} bytes", MAX_COPY_SIZE));
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Validate and perform an intent on the user's behalf
+///
+/// Skips the permission check, for built-in plugins running intents the
+/// user authored (see `crate::builtins::macros`).
+#[cfg(feature = "macros")]
+pub(crate) fn perform(intent: &ResultIntent) -> Result<(), String> {
+    validate(intent)?;
+    run(intent)
+}
+
+/// Perform a validated intent
+fn run(intent: &ResultIntent) -> Result<(), String> {
+    match intent {
+        ResultIntent::OpenFile { path } if VirtualPath::is_virtual(path) => {
+            Err(format!("Virtual path {} must be materialized before it is opened", path))
+        }
+        ResultIntent::OpenFile { path } => spawn(open_command(path)),
+        ResultIntent::OpenUrl { url } => spawn(open_command(url)),
+        ResultIntent::RunCommand(spec) => spawn(command_for(spec)),
+        ResultIntent::CopyText { text } => copy_text(text),
+        ResultIntent::Share { .. } => Err("Sharing needs the host's share sheet".to_string()),
+    }
+}
+
+/// Build the platform command opening a file or URL with its default handler
+fn open_command(target: &str) -> Command {
+    let opener = if cfg!(target_os = "windows") {
+        // explorer.exe, unlike `cmd /c start`, doesn't interpret its argument
+        "explorer.exe"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+
+    let mut command = Command::new(opener);
+    command.arg(target);
+    command
+}
+
+/// Render a command as a single line, quoting arguments with spaces
+pub(crate) fn command_line(spec: &CommandSpec) -> String {
+    std::iter::once(&spec.program)
+        .chain(&spec.args)
+        .map(|part| {
+            if part.is_empty() || part.contains(char::is_whitespace) {
+                format!("\"{}\"", part.replace('"', "\\\""))
+            } else {
+                part.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn command_for(spec: &CommandSpec) -> Command {
+    let mut command = Command::new(&spec.program);
+    command.args(&spec.args);
+    if let Some(dir) = &spec.working_dir {
+        command.current_dir(dir);
+    }
+    command
+}
+
+fn spawn(mut command: Command) -> Result<(), String> {
+    command
+        .stdin(Stdio::null())
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to start {:?}: {}", command.get_program(), e))
+}
+
+/// Copy text with the platform's clipboard tool
+fn copy_text(text: &str) -> Result<(), String> {
+    let candidates: &[(&str, &[&str])] = if cfg!(target_os = "windows") {
+        &[("clip.exe", &[])]
+    } else if cfg!(target_os = "macos") {
+        &[("pbcopy", &[])]
+    } else {
+        &[("wl-copy", &[]), ("xclip", &["-selection", "clipboard"]), ("xsel", &["--clipboard", "--input"])]
+    };
+
+    for (program, args) in candidates {
+        let Ok(mut child) = Command::new(program).args(*args).stdin(Stdio::piped()).spawn() else {
+            continue;
+        };
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(text.as_bytes())
+                .map_err(|e| format!("Failed to copy text: {}", e))?;
+        }
+        let status = child
+            .wait()
+            .map_err(|e| format!("Failed to copy text: {}", e))?;
+
+        return if status.success() {
+            Ok(())
+        } else {
+            Err(format!("Failed to copy text: {} exited with {}", program, status))
+        };
+    }
+
+    Err("No clipboard tool available".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intents_are_validated() {
+        let url = |url: &str| ResultIntent::OpenUrl { url: url.to_string() };
+        assert!(validate(&url("https://volt.dev")).is_ok());
+        assert!(validate(&url("MAILTO:team@volt.dev")).is_ok());
+        assert!(validate(&url("file:///etc/passwd")).is_err());
+        assert!(validate(&url("javascript:alert(1)")).is_err());
+
+        let file = |path: &str| ResultIntent::OpenFile { path: path.to_string() };
+        let temp_dir = std::env::temp_dir();
+        assert!(validate(&file(&temp_dir.to_string_lossy())).is_ok());
+        assert!(validate(&file("relative/notes.txt")).is_err());
+        assert!(validate(&file(&temp_dir.join("volt_test_missing").to_string_lossy())).is_err());
+        assert!(validate(&file("voltres://archives/backup.zip/notes.txt")).is_ok());
+        assert!(validate(&file("voltres://archives/../notes.txt")).is_err());
+        assert!(run(&file("voltres://archives/backup.zip/notes.txt")).is_err());
+
+        assert!(validate(&ResultIntent::RunCommand(CommandSpec::new(" "))).is_err());
+    }
+
+    #[test]
+    fn test_run_command_requires_permission() {
+        let temp_dir = std::env::temp_dir().join("volt_test_intents");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let api = VoltPluginAPI::new(temp_dir.clone());
+
+        let mut result = PluginResult::new("htop", "htop")
+            .with_intent(ResultIntent::RunCommand(CommandSpec::new("htop")));
+        result.plugin_id = Some("monitor".into());
+
+        let outcome = execute(&api, &result).unwrap();
+        assert!(outcome.unwrap_err().contains("not installed"));
+        assert!(execute(&api, &PluginResult::new("plain", "Plain")).is_none());
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
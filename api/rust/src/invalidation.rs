@@ -0,0 +1,415 @@
+/// Cache invalidation bus
+///
+/// Data is cached at several layers: plugin cache entries, the shared HTTP
+/// cache, and whatever caches the host and plugins keep themselves. When
+/// the data behind them changes, every dependent layer must drop its stale
+/// entries, not just the one that noticed. Each cache entry declares what
+/// it was derived from when it is written:
+///
+/// ```ignore
+/// api.write_cache("notes", "titles.json", &titles)?;
+/// api.cache_depends_on("notes", "titles.json", vec![
+///     Dependency::File(notes_dir.clone()),
+///     Dependency::Config { plugin_id: "notes".into(), config_name: None },
+///     Dependency::MaxAge(Duration::from_secs(3600)),
+/// ])?;
+/// ```
+///
+/// and data changes are published on the `InvalidationBus`. Configuration
+/// saves and index updates are published by the API itself; file changes
+/// come from the host's file watcher through `VoltPluginAPI::publish_change`.
+/// Every entry depending on the change is invalidated in its layer, then
+/// every entry depending on those entries, and so on, so a layer never
+/// keeps something derived from data another layer dropped. Entries past
+/// their `MaxAge` are invalidated by `InvalidationBus::expire`.
+///
+/// Layers are registered by name with `register_layer`; the API registers
+/// `PLUGIN_CACHE_LAYER` and, with downloads, `HTTP_CACHE_LAYER`.
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Layer of the plugin cache entries, keyed by `<plugin id>/<cache key>`
+pub const PLUGIN_CACHE_LAYER: &str = "plugin_cache";
+
+/// Layer of the shared HTTP cache, keyed by URL
+pub const HTTP_CACHE_LAYER: &str = "http";
+
+/// A change to data caches may depend on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataChange {
+    /// A file or directory changed on disk
+    FileChanged(PathBuf),
+    /// A plugin's documents in the central index changed
+    PluginReindexed(String),
+    /// A plugin configuration was saved or edited
+    ConfigSaved {
+        /// Plugin owning the configuration
+        plugin_id: String,
+        /// Name of the configuration
+        config_name: String,
+    },
+    /// A change named by the host or a plugin
+    Custom(String),
+}
+
+/// What a cache entry was derived from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Dependency {
+    /// A file, or any file below a directory
+    File(PathBuf),
+    /// A plugin's documents in the central index
+    PluginIndex(String),
+    /// A plugin's configuration, one of them or all of them
+    Config {
+        /// Plugin owning the configuration
+        plugin_id: String,
+        /// Name of the configuration, None for any
+        config_name: Option<String>,
+    },
+    /// A change named by the host or a plugin, see `DataChange::Custom`
+    Custom(String),
+    /// Another cache entry
+    Entry(CacheEntry),
+    /// The entry is only valid for this long after it is declared
+    MaxAge(Duration),
+}
+
+impl Dependency {
+    /// Check if a change invalidates entries with this dependency
+    pub fn is_affected_by(&self, change: &DataChange) -> bool {
+        match (self, change) {
+            (Dependency::File(path), DataChange::FileChanged(changed)) => {
+                changed.starts_with(path) || path.starts_with(changed)
+            }
+            (Dependency::PluginIndex(plugin_id), DataChange::PluginReindexed(changed)) => {
+                plugin_id == changed
+            }
+            (
+                Dependency::Config {
+                    plugin_id,
+                    config_name,
+                },
+                DataChange::ConfigSaved {
+                    plugin_id: changed_plugin,
+                    config_name: changed_name,
+                },
+            ) => {
+                plugin_id == changed_plugin
+                    && config_name.as_ref().is_none_or(|name| name == changed_name)
+            }
+            (Dependency::Custom(topic), DataChange::Custom(changed)) => topic == changed,
+            _ => false,
+        }
+    }
+}
+
+/// An entry of a cache layer
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CacheEntry {
+    /// Name of the layer
+    pub layer: String,
+    /// Key of the entry in the layer
+    pub key: String,
+}
+
+impl CacheEntry {
+    /// Name an entry of a layer
+    pub fn new(layer: impl Into<String>, key: impl Into<String>) -> Self {
+        Self {
+            layer: layer.into(),
+            key: key.into(),
+        }
+    }
+}
+
+/// A cache whose entries can be invalidated through the bus
+pub trait CacheLayer: Send + Sync {
+    /// Drop an entry; unknown keys are ignored
+    fn invalidate(&self, key: &str);
+}
+
+/// Dependencies declared for an entry
+#[derive(Debug)]
+struct Declared {
+    dependencies: Vec<Dependency>,
+    expires_at: Option<Instant>,
+}
+
+#[derive(Default)]
+struct BusState {
+    layers: HashMap<String, Arc<dyn CacheLayer>>,
+    entries: HashMap<CacheEntry, Declared>,
+}
+
+/// Routes data changes to the cache entries depending on them
+///
+/// Cheap to clone; all clones share the same layers and entries.
+#[derive(Clone, Default)]
+pub struct InvalidationBus {
+    state: Arc<Mutex<BusState>>,
+}
+
+impl InvalidationBus {
+    /// Create a bus without layers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a cache layer, replacing the one of the same name
+    pub fn register_layer(&self, name: &str, layer: Arc<dyn CacheLayer>) {
+        self.state().layers.insert(name.to_string(), layer);
+    }
+
+    /// Declare what an entry depends on, replacing earlier declarations
+    ///
+    /// # Arguments
+    /// * `entry` - The entry, in a registered layer
+    /// * `dependencies` - What the entry was derived from
+    pub fn depend(&self, entry: CacheEntry, dependencies: Vec<Dependency>) -> Result<(), String> {
+        let mut state = self.state();
+        if !state.layers.contains_key(&entry.layer) {
+            return Err(format!("Unknown cache layer '{}'", entry.layer));
+        }
+
+        let expires_at = dependencies
+            .iter()
+            .filter_map(|dependency| match dependency {
+                Dependency::MaxAge(age) => Instant::now().checked_add(*age),
+                _ => None,
+            })
+            .min();
+        state.entries.insert(
+            entry,
+            Declared {
+                dependencies,
+                expires_at,
+            },
+        );
+        Ok(())
+    }
+
+    /// Forget the dependencies of an entry, without invalidating it
+    pub fn forget(&self, entry: &CacheEntry) {
+        self.state().entries.remove(entry);
+    }
+
+    /// Invalidate the entries depending on a change
+    ///
+    /// # Returns
+    /// Every entry invalidated, those depending on invalidated entries included
+    pub fn publish(&self, change: &DataChange) -> Vec<CacheEntry> {
+        self.invalidate_matching(|declared| {
+            declared
+                .dependencies
+                .iter()
+                .any(|dependency| dependency.is_affected_by(change))
+        })
+    }
+
+    /// Invalidate an entry and the entries depending on it
+    ///
+    /// # Returns
+    /// Every entry invalidated
+    pub fn invalidate(&self, entry: &CacheEntry) -> Vec<CacheEntry> {
+        let mut state = self.state();
+        let invalidated = Self::cascade(&mut state, vec![entry.clone()]);
+        Self::notify_layers(state, invalidated)
+    }
+
+    /// Invalidate the entries past their maximum age
+    ///
+    /// # Arguments
+    /// * `now` - The current time
+    ///
+    /// # Returns
+    /// Every entry invalidated, those depending on expired entries included
+    pub fn expire(&self, now: Instant) -> Vec<CacheEntry> {
+        self.invalidate_matching(|declared| {
+            declared
+                .expires_at
+                .is_some_and(|expires_at| expires_at <= now)
+        })
+    }
+
+    /// Get the number of entries with declared dependencies
+    pub fn len(&self) -> usize {
+        self.state().entries.len()
+    }
+
+    /// Check if no entry has declared dependencies
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn invalidate_matching(&self, matches: impl Fn(&Declared) -> bool) -> Vec<CacheEntry> {
+        let mut state = self.state();
+        let roots = state
+            .entries
+            .iter()
+            .filter(|(_, declared)| matches(declared))
+            .map(|(entry, _)| entry.clone())
+            .collect();
+        let invalidated = Self::cascade(&mut state, roots);
+        Self::notify_layers(state, invalidated)
+    }
+
+    /// Remove entries and, transitively, the entries depending on them
+    fn cascade(state: &mut BusState, mut pending: Vec<CacheEntry>) -> Vec<CacheEntry> {
+        let mut invalidated = Vec::new();
+        while let Some(entry) = pending.pop() {
+            if invalidated.contains(&entry) {
+                continue;
+            }
+            state.entries.remove(&entry);
+            pending.extend(
+                state
+                    .entries
+                    .iter()
+                    .filter(|(_, declared)| {
+                        declared
+                            .dependencies
+                            .contains(&Dependency::Entry(entry.clone()))
+                    })
+                    .map(|(dependent, _)| dependent.clone()),
+            );
+            invalidated.push(entry);
+        }
+        invalidated.sort();
+        invalidated
+    }
+
+    /// Tell each layer which of its entries are invalid, without the lock held
+    fn notify_layers(
+        state: std::sync::MutexGuard<'_, BusState>,
+        invalidated: Vec<CacheEntry>,
+    ) -> Vec<CacheEntry> {
+        let layers = state.layers.clone();
+        drop(state);
+
+        for entry in &invalidated {
+            if let Some(layer) = layers.get(&entry.layer) {
+                layer.invalidate(&entry.key);
+            }
+        }
+        invalidated
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, BusState> {
+        self.state.lock().unwrap_or_else(|p| p.into_inner())
+    }
+}
+
+/// Plugin cache entries, stored as `<dir>/<plugin id>/<cache key>`
+pub(crate) struct PluginCacheLayer {
+    pub(crate) dir: PathBuf,
+}
+
+impl CacheLayer for PluginCacheLayer {
+    fn invalidate(&self, key: &str) {
+        let key = Path::new(key);
+        if key
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            let _ = std::fs::remove_file(self.dir.join(key));
+        }
+    }
+}
+
+#[cfg(feature = "download")]
+impl CacheLayer for crate::http_cache::HttpCache {
+    fn invalidate(&self, key: &str) {
+        self.remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Layer recording what it was told to invalidate
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+
+    impl CacheLayer for Recorder {
+        fn invalidate(&self, key: &str) {
+            self.0.lock().unwrap().push(key.to_string());
+        }
+    }
+
+    #[test]
+    fn test_changes_cascade_through_layers() {
+        let bus = InvalidationBus::new();
+        let memo = Arc::new(Recorder::default());
+        let thumbnails = Arc::new(Recorder::default());
+        bus.register_layer("memo", memo.clone());
+        bus.register_layer("thumbnails", thumbnails.clone());
+
+        let listing = CacheEntry::new("memo", "photos");
+        bus.depend(
+            listing.clone(),
+            vec![Dependency::File(PathBuf::from("/home/me/Pictures"))],
+        )
+        .unwrap();
+        bus.depend(
+            CacheEntry::new("thumbnails", "photos-grid"),
+            vec![Dependency::Entry(listing.clone())],
+        )
+        .unwrap();
+        bus.depend(
+            CacheEntry::new("memo", "settings"),
+            vec![Dependency::Config {
+                plugin_id: "photos".to_string(),
+                config_name: None,
+            }],
+        )
+        .unwrap();
+        assert!(
+            bus.depend(CacheEntry::new("nope", "x"), Vec::new())
+                .is_err()
+        );
+
+        let invalidated = bus.publish(&DataChange::FileChanged(PathBuf::from(
+            "/home/me/Pictures/cat.png",
+        )));
+        assert_eq!(
+            invalidated,
+            vec![listing, CacheEntry::new("thumbnails", "photos-grid")]
+        );
+        assert_eq!(*memo.0.lock().unwrap(), vec!["photos"]);
+        assert_eq!(*thumbnails.0.lock().unwrap(), vec!["photos-grid"]);
+
+        // Invalidated entries are forgotten until declared again
+        assert!(
+            bus.publish(&DataChange::FileChanged(PathBuf::from("/home/me/Pictures")))
+                .is_empty()
+        );
+        let change = DataChange::ConfigSaved {
+            plugin_id: "photos".to_string(),
+            config_name: "settings".to_string(),
+        };
+        assert_eq!(bus.publish(&change).len(), 1);
+        assert!(bus.is_empty());
+    }
+
+    #[test]
+    fn test_entries_expire() {
+        let bus = InvalidationBus::new();
+        let memo = Arc::new(Recorder::default());
+        bus.register_layer("memo", memo.clone());
+        bus.depend(
+            CacheEntry::new("memo", "weather"),
+            vec![Dependency::MaxAge(Duration::from_secs(60))],
+        )
+        .unwrap();
+
+        assert!(bus.expire(Instant::now()).is_empty());
+        assert_eq!(
+            bus.expire(Instant::now() + Duration::from_secs(61)).len(),
+            1
+        );
+        assert_eq!(*memo.0.lock().unwrap(), vec!["weather"]);
+    }
+}
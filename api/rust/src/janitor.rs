@@ -0,0 +1,514 @@
+/// Startup cleanup of state left behind by a crash
+///
+/// Bridges running plugins out of process write a PID file to the run
+/// directory when they start a plugin and remove it when the plugin stops.
+/// Plugins and bridges guard shared files with `LockFile`, which records
+/// the owning process. If the launcher crashes, both are left behind: at
+/// the next startup `clean_stale_state` kills plugin processes that are
+/// still running, deletes their PID files and removes locks whose owner is
+/// gone.
+///
+/// A PID file records the program as well as the PID, so a process that
+/// reused the PID of a dead plugin is never killed.
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Directory of the app data directory holding PID files
+pub const RUN_DIR: &str = "run";
+
+/// Extension of PID files
+pub const PID_EXTENSION: &str = "pid";
+
+/// Extension of lock files
+pub const LOCK_EXTENSION: &str = "lock";
+
+/// Depth below the app data directory searched for lock files
+const MAX_LOCK_SCAN_DEPTH: usize = 4;
+
+/// Length at which Linux truncates process names
+const TRUNCATED_NAME_LEN: usize = 15;
+
+/// Extension appended to a lock file's name for its guard
+const GUARD_EXTENSION: &str = "guard";
+
+/// Age after which a guard is considered left by a crashed process
+const GUARD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Suffix of the temporary files lock files are staged in
+static NEXT_STAGED_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Contents of a PID file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PidFile {
+    /// Plugin run by the process
+    pub plugin_id: String,
+    /// Process ID
+    pub pid: u32,
+    /// Program the process runs (e.g., `node`)
+    pub program: String,
+}
+
+impl PidFile {
+    /// Describe a plugin process
+    pub fn new(plugin_id: impl Into<String>, pid: u32, program: impl Into<String>) -> Self {
+        Self {
+            plugin_id: plugin_id.into(),
+            pid,
+            program: program.into(),
+        }
+    }
+
+    /// Path of a plugin's PID file
+    pub fn path(run_dir: &Path, plugin_id: &str) -> PathBuf {
+        run_dir.join(format!("{}.{}", plugin_id, PID_EXTENSION))
+    }
+
+    /// Write the PID file when the plugin process starts
+    pub fn write(&self, run_dir: &Path) -> Result<PathBuf, String> {
+        std::fs::create_dir_all(run_dir).map_err(|e| format!("Failed to create run directory: {}", e))?;
+
+        let path = Self::path(run_dir, &self.plugin_id);
+        let content =
+            serde_json::to_string(self).map_err(|e| format!("Failed to serialize PID file: {}", e))?;
+        std::fs::write(&path, content).map_err(|e| format!("Failed to write PID file: {}", e))?;
+        Ok(path)
+    }
+
+    /// Remove a plugin's PID file once its process has stopped
+    pub fn remove(run_dir: &Path, plugin_id: &str) -> Result<(), String> {
+        match std::fs::remove_file(Self::path(run_dir, plugin_id)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(format!("Failed to remove PID file: {}", e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// An exclusive lock on a resource, released when dropped
+///
+/// The lock file holds the owner's PID so a lock left by a crashed
+/// process can be told apart from one still held. It is written to a
+/// staging file first and hard-linked into place, so no other process ever
+/// sees a lock file without its owner. Processes acquiring the lock or
+/// removing it as stale hold its guard file meanwhile, so a stale lock is
+/// taken over by one process only.
+#[derive(Debug)]
+pub struct LockFile {
+    path: PathBuf,
+}
+
+impl LockFile {
+    /// Acquire a lock, taking it over if its owner is gone
+    ///
+    /// # Arguments
+    /// * `path` - Path of the lock file, conventionally ending in `.lock`
+    ///
+    /// # Returns
+    /// Err if another running process holds the lock
+    pub fn acquire(path: &Path) -> Result<Self, String> {
+        let staged = staging_path(path);
+        std::fs::write(&staged, std::process::id().to_string())
+            .map_err(|e| format!("Failed to write lock file: {}", e))?;
+        let acquired = Guard::acquire(path).and_then(|_guard| Self::link(&staged, path));
+        let _ = std::fs::remove_file(&staged);
+        acquired
+    }
+
+    /// Link a staged lock file into place, taking over a stale lock
+    fn link(staged: &Path, path: &Path) -> Result<Self, String> {
+        match std::fs::hard_link(staged, path) {
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                if !lock_is_stale(path) {
+                    return Err(format!("{} is locked by another process", path.display()));
+                }
+                std::fs::remove_file(path).map_err(|e| format!("Failed to remove stale lock: {}", e))?;
+                std::fs::hard_link(staged, path).map_err(|e| format!("Failed to create lock file: {}", e))?;
+            }
+            Err(e) => return Err(format!("Failed to create lock file: {}", e)),
+            Ok(()) => {}
+        }
+        Ok(Self { path: path.to_path_buf() })
+    }
+
+    /// Path of the lock file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for LockFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Exclusive right to replace or remove a lock file, released when dropped
+struct Guard {
+    path: PathBuf,
+}
+
+impl Guard {
+    /// Wait for the guard of a lock file
+    ///
+    /// The guard is only held while a lock file is checked and replaced,
+    /// so a guard older than `GUARD_TIMEOUT` was left by a crashed process.
+    fn acquire(lock_path: &Path) -> Result<Self, String> {
+        let mut name = lock_path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".{}", GUARD_EXTENSION));
+        let path = lock_path.with_file_name(name);
+
+        for _ in 0..1000 {
+            match std::fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(Self { path }),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let abandoned = std::fs::metadata(&path)
+                        .and_then(|metadata| metadata.modified())
+                        .ok()
+                        .and_then(|modified| modified.elapsed().ok())
+                        .is_some_and(|age| age > GUARD_TIMEOUT);
+                    if abandoned {
+                        let _ = std::fs::remove_file(&path);
+                    } else {
+                        std::thread::sleep(Duration::from_millis(1));
+                    }
+                }
+                Err(e) => return Err(format!("Failed to create lock guard: {}", e)),
+            }
+        }
+
+        Err(format!("{} is locked by another process", lock_path.display()))
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Remove a stale lock file, unless another process took the lock over first
+///
+/// # Returns
+/// Whether a stale lock was removed
+fn remove_stale_lock(path: &Path) -> Result<bool, String> {
+    let _guard = Guard::acquire(path)?;
+    if !lock_is_stale(path) {
+        return Ok(false);
+    }
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Unique sibling of a lock file, not ending in `.lock`
+fn staging_path(path: &Path) -> PathBuf {
+    let id = NEXT_STAGED_ID.fetch_add(1, Ordering::Relaxed);
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.{}.tmp", std::process::id(), id));
+    path.with_file_name(name)
+}
+
+/// Check if a lock file was left by a process that is no longer running
+///
+/// Unreadable lock files are considered stale: `LockFile` never leaves one
+/// empty, even while it is being acquired.
+pub fn lock_is_stale(path: &Path) -> bool {
+    let owner = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| content.trim().parse::<u32>().ok());
+
+    match owner {
+        Some(pid) => pid != std::process::id() && process_name(pid).is_none(),
+        None => true,
+    }
+}
+
+/// A plugin process killed because its launcher was gone
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanedProcess {
+    /// Plugin run by the process
+    pub plugin_id: String,
+    /// Process ID
+    pub pid: u32,
+    /// Program the process ran
+    pub program: String,
+}
+
+/// What the startup cleanup removed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupReport {
+    /// Plugin processes killed
+    pub killed: Vec<OrphanedProcess>,
+    /// PID files deleted
+    pub removed_pid_files: Vec<PathBuf>,
+    /// Stale lock files deleted
+    pub removed_locks: Vec<PathBuf>,
+    /// Problems that prevented part of the cleanup
+    pub errors: Vec<String>,
+}
+
+impl CleanupReport {
+    /// Check if nothing was left behind
+    pub fn is_empty(&self) -> bool {
+        self.killed.is_empty()
+            && self.removed_pid_files.is_empty()
+            && self.removed_locks.is_empty()
+            && self.errors.is_empty()
+    }
+}
+
+/// Kill orphaned plugin processes and remove stale PID and lock files
+///
+/// Must run at startup, before any plugin process is started: every PID
+/// file found belongs to a previous launcher session.
+///
+/// # Arguments
+/// * `app_data_dir` - Application data directory; PID files are read from
+///   its `run` directory and lock files searched for below it
+pub fn clean_stale_state(app_data_dir: &Path) -> CleanupReport {
+    let mut report = CleanupReport::default();
+
+    let run_dir = app_data_dir.join(RUN_DIR);
+    for path in files_with_extension(&run_dir, PID_EXTENSION, 0) {
+        clean_pid_file(&path, &mut report);
+    }
+
+    for path in files_with_extension(app_data_dir, LOCK_EXTENSION, MAX_LOCK_SCAN_DEPTH) {
+        if !lock_is_stale(&path) {
+            continue;
+        }
+        match remove_stale_lock(&path) {
+            Ok(true) => report.removed_locks.push(path),
+            Ok(false) => {}
+            Err(e) => report
+                .errors
+                .push(format!("Failed to remove stale lock {}: {}", path.display(), e)),
+        }
+    }
+
+    report
+}
+
+fn clean_pid_file(path: &Path, report: &mut CleanupReport) {
+    let pid_file = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<PidFile>(&content).ok());
+
+    if let Some(pid_file) = pid_file
+        && pid_file.pid != std::process::id()
+        && process_name(pid_file.pid).is_some_and(|running| same_program(&pid_file.program, &running))
+    {
+        if let Err(e) = kill_process(pid_file.pid) {
+            // Keep the PID file so the next startup tries again
+            report.errors.push(format!(
+                "Failed to kill process {} of plugin '{}': {}",
+                pid_file.pid, pid_file.plugin_id, e
+            ));
+            return;
+        }
+        report.killed.push(OrphanedProcess {
+            plugin_id: pid_file.plugin_id,
+            pid: pid_file.pid,
+            program: pid_file.program,
+        });
+    }
+
+    match std::fs::remove_file(path) {
+        Ok(()) => report.removed_pid_files.push(path.to_path_buf()),
+        Err(e) => report
+            .errors
+            .push(format!("Failed to remove PID file {}: {}", path.display(), e)),
+    }
+}
+
+/// Files with an extension in a directory and, down to `depth`, its subdirectories
+fn files_with_extension(dir: &Path, extension: &str, depth: usize) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files = Vec::new();
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+
+        // Symlinks are skipped so the scan never leaves the data directory
+        if file_type.is_dir() && depth > 0 {
+            files.extend(files_with_extension(&path, extension, depth - 1));
+        } else if file_type.is_file() && path.extension().is_some_and(|ext| ext == extension) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Check if a running process name matches the program recorded for it
+fn same_program(recorded: &str, running: &str) -> bool {
+    // Split on both separators: Windows paths can be recorded on any host
+    let stem = |program: &str| {
+        let name = program.rsplit(['/', '\\']).next().unwrap_or(program).to_lowercase();
+        name.trim_end_matches(".exe").to_string()
+    };
+    let (recorded, running) = (stem(recorded), stem(running));
+
+    recorded == running || (running.len() == TRUNCATED_NAME_LEN && recorded.starts_with(&running))
+}
+
+/// Get the name of a running process
+///
+/// # Returns
+/// None if no process has this PID
+#[cfg(unix)]
+pub fn process_name(pid: u32) -> Option<String> {
+    let output = Command::new("ps")
+        .args(["-p", &pid.to_string(), "-o", "comm="])
+        .output()
+        .ok()?;
+
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !name.is_empty()).then_some(name)
+}
+
+/// Get the name of a running process
+///
+/// # Returns
+/// None if no process has this PID
+#[cfg(windows)]
+pub fn process_name(pid: u32) -> Option<String> {
+    let output = Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+        .output()
+        .ok()?;
+
+    // `"node.exe","1234",...`, or an informational line without quotes
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let name = stdout.trim().strip_prefix('"')?.split('"').next()?;
+    Some(name.to_string())
+}
+
+/// Forcibly terminate a process
+#[cfg(unix)]
+pub fn kill_process(pid: u32) -> Result<(), String> {
+    run_kill(Command::new("kill").args(["-KILL", &pid.to_string()]))
+}
+
+/// Forcibly terminate a process and its children
+#[cfg(windows)]
+pub fn kill_process(pid: u32) -> Result<(), String> {
+    run_kill(Command::new("taskkill").args(["/F", "/T", "/PID", &pid.to_string()]))
+}
+
+fn run_kill(command: &mut Command) -> Result<(), String> {
+    let output = command.output().map_err(|e| format!("Failed to run kill command: {}", e))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_program_names_match_truncated_names() {
+        assert!(same_program("/usr/bin/node", "node"));
+        assert!(same_program("Node.exe", "C:\\Program Files\\nodejs\\node.exe"));
+        assert!(same_program("volt-plugin-host-worker", "volt-plugin-hos"));
+        assert!(!same_program("node", "python3"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_orphans_and_stale_locks_are_cleaned() {
+        let temp_dir = std::env::temp_dir().join("volt_test_janitor");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let run_dir = temp_dir.join(RUN_DIR);
+        let plugin_dir = temp_dir.join("plugins/indexer");
+        std::fs::create_dir_all(&plugin_dir).unwrap();
+
+        let mut orphan = Command::new("sleep").arg("30").spawn().unwrap();
+        PidFile::new("indexer", orphan.id(), "sleep").write(&run_dir).unwrap();
+
+        let mut finished = Command::new("true").spawn().unwrap();
+        finished.wait().unwrap();
+        PidFile::new("weather", finished.id(), "node").write(&run_dir).unwrap();
+
+        // A live process that isn't the recorded program reused the PID
+        let mut reused = Command::new("sleep").arg("30").spawn().unwrap();
+        PidFile::new("weather-v2", reused.id(), "node").write(&run_dir).unwrap();
+
+        std::fs::write(plugin_dir.join("index.lock"), finished.id().to_string()).unwrap();
+        let held = LockFile::acquire(&plugin_dir.join("db.lock")).unwrap();
+        assert!(LockFile::acquire(held.path()).is_err());
+
+        let report = clean_stale_state(&temp_dir);
+        assert_eq!(report.killed.len(), 1);
+        assert_eq!(report.killed[0].plugin_id, "indexer");
+        assert!(orphan.wait().unwrap().code().is_none());
+        assert!(reused.try_wait().unwrap().is_none());
+        reused.kill().unwrap();
+        let _ = reused.wait();
+        assert_eq!(report.removed_pid_files.len(), 3);
+        assert_eq!(report.removed_locks, vec![plugin_dir.join("index.lock")]);
+        assert!(held.path().exists());
+        assert!(report.errors.is_empty());
+
+        drop(held);
+        assert!(clean_stale_state(&temp_dir).is_empty());
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_concurrent_acquirers_get_one_lock() {
+        let temp_dir = std::env::temp_dir().join("volt_test_janitor_race");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let path = temp_dir.join("db.lock");
+
+        let mut finished = Command::new("true").spawn().unwrap();
+        finished.wait().unwrap();
+
+        for stale in [false, true] {
+            if stale {
+                std::fs::write(&path, finished.id().to_string()).unwrap();
+            }
+            let barrier = std::sync::Barrier::new(8);
+            let locks: Vec<Result<LockFile, String>> = std::thread::scope(|scope| {
+                let handles: Vec<_> = (0..8)
+                    .map(|_| {
+                        scope.spawn(|| {
+                            barrier.wait();
+                            LockFile::acquire(&path)
+                        })
+                    })
+                    .collect();
+                handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+            });
+
+            assert_eq!(locks.iter().filter(|lock| lock.is_ok()).count(), 1, "{:?}", locks);
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), std::process::id().to_string());
+            drop(locks);
+            assert!(!path.exists());
+        }
+        assert_eq!(std::fs::read_dir(&temp_dir).unwrap().count(), 0);
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
@@ -0,0 +1,210 @@
+/// Keyboard events for interactive plugins
+///
+/// Results cover most interactions: the launcher moves the selection with
+/// the arrow keys and executes the selected result with Enter. Some plugins
+/// want more, e.g. a process monitor expanding a row with Right or a REPL
+/// completing with Tab. Such a plugin opts in per view: executing one of its
+/// results returns `ExecuteOutcome::requery(...).capture_keys()`, and the
+/// plugin owns the drill-down view opened by that query. While it does,
+/// `PluginRegistry::dispatch_key_event` delivers key presses to its
+/// `Plugin::on_key_event` hook.
+///
+/// Focus handoff:
+/// - at most one plugin owns the view, and only it receives key events
+/// - Escape and the quick-select keys (`Mod+1` to `Mod+9`) always belong to
+///   the launcher and are never delivered; Escape leaves the view
+/// - keys the plugin answers `KeyResponse::Ignored` get the launcher's usual
+///   handling
+/// - the plugin hands focus back with `KeyResponse::Release`; the launcher
+///   takes it back when the view closes (`PluginRegistry::release_key_focus`)
+///   or another view opens, and the registry when the plugin fails, panics,
+///   is disabled or unregistered
+use crate::result::PluginResult;
+use serde::{Deserialize, Serialize};
+
+/// A key, independent of the keyboard layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Tab,
+    Enter,
+    Escape,
+    Backspace,
+    Delete,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    /// Printable character, as typed with the current layout
+    Char(char),
+    /// Function key, e.g. 5 for F5
+    Function(u8),
+}
+
+/// Modifier keys held during a key press
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(default, rename_all = "camelCase")]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    /// Command on macOS, the Windows key elsewhere
+    pub meta: bool,
+}
+
+impl Modifiers {
+    /// Check if no modifier is held
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Check if the platform's primary modifier (`Mod`) is held
+    ///
+    /// Command on macOS, Ctrl elsewhere.
+    pub fn has_mod(&self) -> bool {
+        if cfg!(target_os = "macos") {
+            self.meta
+        } else {
+            self.ctrl
+        }
+    }
+}
+
+/// A key press in the launcher
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct KeyEvent {
+    /// The key pressed
+    pub key: Key,
+    /// Modifier keys held
+    #[serde(default)]
+    pub modifiers: Modifiers,
+    /// Generated by holding the key down
+    #[serde(default)]
+    pub repeat: bool,
+    /// ID of the selected result, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selected: Option<String>,
+}
+
+impl KeyEvent {
+    /// Create a key press without modifiers
+    pub fn new(key: Key) -> Self {
+        Self {
+            key,
+            modifiers: Modifiers::default(),
+            repeat: false,
+            selected: None,
+        }
+    }
+
+    /// Set the modifier keys held
+    pub fn with_modifiers(mut self, modifiers: Modifiers) -> Self {
+        self.modifiers = modifiers;
+        self
+    }
+
+    /// Set the selected result
+    pub fn with_selected(mut self, result_id: impl Into<String>) -> Self {
+        self.selected = Some(result_id.into());
+        self
+    }
+
+    /// Check if the key always belongs to the launcher
+    ///
+    /// Escape leaves the view, and `Mod+1` to `Mod+9` execute results by
+    /// position, so plugins never see them.
+    pub fn is_reserved(&self) -> bool {
+        match self.key {
+            Key::Escape => true,
+            Key::Char(c) => self.modifiers.has_mod() && ('1'..='9').contains(&c),
+            _ => false,
+        }
+    }
+}
+
+/// What the launcher does with a key event
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum KeyResponse {
+    /// Not handled; the launcher handles the key as usual
+    #[default]
+    Ignored,
+    /// Handled; the launcher does nothing else
+    Handled,
+    /// Handled; the view shows these results in place of the current ones
+    Update { results: Vec<PluginResult> },
+    /// Handled; the launcher runs another query, keeping the view
+    Requery { query: String },
+    /// Not handled, and the plugin hands focus back to the launcher
+    Release,
+}
+
+impl KeyResponse {
+    /// Check if the plugin consumed the key
+    pub fn is_handled(&self) -> bool {
+        !matches!(self, KeyResponse::Ignored | KeyResponse::Release)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_events() {
+        let primary = if cfg!(target_os = "macos") {
+            Modifiers {
+                meta: true,
+                ..Modifiers::default()
+            }
+        } else {
+            Modifiers {
+                ctrl: true,
+                ..Modifiers::default()
+            }
+        };
+        assert!(KeyEvent::new(Key::Escape).is_reserved());
+        assert!(
+            KeyEvent::new(Key::Char('3'))
+                .with_modifiers(primary)
+                .is_reserved()
+        );
+        assert!(!KeyEvent::new(Key::Char('3')).is_reserved());
+        assert!(!KeyEvent::new(Key::Tab).is_reserved());
+
+        let event: KeyEvent = serde_json::from_value(
+            serde_json::json!({ "key": { "char": "k" }, "modifiers": { "shift": true } }),
+        )
+        .unwrap();
+        assert_eq!(event.key, Key::Char('k'));
+        assert!(event.modifiers.shift && !event.repeat);
+        assert_eq!(
+            serde_json::to_value(KeyEvent::new(Key::PageDown).with_selected("pid-42")).unwrap(),
+            serde_json::json!({
+                "key": "pageDown",
+                "modifiers": { "shift": false, "ctrl": false, "alt": false, "meta": false },
+                "repeat": false,
+                "selected": "pid-42",
+            })
+        );
+
+        assert_eq!(
+            serde_json::to_value(KeyResponse::Requery {
+                query: "ps firefox".to_string()
+            })
+            .unwrap(),
+            serde_json::json!({ "type": "requery", "query": "ps firefox" })
+        );
+        assert!(!KeyResponse::default().is_handled());
+        assert!(!KeyResponse::Release.is_handled());
+    }
+}
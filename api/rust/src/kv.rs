@@ -0,0 +1,127 @@
+/// Per-plugin key-value store
+///
+/// Small structured data (snippets, bookmarks, counters) that doesn't
+/// warrant a database. Each plugin's entries are kept in a JSON file in its
+/// data directory and accessed through `VoltPluginAPI::kv_get` and friends.
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// File of a plugin's data directory holding its entries
+pub const KV_FILE: &str = "kv.json";
+
+/// Longest accepted key, in bytes
+pub const MAX_KEY_LEN: usize = 256;
+
+/// Entries of one plugin, sorted by key
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct KvStore {
+    entries: BTreeMap<String, Value>,
+}
+
+impl KvStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a store from disk, or an empty store if the file doesn't exist
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::new());
+        }
+
+        let content =
+            std::fs::read_to_string(path).map_err(|e| format!("Failed to read key-value store: {}", e))?;
+
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse key-value store: {}", e))
+    }
+
+    /// Save the store to disk
+    ///
+    /// Written to a temporary file first so a crash never truncates it.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string(self)
+            .map_err(|e| format!("Failed to serialize key-value store: {}", e))?;
+
+        let partial = path.with_extension("partial");
+        std::fs::write(&partial, content).map_err(|e| format!("Failed to write key-value store: {}", e))?;
+        std::fs::rename(&partial, path).map_err(|e| format!("Failed to write key-value store: {}", e))
+    }
+
+    /// Get the value of a key
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.entries.get(key)
+    }
+
+    /// Set the value of a key
+    pub fn set(&mut self, key: &str, value: Value) -> Result<(), String> {
+        validate_key(key)?;
+        self.entries.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    /// Remove a key
+    ///
+    /// # Returns
+    /// The removed value, if the key existed
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        self.entries.remove(key)
+    }
+
+    /// Get the keys starting with a prefix, sorted
+    pub fn keys(&self, prefix: &str) -> Vec<String> {
+        self.entries
+            .range(prefix.to_string()..)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+}
+
+/// Check that a key is non-empty, short and printable
+pub fn validate_key(key: &str) -> Result<(), String> {
+    if key.is_empty() {
+        return Err("Key cannot be empty".to_string());
+    }
+
+    if key.len() > MAX_KEY_LEN {
+        return Err(format!("Key too long (max {} characters)", MAX_KEY_LEN));
+    }
+
+    if key.chars().any(char::is_control) {
+        return Err("Key cannot contain control characters".to_string());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_round_trip() {
+        let temp_dir = std::env::temp_dir().join("volt_test_kv");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let path = temp_dir.join(KV_FILE);
+
+        let mut store = KvStore::load(&path).unwrap();
+        store.set("snippet:sig", serde_json::json!({"body": "Regards"})).unwrap();
+        store.set("snippet:addr", serde_json::json!("1 Main St")).unwrap();
+        store.set("settings", serde_json::json!(true)).unwrap();
+        assert!(store.set("", Value::Null).is_err());
+        assert!(store.set("a\nb", Value::Null).is_err());
+        store.save(&path).unwrap();
+
+        let mut store = KvStore::load(&path).unwrap();
+        assert_eq!(store.keys("snippet:"), vec!["snippet:addr", "snippet:sig"]);
+        assert_eq!(store.remove("settings"), Some(serde_json::json!(true)));
+        assert_eq!(store.get("settings"), None);
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
@@ -2,8 +2,167 @@
 //!
 //! This crate provides the core API for building Volt launcher plugins.
 
+pub mod actions;
+pub mod aggregator;
+pub mod annotations;
 pub mod api;
+pub mod api_trace;
+pub mod apps;
+pub mod assets;
+pub mod audit;
+pub mod backends;
+pub mod builder;
+pub mod builtins;
+pub mod bundles;
+pub mod cancel;
+pub mod canonical;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+pub mod clock;
+pub mod compat;
+pub mod crash;
+#[cfg(feature = "database")]
+pub mod database;
+pub mod desktop;
+pub mod devmode;
+#[cfg(feature = "diagnostics")]
+pub mod diagnostics;
+pub mod diff;
+pub mod discovery;
+pub mod dispatch;
+#[cfg(feature = "download")]
+pub mod download;
+pub mod elevation;
+pub mod events;
+pub mod export;
+pub mod extensions;
+pub mod features;
+pub mod feeds;
+#[cfg(feature = "download")]
+pub mod http_cache;
+#[cfg(feature = "fulltext")]
+pub mod fulltext;
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod fuzz;
+pub mod i18n;
+pub mod identity;
+#[cfg(feature = "imaging")]
+pub mod imaging;
+pub mod index;
+pub mod input;
+pub mod invalidation;
+pub mod intents;
+pub mod janitor;
+pub mod keys;
+pub mod kv;
+pub mod locks;
+pub mod logging;
+#[cfg(feature = "macos")]
+pub mod macos;
+pub mod manifest;
+pub mod marketplace;
+pub mod matching;
+pub mod middleware;
+pub mod native;
+pub mod network;
+pub mod no_results;
+pub mod notifications;
+pub mod open_with;
+pub mod outcome;
+pub mod patterns;
+pub mod platform;
+pub mod plugin;
+pub mod power;
+pub mod preflight;
+#[cfg(feature = "download")]
+pub mod previews;
+#[cfg(feature = "process")]
+pub mod process;
+pub mod protocol;
 pub mod registry;
+#[cfg(feature = "remote")]
+pub mod remote;
+#[cfg(feature = "replay")]
+pub mod replay;
+pub mod resources;
+pub mod result;
+#[cfg(feature = "isolation")]
+pub mod runtime;
+pub mod selection;
+pub mod session;
+pub mod settings;
+pub mod spell;
+pub mod startup;
+pub mod stats;
+pub mod suggestions;
+#[cfg(feature = "sync")]
+pub mod sync;
+#[cfg(feature = "tauri-bindings")]
+pub mod tauri_bindings;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "ts-bindings")]
+pub mod typescript;
+pub mod warmup;
+pub mod wasm;
+pub mod watchdog;
+pub mod windows;
+pub mod wire;
 
-pub use api::VoltPluginAPI;
-pub use registry::PluginRegistry;
+pub use actions::RecentAction;
+pub use aggregator::{MergedResults, ResultAggregator, StalenessDecay};
+pub use annotations::{Annotation, AnnotationReport};
+pub use api::{CapabilityBroker, ManifestCapabilities, VoltPluginAPI};
+pub use api_trace::{ApiTrace, ApiTraceMode};
+pub use apps::{AppTarget, ShellLink};
+pub use audit::{AuditEntry, AuditOutcome};
+pub use backends::{NoopBackend, PlatformBackend, ShareBackend};
+pub use builder::{DirectoryLayout, VoltPluginAPIBuilder};
+pub use bundles::{BundleState, PluginBundle};
+pub use cancel::CancellationToken;
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use compat::{ApiVersion, PluginV1};
+pub use crash::{CrashCause, CrashRecorder, CrashReport};
+pub use devmode::{DevWatcher, PluginLoader};
+pub use diff::{DiffDecoder, DiffEncoder, ResultDiff};
+pub use discovery::{DiscoveredPlugin, DiscoveryOutcome, PluginBackend, PluginLoaders};
+pub use elevation::{ElevationConfirmer, ElevationRequest};
+pub use export::{ExportDestination, ExportFormat};
+pub use dispatch::{AdaptiveConfig, AdaptiveController, DispatchBudget, DispatchPass, DispatchScheduler, SchedulerConfig};
+pub use extensions::{
+    Annotator, PluginExt, Preview, Previewer, SendHandler, SendTarget, SettingsProvider, Suggester, UriHandler,
+};
+pub use features::{FeatureSet, HostFeature};
+pub use feeds::DataFeed;
+pub use i18n::Messages;
+pub use identity::PluginRename;
+pub use index::{IndexBatch, IndexDoc, IndexHit};
+pub use invalidation::{CacheEntry, CacheLayer, DataChange, Dependency, InvalidationBus};
+pub use keys::{Key, KeyEvent, KeyResponse, Modifiers};
+pub use logging::{Diagnostic, DiagnosticsSink};
+pub use manifest::{ManifestDiagnostic, PluginInfo, PluginManifest};
+pub use matching::Tokenizer;
+pub use middleware::{BeforeRouting, DispatchMiddleware};
+pub use network::{NetworkGrantRequest, NetworkPolicy};
+pub use no_results::{NoResultsNote, NoResultsReason};
+pub use notifications::Notification;
+pub use open_with::OpenWithApp;
+pub use patterns::{Regex, RegexCache, RegexLimits};
+pub use outcome::{ExecuteOutcome, Toast, ToastStyle};
+pub use platform::Platform;
+pub use plugin::{Plugin, QueryContext};
+pub use power::{PowerMode, PowerMonitor, PowerPolicy, PowerState, SystemPowerMonitor};
+pub use preflight::{PreflightConfig, PreflightIssue, PreflightReport};
+pub use registry::{PluginDescriptor, PluginRegistry, PluginSnapshot, PluginStatus, RegistryEvent};
+pub use resources::{ResolvedResource, VirtualPath};
+pub use result::{Accessibility, AccessibilityRole, Accessory, CommandSpec, IntentKind, KeyHint, PluginResult, ResultAction, ResultActions, ResultIntent};
+pub use selection::{Selection, SelectionReader};
+pub use session::{SessionLimits, SessionScope, SessionStore};
+pub use settings::{Control, SettingsField, SettingsPage, SettingsSection};
+pub use spell::{Correction, SpellCorrector};
+pub use startup::{StartupPhase, StartupReport};
+pub use stats::{PluginStats, UsageReport, UsageStats};
+pub use suggestions::KeywordSuggester;
+pub use warmup::{WarmupScheduler, WarmupStatus, WarmupTask, WarmupTaskStatus};
+pub use watchdog::{HangReport, Watchdog};
+pub use windows::{WindowEvent, WindowInfo, WindowManager};
@@ -0,0 +1,129 @@
+/// Lock poisoning recovery
+///
+/// A panic while a `RwLock` is held poisons it, and with plain
+/// `.read().map_err(...)?` every later call fails until the launcher
+/// restarts. The registry and API acquire their locks through these helpers
+/// instead: a poisoned lock has its state repaired through [`Recover`], the
+/// poison flag cleared, and a diagnostic emitted.
+use crate::logging;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Number of poisoned locks recovered since startup
+static RECOVERIES: AtomicU64 = AtomicU64::new(0);
+
+/// State that can be repaired after a panic interrupted its update
+pub trait Recover {
+    /// Bring the state back to a consistent value
+    ///
+    /// Derived data (caches, scan results) should be cleared so it is
+    /// rebuilt; data that cannot be rebuilt should be kept.
+    fn recover(&mut self);
+}
+
+impl<K, V> Recover for HashMap<K, V> {
+    fn recover(&mut self) {
+        // Std maps are left consistent by a panic, so their entries are kept
+    }
+}
+
+impl<T> Recover for HashSet<T> {
+    fn recover(&mut self) {
+        // Sets are maps without values
+    }
+}
+
+impl<T> Recover for Vec<T> {
+    fn recover(&mut self) {
+        // Same as maps: a panic never leaves a vector half-updated
+    }
+}
+
+/// Number of poisoned locks recovered since startup
+pub fn poison_recoveries() -> u64 {
+    RECOVERIES.load(Ordering::Relaxed)
+}
+
+/// Acquire a read lock, recovering it if poisoned
+///
+/// # Arguments
+/// * `lock` - The lock
+/// * `name` - Name of the lock in diagnostics
+pub fn read<'a, T: Recover>(lock: &'a RwLock<T>, name: &str) -> RwLockReadGuard<'a, T> {
+    if lock.is_poisoned() {
+        drop(write(lock, name));
+    }
+
+    // A panic between the recovery above and here poisons the lock again;
+    // the state was just repaired, so reading it is still safe
+    lock.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Acquire a write lock, recovering it if poisoned
+///
+/// # Arguments
+/// * `lock` - The lock
+/// * `name` - Name of the lock in diagnostics
+pub fn write<'a, T: Recover>(lock: &'a RwLock<T>, name: &str) -> RwLockWriteGuard<'a, T> {
+    match lock.write() {
+        Ok(guard) => guard,
+        Err(poisoned) => {
+            let mut guard = poisoned.into_inner();
+            guard.recover();
+            lock.clear_poison();
+
+            RECOVERIES.fetch_add(1, Ordering::Relaxed);
+            logging::warn(
+                "locks",
+                &format!("Recovered {} after a panic; derived state was reset", name),
+            );
+            guard
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[derive(Default)]
+    struct Counter {
+        value: u32,
+        cache: Option<u32>,
+    }
+
+    impl Recover for Counter {
+        fn recover(&mut self) {
+            self.cache = None;
+        }
+    }
+
+    #[test]
+    fn test_poisoned_lock_is_recovered() {
+        let lock = Arc::new(RwLock::new(Counter::default()));
+
+        let poisoner = lock.clone();
+        let _ = std::thread::spawn(move || {
+            let mut counter = poisoner.write().unwrap();
+            counter.value = 1;
+            counter.cache = Some(1);
+            panic!("bug while holding the lock");
+        })
+        .join();
+        assert!(lock.is_poisoned());
+
+        let before = poison_recoveries();
+        {
+            let counter = read(&lock, "counter");
+            assert_eq!(counter.value, 1);
+            assert_eq!(counter.cache, None);
+        }
+        assert!(!lock.is_poisoned());
+        assert!(poison_recoveries() > before);
+
+        write(&lock, "counter").value = 2;
+        assert_eq!(read(&lock, "counter").value, 2);
+    }
+}
@@ -0,0 +1,278 @@
+/// Diagnostics emitted by the registry, the API and plugin logs
+///
+/// Status lines used to be printed to stdout, which corrupts hosts that
+/// speak a protocol over stdout. Everything now goes through [`emit`]:
+/// messages are written to stderr unless quiet mode is on, and handed to the
+/// host's [`DiagnosticsSink`] if one is installed.
+///
+/// Those two settings are process-wide. Each `VoltPluginAPI` and
+/// `PluginRegistry` emits through its own [`Diagnostics`], which falls back
+/// to them unless the host isolated it; only code running for no particular
+/// instance uses them directly.
+///
+/// ```ignore
+/// volt_plugin_api::logging::set_quiet(true);
+/// volt_plugin_api::logging::set_sink(Arc::new(|diagnostic: &Diagnostic| {
+///     host_log(diagnostic.level, diagnostic.source, diagnostic.message);
+/// }));
+/// ```
+use crate::api::LogLevel;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// Whether console output is suppressed
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Sink installed by the host
+static SINK: RwLock<Option<Arc<dyn DiagnosticsSink>>> = RwLock::new(None);
+
+/// A diagnostic message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Diagnostic<'a> {
+    /// Severity of the message
+    pub level: LogLevel,
+    /// Plugin ID, or a subsystem such as `registry`
+    pub source: &'a str,
+    /// The message
+    pub message: &'a str,
+}
+
+/// Receives diagnostics on behalf of the host
+///
+/// Implemented for closures taking a `&Diagnostic`.
+pub trait DiagnosticsSink: Send + Sync {
+    /// Handle a diagnostic; must not block
+    fn emit(&self, diagnostic: &Diagnostic<'_>);
+}
+
+impl<F> DiagnosticsSink for F
+where
+    F: Fn(&Diagnostic<'_>) + Send + Sync,
+{
+    fn emit(&self, diagnostic: &Diagnostic<'_>) {
+        self(diagnostic)
+    }
+}
+
+/// Suppress or restore console output
+///
+/// The installed sink keeps receiving diagnostics in quiet mode.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Check if console output is suppressed
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Install the sink receiving every diagnostic, replacing the previous one
+pub fn set_sink(sink: Arc<dyn DiagnosticsSink>) {
+    *SINK.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(sink);
+}
+
+/// Remove the installed sink
+pub fn clear_sink() {
+    *SINK.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+}
+
+/// Emit a diagnostic
+///
+/// # Arguments
+/// * `level` - Severity of the message
+/// * `source` - Plugin ID, or a subsystem such as `registry`
+/// * `message` - The message
+pub fn emit(level: LogLevel, source: &str, message: &str) {
+    let diagnostic = Diagnostic { level, source, message };
+
+    if !is_quiet() {
+        eprintln!("[{}] {}: {}", source, level.as_str(), message);
+    }
+
+    // Cloned so a sink emitting diagnostics itself doesn't deadlock
+    let sink = SINK.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+    if let Some(sink) = sink {
+        sink.emit(&diagnostic);
+    }
+}
+
+/// Emit an informational diagnostic
+pub fn info(source: &str, message: &str) {
+    emit(LogLevel::Info, source, message);
+}
+
+/// Emit a warning
+pub fn warn(source: &str, message: &str) {
+    emit(LogLevel::Warn, source, message);
+}
+
+/// Emit a debug diagnostic
+pub fn debug(source: &str, message: &str) {
+    emit(LogLevel::Debug, source, message);
+}
+
+// ========== Instance Diagnostics ==========
+
+/// Diagnostics of one API or registry instance
+///
+/// `Diagnostics::process()`, the default, is the explicit fallback to the
+/// process-wide [`set_sink`] and [`set_quiet`]. Hosts embedding several
+/// registries in one process give each isolated diagnostics instead, which
+/// never read the process-wide settings:
+///
+/// ```ignore
+/// let diagnostics = Diagnostics::new(tenant_sink).with_quiet(true);
+/// let registry = PluginRegistry::new().with_diagnostics(diagnostics);
+/// ```
+#[derive(Clone, Default)]
+pub struct Diagnostics {
+    /// Settings of isolated diagnostics, `None` for the process-wide ones
+    isolated: Option<Isolated>,
+}
+
+/// Sink and console output of isolated diagnostics
+#[derive(Clone, Default)]
+struct Isolated {
+    sink: Option<Arc<dyn DiagnosticsSink>>,
+    quiet: bool,
+}
+
+impl Diagnostics {
+    /// Use the process-wide sink and quiet mode
+    pub fn process() -> Self {
+        Self::default()
+    }
+
+    /// Write to stderr only, whatever the process-wide settings
+    pub fn isolated() -> Self {
+        Self {
+            isolated: Some(Isolated::default()),
+        }
+    }
+
+    /// Send diagnostics to a sink, whatever the process-wide settings
+    pub fn new(sink: Arc<dyn DiagnosticsSink>) -> Self {
+        Self::isolated().with_sink(sink)
+    }
+
+    /// Send diagnostics to a sink, replacing the previous one
+    ///
+    /// Detaches the diagnostics from the process-wide settings.
+    pub fn with_sink(mut self, sink: Arc<dyn DiagnosticsSink>) -> Self {
+        self.isolated.get_or_insert_with(Isolated::default).sink = Some(sink);
+        self
+    }
+
+    /// Suppress or restore console output
+    ///
+    /// Detaches the diagnostics from the process-wide settings.
+    pub fn with_quiet(mut self, quiet: bool) -> Self {
+        self.isolated.get_or_insert_with(Isolated::default).quiet = quiet;
+        self
+    }
+
+    /// Check if the process-wide settings are ignored
+    pub fn is_isolated(&self) -> bool {
+        self.isolated.is_some()
+    }
+
+    /// Emit a diagnostic, see [`emit`]
+    pub fn emit(&self, level: LogLevel, source: &str, message: &str) {
+        let Some(isolated) = &self.isolated else {
+            emit(level, source, message);
+            return;
+        };
+        if !isolated.quiet {
+            eprintln!("[{}] {}: {}", source, level.as_str(), message);
+        }
+        if let Some(sink) = &isolated.sink {
+            sink.emit(&Diagnostic { level, source, message });
+        }
+    }
+
+    /// Emit an informational diagnostic
+    pub fn info(&self, source: &str, message: &str) {
+        self.emit(LogLevel::Info, source, message);
+    }
+
+    /// Emit a warning
+    pub fn warn(&self, source: &str, message: &str) {
+        self.emit(LogLevel::Warn, source, message);
+    }
+
+    /// Emit a debug diagnostic
+    pub fn debug(&self, source: &str, message: &str) {
+        self.emit(LogLevel::Debug, source, message);
+    }
+}
+
+impl std::fmt::Debug for Diagnostics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Diagnostics")
+            .field("isolated", &self.isolated.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Held by tests changing the process-wide settings
+    pub(crate) static PROCESS_SETTINGS: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn test_sink_receives_diagnostics() {
+        let _settings = PROCESS_SETTINGS.lock().unwrap_or_else(|p| p.into_inner());
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let collected = received.clone();
+        set_sink(Arc::new(move |diagnostic: &Diagnostic<'_>| {
+            // Other tests emit concurrently through the same sink
+            if diagnostic.source == "logging-test" {
+                collected
+                    .lock()
+                    .unwrap()
+                    .push((diagnostic.level, diagnostic.message.to_string()));
+            }
+        }));
+
+        warn("logging-test", "disk almost full");
+        info("logging-test", "indexed 3 files");
+        clear_sink();
+        info("logging-test", "not delivered");
+
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![
+                (LogLevel::Warn, "disk almost full".to_string()),
+                (LogLevel::Info, "indexed 3 files".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_isolated_diagnostics_ignore_process_settings() {
+        let _settings = PROCESS_SETTINGS.lock().unwrap_or_else(|p| p.into_inner());
+        let collector = |received: &Arc<Mutex<Vec<String>>>| -> Arc<dyn DiagnosticsSink> {
+            let collected = received.clone();
+            Arc::new(move |diagnostic: &Diagnostic<'_>| {
+                if diagnostic.source == "isolation-test" {
+                    collected.lock().unwrap().push(diagnostic.message.to_string());
+                }
+            })
+        };
+        let process_wide = Arc::new(Mutex::new(Vec::new()));
+        let own = Arc::new(Mutex::new(Vec::new()));
+        set_sink(collector(&process_wide));
+
+        let isolated = Diagnostics::new(collector(&own)).with_quiet(true);
+        assert!(isolated.is_isolated());
+        isolated.warn("isolation-test", "tenant only");
+        Diagnostics::process().warn("isolation-test", "process-wide");
+        clear_sink();
+
+        assert_eq!(*own.lock().unwrap(), vec!["tenant only".to_string()]);
+        assert_eq!(*process_wide.lock().unwrap(), vec!["process-wide".to_string()]);
+    }
+}
@@ -0,0 +1,215 @@
+/// macOS automation bridge
+///
+/// Runs Shortcuts and AppleScript on behalf of plugins, so automation
+/// plugins don't each need their own `osascript` plumbing. Plugins call
+/// these through `VoltPluginAPI::run_shortcut` and
+/// `VoltPluginAPI::run_applescript`, which require the `execute_commands`
+/// permission.
+///
+/// AppleScript sources are checked against a `ScriptPolicy` before they
+/// run: by default scripts may drive applications but cannot escape to the
+/// shell or load other scripts.
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Default time a script or shortcut may run before it is killed
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Largest AppleScript source accepted, in bytes
+pub const MAX_SCRIPT_SIZE: usize = 64 * 1024;
+
+/// Constructs that escape the AppleScript sandbox
+///
+/// Matched case-insensitively with whitespace collapsed.
+const SHELL_CONSTRUCTS: &[&str] = &["do shell script"];
+const DYNAMIC_CONSTRUCTS: &[&str] = &["run script", "load script", "store script"];
+
+/// What AppleScript sources are allowed to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScriptPolicy {
+    /// Allow `do shell script`
+    pub allow_shell: bool,
+    /// Allow raw Apple event codes (`«event ...»`) and loading other scripts
+    pub allow_dynamic: bool,
+    /// Time the script may run before it is killed
+    pub timeout: Duration,
+}
+
+impl Default for ScriptPolicy {
+    fn default() -> Self {
+        Self {
+            allow_shell: false,
+            allow_dynamic: false,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+impl ScriptPolicy {
+    /// Check an AppleScript source against the policy
+    pub fn check(&self, source: &str) -> Result<(), String> {
+        if source.len() > MAX_SCRIPT_SIZE {
+            return Err(format!("Script exceeds {} bytes", MAX_SCRIPT_SIZE));
+        }
+
+        let normalized = source
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+
+        let mut denied: Vec<&str> = Vec::new();
+        if !self.allow_shell {
+            denied.extend(SHELL_CONSTRUCTS);
+        }
+        if !self.allow_dynamic {
+            denied.extend(DYNAMIC_CONSTRUCTS);
+            if normalized.contains('«') {
+                return Err("Script uses raw Apple event codes, which are not allowed".to_string());
+            }
+        }
+
+        match denied.into_iter().find(|construct| normalized.contains(construct)) {
+            Some(construct) => Err(format!("Script uses '{}', which is not allowed", construct)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Run an AppleScript and capture its result
+///
+/// # Arguments
+/// * `source` - AppleScript source
+/// * `policy` - What the script may do
+///
+/// # Returns
+/// The value of the script's last statement, as printed by `osascript`
+pub fn run_applescript(source: &str, policy: &ScriptPolicy) -> Result<String, String> {
+    ensure_macos()?;
+    policy.check(source)?;
+
+    // The source is piped rather than passed as an argument so it never
+    // shows up in process listings or hits argument length limits
+    run_command(Command::new("osascript"), Some(source.as_bytes()), policy.timeout)
+}
+
+/// Run a Shortcut from the Shortcuts app and capture its output
+///
+/// # Arguments
+/// * `name` - Name of the shortcut
+/// * `input` - Text passed to the shortcut as input, if any
+/// * `timeout` - Time the shortcut may run before it is killed
+///
+/// # Returns
+/// The shortcut's output as text
+pub fn run_shortcut(name: &str, input: Option<&str>, timeout: Duration) -> Result<String, String> {
+    ensure_macos()?;
+    validate_shortcut_name(name)?;
+
+    let mut command = Command::new("shortcuts");
+    command.args(["run", name, "--output-path", "-"]);
+    if input.is_some() {
+        command.args(["--input-path", "-"]);
+    }
+
+    run_command(command, input.map(str::as_bytes), timeout)
+}
+
+/// Check that a shortcut name is usable on a command line
+fn validate_shortcut_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Shortcut name cannot be empty".to_string());
+    }
+    if name.len() > 255 || name.chars().any(char::is_control) {
+        return Err(format!("Invalid shortcut name: {:?}", name));
+    }
+    Ok(())
+}
+
+fn ensure_macos() -> Result<(), String> {
+    if cfg!(target_os = "macos") {
+        Ok(())
+    } else {
+        Err("macOS automation is only available on macOS".to_string())
+    }
+}
+
+/// Run a command, feeding it stdin and killing it after `timeout`
+fn run_command(mut command: Command, stdin: Option<&[u8]>, timeout: Duration) -> Result<String, String> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start {:?}: {}", command.get_program(), e))?;
+
+    if let (Some(mut pipe), Some(input)) = (child.stdin.take(), stdin) {
+        pipe.write_all(input)
+            .map_err(|e| format!("Failed to write script input: {}", e))?;
+    }
+
+    let stdout = child.stdout.take().map(read_in_background);
+    let stderr = child.stderr.take().map(read_in_background);
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() >= timeout => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("Script timed out after {}s", timeout.as_secs()));
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(10)),
+            Err(e) => return Err(format!("Failed to wait for script: {}", e)),
+        }
+    };
+
+    let collect = |reader: Option<thread::JoinHandle<String>>| {
+        reader.and_then(|handle| handle.join().ok()).unwrap_or_default()
+    };
+    let stdout = collect(stdout);
+    let stderr = collect(stderr);
+
+    if status.success() {
+        Ok(stdout.trim_end_matches('\n').to_string())
+    } else {
+        Err(format!("Script failed ({}): {}", status, stderr.trim()))
+    }
+}
+
+fn read_in_background(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut output = String::new();
+        let _ = pipe.read_to_string(&mut output);
+        output
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_policy() {
+        let policy = ScriptPolicy::default();
+        assert!(policy.check(r#"tell application "Music" to playpause"#).is_ok());
+        assert!(policy.check("DO   SHELL\n script \"rm -rf ~\"").is_err());
+        assert!(policy.check("run script (read POSIX file \"/tmp/x\")").is_err());
+        assert!(policy.check("«event sysoexec» \"ls\"").is_err());
+        assert!(policy.check(&"-- padding\n".repeat(MAX_SCRIPT_SIZE)).is_err());
+
+        let permissive = ScriptPolicy {
+            allow_shell: true,
+            ..Default::default()
+        };
+        assert!(permissive.check("do shell script \"date\"").is_ok());
+        assert!(permissive.check("load script file \"x.scpt\"").is_err());
+
+        assert!(validate_shortcut_name("Add to Reading List").is_ok());
+        assert!(validate_shortcut_name(" ").is_err());
+        assert!(validate_shortcut_name("bad\nname").is_err());
+    }
+}
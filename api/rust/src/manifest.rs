@@ -0,0 +1,950 @@
+/// Plugin manifest (`manifest.json`) parsing
+///
+/// The manifest describes an installed plugin package: identity, authorship,
+/// requested permissions and the platforms it can run on.
+///
+/// Manifests declare the schema they follow in `manifestVersion`; those
+/// without one follow version 1. Older manifests are upgraded to the current
+/// schema as they are loaded, so `PluginManifest` always has its latest
+/// shape:
+/// - version 2: `author` is an object rather than a `"Name <email>"` string,
+///   `keywords` an array rather than a comma-separated string, and `os` is
+///   renamed `platforms`
+///
+/// Loading only fails on what makes a manifest unusable. Tooling gets every
+/// problem, each with its field and severity, from `validate_file`, and
+/// `ManifestValidator` re-validates only the manifests that changed.
+use crate::api::VoltPluginAPI;
+use crate::network::{HostPattern, NetworkManifest};
+use crate::platform::{Platform, PlatformInfo};
+use crate::warmup::WarmupTask;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Current version of the manifest schema
+pub const MANIFEST_VERSION: u32 = 2;
+
+/// Fields of the current schema, to flag unknown ones
+const KNOWN_FIELDS: &[&str] = &[
+    "manifestVersion",
+    "id",
+    "uuid",
+    "name",
+    "version",
+    "description",
+    "main",
+    "author",
+    "icon",
+    "keywords",
+    "category",
+    "repository",
+    "homepage",
+    "license",
+    "minVoltVersion",
+    "apiVersion",
+    "permissions",
+    "network",
+    "files",
+    "platforms",
+    "minOsVersion",
+    "devMode",
+    "warmup",
+];
+
+/// Upgrade of a manifest from one schema version to the next
+type Upgrade = fn(&mut Map<String, Value>, &mut Vec<ManifestDiagnostic>);
+
+/// Upgrades from each previous schema version, `UPGRADES[0]` upgrading version 1
+const UPGRADES: &[Upgrade] = &[upgrade_v1];
+
+/// File names, in order of preference, of a package's readme
+const README_FILES: &[&str] = &["README.md", "readme.md", "README.txt", "README"];
+
+/// File names, in order of preference, of a package's changelog
+const CHANGELOG_FILES: &[&str] = &["CHANGELOG.md", "changelog.md", "CHANGES.md", "CHANGELOG"];
+
+/// Contents of a plugin's `manifest.json`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginManifest {
+    /// Unique identifier of the plugin
+    pub id: String,
+    /// Stable identifier that survives renames of `id`, see `crate::identity`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<String>,
+    /// Human-readable name
+    pub name: String,
+    /// Plugin version
+    pub version: String,
+    /// Short description
+    #[serde(default)]
+    pub description: String,
+    /// Entry point of the plugin
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub main: Option<String>,
+    /// Plugin author
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<ManifestAuthor>,
+    /// Icon path relative to the package root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// Search keywords
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+    /// Marketplace category
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// Source repository URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<String>,
+    /// Homepage URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+    /// SPDX license identifier
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// Minimum Volt version required
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_volt_version: Option<String>,
+    /// Plugin API version targeted, see `crate::compat`
+    ///
+    /// When missing, the version is detected from the plugin binary.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_version: Option<u32>,
+    /// Permissions requested by the plugin
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub permissions: Vec<String>,
+    /// Hosts the plugin may connect to, see `crate::network`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<NetworkManifest>,
+    /// Files included in the package
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+    /// Supported platforms, empty for all platforms
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub platforms: Vec<Platform>,
+    /// Minimum OS version per platform, e.g. `{"macos": "13.0"}`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub min_os_version: BTreeMap<Platform, String>,
+    /// Reload the plugin whenever its files change, see `crate::devmode`
+    ///
+    /// Meant for local development; published plugins should leave it off.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dev_mode: bool,
+    /// Tasks filling the plugin's caches ahead of queries, see `crate::warmup`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warmup: Vec<WarmupTask>,
+}
+
+/// Author section of a manifest
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestAuthor {
+    /// Author name
+    pub name: String,
+    /// GitHub username
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub github: Option<String>,
+    /// Contact email
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+impl PluginManifest {
+    /// Parse a manifest from JSON, upgrading it from older schema versions
+    pub fn from_json(content: &str) -> Result<Self, String> {
+        let mut diagnostics = Vec::new();
+        match parse(content, &mut diagnostics) {
+            Some((_, manifest)) => Ok(manifest),
+            None => {
+                let error = diagnostics
+                    .iter()
+                    .find(|diagnostic| diagnostic.severity == Severity::Error)
+                    .map(ToString::to_string)
+                    .unwrap_or_default();
+                Err(format!("Failed to parse manifest: {}", error))
+            }
+        }
+    }
+
+    /// Read and parse a `manifest.json` file
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read manifest: {}", e))?;
+
+        Self::from_json(&content)
+    }
+
+    /// Serialize the manifest canonically, e.g. as the input of its signature
+    ///
+    /// See `crate::canonical`.
+    pub fn canonical_json(&self) -> Result<String, String> {
+        crate::canonical::to_string(self)
+    }
+
+    /// Hash of the canonical serialization, equal for manifests with the
+    /// same content however they are formatted
+    pub fn content_hash(&self) -> Result<String, String> {
+        crate::canonical::hash(self)
+    }
+
+    /// Resolve the entry point (`main`) inside a package directory
+    ///
+    /// `main` must be a normalized relative path, and the resolved file,
+    /// following symlinks, must still be inside the package.
+    ///
+    /// # Returns
+    /// Canonical path of the entry point
+    pub fn entry_point(&self, package_dir: &Path) -> Result<PathBuf, String> {
+        let main = self
+            .main
+            .as_deref()
+            .ok_or_else(|| format!("Plugin '{}' has no entry point", self.id))?;
+        let relative = crate::assets::validate_package_path("Entry point", main)?;
+
+        let package_dir = package_dir
+            .canonicalize()
+            .map_err(|e| format!("Failed to resolve package directory: {}", e))?;
+        let entry = package_dir.join(relative);
+        let resolved = entry
+            .canonicalize()
+            .map_err(|_| format!("Entry point {} not found", entry.display()))?;
+
+        if !resolved.starts_with(&package_dir) {
+            return Err(format!("Entry point {} is outside the package", main));
+        }
+
+        Ok(resolved)
+    }
+
+    /// Check if the plugin can run on the given platform
+    ///
+    /// # Returns
+    /// Ok(()) if supported, Err with a user-facing reason otherwise
+    pub fn check_platform(&self, info: &PlatformInfo) -> Result<(), String> {
+        let min_os_version = info
+            .platform
+            .and_then(|platform| self.min_os_version.get(&platform))
+            .map(String::as_str);
+
+        info.check_support(&self.platforms, min_os_version)
+    }
+}
+
+/// How serious a problem found in a manifest is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    /// The manifest can't be loaded, or the plugin can't work
+    Error,
+    /// The manifest loads, but should be fixed
+    Warning,
+}
+
+/// A problem found in a manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestDiagnostic {
+    /// Field the problem is about, e.g. "author"; empty for the whole manifest
+    pub field: String,
+    /// What is wrong
+    pub reason: String,
+    /// How serious it is
+    pub severity: Severity,
+}
+
+impl ManifestDiagnostic {
+    /// Create an error
+    pub fn error(field: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            reason: reason.into(),
+            severity: Severity::Error,
+        }
+    }
+
+    /// Create a warning
+    pub fn warning(field: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            reason: reason.into(),
+            severity: Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for ManifestDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.field.is_empty() {
+            write!(f, "{}", self.reason)
+        } else {
+            write!(f, "{}: {}", self.field, self.reason)
+        }
+    }
+}
+
+/// Outcome of validating a manifest
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestValidation {
+    /// Schema version the manifest declared, None if it couldn't be read
+    pub schema_version: Option<u32>,
+    /// The manifest upgraded to the current schema, None if it doesn't load
+    #[serde(skip)]
+    pub manifest: Option<PluginManifest>,
+    /// Problems found, in the order they were found
+    pub diagnostics: Vec<ManifestDiagnostic>,
+}
+
+impl ManifestValidation {
+    /// Check if the manifest has no errors; warnings are allowed
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    /// Get the errors
+    pub fn errors(&self) -> impl Iterator<Item = &ManifestDiagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
+    /// Get the warnings
+    pub fn warnings(&self) -> impl Iterator<Item = &ManifestDiagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Warning)
+    }
+}
+
+/// Validate a manifest
+///
+/// Reports everything wrong with the manifest, including problems loading
+/// tolerates: invalid plugin IDs, unknown fields, versions that aren't
+/// semantic versions and fields deprecated by newer schema versions.
+pub fn validate(content: &str) -> ManifestValidation {
+    let mut diagnostics = Vec::new();
+    let Some((schema_version, manifest)) = parse(content, &mut diagnostics) else {
+        let schema_version = serde_json::from_str::<Value>(content)
+            .ok()
+            .and_then(|value| value.get("manifestVersion")?.as_u64())
+            .map(|version| version as u32);
+        return ManifestValidation {
+            schema_version,
+            manifest: None,
+            diagnostics,
+        };
+    };
+
+    if let Ok(Value::Object(object)) = serde_json::from_str::<Value>(content) {
+        for field in object.keys() {
+            let upgraded = schema_version == 1 && field == "os";
+            if !KNOWN_FIELDS.contains(&field.as_str()) && !upgraded {
+                diagnostics.push(ManifestDiagnostic::warning(field, "Unknown field"));
+            }
+        }
+    }
+    if let Some(main) = &manifest.main
+        && let Err(e) = crate::assets::validate_package_path("Entry point", main)
+    {
+        diagnostics.push(ManifestDiagnostic::error("main", e));
+    }
+    if let Err(e) = VoltPluginAPI::validate_plugin_id(&manifest.id) {
+        diagnostics.push(ManifestDiagnostic::error("id", e));
+    }
+    if manifest.name.trim().is_empty() {
+        diagnostics.push(ManifestDiagnostic::error("name", "Name cannot be empty"));
+    }
+    if !is_semantic_version(&manifest.version) {
+        diagnostics.push(ManifestDiagnostic::warning(
+            "version",
+            format!("'{}' is not a semantic version", manifest.version),
+        ));
+    }
+    if let Some(min_volt_version) = &manifest.min_volt_version
+        && !is_semantic_version(min_volt_version)
+    {
+        diagnostics.push(ManifestDiagnostic::warning(
+            "minVoltVersion",
+            format!("'{}' is not a semantic version", min_volt_version),
+        ));
+    }
+    if let Some(api_version) = manifest.api_version
+        && !(crate::compat::MIN_SUPPORTED_API_VERSION..=crate::compat::CURRENT_API_VERSION)
+            .contains(&api_version)
+    {
+        diagnostics.push(ManifestDiagnostic::error(
+            "apiVersion",
+            format!("Plugin API version {} is not supported", api_version),
+        ));
+    }
+    if let Some(network) = &manifest.network {
+        for pattern in &network.allow {
+            if let Err(e) = HostPattern::parse(pattern) {
+                diagnostics.push(ManifestDiagnostic::error("network.allow", e));
+            }
+        }
+        if !manifest.permissions.iter().any(|permission| permission == "network") {
+            diagnostics.push(ManifestDiagnostic::warning(
+                "network",
+                "Allowed hosts have no effect without the 'network' permission",
+            ));
+        }
+    }
+    for task in &manifest.warmup {
+        if let Err(e) = task.validate() {
+            diagnostics.push(ManifestDiagnostic::error("warmup", e));
+        }
+    }
+    if manifest.dev_mode {
+        diagnostics.push(ManifestDiagnostic::warning(
+            "devMode",
+            "Published plugins should leave development mode off",
+        ));
+    }
+
+    ManifestValidation {
+        schema_version: Some(schema_version),
+        manifest: Some(manifest),
+        diagnostics,
+    }
+}
+
+/// Validate a `manifest.json` file, see `validate`
+pub fn validate_file(path: &Path) -> ManifestValidation {
+    match std::fs::read_to_string(path) {
+        Ok(content) => validate(&content),
+        Err(e) => ManifestValidation {
+            diagnostics: vec![ManifestDiagnostic::error(
+                "",
+                format!("Failed to read manifest: {}", e),
+            )],
+            ..ManifestValidation::default()
+        },
+    }
+}
+
+/// Validates manifests, remembering the outcome until the file changes
+///
+/// Meant for tooling that re-validates every manifest of a directory
+/// whenever one of them changes.
+#[derive(Default)]
+pub struct ManifestValidator {
+    cache: Mutex<HashMap<PathBuf, CachedValidation>>,
+}
+
+/// Outcome of validating a file, with its modification time and size then
+type CachedValidation = (Option<SystemTime>, u64, ManifestValidation);
+
+impl ManifestValidator {
+    /// Create a validator with nothing cached
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Validate a `manifest.json` file, unless it didn't change since last time
+    pub fn validate(&self, path: &Path) -> ManifestValidation {
+        let stamp = std::fs::metadata(path)
+            .ok()
+            .map(|metadata| (metadata.modified().ok(), metadata.len()));
+
+        let mut cache = self.cache.lock().unwrap_or_else(|p| p.into_inner());
+        if let Some((modified, size)) = stamp
+            && let Some((cached_modified, cached_size, validation)) = cache.get(path)
+            && (*cached_modified, *cached_size) == (modified, size)
+        {
+            return validation.clone();
+        }
+
+        let validation = validate_file(path);
+        match stamp {
+            Some((modified, size)) => {
+                cache.insert(path.to_path_buf(), (modified, size, validation.clone()));
+            }
+            None => {
+                cache.remove(path);
+            }
+        }
+        validation
+    }
+
+    /// Forget the outcome of a file, so it is validated again
+    pub fn invalidate(&self, path: &Path) {
+        self.cache
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .remove(path);
+    }
+}
+
+/// Parse and upgrade a manifest
+///
+/// # Returns
+/// The schema version the manifest declared and the upgraded manifest, or
+/// None if it can't be loaded; problems are added to `diagnostics`
+fn parse(content: &str, diagnostics: &mut Vec<ManifestDiagnostic>) -> Option<(u32, PluginManifest)> {
+    let mut object = match serde_json::from_str::<Value>(content) {
+        Ok(Value::Object(object)) => object,
+        Ok(_) => {
+            diagnostics.push(ManifestDiagnostic::error("", "Manifest must be a JSON object"));
+            return None;
+        }
+        Err(e) => {
+            diagnostics.push(ManifestDiagnostic::error("", e.to_string()));
+            return None;
+        }
+    };
+
+    let schema_version = match object.get("manifestVersion") {
+        None => 1,
+        Some(version) => match version.as_u64() {
+            Some(version) if (1..=MANIFEST_VERSION as u64).contains(&version) => version as u32,
+            Some(version) if version > MANIFEST_VERSION as u64 => {
+                diagnostics.push(ManifestDiagnostic::error(
+                    "manifestVersion",
+                    format!(
+                        "Schema version {} is newer than the latest supported, {}",
+                        version, MANIFEST_VERSION
+                    ),
+                ));
+                return None;
+            }
+            _ => {
+                diagnostics.push(ManifestDiagnostic::error(
+                    "manifestVersion",
+                    format!("Invalid schema version: {}", version),
+                ));
+                return None;
+            }
+        },
+    };
+    for upgrade in &UPGRADES[schema_version as usize - 1..] {
+        upgrade(&mut object, diagnostics);
+    }
+
+    for field in ["id", "name", "version"] {
+        if !object.contains_key(field) {
+            diagnostics.push(ManifestDiagnostic::error(field, "Missing required field"));
+        }
+    }
+    if diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error) {
+        return None;
+    }
+
+    match serde_json::from_value(Value::Object(object.clone())) {
+        Ok(manifest) => Some((schema_version, manifest)),
+        Err(e) => {
+            // Find the fields at fault by parsing each on its own
+            let before = diagnostics.len();
+            for (field, value) in &object {
+                let mut probe = Map::new();
+                for required in ["id", "name", "version"] {
+                    probe.insert(required.to_string(), Value::from("x"));
+                }
+                probe.insert(field.clone(), value.clone());
+                if let Err(e) = serde_json::from_value::<PluginManifest>(Value::Object(probe)) {
+                    diagnostics.push(ManifestDiagnostic::error(field, e.to_string()));
+                }
+            }
+            if diagnostics.len() == before {
+                diagnostics.push(ManifestDiagnostic::error("", e.to_string()));
+            }
+            None
+        }
+    }
+}
+
+/// Upgrade a version 1 manifest to version 2
+fn upgrade_v1(object: &mut Map<String, Value>, diagnostics: &mut Vec<ManifestDiagnostic>) {
+    if let Some(Value::String(author)) = object.get("author") {
+        // "Name <email>"
+        let (name, email) = match author.split_once('<') {
+            Some((name, email)) => (name.trim(), Some(email.trim_end().trim_end_matches('>').trim())),
+            None => (author.trim(), None),
+        };
+        let mut upgraded = Map::new();
+        upgraded.insert("name".to_string(), Value::from(name));
+        if let Some(email) = email.filter(|email| !email.is_empty()) {
+            upgraded.insert("email".to_string(), Value::from(email));
+        }
+        object.insert("author".to_string(), Value::Object(upgraded));
+        diagnostics.push(ManifestDiagnostic::warning(
+            "author",
+            "A string author is deprecated since schema version 2, use an object",
+        ));
+    }
+
+    if let Some(Value::String(keywords)) = object.get("keywords") {
+        let keywords: Vec<Value> = keywords
+            .split(',')
+            .map(str::trim)
+            .filter(|keyword| !keyword.is_empty())
+            .map(Value::from)
+            .collect();
+        object.insert("keywords".to_string(), Value::Array(keywords));
+        diagnostics.push(ManifestDiagnostic::warning(
+            "keywords",
+            "Comma-separated keywords are deprecated since schema version 2, use an array",
+        ));
+    }
+
+    if let Some(os) = object.remove("os") {
+        object.entry("platforms").or_insert(os);
+        diagnostics.push(ManifestDiagnostic::warning(
+            "os",
+            "Renamed 'platforms' in schema version 2",
+        ));
+    }
+
+    object.insert("manifestVersion".to_string(), Value::from(2));
+}
+
+/// Check if a version looks like `1.2.3`, optionally with a pre-release or build suffix
+fn is_semantic_version(version: &str) -> bool {
+    let core = version.split(['-', '+']).next().unwrap_or_default();
+    let parts: Vec<&str> = core.split('.').collect();
+    (1..=3).contains(&parts.len())
+        && parts
+            .iter()
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Documentation and metadata of an installed plugin
+///
+/// Everything is read from the installed package, so the settings UI and
+/// help surfaces work offline.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginInfo {
+    /// Unique identifier of the plugin
+    pub id: String,
+    /// Human-readable name
+    pub name: String,
+    /// Installed version
+    pub version: String,
+    /// Short description
+    pub description: String,
+    /// Plugin author
+    pub author: Option<ManifestAuthor>,
+    /// Project homepage
+    pub homepage: Option<String>,
+    /// Source repository
+    pub repository: Option<String>,
+    /// License identifier
+    pub license: Option<String>,
+    /// Category in the extension store
+    pub category: Option<String>,
+    /// Declared search keywords
+    pub keywords: Vec<String>,
+    /// Declared capabilities (the manifest's permissions)
+    pub capabilities: Vec<String>,
+    /// Hosts the plugin may connect to, None if not restricted by host
+    pub network_allow: Option<Vec<String>>,
+    /// Contents of the package's readme, if any
+    pub readme: Option<String>,
+    /// Contents of the package's changelog, if any
+    pub changelog: Option<String>,
+}
+
+impl PluginInfo {
+    /// Read the metadata of an installed package
+    ///
+    /// # Arguments
+    /// * `package_dir` - Directory containing the package's `manifest.json`
+    pub fn from_package(package_dir: &Path) -> Result<Self, String> {
+        let manifest = PluginManifest::from_file(&package_dir.join("manifest.json"))?;
+
+        Ok(Self {
+            readme: read_first(package_dir, README_FILES),
+            changelog: read_first(package_dir, CHANGELOG_FILES),
+            id: manifest.id,
+            name: manifest.name,
+            version: manifest.version,
+            description: manifest.description,
+            author: manifest.author,
+            homepage: manifest.homepage,
+            repository: manifest.repository,
+            license: manifest.license,
+            category: manifest.category,
+            keywords: manifest.keywords,
+            capabilities: manifest.permissions,
+            network_allow: manifest.network.map(|network| network.allow),
+        })
+    }
+}
+
+/// Read the first of several candidate files that exists
+fn read_first(dir: &Path, candidates: &[&str]) -> Option<String> {
+    candidates
+        .iter()
+        .find_map(|name| std::fs::read_to_string(dir.join(name)).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fuzz;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = PluginManifest::from_json(
+            r#"{
+                "id": "password-generator",
+                "name": "Password Generator",
+                "version": "1.0.4",
+                "author": {"name": "VoltLaunchr Community", "github": "VoltLaunchr"},
+                "minVoltVersion": "0.4.0",
+                "permissions": ["clipboard"],
+                "platforms": ["windows", "darwin"],
+                "minOsVersion": {"windows": "10.0.19041"}
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.id, "password-generator");
+        assert_eq!(manifest.author.unwrap().github.as_deref(), Some("VoltLaunchr"));
+        assert_eq!(manifest.platforms, vec![Platform::Windows, Platform::Macos]);
+        assert_eq!(manifest.min_os_version[&Platform::Windows], "10.0.19041");
+        assert!(PluginManifest::from_json(r#"{"id": "x"}"#).is_err());
+    }
+
+    #[test]
+    fn test_upgrade_v1_manifest() {
+        let manifest = PluginManifest::from_json(
+            r#"{
+                "id": "clipboard-history",
+                "name": "Clipboard History",
+                "version": "0.9.0",
+                "author": "Ada Lovelace <ada@example.com>",
+                "keywords": "clipboard, history",
+                "os": ["linux"]
+            }"#,
+        )
+        .unwrap();
+
+        let author = manifest.author.clone().unwrap();
+        assert_eq!(author.name, "Ada Lovelace");
+        assert_eq!(author.email.as_deref(), Some("ada@example.com"));
+        assert_eq!(manifest.keywords, vec!["clipboard", "history"]);
+        assert_eq!(manifest.platforms, vec![Platform::Linux]);
+
+        let error = PluginManifest::from_json(
+            r#"{"manifestVersion": 3, "id": "x", "name": "x", "version": "1.0.0"}"#,
+        )
+        .unwrap_err();
+        assert!(error.contains("manifestVersion: Schema version 3 is newer"));
+    }
+
+    #[test]
+    fn test_validate_diagnostics() {
+        let validation = validate(
+            r#"{
+                "id": "clipboard-history",
+                "name": "Clipboard History",
+                "version": "0.9",
+                "author": "Ada Lovelace",
+                "permisions": ["clipboard"],
+                "devMode": true
+            }"#,
+        );
+        assert!(validation.is_valid());
+        assert_eq!(validation.schema_version, Some(1));
+        let warnings: Vec<&str> = validation.warnings().map(|warning| warning.field.as_str()).collect();
+        assert_eq!(warnings, vec!["author", "permisions", "devMode"]);
+
+        let validation = validate(
+            r#"{
+                "manifestVersion": 2,
+                "id": "../escape",
+                "name": "Escape",
+                "version": "1.0.0",
+                "keywords": "a,b",
+                "platforms": ["beos"]
+            }"#,
+        );
+        assert!(validation.manifest.is_none());
+        let errors: Vec<&str> = validation.errors().map(|error| error.field.as_str()).collect();
+        assert_eq!(errors, vec!["keywords", "platforms"]);
+
+        let validation = validate(r#"{"manifestVersion": 2, "id": "../escape", "name": " ", "version": "1"}"#);
+        let errors: Vec<&str> = validation.errors().map(|error| error.field.as_str()).collect();
+        assert_eq!(errors, vec!["id", "name"]);
+        assert_eq!(
+            validation.diagnostics[0].to_string(),
+            "id: Plugin ID cannot contain path separators"
+        );
+
+        let validation = validate(
+            r#"{"manifestVersion": 2, "id": "github", "name": "GitHub", "version": "1.0.0",
+                "network": {"allow": ["api.github.com", "https://evil.com"]}}"#,
+        );
+        let fields: Vec<&str> = validation.diagnostics.iter().map(|diagnostic| diagnostic.field.as_str()).collect();
+        assert_eq!(fields, vec!["network.allow", "network"]);
+    }
+
+    #[test]
+    fn test_validator_caches_unchanged_files() {
+        let dir = std::env::temp_dir().join("volt_test_manifest_validator");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("manifest.json");
+
+        let validator = ManifestValidator::new();
+        assert!(!validator.validate(&path).is_valid());
+
+        std::fs::write(&path, r#"{"id": "notes", "name": "Notes", "version": "1.0.0"}"#).unwrap();
+        let validation = validator.validate(&path);
+        assert!(validation.is_valid());
+        assert_eq!(validator.validate(&path), validation);
+
+        // A change of size is noticed even within the timestamp granularity
+        std::fs::write(&path, r#"{"id": "notes", "name": "Notes"}"#).unwrap();
+        let errors: Vec<String> = validator.validate(&path).errors().map(ToString::to_string).collect();
+        assert_eq!(errors, vec!["version: Missing required field"]);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_check_platform() {
+        let manifest = PluginManifest {
+            id: "windows-only".to_string(),
+            platforms: vec![Platform::Windows],
+            min_os_version: BTreeMap::from([(Platform::Windows, "10.0.19041".to_string())]),
+            ..Default::default()
+        };
+
+        let old_windows = PlatformInfo {
+            platform: Some(Platform::Windows),
+            os_version: Some("6.1.7601".to_string()),
+        };
+        let linux = PlatformInfo {
+            platform: Some(Platform::Linux),
+            os_version: None,
+        };
+
+        assert!(manifest.check_platform(&old_windows).is_err());
+        assert!(manifest.check_platform(&linux).is_err());
+        assert!(PluginManifest::default().check_platform(&linux).is_ok());
+    }
+
+    #[test]
+    fn test_plugin_info_from_package() {
+        let package_dir = std::env::temp_dir().join("volt_test_plugin_info");
+        let _ = std::fs::remove_dir_all(&package_dir);
+        std::fs::create_dir_all(&package_dir).unwrap();
+        std::fs::write(
+            package_dir.join("manifest.json"),
+            r#"{
+                "id": "password-generator",
+                "name": "Password Generator",
+                "version": "1.0.4",
+                "homepage": "https://example.com/password-generator",
+                "keywords": ["password"],
+                "permissions": ["clipboard"]
+            }"#,
+        )
+        .unwrap();
+        std::fs::write(package_dir.join("README.md"), "# Password Generator").unwrap();
+
+        let info = PluginInfo::from_package(&package_dir).unwrap();
+        assert_eq!(info.id, "password-generator");
+        assert_eq!(info.capabilities, vec!["clipboard"]);
+        assert_eq!(info.readme.as_deref(), Some("# Password Generator"));
+        assert_eq!(info.changelog, None);
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(package_dir);
+    }
+
+    #[test]
+    fn test_entry_point_stays_in_package() {
+        let root = std::env::temp_dir().join("volt_test_entry_point");
+        let _ = std::fs::remove_dir_all(&root);
+        let package_dir = root.join("plugin");
+        std::fs::create_dir_all(package_dir.join("bin")).unwrap();
+        std::fs::write(package_dir.join("bin").join("plugin.wasm"), b"").unwrap();
+        std::fs::write(root.join("outside.wasm"), b"").unwrap();
+
+        let manifest = |main: &str| PluginManifest {
+            id: "entry-point".to_string(),
+            main: Some(main.to_string()),
+            ..Default::default()
+        };
+
+        let entry = manifest("bin/plugin.wasm").entry_point(&package_dir).unwrap();
+        assert!(entry.starts_with(package_dir.canonicalize().unwrap()));
+        assert!(manifest("../outside.wasm").entry_point(&package_dir).is_err());
+        assert!(manifest("/bin/sh").entry_point(&package_dir).is_err());
+        assert!(manifest("bin/missing.wasm").entry_point(&package_dir).is_err());
+        assert!(PluginManifest::default().entry_point(&package_dir).is_err());
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(root.join("outside.wasm"), package_dir.join("link.wasm")).unwrap();
+            let escaped = manifest("link.wasm").entry_point(&package_dir).unwrap_err();
+            assert!(escaped.contains("outside the package"));
+        }
+
+        let validation = validate(r#"{"id": "entry-point", "name": "Entry", "version": "1.0.0", "main": "../x.so"}"#);
+        assert!(validation.errors().any(|diagnostic| diagnostic.field == "main"));
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    /// Strategy generating manifests with arbitrary field contents
+    fn any_manifest() -> impl Strategy<Value = PluginManifest> {
+        let platform = prop_oneof![Just(Platform::Windows), Just(Platform::Macos), Just(Platform::Linux)];
+        (
+            (".{0,16}", ".{0,16}", ".{0,16}", ".{0,32}"),
+            proptest::option::of((".{0,16}", proptest::option::of(".{0,16}"))),
+            prop::collection::vec(".{0,8}", 0..4),
+            proptest::option::of(".{0,16}"),
+            prop::collection::vec(platform.clone(), 0..3),
+            prop::collection::btree_map(platform, "[0-9.]{0,12}", 0..3),
+        )
+            .prop_map(|((id, name, version, description), author, permissions, min_volt_version, platforms, min_os_version)| {
+                PluginManifest {
+                    id,
+                    name,
+                    version,
+                    description,
+                    author: author.map(|(name, github)| ManifestAuthor {
+                        name,
+                        github,
+                        email: None,
+                    }),
+                    permissions,
+                    min_volt_version,
+                    platforms,
+                    min_os_version,
+                    ..Default::default()
+                }
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_manifest_round_trips(manifest in any_manifest()) {
+            let encoded = serde_json::to_string(&manifest).unwrap();
+            prop_assert_eq!(PluginManifest::from_json(&encoded).unwrap(), manifest);
+            fuzz::manifest(encoded.as_bytes());
+        }
+
+        #[test]
+        fn prop_parser_never_panics(text in ".{0,256}", version in ".{0,16}") {
+            fuzz::manifest(text.as_bytes());
+            let manifest = format!(
+                r#"{{"id":"x","name":"x","version":"1","minOsVersion":{{"linux":{}}}}}"#,
+                serde_json::Value::from(version)
+            );
+            fuzz::manifest(manifest.as_bytes());
+        }
+    }
+}
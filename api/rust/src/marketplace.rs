@@ -0,0 +1,347 @@
+/// Garbage collection of downloaded marketplace packages
+///
+/// Packages downloaded from the marketplace are kept as
+/// `<plugin id>-<version>.voltpkg` archives in the marketplace cache, so a
+/// plugin can be reinstalled or rolled back without downloading it again.
+/// Left alone, every update adds an archive. `gc` applies a
+/// `RetentionPolicy` to the cache:
+/// - only the newest `keep_versions` archives of each plugin are kept, plus
+///   the archive of the installed version
+/// - interrupted downloads (`.partial` files) older than `max_partial_age`
+///   are deleted
+/// - while the cache exceeds its `max_bytes` quota, archives of plugins
+///   that aren't installed go first, then the least recently downloaded
+///
+/// A dry run reports what would be deleted without touching anything.
+use crate::platform::compare_versions;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Directory of the cache directory holding downloaded packages
+pub const MARKETPLACE_CACHE_DIR: &str = "marketplace";
+
+/// Extension of package archives
+pub const PACKAGE_EXTENSION: &str = "voltpkg";
+
+/// Extension of downloads in progress
+pub const PARTIAL_EXTENSION: &str = "partial";
+
+/// What `gc` keeps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Archives kept per plugin, newest versions first
+    pub keep_versions: usize,
+    /// Quota of the whole cache, in bytes
+    pub max_bytes: Option<u64>,
+    /// Age after which an interrupted download is abandoned
+    pub max_partial_age: Duration,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            keep_versions: 2,
+            max_bytes: Some(512 * 1024 * 1024),
+            max_partial_age: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+/// A package archive in the cache
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedPackage {
+    /// Plugin the package installs
+    pub plugin_id: String,
+    /// Version of the package
+    pub version: String,
+    /// Path of the archive
+    pub path: PathBuf,
+    /// Size of the archive, in bytes
+    pub size: u64,
+    /// When the archive was downloaded
+    pub modified: SystemTime,
+}
+
+/// Why a file was collected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GcReason {
+    /// A newer version is kept and this one isn't installed
+    OlderVersion,
+    /// The cache exceeded its quota
+    OverQuota,
+    /// An interrupted download was abandoned
+    StalePartial,
+}
+
+/// A file `gc` deleted, or would delete on a dry run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectedFile {
+    /// Path of the file
+    pub path: PathBuf,
+    /// Size of the file, in bytes
+    pub size: u64,
+    /// Why it was collected
+    pub reason: GcReason,
+}
+
+/// What `gc` did
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GcReport {
+    /// Whether files were only reported, not deleted
+    pub dry_run: bool,
+    /// Files collected
+    pub collected: Vec<CollectedFile>,
+    /// Bytes freed, or that would be freed
+    pub freed_bytes: u64,
+    /// Bytes left in the cache
+    pub kept_bytes: u64,
+    /// Files that couldn't be deleted
+    pub errors: Vec<String>,
+}
+
+/// Name of the archive of a package version
+pub fn package_file_name(plugin_id: &str, version: &str) -> String {
+    format!("{}-{}.{}", plugin_id, version, PACKAGE_EXTENSION)
+}
+
+/// Split an archive name into plugin ID and version
+///
+/// The version starts after the last `-` followed by a digit, so plugin
+/// IDs may contain dashes.
+pub fn parse_package_file_name(file_name: &str) -> Option<(String, String)> {
+    let stem = file_name
+        .strip_suffix(PACKAGE_EXTENSION)?
+        .strip_suffix('.')?;
+    let (split, _) = stem
+        .match_indices('-')
+        .rev()
+        .find(|(index, _)| stem[index + 1..].starts_with(|c: char| c.is_ascii_digit()))?;
+
+    if split == 0 {
+        return None;
+    }
+    Some((stem[..split].to_string(), stem[split + 1..].to_string()))
+}
+
+/// List the package archives of a cache directory
+///
+/// Files that aren't archives are ignored.
+pub fn cached_packages(dir: &Path) -> Result<Vec<CachedPackage>, String> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read package cache: {}", e)),
+    };
+
+    let mut packages = Vec::new();
+    for entry in entries.flatten() {
+        let Some((plugin_id, version)) =
+            parse_package_file_name(&entry.file_name().to_string_lossy())
+        else {
+            continue;
+        };
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        packages.push(CachedPackage {
+            plugin_id,
+            version,
+            path: entry.path(),
+            size: metadata.len(),
+            modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+        });
+    }
+    Ok(packages)
+}
+
+/// Apply a retention policy to the package cache
+///
+/// # Arguments
+/// * `dir` - The marketplace cache directory
+/// * `policy` - What to keep
+/// * `installed` - Installed version of each installed plugin, never
+///   collected for being old
+/// * `dry_run` - Only report what would be deleted
+pub fn gc(
+    dir: &Path,
+    policy: &RetentionPolicy,
+    installed: &HashMap<String, String>,
+    dry_run: bool,
+) -> Result<GcReport, String> {
+    let mut report = GcReport {
+        dry_run,
+        ..GcReport::default()
+    };
+    let collect = |path: &Path, size: u64, reason: GcReason, report: &mut GcReport| {
+        if !dry_run && let Err(e) = std::fs::remove_file(path) {
+            report
+                .errors
+                .push(format!("Failed to delete {}: {}", path.display(), e));
+            return false;
+        }
+        report.collected.push(CollectedFile {
+            path: path.to_path_buf(),
+            size,
+            reason,
+        });
+        report.freed_bytes += size;
+        true
+    };
+
+    // Abandoned downloads
+    let now = SystemTime::now();
+    for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let age = now
+            .duration_since(metadata.modified().unwrap_or(now))
+            .unwrap_or_default();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == PARTIAL_EXTENSION)
+            && age > policy.max_partial_age
+        {
+            collect(&path, metadata.len(), GcReason::StalePartial, &mut report);
+        }
+    }
+
+    // Older versions of each plugin
+    let mut by_plugin: HashMap<String, Vec<CachedPackage>> = HashMap::new();
+    for package in cached_packages(dir)? {
+        by_plugin
+            .entry(package.plugin_id.clone())
+            .or_default()
+            .push(package);
+    }
+    let mut kept = Vec::new();
+    for (plugin_id, mut packages) in by_plugin {
+        packages.sort_by(|a, b| compare_versions(&b.version, &a.version));
+        let installed_version = installed.get(&plugin_id);
+        for (index, package) in packages.into_iter().enumerate() {
+            let retained =
+                index < policy.keep_versions || installed_version == Some(&package.version);
+            if retained
+                || !collect(
+                    &package.path,
+                    package.size,
+                    GcReason::OlderVersion,
+                    &mut report,
+                )
+            {
+                kept.push(package);
+            }
+        }
+    }
+
+    // Quota: packages of uninstalled plugins first, then the least recently downloaded
+    kept.sort_by_key(|package| (installed.contains_key(&package.plugin_id), package.modified));
+    let mut kept_bytes: u64 = kept.iter().map(|package| package.size).sum();
+    if let Some(max_bytes) = policy.max_bytes {
+        for package in &kept {
+            if kept_bytes <= max_bytes {
+                break;
+            }
+            if collect(
+                &package.path,
+                package.size,
+                GcReason::OverQuota,
+                &mut report,
+            ) {
+                kept_bytes -= package.size;
+            }
+        }
+    }
+    report.kept_bytes = kept_bytes;
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_package_file_names() {
+        assert_eq!(
+            package_file_name("git-repos", "1.2.0"),
+            "git-repos-1.2.0.voltpkg"
+        );
+        assert_eq!(
+            parse_package_file_name("git-repos-1.2.0-beta.1.voltpkg"),
+            Some(("git-repos".to_string(), "1.2.0-beta.1".to_string()))
+        );
+        assert_eq!(parse_package_file_name("notes.voltpkg"), None);
+        assert_eq!(parse_package_file_name("notes-1.0.zip"), None);
+    }
+
+    #[test]
+    fn test_gc_retention_and_quota() {
+        let temp_dir = std::env::temp_dir().join("volt_test_marketplace_gc");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(&temp_dir).unwrap();
+
+        let write =
+            |name: &str, size: usize| std::fs::write(temp_dir.join(name), vec![0u8; size]).unwrap();
+        for version in ["1.0.0", "1.2.0", "1.10.0", "2.0.0"] {
+            write(&package_file_name("notes", version), 100);
+        }
+        write(&package_file_name("weather", "0.3.0"), 300);
+        write("notes-3.0.0.voltpkg.partial", 50);
+        write("README.txt", 10);
+
+        let installed = HashMap::from([("notes".to_string(), "1.2.0".to_string())]);
+        let policy = RetentionPolicy {
+            keep_versions: 2,
+            max_bytes: Some(400),
+            max_partial_age: Duration::ZERO,
+        };
+
+        let report = gc(&temp_dir, &policy, &installed, true).unwrap();
+        let reasons = |report: &GcReport| {
+            let mut reasons: Vec<(String, GcReason)> = report
+                .collected
+                .iter()
+                .map(|file| {
+                    (
+                        file.path.file_name().unwrap().to_string_lossy().to_string(),
+                        file.reason,
+                    )
+                })
+                .collect();
+            reasons.sort();
+            reasons
+        };
+        assert_eq!(
+            reasons(&report),
+            vec![
+                ("notes-1.0.0.voltpkg".to_string(), GcReason::OlderVersion),
+                (
+                    "notes-3.0.0.voltpkg.partial".to_string(),
+                    GcReason::StalePartial
+                ),
+                ("weather-0.3.0.voltpkg".to_string(), GcReason::OverQuota),
+            ]
+        );
+        assert_eq!((report.freed_bytes, report.kept_bytes), (450, 300));
+        assert_eq!(cached_packages(&temp_dir).unwrap().len(), 5);
+
+        let applied = gc(&temp_dir, &policy, &installed, false).unwrap();
+        assert_eq!(reasons(&applied), reasons(&report));
+        let mut left: Vec<String> = cached_packages(&temp_dir)
+            .unwrap()
+            .into_iter()
+            .map(|package| package.version)
+            .collect();
+        left.sort();
+        assert_eq!(left, vec!["1.10.0", "1.2.0", "2.0.0"]);
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
@@ -0,0 +1,417 @@
+/// Tokenization shared by matching, full-text search and suggestions
+///
+/// Fuzzy matching (`fuzzy_score`), full-text indexes (`crate::fulltext`)
+/// and the keyword suggester (`crate::suggestions`) split text into words
+/// the same way, so a query ranks the same wherever it is matched. A
+/// `Tokenizer`:
+///
+/// - lower-cases words and folds full-width ASCII ("ＡＢＣ" is "abc")
+/// - splits camelCase ("gitHubDesktop" is "git", "hub", "desktop") and
+///   snake_case or kebab-case words, each can be turned off
+/// - segments Chinese, Japanese and Korean runs into overlapping bigrams,
+///   with the `cjk` feature; without it a run is a single word
+/// - drops the stop words of a locale ("the", "der", "le", ...) from terms
+///
+/// Hosts configure it once, usually from the user's locale:
+///
+/// ```ignore
+/// matching::set_tokenizer(Tokenizer::for_locale(&locale));
+/// ```
+///
+/// The installed tokenizer is the process-wide fallback. An API built with
+/// `VoltPluginAPIBuilder::with_tokenizer`, and the suggesters and full-text
+/// schemas given a tokenizer, use their own and never read it, so hosts
+/// embedding several registries configure each separately. Full-text
+/// indexes take the tokenizer set when they are opened; documents indexed
+/// before the configuration changed keep their old terms until reindexed.
+use crate::i18n::Messages;
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+
+/// Tokenizer installed by the host
+static TOKENIZER: RwLock<Option<Arc<Tokenizer>>> = RwLock::new(None);
+
+/// Stop words, per language
+const STOP_WORDS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "in", "is", "it", "of",
+            "on", "or", "that", "the", "to", "was", "with",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "der", "die", "das", "den", "dem", "des", "ein", "eine", "einen", "einem", "einer",
+            "und", "oder", "ist", "im", "in", "mit", "von", "zu", "zum", "zur", "auf", "für",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "el", "la", "los", "las", "un", "una", "unos", "unas", "y", "o", "de", "del", "al",
+            "en", "con", "por", "para", "es", "que",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "le", "la", "les", "l", "un", "une", "des", "du", "de", "d", "et", "ou", "en", "au",
+            "aux", "avec", "pour", "par", "est", "que",
+        ],
+    ),
+];
+
+/// Get the stop words of a locale
+///
+/// Falls back like `Messages::for_locale` (`de-AT` to `de`, unknown
+/// locales to English). Locales without a list, like Japanese, get none.
+pub fn stop_words(locale: &str) -> &'static [&'static str] {
+    let language = Messages::for_locale(locale).locale();
+    STOP_WORDS
+        .iter()
+        .find(|(candidate, _)| *candidate == language)
+        .map(|(_, words)| *words)
+        .unwrap_or_default()
+}
+
+/// Use a tokenizer wherever text is matched
+pub fn set_tokenizer(tokenizer: Tokenizer) {
+    *TOKENIZER
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(Arc::new(tokenizer));
+}
+
+/// Get the tokenizer installed with `set_tokenizer`, or the default one
+pub fn tokenizer() -> Arc<Tokenizer> {
+    TOKENIZER
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
+        .unwrap_or_default()
+}
+
+/// A word of a text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    /// Normalized word
+    pub text: String,
+    /// Byte offset of the word in the text
+    pub start: usize,
+    /// Byte offset of the end of the word
+    pub end: usize,
+    /// Index of the word in the text, counting stop words
+    pub position: usize,
+}
+
+/// Splits text into words, see the module documentation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tokenizer {
+    split_camel_case: bool,
+    split_snake_case: bool,
+    segment_cjk: bool,
+    stop_words: HashSet<String>,
+}
+
+impl Default for Tokenizer {
+    fn default() -> Self {
+        Self {
+            split_camel_case: true,
+            split_snake_case: true,
+            segment_cjk: cfg!(feature = "cjk"),
+            stop_words: HashSet::new(),
+        }
+    }
+}
+
+/// Kind of a character, for finding word boundaries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharKind {
+    Lower,
+    Upper,
+    Other,
+    Cjk,
+    Separator,
+}
+
+impl Tokenizer {
+    /// Create a tokenizer splitting camelCase and snake_case, without stop words
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a tokenizer dropping the stop words of a locale
+    pub fn for_locale(locale: &str) -> Self {
+        Self::new().with_stop_words(stop_words(locale).iter().copied())
+    }
+
+    /// Split camelCase words
+    pub fn with_camel_case(mut self, split: bool) -> Self {
+        self.split_camel_case = split;
+        self
+    }
+
+    /// Split words at underscores and hyphens
+    pub fn with_snake_case(mut self, split: bool) -> Self {
+        self.split_snake_case = split;
+        self
+    }
+
+    /// Segment Chinese, Japanese and Korean text into bigrams
+    #[cfg(feature = "cjk")]
+    pub fn with_cjk_segmentation(mut self, segment: bool) -> Self {
+        self.segment_cjk = segment;
+        self
+    }
+
+    /// Add stop words, dropped from terms
+    pub fn with_stop_words<'a>(mut self, words: impl IntoIterator<Item = &'a str>) -> Self {
+        let words: Vec<String> = words
+            .into_iter()
+            .map(|word| self.normalize(word.trim()))
+            .collect();
+        self.stop_words.extend(words);
+        self
+    }
+
+    /// Check if a word is a stop word
+    pub fn is_stop_word(&self, word: &str) -> bool {
+        self.stop_words.contains(&self.normalize(word))
+    }
+
+    /// Lower-case text and fold full-width ASCII
+    pub fn normalize(&self, text: &str) -> String {
+        text.chars()
+            .map(fold_width)
+            .flat_map(char::to_lowercase)
+            .collect()
+    }
+
+    /// Split text into words, without stop words
+    pub fn tokens(&self, text: &str) -> Vec<Token> {
+        self.segments(text)
+            .into_iter()
+            .enumerate()
+            .map(|(position, (start, end))| Token {
+                text: self.normalize(&text[start..end]),
+                start,
+                end,
+                position,
+            })
+            .filter(|token| !self.stop_words.contains(&token.text))
+            .collect()
+    }
+
+    /// Split text into normalized words, without stop words
+    pub fn terms(&self, text: &str) -> Vec<String> {
+        self.tokens(text)
+            .into_iter()
+            .map(|token| token.text)
+            .collect()
+    }
+
+    /// Byte offsets where words start, stop words included
+    pub fn word_starts(&self, text: &str) -> Vec<usize> {
+        self.segments(text)
+            .into_iter()
+            .map(|(start, _)| start)
+            .collect()
+    }
+
+    /// Byte ranges of every word
+    fn segments(&self, text: &str) -> Vec<(usize, usize)> {
+        let mut segments = Vec::new();
+        let chars: Vec<(usize, CharKind)> = text
+            .char_indices()
+            .map(|(offset, c)| (offset, self.kind(c)))
+            .chain([(text.len(), CharKind::Separator)])
+            .collect();
+
+        let mut start = 0;
+        for i in 1..chars.len() {
+            let (offset, kind) = chars[i];
+            let previous = chars[i - 1].1;
+            let next = chars.get(i + 1).map(|(_, kind)| *kind);
+
+            let boundary = match (previous, kind) {
+                (CharKind::Separator, _) => {
+                    start = offset;
+                    continue;
+                }
+                (_, CharKind::Separator) => true,
+                (CharKind::Cjk, CharKind::Cjk) => false,
+                (CharKind::Cjk, _) | (_, CharKind::Cjk) => true,
+                // "gitHub" and "HTTPServer"
+                (CharKind::Lower, CharKind::Upper) => self.split_camel_case,
+                (CharKind::Upper, CharKind::Upper) => {
+                    self.split_camel_case && next == Some(CharKind::Lower)
+                }
+                _ => false,
+            };
+            if !boundary {
+                continue;
+            }
+
+            if previous == CharKind::Cjk && self.segment_cjk {
+                push_bigrams(text, start, offset, &mut segments);
+            } else {
+                segments.push((start, offset));
+            }
+            start = offset;
+        }
+
+        segments
+    }
+
+    fn kind(&self, c: char) -> CharKind {
+        if is_cjk(c) {
+            CharKind::Cjk
+        } else if c.is_lowercase() {
+            CharKind::Lower
+        } else if c.is_uppercase() {
+            CharKind::Upper
+        } else if c.is_alphanumeric() || (!self.split_snake_case && (c == '_' || c == '-')) {
+            CharKind::Other
+        } else {
+            CharKind::Separator
+        }
+    }
+}
+
+/// Push the overlapping character pairs of a CJK run
+fn push_bigrams(text: &str, start: usize, end: usize, segments: &mut Vec<(usize, usize)>) {
+    let offsets: Vec<usize> = text[start..end]
+        .char_indices()
+        .map(|(offset, _)| start + offset)
+        .chain([end])
+        .collect();
+    if offsets.len() <= 3 {
+        segments.push((start, end));
+        return;
+    }
+    for pair in offsets.windows(3) {
+        segments.push((pair[0], pair[2]));
+    }
+}
+
+/// Check if a character is written without spaces between words
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}' // Hiragana, Katakana
+        | '\u{3400}'..='\u{4DBF}' // CJK Extension A
+        | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+        | '\u{AC00}'..='\u{D7AF}' // Hangul syllables
+        | '\u{F900}'..='\u{FAFF}' // CJK Compatibility Ideographs
+        | '\u{FF66}'..='\u{FF9F}' // Half-width Katakana
+    )
+}
+
+/// Map full-width ASCII to ASCII
+fn fold_width(c: char) -> char {
+    match c {
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        '\u{3000}' => ' ',
+        _ => c,
+    }
+}
+
+/// Score how well a query fuzzily matches a text
+///
+/// Query characters must appear in order; consecutive matches and matches
+/// at word starts, as found by the installed tokenizer, score higher.
+///
+/// # Returns
+/// None if the query doesn't match
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    fuzzy_score_with(&tokenizer(), query, text)
+}
+
+/// Score a fuzzy match with a given tokenizer, see `fuzzy_score`
+pub fn fuzzy_score_with(tokenizer: &Tokenizer, query: &str, text: &str) -> Option<u32> {
+    let query: Vec<char> = tokenizer
+        .normalize(query)
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    let word_starts: HashSet<usize> = tokenizer.word_starts(text).into_iter().collect();
+
+    let mut score = 0;
+    let mut matched = 0;
+    let mut previous = None;
+    for (i, (offset, c)) in text.char_indices().enumerate() {
+        if matched == query.len() {
+            break;
+        }
+        // Lower-casing may yield several characters; compare the first
+        let c = fold_width(c).to_lowercase().next().unwrap_or(c);
+        if c != query[matched] {
+            continue;
+        }
+
+        score += 1;
+        if i > 0 && previous == Some(i - 1) {
+            score += 2;
+        }
+        if word_starts.contains(&offset) {
+            score += 3;
+        }
+        previous = Some(i);
+        matched += 1;
+    }
+
+    (matched == query.len()).then_some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenizer_splits_words() {
+        let tokenizer = Tokenizer::for_locale("en-GB");
+        assert_eq!(
+            tokenizer.terms("Open the gitHubDesktop HTTPServer_config ＦＯＯ-bar"),
+            vec![
+                "open", "git", "hub", "desktop", "http", "server", "config", "foo", "bar"
+            ]
+        );
+        let tokens = tokenizer.tokens("the Quick fox");
+        assert_eq!(tokens[0].text, "quick");
+        assert_eq!(
+            (tokens[0].start, tokens[0].end, tokens[0].position),
+            (4, 9, 1)
+        );
+
+        let plain = Tokenizer::new()
+            .with_camel_case(false)
+            .with_snake_case(false);
+        assert_eq!(
+            plain.terms("gitHub snake_case"),
+            vec!["github", "snake_case"]
+        );
+        assert!(Tokenizer::for_locale("de_AT.UTF-8").is_stop_word("Der"));
+        assert!(stop_words("ja").is_empty());
+
+        // Camel-case humps count as word starts
+        assert!(
+            fuzzy_score_with(&tokenizer, "gh", "gitHub") > fuzzy_score_with(&plain, "gh", "gitHub")
+        );
+        assert_eq!(
+            fuzzy_score_with(&tokenizer, "ｇｈ", "gitHub"),
+            fuzzy_score_with(&tokenizer, "gh", "gitHub")
+        );
+    }
+
+    #[cfg(feature = "cjk")]
+    #[test]
+    fn test_cjk_bigrams() {
+        let tokenizer = Tokenizer::new();
+        assert_eq!(tokenizer.terms("東京都 rust"), vec!["東京", "京都", "rust"]);
+        assert_eq!(tokenizer.terms("東京"), vec!["東京"]);
+        assert_eq!(
+            tokenizer.with_cjk_segmentation(false).terms("東京都"),
+            vec!["東京都"]
+        );
+    }
+}
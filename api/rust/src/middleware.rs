@@ -0,0 +1,200 @@
+/// Host middleware around query dispatch
+///
+/// Hosts hook into `PluginRegistry::dispatch_query` with a
+/// `DispatchMiddleware`, at two points:
+/// - before routing, to rewrite the query (e.g. strip profanity) or answer
+///   it outright with synthetic results, skipping the plugins
+/// - after merge, to edit, redact or drop the ranked results (e.g. DLP
+///   redaction) or just log them
+///
+/// Middleware runs in ascending `order`, ties in the order they were added,
+/// at both points. A middleware that panics is logged and skipped.
+use crate::aggregator::MergedResults;
+use crate::logging::Diagnostics;
+use crate::plugin::QueryContext;
+use crate::result::PluginResult;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, RwLock};
+
+/// What dispatch does after a middleware saw the query
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum BeforeRouting {
+    /// Route the (possibly rewritten) query to the plugins
+    #[default]
+    Continue,
+    /// Skip the plugins and show these results instead
+    ///
+    /// No plugin executes them, so each should carry a `ResultIntent` the
+    /// host performs itself.
+    Respond(Vec<PluginResult>),
+}
+
+/// A step of the dispatch pipeline inserted by the host
+pub trait DispatchMiddleware: Send + Sync {
+    /// Name of the middleware, for logs and `PluginRegistry::remove_middleware`
+    ///
+    /// Synthetic results are attributed to it as if it were a plugin.
+    fn name(&self) -> &str;
+
+    /// Inspect or rewrite the query before plugins are picked
+    fn before_routing(&self, _context: &mut QueryContext) -> BeforeRouting {
+        BeforeRouting::Continue
+    }
+
+    /// Inspect or edit the merged results before they are shown
+    ///
+    /// Also runs on synthetic and fallback results.
+    fn after_merge(&self, _context: &QueryContext, _merged: &mut MergedResults) {}
+}
+
+/// A middleware and its position in the chain
+type OrderedMiddleware = (i32, Arc<dyn DispatchMiddleware>);
+
+/// Middleware of a registry, in running order
+#[derive(Clone, Default)]
+pub struct MiddlewareChain {
+    entries: Arc<RwLock<Vec<OrderedMiddleware>>>,
+    /// Where panicking middleware is reported
+    diagnostics: Diagnostics,
+}
+
+impl MiddlewareChain {
+    /// Create an empty chain
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report panicking middleware through an instance's diagnostics
+    pub fn with_diagnostics(mut self, diagnostics: Diagnostics) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    /// Insert a middleware
+    ///
+    /// # Arguments
+    /// * `middleware` - The middleware; replaces one of the same name
+    /// * `order` - Position in the chain, lower runs first
+    pub fn add(&self, middleware: Arc<dyn DispatchMiddleware>, order: i32) {
+        let mut entries = self.entries.write().unwrap_or_else(|p| p.into_inner());
+        entries.retain(|(_, existing)| existing.name() != middleware.name());
+
+        let index = entries.partition_point(|(existing, _)| *existing <= order);
+        entries.insert(index, (order, middleware));
+    }
+
+    /// Remove a middleware by name
+    ///
+    /// # Returns
+    /// Whether a middleware was removed
+    pub fn remove(&self, name: &str) -> bool {
+        let mut entries = self.entries.write().unwrap_or_else(|p| p.into_inner());
+        let before = entries.len();
+        entries.retain(|(_, middleware)| middleware.name() != name);
+        entries.len() != before
+    }
+
+    /// Get the names of the middleware, in running order
+    pub fn names(&self) -> Vec<String> {
+        self.snapshot()
+            .iter()
+            .map(|middleware| middleware.name().to_string())
+            .collect()
+    }
+
+    /// Run the before-routing step of every middleware
+    ///
+    /// # Returns
+    /// The name of the middleware that answered the query and its results,
+    /// or None to route the query
+    pub fn before_routing(
+        &self,
+        context: &mut QueryContext,
+    ) -> Option<(String, Vec<PluginResult>)> {
+        for middleware in self.snapshot() {
+            let step = panic::catch_unwind(AssertUnwindSafe(|| middleware.before_routing(context)));
+            match step {
+                Ok(BeforeRouting::Continue) => {}
+                Ok(BeforeRouting::Respond(results)) => {
+                    return Some((middleware.name().to_string(), results));
+                }
+                Err(_) => self.diagnostics.warn(
+                    "middleware",
+                    &format!("Middleware '{}' panicked before routing", middleware.name()),
+                ),
+            }
+        }
+        None
+    }
+
+    /// Run the after-merge step of every middleware
+    pub fn after_merge(&self, context: &QueryContext, merged: &mut MergedResults) {
+        for middleware in self.snapshot() {
+            if panic::catch_unwind(AssertUnwindSafe(|| middleware.after_merge(context, merged)))
+                .is_err()
+            {
+                self.diagnostics.warn(
+                    "middleware",
+                    &format!("Middleware '{}' panicked after merge", middleware.name()),
+                );
+            }
+        }
+    }
+
+    /// Copy of the chain, so middleware runs without holding the lock
+    fn snapshot(&self) -> Vec<Arc<dyn DispatchMiddleware>> {
+        let entries = self.entries.read().unwrap_or_else(|p| p.into_inner());
+        entries
+            .iter()
+            .map(|(_, middleware)| middleware.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Tagger {
+        name: &'static str,
+        panics: bool,
+    }
+
+    impl DispatchMiddleware for Tagger {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn before_routing(&self, context: &mut QueryContext) -> BeforeRouting {
+            if self.panics {
+                panic!("tagger failed");
+            }
+            context.query.push_str(self.name);
+            BeforeRouting::Continue
+        }
+    }
+
+    fn tagger(name: &'static str, panics: bool) -> Arc<dyn DispatchMiddleware> {
+        Arc::new(Tagger { name, panics })
+    }
+
+    #[test]
+    fn test_chain_order() {
+        let chain = MiddlewareChain::new();
+        chain.add(tagger("b", false), 10);
+        chain.add(tagger("a", false), -5);
+        chain.add(tagger("c", false), 10);
+        chain.add(tagger("boom", true), 0);
+        assert_eq!(chain.names(), vec!["a", "boom", "b", "c"]);
+
+        let mut context = QueryContext::new(">");
+        assert_eq!(chain.before_routing(&mut context), None);
+        assert_eq!(context.query, ">abc");
+
+        // Adding under an existing name moves it
+        chain.add(tagger("a", false), 20);
+        assert!(chain.remove("boom"));
+        assert!(!chain.remove("boom"));
+        assert_eq!(chain.names(), vec!["b", "c", "a"]);
+    }
+}
@@ -0,0 +1,747 @@
+/// Plugins distributed as native libraries
+///
+/// Third-party plugins can ship as a `cdylib` (`.so`, `.dll`, `.dylib`)
+/// that the launcher loads at runtime, without Volt being recompiled. Rust
+/// has no stable ABI and the library may be built with another compiler
+/// against another release of this crate, so the two sides share no Rust
+/// types. They talk through a small C interface instead:
+///
+/// - the library exports `volt_plugin_entry_v1`, returning a
+///   `NativePluginVTable`
+/// - the host refuses tables whose `abi_version` isn't `NATIVE_ABI_VERSION`,
+///   or whose `api_version` it doesn't support (see `crate::compat`)
+/// - each call hands over a JSON-RPC request of the plugin protocol
+///   (`crate::protocol`) and gets the response back in a buffer, which the
+///   library frees itself
+///
+/// Plugin crates built as `cdylib` export their plugin with a macro:
+///
+/// ```ignore
+/// volt_plugin_api::export_native_plugin!(MyPlugin::new);
+/// ```
+///
+/// Hosts built with the `native-plugins` feature load them during discovery:
+///
+/// ```ignore
+/// let loaders = PluginLoaders::new().with(PluginBackend::Native, native::loader());
+/// registry.discover_and_register(&plugins_dir, &loaders)?;
+/// ```
+///
+/// Native plugins run in the launcher's process with its privileges: only
+/// load libraries from trusted sources. The library side drives the plugin's
+/// futures on the calling thread with a minimal executor, so plugins needing
+/// a runtime (e.g. for tokio-based I/O) must start their own.
+use crate::compat::CURRENT_API_VERSION;
+use crate::keys::{KeyEvent, KeyResponse};
+use crate::plugin::{Plugin, QueryContext};
+use crate::protocol::{RemotePluginInfo, RpcRequest, RpcResponse, error_codes, methods};
+use crate::result::PluginResult;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::ffi::c_void;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake};
+
+/// Version of the C interface described in this module
+///
+/// Bumped on any change to `NativePluginVTable` or `NativeBuffer`, together
+/// with the name of the entry point.
+pub const NATIVE_ABI_VERSION: u32 = 1;
+
+/// Symbol exported by native plugin libraries, see `NativeEntryPoint`
+pub const ENTRY_SYMBOL: &str = "volt_plugin_entry_v1";
+
+/// Signature of the entry point
+pub type NativeEntryPoint = unsafe extern "C" fn() -> NativePluginVTable;
+
+/// Bytes handed across the library boundary
+///
+/// Only the side that created a buffer may free it.
+#[repr(C)]
+#[derive(Debug)]
+pub struct NativeBuffer {
+    /// First byte
+    pub ptr: *mut u8,
+    /// Number of bytes
+    pub len: usize,
+    /// Allocated capacity
+    pub capacity: usize,
+}
+
+impl NativeBuffer {
+    /// Hand a string over to the other side
+    pub fn from_string(text: String) -> Self {
+        let mut bytes = std::mem::ManuallyDrop::new(text.into_bytes());
+        Self {
+            ptr: bytes.as_mut_ptr(),
+            len: bytes.len(),
+            capacity: bytes.capacity(),
+        }
+    }
+
+    /// Read the bytes without taking them over
+    ///
+    /// # Safety
+    /// `ptr` must be valid for reads of `len` bytes while the slice lives
+    pub unsafe fn as_bytes(&self) -> &[u8] {
+        if self.ptr.is_null() {
+            return &[];
+        }
+        // SAFETY: guaranteed by the caller
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+
+    /// Free a buffer created by `from_string` on this side of the boundary
+    ///
+    /// # Safety
+    /// The buffer must come from `from_string` in the same library and must
+    /// not be used afterwards
+    pub unsafe fn free(self) {
+        if !self.ptr.is_null() {
+            // SAFETY: guaranteed by the caller
+            drop(unsafe { Vec::from_raw_parts(self.ptr, self.len, self.capacity) });
+        }
+    }
+}
+
+/// Functions of a native plugin, returned by its entry point
+///
+/// The table and the instance stay valid until `destroy` is called.
+#[repr(C)]
+#[derive(Debug)]
+pub struct NativePluginVTable {
+    /// Version of the C interface the library was built with
+    pub abi_version: u32,
+    /// Plugin API version the library was built with
+    pub api_version: u32,
+    /// Plugin instance, passed back to every function
+    pub instance: *mut c_void,
+    /// Answer a JSON-RPC request, as UTF-8 JSON
+    ///
+    /// The response must be freed with `free_buffer`. May be called from
+    /// several threads at once.
+    pub call: unsafe extern "C" fn(instance: *mut c_void, request: *const u8, request_len: usize) -> NativeBuffer,
+    /// Free a buffer returned by `call`
+    pub free_buffer: unsafe extern "C" fn(buffer: NativeBuffer),
+    /// Drop the plugin instance; nothing is called afterwards
+    pub destroy: unsafe extern "C" fn(instance: *mut c_void),
+}
+
+impl NativePluginVTable {
+    /// Wrap a plugin for the host, on the library side
+    ///
+    /// Used by `export_native_plugin!`.
+    pub fn new(plugin: Box<dyn Plugin + Send + Sync>) -> Self {
+        Self {
+            abi_version: NATIVE_ABI_VERSION,
+            api_version: CURRENT_API_VERSION,
+            instance: Box::into_raw(Box::new(plugin)) as *mut c_void,
+            call: library_call,
+            free_buffer: library_free_buffer,
+            destroy: library_destroy,
+        }
+    }
+}
+
+/// Export a plugin from a `cdylib` crate
+///
+/// Defines the entry point and declares the API version (so don't also
+/// invoke `declare_api_version!`). Takes a function creating the plugin:
+///
+/// ```ignore
+/// volt_plugin_api::export_native_plugin!(MyPlugin::new);
+/// ```
+#[macro_export]
+macro_rules! export_native_plugin {
+    ($constructor:path) => {
+        $crate::declare_api_version!();
+
+        /// Entry point of the plugin, see `volt_plugin_api::native`
+        #[unsafe(no_mangle)]
+        pub extern "C" fn volt_plugin_entry_v1() -> $crate::native::NativePluginVTable {
+            $crate::native::NativePluginVTable::new(::std::boxed::Box::new($constructor()))
+        }
+    };
+}
+
+// ========== Library Side ==========
+
+type ExportedPlugin = Box<dyn Plugin + Send + Sync>;
+
+unsafe extern "C" fn library_call(instance: *mut c_void, request: *const u8, request_len: usize) -> NativeBuffer {
+    // SAFETY: `instance` comes from `NativePluginVTable::new` and isn't destroyed yet
+    let plugin = unsafe { &*(instance as *const ExportedPlugin) };
+    let request = if request.is_null() {
+        &[][..]
+    } else {
+        // SAFETY: the host passes a buffer of `request_len` bytes
+        unsafe { std::slice::from_raw_parts(request, request_len) }
+    };
+
+    // Unwinding into the host would abort it
+    let response = panic::catch_unwind(AssertUnwindSafe(|| answer(plugin.as_ref(), request)))
+        .unwrap_or_else(|_| RpcResponse::failure(0, error_codes::INTERNAL_ERROR, "Native plugin panicked"));
+    NativeBuffer::from_string(serde_json::to_string(&response).unwrap_or_default())
+}
+
+unsafe extern "C" fn library_free_buffer(buffer: NativeBuffer) {
+    // SAFETY: the host only frees buffers returned by `library_call`
+    unsafe { buffer.free() }
+}
+
+unsafe extern "C" fn library_destroy(instance: *mut c_void) {
+    if !instance.is_null() {
+        // SAFETY: `instance` comes from `NativePluginVTable::new` and is destroyed once
+        let plugin = unsafe { Box::from_raw(instance as *mut ExportedPlugin) };
+        let _ = panic::catch_unwind(AssertUnwindSafe(move || drop(plugin)));
+    }
+}
+
+/// Answer one request of the host, for native and WASM plugins
+pub(crate) fn answer(plugin: &dyn Plugin, request: &[u8]) -> RpcResponse {
+    let request = match std::str::from_utf8(request)
+        .map_err(|e| format!("Failed to parse request: {}", e))
+        .and_then(RpcRequest::decode)
+    {
+        Ok(request) => request,
+        Err(e) => return RpcResponse::failure(0, error_codes::PARSE_ERROR, e),
+    };
+
+    let params = &request.params;
+    let result = match request.method.as_str() {
+        methods::LIST_PLUGINS => to_value(vec![describe(plugin)]),
+        methods::CAN_HANDLE => {
+            param::<QueryContext>(params, "context").map(|context| Value::Bool(plugin.can_handle(&context)))
+        }
+        methods::MATCH_QUERY => param::<QueryContext>(params, "context").and_then(|context| {
+            block_on(plugin.match_query(&context))
+                .map_err(internal_error)
+                .and_then(to_value)
+        }),
+        methods::EXECUTE => param::<PluginResult>(params, "result").and_then(|result| {
+            block_on(plugin.execute(&result))
+                .map_err(internal_error)
+                .and_then(to_value)
+        }),
+        methods::KEY_EVENT => param::<KeyEvent>(params, "event").and_then(|event| {
+            let context = param::<QueryContext>(params, "context")?;
+            block_on(plugin.on_key_event(&event, &context))
+                .map_err(internal_error)
+                .and_then(|response: KeyResponse| to_value(response))
+        }),
+        method => Err((
+            error_codes::METHOD_NOT_FOUND,
+            format!("Unknown method '{}'", method),
+        )),
+    };
+
+    match result {
+        Ok(value) => RpcResponse::success(request.id, value),
+        Err((code, message)) => RpcResponse::failure(request.id, code, message),
+    }
+}
+
+/// Describe a plugin as hosts of the plugin protocol do
+fn describe(plugin: &dyn Plugin) -> RemotePluginInfo {
+    RemotePluginInfo {
+        id: plugin.id().to_string(),
+        name: plugin.name().to_string(),
+        description: plugin.description().to_string(),
+        version: plugin.version().map(str::to_string),
+        author: plugin.author().map(str::to_string),
+        homepage: plugin.homepage().map(str::to_string),
+        license: plugin.license().map(str::to_string),
+    }
+}
+
+/// Read a named request parameter
+fn param<T: DeserializeOwned>(params: &Value, name: &str) -> Result<T, (i64, String)> {
+    serde_json::from_value(params.get(name).cloned().unwrap_or(Value::Null)).map_err(|e| {
+        (
+            error_codes::INVALID_PARAMS,
+            format!("Invalid parameter '{}': {}", name, e),
+        )
+    })
+}
+
+fn to_value<T: serde::Serialize>(value: T) -> Result<Value, (i64, String)> {
+    serde_json::to_value(value).map_err(|e| internal_error(format!("Failed to serialize response: {}", e)))
+}
+
+fn internal_error(message: String) -> (i64, String) {
+    (error_codes::INTERNAL_ERROR, message)
+}
+
+/// Wakes the thread polling a future
+struct ThreadWaker(std::thread::Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Run a future to completion on the current thread
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Arc::new(ThreadWaker(std::thread::current())).into();
+    let mut context = Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+// ========== Host Side ==========
+
+#[cfg(feature = "native-plugins")]
+pub use host::{NativePlugin, loader};
+
+#[cfg(feature = "native-plugins")]
+mod host {
+    use super::*;
+    use crate::cancel::CANCELLED;
+    use crate::compat::ApiVersion;
+    use crate::devmode::PluginLoader;
+    use crate::outcome::ExecuteOutcome;
+    use async_trait::async_trait;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    /// Prefix of the temp directories holding copies of dev mode libraries
+    const SHADOW_PREFIX: &str = "volt-native-dev-";
+
+    /// A loaded library and the plugin instance it created
+    struct Instance {
+        vtable: NativePluginVTable,
+        next_id: AtomicU64,
+        /// Unloaded after the instance is destroyed
+        _library: Option<libloading::Library>,
+    }
+
+    // SAFETY: the instance is a `Box<dyn Plugin + Send + Sync>` on the
+    // library side, and `call` may be used from several threads
+    unsafe impl Send for Instance {}
+    unsafe impl Sync for Instance {}
+
+    impl Instance {
+        /// Send a request to the plugin and wait for its answer
+        fn call(&self, method: &str, params: Value) -> Result<Value, String> {
+            let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+            let request = serde_json::to_string(&RpcRequest::new(id, method, params))
+                .map_err(|e| format!("Failed to serialize request: {}", e))?;
+
+            // SAFETY: the table was checked when loading and the instance is alive
+            let response = unsafe {
+                let buffer = (self.vtable.call)(self.vtable.instance, request.as_ptr(), request.len());
+                let text = String::from_utf8_lossy(buffer.as_bytes()).into_owned();
+                (self.vtable.free_buffer)(buffer);
+                text
+            };
+            RpcResponse::decode(&response)?.into_result()
+        }
+    }
+
+    impl Drop for Instance {
+        fn drop(&mut self) {
+            // SAFETY: the instance is destroyed once, before its library is unloaded
+            unsafe { (self.vtable.destroy)(self.vtable.instance) }
+        }
+    }
+
+    /// A private copy of a library, removed once the library is unloaded
+    pub(super) struct ShadowCopy {
+        pub(super) path: PathBuf,
+        /// Randomly named and only accessible to the current user, so other
+        /// users can't plant or swap the library; removed when dropped
+        _dir: tempfile::TempDir,
+    }
+
+    impl ShadowCopy {
+        /// Copy a library into a directory of its own
+        pub(super) fn new(path: &Path) -> Result<Self, String> {
+            let file_name = path
+                .file_name()
+                .ok_or_else(|| format!("Invalid native plugin path {}", path.display()))?;
+            let mut builder = tempfile::Builder::new();
+            builder.prefix(SHADOW_PREFIX);
+            #[cfg(unix)]
+            builder.permissions(std::os::unix::fs::PermissionsExt::from_mode(0o700));
+            let dir = builder
+                .tempdir()
+                .map_err(|e| format!("Failed to create directory for native plugin copy: {}", e))?;
+            let copy = dir.path().join(file_name);
+
+            let copied = std::fs::File::open(path).and_then(|mut source| {
+                let mut target = std::fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(&copy)?;
+                std::io::copy(&mut source, &mut target)
+            });
+            copied.map_err(|e| format!("Failed to copy native plugin {}: {}", path.display(), e))?;
+
+            Ok(Self { path: copy, _dir: dir })
+        }
+    }
+
+    /// A plugin loaded from a native library
+    pub struct NativePlugin {
+        info: RemotePluginInfo,
+        /// Shared with the blocking threads calls run on
+        instance: Arc<Instance>,
+        /// Dropped after the instance, see `load_copy`
+        _shadow: Option<ShadowCopy>,
+    }
+
+    impl NativePlugin {
+        /// Load a plugin library
+        ///
+        /// # Arguments
+        /// * `path` - Library exporting `volt_plugin_entry_v1`
+        ///
+        /// # Returns
+        /// The plugin, or an error if the library can't be loaded or was
+        /// built for another ABI or API version
+        pub fn load(path: &Path) -> Result<Self, String> {
+            // SAFETY: loading runs the library's initializers; native plugins are trusted code
+            let library = unsafe { libloading::Library::new(path) }
+                .map_err(|e| format!("Failed to load native plugin {}: {}", path.display(), e))?;
+
+            // SAFETY: the symbol is declared with this signature by `export_native_plugin!`
+            let vtable = unsafe {
+                let entry = library.get::<NativeEntryPoint>(ENTRY_SYMBOL.as_bytes()).map_err(|e| {
+                    format!(
+                        "Native plugin {} doesn't export '{}' (built for another ABI version?): {}",
+                        path.display(),
+                        ENTRY_SYMBOL,
+                        e
+                    )
+                })?;
+                entry()
+            };
+
+            // SAFETY: the table comes from the library's entry point
+            unsafe { Self::from_vtable(vtable, Some(library)) }
+        }
+
+        /// Load a private copy of a plugin library
+        ///
+        /// The system loader hands out a library already loaded from the
+        /// same path instead of loading it again, and Windows locks loaded
+        /// libraries so they can't be rebuilt. Plugins in dev mode are loaded
+        /// from copies, so a rebuilt library is picked up while the previous
+        /// build is still loaded. Each copy gets a new directory only the
+        /// current user can access, removed when the plugin is dropped.
+        pub fn load_copy(path: &Path) -> Result<Self, String> {
+            // Removed again if loading fails
+            let shadow = ShadowCopy::new(path)?;
+            let mut plugin = Self::load(&shadow.path)?;
+            plugin._shadow = Some(shadow);
+            Ok(plugin)
+        }
+
+        /// Wrap a function table, taking over its instance
+        ///
+        /// # Safety
+        /// If `abi_version` matches, the functions must be valid for the
+        /// instance, and stay so while `library` is loaded
+        pub unsafe fn from_vtable(
+            vtable: NativePluginVTable,
+            library: Option<libloading::Library>,
+        ) -> Result<Self, String> {
+            if vtable.abi_version != NATIVE_ABI_VERSION {
+                // The table's layout may differ; don't call into it, not even to destroy the instance
+                return Err(format!(
+                    "Native plugin uses ABI version {}, but this version of Volt supports version {}",
+                    vtable.abi_version, NATIVE_ABI_VERSION
+                ));
+            }
+
+            let instance = Instance {
+                vtable,
+                next_id: AtomicU64::new(1),
+                _library: library,
+            };
+            ApiVersion(instance.vtable.api_version)
+                .check()
+                .map_err(|reason| format!("Native plugin cannot be loaded: it {}", reason))?;
+
+            let plugins: Vec<RemotePluginInfo> =
+                serde_json::from_value(instance.call(methods::LIST_PLUGINS, Value::Null)?)
+                    .map_err(|e| format!("Failed to parse native plugin info: {}", e))?;
+            let info = plugins
+                .into_iter()
+                .next()
+                .ok_or_else(|| "Native plugin describes no plugin".to_string())?;
+
+            Ok(Self {
+                info,
+                instance: Arc::new(instance),
+                _shadow: None,
+            })
+        }
+
+        /// Call the plugin on a blocking thread, so a slow plugin doesn't
+        /// stall the runtime
+        ///
+        /// Once the query's token is cancelled the call stops waiting; the
+        /// plugin finishes on its own, as native code can't be interrupted.
+        async fn call_blocking(
+            &self,
+            method: &'static str,
+            params: Value,
+            context: Option<&QueryContext>,
+        ) -> Result<Value, String> {
+            let instance = Arc::clone(&self.instance);
+            let call = async move {
+                tokio::task::spawn_blocking(move || instance.call(method, params))
+                    .await
+                    .map_err(|e| format!("Native plugin call failed: {}", e))?
+            };
+
+            match context.and_then(QueryContext::cancellation) {
+                Some(token) => token.run(call).await.unwrap_or_else(|| Err(CANCELLED.to_string())),
+                None => call.await,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Plugin for NativePlugin {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn id(&self) -> &str {
+            &self.info.id
+        }
+
+        fn name(&self) -> &str {
+            &self.info.name
+        }
+
+        fn description(&self) -> &str {
+            &self.info.description
+        }
+
+        fn version(&self) -> Option<&str> {
+            self.info.version.as_deref()
+        }
+
+        fn author(&self) -> Option<&str> {
+            self.info.author.as_deref()
+        }
+
+        fn homepage(&self) -> Option<&str> {
+            self.info.homepage.as_deref()
+        }
+
+        fn license(&self) -> Option<&str> {
+            self.info.license.as_deref()
+        }
+
+        fn can_handle(&self, context: &QueryContext) -> bool {
+            self.instance
+                .call(methods::CAN_HANDLE, serde_json::json!({ "context": context }))
+                .is_ok_and(|value| value == Value::Bool(true))
+        }
+
+        async fn match_query(&self, context: &QueryContext) -> Result<Vec<PluginResult>, String> {
+            let value = self
+                .call_blocking(methods::MATCH_QUERY, serde_json::json!({ "context": context }), Some(context))
+                .await?;
+            serde_json::from_value(value).map_err(|e| format!("Failed to parse results: {}", e))
+        }
+
+        async fn execute(&self, result: &PluginResult) -> Result<ExecuteOutcome, String> {
+            let value = self
+                .call_blocking(methods::EXECUTE, serde_json::json!({ "result": result }), None)
+                .await?;
+            serde_json::from_value(value).map_err(|e| format!("Failed to parse execute outcome: {}", e))
+        }
+
+        async fn on_key_event(&self, event: &KeyEvent, context: &QueryContext) -> Result<KeyResponse, String> {
+            let value = self
+                .call_blocking(
+                    methods::KEY_EVENT,
+                    serde_json::json!({ "event": event, "context": context }),
+                    Some(context),
+                )
+                .await?;
+            serde_json::from_value(value).map_err(|e| format!("Failed to parse key response: {}", e))
+        }
+    }
+
+    /// Loader of native plugin packages, for `PluginBackend::Native`
+    ///
+    /// Loads the library named by the manifest's `main`, from a copy for
+    /// plugins in dev mode (see `NativePlugin::load_copy`).
+    pub fn loader() -> PluginLoader {
+        Arc::new(|package_dir: &Path, manifest: &crate::manifest::PluginManifest| {
+            let path = manifest.entry_point(package_dir)?;
+            let plugin = if manifest.dev_mode {
+                NativePlugin::load_copy(&path)?
+            } else {
+                NativePlugin::load(&path)?
+            };
+            Ok(Box::new(plugin) as Box<dyn Plugin + Send + Sync>)
+        })
+    }
+}
+
+#[cfg(all(test, feature = "native-plugins"))]
+mod tests {
+    use super::*;
+    use crate::outcome::ExecuteOutcome;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    struct Echo {
+        dropped: Arc<AtomicBool>,
+    }
+
+    impl Drop for Echo {
+        fn drop(&mut self) {
+            self.dropped.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Plugin for Echo {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn id(&self) -> &str {
+            "echo"
+        }
+
+        fn name(&self) -> &str {
+            "Echo"
+        }
+
+        fn description(&self) -> &str {
+            "Repeats the query"
+        }
+
+        fn version(&self) -> Option<&str> {
+            Some("1.2.0")
+        }
+
+        fn can_handle(&self, context: &QueryContext) -> bool {
+            !context.query.is_empty()
+        }
+
+        async fn match_query(&self, context: &QueryContext) -> Result<Vec<PluginResult>, String> {
+            match context.query.as_str() {
+                "panic" => panic!("echo panicked"),
+                "fail" => Err("Nothing to echo".to_string()),
+                "slow" => {
+                    std::thread::sleep(std::time::Duration::from_millis(500));
+                    Ok(Vec::new())
+                }
+                query => Ok(vec![PluginResult::new("echo", query.to_string())]),
+            }
+        }
+    }
+
+    fn echo(dropped: &Arc<AtomicBool>) -> NativePluginVTable {
+        NativePluginVTable::new(Box::new(Echo {
+            dropped: dropped.clone(),
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_native_plugin_roundtrip() {
+        let dropped = Arc::new(AtomicBool::new(false));
+        let plugin = unsafe { NativePlugin::from_vtable(echo(&dropped), None) }.unwrap();
+        assert_eq!(plugin.id(), "echo");
+        assert_eq!(plugin.version(), Some("1.2.0"));
+
+        assert!(plugin.can_handle(&QueryContext::new("hi")));
+        assert!(!plugin.can_handle(&QueryContext::new("")));
+        let results = plugin.match_query(&QueryContext::new("hi")).await.unwrap();
+        assert_eq!(results[0].title, "hi");
+        assert_eq!(
+            plugin.execute(&results[0]).await.unwrap(),
+            ExecuteOutcome::default()
+        );
+
+        // Errors and panics come back as errors instead of unwinding into the host
+        let failed = plugin.match_query(&QueryContext::new("fail")).await.unwrap_err();
+        assert!(failed.contains("Nothing to echo"), "{}", failed);
+        let panicked = plugin.match_query(&QueryContext::new("panic")).await.unwrap_err();
+        assert!(panicked.contains("panicked"), "{}", panicked);
+
+        drop(plugin);
+        assert!(dropped.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_native_plugin_call_gives_up_when_cancelled() {
+        let dropped = Arc::new(AtomicBool::new(false));
+        let plugin = unsafe { NativePlugin::from_vtable(echo(&dropped), None) }.unwrap();
+
+        let token = crate::cancel::CancellationToken::new();
+        token.cancel_after(std::time::Duration::from_millis(20));
+        let started = std::time::Instant::now();
+        let cancelled = plugin
+            .match_query(&QueryContext::new("slow").with_cancellation(token))
+            .await
+            .unwrap_err();
+        assert_eq!(cancelled, crate::cancel::CANCELLED);
+        assert!(started.elapsed() < std::time::Duration::from_millis(400));
+    }
+
+    #[test]
+    fn test_shadow_copies_are_private() {
+        let temp_dir = std::env::temp_dir().join("volt_test_native_shadow");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let library = temp_dir.join("libecho.so");
+        std::fs::write(&library, b"not a library").unwrap();
+
+        let shadow = super::host::ShadowCopy::new(&library).unwrap();
+        assert_eq!(std::fs::read(&shadow.path).unwrap(), b"not a library");
+        let dir = shadow.path.parent().unwrap().to_path_buf();
+        assert_ne!(dir, std::env::temp_dir());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&dir).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+        drop(shadow);
+        assert!(!dir.exists());
+
+        let failed = NativePlugin::load_copy(&library).err().unwrap();
+        assert!(failed.contains("Failed to load native plugin"), "{}", failed);
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn test_native_plugin_version_checks() {
+        let dropped = Arc::new(AtomicBool::new(false));
+        let mut vtable = echo(&dropped);
+        vtable.abi_version = NATIVE_ABI_VERSION + 1;
+        let refused = unsafe { NativePlugin::from_vtable(vtable, None) }.err().unwrap();
+        assert!(refused.contains("ABI version 2"), "{}", refused);
+
+        let mut vtable = echo(&dropped);
+        vtable.api_version = CURRENT_API_VERSION + 1;
+        let refused = unsafe { NativePlugin::from_vtable(vtable, None) }.err().unwrap();
+        assert!(refused.contains("requires plugin API"), "{}", refused);
+        assert!(dropped.load(Ordering::SeqCst));
+
+        let missing = NativePlugin::load(std::path::Path::new("/nonexistent/libecho.so"))
+            .err()
+            .unwrap();
+        assert!(missing.contains("Failed to load native plugin"), "{}", missing);
+    }
+}
@@ -0,0 +1,364 @@
+/// Per-plugin network allowlists
+///
+/// The `network` permission lets a plugin reach the internet. A manifest
+/// can narrow it down to the hosts the plugin actually talks to:
+///
+/// ```json
+/// "permissions": ["network"],
+/// "network": { "allow": ["api.github.com", "*.githubusercontent.com"] }
+/// ```
+///
+/// `VoltPluginAPI::download` and `VoltPluginAPI::request_url_preview` then
+/// refuse every other host, including hosts reached through redirects.
+/// Plugins without an `allow` list aren't restricted by host.
+///
+/// Users can expand a plugin's grant: when a plugin reaches for a host
+/// outside its allowlist, the host's `NetworkGrantHandler` asks whether to
+/// allow it, and approved hosts are remembered in `network_grants.json`.
+/// Settings surfaces list and revoke them with `VoltPluginAPI::network_grants`
+/// and `VoltPluginAPI::revoke_network_host`.
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// File in the app data directory holding the hosts users granted
+pub const NETWORK_GRANTS_FILE: &str = "network_grants.json";
+
+/// `network` section of a manifest
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkManifest {
+    /// Hosts the plugin may connect to, see `HostPattern`
+    #[serde(default)]
+    pub allow: Vec<String>,
+}
+
+/// Host of an allowlist
+///
+/// `api.github.com` only matches that host; `*.github.com` matches its
+/// subdomains, but not `github.com` itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostPattern {
+    /// A single host
+    Exact(String),
+    /// Any subdomain of a domain
+    Subdomains(String),
+}
+
+impl HostPattern {
+    /// Parse a host pattern
+    ///
+    /// # Returns
+    /// Err if the pattern isn't a bare host, e.g. a URL or a host with a port
+    pub fn parse(pattern: &str) -> Result<Self, String> {
+        let normalized = normalize_host(pattern);
+        let (domain, subdomains) = match normalized.strip_prefix("*.") {
+            Some(domain) => (domain, true),
+            None => (normalized.as_str(), false),
+        };
+
+        let invalid = domain.is_empty()
+            || domain.split('.').any(|label| {
+                label.is_empty() || !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            });
+        if invalid {
+            return Err(format!(
+                "'{}' is not a host name, e.g. 'api.example.com' or '*.example.com'",
+                pattern
+            ));
+        }
+
+        Ok(if subdomains {
+            HostPattern::Subdomains(domain.to_string())
+        } else {
+            HostPattern::Exact(domain.to_string())
+        })
+    }
+
+    /// Check if a host matches the pattern
+    pub fn matches(&self, host: &str) -> bool {
+        let host = normalize_host(host);
+        match self {
+            HostPattern::Exact(expected) => host == *expected,
+            HostPattern::Subdomains(domain) => host
+                .strip_suffix(domain.as_str())
+                .is_some_and(|subdomain| subdomain.len() > 1 && subdomain.ends_with('.')),
+        }
+    }
+}
+
+impl fmt::Display for HostPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HostPattern::Exact(host) => write!(f, "{}", host),
+            HostPattern::Subdomains(domain) => write!(f, "*.{}", domain),
+        }
+    }
+}
+
+/// Lowercase a host and drop the trailing dot of fully qualified names
+fn normalize_host(host: &str) -> String {
+    host.trim().trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// Callback installed by the host to expand a plugin's network grant
+///
+/// Returns whether the user allowed the plugin to connect to the host.
+pub type NetworkGrantHandler = Arc<dyn Fn(&NetworkGrantRequest) -> bool + Send + Sync>;
+
+/// A host outside a plugin's allowlist, awaiting the user's decision
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkGrantRequest {
+    /// Plugin asking for the host
+    pub plugin_id: String,
+    /// Display name of the plugin
+    pub plugin_name: String,
+    /// The host, e.g. "cdn.example.com"
+    pub host: String,
+    /// URL the plugin requested
+    pub url: String,
+}
+
+/// Hosts users granted plugins beyond their allowlists
+///
+/// Cheap to clone; all clones share the same grants.
+#[derive(Clone)]
+pub struct NetworkGrants {
+    path: PathBuf,
+    hosts: Arc<Mutex<BTreeMap<String, BTreeSet<String>>>>,
+}
+
+impl NetworkGrants {
+    /// Load the grants stored in a file; a missing or corrupt file grants nothing
+    pub fn new(path: PathBuf) -> Self {
+        let hosts = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            hosts: Arc::new(Mutex::new(hosts)),
+        }
+    }
+
+    /// Get the hosts granted to a plugin, sorted
+    pub fn hosts(&self, plugin_id: &str) -> Vec<String> {
+        let hosts = self.hosts.lock().unwrap_or_else(|p| p.into_inner());
+        hosts
+            .get(plugin_id)
+            .map(|granted| granted.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Check if a host was granted to a plugin
+    pub fn is_granted(&self, plugin_id: &str, host: &str) -> bool {
+        let hosts = self.hosts.lock().unwrap_or_else(|p| p.into_inner());
+        hosts
+            .get(plugin_id)
+            .is_some_and(|granted| granted.contains(&normalize_host(host)))
+    }
+
+    /// Let a plugin connect to a host, and remember it
+    pub fn grant(&self, plugin_id: &str, host: &str) -> Result<(), String> {
+        let mut hosts = self.hosts.lock().unwrap_or_else(|p| p.into_inner());
+        hosts
+            .entry(plugin_id.to_string())
+            .or_default()
+            .insert(normalize_host(host));
+        self.save(&hosts)
+    }
+
+    /// Withdraw a host granted to a plugin
+    ///
+    /// # Returns
+    /// Whether the host was granted
+    pub fn revoke(&self, plugin_id: &str, host: &str) -> Result<bool, String> {
+        let mut hosts = self.hosts.lock().unwrap_or_else(|p| p.into_inner());
+        let Some(granted) = hosts.get_mut(plugin_id) else {
+            return Ok(false);
+        };
+        if !granted.remove(&normalize_host(host)) {
+            return Ok(false);
+        }
+        if granted.is_empty() {
+            hosts.remove(plugin_id);
+        }
+        self.save(&hosts).map(|()| true)
+    }
+
+    fn save(&self, hosts: &BTreeMap<String, BTreeSet<String>>) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(hosts)
+            .map_err(|e| format!("Failed to serialize network grants: {}", e))?;
+        std::fs::write(&self.path, content)
+            .map_err(|e| format!("Failed to write network grants: {}", e))
+    }
+}
+
+/// Hosts a plugin may connect to
+#[derive(Clone)]
+pub struct NetworkPolicy {
+    plugin_id: String,
+    plugin_name: String,
+    allow: Vec<HostPattern>,
+    grants: NetworkGrants,
+    handler: Option<NetworkGrantHandler>,
+}
+
+impl NetworkPolicy {
+    /// Create the policy of a plugin
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier of the plugin
+    /// * `plugin_name` - Display name, shown when asking for a grant
+    /// * `allow` - The manifest's allowlist
+    /// * `grants` - Hosts granted beyond the allowlist
+    pub fn new(
+        plugin_id: &str,
+        plugin_name: &str,
+        allow: Vec<HostPattern>,
+        grants: NetworkGrants,
+    ) -> Self {
+        Self {
+            plugin_id: plugin_id.to_string(),
+            plugin_name: plugin_name.to_string(),
+            allow,
+            grants,
+            handler: None,
+        }
+    }
+
+    /// Ask the user about hosts outside the allowlist
+    pub fn with_grant_handler(mut self, handler: NetworkGrantHandler) -> Self {
+        self.handler = Some(handler);
+        self
+    }
+
+    /// Check if the plugin may connect to a host without asking
+    pub fn allows(&self, host: &str) -> bool {
+        self.allow.iter().any(|pattern| pattern.matches(host))
+            || self.grants.is_granted(&self.plugin_id, host)
+    }
+
+    /// Check that the plugin may connect to a URL's host
+    ///
+    /// Hosts outside the allowlist go to the grant handler, if any; approved
+    /// hosts are remembered.
+    ///
+    /// # Arguments
+    /// * `url` - The URL requested, shown to the user
+    /// * `host` - Host of the URL
+    ///
+    /// # Returns
+    /// Err naming the refused host and the allowed ones
+    pub fn check(&self, url: &str, host: &str) -> Result<(), String> {
+        if self.allows(host) {
+            return Ok(());
+        }
+
+        if let Some(handler) = &self.handler {
+            let request = NetworkGrantRequest {
+                plugin_id: self.plugin_id.clone(),
+                plugin_name: self.plugin_name.clone(),
+                host: normalize_host(host),
+                url: url.to_string(),
+            };
+            if handler(&request) {
+                self.grants.grant(&self.plugin_id, host)?;
+                return Ok(());
+            }
+        }
+
+        let allowed: Vec<String> = self.allow.iter().map(ToString::to_string).collect();
+        Err(format!(
+            "Plugin '{}' may not connect to {}: its manifest only allows {}",
+            self.plugin_id,
+            normalize_host(host),
+            if allowed.is_empty() {
+                "no hosts".to_string()
+            } else {
+                allowed.join(", ")
+            }
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_host_patterns() {
+        let exact = HostPattern::parse("API.GitHub.com.").unwrap();
+        assert!(exact.matches("api.github.com"));
+        assert!(!exact.matches("evil-api.github.com"));
+
+        let subdomains = HostPattern::parse("*.githubusercontent.com").unwrap();
+        assert!(subdomains.matches("raw.githubusercontent.com"));
+        assert!(!subdomains.matches("githubusercontent.com"));
+        assert!(!subdomains.matches("evilgithubusercontent.com"));
+        assert_eq!(subdomains.to_string(), "*.githubusercontent.com");
+
+        for invalid in [
+            "",
+            "https://api.github.com",
+            "api.github.com:443",
+            "api.github.com/v3",
+            "*",
+            "a..b",
+        ] {
+            assert!(
+                HostPattern::parse(invalid).is_err(),
+                "{} should be rejected",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_grants_expand_policy() {
+        let path = std::env::temp_dir().join("volt_test_network_grants.json");
+        let _ = std::fs::remove_file(&path);
+
+        let allow = vec![HostPattern::parse("api.github.com").unwrap()];
+        let policy =
+            NetworkPolicy::new("github", "GitHub", allow, NetworkGrants::new(path.clone()));
+        assert!(
+            policy
+                .check("https://api.github.com/repos", "api.github.com")
+                .is_ok()
+        );
+        let error = policy.check("https://evil.com/", "evil.com").unwrap_err();
+        assert!(error.contains("evil.com") && error.contains("api.github.com"));
+
+        let asked = Arc::new(AtomicUsize::new(0));
+        let counter = asked.clone();
+        let policy = policy.with_grant_handler(Arc::new(move |request| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            request.host == "cdn.github.com"
+        }));
+        assert!(
+            policy
+                .check("https://cdn.github.com/logo.png", "cdn.github.com")
+                .is_ok()
+        );
+        assert!(
+            policy
+                .check("https://cdn.github.com/logo.png", "CDN.github.com")
+                .is_ok()
+        );
+        assert!(policy.check("https://evil.com/", "evil.com").is_err());
+        assert_eq!(asked.load(Ordering::SeqCst), 2);
+
+        // Grants survive restarts until revoked
+        let grants = NetworkGrants::new(path.clone());
+        assert_eq!(grants.hosts("github"), vec!["cdn.github.com"]);
+        assert!(grants.revoke("github", "cdn.github.com").unwrap());
+        assert!(!NetworkGrants::new(path.clone()).is_granted("github", "cdn.github.com"));
+
+        let _ = std::fs::remove_file(path);
+    }
+}
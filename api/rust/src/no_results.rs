@@ -0,0 +1,114 @@
+/// "Why not" notes of plugins returning no results
+///
+/// A plugin answering a query with nothing leaves its author guessing:
+/// did the keyword not match, was the index still empty, did a token
+/// expire? While answering, a plugin can say why with
+/// `QueryContext::why_not`:
+///
+/// ```ignore
+/// if self.token_expired() {
+///     context.why_not(NoResultsReason::AuthExpired, "Jira token expired on 2024-05-01");
+///     return Ok(Vec::new());
+/// }
+/// ```
+///
+/// Notes are never shown to users. The registry keeps the notes of plugins
+/// that returned no results, logs them at debug level under the plugin's ID
+/// for the log viewer, and attaches those of dev mode plugins to the
+/// dispatch's `MergedResults::diagnostics`. Notes of plugins that did
+/// return results are dropped.
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+
+/// Most notes kept per dispatch
+pub const MAX_NOTES_PER_DISPATCH: usize = 64;
+
+/// Why a plugin returned no results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub enum NoResultsReason {
+    /// The query doesn't start with one of the plugin's keywords
+    KeywordMismatch,
+    /// The plugin's index has nothing in it yet
+    IndexEmpty,
+    /// Credentials of a remote service expired or were revoked
+    AuthExpired,
+    /// A remote service couldn't be reached
+    Offline,
+    /// A remote service refused requests for now
+    RateLimited,
+    /// The plugin needs settings the user hasn't filled in
+    NotConfigured,
+    /// Anything else, explained by the detail
+    Other,
+}
+
+impl NoResultsReason {
+    /// Get the reason as written in logs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NoResultsReason::KeywordMismatch => "keyword mismatch",
+            NoResultsReason::IndexEmpty => "index empty",
+            NoResultsReason::AuthExpired => "auth expired",
+            NoResultsReason::Offline => "offline",
+            NoResultsReason::RateLimited => "rate limited",
+            NoResultsReason::NotConfigured => "not configured",
+            NoResultsReason::Other => "other",
+        }
+    }
+}
+
+/// A plugin's note on why it returned no results
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct NoResultsNote {
+    /// Plugin that wrote the note
+    pub plugin_id: String,
+    /// Why it returned no results
+    pub reason: NoResultsReason,
+    /// Explanation for the plugin's author
+    pub detail: String,
+}
+
+/// Notes written by the plugins of one dispatch
+///
+/// Cheap to clone; all clones share the same notes.
+#[derive(Clone, Default)]
+pub struct NoResultsNotes {
+    notes: Arc<Mutex<Vec<NoResultsNote>>>,
+}
+
+impl NoResultsNotes {
+    /// Create an empty set of notes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a note, unless the dispatch already has `MAX_NOTES_PER_DISPATCH`
+    pub fn add(&self, note: NoResultsNote) {
+        let mut notes = self.notes.lock().unwrap_or_else(|p| p.into_inner());
+        if notes.len() < MAX_NOTES_PER_DISPATCH {
+            notes.push(note);
+        }
+    }
+
+    /// Take every note added so far
+    pub fn take(&self) -> Vec<NoResultsNote> {
+        std::mem::take(&mut *self.notes.lock().unwrap_or_else(|p| p.into_inner()))
+    }
+}
+
+impl std::fmt::Debug for NoResultsNotes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NoResultsNotes").finish_non_exhaustive()
+    }
+}
+
+/// Two handles are equal when they share the same notes
+impl PartialEq for NoResultsNotes {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.notes, &other.notes)
+    }
+}
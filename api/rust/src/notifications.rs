@@ -0,0 +1,69 @@
+/// Desktop notifications sent by plugins
+///
+/// Plugins build a `Notification` and send it with `VoltPluginAPI::notify`;
+/// the host displays it through the `Notifier` it installed. When the user
+/// clicks one of the notification's actions, the host routes it back with
+/// `PluginRegistry::notification_action`, which calls the plugin's
+/// `on_notification_action` hook.
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Callback installed by the host to display notifications
+pub type Notifier = Arc<dyn Fn(&Notification) -> Result<(), String> + Send + Sync>;
+
+/// A notification to display
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Notification {
+    /// Identifier of the notification, unique within the sending plugin
+    pub id: String,
+    /// Plugin that sent the notification; set by `VoltPluginAPI::notify`
+    #[serde(default)]
+    pub plugin_id: String,
+    /// Title line
+    pub title: String,
+    /// Body text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// Buttons shown on the notification
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<NotificationAction>,
+}
+
+/// A button on a notification
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationAction {
+    /// Action identifier passed back to the plugin
+    pub id: String,
+    /// Button label
+    pub label: String,
+}
+
+impl Notification {
+    /// Create a notification with a title
+    pub fn new(id: impl Into<String>, title: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            plugin_id: String::new(),
+            title: title.into(),
+            body: None,
+            actions: Vec::new(),
+        }
+    }
+
+    /// Set the body text
+    pub fn with_body(mut self, body: impl Into<String>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// Add a button
+    pub fn with_action(mut self, id: impl Into<String>, label: impl Into<String>) -> Self {
+        self.actions.push(NotificationAction {
+            id: id.into(),
+            label: label.into(),
+        });
+        self
+    }
+}
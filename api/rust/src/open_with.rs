@@ -0,0 +1,359 @@
+/// "Open with…" application chooser
+///
+/// The aggregator adds an "Open with…" action (`OPEN_WITH_ACTION`) to every
+/// result whose intent opens a file. When the user picks it, the host lists
+/// the applications registered for the file's type with
+/// `intents::open_with_apps`, and opens the file with the chosen one through
+/// `intents::open_with`. File-oriented plugins get the chooser for free
+/// instead of each looking up app associations.
+///
+/// Applications are found through the platform's own registrations:
+/// - Linux: desktop entries declaring the file's MIME type, as reported by
+///   `xdg-mime`
+/// - macOS: Launch Services, queried through `osascript`
+/// - Windows: the `OpenWithProgids` and `OpenWithList` keys of the file's
+///   extension, read with `reg`
+///
+/// The default application comes first. Applications are identified by
+/// their desktop file ID, bundle path or ProgID; `open` looks the chosen one
+/// up again, so hosts never pass a command line back.
+use crate::apps::AppTarget;
+use serde::Serialize;
+use std::path::Path;
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+use std::process::Command;
+
+/// Action ID of the "Open with…" action of file results
+pub const OPEN_WITH_ACTION: &str = "open-with";
+
+/// Label of the "Open with…" action
+pub const OPEN_WITH_LABEL: &str = "Open with…";
+
+/// An application able to open a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenWithApp {
+    /// Desktop file ID, app bundle path or ProgID
+    pub id: String,
+    /// Display name
+    pub name: String,
+    /// Icon name or path
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// The file type's default application
+    pub is_default: bool,
+    /// How to open the file with the application
+    #[serde(skip)]
+    pub target: AppTarget,
+}
+
+/// List the applications able to open a file, the default one first
+///
+/// # Returns
+/// Err if the platform's registrations can't be read
+pub fn applications(file: &Path) -> Result<Vec<OpenWithApp>, String> {
+    let mut apps = platform_applications(file)?;
+
+    let mut seen = std::collections::HashSet::new();
+    apps.retain(|app| seen.insert(app.id.clone()));
+    // Stable sort keeps the platform's order after the default
+    apps.sort_by_key(|app| !app.is_default);
+    Ok(apps)
+}
+
+/// Open a file with one of the applications `applications` lists
+///
+/// # Arguments
+/// * `file` - The file to open
+/// * `app_id` - ID of the chosen application
+pub fn open(file: &Path, app_id: &str) -> Result<(), String> {
+    let app = applications(file)?
+        .into_iter()
+        .find(|app| app.id == app_id)
+        .ok_or_else(|| format!("Application '{}' can't open {}", app_id, file.display()))?;
+
+    app.target.launch(false)
+}
+
+#[cfg(target_os = "linux")]
+fn platform_applications(file: &Path) -> Result<Vec<OpenWithApp>, String> {
+    use crate::desktop;
+
+    let mime_type = run(Command::new("xdg-mime")
+        .args(["query", "filetype"])
+        .arg(file))?;
+    let default = run(Command::new("xdg-mime").args(["query", "default", mime_type.as_str()])).ok();
+
+    Ok(desktop::desktop_entries(&desktop::application_dirs())
+        .into_iter()
+        .filter(|entry| entry.opens(&mime_type))
+        .filter_map(|entry| {
+            let is_default = default.as_deref() == Some(entry.id.as_str());
+            if entry.hidden && !is_default {
+                return None;
+            }
+            Some(OpenWithApp {
+                target: entry.open_target(file).ok()?,
+                id: entry.id,
+                name: entry.name,
+                icon: entry.icon,
+                is_default,
+            })
+        })
+        .collect())
+}
+
+/// Script listing the applications Launch Services has for a file, the
+/// default one first, one bundle path per line
+#[cfg(target_os = "macos")]
+const LAUNCH_SERVICES_SCRIPT: &str = r#"
+ObjC.import('AppKit');
+function run(argv) {
+    const workspace = $.NSWorkspace.sharedWorkspace;
+    const file = $.NSURL.fileURLWithPath(argv[0]);
+    const paths = [];
+    const preferred = workspace.URLForApplicationToOpenURL(file);
+    if (!preferred.isNil()) paths.push(preferred.path.js);
+    const all = workspace.URLsForApplicationsToOpenURL(file);
+    for (let i = 0; i < all.count; i++) paths.push(all.objectAtIndex(i).path.js);
+    return paths.join('\n');
+}
+"#;
+
+#[cfg(target_os = "macos")]
+fn platform_applications(file: &Path) -> Result<Vec<OpenWithApp>, String> {
+    let output = run(Command::new("osascript")
+        .args(["-l", "JavaScript", "-e", LAUNCH_SERVICES_SCRIPT])
+        .arg(file))?;
+
+    Ok(output
+        .lines()
+        .filter(|line| !line.is_empty())
+        .enumerate()
+        .map(|(index, bundle)| OpenWithApp {
+            id: bundle.to_string(),
+            name: Path::new(bundle)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_else(|| bundle.to_string()),
+            icon: Some(bundle.to_string()),
+            is_default: index == 0,
+            target: AppTarget::Executable {
+                path: "open".into(),
+                args: vec![
+                    "-a".to_string(),
+                    bundle.to_string(),
+                    file.to_string_lossy().into_owned(),
+                ],
+                working_dir: None,
+            },
+        })
+        .collect())
+}
+
+#[cfg(target_os = "windows")]
+fn platform_applications(file: &Path) -> Result<Vec<OpenWithApp>, String> {
+    let extension = file
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .ok_or_else(|| format!("{} has no file extension", file.display()))?;
+    let query =
+        |args: &[&str]| run(Command::new("reg").arg("query").args(args)).unwrap_or_default();
+    let class_key = format!("HKCR\\{}", extension);
+    let default = reg_default(&query(&[&class_key, "/ve"]));
+
+    let mut apps = Vec::new();
+    let progids = default.iter().cloned().chain(reg_values(&query(&[&format!(
+        "{}\\OpenWithProgids",
+        class_key
+    )])));
+    for progid in progids {
+        let Some(command) = reg_default(&query(&[
+            &format!("HKCR\\{}\\shell\\open\\command", progid),
+            "/ve",
+        ])) else {
+            continue;
+        };
+        apps.push(OpenWithApp {
+            name: reg_default(&query(&[&format!("HKCR\\{}", progid), "/ve"]))
+                .unwrap_or_else(|| progid.clone()),
+            icon: None,
+            is_default: default.as_ref() == Some(&progid),
+            target: command_target(&command, file)?,
+            id: progid,
+        });
+    }
+
+    // Executables registered under `HKCR\Applications`, e.g. `notepad.exe`
+    let list_key = format!("{}\\OpenWithList", class_key);
+    for exe in reg_subkeys(&query(&[&list_key]), &list_key) {
+        let Some(command) = reg_default(&query(&[
+            &format!("HKCR\\Applications\\{}\\shell\\open\\command", exe),
+            "/ve",
+        ])) else {
+            continue;
+        };
+        apps.push(OpenWithApp {
+            name: exe.trim_end_matches(".exe").to_string(),
+            icon: None,
+            is_default: false,
+            target: command_target(&command, file)?,
+            id: exe,
+        });
+    }
+    Ok(apps)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn platform_applications(_file: &Path) -> Result<Vec<OpenWithApp>, String> {
+    Err("Open with is not supported on this platform".to_string())
+}
+
+/// Run a command and return its trimmed output
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+fn run(command: &mut Command) -> Result<String, String> {
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to run {:?}: {}", command.get_program(), e))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || stdout.is_empty() {
+        return Err(format!(
+            "{:?} failed: {}",
+            command.get_program(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(stdout)
+}
+
+/// Read the default value from `reg query <key> /ve` output
+#[cfg(any(target_os = "windows", test))]
+fn reg_default(output: &str) -> Option<String> {
+    reg_entries(output)
+        .find(|(name, _)| name.starts_with('('))
+        .map(|(_, data)| data)
+        .filter(|data| !data.is_empty())
+}
+
+/// Read the value names from `reg query <key>` output, default value aside
+#[cfg(any(target_os = "windows", test))]
+fn reg_values(output: &str) -> Vec<String> {
+    reg_entries(output)
+        .map(|(name, _)| name)
+        .filter(|name| !name.starts_with('('))
+        .collect()
+}
+
+/// Read the names of the subkeys of a key from `reg query <key>` output
+#[cfg(any(target_os = "windows", test))]
+fn reg_subkeys(output: &str, key: &str) -> Vec<String> {
+    let suffix = key.trim_start_matches("HKCR\\").to_lowercase();
+    output
+        .lines()
+        .filter_map(|line| {
+            let (parent, name) = line.trim().rsplit_once('\\')?;
+            parent
+                .to_lowercase()
+                .ends_with(&suffix)
+                .then(|| name.to_string())
+        })
+        .collect()
+}
+
+/// Split the value lines of `reg query` output into names and data
+///
+/// Value lines are indented and read `<name>    <type>    <data>`.
+#[cfg(any(target_os = "windows", test))]
+fn reg_entries(output: &str) -> impl Iterator<Item = (String, String)> + '_ {
+    output
+        .lines()
+        .filter(|line| line.starts_with(' '))
+        .filter_map(|line| {
+            let mut fields = line.trim().splitn(3, "    ");
+            let name = fields.next()?.to_string();
+            let kind = fields.next()?;
+            kind.starts_with("REG_")
+                .then(|| (name, fields.next().unwrap_or_default().trim().to_string()))
+        })
+}
+
+/// Build the target of a registered open command, e.g. `"app.exe" "%1"`
+///
+/// `%1` and `%L` are replaced by the file, which is appended if the command
+/// has neither.
+#[cfg(any(target_os = "windows", test))]
+fn command_target(command: &str, file: &Path) -> Result<AppTarget, String> {
+    let file = file.to_string_lossy();
+    let mut args = crate::apps::split_arguments(command);
+    if args.is_empty() {
+        return Err("Open command is empty".to_string());
+    }
+
+    let mut substituted = false;
+    for arg in &mut args {
+        if arg.contains("%1") || arg.contains("%L") || arg.contains("%l") {
+            *arg = arg
+                .replace("%1", &file)
+                .replace("%L", &file)
+                .replace("%l", &file);
+            substituted = true;
+        }
+    }
+    if !substituted {
+        args.push(file.into_owned());
+    }
+
+    let path = args.remove(0).into();
+    Ok(AppTarget::Executable {
+        path,
+        args,
+        working_dir: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_associations() {
+        let class = "\r\nHKEY_CLASSES_ROOT\\.txt\r\n    (Default)    REG_SZ    txtfile\r\n";
+        assert_eq!(reg_default(class), Some("txtfile".to_string()));
+
+        let progids = "\r\nHKEY_CLASSES_ROOT\\.txt\\OpenWithProgids\r\n    \
+                       AppX4ztfk9yxwpq    REG_NONE    \r\n    txtfile    REG_SZ    \r\n";
+        assert_eq!(reg_values(progids), vec!["AppX4ztfk9yxwpq", "txtfile"]);
+        assert_eq!(reg_default(progids), None);
+
+        let list = "\r\nHKEY_CLASSES_ROOT\\.txt\\OpenWithList\r\n\
+                    HKEY_CLASSES_ROOT\\.txt\\OpenWithList\\notepad.exe\r\n\
+                    HKEY_CLASSES_ROOT\\.txt\\OpenWithList\\WordPad.exe\r\n";
+        assert_eq!(
+            reg_subkeys(list, "HKCR\\.txt\\OpenWithList"),
+            vec!["notepad.exe", "WordPad.exe"]
+        );
+
+        let file = Path::new("C:\\Notes\\todo list.txt");
+        assert_eq!(
+            command_target(
+                "\"C:\\Program Files\\Editor\\editor.exe\" --edit \"%1\"",
+                file
+            )
+            .unwrap(),
+            AppTarget::Executable {
+                path: "C:\\Program Files\\Editor\\editor.exe".into(),
+                args: vec!["--edit".to_string(), "C:\\Notes\\todo list.txt".to_string()],
+                working_dir: None,
+            }
+        );
+        assert_eq!(
+            command_target("notepad.exe", file).unwrap(),
+            AppTarget::Executable {
+                path: "notepad.exe".into(),
+                args: vec!["C:\\Notes\\todo list.txt".to_string()],
+                working_dir: None,
+            }
+        );
+    }
+}
@@ -0,0 +1,182 @@
+/// What the launcher does after a result was executed
+///
+/// `Plugin::execute` returns an `ExecuteOutcome` telling the host whether to
+/// close the window, run another query, or show a message. The default
+/// outcome closes the window, which is what hosts did before outcomes
+/// existed; remote plugins answering `null` get the default too.
+use serde::{Deserialize, Serialize};
+
+/// Follow-up behavior after executing a result
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct ExecuteOutcome {
+    /// Hide the launcher window
+    #[serde(default = "default_close_window")]
+    pub close_window: bool,
+    /// Query to run next in place of the current one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requery: Option<String>,
+    /// Message shown briefly in the launcher
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<Toast>,
+    /// Why the action failed, shown to the user
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Route key presses to the plugin while the view opened by `requery`
+    /// is shown, see `crate::keys`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub capture_keys: bool,
+}
+
+fn default_close_window() -> bool {
+    true
+}
+
+impl Default for ExecuteOutcome {
+    fn default() -> Self {
+        Self {
+            close_window: true,
+            requery: None,
+            message: None,
+            error: None,
+            capture_keys: false,
+        }
+    }
+}
+
+impl ExecuteOutcome {
+    /// Close the window, the default
+    pub fn close() -> Self {
+        Self::default()
+    }
+
+    /// Keep the window open, e.g. after copying something
+    pub fn keep_open() -> Self {
+        Self {
+            close_window: false,
+            ..Self::default()
+        }
+    }
+
+    /// Keep the window open and run another query
+    ///
+    /// Used by drill-down flows, e.g. "pick a repository" followed by
+    /// "pick a branch".
+    pub fn requery(query: impl Into<String>) -> Self {
+        Self {
+            requery: Some(query.into()),
+            ..Self::keep_open()
+        }
+    }
+
+    /// Keep the window open and show why the action failed
+    pub fn failed(error: impl Into<String>) -> Self {
+        Self {
+            error: Some(error.into()),
+            ..Self::keep_open()
+        }
+    }
+
+    /// Own the view opened by `requery`, receiving its key presses
+    ///
+    /// The plugin's `on_key_event` is called for keys pressed in the view,
+    /// e.g. Right to expand a process in a process monitor.
+    pub fn capture_keys(mut self) -> Self {
+        self.capture_keys = true;
+        self
+    }
+
+    /// Show a message
+    pub fn with_message(mut self, message: Toast) -> Self {
+        self.message = Some(message);
+        self
+    }
+
+    /// Check if the action failed
+    pub fn is_error(&self) -> bool {
+        self.error.is_some()
+    }
+}
+
+/// Short message shown in the launcher after an action
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct Toast {
+    /// Message text
+    pub text: String,
+    /// How the message is styled
+    #[serde(default)]
+    pub style: ToastStyle,
+}
+
+/// Style of a toast
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub enum ToastStyle {
+    /// Neutral information
+    #[default]
+    Info,
+    /// The action succeeded, e.g. "Copied to clipboard"
+    Success,
+    /// The action succeeded with caveats
+    Warning,
+}
+
+impl Toast {
+    /// Create an informational toast
+    pub fn info(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            style: ToastStyle::Info,
+        }
+    }
+
+    /// Create a success toast
+    pub fn success(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            style: ToastStyle::Success,
+        }
+    }
+
+    /// Create a warning toast
+    pub fn warning(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            style: ToastStyle::Warning,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outcome_defaults() {
+        assert!(ExecuteOutcome::default().close_window);
+        assert_eq!(serde_json::from_str::<ExecuteOutcome>("{}").unwrap(), ExecuteOutcome::close());
+
+        let outcome = ExecuteOutcome::requery("git branch ").with_message(Toast::success("Cloned"));
+        assert!(!outcome.close_window && !outcome.is_error());
+        let json = serde_json::to_value(&outcome).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "closeWindow": false,
+                "requery": "git branch ",
+                "message": { "text": "Cloned", "style": "success" },
+            })
+        );
+        assert_eq!(serde_json::from_value::<ExecuteOutcome>(json).unwrap(), outcome);
+
+        assert!(ExecuteOutcome::failed("Network unreachable").is_error());
+        assert_eq!(
+            serde_json::to_value(ExecuteOutcome::requery("ps ").capture_keys()).unwrap()["captureKeys"],
+            serde_json::json!(true)
+        );
+    }
+}
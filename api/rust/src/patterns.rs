@@ -0,0 +1,206 @@
+/// Sandboxed regular expressions
+///
+/// Plugins that let users type patterns (log search, snippet triggers,
+/// file filters) shouldn't compile them with a backtracking engine, where
+/// a pattern like `(a+)+$` can take exponential time on a short input.
+/// `VoltPluginAPI::compile_regex` compiles patterns with the `regex`
+/// crate, which matches in time linear in the input whatever the pattern,
+/// and within `RegexLimits`, so a pattern can't exhaust memory while it
+/// compiles either.
+///
+/// Compiled patterns are kept in a cache shared by all plugins, so a
+/// pattern compiled on every keystroke, or by several plugins, is compiled
+/// once. Past its capacity, the least recently used patterns are evicted.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+pub use regex::Regex;
+
+/// Default length limit of a pattern, in bytes
+pub const DEFAULT_MAX_PATTERN_LEN: usize = 1024;
+
+/// Default size limit of a compiled pattern, in bytes
+pub const DEFAULT_SIZE_LIMIT: usize = 1024 * 1024;
+
+/// Default depth limit of nested groups and repetitions
+pub const DEFAULT_NEST_LIMIT: u32 = 32;
+
+/// Default number of compiled patterns kept
+pub const DEFAULT_CACHE_CAPACITY: usize = 256;
+
+/// Limits on the patterns plugins compile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegexLimits {
+    /// Longest pattern accepted, in bytes
+    pub max_pattern_len: usize,
+    /// Largest compiled pattern, in bytes
+    pub size_limit: usize,
+    /// Deepest nesting of groups and repetitions
+    pub nest_limit: u32,
+}
+
+impl Default for RegexLimits {
+    fn default() -> Self {
+        Self {
+            max_pattern_len: DEFAULT_MAX_PATTERN_LEN,
+            size_limit: DEFAULT_SIZE_LIMIT,
+            nest_limit: DEFAULT_NEST_LIMIT,
+        }
+    }
+}
+
+/// Cache of compiled patterns
+///
+/// Cheap to clone; all clones share the same patterns.
+#[derive(Clone)]
+pub struct RegexCache {
+    limits: RegexLimits,
+    capacity: usize,
+    inner: Arc<Mutex<CacheInner>>,
+}
+
+#[derive(Default)]
+struct CacheInner {
+    /// Compiled patterns, with the tick they were last used at
+    entries: HashMap<String, (Regex, u64)>,
+    tick: u64,
+}
+
+impl Default for RegexCache {
+    fn default() -> Self {
+        Self::new(RegexLimits::default())
+    }
+}
+
+impl RegexCache {
+    /// Create an empty cache compiling patterns within `limits`
+    pub fn new(limits: RegexLimits) -> Self {
+        Self {
+            limits,
+            capacity: DEFAULT_CACHE_CAPACITY,
+            inner: Arc::new(Mutex::new(CacheInner::default())),
+        }
+    }
+
+    /// Keep at most `capacity` compiled patterns
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Get the limits patterns are compiled within
+    pub fn limits(&self) -> RegexLimits {
+        self.limits
+    }
+
+    /// Compile a pattern, or get it from the cache
+    ///
+    /// # Arguments
+    /// * `pattern` - Pattern in the syntax of the `regex` crate
+    ///
+    /// # Returns
+    /// The compiled pattern, or an error if it is invalid or exceeds the
+    /// limits
+    pub fn compile(&self, pattern: &str) -> Result<Regex, String> {
+        let mut inner = self.inner.lock().unwrap_or_else(|p| p.into_inner());
+        inner.tick += 1;
+        let tick = inner.tick;
+        if let Some((regex, last_used)) = inner.entries.get_mut(pattern) {
+            *last_used = tick;
+            return Ok(regex.clone());
+        }
+        drop(inner);
+
+        if pattern.len() > self.limits.max_pattern_len {
+            return Err(format!(
+                "Pattern too long ({} bytes, max {})",
+                pattern.len(),
+                self.limits.max_pattern_len
+            ));
+        }
+        let regex = regex::RegexBuilder::new(pattern)
+            .size_limit(self.limits.size_limit)
+            .dfa_size_limit(self.limits.size_limit)
+            .nest_limit(self.limits.nest_limit)
+            .build()
+            .map_err(|e| format!("Invalid pattern: {}", e))?;
+
+        let mut inner = self.inner.lock().unwrap_or_else(|p| p.into_inner());
+        if inner.entries.len() >= self.capacity {
+            let oldest = inner
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(pattern, _)| pattern.clone());
+            if let Some(oldest) = oldest {
+                inner.entries.remove(&oldest);
+            }
+        }
+        inner
+            .entries
+            .insert(pattern.to_string(), (regex.clone(), tick));
+        Ok(regex)
+    }
+
+    /// Get the number of compiled patterns kept
+    pub fn len(&self) -> usize {
+        self.inner
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .entries
+            .len()
+    }
+
+    /// Check if no pattern is kept
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop every compiled pattern
+    pub fn clear(&self) {
+        self.inner
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .entries
+            .clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_patterns_compile_within_limits() {
+        let cache = RegexCache::new(RegexLimits {
+            size_limit: 64 * 1024,
+            ..Default::default()
+        })
+        .with_capacity(2);
+
+        // Matches in linear time where a backtracking engine wouldn't finish
+        let evil = cache.compile("(a+)+$").unwrap();
+        let input = format!("{}!", "a".repeat(50_000));
+        let started = Instant::now();
+        assert!(!evil.is_match(&input));
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        assert!(cache.compile(r"\w{1000}{1000}").is_err());
+        assert!(cache.compile(&"a".repeat(2000)).is_err());
+        assert!(
+            cache
+                .compile(&format!("{}a{}", "(".repeat(40), ")".repeat(40)))
+                .is_err()
+        );
+        assert!(cache.compile("(unclosed").is_err());
+        assert_eq!(cache.len(), 1);
+
+        cache.compile("b+").unwrap();
+        cache.compile("(a+)+$").unwrap();
+        cache.compile("c+").unwrap();
+        // "b+" was the least recently used
+        assert_eq!(cache.len(), 2);
+        assert!(cache.inner.lock().unwrap().entries.contains_key("(a+)+$"));
+    }
+}
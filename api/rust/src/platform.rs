@@ -0,0 +1,188 @@
+/// Host platform detection
+///
+/// Used to decide whether a plugin can run on the current operating system
+/// before it is loaded, so plugins don't crash on missing platform APIs.
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// Operating systems a plugin can declare support for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Platform {
+    /// Microsoft Windows
+    Windows,
+    /// Apple macOS
+    #[serde(alias = "darwin", alias = "mac")]
+    Macos,
+    /// Linux distributions
+    Linux,
+}
+
+impl Platform {
+    /// Get the platform Volt was compiled for, if it is a supported one
+    pub fn current() -> Option<Self> {
+        if cfg!(target_os = "windows") {
+            Some(Platform::Windows)
+        } else if cfg!(target_os = "macos") {
+            Some(Platform::Macos)
+        } else if cfg!(target_os = "linux") {
+            Some(Platform::Linux)
+        } else {
+            None
+        }
+    }
+
+    /// Get the identifier used in manifests
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Platform::Windows => "windows",
+            Platform::Macos => "macos",
+            Platform::Linux => "linux",
+        }
+    }
+}
+
+/// Platform and OS version of the running host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlatformInfo {
+    /// Current platform, None on operating systems Volt doesn't target
+    pub platform: Option<Platform>,
+    /// OS version (kernel release on Linux), None if it couldn't be detected
+    pub os_version: Option<String>,
+}
+
+impl PlatformInfo {
+    /// Detect the platform and OS version of the running host
+    pub fn current() -> Self {
+        Self {
+            platform: Platform::current(),
+            os_version: detect_os_version(),
+        }
+    }
+
+    /// Check if a plugin declaring the given requirements can run here
+    ///
+    /// An empty `platforms` list means the plugin supports every platform.
+    /// Minimum versions are only enforced when the OS version is known.
+    ///
+    /// # Returns
+    /// Ok(()) if supported, Err with a user-facing reason otherwise
+    pub fn check_support(&self, platforms: &[Platform], min_os_version: Option<&str>) -> Result<(), String> {
+        let Some(platform) = self.platform else {
+            return if platforms.is_empty() {
+                Ok(())
+            } else {
+                Err("Unsupported on this platform".to_string())
+            };
+        };
+
+        if !platforms.is_empty() && !platforms.contains(&platform) {
+            let supported: Vec<&str> = platforms.iter().map(Platform::as_str).collect();
+            return Err(format!(
+                "Unsupported on {} (supports: {})",
+                platform.as_str(),
+                supported.join(", ")
+            ));
+        }
+
+        if let (Some(required), Some(actual)) = (min_os_version, self.os_version.as_deref())
+            && compare_versions(actual, required) == Ordering::Less
+        {
+            return Err(format!(
+                "Requires {} {} or newer (found {})",
+                platform.as_str(),
+                required,
+                actual
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Compare two dotted version strings numerically
+///
+/// Non-numeric suffixes ("-rc1", "-generic") are ignored and missing
+/// components count as zero, so "10.15" == "10.15.0" and "6.1-arch1" > "5.19".
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let a = parse_version(a);
+    let b = parse_version(b);
+
+    for i in 0..a.len().max(b.len()) {
+        let ordering = a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+fn parse_version(version: &str) -> Vec<u64> {
+    let mut components = Vec::new();
+    for part in version.trim().trim_start_matches('v').split('.') {
+        let digits: String = part.chars().take_while(|c| c.is_ascii_digit()).collect();
+        match digits.parse() {
+            Ok(number) => components.push(number),
+            Err(_) => break,
+        }
+        if digits.len() != part.len() {
+            break;
+        }
+    }
+    components
+}
+
+#[cfg(target_os = "linux")]
+fn detect_os_version() -> Option<String> {
+    std::fs::read_to_string("/proc/sys/kernel/osrelease")
+        .ok()
+        .map(|release| release.trim().to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn detect_os_version() -> Option<String> {
+    let plist = std::fs::read_to_string("/System/Library/CoreServices/SystemVersion.plist").ok()?;
+    let after_key = plist.split("<key>ProductVersion</key>").nth(1)?;
+    let start = after_key.find("<string>")? + "<string>".len();
+    let end = after_key[start..].find("</string>")? + start;
+    Some(after_key[start..end].trim().to_string())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn detect_os_version() -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("10.15", "10.15.0"), Ordering::Equal);
+        assert_eq!(compare_versions("6.1.0-arch1", "5.19"), Ordering::Greater);
+        assert_eq!(compare_versions("10.9", "10.10"), Ordering::Less);
+        assert_eq!(compare_versions("v1.2.3", "1.2.3"), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_check_support() {
+        let linux = PlatformInfo {
+            platform: Some(Platform::Linux),
+            os_version: Some("6.1.0-generic".to_string()),
+        };
+
+        assert!(linux.check_support(&[], None).is_ok());
+        assert!(linux.check_support(&[Platform::Windows, Platform::Linux], Some("5.4")).is_ok());
+        assert!(linux.check_support(&[Platform::Linux], Some("6.8")).is_err());
+
+        let err = linux.check_support(&[Platform::Windows], None).unwrap_err();
+        assert_eq!(err, "Unsupported on linux (supports: windows)");
+
+        let unknown_version = PlatformInfo {
+            os_version: None,
+            ..linux
+        };
+        assert!(unknown_version.check_support(&[Platform::Linux], Some("99")).is_ok());
+    }
+}
@@ -0,0 +1,382 @@
+/// Plugin trait implemented by every backend plugin
+///
+/// Hooks beyond identification have default implementations, so plugins
+/// only override the behavior they need. Optional capabilities are exposed
+/// through the extension traits in `crate::extensions`.
+use crate::cancel::CancellationToken;
+use crate::extensions::{Annotator, Previewer, ResourceResolver, SendHandler, SettingsProvider, Suggester, UriHandler};
+use crate::feeds::DataFeed;
+use crate::keys::{KeyEvent, KeyResponse};
+use crate::no_results::{NoResultsNote, NoResultsNotes, NoResultsReason};
+use crate::outcome::ExecuteOutcome;
+use crate::protocol::NegotiatedProtocol;
+use crate::result::PluginResult;
+use crate::selection::Selection;
+use crate::session::{SessionScope, SessionStore};
+use crate::warmup::WarmupTask;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::path::Path;
+
+/// Context passed to plugins for each query
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct QueryContext {
+    /// Raw query typed by the user
+    pub query: String,
+    /// Number of results the host will actually display, if limited
+    ///
+    /// Plugins should stop producing results once they have this many;
+    /// anything beyond it is dropped before merging.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_results: Option<usize>,
+    /// Query as typed, when `query` is a spelling correction of it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub corrected_from: Option<String>,
+    /// State of the launcher session the query belongs to
+    #[serde(skip)]
+    session: Option<SessionStore>,
+    /// Text selected in the application the launcher was opened from
+    #[serde(skip)]
+    selection: Option<Selection>,
+    /// Plugin the context was handed to
+    #[serde(skip)]
+    plugin_id: Option<String>,
+    /// Cancelled by the host once the query is superseded
+    #[serde(skip)]
+    cancellation: Option<CancellationToken>,
+    /// Collects the plugins' notes on why they returned no results
+    #[serde(skip)]
+    no_results_notes: Option<NoResultsNotes>,
+}
+
+impl QueryContext {
+    /// Create a context for the given query
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            max_results: None,
+            corrected_from: None,
+            session: None,
+            selection: None,
+            plugin_id: None,
+            cancellation: None,
+            no_results_notes: None,
+        }
+    }
+
+    /// Limit the number of results the host wants
+    pub fn with_max_results(mut self, max_results: usize) -> Self {
+        self.max_results = Some(max_results);
+        self
+    }
+
+    /// Attach the state of the current launcher session
+    pub fn with_session(mut self, store: SessionStore) -> Self {
+        self.session = Some(store);
+        self
+    }
+
+    /// Attach the selection of the current launcher activation
+    pub fn with_selection(mut self, selection: Selection) -> Self {
+        self.selection = Some(selection);
+        self
+    }
+
+    /// Attach the token the host cancels when the query is superseded
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Get the query's cancellation token, if the host attached one
+    pub fn cancellation(&self) -> Option<&CancellationToken> {
+        self.cancellation.as_ref()
+    }
+
+    /// Attach the collector of the plugins' "why not" notes
+    pub fn with_no_results_notes(mut self, notes: NoResultsNotes) -> Self {
+        self.no_results_notes = Some(notes);
+        self
+    }
+
+    /// Note why the plugin returns no results for this query
+    ///
+    /// Hidden from users; plugin authors see the note in the log viewer,
+    /// and in dev mode next to the results (see `crate::no_results`).
+    /// Ignored unless the host collects notes and the context was scoped
+    /// to a plugin.
+    ///
+    /// # Arguments
+    /// * `reason` - Why there are no results
+    /// * `detail` - Explanation for the plugin's author
+    pub fn why_not(&self, reason: NoResultsReason, detail: impl Into<String>) {
+        if let (Some(notes), Some(plugin_id)) = (&self.no_results_notes, &self.plugin_id) {
+            notes.add(NoResultsNote {
+                plugin_id: plugin_id.clone(),
+                reason,
+                detail: detail.into(),
+            });
+        }
+    }
+
+    /// Check if the query was superseded and its results are no longer wanted
+    ///
+    /// Plugins doing expensive work should check it between steps and stop
+    /// early.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.as_ref().is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Get the text selected in the application the launcher was opened from
+    ///
+    /// Requires the `selection` permission. The text is read from the
+    /// application once per launcher activation and trimmed. None if
+    /// nothing was selected, the host attached no selection, or the context
+    /// wasn't scoped to a plugin.
+    pub fn selection(&self) -> Option<String> {
+        self.selection.as_ref()?.read(self.plugin_id.as_ref()?)
+    }
+
+    /// Get a copy of the context to hand to a plugin
+    ///
+    /// The registry does this before calling plugin hooks, so each plugin's
+    /// `session_store` only sees its own state.
+    pub fn scoped_to(&self, plugin_id: &str) -> Self {
+        Self {
+            plugin_id: Some(plugin_id.to_string()),
+            ..self.clone()
+        }
+    }
+
+    /// Get the plugin's state for the current launcher session
+    ///
+    /// Use it to carry state between the keystrokes of a drill-down flow.
+    /// None if the host attached no session or the context wasn't scoped to
+    /// a plugin.
+    pub fn session_store(&self) -> Option<SessionScope> {
+        Some(self.session.as_ref()?.scope(self.plugin_id.as_ref()?))
+    }
+}
+
+/// Core trait for Volt backend plugins
+#[async_trait]
+pub trait Plugin: Send + Sync {
+    /// Access the concrete plugin type for downcasting
+    fn as_any(&self) -> &dyn Any;
+
+    /// Unique identifier of the plugin
+    fn id(&self) -> &str;
+
+    /// Human-readable name of the plugin
+    fn name(&self) -> &str;
+
+    /// Short description of what the plugin does
+    fn description(&self) -> &str;
+
+    /// Version of the plugin, typically `Some(env!("CARGO_PKG_VERSION"))`
+    fn version(&self) -> Option<&str> {
+        None
+    }
+
+    /// Author of the plugin
+    fn author(&self) -> Option<&str> {
+        None
+    }
+
+    /// Project homepage
+    fn homepage(&self) -> Option<&str> {
+        None
+    }
+
+    /// SPDX license identifier
+    fn license(&self) -> Option<&str> {
+        None
+    }
+
+    /// Check if the plugin is currently enabled
+    fn is_enabled(&self) -> bool {
+        true
+    }
+
+    /// Prepare the plugin after registration
+    ///
+    /// Called once by `PluginRegistry::initialize_all`. Expensive setup
+    /// belongs here rather than in the constructor; its cost shows up in
+    /// the startup report.
+    fn initialize(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Called when the user enables the plugin, directly or through a bundle
+    fn on_enabled(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Called when the user disables the plugin, directly or through a bundle
+    ///
+    /// Release shared resources (hotkeys, watchers) here; another plugin may
+    /// be enabled right after.
+    fn on_disabled(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Maximum number of results this plugin wants to contribute per query
+    ///
+    /// The aggregator keeps at most this many of the plugin's best results,
+    /// so plugins producing large result sets don't crowd out the others.
+    fn preferred_batch_size(&self) -> Option<usize> {
+        None
+    }
+
+    /// Protocol negotiated with the bridge serving this plugin
+    ///
+    /// None for plugins running in the launcher process.
+    fn protocol(&self) -> Option<NegotiatedProtocol> {
+        None
+    }
+
+    /// Check if this plugin should handle the query
+    fn can_handle(&self, _context: &QueryContext) -> bool {
+        false
+    }
+
+    /// Generate results for the query
+    async fn match_query(&self, _context: &QueryContext) -> Result<Vec<PluginResult>, String> {
+        Ok(Vec::new())
+    }
+
+    /// Generate results offered when no plugin has any for the query
+    ///
+    /// Called for every enabled plugin, whether or not it handles the query,
+    /// but only when the query produced nothing at all. Typical fallbacks
+    /// act on the raw query ("Search the web for ...", "Create note ...").
+    fn fallback_results(&self, _context: &QueryContext) -> Vec<PluginResult> {
+        Vec::new()
+    }
+
+    /// Execute the action for one of this plugin's results
+    ///
+    /// The outcome tells the host what to do next; return
+    /// `ExecuteOutcome::default()` to close the window. Errors are shown to
+    /// the user like `ExecuteOutcome::failed`.
+    async fn execute(&self, _result: &PluginResult) -> Result<ExecuteOutcome, String> {
+        Ok(ExecuteOutcome::default())
+    }
+
+    /// Handle a key pressed while the plugin owns the drill-down view
+    ///
+    /// Only called after the plugin opted in with
+    /// `ExecuteOutcome::capture_keys`; see `crate::keys` for which keys are
+    /// delivered and when focus returns to the launcher. Errors release focus.
+    ///
+    /// # Arguments
+    /// * `event` - The key press
+    /// * `context` - The query shown in the view
+    async fn on_key_event(&self, _event: &KeyEvent, _context: &QueryContext) -> Result<KeyResponse, String> {
+        Ok(KeyResponse::Ignored)
+    }
+
+    /// Called when one of the plugin's configuration files changes
+    ///
+    /// Invoked after the settings UI saves the configuration or after the
+    /// file is edited on disk, so plugins can apply settings without a
+    /// launcher restart.
+    ///
+    /// # Arguments
+    /// * `config_name` - Name of the configuration file (without .json extension)
+    /// * `config` - The new configuration
+    fn on_config_changed(&self, _config_name: &str, _config: &serde_json::Value) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Remote data feeds the host should keep up to date for the plugin
+    ///
+    /// Refreshed by `feeds::FeedScheduler`; read the current data with
+    /// `feeds::feed_path`.
+    fn data_feeds(&self) -> Vec<DataFeed> {
+        Vec::new()
+    }
+
+    /// Called when a new version of one of the plugin's data feeds was downloaded
+    ///
+    /// # Arguments
+    /// * `feed_id` - ID of the refreshed feed
+    /// * `path` - File holding the new data
+    fn on_feed_updated(&self, _feed_id: &str, _path: &Path) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Run one of the warmup tasks declared in the plugin's manifest
+    ///
+    /// Called by `warmup::WarmupScheduler` on a background thread; fill
+    /// the caches the first queries read.
+    ///
+    /// # Arguments
+    /// * `task` - The task, with its parameters
+    fn warm_up(&self, _task: &WarmupTask) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Called when the user clicks an action of one of the plugin's notifications
+    ///
+    /// # Arguments
+    /// * `notification_id` - ID of the notification
+    /// * `action_id` - ID of the clicked action
+    fn on_notification_action(&self, _notification_id: &str, _action_id: &str) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Called when the user turns privacy mode on or off
+    ///
+    /// While privacy mode is on, plugins must stop recording history,
+    /// usage statistics and clipboard contents.
+    fn on_privacy_mode_changed(&self, _enabled: bool) {}
+
+    /// Forget everything the plugin recorded at or after a time
+    ///
+    /// # Arguments
+    /// * `since` - Time in milliseconds since the Unix epoch
+    fn purge_recorded_since(&self, _since: u64) -> Result<(), String> {
+        Ok(())
+    }
+
+    // ========== Extensions ==========
+
+    /// Query completion capability, if supported
+    fn as_suggester(&self) -> Option<&dyn Suggester> {
+        None
+    }
+
+    /// Result preview capability, if supported
+    fn as_previewer(&self) -> Option<&dyn Previewer> {
+        None
+    }
+
+    /// URI handling capability, if supported
+    fn as_uri_handler(&self) -> Option<&dyn UriHandler> {
+        None
+    }
+
+    /// "Send to" target capability, if supported
+    fn as_send_handler(&self) -> Option<&dyn SendHandler> {
+        None
+    }
+
+    /// Result annotation capability, if supported
+    fn as_annotator(&self) -> Option<&dyn Annotator> {
+        None
+    }
+
+    /// Settings pages capability, if supported
+    fn as_settings_provider(&self) -> Option<&dyn SettingsProvider> {
+        None
+    }
+
+    /// Virtual resource capability, if supported
+    fn as_resource_resolver(&self) -> Option<&dyn ResourceResolver> {
+        None
+    }
+}
@@ -0,0 +1,357 @@
+/// Power state and battery policies
+///
+/// On battery, work the user didn't ask for should wait: background
+/// refreshes of data feeds and speculative deep dispatch passes drain the
+/// battery for results nobody may look at. The host reads the power state
+/// with a `PowerMonitor` (`SystemPowerMonitor` asks the OS) and reports it
+/// with `VoltPluginAPI::set_power_state`, which notifies subscribers when it
+/// changes. The `PowerPolicy` decides what throttles in each `PowerMode`:
+///
+/// - `Normal`, on AC power: nothing
+/// - `Saving`, on battery: feed refreshes are spaced out and deep passes
+///   are skipped
+/// - `Critical`, on low battery or with the OS low power mode on: feed
+///   refreshes stop until power is back
+///
+/// ```ignore
+/// let controller = controller.clone();
+/// api.subscribe_power_changes(Arc::new(move |_state, mode| {
+///     controller.set_deep_passes(policy.allows_deep_passes(mode));
+/// }))?;
+/// api.refresh_power_state(&SystemPowerMonitor)?;
+/// ```
+use crate::platform::Platform;
+use serde::Serialize;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+/// Default charge at or below which the battery counts as low, in percent
+pub const DEFAULT_LOW_BATTERY_PERCENT: u8 = 20;
+
+/// Default factor background refresh intervals are stretched by on battery
+pub const DEFAULT_BATTERY_REFRESH_FACTOR: u32 = 4;
+
+/// Power supply of the machine
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PowerState {
+    /// Running on battery rather than AC power
+    pub on_battery: bool,
+    /// Remaining charge in percent, None without a battery
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battery_percent: Option<u8>,
+    /// The OS low power mode is on
+    pub low_power_mode: bool,
+}
+
+/// How much background work is throttled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PowerMode {
+    /// No throttling
+    Normal,
+    /// Background work is reduced
+    Saving,
+    /// Background work stops
+    Critical,
+}
+
+/// What throttles on battery
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PowerPolicy {
+    /// Charge at or below which the battery counts as low, in percent
+    pub low_battery_percent: u8,
+    /// Factor background refresh intervals are stretched by while saving
+    pub battery_refresh_factor: u32,
+    /// Keep running deep dispatch passes while saving
+    pub deep_passes_on_battery: bool,
+    /// Keep refreshing in the background, spaced out, on low battery
+    pub refresh_on_low_battery: bool,
+}
+
+impl Default for PowerPolicy {
+    fn default() -> Self {
+        Self {
+            low_battery_percent: DEFAULT_LOW_BATTERY_PERCENT,
+            battery_refresh_factor: DEFAULT_BATTERY_REFRESH_FACTOR,
+            deep_passes_on_battery: false,
+            refresh_on_low_battery: false,
+        }
+    }
+}
+
+impl PowerPolicy {
+    /// Never throttle, whatever the power state
+    pub fn unrestricted() -> Self {
+        Self {
+            low_battery_percent: 0,
+            battery_refresh_factor: 1,
+            deep_passes_on_battery: true,
+            refresh_on_low_battery: true,
+        }
+    }
+
+    /// Get the mode a power state puts the host in
+    pub fn mode(&self, state: &PowerState) -> PowerMode {
+        let low = state
+            .battery_percent
+            .is_some_and(|percent| percent <= self.low_battery_percent);
+        if state.low_power_mode || (state.on_battery && low) {
+            PowerMode::Critical
+        } else if state.on_battery {
+            PowerMode::Saving
+        } else {
+            PowerMode::Normal
+        }
+    }
+
+    /// Get the interval of a background refresh in a mode
+    ///
+    /// # Arguments
+    /// * `interval` - Interval on AC power
+    /// * `mode` - Current power mode
+    ///
+    /// # Returns
+    /// The stretched interval, or None if background refreshes are paused
+    pub fn refresh_interval(&self, interval: Duration, mode: PowerMode) -> Option<Duration> {
+        match mode {
+            PowerMode::Normal => Some(interval),
+            PowerMode::Critical if !self.refresh_on_low_battery => None,
+            PowerMode::Saving | PowerMode::Critical => {
+                Some(interval.saturating_mul(self.battery_refresh_factor.max(1)))
+            }
+        }
+    }
+
+    /// Check if deep dispatch passes run in a mode
+    pub fn allows_deep_passes(&self, mode: PowerMode) -> bool {
+        mode == PowerMode::Normal || self.deep_passes_on_battery
+    }
+}
+
+/// Reads the power state of the machine
+pub trait PowerMonitor: Send + Sync {
+    /// Read the current power state
+    fn read(&self) -> Result<PowerState, String>;
+}
+
+/// Reads the power state from the OS
+///
+/// Uses `/sys/class/power_supply` on Linux, `pmset` on macOS and the
+/// `Win32_Battery` class on Windows. Machines without a battery are on AC
+/// power.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemPowerMonitor;
+
+impl PowerMonitor for SystemPowerMonitor {
+    fn read(&self) -> Result<PowerState, String> {
+        match Platform::current() {
+            Some(Platform::Linux) => read_power_supplies(Path::new("/sys/class/power_supply")),
+            Some(Platform::Macos) => {
+                let batt = run("pmset", &["-g", "batt"])?;
+                let settings = run("pmset", &["-g"]).unwrap_or_default();
+                Ok(parse_pmset(&batt, &settings))
+            }
+            Some(Platform::Windows) => {
+                let output = run(
+                    "powershell",
+                    &[
+                        "-NoProfile",
+                        "-Command",
+                        "Get-CimInstance Win32_Battery | ForEach-Object { \"$($_.BatteryStatus) $($_.EstimatedChargeRemaining)\" }",
+                    ],
+                )?;
+                Ok(parse_win32_battery(&output))
+            }
+            None => Ok(PowerState::default()),
+        }
+    }
+}
+
+/// Run a command and get its standard output
+fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(format!("{} exited with {}", program, output.status));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Read the power state from the power supplies of a Linux sysfs directory
+///
+/// The machine is on battery when a battery is discharging, or when no
+/// mains supply is online.
+pub(crate) fn read_power_supplies(dir: &Path) -> Result<PowerState, String> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(PowerState::default()),
+        Err(e) => return Err(format!("Failed to read power supplies: {}", e)),
+    };
+    let read = |supply: &Path, name: &str| {
+        std::fs::read_to_string(supply.join(name))
+            .map(|value| value.trim().to_string())
+            .unwrap_or_default()
+    };
+
+    let mut mains_online = None;
+    let mut discharging = false;
+    let mut battery_percent = None;
+    for entry in entries.flatten() {
+        let supply = entry.path();
+        match read(&supply, "type").as_str() {
+            "Mains" => {
+                mains_online =
+                    Some(mains_online.unwrap_or(false) || read(&supply, "online") == "1");
+            }
+            "Battery" => {
+                discharging |= read(&supply, "status") == "Discharging";
+                battery_percent =
+                    battery_percent.or_else(|| read(&supply, "capacity").parse().ok());
+            }
+            _ => {}
+        }
+    }
+
+    Ok(PowerState {
+        on_battery: battery_percent.is_some() && (discharging || mains_online == Some(false)),
+        battery_percent,
+        low_power_mode: false,
+    })
+}
+
+/// Parse the output of `pmset -g batt` and `pmset -g`
+pub(crate) fn parse_pmset(batt: &str, settings: &str) -> PowerState {
+    let battery_percent = batt
+        .lines()
+        .filter(|line| line.contains("InternalBattery"))
+        .find_map(|line| {
+            let (before, _) = line.split_once('%')?;
+            before
+                .rsplit(|c: char| !c.is_ascii_digit())
+                .next()?
+                .parse()
+                .ok()
+        });
+    let low_power_mode = settings.lines().any(|line| {
+        let mut words = line.split_whitespace();
+        words.next() == Some("lowpowermode") && words.next() == Some("1")
+    });
+
+    PowerState {
+        on_battery: batt.contains("'Battery Power'"),
+        battery_percent,
+        low_power_mode,
+    }
+}
+
+/// Parse the `BatteryStatus EstimatedChargeRemaining` lines of `Win32_Battery`
+///
+/// Status 2 means on AC power; every other status means on battery.
+pub(crate) fn parse_win32_battery(output: &str) -> PowerState {
+    let Some(line) = output.lines().map(str::trim).find(|line| !line.is_empty()) else {
+        return PowerState::default();
+    };
+    let mut fields = line.split_whitespace();
+    let status: Option<u16> = fields.next().and_then(|status| status.parse().ok());
+
+    PowerState {
+        on_battery: status.is_some_and(|status| status != 2),
+        battery_percent: fields.next().and_then(|percent| percent.parse().ok()),
+        low_power_mode: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_power_policy_modes() {
+        let policy = PowerPolicy::default();
+        let hour = Duration::from_secs(3600);
+
+        let plugged = PowerState {
+            on_battery: false,
+            battery_percent: Some(10),
+            low_power_mode: false,
+        };
+        assert_eq!(policy.mode(&plugged), PowerMode::Normal);
+        assert_eq!(policy.refresh_interval(hour, PowerMode::Normal), Some(hour));
+
+        let unplugged = PowerState {
+            on_battery: true,
+            battery_percent: Some(60),
+            ..plugged
+        };
+        assert_eq!(policy.mode(&unplugged), PowerMode::Saving);
+        assert_eq!(
+            policy.refresh_interval(hour, PowerMode::Saving),
+            Some(hour * 4)
+        );
+        assert!(!policy.allows_deep_passes(PowerMode::Saving));
+
+        let low = PowerState {
+            battery_percent: Some(20),
+            ..unplugged
+        };
+        assert_eq!(policy.mode(&low), PowerMode::Critical);
+        assert_eq!(policy.refresh_interval(hour, PowerMode::Critical), None);
+        assert_eq!(
+            PowerPolicy::unrestricted().refresh_interval(hour, PowerMode::Critical),
+            Some(hour)
+        );
+    }
+
+    #[test]
+    fn test_read_platform_power_state() {
+        let dir = std::env::temp_dir().join("volt_test_power_supply");
+        let _ = std::fs::remove_dir_all(&dir);
+        for (name, files) in [
+            ("AC", &[("type", "Mains"), ("online", "0")][..]),
+            (
+                "BAT0",
+                &[
+                    ("type", "Battery"),
+                    ("status", "Discharging"),
+                    ("capacity", "42"),
+                ][..],
+            ),
+        ] {
+            std::fs::create_dir_all(dir.join(name)).unwrap();
+            for (file, value) in files {
+                std::fs::write(dir.join(name).join(file), format!("{}\n", value)).unwrap();
+            }
+        }
+        let state = read_power_supplies(&dir).unwrap();
+        assert!(state.on_battery);
+        assert_eq!(state.battery_percent, Some(42));
+        assert_eq!(
+            read_power_supplies(&dir.join("missing")).unwrap(),
+            PowerState::default()
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let state = parse_pmset(
+            "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=4653155)\t87%; discharging; 5:12 remaining present: true\n",
+            "System-wide power settings:\n lowpowermode         1\n",
+        );
+        assert_eq!(
+            state,
+            PowerState {
+                on_battery: true,
+                battery_percent: Some(87),
+                low_power_mode: true,
+            }
+        );
+
+        assert_eq!(parse_win32_battery("2 100\r\n").battery_percent, Some(100));
+        assert!(!parse_win32_battery("2 100\r\n").on_battery);
+        assert!(parse_win32_battery("1 55").on_battery);
+        assert_eq!(parse_win32_battery(""), PowerState::default());
+    }
+}
@@ -0,0 +1,452 @@
+/// Startup preflight checks
+///
+/// A read-only data directory, a full disk or a missing Node.js install
+/// otherwise surface as plugins failing one by one, each with its own
+/// obscure error. `PluginRegistry::preflight` checks the environment before
+/// plugins are discovered and initialized, and reports each problem once,
+/// with a fix the user can apply:
+/// - the directories plugins write to are writable
+/// - the disk holding them has room left
+/// - the interpreters script plugins run in (`node`, `python3`, ...) are
+///   installed
+/// - every package in the plugins directory has a readable manifest and an
+///   entry point
+/// - the trust store holding the keys plugin signatures are checked
+///   against is readable, and only writable by its owner
+///
+/// The host renders the report as setup warnings; nothing is fixed
+/// automatically.
+use crate::api::VoltPluginAPI;
+use crate::manifest::Severity;
+use serde::Serialize;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Free space below which the disk is reported as almost full, in bytes
+pub const DEFAULT_MIN_FREE_SPACE: u64 = 100 * 1024 * 1024;
+
+/// File written and removed to check that a directory is writable
+const PROBE_FILE: &str = ".volt-preflight";
+
+/// What a preflight check looks at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PreflightCheck {
+    /// A directory plugins write to
+    Writable,
+    /// Free space on the disk
+    DiskSpace,
+    /// An interpreter script plugins need
+    Interpreter,
+    /// A package in the plugins directory
+    Manifest,
+    /// The trust store of signing keys
+    TrustStore,
+}
+
+/// A problem found before startup
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreflightIssue {
+    /// Check that found the problem
+    pub check: PreflightCheck,
+    /// Error if plugins will fail, warning if they may
+    pub severity: Severity,
+    /// What is wrong
+    pub message: String,
+    /// What the user can do about it
+    pub fix: String,
+    /// File or directory the problem is about
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    /// Plugins affected
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub plugin_ids: Vec<String>,
+}
+
+impl PreflightIssue {
+    /// Create an error
+    pub fn error(
+        check: PreflightCheck,
+        message: impl Into<String>,
+        fix: impl Into<String>,
+    ) -> Self {
+        Self {
+            check,
+            severity: Severity::Error,
+            message: message.into(),
+            fix: fix.into(),
+            path: None,
+            plugin_ids: Vec::new(),
+        }
+    }
+
+    /// Create a warning
+    pub fn warning(
+        check: PreflightCheck,
+        message: impl Into<String>,
+        fix: impl Into<String>,
+    ) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(check, message, fix)
+        }
+    }
+
+    /// Set the file or directory the problem is about
+    pub fn with_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Set the plugins affected
+    pub fn with_plugins(mut self, plugin_ids: Vec<String>) -> Self {
+        self.plugin_ids = plugin_ids;
+        self
+    }
+}
+
+/// Outcome of the preflight checks
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreflightReport {
+    /// Problems found, errors first
+    pub issues: Vec<PreflightIssue>,
+}
+
+impl PreflightReport {
+    /// Check if nothing will make plugins fail; warnings are allowed
+    pub fn is_ok(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    /// Get the errors
+    pub fn errors(&self) -> impl Iterator<Item = &PreflightIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+    }
+
+    /// Get the issues found by a check
+    pub fn of(&self, check: PreflightCheck) -> impl Iterator<Item = &PreflightIssue> {
+        self.issues.iter().filter(move |issue| issue.check == check)
+    }
+}
+
+/// What the preflight checks look at
+#[derive(Debug, Clone)]
+pub struct PreflightConfig {
+    /// Directories plugins write to, created if missing
+    pub directories: Vec<PathBuf>,
+    /// Directory plugin packages are installed in
+    pub plugins_dir: Option<PathBuf>,
+    /// File holding the keys plugin signatures are checked against
+    pub trust_store: Option<PathBuf>,
+    /// Free space below which the disk is reported as almost full, in bytes
+    pub min_free_space: u64,
+    /// Directories searched for interpreters, in `PATH` format
+    pub search_path: OsString,
+}
+
+impl PreflightConfig {
+    /// Check the app data directory of an API, and search the `PATH`
+    pub fn new(api: &VoltPluginAPI) -> Self {
+        Self {
+            directories: api.get_app_data_dir().into_iter().collect(),
+            plugins_dir: None,
+            trust_store: None,
+            min_free_space: DEFAULT_MIN_FREE_SPACE,
+            search_path: std::env::var_os("PATH").unwrap_or_default(),
+        }
+    }
+
+    /// Check another directory plugins write to
+    pub fn with_directory(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.directories.push(dir.into());
+        self
+    }
+
+    /// Check the packages installed in a plugins directory
+    pub fn with_plugins_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.plugins_dir = Some(dir.into());
+        self
+    }
+
+    /// Check the trust store of signing keys
+    pub fn with_trust_store(mut self, path: impl Into<PathBuf>) -> Self {
+        self.trust_store = Some(path.into());
+        self
+    }
+
+    /// Set the free space below which the disk is reported as almost full
+    pub fn with_min_free_space(mut self, bytes: u64) -> Self {
+        self.min_free_space = bytes;
+        self
+    }
+
+    /// Search interpreters in other directories
+    ///
+    /// Apps started from the macOS Dock don't inherit the `PATH` of the
+    /// user's shell, where Node.js or Python are usually installed; hosts
+    /// pass the login shell's `PATH` here.
+    pub fn with_search_path(mut self, path: impl Into<OsString>) -> Self {
+        self.search_path = path.into();
+        self
+    }
+}
+
+/// Check that a directory exists, or can be created, and is writable
+pub fn check_writable(dir: &Path) -> Option<PreflightIssue> {
+    let probe = dir.join(PROBE_FILE);
+    let written = std::fs::create_dir_all(dir).and_then(|()| std::fs::write(&probe, b"volt"));
+    let _ = std::fs::remove_file(&probe);
+
+    written.err().map(|e| {
+        PreflightIssue::error(
+            PreflightCheck::Writable,
+            format!("Can't write to {}: {}", dir.display(), e),
+            "Give your user write access to the directory, or free up the disk if it is full",
+        )
+        .with_path(dir)
+    })
+}
+
+/// Check that the disk holding a directory has room left
+///
+/// Skipped when the free space can't be determined.
+pub fn check_disk_space(dir: &Path, min_free_space: u64) -> Option<PreflightIssue> {
+    let free = free_space(dir)?;
+    (free < min_free_space).then(|| {
+        PreflightIssue::warning(
+            PreflightCheck::DiskSpace,
+            format!(
+                "Only {} MB left on the disk holding {}",
+                free / (1024 * 1024),
+                dir.display()
+            ),
+            "Free up disk space; plugins may fail to save data or download updates",
+        )
+        .with_path(dir)
+    })
+}
+
+/// Get the free space of the disk holding a directory, in bytes
+#[cfg(unix)]
+pub fn free_space(dir: &Path) -> Option<u64> {
+    let output = std::process::Command::new("df")
+        .arg("-Pk")
+        .arg(dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    // Header, then `filesystem blocks used available capacity mount`
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let available: u64 = stdout
+        .lines()
+        .nth(1)?
+        .split_whitespace()
+        .nth(3)?
+        .parse()
+        .ok()?;
+    Some(available * 1024)
+}
+
+/// Get the free space of the disk holding a directory, in bytes
+#[cfg(windows)]
+pub fn free_space(dir: &Path) -> Option<u64> {
+    let output = std::process::Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "(Get-Item -LiteralPath $args[0]).PSDrive.Free",
+        ])
+        .arg(dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+/// Get the interpreters able to run a script, most common first
+///
+/// # Returns
+/// Empty if scripts with this extension don't need an interpreter
+pub fn interpreters(extension: &str) -> &'static [&'static str] {
+    match extension.to_lowercase().as_str() {
+        "js" | "mjs" | "cjs" | "ts" => &["node"],
+        "py" => &["python3", "python"],
+        "lua" => &["lua"],
+        "rb" => &["ruby"],
+        "ps1" => &["pwsh", "powershell"],
+        _ => &[],
+    }
+}
+
+/// Find a program in the directories of a search path
+///
+/// On Windows, the extensions of `PATHEXT` are tried too.
+pub fn find_program(name: &str, search_path: &OsString) -> Option<PathBuf> {
+    let extensions: Vec<String> = if cfg!(windows) {
+        std::env::var("PATHEXT")
+            .unwrap_or_else(|_| ".EXE;.CMD;.BAT".to_string())
+            .split(';')
+            .map(str::to_string)
+            .chain([String::new()])
+            .collect()
+    } else {
+        vec![String::new()]
+    };
+
+    std::env::split_paths(search_path).find_map(|dir| {
+        extensions
+            .iter()
+            .map(|extension| dir.join(format!("{}{}", name, extension)))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+/// Check that the trust store is readable, well-formed and not empty
+///
+/// On Unix, a trust store other users can write to is reported too: anyone
+/// could add a key and get their plugins accepted as signed.
+pub fn check_trust_store(path: &Path) -> Option<PreflightIssue> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Some(
+                PreflightIssue::error(
+                    PreflightCheck::TrustStore,
+                    "The trust store of signing keys is missing",
+                    "Reinstall Volt to restore the trust store",
+                )
+                .with_path(path),
+            );
+        }
+        Err(e) => {
+            return Some(
+                PreflightIssue::error(
+                    PreflightCheck::TrustStore,
+                    format!("Can't read the trust store: {}", e),
+                    "Give your user read access to the trust store",
+                )
+                .with_path(path),
+            );
+        }
+    };
+
+    let keys = match serde_json::from_str::<serde_json::Value>(&content) {
+        Ok(serde_json::Value::Array(keys)) => keys.len(),
+        Ok(serde_json::Value::Object(keys)) => keys.len(),
+        _ => {
+            return Some(
+                PreflightIssue::error(
+                    PreflightCheck::TrustStore,
+                    "The trust store is corrupt",
+                    "Reinstall Volt to restore the trust store",
+                )
+                .with_path(path),
+            );
+        }
+    };
+    if keys == 0 {
+        return Some(
+            PreflightIssue::warning(
+                PreflightCheck::TrustStore,
+                "The trust store holds no keys, so no plugin signature can be verified",
+                "Reinstall Volt to restore the trust store",
+            )
+            .with_path(path),
+        );
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Ok(metadata) = std::fs::metadata(path)
+            && metadata.permissions().mode() & 0o022 != 0
+        {
+            return Some(
+                PreflightIssue::warning(
+                    PreflightCheck::TrustStore,
+                    "The trust store can be modified by other users",
+                    format!("Run `chmod go-w {}`", path.display()),
+                )
+                .with_path(path),
+            );
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_environment_checks() {
+        let temp_dir = std::env::temp_dir().join("volt_test_preflight");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert_eq!(check_writable(&temp_dir.join("data")), None);
+        assert!(temp_dir.join("data").is_dir());
+        assert!(!temp_dir.join("data").join(PROBE_FILE).exists());
+        std::fs::write(temp_dir.join("file"), "").unwrap();
+        let issue = check_writable(&temp_dir.join("file").join("data")).unwrap();
+        assert_eq!(issue.severity, Severity::Error);
+
+        #[cfg(unix)]
+        {
+            assert!(check_disk_space(&temp_dir, 0).is_none());
+            assert!(check_disk_space(&temp_dir, u64::MAX).is_some());
+        }
+
+        let bin = temp_dir.join("bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        std::fs::write(bin.join("python3"), "").unwrap();
+        let search_path = std::env::join_paths([&bin]).unwrap();
+        assert_eq!(interpreters("PY"), ["python3", "python"]);
+        assert!(interpreters("exe").is_empty());
+        assert_eq!(
+            find_program("python3", &search_path),
+            Some(bin.join("python3"))
+        );
+        assert_eq!(find_program("node", &search_path), None);
+
+        let trust_store = temp_dir.join("trusted_keys.json");
+        assert_eq!(
+            check_trust_store(&trust_store).unwrap().severity,
+            Severity::Error
+        );
+        std::fs::write(&trust_store, "{ not json").unwrap();
+        assert_eq!(
+            check_trust_store(&trust_store).unwrap().message,
+            "The trust store is corrupt"
+        );
+        std::fs::write(&trust_store, "[]").unwrap();
+        assert_eq!(
+            check_trust_store(&trust_store).unwrap().severity,
+            Severity::Warning
+        );
+        std::fs::write(&trust_store, r#"["ed25519:AAAA"]"#).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&trust_store, std::fs::Permissions::from_mode(0o666)).unwrap();
+            assert!(
+                check_trust_store(&trust_store)
+                    .unwrap()
+                    .fix
+                    .contains("chmod go-w")
+            );
+            std::fs::set_permissions(&trust_store, std::fs::Permissions::from_mode(0o644)).unwrap();
+        }
+        assert_eq!(check_trust_store(&trust_store), None);
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
@@ -0,0 +1,560 @@
+/// Rich previews of URL results
+///
+/// Bookmark and web plugins call `VoltPluginAPI::request_url_preview` to get
+/// a page's title, description, favicon and OpenGraph image without shipping
+/// their own scraper. Pages and images go through the download manager into
+/// the plugin's cache with size caps, each host is fetched at most once per
+/// `DEFAULT_HOST_INTERVAL`, and previews are reused from disk until they are
+/// `DEFAULT_PREVIEW_MAX_AGE` old. With the `imaging` feature, images are
+/// re-encoded as bounded PNGs before the preview refers to them.
+use crate::download::{DownloadOpts, Downloader};
+#[cfg(feature = "imaging")]
+use crate::imaging::{self, ImageLimits};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Age after which a cached preview is fetched again
+pub const DEFAULT_PREVIEW_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Shortest delay between two fetches from the same host
+pub const DEFAULT_HOST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Largest page read for its metadata, in bytes
+pub const MAX_PAGE_BYTES: u64 = 512 * 1024;
+
+/// Largest OpenGraph image downloaded, in bytes
+pub const MAX_IMAGE_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Largest favicon downloaded, in bytes
+pub const MAX_FAVICON_BYTES: u64 = 256 * 1024;
+
+/// Image extensions kept in cache file names, so hosts can sniff the format
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "ico", "svg"];
+
+/// Preview of a web page
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UrlPreview {
+    /// The previewed URL
+    pub url: String,
+    /// Page title, from OpenGraph or `<title>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Page description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Name of the site, e.g. "GitHub"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub site_name: Option<String>,
+    /// Cached favicon of the page's host
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub favicon: Option<PathBuf>,
+    /// Cached OpenGraph image
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<PathBuf>,
+}
+
+/// Metadata found in a page's `<head>`, with links as written in the page
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageMeta {
+    /// `og:title`, or the `<title>` element
+    pub title: Option<String>,
+    /// `og:description`, or the `description` meta tag
+    pub description: Option<String>,
+    /// `og:site_name`
+    pub site_name: Option<String>,
+    /// `og:image`, or `twitter:image`
+    pub image: Option<String>,
+    /// First `<link rel="icon">`
+    pub icon: Option<String>,
+}
+
+/// Fetches and caches URL previews, shared by all plugins
+#[derive(Clone)]
+pub struct PreviewService {
+    max_age: Duration,
+    host_interval: Duration,
+    /// When each host was last fetched from
+    last_fetch: Arc<Mutex<HashMap<String, Instant>>>,
+}
+
+impl Default for PreviewService {
+    fn default() -> Self {
+        Self::new(DEFAULT_PREVIEW_MAX_AGE, DEFAULT_HOST_INTERVAL)
+    }
+}
+
+impl PreviewService {
+    /// Create a service
+    ///
+    /// # Arguments
+    /// * `max_age` - Age after which a cached preview is fetched again
+    /// * `host_interval` - Shortest delay between two fetches from a host
+    pub fn new(max_age: Duration, host_interval: Duration) -> Self {
+        Self {
+            max_age,
+            host_interval,
+            last_fetch: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Get the preview of a URL, from the cache or the network
+    ///
+    /// Plugins normally go through `VoltPluginAPI::request_url_preview`,
+    /// which checks the `network` permission and picks the plugin's cache.
+    /// Missing images don't fail the preview; they are left out.
+    ///
+    /// # Arguments
+    /// * `downloader` - Download client fetching the page and images
+    /// * `cache_dir` - Directory previews are cached in
+    /// * `url` - The http(s) URL to preview
+    pub async fn preview(
+        &self,
+        downloader: &Downloader,
+        cache_dir: &Path,
+        url: &str,
+    ) -> Result<UrlPreview, String> {
+        let page_url =
+            web_url(url).ok_or_else(|| format!("Cannot preview '{}': not an http(s) URL", url))?;
+        let host = page_url.host_str().unwrap_or_default().to_string();
+        let key = cache_key(url);
+        let preview_path = cache_dir.join(format!("preview-{}.json", key));
+
+        if let Some(preview) = self.load_fresh(&preview_path) {
+            return Ok(preview);
+        }
+        if !self.acquire(&host, Instant::now()) {
+            return Err(format!(
+                "Too many preview requests for {}, try again later",
+                host
+            ));
+        }
+
+        let page_opts = DownloadOpts::new().max_bytes(MAX_PAGE_BYTES);
+        let page = downloader
+            .download(cache_dir, &format!("preview-{}.html", key), url, &page_opts)
+            .await?;
+        let html = std::fs::read(&page.path).map_err(|e| format!("Failed to read page: {}", e))?;
+        let meta = parse_page(&String::from_utf8_lossy(&html));
+
+        let image = match meta
+            .image
+            .as_deref()
+            .and_then(|href| page_url.join(href).ok())
+        {
+            Some(image_url) => {
+                let file_name = image_file_name(&format!("preview-{}-image", key), &image_url);
+                fetch_image(
+                    downloader,
+                    cache_dir,
+                    &file_name,
+                    &image_url,
+                    MAX_IMAGE_BYTES,
+                )
+                .await
+            }
+            None => None,
+        };
+        // Pages without an icon link fall back to the host's /favicon.ico
+        let favicon_url = meta
+            .icon
+            .as_deref()
+            .and_then(|href| page_url.join(href).ok())
+            .or_else(|| page_url.join("/favicon.ico").ok());
+        let favicon = match favicon_url {
+            Some(favicon_url) => {
+                let file_name =
+                    image_file_name(&format!("favicon-{}", cache_key(&host)), &favicon_url);
+                fetch_image(
+                    downloader,
+                    cache_dir,
+                    &file_name,
+                    &favicon_url,
+                    MAX_FAVICON_BYTES,
+                )
+                .await
+            }
+            None => None,
+        };
+        // The host displays these files; only keep images that decode within limits
+        #[cfg(feature = "imaging")]
+        let (image, favicon) = (
+            image.and_then(|path| imaging::normalize_file(&path, &ImageLimits::THUMBNAIL).ok()),
+            favicon.and_then(|path| imaging::normalize_file(&path, &ImageLimits::ICON).ok()),
+        );
+
+        let preview = UrlPreview {
+            url: url.to_string(),
+            title: meta.title,
+            description: meta.description,
+            site_name: meta.site_name,
+            favicon,
+            image,
+        };
+        let content = serde_json::to_string(&preview)
+            .map_err(|e| format!("Failed to serialize preview: {}", e))?;
+        std::fs::write(&preview_path, content)
+            .map_err(|e| format!("Failed to write preview: {}", e))?;
+
+        Ok(preview)
+    }
+
+    /// Read a cached preview younger than the maximum age
+    ///
+    /// Previews whose images were cleaned up since count as stale.
+    fn load_fresh(&self, path: &Path) -> Option<UrlPreview> {
+        let age = std::fs::metadata(path)
+            .ok()?
+            .modified()
+            .ok()?
+            .elapsed()
+            .ok()?;
+        if age > self.max_age {
+            return None;
+        }
+
+        let preview: UrlPreview =
+            serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()?;
+        let images_exist = [&preview.favicon, &preview.image]
+            .into_iter()
+            .flatten()
+            .all(|image| image.exists());
+        images_exist.then_some(preview)
+    }
+
+    /// Take the host's fetch slot, unless it was taken too recently
+    fn acquire(&self, host: &str, now: Instant) -> bool {
+        let mut last_fetch = self.last_fetch.lock().unwrap_or_else(|p| p.into_inner());
+
+        if let Some(last) = last_fetch.get(host)
+            && now.saturating_duration_since(*last) < self.host_interval
+        {
+            return false;
+        }
+        last_fetch.insert(host.to_string(), now);
+        true
+    }
+}
+
+/// Download an image, leaving it out of the preview if that fails
+async fn fetch_image(
+    downloader: &Downloader,
+    cache_dir: &Path,
+    file_name: &str,
+    url: &reqwest::Url,
+    max_bytes: u64,
+) -> Option<PathBuf> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+
+    let opts = DownloadOpts::new().max_bytes(max_bytes);
+    let downloaded = downloader
+        .download(cache_dir, file_name, url.as_str(), &opts)
+        .await
+        .ok()?;
+    Some(downloaded.path)
+}
+
+/// Parse an http(s) URL
+fn web_url(url: &str) -> Option<reqwest::Url> {
+    let parsed = reqwest::Url::parse(url).ok()?;
+    (matches!(parsed.scheme(), "http" | "https") && parsed.host_str().is_some()).then_some(parsed)
+}
+
+/// Short hash naming a URL's or host's cache files
+fn cache_key(text: &str) -> String {
+    Sha256::digest(text.as_bytes())
+        .iter()
+        .take(8)
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Cache file name of an image, keeping known extensions
+fn image_file_name(stem: &str, url: &reqwest::Url) -> String {
+    let extension = url
+        .path()
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_ascii_lowercase())
+        .filter(|extension| IMAGE_EXTENSIONS.contains(&extension.as_str()))
+        .unwrap_or_else(|| "img".to_string());
+    format!("{}.{}", stem, extension)
+}
+
+/// Extract preview metadata from a page
+///
+/// Only `<meta>`, `<link>` and `<title>` in the page's head are looked at;
+/// OpenGraph values take precedence over their plain HTML counterparts.
+pub fn parse_page(html: &str) -> PageMeta {
+    // ASCII lowercasing keeps byte offsets, so positions found in `lower`
+    // index into `html`
+    let lower = html.to_ascii_lowercase();
+    let head_end = lower.find("</head>").unwrap_or(lower.len());
+    let mut meta = PageMeta::default();
+    let mut fallback_title = None;
+    let mut fallback_description = None;
+    let mut fallback_image = None;
+
+    let mut position = 0;
+    while let Some(offset) = lower[position..head_end].find('<') {
+        let start = position + offset + 1;
+        let name_len = lower[start..head_end]
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(head_end - start);
+        let name = &lower[start..start + name_len];
+        let Some(tag_len) = tag_length(&html[start..head_end]) else {
+            break;
+        };
+        let tag = &html[start + name_len..start + tag_len];
+        position = start + tag_len;
+
+        match name {
+            "meta" => {
+                let attributes = parse_attributes(tag);
+                let key = attributes
+                    .get("property")
+                    .or_else(|| attributes.get("name"));
+                let (Some(key), Some(content)) = (key, attributes.get("content")) else {
+                    continue;
+                };
+                let content = Some(content.clone());
+                match key.to_ascii_lowercase().as_str() {
+                    "og:title" => meta.title = content,
+                    "og:description" => meta.description = content,
+                    "og:site_name" => meta.site_name = content,
+                    "og:image" | "og:image:url" | "og:image:secure_url" => {
+                        meta.image = meta.image.or(content)
+                    }
+                    "description" => fallback_description = fallback_description.or(content),
+                    "twitter:image" => fallback_image = fallback_image.or(content),
+                    _ => {}
+                }
+            }
+            "link" if meta.icon.is_none() => {
+                let attributes = parse_attributes(tag);
+                let is_icon = attributes.get("rel").is_some_and(|rel| {
+                    rel.split_whitespace()
+                        .any(|rel| rel.eq_ignore_ascii_case("icon"))
+                });
+                if is_icon {
+                    meta.icon = attributes.get("href").cloned();
+                }
+            }
+            "title" if fallback_title.is_none() => {
+                let text_end = lower[position..head_end]
+                    .find("</title")
+                    .map_or(head_end, |end| position + end);
+                fallback_title = Some(clean_text(&html[position..text_end]));
+                position = text_end;
+            }
+            _ => {}
+        }
+    }
+
+    meta.title = meta
+        .title
+        .or(fallback_title)
+        .filter(|title| !title.is_empty());
+    meta.description = meta.description.or(fallback_description);
+    meta.image = meta.image.or(fallback_image);
+    meta
+}
+
+/// Length of a tag up to and including its `>`, skipping quoted values
+fn tag_length(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    for (index, c) in tag.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (None, '>') => return Some(index + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parse `name="value"` attributes, names lowercased and values decoded
+fn parse_attributes(tag: &str) -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+    let mut rest = tag.trim_end_matches('>').trim_end_matches('/');
+
+    loop {
+        rest = rest.trim_start();
+        let name_len = rest
+            .find(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or(rest.len());
+        if name_len == 0 {
+            break;
+        }
+        let name = rest[..name_len].to_ascii_lowercase();
+        rest = rest[name_len..].trim_start();
+
+        let Some(after_equals) = rest.strip_prefix('=') else {
+            attributes.insert(name, String::new());
+            continue;
+        };
+        let after_equals = after_equals.trim_start();
+        let (value, remaining) = match after_equals.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let value = &after_equals[1..];
+                let end = value.find(quote).unwrap_or(value.len());
+                (&value[..end], value.get(end + 1..).unwrap_or_default())
+            }
+            _ => {
+                let end = after_equals
+                    .find(char::is_whitespace)
+                    .unwrap_or(after_equals.len());
+                after_equals.split_at(end)
+            }
+        };
+        attributes.insert(name, clean_text(value));
+        rest = remaining;
+    }
+
+    attributes
+}
+
+/// Decode common entities and collapse whitespace
+fn clean_text(text: &str) -> String {
+    let decoded = text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&");
+    decoded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html><head>
+  <meta charset="utf-8">
+  <title>Volt &amp; friends</title>
+  <meta name="description" content="Plain description">
+  <META property="og:description" content='A "fast" launcher'>
+  <meta property="og:image" content="/social.png">
+  <link rel="shortcut icon" href="/static/icon.ico">
+</head><body><meta property="og:title" content="Not in head"></body></html>"#;
+
+    /// A 1x1 PNG
+    const PIXEL_PNG: &[u8] = &[
+        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52, 0x00, 0x00,
+        0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x02, 0x00, 0x00, 0x00, 0x90, 0x77, 0x53, 0xde, 0x00, 0x00, 0x00,
+        0x0c, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0xf8, 0xcf, 0xc0, 0x00, 0x00, 0x03, 0x01, 0x01, 0x00, 0xc9,
+        0xfe, 0x92, 0xef, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+    ];
+
+    /// HTTP server answering `/page`, `/huge`, and images for any other path
+    async fn serve() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let mut buffer = vec![0; 4096];
+                let read = stream.read(&mut buffer).await.unwrap();
+                let request = String::from_utf8_lossy(&buffer[..read]).to_string();
+
+                let body = match request.split_whitespace().nth(1) {
+                    Some("/page") => PAGE.as_bytes().to_vec(),
+                    Some("/huge") => vec![b'x'; MAX_PAGE_BYTES as usize + 1],
+                    _ => PIXEL_PNG.to_vec(),
+                };
+                let header = format!("HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n", body.len());
+                let _ = stream.write_all(&[header.as_bytes(), &body].concat()).await;
+            }
+        });
+
+        format!("http://{}", address)
+    }
+
+    #[test]
+    fn test_page_metadata_is_extracted() {
+        let meta = parse_page(PAGE);
+        assert_eq!(meta.title.as_deref(), Some("Volt & friends"));
+        assert_eq!(meta.description.as_deref(), Some("A \"fast\" launcher"));
+        assert_eq!(meta.image.as_deref(), Some("/social.png"));
+        assert_eq!(meta.icon.as_deref(), Some("/static/icon.ico"));
+        assert_eq!(meta.site_name, None);
+
+        assert_eq!(parse_page("<p>no head</p>"), PageMeta::default());
+        assert_eq!(
+            parse_page("<head><meta content=\"unterminated"),
+            PageMeta::default()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_previews_are_cached_and_rate_limited() {
+        let dir = std::env::temp_dir().join("volt_test_previews");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let base_url = serve().await;
+        let downloader = Downloader::default();
+        let service = PreviewService::new(DEFAULT_PREVIEW_MAX_AGE, Duration::from_secs(60));
+
+        let page_url = format!("{}/page", base_url);
+        let preview = service.preview(&downloader, &dir, &page_url).await.unwrap();
+        assert_eq!(preview.title.as_deref(), Some("Volt & friends"));
+        assert!(
+            preview
+                .image
+                .as_ref()
+                .unwrap()
+                .to_string_lossy()
+                .ends_with("-image.png")
+        );
+        assert!(
+            preview
+                .favicon
+                .as_ref()
+                .unwrap()
+                .to_string_lossy()
+                .ends_with(if cfg!(feature = "imaging") { ".png" } else { ".ico" })
+        );
+
+        // Cached previews don't count against the host's rate
+        assert_eq!(
+            service.preview(&downloader, &dir, &page_url).await.unwrap(),
+            preview
+        );
+        let error = service
+            .preview(&downloader, &dir, &format!("{}/other", base_url))
+            .await
+            .unwrap_err();
+        assert!(error.starts_with("Too many preview requests"));
+
+        let unlimited = PreviewService::new(DEFAULT_PREVIEW_MAX_AGE, Duration::ZERO);
+        let error = unlimited
+            .preview(&downloader, &dir, &format!("{}/huge", base_url))
+            .await
+            .unwrap_err();
+        assert!(error.contains("larger than"));
+        assert!(
+            unlimited
+                .preview(&downloader, &dir, "file:///etc/passwd")
+                .await
+                .is_err()
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
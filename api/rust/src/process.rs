@@ -0,0 +1,1085 @@
+/// Subprocess plugins over JSON-RPC stdio
+///
+/// Lets plugins be written in any language: the launcher spawns the plugin's
+/// executable and speaks the JSON-RPC plugin protocol (see `crate::protocol`)
+/// over its stdin and stdout, one message per line. Lines the plugin writes
+/// to stderr go to the plugin's log.
+///
+/// Every request carries its own ID, so the plugin may answer out of order.
+/// Requests not answered within `ProcessConfig::request_timeout` fail, and
+/// are cancelled in the plugin with a `cancel` message when it agreed to
+/// cancellation during the handshake. A plugin that exits or crashes is
+/// started again on the next call, up to `ProcessConfig::max_restarts` times,
+/// and the handshake is repeated with the new process. `shutdown` closes the
+/// plugin's stdin, which asks it to exit, and kills it when it doesn't exit in
+/// time; dropping the last handle kills it right away.
+///
+/// With `ProcessConfig::with_pid_file` the process is recorded in a PID file
+/// while it runs, so `crate::janitor` kills it at the next startup if the
+/// launcher crashes.
+///
+/// Hosts load subprocess plugins during discovery:
+///
+/// ```ignore
+/// let loaders = PluginLoaders::new().with(PluginBackend::Process, PluginProcess::loader(handle, api.clone()));
+/// ```
+use crate::cancel::{CANCELLED, CancellationToken};
+use crate::api::VoltPluginAPI;
+use crate::compat::ApiVersion;
+use crate::devmode::PluginLoader;
+use crate::janitor::{PidFile, RUN_DIR};
+use crate::keys::{KeyEvent, KeyResponse};
+use crate::logging::Diagnostics;
+use crate::manifest::PluginManifest;
+use crate::outcome::ExecuteOutcome;
+use crate::plugin::{Plugin, QueryContext};
+use crate::protocol::{
+    HandshakeRequest, HandshakeResponse, NegotiatedProtocol, ProtocolFeature, RemotePluginInfo,
+    RpcRequest, RpcResponse, error_codes, methods, negotiate,
+};
+use crate::result::PluginResult;
+use crate::wire::{Compression, Frame, WireCodec, WireLimits};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::oneshot;
+
+/// How a plugin executable is started and spoken to
+#[derive(Debug, Clone)]
+pub struct ProcessConfig {
+    /// Executable to run
+    pub program: PathBuf,
+    /// Arguments passed to the executable
+    pub args: Vec<String>,
+    /// Working directory, the launcher's when None
+    pub working_dir: Option<PathBuf>,
+    /// Environment variables added to the launcher's
+    pub env: Vec<(String, String)>,
+    /// Time the plugin has to answer a request
+    pub request_timeout: Duration,
+    /// Time the plugin has to exit once its stdin is closed
+    pub shutdown_timeout: Duration,
+    /// Times the plugin is started again after exiting
+    pub max_restarts: u32,
+    /// Size limits of messages
+    pub limits: WireLimits,
+    /// Where the plugin's stderr and failures are reported
+    pub diagnostics: Diagnostics,
+    /// Run directory and plugin ID of the process's PID file; none is
+    /// written when None
+    pub pid_file: Option<(PathBuf, String)>,
+}
+
+impl ProcessConfig {
+    /// Create a configuration with default timeouts
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            working_dir: None,
+            env: Vec::new(),
+            request_timeout: Duration::from_secs(2),
+            shutdown_timeout: Duration::from_secs(2),
+            max_restarts: 3,
+            limits: WireLimits::default(),
+            diagnostics: Diagnostics::process(),
+            pid_file: None,
+        }
+    }
+
+    /// Pass arguments to the executable
+    pub fn with_args(mut self, args: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.args = args.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Run the executable in a directory
+    pub fn with_working_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.working_dir = Some(dir.into());
+        self
+    }
+
+    /// Set an environment variable of the plugin
+    pub fn with_env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+
+    /// Use a custom request timeout
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Restart the plugin at most `max_restarts` times
+    pub fn with_max_restarts(mut self, max_restarts: u32) -> Self {
+        self.max_restarts = max_restarts;
+        self
+    }
+
+    /// Use custom message size limits
+    pub fn with_limits(mut self, limits: WireLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Report through an instance's diagnostics
+    pub fn with_diagnostics(mut self, diagnostics: Diagnostics) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    /// Record the running process in a PID file, see `crate::janitor`
+    ///
+    /// # Arguments
+    /// * `run_dir` - Directory of PID files, `janitor::RUN_DIR` of the app data directory
+    /// * `plugin_id` - Plugin the PID file is named after
+    pub fn with_pid_file(mut self, run_dir: impl Into<PathBuf>, plugin_id: impl Into<String>) -> Self {
+        self.pid_file = Some((run_dir.into(), plugin_id.into()));
+        self
+    }
+
+    /// Name of the plugin in logs
+    fn label(&self) -> String {
+        self.program
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.program.display().to_string())
+    }
+}
+
+/// A running plugin process
+struct Running {
+    child: Child,
+    stdin: ChildStdin,
+    /// Distinguishes this process from the ones started after it
+    generation: u64,
+}
+
+/// State shared between process handles and the reader tasks
+struct ProcessInner {
+    config: ProcessConfig,
+    codec: WireCodec,
+    next_id: AtomicU64,
+    pending: Mutex<HashMap<u64, oneshot::Sender<RpcResponse>>>,
+    running: tokio::sync::Mutex<Option<Running>>,
+    generation: AtomicU64,
+    restarts: AtomicU32,
+    /// Protocol settled by the last handshake
+    protocol: Mutex<Option<NegotiatedProtocol>>,
+    closed: AtomicBool,
+}
+
+impl ProcessInner {
+    /// Route a line written by the plugin to the call waiting for it
+    fn dispatch_response(&self, line: String) {
+        let response = match self.codec.decode_response(Frame::Text(line)) {
+            Ok(response) => response,
+            Err(e) => {
+                self.config.diagnostics.warn(
+                    "process",
+                    &format!("Ignoring message from {}: {}", self.config.label(), e),
+                );
+                return;
+            }
+        };
+
+        let waiter = self
+            .pending
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .remove(&response.id);
+        if let Some(waiter) = waiter {
+            let _ = waiter.send(response);
+        }
+    }
+
+    /// Fail every in-flight call by dropping its response channel
+    fn fail_pending(&self) {
+        self.pending
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .clear();
+    }
+
+    /// Record a started process in the PID file
+    fn write_pid_file(&self, pid: Option<u32>) {
+        let (Some((run_dir, plugin_id)), Some(pid)) = (&self.config.pid_file, pid) else {
+            return;
+        };
+        let pid_file = PidFile::new(plugin_id.clone(), pid, self.config.program.display().to_string());
+        if let Err(e) = pid_file.write(run_dir) {
+            self.config.diagnostics.warn(
+                "process",
+                &format!("Plugin process {} has no PID file: {}", self.config.label(), e),
+            );
+        }
+    }
+
+    /// Remove the PID file once the process stopped
+    fn remove_pid_file(&self) {
+        if let Some((run_dir, plugin_id)) = &self.config.pid_file
+            && let Err(e) = PidFile::remove(run_dir, plugin_id)
+        {
+            self.config.diagnostics.warn("process", &e);
+        }
+    }
+}
+
+impl Drop for ProcessInner {
+    fn drop(&mut self) {
+        // The process is killed along with its `Running`
+        self.remove_pid_file();
+    }
+}
+
+/// Handle to a plugin process
+///
+/// Cheap to clone; all clones share the same process.
+#[derive(Clone)]
+pub struct PluginProcess {
+    inner: Arc<ProcessInner>,
+}
+
+impl PluginProcess {
+    /// Start a plugin executable
+    ///
+    /// Must be called from within a tokio runtime.
+    ///
+    /// # Returns
+    /// A handle to the process, or Err if it could not be started
+    pub async fn spawn(config: ProcessConfig) -> Result<Self, String> {
+        let codec = WireCodec::new(config.limits, Compression::None);
+        let process = Self {
+            inner: Arc::new(ProcessInner {
+                config,
+                codec,
+                next_id: AtomicU64::new(1),
+                pending: Mutex::new(HashMap::new()),
+                running: tokio::sync::Mutex::new(None),
+                generation: AtomicU64::new(0),
+                restarts: AtomicU32::new(0),
+                protocol: Mutex::new(None),
+                closed: AtomicBool::new(false),
+            }),
+        };
+
+        let running = process.start()?;
+        *process.inner.running.lock().await = Some(running);
+        Ok(process)
+    }
+
+    /// Check if the plugin process is running
+    pub fn is_running(&self) -> bool {
+        self.inner
+            .running
+            .try_lock()
+            .map_or(true, |running| running.is_some())
+    }
+
+    /// Get the number of times the plugin was started again
+    pub fn restarts(&self) -> u32 {
+        self.inner.restarts.load(Ordering::SeqCst)
+    }
+
+    /// Ask the plugin to exit, killing it if it doesn't in time
+    ///
+    /// The plugin isn't started again afterwards.
+    pub async fn shutdown(&self) {
+        self.inner.closed.store(true, Ordering::SeqCst);
+        let Some(Running {
+            mut child, stdin, ..
+        }) = self.inner.running.lock().await.take()
+        else {
+            return;
+        };
+
+        // Closing stdin is the plugin's signal to exit
+        drop(stdin);
+        let exited = tokio::time::timeout(self.inner.config.shutdown_timeout, child.wait()).await;
+        if !matches!(exited, Ok(Ok(_))) {
+            self.inner.config.diagnostics.warn(
+                "process",
+                &format!(
+                    "Plugin {} did not exit in time, killing it",
+                    self.inner.config.label()
+                ),
+            );
+            let _ = child.kill().await;
+        }
+        self.inner.remove_pid_file();
+        self.inner.fail_pending();
+    }
+
+    /// Call a method of the plugin
+    ///
+    /// Starts the plugin again first if it exited.
+    pub async fn call(&self, method: &str, params: Value) -> Result<Value, String> {
+        self.ensure_running().await?;
+        self.request(method, params).await
+    }
+
+    /// Call a method of the plugin until the work is cancelled
+    ///
+    /// Once `token` is cancelled the call stops waiting and the plugin is
+    /// told to stop working on the request.
+    pub async fn call_with_cancellation(
+        &self,
+        method: &str,
+        params: Value,
+        token: &CancellationToken,
+    ) -> Result<Value, String> {
+        token
+            .run(self.call(method, params))
+            .await
+            .unwrap_or_else(|| Err(CANCELLED.to_string()))
+    }
+
+    /// Negotiate the protocol with the plugin and list what it serves
+    ///
+    /// Plugins predating the handshake are spoken to with the legacy
+    /// protocol, and their plugins listed with `list_plugins`. Plugins
+    /// targeting an API version the launcher can't load are left out.
+    pub async fn handshake(&self) -> Result<Vec<ProcessPlugin>, String> {
+        self.ensure_running().await?;
+        let request = HandshakeRequest::new(vec![ProtocolFeature::Cancellation]);
+        let params = serde_json::to_value(&request)
+            .map_err(|e| format!("Failed to serialize handshake: {}", e))?;
+
+        let value = match self.request(methods::HANDSHAKE, params).await {
+            Ok(value) => value,
+            Err(e) if is_method_not_found(&e) => {
+                self.set_protocol(NegotiatedProtocol::legacy());
+                return self.list_plugins().await;
+            }
+            Err(e) => return Err(e),
+        };
+        let response: HandshakeResponse = serde_json::from_value(value)
+            .map_err(|e| format!("Failed to parse handshake: {}", e))?;
+        let protocol = negotiate(&request, &response)?;
+        self.set_protocol(protocol);
+
+        Ok(response
+            .plugins
+            .into_iter()
+            .filter(|plugin| match plugin.api_version.map(ApiVersion) {
+                Some(version) => match version.check() {
+                    Ok(()) => true,
+                    Err(reason) => {
+                        self.inner.config.diagnostics.warn(
+                            "process",
+                            &format!(
+                                "Plugin '{}' from {} not loaded: it {}",
+                                plugin.info.id,
+                                self.inner.config.label(),
+                                reason
+                            ),
+                        );
+                        false
+                    }
+                },
+                None => true,
+            })
+            .map(|plugin| ProcessPlugin {
+                info: plugin.info,
+                capabilities: plugin.capabilities,
+                process: self.clone(),
+            })
+            .collect())
+    }
+
+    /// List the plugins served by the process
+    pub async fn list_plugins(&self) -> Result<Vec<ProcessPlugin>, String> {
+        let value = self.call(methods::LIST_PLUGINS, Value::Null).await?;
+        let infos: Vec<RemotePluginInfo> = serde_json::from_value(value)
+            .map_err(|e| format!("Failed to parse plugin list: {}", e))?;
+
+        Ok(infos
+            .into_iter()
+            .map(|info| ProcessPlugin {
+                info,
+                capabilities: Vec::new(),
+                process: self.clone(),
+            })
+            .collect())
+    }
+
+    /// Get the protocol settled by the last handshake
+    ///
+    /// None until `handshake` succeeds.
+    pub fn protocol(&self) -> Option<NegotiatedProtocol> {
+        self.inner
+            .protocol
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .clone()
+    }
+
+    fn set_protocol(&self, protocol: NegotiatedProtocol) {
+        *self
+            .inner
+            .protocol
+            .lock()
+            .unwrap_or_else(|p| p.into_inner()) = Some(protocol);
+    }
+
+    /// Loader of subprocess plugin packages, for `PluginBackend::Process`
+    ///
+    /// Runs the executable named by the manifest's `main` in the package
+    /// directory and picks the plugin with the manifest's ID from its
+    /// handshake. Loaders are synchronous, so the handshake runs on `handle`
+    /// from a thread of its own; the runtime must not be a current-thread
+    /// runtime driven by the calling thread.
+    ///
+    /// # Arguments
+    /// * `handle` - Runtime the plugin processes are driven by
+    /// * `api` - API whose app data directory holds the PID files, and whose
+    ///   diagnostics the processes report to
+    pub fn loader(handle: tokio::runtime::Handle, api: VoltPluginAPI) -> PluginLoader {
+        Arc::new(move |package_dir: &Path, manifest: &PluginManifest| {
+            let config = ProcessConfig::new(manifest.entry_point(package_dir)?)
+                .with_working_dir(package_dir)
+                .with_pid_file(api.get_app_data_dir()?.join(RUN_DIR), &manifest.id)
+                .with_diagnostics(api.diagnostics());
+            let plugin_id = manifest.id.clone();
+
+            let load = async move {
+                let process = PluginProcess::spawn(config).await?;
+                let plugin = process
+                    .handshake()
+                    .await?
+                    .into_iter()
+                    .find(|plugin| plugin.info.id == plugin_id);
+                match plugin {
+                    Some(plugin) => Ok(plugin),
+                    None => {
+                        process.shutdown().await;
+                        Err(format!("Plugin process does not serve '{}'", plugin_id))
+                    }
+                }
+            };
+            let plugin = std::thread::scope(|scope| {
+                scope
+                    .spawn(|| handle.block_on(load))
+                    .join()
+                    .unwrap_or_else(|_| Err("Plugin process loader panicked".to_string()))
+            })?;
+            Ok(Box::new(plugin) as Box<dyn Plugin + Send + Sync>)
+        })
+    }
+
+    /// Start the executable and the tasks reading its output
+    fn start(&self) -> Result<Running, String> {
+        let config = &self.inner.config;
+        let mut command = Command::new(&config.program);
+        command
+            .args(&config.args)
+            .envs(config.env.iter().cloned())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(dir) = &config.working_dir {
+            command.current_dir(dir);
+        }
+
+        let mut child = command.spawn().map_err(|e| {
+            format!(
+                "Failed to start plugin process '{}': {}",
+                config.program.display(),
+                e
+            )
+        })?;
+        let (Some(stdin), Some(stdout), Some(stderr)) =
+            (child.stdin.take(), child.stdout.take(), child.stderr.take())
+        else {
+            return Err("Failed to open plugin process pipes".to_string());
+        };
+        self.inner.write_pid_file(child.id());
+
+        let generation = self.inner.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        tokio::spawn(Self::read_stdout(
+            Arc::downgrade(&self.inner),
+            BufReader::new(stdout),
+            generation,
+        ));
+        tokio::spawn(Self::read_stderr(
+            config.label(),
+            config.diagnostics.clone(),
+            BufReader::new(stderr),
+        ));
+
+        Ok(Running {
+            child,
+            stdin,
+            generation,
+        })
+    }
+
+    /// Start the plugin again if it exited
+    ///
+    /// The handshake is repeated with the new process.
+    async fn ensure_running(&self) -> Result<(), String> {
+        if self.inner.closed.load(Ordering::SeqCst) {
+            return Err(format!(
+                "Plugin process {} was shut down",
+                self.inner.config.label()
+            ));
+        }
+
+        {
+            let mut running = self.inner.running.lock().await;
+            if running.is_some() {
+                return Ok(());
+            }
+            let restarts = self.inner.restarts.fetch_add(1, Ordering::SeqCst);
+            if restarts >= self.inner.config.max_restarts {
+                self.inner.restarts.store(restarts, Ordering::SeqCst);
+                return Err(format!(
+                    "Plugin process {} exited too often",
+                    self.inner.config.label()
+                ));
+            }
+            self.inner.config.diagnostics.info(
+                "process",
+                &format!("Restarting plugin process {}", self.inner.config.label()),
+            );
+            *running = Some(self.start()?);
+        }
+
+        if self.protocol().is_some_and(|protocol| !protocol.legacy) {
+            let request = HandshakeRequest::new(vec![ProtocolFeature::Cancellation]);
+            let params = serde_json::to_value(&request)
+                .map_err(|e| format!("Failed to serialize handshake: {}", e))?;
+            let value = self.request(methods::HANDSHAKE, params).await?;
+            let response: HandshakeResponse = serde_json::from_value(value)
+                .map_err(|e| format!("Failed to parse handshake: {}", e))?;
+            self.set_protocol(negotiate(&request, &response)?);
+        }
+        Ok(())
+    }
+
+    /// Send a request to the running process and wait for its answer
+    async fn request(&self, method: &str, params: Value) -> Result<Value, String> {
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.inner
+            .pending
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .insert(id, tx);
+
+        if let Err(e) = self.send(&RpcRequest::new(id, method, params)).await {
+            self.forget(id);
+            return Err(e);
+        }
+        // Cancels the request in the plugin if this future is dropped early
+        let mut in_flight = InFlight {
+            process: self,
+            id,
+            answered: false,
+        };
+
+        let timeout = self.inner.config.request_timeout;
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(response)) => {
+                in_flight.answered = true;
+                response.into_result()
+            }
+            Ok(Err(_)) => {
+                in_flight.answered = true;
+                Err(format!(
+                    "Plugin process {} exited",
+                    self.inner.config.label()
+                ))
+            }
+            // Dropping `in_flight` cancels the request in the plugin
+            Err(_) => Err(format!(
+                "Plugin call '{}' timed out after {} ms",
+                method,
+                timeout.as_millis()
+            )),
+        }
+    }
+
+    /// Write a request to the plugin's stdin
+    async fn send(&self, request: &RpcRequest) -> Result<(), String> {
+        let Frame::Text(mut line) = self.inner.codec.encode_request(request)? else {
+            return Err("Failed to encode request as text".to_string());
+        };
+        line.push('\n');
+
+        let mut running = self.inner.running.lock().await;
+        let running = running.as_mut().ok_or_else(|| {
+            format!(
+                "Plugin process {} is not running",
+                self.inner.config.label()
+            )
+        })?;
+        let written = running.stdin.write_all(line.as_bytes()).await;
+        match written {
+            Ok(()) => running
+                .stdin
+                .flush()
+                .await
+                .map_err(|e| format!("Failed to write to plugin process: {}", e)),
+            Err(e) => Err(format!("Failed to write to plugin process: {}", e)),
+        }
+    }
+
+    /// Tell the plugin to stop working on a request nobody waits for anymore
+    fn cancel(&self, id: u64) {
+        self.forget(id);
+        // Legacy plugins ignore `cancel`; negotiated ones must have agreed to it
+        if self.protocol().is_some_and(|protocol| {
+            !protocol.legacy && !protocol.supports(ProtocolFeature::Cancellation)
+        }) {
+            return;
+        }
+
+        let process = self.clone();
+        let cancel_id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            handle.spawn(async move {
+                let _ = process.send(&RpcRequest::cancel(cancel_id, id)).await;
+            });
+        }
+    }
+
+    fn forget(&self, id: u64) {
+        self.inner
+            .pending
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .remove(&id);
+    }
+
+    /// Route the plugin's answers until it exits
+    async fn read_stdout(
+        inner: Weak<ProcessInner>,
+        mut stdout: BufReader<tokio::process::ChildStdout>,
+        generation: u64,
+    ) {
+        let Some(max_bytes) = inner
+            .upgrade()
+            .map(|inner| inner.config.limits.max_message_bytes)
+        else {
+            return;
+        };
+
+        loop {
+            let line = read_line(&mut stdout, max_bytes).await;
+            let Some(inner) = inner.upgrade() else {
+                return;
+            };
+            match line {
+                Ok(Some(line)) if line.trim().is_empty() => {}
+                Ok(Some(line)) => inner.dispatch_response(line),
+                Ok(None) => break,
+                Err(e) => inner.config.diagnostics.warn(
+                    "process",
+                    &format!("Ignoring message from {}: {}", inner.config.label(), e),
+                ),
+            }
+        }
+
+        let Some(inner) = inner.upgrade() else {
+            return;
+        };
+        let mut running = inner.running.lock().await;
+        // A newer process may already have replaced this one
+        if running
+            .as_ref()
+            .is_some_and(|running| running.generation == generation)
+            && let Some(mut exited) = running.take()
+        {
+            let status = exited.child.wait().await;
+            inner.remove_pid_file();
+            if !inner.closed.load(Ordering::SeqCst) {
+                inner.config.diagnostics.warn(
+                    "process",
+                    &format!(
+                        "Plugin process {} exited: {}",
+                        inner.config.label(),
+                        status.map_or_else(|e| e.to_string(), |status| status.to_string())
+                    ),
+                );
+            }
+        }
+        drop(running);
+        inner.fail_pending();
+    }
+
+    /// Forward the plugin's stderr to its log
+    async fn read_stderr(
+        label: String,
+        diagnostics: Diagnostics,
+        stderr: BufReader<tokio::process::ChildStderr>,
+    ) {
+        let mut lines = stderr.lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            diagnostics.debug(&label, &line);
+        }
+    }
+}
+
+/// Read one line, refusing lines longer than `max_bytes`
+///
+/// # Returns
+/// The line without its newline, None at the end of the stream, or Err if
+/// the line was too long or not UTF-8 (the rest of it is skipped)
+async fn read_line<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    max_bytes: usize,
+) -> Result<Option<String>, String> {
+    let mut line = Vec::new();
+    let read = (&mut *reader)
+        .take(max_bytes as u64 + 1)
+        .read_until(b'\n', &mut line)
+        .await
+        .map_err(|e| format!("Failed to read from plugin process: {}", e))?;
+    if read == 0 {
+        return Ok(None);
+    }
+
+    if line.last() == Some(&b'\n') {
+        line.pop();
+    } else if line.len() > max_bytes {
+        // Skip the rest of the oversized line
+        let mut rest = Vec::new();
+        while !rest.ends_with(b"\n") {
+            rest.clear();
+            let read = (&mut *reader)
+                .take(max_bytes as u64)
+                .read_until(b'\n', &mut rest)
+                .await
+                .map_err(|e| format!("Failed to read from plugin process: {}", e))?;
+            if read == 0 {
+                break;
+            }
+        }
+        return Err(format!("Message exceeds the {} byte limit", max_bytes));
+    }
+
+    String::from_utf8(line)
+        .map(Some)
+        .map_err(|e| format!("Message is not UTF-8: {}", e))
+}
+
+fn is_method_not_found(error: &str) -> bool {
+    error.starts_with(&format!(
+        "Plugin host error {}:",
+        error_codes::METHOD_NOT_FOUND
+    ))
+}
+
+/// A request sent to the plugin whose response hasn't arrived yet
+struct InFlight<'a> {
+    process: &'a PluginProcess,
+    id: u64,
+    answered: bool,
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        if !self.answered {
+            self.process.cancel(self.id);
+        }
+    }
+}
+
+/// A plugin served by a plugin process
+pub struct ProcessPlugin {
+    info: RemotePluginInfo,
+    capabilities: Vec<String>,
+    process: PluginProcess,
+}
+
+impl ProcessPlugin {
+    /// Get the permissions the plugin declared during the handshake
+    pub fn capabilities(&self) -> &[String] {
+        &self.capabilities
+    }
+
+    /// Get the process serving the plugin
+    pub fn process(&self) -> &PluginProcess {
+        &self.process
+    }
+
+    /// Call a method with a cancellation token, if the query has one
+    async fn call(
+        &self,
+        method: &str,
+        params: Value,
+        context: &QueryContext,
+    ) -> Result<Value, String> {
+        match context.cancellation() {
+            Some(token) => {
+                self.process
+                    .call_with_cancellation(method, params, token)
+                    .await
+            }
+            None => self.process.call(method, params).await,
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for ProcessPlugin {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn id(&self) -> &str {
+        &self.info.id
+    }
+
+    fn name(&self) -> &str {
+        &self.info.name
+    }
+
+    fn description(&self) -> &str {
+        &self.info.description
+    }
+
+    fn version(&self) -> Option<&str> {
+        self.info.version.as_deref()
+    }
+
+    fn author(&self) -> Option<&str> {
+        self.info.author.as_deref()
+    }
+
+    fn homepage(&self) -> Option<&str> {
+        self.info.homepage.as_deref()
+    }
+
+    fn license(&self) -> Option<&str> {
+        self.info.license.as_deref()
+    }
+
+    fn protocol(&self) -> Option<NegotiatedProtocol> {
+        self.process.protocol()
+    }
+
+    fn can_handle(&self, _context: &QueryContext) -> bool {
+        // `can_handle` can't wait for the process; it is asked in `match_query`
+        !self.process.inner.closed.load(Ordering::SeqCst)
+    }
+
+    async fn match_query(&self, context: &QueryContext) -> Result<Vec<PluginResult>, String> {
+        let params = serde_json::json!({ "plugin": self.info.id, "context": context });
+        // Plugins without `can_handle` handle every query
+        match self
+            .call(methods::CAN_HANDLE, params.clone(), context)
+            .await
+        {
+            Ok(Value::Bool(false)) => return Ok(Vec::new()),
+            Err(e) if !is_method_not_found(&e) => return Err(e),
+            _ => {}
+        }
+
+        let value = self.call(methods::MATCH_QUERY, params, context).await?;
+        serde_json::from_value(value).map_err(|e| format!("Failed to parse results: {}", e))
+    }
+
+    async fn execute(&self, result: &PluginResult) -> Result<ExecuteOutcome, String> {
+        let value = self
+            .process
+            .call(
+                methods::EXECUTE,
+                serde_json::json!({ "plugin": self.info.id, "result": result }),
+            )
+            .await?;
+
+        // Plugins predating outcomes answer null
+        if value.is_null() {
+            return Ok(ExecuteOutcome::default());
+        }
+        serde_json::from_value(value).map_err(|e| format!("Failed to parse execute outcome: {}", e))
+    }
+
+    async fn on_key_event(
+        &self,
+        event: &KeyEvent,
+        context: &QueryContext,
+    ) -> Result<KeyResponse, String> {
+        let value = self
+            .process
+            .call(
+                methods::KEY_EVENT,
+                serde_json::json!({ "plugin": self.info.id, "event": event, "context": context }),
+            )
+            .await?;
+
+        // Plugins without key events answer null
+        if value.is_null() {
+            return Ok(KeyResponse::Ignored);
+        }
+        serde_json::from_value(value).map_err(|e| format!("Failed to parse key response: {}", e))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::compat::CURRENT_API_VERSION;
+
+    /// A plugin written in shell: answers by the method of each request line
+    fn echo_plugin() -> ProcessConfig {
+        let script = format!(
+            r#"
+            echo "echo started" >&2
+            while read -r line; do
+                id=$(echo "$line" | sed 's/^{{"jsonrpc":"2.0","id":\([0-9]*\),.*/\1/')
+                case "$line" in
+                *'"method":"handshake"'*)
+                    result='{{"apiVersion":{},"features":["cancellation"],"plugins":[{{"id":"echo","name":"Echo"}}]}}' ;;
+                *'"method":"can_handle"'*'"query":"skip'*) result=false ;;
+                *'"method":"can_handle"'*) result=true ;;
+                *'"method":"match_query"'*'"query":"crash'*) exit 1 ;;
+                *'"method":"match_query"'*'"query":"slow'*) sleep 1; result='[]' ;;
+                *'"method":"match_query"'*) result='[{{"id":"r1","title":"echo"}}]' ;;
+                *'"method":"execute"'*) result=null ;;
+                *'"method":"cancel"'*) continue ;;
+                *)
+                    echo "{{\"jsonrpc\":\"2.0\",\"id\":$id,\"error\":{{\"code\":-32601,\"message\":\"unknown\"}}}}"
+                    continue ;;
+                esac
+                echo "{{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":$result}}"
+            done
+            "#,
+            CURRENT_API_VERSION
+        );
+
+        ProcessConfig::new("sh")
+            .with_args(["-c".to_string(), script])
+            .with_request_timeout(Duration::from_millis(500))
+            .with_max_restarts(1)
+    }
+
+    #[tokio::test]
+    async fn test_process_plugin_round_trip() {
+        let process = PluginProcess::spawn(echo_plugin()).await.unwrap();
+        let plugins = process.handshake().await.unwrap();
+        assert_eq!(plugins.len(), 1);
+        let plugin = &plugins[0];
+        assert_eq!(plugin.id(), "echo");
+        assert!(
+            plugin
+                .protocol()
+                .unwrap()
+                .supports(ProtocolFeature::Cancellation)
+        );
+
+        let results = plugin
+            .match_query(&QueryContext::new("hello"))
+            .await
+            .unwrap();
+        assert_eq!(results[0].title, "echo");
+        assert!(
+            plugin
+                .match_query(&QueryContext::new("skip"))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        plugin.execute(&results[0]).await.unwrap();
+        assert!(
+            process
+                .call("unknown", Value::Null)
+                .await
+                .unwrap_err()
+                .contains("-32601")
+        );
+
+        // Slow answers time out, and later answers still reach their callers
+        let slow = plugin.match_query(&QueryContext::new("slow")).await;
+        assert!(slow.unwrap_err().contains("timed out"));
+        tokio::time::sleep(Duration::from_millis(700)).await;
+        let results = plugin
+            .match_query(&QueryContext::new("again"))
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+
+        process.shutdown().await;
+        assert!(!process.is_running());
+        assert!(
+            plugin
+                .match_query(&QueryContext::new("hello"))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_crashed_process_is_restarted() {
+        let process = PluginProcess::spawn(echo_plugin()).await.unwrap();
+        let plugin = process.handshake().await.unwrap().remove(0);
+
+        assert!(
+            plugin
+                .match_query(&QueryContext::new("crash"))
+                .await
+                .is_err()
+        );
+        let results = plugin
+            .match_query(&QueryContext::new("hello"))
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(process.restarts(), 1);
+
+        // Out of restarts
+        assert!(
+            plugin
+                .match_query(&QueryContext::new("crash"))
+                .await
+                .is_err()
+        );
+        assert!(
+            plugin
+                .match_query(&QueryContext::new("hello"))
+                .await
+                .is_err()
+        );
+        process.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_janitor_kills_processes_left_behind() {
+        let temp_dir = std::env::temp_dir().join("volt_test_process_pid_file");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let run_dir = temp_dir.join(RUN_DIR);
+        let pid_path = PidFile::path(&run_dir, "sleeper");
+        let config = || ProcessConfig::new("sleep").with_args(["30"]).with_pid_file(&run_dir, "sleeper");
+
+        // Stopped processes remove their PID file
+        let process = PluginProcess::spawn(config()).await.unwrap();
+        assert!(pid_path.exists());
+        process.shutdown().await;
+        assert!(!pid_path.exists());
+
+        // The launcher crashing leaves the process and its PID file behind
+        let process = PluginProcess::spawn(config()).await.unwrap();
+        let recorded: PidFile = serde_json::from_str(&std::fs::read_to_string(&pid_path).unwrap()).unwrap();
+        std::mem::forget(process);
+
+        let report = crate::janitor::clean_stale_state(&temp_dir);
+        assert_eq!(report.killed.len(), 1);
+        assert_eq!(report.killed[0].plugin_id, "sleeper");
+        assert_eq!(report.killed[0].pid, recorded.pid);
+        assert!(!pid_path.exists());
+        assert!(report.errors.is_empty());
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
@@ -0,0 +1,481 @@
+/// JSON-RPC plugin protocol
+///
+/// Message types shared by every bridge that talks to plugins running outside
+/// the launcher process (remote hosts, subprocesses, ...). Messages follow
+/// JSON-RPC 2.0 and carry the same `QueryContext` and `PluginResult` types as
+/// in-process plugins.
+///
+/// A bridge opens with a `handshake`: the launcher sends the API version it
+/// implements and the protocol features it can use, and the plugin host
+/// answers with the version it targets, the features it agrees to and the
+/// plugins it serves with their declared capabilities. `negotiate` turns the
+/// answer into a `NegotiatedProtocol`, refusing hosts targeting an API
+/// version the launcher can't load and keeping only the features both sides
+/// understand. Hosts predating the handshake answer `METHOD_NOT_FOUND` and
+/// are spoken to as `NegotiatedProtocol::legacy()`.
+use crate::compat::{ApiVersion, CURRENT_API_VERSION};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// JSON-RPC version sent with every message
+pub const JSONRPC_VERSION: &str = "2.0";
+
+/// Method names understood by plugin hosts
+pub mod methods {
+    /// Negotiate the API version and protocol features, see `HandshakeRequest`
+    pub const HANDSHAKE: &str = "handshake";
+    /// List the plugins served by the host
+    pub const LIST_PLUGINS: &str = "list_plugins";
+    /// Ask a plugin whether it handles a query
+    pub const CAN_HANDLE: &str = "can_handle";
+    /// Ask a plugin for results
+    pub const MATCH_QUERY: &str = "match_query";
+    /// Execute a result
+    pub const EXECUTE: &str = "execute";
+    /// Deliver a key press to the plugin owning the drill-down view
+    pub const KEY_EVENT: &str = "key_event";
+    /// Stop working on an earlier request, whose `id` is the only parameter
+    ///
+    /// Sent when the launcher no longer wants the answer, e.g. because the
+    /// user kept typing. Hosts should stop the request's work and may answer
+    /// it with `REQUEST_CANCELLED`; the answer to `cancel` itself is ignored.
+    pub const CANCEL: &str = "cancel";
+}
+
+/// Standard JSON-RPC error codes
+pub mod error_codes {
+    /// Invalid JSON was received
+    pub const PARSE_ERROR: i64 = -32700;
+    /// The JSON sent is not a valid request
+    pub const INVALID_REQUEST: i64 = -32600;
+    /// The method does not exist
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    /// Invalid method parameters
+    pub const INVALID_PARAMS: i64 = -32602;
+    /// Internal error in the plugin host
+    pub const INTERNAL_ERROR: i64 = -32603;
+    /// The response exceeded the bridge's size limit, see `crate::wire`
+    pub const PAYLOAD_TOO_LARGE: i64 = -32001;
+    /// The request was cancelled by a `cancel` message
+    pub const REQUEST_CANCELLED: i64 = -32800;
+}
+
+/// A request sent to a plugin host
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+pub struct RpcRequest {
+    /// Always "2.0"
+    pub jsonrpc: String,
+    /// Identifier echoed back in the response
+    pub id: u64,
+    /// Method to invoke
+    pub method: String,
+    /// Method parameters
+    #[serde(default)]
+    pub params: Value,
+}
+
+impl RpcRequest {
+    /// Create a new request
+    pub fn new(id: u64, method: &str, params: Value) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id,
+            method: method.to_string(),
+            params,
+        }
+    }
+
+    /// Create a request cancelling an earlier one
+    ///
+    /// # Arguments
+    /// * `id` - Identifier of this request
+    /// * `cancelled_id` - Identifier of the request to cancel
+    pub fn cancel(id: u64, cancelled_id: u64) -> Self {
+        Self::new(id, methods::CANCEL, serde_json::json!({ "id": cancelled_id }))
+    }
+
+    /// Get the identifier of the request a `cancel` request cancels
+    pub fn cancelled_id(&self) -> Option<u64> {
+        if self.method != methods::CANCEL {
+            return None;
+        }
+        self.params.get("id")?.as_u64()
+    }
+
+    /// Decode a request received from a bridge
+    pub fn decode(text: &str) -> Result<Self, String> {
+        let request: Self =
+            serde_json::from_str(text).map_err(|e| format!("Failed to parse request: {}", e))?;
+        check_version(&request.jsonrpc)?;
+
+        Ok(request)
+    }
+}
+
+/// A response received from a plugin host
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+pub struct RpcResponse {
+    /// Always "2.0"
+    pub jsonrpc: String,
+    /// Identifier of the request being answered
+    pub id: u64,
+    /// Result of a successful call
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    /// Error of a failed call
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+/// Error object of a failed call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+pub struct RpcError {
+    /// Error code, see [`error_codes`]
+    pub code: i64,
+    /// Human-readable message
+    pub message: String,
+    /// Additional error data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl RpcResponse {
+    /// Create a successful response
+    pub fn success(id: u64, result: Value) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    /// Create a failed response
+    pub fn failure(id: u64, code: i64, message: impl Into<String>) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id,
+            result: None,
+            error: Some(RpcError {
+                code,
+                message: message.into(),
+                data: None,
+            }),
+        }
+    }
+
+    /// Decode a response received from a plugin host
+    pub fn decode(text: &str) -> Result<Self, String> {
+        let response: Self =
+            serde_json::from_str(text).map_err(|e| format!("Failed to parse response: {}", e))?;
+        check_version(&response.jsonrpc)?;
+
+        if response.result.is_some() && response.error.is_some() {
+            return Err("Response cannot have both a result and an error".to_string());
+        }
+
+        Ok(response)
+    }
+
+    /// Convert into the call's result
+    pub fn into_result(self) -> Result<Value, String> {
+        match (self.result, self.error) {
+            (_, Some(error)) => Err(format!("Plugin host error {}: {}", error.code, error.message)),
+            (Some(result), None) => Ok(result),
+            (None, None) => Ok(Value::Null),
+        }
+    }
+}
+
+/// Check the `jsonrpc` field of a decoded message
+fn check_version(version: &str) -> Result<(), String> {
+    if version != JSONRPC_VERSION {
+        return Err(format!("Unsupported JSON-RPC version '{}'", version));
+    }
+
+    Ok(())
+}
+
+/// Description of a plugin served by a plugin host
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+pub struct RemotePluginInfo {
+    /// Unique identifier of the plugin
+    pub id: String,
+    /// Human-readable name
+    pub name: String,
+    /// Short description
+    #[serde(default)]
+    pub description: String,
+    /// Version of the plugin
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Author of the plugin
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Project homepage
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+    /// SPDX license identifier
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+}
+
+/// Optional parts of the protocol, used only when both sides agree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "snake_case")]
+pub enum ProtocolFeature {
+    /// Results sent in several batches as they are found
+    Streaming,
+    /// Result updates sent as diffs, see `crate::diff`
+    Diffs,
+    /// Compressed messages, see `crate::wire`
+    Compression,
+    /// Abandoned requests stopped with `cancel`
+    Cancellation,
+}
+
+impl ProtocolFeature {
+    /// All features, in declaration order
+    pub const ALL: [ProtocolFeature; 4] = [
+        ProtocolFeature::Streaming,
+        ProtocolFeature::Diffs,
+        ProtocolFeature::Compression,
+        ProtocolFeature::Cancellation,
+    ];
+
+    /// Get the name of the feature on the wire
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProtocolFeature::Streaming => "streaming",
+            ProtocolFeature::Diffs => "diffs",
+            ProtocolFeature::Compression => "compression",
+            ProtocolFeature::Cancellation => "cancellation",
+        }
+    }
+}
+
+/// Parameters of the `handshake` request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct HandshakeRequest {
+    /// API version the launcher implements
+    pub api_version: u32,
+    /// Features the launcher can use
+    pub features: Vec<ProtocolFeature>,
+}
+
+impl HandshakeRequest {
+    /// Offer the current API version and the given features
+    pub fn new(features: Vec<ProtocolFeature>) -> Self {
+        Self {
+            api_version: CURRENT_API_VERSION,
+            features,
+        }
+    }
+}
+
+/// Answer of a plugin host to the `handshake` request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct HandshakeResponse {
+    /// API version the host targets
+    pub api_version: u32,
+    /// Features the host agrees to use
+    ///
+    /// Names the launcher doesn't know, from newer hosts, are ignored.
+    #[serde(default)]
+    pub features: Vec<String>,
+    /// Plugins served by the host
+    #[serde(default)]
+    pub plugins: Vec<HandshakePlugin>,
+}
+
+/// A plugin announced during the handshake
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct HandshakePlugin {
+    /// Plugin metadata
+    #[serde(flatten)]
+    pub info: RemotePluginInfo,
+    /// Permissions the plugin needs, as declared in manifests
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// API version the plugin targets, when it differs from the host's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_version: Option<u32>,
+}
+
+/// What the launcher and a plugin host agreed on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NegotiatedProtocol {
+    /// API version spoken on the bridge
+    pub api_version: u32,
+    /// Features both sides use, in declaration order
+    pub features: Vec<ProtocolFeature>,
+    /// The host predates the handshake
+    pub legacy: bool,
+}
+
+impl NegotiatedProtocol {
+    /// Protocol of hosts predating the handshake: API v1, no features
+    pub fn legacy() -> Self {
+        Self {
+            api_version: 1,
+            features: Vec::new(),
+            legacy: true,
+        }
+    }
+
+    /// Check if both sides agreed to use a feature
+    pub fn supports(&self, feature: ProtocolFeature) -> bool {
+        self.features.contains(&feature)
+    }
+}
+
+/// Settle the protocol from a host's answer to the handshake
+///
+/// # Arguments
+/// * `request` - Handshake sent by the launcher
+/// * `response` - Answer of the host
+///
+/// # Returns
+/// The negotiated protocol, or an error if the host targets an API version
+/// the launcher can't load
+pub fn negotiate(request: &HandshakeRequest, response: &HandshakeResponse) -> Result<NegotiatedProtocol, String> {
+    ApiVersion(response.api_version)
+        .check()
+        .map_err(|e| format!("Plugin host refused: it {}", e))?;
+
+    let features = ProtocolFeature::ALL
+        .into_iter()
+        .filter(|feature| request.features.contains(feature))
+        .filter(|feature| response.features.iter().any(|agreed| agreed == feature.name()))
+        .collect();
+
+    Ok(NegotiatedProtocol {
+        api_version: response.api_version,
+        features,
+        legacy: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fuzz::{self, json_value};
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_request_wire_format() {
+        let request = RpcRequest::new(7, methods::MATCH_QUERY, serde_json::json!({"query": "calc"}));
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 7,
+                "method": "match_query",
+                "params": {"query": "calc"}
+            })
+        );
+    }
+
+    #[test]
+    fn test_cancel_request() {
+        let cancel = RpcRequest::cancel(8, 7);
+        assert_eq!(
+            serde_json::to_value(&cancel).unwrap(),
+            serde_json::json!({"jsonrpc": "2.0", "id": 8, "method": "cancel", "params": {"id": 7}})
+        );
+        assert_eq!(cancel.cancelled_id(), Some(7));
+        assert_eq!(RpcRequest::new(9, methods::EXECUTE, serde_json::json!({"id": 7})).cancelled_id(), None);
+    }
+
+    #[test]
+    fn test_negotiate_handshake() {
+        let request = HandshakeRequest::new(vec![ProtocolFeature::Diffs, ProtocolFeature::Cancellation]);
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({"apiVersion": CURRENT_API_VERSION, "features": ["diffs", "cancellation"]})
+        );
+
+        let response: HandshakeResponse = serde_json::from_value(serde_json::json!({
+            "apiVersion": 1,
+            "features": ["cancellation", "compression", "teleport"],
+            "plugins": [{"id": "jira", "name": "Jira", "capabilities": ["network"]}]
+        }))
+        .unwrap();
+        assert_eq!(response.plugins[0].info.id, "jira");
+        assert_eq!(response.plugins[0].capabilities, vec!["network"]);
+
+        let protocol = negotiate(&request, &response).unwrap();
+        assert_eq!(protocol.api_version, 1);
+        assert_eq!(protocol.features, vec![ProtocolFeature::Cancellation]);
+        assert!(protocol.supports(ProtocolFeature::Cancellation));
+        assert!(!protocol.supports(ProtocolFeature::Diffs));
+
+        let too_new = HandshakeResponse {
+            api_version: CURRENT_API_VERSION + 1,
+            ..response
+        };
+        assert!(negotiate(&request, &too_new).unwrap_err().starts_with("Plugin host refused: it requires plugin API"));
+    }
+
+    #[test]
+    fn test_response_into_result() {
+        let ok: RpcResponse = serde_json::from_str(r#"{"jsonrpc":"2.0","id":1,"result":[1,2]}"#).unwrap();
+        assert_eq!(ok.into_result().unwrap(), serde_json::json!([1, 2]));
+
+        let failed = RpcResponse::failure(2, error_codes::METHOD_NOT_FOUND, "no such method");
+        let encoded = serde_json::to_string(&failed).unwrap();
+        let decoded: RpcResponse = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(
+            decoded.into_result().unwrap_err(),
+            "Plugin host error -32601: no such method"
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid_messages() {
+        assert!(RpcRequest::decode(r#"{"jsonrpc":"1.0","id":1,"method":"execute"}"#).is_err());
+        assert!(RpcResponse::decode(r#"{"jsonrpc":"2.0","id":1,"result":1,"error":{"code":1,"message":"x"}}"#).is_err());
+        assert!(RpcResponse::decode("not json").is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_request_round_trips(id in any::<u64>(), method in ".{0,32}", params in json_value()) {
+            let request = RpcRequest::new(id, &method, params);
+            let encoded = serde_json::to_string(&request).unwrap();
+            prop_assert_eq!(RpcRequest::decode(&encoded).unwrap(), request);
+        }
+
+        #[test]
+        fn prop_response_round_trips(id in any::<u64>(), result in json_value(), code in any::<i64>(), failed in any::<bool>()) {
+            let response = if failed {
+                RpcResponse::failure(id, code, "failed")
+            } else {
+                RpcResponse::success(id, result)
+            };
+            let encoded = serde_json::to_string(&response).unwrap();
+            let decoded = RpcResponse::decode(&encoded).unwrap();
+            // A null result decodes as no result, which converts to the same value
+            prop_assert_eq!(decoded.into_result(), response.into_result());
+        }
+
+        #[test]
+        fn prop_decoder_never_panics(text in ".{0,256}", value in json_value()) {
+            fuzz::bridge_message(text.as_bytes());
+            fuzz::bridge_message(value.to_string().as_bytes());
+            fuzz::bridge_message(format!(r#"{{"jsonrpc":"2.0","id":1,"method":"execute","params":{}}}"#, value).as_bytes());
+        }
+    }
+}
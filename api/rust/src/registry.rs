@@ -1,12 +1,225 @@
 /// Plugin registry for managing backend plugins
-use crate::core::traits::Plugin;
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use crate::aggregator::{MergedResults, ResultAggregator};
+use crate::annotations::{self, AnnotationReport};
+use crate::api::{ConfigChange, VoltPluginAPI};
+use crate::bundles::{BundleState, BundleTransition, PluginBundle};
+use crate::cancel::{CancellationToken, CANCELLED};
+use crate::compat::{self, ApiVersion};
+use crate::crash::CrashReport;
+use crate::discovery::{self, DiscoveredPlugin, DiscoveryOutcome, PluginBackend, PluginLoaders};
+use crate::dispatch::{self, AdaptiveController, DispatchBudget, DispatchScheduler, DEADLINE_MISSED};
+use crate::extensions::{Preview, SendTarget};
+use crate::feeds::DataFeed;
+use crate::identity::{self, PluginRename};
+use crate::janitor::CleanupReport;
+use crate::keys::{Key, KeyEvent, KeyResponse};
+use crate::locks;
+use crate::logging::Diagnostics;
+use crate::manifest::{PluginManifest, Severity};
+use crate::middleware::{DispatchMiddleware, MiddlewareChain};
+use crate::no_results::{NoResultsNote, NoResultsNotes};
+use crate::outcome::{ExecuteOutcome, Toast};
+use crate::platform::PlatformInfo;
+use crate::preflight::{self, PreflightCheck, PreflightConfig, PreflightIssue, PreflightReport};
+use crate::plugin::{Plugin, QueryContext};
+use crate::protocol::NegotiatedProtocol;
+use crate::resources::{self, ResolvedResource, VirtualPath, RESOURCES_CACHE_DIR};
+use crate::result::{PluginResult, ResultIntent};
+use crate::settings::SettingsPage;
+use crate::startup::{PluginStartup, StartupPhase, StartupReport};
+use crate::stats::{UsageEvent, UsageStats};
+use crate::warmup::{WarmupEntry, WarmupStatus, WarmupTask, WarmupTaskStatus};
+use crate::watchdog::{HangReport, Watchdog};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 
 /// Thread-safe plugin registry
 #[derive(Clone)]
 pub struct PluginRegistry {
-    plugins: Arc<RwLock<HashMap<String, Box<dyn Plugin + Send + Sync>>>>,
+    plugins: Arc<RwLock<HashMap<String, Arc<dyn Plugin + Send + Sync>>>>,
+    /// Configuration changes waiting for their debounce window to elapse
+    pending_config: Arc<Mutex<HashMap<(String, String), serde_json::Value>>>,
+    /// Last error reported by each plugin while applying a configuration
+    config_errors: Arc<RwLock<HashMap<String, String>>>,
+    /// Plugins refused because they don't support this platform
+    unsupported: Arc<RwLock<HashMap<String, PluginSnapshot>>>,
+    /// Platform plugin manifests are checked against
+    platform: PlatformInfo,
+    /// Time each plugin spent in each startup phase
+    startup: Arc<RwLock<HashMap<String, PluginStartup>>>,
+    /// Crash count and latest crash report of each external plugin
+    crashes: Arc<RwLock<HashMap<String, (u32, CrashReport)>>>,
+    /// Plugin bundles and the plugins the user disabled
+    bundles: Arc<RwLock<BundleState>>,
+    /// Callbacks notified of registry events
+    listeners: Arc<RwLock<Vec<RegistryListener>>>,
+    /// Plugins whose fallback results are listed first, in order
+    fallback_order: Arc<RwLock<Vec<String>>>,
+    /// Plugins whose manifest opts into dev mode
+    dev_plugins: Arc<RwLock<HashSet<String>>>,
+    /// ID each plugin UUID was registered under, to follow renames
+    identities: Arc<RwLock<HashMap<String, String>>>,
+    /// Warmup tasks declared in each plugin's manifest, with their status
+    warmups: Arc<RwLock<HashMap<String, Vec<WarmupEntry>>>>,
+    /// Watches synchronous calls of legacy plugins
+    watchdog: Watchdog,
+    /// Local usage statistics queries and executions are recorded in
+    usage: Option<UsageStats>,
+    /// Host middleware run around every dispatched query
+    middleware: MiddlewareChain,
+    /// Plugin owning the current drill-down view, receiving key events
+    key_focus: Arc<Mutex<Option<String>>>,
+    /// Where the registry's diagnostics go
+    diagnostics: Diagnostics,
+    /// Tasks of plugins, force-stopped when a plugin is disabled
+    #[cfg(feature = "isolation")]
+    tasks: Option<crate::runtime::TaskSupervisor>,
+}
+
+/// Status of a plugin known to the registry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum PluginStatus {
+    /// Loaded and enabled
+    Enabled,
+    /// Loaded but disabled
+    Disabled,
+    /// Refused because it cannot run on this platform
+    Unsupported {
+        /// User-facing explanation
+        reason: String,
+    },
+    /// Enabled, but a call hung and is still running; calls fail fast
+    Degraded {
+        /// User-facing explanation
+        reason: String,
+    },
+}
+
+/// Point-in-time view of a plugin for management surfaces
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginSnapshot {
+    /// Plugin identifier
+    pub id: String,
+    /// Human-readable name
+    pub name: String,
+    /// Current status
+    pub status: PluginStatus,
+    /// Number of times the plugin crashed since the registry was created
+    pub crash_count: u32,
+    /// Most recent crash report
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_crash: Option<CrashReport>,
+    /// Loaded in dev mode, reloaded when its files change
+    pub dev: bool,
+    /// Most recent call that hung, see `crate::watchdog`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_hang: Option<HangReport>,
+    /// Protocol negotiated with the bridge serving the plugin
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<NegotiatedProtocol>,
+    /// Status of the plugin's warmup tasks, see `crate::warmup`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warmup: Vec<WarmupTaskStatus>,
+}
+
+/// Something that happened to the registry's plugins
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum RegistryEvent {
+    /// A plugin was registered
+    PluginRegistered {
+        /// Plugin identifier
+        plugin_id: String,
+    },
+    /// A plugin was unregistered
+    PluginUnregistered {
+        /// Plugin identifier
+        plugin_id: String,
+    },
+    /// A plugin was replaced by a fresh build, see `PluginRegistry::reload`
+    PluginReloaded {
+        /// Plugin identifier
+        plugin_id: String,
+    },
+    /// A plugin's state moved to its new ID, see `PluginRegistry::rename_plugin`
+    PluginRenamed {
+        /// Previous plugin identifier
+        from: String,
+        /// New plugin identifier
+        to: String,
+    },
+    /// The user enabled a plugin, directly or through a bundle
+    PluginEnabled {
+        /// Plugin identifier
+        plugin_id: String,
+    },
+    /// The user disabled a plugin, directly or through a bundle
+    PluginDisabled {
+        /// Plugin identifier
+        plugin_id: String,
+    },
+    /// An external plugin crashed
+    PluginCrashed {
+        /// Plugin identifier
+        plugin_id: String,
+        /// Where the crash report was saved
+        report_path: PathBuf,
+    },
+    /// Processes and files left behind by a crashed session were cleaned up
+    StaleStateCleaned {
+        /// What was cleaned
+        report: CleanupReport,
+    },
+}
+
+/// Callback invoked for each registry event
+pub type RegistryListener = Arc<dyn Fn(&RegistryEvent) + Send + Sync>;
+
+/// Metadata of a registered plugin
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct PluginDescriptor {
+    /// Plugin identifier
+    pub id: String,
+    /// Human-readable name
+    pub name: String,
+    /// Short description
+    pub description: String,
+    /// Version of the plugin
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Author of the plugin
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Project homepage
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+    /// SPDX license identifier
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+}
+
+impl PluginDescriptor {
+    /// Read the metadata a plugin declares
+    pub fn of(plugin: &dyn Plugin) -> Self {
+        Self {
+            id: plugin.id().to_string(),
+            name: plugin.name().to_string(),
+            description: plugin.description().to_string(),
+            version: plugin.version().map(str::to_string),
+            author: plugin.author().map(str::to_string),
+            homepage: plugin.homepage().map(str::to_string),
+            license: plugin.license().map(str::to_string),
+        }
+    }
 }
 
 impl PluginRegistry {
@@ -14,41 +227,352 @@ impl PluginRegistry {
     pub fn new() -> Self {
         Self {
             plugins: Arc::new(RwLock::new(HashMap::new())),
+            pending_config: Arc::new(Mutex::new(HashMap::new())),
+            config_errors: Arc::new(RwLock::new(HashMap::new())),
+            unsupported: Arc::new(RwLock::new(HashMap::new())),
+            platform: PlatformInfo::current(),
+            startup: Arc::new(RwLock::new(HashMap::new())),
+            crashes: Arc::new(RwLock::new(HashMap::new())),
+            bundles: Arc::new(RwLock::new(BundleState::new())),
+            listeners: Arc::new(RwLock::new(Vec::new())),
+            fallback_order: Arc::new(RwLock::new(Vec::new())),
+            dev_plugins: Arc::new(RwLock::new(HashSet::new())),
+            identities: Arc::new(RwLock::new(HashMap::new())),
+            warmups: Arc::new(RwLock::new(HashMap::new())),
+            watchdog: Watchdog::default(),
+            usage: None,
+            middleware: MiddlewareChain::new(),
+            key_focus: Arc::new(Mutex::new(None)),
+            diagnostics: Diagnostics::default(),
+            #[cfg(feature = "isolation")]
+            tasks: None,
         }
     }
 
+    /// Check manifests against a specific platform instead of the host's
+    pub fn with_platform(mut self, platform: PlatformInfo) -> Self {
+        self.platform = platform;
+        self
+    }
+
+    /// Record queries and executions in plugins' usage statistics
+    ///
+    /// Pass the statistics returned by `VoltPluginAPI::usage_stats`.
+    pub fn with_usage_stats(mut self, usage: UsageStats) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+
+    /// Send the registry's diagnostics to the API's sink
+    ///
+    /// Pass the diagnostics returned by `VoltPluginAPI::diagnostics`.
+    pub fn with_diagnostics(mut self, diagnostics: Diagnostics) -> Self {
+        self.watchdog = self.watchdog.with_diagnostics(diagnostics.clone());
+        self.middleware = self.middleware.with_diagnostics(diagnostics.clone());
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    /// Get where the registry's diagnostics go
+    pub fn diagnostics(&self) -> Diagnostics {
+        self.diagnostics.clone()
+    }
+
+    /// Stop the tasks of plugins when they are disabled or unregistered
+    ///
+    /// Pass the supervisor returned by `VoltPluginAPI::tasks`.
+    #[cfg(feature = "isolation")]
+    pub fn with_tasks(mut self, tasks: crate::runtime::TaskSupervisor) -> Self {
+        self.tasks = Some(tasks);
+        self
+    }
+
     /// Register a new plugin
     pub fn register(&self, plugin: Box<dyn Plugin + Send + Sync>) -> Result<(), String> {
         let plugin_id = plugin.id().to_string();
         let plugin_name = plugin.name().to_string();
 
-        let mut plugins = self
-            .plugins
-            .write()
-            .map_err(|e| format!("Failed to acquire write lock: {}", e))?;
+        let mut plugins = locks::write(&self.plugins, "plugin registry");
 
         if plugins.contains_key(&plugin_id) {
-            println!(
-                "⚠ Plugin '{}' is already registered. Overwriting.",
-                plugin_id
+            self.diagnostics.warn(
+                "registry",
+                &format!("Plugin '{}' is already registered. Overwriting.", plugin_id),
             );
         }
 
-        plugins.insert(plugin_id.clone(), plugin);
-        println!("✓ Plugin registered: {} ({})", plugin_name, plugin_id);
+        plugins.insert(plugin_id.clone(), Arc::from(plugin));
+        drop(plugins);
+        self.diagnostics.info("registry", &format!("Plugin registered: {} ({})", plugin_name, plugin_id));
+
+        self.emit(RegistryEvent::PluginRegistered { plugin_id });
+        Ok(())
+    }
+
+    /// Register a plugin after checking its manifest against this platform
+    ///
+    /// Plugins that don't support the current platform or OS version are not
+    /// loaded; they are reported as unsupported in `snapshot` instead.
+    /// Plugins opting into dev mode are flagged in `snapshot`. If a plugin
+    /// with the same UUID was registered under another ID, its state moves
+    /// to the new ID and the old instance is unregistered. Warmup tasks
+    /// declared in the manifest are scheduled, see `crate::warmup`.
+    ///
+    /// # Arguments
+    /// * `plugin` - The plugin instance
+    /// * `manifest` - The manifest shipped with the plugin
+    pub fn register_with_manifest(
+        &self,
+        plugin: Box<dyn Plugin + Send + Sync>,
+        manifest: &PluginManifest,
+    ) -> Result<(), String> {
+        self.check_manifest(manifest)?;
+        self.follow_identity(manifest);
+        self.set_dev_mode(&manifest.id, manifest.dev_mode);
+        self.register(plugin)?;
+        self.set_warmup_tasks(&manifest.id, &manifest.warmup);
+        Ok(())
+    }
+
+    /// Move the state of a plugin registered under an older ID of its UUID
+    fn follow_identity(&self, manifest: &PluginManifest) {
+        let Some(uuid) = &manifest.uuid else {
+            return;
+        };
+        let uuid = identity::normalize(uuid);
+        let previous = locks::write(&self.identities, "plugin identities").insert(uuid.clone(), manifest.id.clone());
+
+        if let Some(from) = previous.filter(|from| *from != manifest.id) {
+            let _ = self.unregister(&from);
+            self.rename_plugin(&PluginRename {
+                uuid,
+                from,
+                to: manifest.id.clone(),
+            });
+        }
+    }
+
+    /// Relabel the state kept for a renamed plugin
+    ///
+    /// Bundle memberships, the enabled state, fallback order, crash and
+    /// startup records all move to the new ID. Hosts call this with the
+    /// rename returned by `VoltPluginAPI::migrate_plugin_identity` before
+    /// registering the renamed plugin.
+    pub fn rename_plugin(&self, rename: &PluginRename) {
+        let (from, to) = (rename.from.as_str(), rename.to.as_str());
+
+        locks::write(&self.bundles, "plugin bundles").rename_plugin(from, to);
+        let mut fallback_order = locks::write(&self.fallback_order, "fallback order");
+        if fallback_order.iter().any(|plugin_id| plugin_id == from) {
+            fallback_order.retain(|plugin_id| plugin_id != to);
+            for plugin_id in fallback_order.iter_mut().filter(|plugin_id| *plugin_id == from) {
+                *plugin_id = to.to_string();
+            }
+        }
+        drop(fallback_order);
+
+        let mut crashes = locks::write(&self.crashes, "plugin crashes");
+        if let Some((count, mut report)) = crashes.remove(from) {
+            report.plugin_id = to.to_string();
+            crashes.insert(to.to_string(), (count, report));
+        }
+        drop(crashes);
+        let mut startup = locks::write(&self.startup, "startup timings");
+        if let Some(mut timings) = startup.remove(from) {
+            timings.plugin_id = to.to_string();
+            startup.insert(to.to_string(), timings);
+        }
+        drop(startup);
+        let mut config_errors = locks::write(&self.config_errors, "plugin config errors");
+        if let Some(error) = config_errors.remove(from) {
+            config_errors.insert(to.to_string(), error);
+        }
+        drop(config_errors);
+        locks::write(&self.unsupported, "unsupported plugin list").remove(from);
+        if locks::write(&self.dev_plugins, "dev plugin list").remove(from) {
+            self.set_dev_mode(to, true);
+        }
+        let mut warmups = locks::write(&self.warmups, "plugin warmups");
+        if let Some(entries) = warmups.remove(from) {
+            warmups.insert(to.to_string(), entries);
+        }
+        drop(warmups);
+        locks::write(&self.identities, "plugin identities").insert(rename.uuid.clone(), to.to_string());
+
+        self.diagnostics.info("registry", &format!("Plugin renamed: {} -> {}", from, to));
+        self.emit(RegistryEvent::PluginRenamed {
+            from: from.to_string(),
+            to: to.to_string(),
+        });
+    }
+
+    /// Replace a registered plugin with a fresh instance
+    ///
+    /// Used by `DevWatcher` after a plugin was rebuilt. The old instance is
+    /// unloaded first: an enabled plugin gets its `on_disabled` hook, its
+    /// tasks are stopped and it loses key focus. It is dropped, unloading its
+    /// library, once queries still running on it are done. The new instance
+    /// is initialized right away, and gets its `on_enabled` hook if the
+    /// plugin is enabled. If the plugin isn't registered, nothing is
+    /// replaced.
+    pub fn reload(&self, plugin: Box<dyn Plugin + Send + Sync>) -> Result<(), String> {
+        let plugin_id = plugin.id().to_string();
+        let plugin: Arc<dyn Plugin + Send + Sync> = Arc::from(plugin);
+        let enabled = self.is_enabled(&plugin_id);
+
+        let old = {
+            let mut plugins = locks::write(&self.plugins, "plugin registry");
+
+            let Some(slot) = plugins.get_mut(&plugin_id) else {
+                return Err(format!("Plugin '{}' not found", plugin_id));
+            };
+            std::mem::replace(slot, plugin.clone())
+        };
+
+        if enabled {
+            let outcome = panic::catch_unwind(AssertUnwindSafe(|| old.on_disabled()))
+                .unwrap_or_else(|_| Err("Plugin panicked".to_string()));
+            if let Err(e) = outcome {
+                self.diagnostics.warn(
+                    "registry",
+                    &format!("Previous build of plugin '{}' failed to unload cleanly: {}", plugin_id, e),
+                );
+            }
+        }
+        self.stop_tasks(&plugin_id);
+        self.release_key_focus_of(&plugin_id);
+        drop(old);
+
+        let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+            plugin.initialize()?;
+            if enabled {
+                plugin.on_enabled()?;
+            }
+            Ok::<(), String>(())
+        }))
+        .unwrap_or_else(|_| Err("Plugin panicked during initialization".to_string()));
+        if let Err(e) = outcome {
+            self.diagnostics.warn(
+                "registry",
+                &format!("Plugin '{}' failed to initialize after reload: {}", plugin_id, e),
+            );
+        }
 
+        self.diagnostics.info("registry", &format!("Plugin reloaded: {}", plugin_id));
+        self.emit(RegistryEvent::PluginReloaded { plugin_id });
         Ok(())
     }
 
+    /// Check if a plugin was loaded in dev mode
+    pub fn is_dev_plugin(&self, plugin_id: &str) -> bool {
+        locks::read(&self.dev_plugins, "dev plugin list").contains(plugin_id)
+    }
+
+    /// Flag or unflag a plugin as loaded in dev mode
+    fn set_dev_mode(&self, plugin_id: &str, dev: bool) {
+        let mut dev_plugins = locks::write(&self.dev_plugins, "dev plugin list");
+
+        if dev {
+            dev_plugins.insert(plugin_id.to_string());
+        } else {
+            dev_plugins.remove(plugin_id);
+        }
+    }
+
+    /// Check if the plugin described by a manifest can be loaded here
+    ///
+    /// Call this before constructing the plugin to avoid touching platform
+    /// APIs that don't exist. Plugins declaring an API version this host
+    /// can't load are refused too. Unsupported plugins are remembered so they
+    /// show up in `snapshot`.
+    pub fn check_manifest(&self, manifest: &PluginManifest) -> Result<(), String> {
+        let mut unsupported = locks::write(&self.unsupported, "unsupported plugin list");
+
+        let supported = manifest
+            .check_platform(&self.platform)
+            .and_then(|()| {
+                manifest
+                    .api_version
+                    .map_or(Ok(()), |version| ApiVersion(version).check())
+            })
+            .and_then(|()| manifest.uuid.as_deref().map_or(Ok(()), identity::check_uuid));
+        match supported {
+            Ok(()) => {
+                unsupported.remove(&manifest.id);
+                Ok(())
+            }
+            Err(reason) => {
+                self.diagnostics.warn(
+                    "registry",
+                    &format!("Plugin '{}' not loaded: {}", manifest.id, reason),
+                );
+                unsupported.insert(
+                    manifest.id.clone(),
+                    PluginSnapshot {
+                        id: manifest.id.clone(),
+                        name: manifest.name.clone(),
+                        status: PluginStatus::Unsupported {
+                            reason: reason.clone(),
+                        },
+                        crash_count: 0,
+                        last_crash: None,
+                        last_hang: None,
+                        dev: manifest.dev_mode,
+                        protocol: None,
+                        warmup: Vec::new(),
+                    },
+                );
+                Err(format!("Plugin '{}' cannot be loaded: {}", manifest.id, reason))
+            }
+        }
+    }
+
+    /// Find the API version a dynamic or WASM plugin targets
+    ///
+    /// The manifest's `apiVersion` wins; otherwise the binary is scanned for
+    /// the marker of `declare_api_version!()`. Plugins targeting an older
+    /// version must be wrapped in their adapter (e.g., `compat::adapt_v1`)
+    /// before registration.
+    ///
+    /// # Arguments
+    /// * `manifest` - The manifest shipped with the plugin
+    /// * `binary` - The plugin's library or module
+    ///
+    /// # Returns
+    /// The version, or Err with a user-facing reason if it isn't supported
+    pub fn plugin_api_version(&self, manifest: &PluginManifest, binary: &Path) -> Result<ApiVersion, String> {
+        let version = match manifest.api_version {
+            Some(version) => ApiVersion(version),
+            None => compat::detect_api_version_of_file(binary)?,
+        };
+        version
+            .check()
+            .map_err(|reason| format!("Plugin '{}' cannot be loaded: it {}", manifest.id, reason))?;
+
+        if version.needs_adapter() {
+            self.diagnostics.info(
+                "registry",
+                &format!("Plugin '{}' targets API {}, loading it through an adapter", manifest.id, version),
+            );
+        }
+        Ok(version)
+    }
+
     /// Unregister a plugin
     pub fn unregister(&self, plugin_id: &str) -> Result<(), String> {
-        let mut plugins = self
-            .plugins
-            .write()
-            .map_err(|e| format!("Failed to acquire write lock: {}", e))?;
+        let removed = locks::write(&self.plugins, "plugin registry").remove(plugin_id);
 
-        if plugins.remove(plugin_id).is_some() {
-            println!("✓ Plugin unregistered: {}", plugin_id);
+        if removed.is_some() {
+            self.release_key_focus_of(plugin_id);
+            locks::write(&self.warmups, "plugin warmups").remove(plugin_id);
+            #[cfg(feature = "isolation")]
+            if let Some(tasks) = &self.tasks {
+                tasks.remove(plugin_id);
+            }
+            self.diagnostics.info("registry", &format!("Plugin unregistered: {}", plugin_id));
+            self.emit(RegistryEvent::PluginUnregistered {
+                plugin_id: plugin_id.to_string(),
+            });
             Ok(())
         } else {
             Err(format!("Plugin '{}' not found", plugin_id))
@@ -56,129 +580,3266 @@ impl PluginRegistry {
     }
 
     /// Get all registered plugin IDs
-    pub fn list_plugins(&self) -> Result<Vec<String>, String> {
-        let plugins = self
-            .plugins
-            .read()
-            .map_err(|e| format!("Failed to acquire read lock: {}", e))?;
+    pub fn plugin_ids(&self) -> Vec<String> {
+        locks::read(&self.plugins, "plugin registry").keys().cloned().collect()
+    }
 
-        Ok(plugins.keys().cloned().collect())
+    /// Get the metadata of every registered plugin, sorted by ID
+    pub fn list_plugins(&self) -> Result<Vec<PluginDescriptor>, String> {
+        let plugins = locks::read(&self.plugins, "plugin registry");
+
+        let mut descriptors: Vec<PluginDescriptor> =
+            plugins.values().map(|plugin| PluginDescriptor::of(plugin.as_ref())).collect();
+        descriptors.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(descriptors)
     }
 
     /// Get count of registered plugins
     pub fn count(&self) -> Result<usize, String> {
-        let plugins = self
-            .plugins
-            .read()
-            .map_err(|e| format!("Failed to acquire read lock: {}", e))?;
+        let plugins = locks::read(&self.plugins, "plugin registry");
 
         Ok(plugins.len())
     }
 
     /// Check if a plugin is registered
     pub fn has_plugin(&self, plugin_id: &str) -> bool {
-        self.plugins
-            .read()
-            .map(|plugins| plugins.contains_key(plugin_id))
-            .unwrap_or(false)
+        locks::read(&self.plugins, "plugin registry").contains_key(plugin_id)
+    }
+
+    /// Get the status of every known plugin, sorted by ID
+    ///
+    /// Includes plugins refused as unsupported on this platform.
+    pub fn snapshot(&self) -> Result<Vec<PluginSnapshot>, String> {
+        let plugins = locks::read(&self.plugins, "plugin registry");
+        let unsupported = locks::read(&self.unsupported, "unsupported plugin list");
+        let crashes = locks::read(&self.crashes, "plugin crashes");
+        let bundles = locks::read(&self.bundles, "plugin bundles");
+        let dev_plugins = locks::read(&self.dev_plugins, "dev plugin list");
+
+        let mut snapshot: Vec<PluginSnapshot> = plugins
+            .values()
+            .map(|plugin| {
+                let enabled = plugin.is_enabled() && bundles.is_plugin_enabled(plugin.id());
+                let last_hang = self.watchdog.last_hang(plugin.id());
+                PluginSnapshot {
+                    id: plugin.id().to_string(),
+                    name: plugin.name().to_string(),
+                    status: match &last_hang {
+                        Some(hang) if enabled && self.watchdog.is_degraded(plugin.id()) => PluginStatus::Degraded {
+                            reason: format!("{} hung for more than {}ms", hang.operation, hang.timeout_ms),
+                        },
+                        _ if enabled => PluginStatus::Enabled,
+                        _ => PluginStatus::Disabled,
+                    },
+                    crash_count: crashes.get(plugin.id()).map_or(0, |(count, _)| *count),
+                    last_crash: crashes.get(plugin.id()).map(|(_, report)| report.clone()),
+                    dev: dev_plugins.contains(plugin.id()),
+                    last_hang,
+                    protocol: plugin.protocol(),
+                    warmup: self.warmup_status(plugin.id()),
+                }
+            })
+            .chain(
+                unsupported
+                    .values()
+                    .filter(|entry| !plugins.contains_key(&entry.id))
+                    .cloned(),
+            )
+            .collect();
+
+        snapshot.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(snapshot)
+    }
+
+    /// Get the batch size each plugin declared, keyed by plugin ID
+    ///
+    /// Feed this to `ResultAggregator::with_plugin_caps`.
+    pub fn preferred_batch_sizes(&self) -> HashMap<String, usize> {
+        locks::read(&self.plugins, "plugin registry")
+            .iter()
+            .filter_map(|(id, plugin)| Some((id.clone(), plugin.preferred_batch_size()?)))
+            .collect()
+    }
+
+    /// Get the "send to" targets offered by enabled plugins
+    ///
+    /// Each target comes with the ID of the plugin offering it; feed these
+    /// to `ResultAggregator::with_send_targets`.
+    pub fn send_targets(&self) -> Vec<(String, SendTarget)> {
+        let plugins = locks::read(&self.plugins, "plugin registry");
+        let bundles = locks::read(&self.bundles, "plugin bundles");
+
+        let mut targets: Vec<(String, SendTarget)> = plugins
+            .iter()
+            .filter(|(id, plugin)| plugin.is_enabled() && bundles.is_plugin_enabled(id))
+            .filter_map(|(id, plugin)| Some((id, plugin.as_send_handler()?)))
+            .flat_map(|(id, handler)| {
+                handler
+                    .send_targets()
+                    .into_iter()
+                    .map(move |target| (id.clone(), target))
+            })
+            .collect();
+
+        targets.sort_by(|a, b| (&a.0, &a.1.id).cmp(&(&b.0, &b.1.id)));
+        targets
+    }
+
+    /// Get the data feeds declared by enabled plugins
+    ///
+    /// Each feed comes with the ID of the plugin declaring it.
+    pub fn data_feeds(&self) -> Vec<(String, DataFeed)> {
+        let plugins = locks::read(&self.plugins, "plugin registry");
+        let bundles = locks::read(&self.bundles, "plugin bundles");
+
+        let mut feeds: Vec<(String, DataFeed)> = plugins
+            .iter()
+            .filter(|(id, plugin)| plugin.is_enabled() && bundles.is_plugin_enabled(id))
+            .flat_map(|(id, plugin)| plugin.data_feeds().into_iter().map(move |feed| (id.clone(), feed)))
+            .collect();
+
+        feeds.sort_by(|a, b| (&a.0, &a.1.id).cmp(&(&b.0, &b.1.id)));
+        feeds
+    }
+
+    /// Schedule the warmup tasks declared in a plugin's manifest
+    ///
+    /// Invalid tasks are skipped with a warning.
+    fn set_warmup_tasks(&self, plugin_id: &str, tasks: &[WarmupTask]) {
+        let entries: Vec<WarmupEntry> = tasks
+            .iter()
+            .filter(|task| match task.validate() {
+                Ok(()) => true,
+                Err(e) => {
+                    self.diagnostics.warn(plugin_id, &format!("Warmup task skipped: {}", e));
+                    false
+                }
+            })
+            .map(|task| WarmupEntry {
+                task: task.clone(),
+                status: WarmupStatus::Pending,
+                warmed: false,
+            })
+            .collect();
+
+        let mut warmups = locks::write(&self.warmups, "plugin warmups");
+        if entries.is_empty() {
+            warmups.remove(plugin_id);
+        } else {
+            warmups.insert(plugin_id.to_string(), entries);
+        }
+    }
+
+    /// Get the warmup tasks of every enabled plugin, with their status
+    ///
+    /// Sorted by plugin ID, then in manifest order.
+    pub fn warmup_tasks(&self) -> Vec<(String, WarmupTask, WarmupStatus)> {
+        let plugins = locks::read(&self.plugins, "plugin registry");
+        let bundles = locks::read(&self.bundles, "plugin bundles");
+        let warmups = locks::read(&self.warmups, "plugin warmups");
+
+        let mut tasks: Vec<(String, WarmupTask, WarmupStatus)> = warmups
+            .iter()
+            .filter(|(id, _)| {
+                plugins
+                    .get(*id)
+                    .is_some_and(|plugin| plugin.is_enabled() && bundles.is_plugin_enabled(id))
+            })
+            .flat_map(|(id, entries)| {
+                entries
+                    .iter()
+                    .map(move |entry| (id.clone(), entry.task.clone(), entry.status.clone()))
+            })
+            .collect();
+
+        tasks.sort_by(|a, b| a.0.cmp(&b.0));
+        tasks
+    }
+
+    /// Get the status of a plugin's warmup tasks, in manifest order
+    pub fn warmup_status(&self, plugin_id: &str) -> Vec<WarmupTaskStatus> {
+        locks::read(&self.warmups, "plugin warmups")
+            .get(plugin_id)
+            .map(|entries| {
+                entries
+                    .iter()
+                    .map(|entry| WarmupTaskStatus {
+                        task_id: entry.task.id.clone(),
+                        status: entry.status.clone(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Check if a plugin's startup warmup hasn't completed yet
+    ///
+    /// Hosts show an "indexing…" state for such plugins when their first
+    /// queries come back empty. Failed warmups don't count.
+    pub fn is_warming_up(&self, plugin_id: &str) -> bool {
+        locks::read(&self.warmups, "plugin warmups")
+            .get(plugin_id)
+            .is_some_and(|entries| {
+                entries.iter().any(|entry| {
+                    entry.task.on_startup && !entry.warmed && !matches!(entry.status, WarmupStatus::Failed { .. })
+                })
+            })
+    }
+
+    /// Run one of a plugin's warmup tasks and record its status
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin declaring the task
+    /// * `task_id` - ID of the task
+    /// * `now_ms` - Reads the current time, in milliseconds since the Unix epoch
+    pub(crate) fn run_warmup(&self, plugin_id: &str, task_id: &str, now_ms: &dyn Fn() -> u64) -> Result<(), String> {
+        let plugin = locks::read(&self.plugins, "plugin registry")
+            .get(plugin_id)
+            .cloned()
+            .ok_or_else(|| format!("Plugin '{}' not found", plugin_id))?;
+        let task = self
+            .update_warmup(plugin_id, task_id, |entry| entry.status = WarmupStatus::Running)
+            .ok_or_else(|| format!("Plugin '{}' has no warmup task '{}'", plugin_id, task_id))?;
+
+        let outcome = panic::catch_unwind(AssertUnwindSafe(|| plugin.warm_up(&task)))
+            .unwrap_or_else(|_| Err("Plugin panicked while warming up".to_string()));
+        let at_ms = now_ms();
+        self.update_warmup(plugin_id, task_id, |entry| match &outcome {
+            Ok(()) => {
+                entry.status = WarmupStatus::Ready { at_ms };
+                entry.warmed = true;
+            }
+            Err(e) => {
+                entry.status = WarmupStatus::Failed {
+                    at_ms,
+                    error: e.clone(),
+                }
+            }
+        });
+        outcome
+    }
+
+    /// Update a warmup task's entry
+    ///
+    /// # Returns
+    /// The task, or None if the plugin doesn't declare it
+    fn update_warmup(&self, plugin_id: &str, task_id: &str, update: impl FnOnce(&mut WarmupEntry)) -> Option<WarmupTask> {
+        let mut warmups = locks::write(&self.warmups, "plugin warmups");
+        let entry = warmups.get_mut(plugin_id)?.iter_mut().find(|entry| entry.task.id == task_id)?;
+        update(entry);
+        Some(entry.task.clone())
+    }
+
+    /// Hand a refreshed data feed to its plugin
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin declaring the feed
+    /// * `feed_id` - ID of the refreshed feed
+    /// * `path` - File holding the new data
+    pub fn notify_feed_updated(&self, plugin_id: &str, feed_id: &str, path: &Path) -> Result<(), String> {
+        let plugins = locks::read(&self.plugins, "plugin registry");
+
+        let plugin = plugins
+            .get(plugin_id)
+            .ok_or_else(|| format!("Plugin '{}' not found", plugin_id))?;
+
+        panic::catch_unwind(AssertUnwindSafe(|| plugin.on_feed_updated(feed_id, path)))
+            .unwrap_or_else(|_| Err("Plugin panicked while applying feed update".to_string()))
+    }
+
+    /// Deliver a click on a notification action to the plugin that sent it
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin that sent the notification (`Notification::plugin_id`)
+    /// * `notification_id` - ID of the notification
+    /// * `action_id` - ID of the clicked action
+    pub fn notification_action(&self, plugin_id: &str, notification_id: &str, action_id: &str) -> Result<(), String> {
+        let plugins = locks::read(&self.plugins, "plugin registry");
+
+        let plugin = plugins
+            .get(plugin_id)
+            .ok_or_else(|| format!("Plugin '{}' not found", plugin_id))?;
+
+        panic::catch_unwind(AssertUnwindSafe(|| plugin.on_notification_action(notification_id, action_id)))
+            .unwrap_or_else(|_| Err("Plugin panicked while handling a notification action".to_string()))
     }
 
     /// Get enabled plugins count
     pub fn enabled_count(&self) -> Result<usize, String> {
-        let plugins = self
-            .plugins
-            .read()
-            .map_err(|e| format!("Failed to acquire read lock: {}", e))?;
+        let plugins = locks::read(&self.plugins, "plugin registry");
+        let bundles = locks::read(&self.bundles, "plugin bundles");
+
+        Ok(plugins
+            .iter()
+            .filter(|(id, p)| p.is_enabled() && bundles.is_plugin_enabled(id))
+            .count())
+    }
 
-        Ok(plugins.values().filter(|p| p.is_enabled()).count())
+    /// Check if a plugin is registered, enabled, and not disabled by the user
+    pub fn is_enabled(&self, plugin_id: &str) -> bool {
+        let plugins = locks::read(&self.plugins, "plugin registry");
+        let bundles = locks::read(&self.bundles, "plugin bundles");
+
+        plugins
+            .get(plugin_id)
+            .is_some_and(|plugin| plugin.is_enabled() && bundles.is_plugin_enabled(plugin_id))
     }
 
     /// Initialize all registered plugins
+    ///
+    /// Each plugin's `initialize` hook is timed for the startup report,
+    /// which is logged once every plugin is initialized.
     pub async fn initialize_all(&self) -> Result<(), String> {
-        let plugin_ids = self.list_plugins()?;
-        println!("Initializing {} plugins...", plugin_ids.len());
+        let plugin_ids = self.plugin_ids();
+        self.diagnostics.info("registry", &format!("Initializing {} plugins...", plugin_ids.len()));
 
         for plugin_id in plugin_ids {
-            // Note: We can't modify plugins during initialization in this simple implementation
-            // A more advanced implementation would use interior mutability
-            println!("✓ Plugin '{}' initialized", plugin_id);
+            let outcome = {
+                let plugins = locks::read(&self.plugins, "plugin registry");
+                let Some(plugin) = plugins.get(&plugin_id) else {
+                    continue;
+                };
+
+                let started = Instant::now();
+                let outcome = panic::catch_unwind(AssertUnwindSafe(|| plugin.initialize()))
+                    .unwrap_or_else(|_| Err("Plugin panicked during initialization".to_string()));
+                self.record_startup(&plugin_id, StartupPhase::Init, started.elapsed());
+                outcome
+            };
+
+            match outcome {
+                Ok(()) => self.diagnostics.info("registry", &format!("Plugin '{}' initialized", plugin_id)),
+                Err(e) => {
+                    self.diagnostics.warn(
+                        "registry",
+                        &format!("Plugin '{}' failed to initialize: {}", plugin_id, e),
+                    );
+                }
+            }
         }
 
+        self.diagnostics.info("registry", self.startup_report().to_string().trim_end());
         Ok(())
     }
 
     /// Shutdown all registered plugins
     pub async fn shutdown_all(&self) -> Result<(), String> {
-        let plugin_ids = self.list_plugins()?;
-        println!("Shutting down {} plugins...", plugin_ids.len());
+        let plugin_ids = self.plugin_ids();
+        self.diagnostics.info("registry", &format!("Shutting down {} plugins...", plugin_ids.len()));
 
         for plugin_id in plugin_ids {
-            println!("✓ Plugin '{}' shut down", plugin_id);
+            self.diagnostics.info("registry", &format!("Plugin '{}' shut down", plugin_id));
         }
 
         Ok(())
     }
-}
 
-impl Default for PluginRegistry {
-    fn default() -> Self {
-        Self::new()
+    // ========== Query Dispatch ==========
+
+    /// Run a query through every enabled plugin that can handle it
+    ///
+    /// Each plugin gets the context scoped to it, see `QueryContext::scoped_to`.
+    ///
+    /// The scheduler decides which matched plugins run; they answer
+    /// concurrently and their results are merged by the aggregator. Plugins
+    /// that fail or panic are logged and left out. When this leaves nothing
+    /// to show, the plugins' fallback results are returned instead (see
+    /// `fallback_results`). Host middleware runs before routing and after
+    /// merging, see `add_middleware`. Once the context's cancellation token
+    /// is cancelled, plugins still answering are dropped and left out.
+    ///
+    /// # Arguments
+    /// * `context` - The query
+    /// * `scheduler` - Fairness scheduler shared by all queries
+    /// * `aggregator` - Merges and ranks the plugins' results
+    pub async fn dispatch_query(
+        &self,
+        context: &QueryContext,
+        scheduler: &DispatchScheduler,
+        aggregator: &ResultAggregator,
+    ) -> MergedResults {
+        self.dispatch(context, scheduler, aggregator, None).await.0
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Run a query within the budget the adaptive controller set
+    ///
+    /// Like `dispatch_query`, but plugins are asked for at most the
+    /// budget's number of results, and those still answering at its
+    /// deadline are dropped and left out. The pass is recorded in the
+    /// controller's metrics.
+    ///
+    /// # Arguments
+    /// * `context` - The query
+    /// * `scheduler` - Fairness scheduler shared by all queries
+    /// * `aggregator` - Merges and ranks the plugins' results
+    /// * `controller` - Adaptive controller the budget came from
+    /// * `budget` - Budget of the pass, see `AdaptiveController::keystroke`
+    ///   and `AdaptiveController::deep_pass_due`
+    pub async fn dispatch_adaptive(
+        &self,
+        context: &QueryContext,
+        scheduler: &DispatchScheduler,
+        aggregator: &ResultAggregator,
+        controller: &AdaptiveController,
+        budget: DispatchBudget,
+    ) -> MergedResults {
+        let max_results = context.max_results.map_or(budget.max_results, |max| max.min(budget.max_results));
+        let context = context.clone().with_max_results(max_results);
+        let aggregator = aggregator.clone().with_max_results(max_results);
 
-    // Mock plugin for testing
-    struct MockPlugin {
-        id: String,
-        name: String,
+        let (merged, missed) = self.dispatch(&context, scheduler, &aggregator, Some(budget.deadline)).await;
+        controller.record_pass(&budget, merged.results.len(), missed);
+        merged
     }
 
-    #[async_trait::async_trait]
-    impl Plugin for MockPlugin {
-        fn as_any(&self) -> &dyn std::any::Any {
-            self
+    /// Run a query, leaving out plugins still answering at the deadline
+    ///
+    /// # Returns
+    /// The merged results, and the number of plugins that missed the deadline
+    async fn dispatch(
+        &self,
+        context: &QueryContext,
+        scheduler: &DispatchScheduler,
+        aggregator: &ResultAggregator,
+        deadline: Option<Duration>,
+    ) -> (MergedResults, usize) {
+        let mut context = context.clone();
+        if let Some((name, results)) = self.middleware.before_routing(&mut context) {
+            let mut merged = aggregator.merge(vec![(name, results)]);
+            self.middleware.after_merge(&context, &mut merged);
+            return (merged, 0);
+        }
+        let notes = NoResultsNotes::new();
+        let context = &context.with_no_results_notes(notes.clone());
+        let expired = CancellationToken::new();
+        if let Some(deadline) = deadline {
+            expired.cancel_after(deadline);
         }
 
-        fn id(&self) -> &str {
-            &self.id
+        let mut candidates: HashMap<String, Arc<dyn Plugin + Send + Sync>> = {
+            let plugins = locks::read(&self.plugins, "plugin registry");
+            let bundles = locks::read(&self.bundles, "plugin bundles");
+
+            plugins
+                .iter()
+                .filter(|(id, plugin)| plugin.is_enabled() && bundles.is_plugin_enabled(id))
+                .filter(|(id, plugin)| {
+                    let context = context.scoped_to(id);
+                    panic::catch_unwind(AssertUnwindSafe(|| plugin.can_handle(&context))).unwrap_or(false)
+                })
+                .map(|(id, plugin)| (id.clone(), plugin.clone()))
+                .collect()
+        };
+
+        let plugin_ids: Vec<&String> = candidates.keys().collect();
+        let plan = scheduler.plan(&plugin_ids);
+        let queries: Vec<_> = plan
+            .ready
+            .into_iter()
+            .filter_map(|slot| {
+                let plugin_id = slot.plugin_id().to_string();
+                let plugin = candidates.remove(&plugin_id)?;
+                let context = context.scoped_to(&plugin_id);
+                let usage = self.usage.clone();
+                let cancellation = context.cancellation().cloned().unwrap_or_default();
+                let expired = expired.clone();
+                Some(async move {
+                    let started = Instant::now();
+                    // Dropping the plugin's future lets bridges cancel its requests
+                    let query = cancellation.run(dispatch::catch_panic(plugin.match_query(&context)));
+                    let outcome = match expired.run(query).await {
+                        Some(Some(outcome)) => outcome
+                            .unwrap_or_else(|| Err("Plugin panicked while answering a query".to_string())),
+                        Some(None) => Err(CANCELLED.to_string()),
+                        None => Err(DEADLINE_MISSED.to_string()),
+                    };
+                    // Frees the slot and records the plugin's latency
+                    drop(slot);
+                    if let Some(usage) = usage.filter(|_| !cancellation.is_cancelled()) {
+                        let event = UsageEvent::Query {
+                            latency_ms: started.elapsed().as_millis() as u64,
+                            results: outcome.as_ref().map_or(0, Vec::len),
+                            failed: outcome.is_err(),
+                        };
+                        usage.record(&plugin_id, event);
+                    }
+                    (plugin_id, outcome)
+                })
+            })
+            .collect();
+
+        let mut batches = Vec::new();
+        let mut missed = 0;
+        let mut answered = HashSet::new();
+        for (plugin_id, outcome) in dispatch::join_catching(queries).await.into_iter().flatten() {
+            match outcome {
+                Ok(results) => {
+                    if !results.is_empty() {
+                        answered.insert(plugin_id.clone());
+                    }
+                    batches.push((plugin_id, results));
+                }
+                Err(_) if context.is_cancelled() => {}
+                Err(e) if e == DEADLINE_MISSED => missed += 1,
+                Err(e) => self.diagnostics.warn(
+                    "registry",
+                    &format!("Plugin '{}' failed to answer query: {}", plugin_id, e),
+                ),
+            }
         }
 
-        fn name(&self) -> &str {
-            &self.name
+        let mut merged = aggregator.merge(batches);
+        if merged.results.is_empty() {
+            merged = self.fallback_results(context, aggregator);
         }
+        merged.diagnostics = self.no_results_diagnostics(context, notes, &answered);
+        self.middleware.after_merge(context, &mut merged);
+        (merged, missed)
+    }
 
-        fn description(&self) -> &str {
-            "Mock plugin for testing"
+    /// Log the "why not" notes of plugins that returned no results
+    ///
+    /// # Returns
+    /// The notes of dev mode plugins, to show next to the results
+    fn no_results_diagnostics(
+        &self,
+        context: &QueryContext,
+        notes: NoResultsNotes,
+        answered: &HashSet<String>,
+    ) -> Vec<NoResultsNote> {
+        let notes: Vec<NoResultsNote> = notes
+            .take()
+            .into_iter()
+            .filter(|note| !answered.contains(&note.plugin_id))
+            .collect();
+        for note in &notes {
+            self.diagnostics.debug(
+                &note.plugin_id,
+                &format!("No results for '{}' ({}): {}", context.query, note.reason.as_str(), note.detail),
+            );
         }
+
+        let dev_plugins = locks::read(&self.dev_plugins, "dev plugin list");
+        notes
+            .into_iter()
+            .filter(|note| dev_plugins.contains(&note.plugin_id))
+            .collect()
     }
 
-    #[test]
-    fn test_register_plugin() {
-        let registry = PluginRegistry::new();
-        let plugin = Box::new(MockPlugin {
-            id: "test".to_string(),
-            name: "Test Plugin".to_string(),
+    /// Query one plugin again and merge its new results into those on screen
+    ///
+    /// Answers `VoltPluginAPI::refresh_results`: hosts subscribe with
+    /// `VoltPluginAPI::subscribe_refresh_requests`, call this with the query
+    /// of the current view, and send the returned list through the
+    /// `DiffEncoder` that sent the view, so the UI only receives the changed
+    /// rows. The plugin's rows are removed when it no longer handles the
+    /// query or has been disabled. Middleware doesn't run again, the other
+    /// rows already went through it.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin asking for the refresh
+    /// * `context` - Query of the current view
+    /// * `aggregator` - Aggregator that merged the current view
+    /// * `current` - The results on screen
+    ///
+    /// # Returns
+    /// The updated view, or Err if the plugin is unknown or failed to answer,
+    /// in which case the current view stays as it is
+    pub async fn refresh_plugin(
+        &self,
+        plugin_id: &str,
+        context: &QueryContext,
+        aggregator: &ResultAggregator,
+        current: &MergedResults,
+    ) -> Result<MergedResults, String> {
+        let plugin = locks::read(&self.plugins, "plugin registry")
+            .get(plugin_id)
+            .cloned()
+            .ok_or_else(|| format!("Plugin '{}' not found", plugin_id))?;
+
+        let context = context.scoped_to(plugin_id);
+        let handles = self.is_enabled(plugin_id)
+            && panic::catch_unwind(AssertUnwindSafe(|| plugin.can_handle(&context))).unwrap_or(false);
+        let results = if handles {
+            let cancellation = context.cancellation().cloned().unwrap_or_default();
+            cancellation
+                .run(dispatch::catch_panic(plugin.match_query(&context)))
+                .await
+                .ok_or_else(|| CANCELLED.to_string())?
+                .unwrap_or_else(|| Err("Plugin panicked while answering a query".to_string()))
+                .map_err(|e| format!("Plugin '{}' failed to refresh its results: {}", plugin_id, e))?
+        } else {
+            Vec::new()
+        };
+
+        Ok(aggregator.merge_refresh(current, plugin_id.to_string(), results))
+    }
+
+    // ========== Middleware ==========
+
+    /// Insert host middleware into the dispatch pipeline
+    ///
+    /// # Arguments
+    /// * `middleware` - The middleware; replaces one of the same name
+    /// * `order` - Position in the pipeline, lower runs first
+    pub fn add_middleware(&self, middleware: Arc<dyn DispatchMiddleware>, order: i32) {
+        self.middleware.add(middleware, order);
+    }
+
+    /// Remove middleware by name
+    ///
+    /// # Returns
+    /// Whether a middleware was removed
+    pub fn remove_middleware(&self, name: &str) -> bool {
+        self.middleware.remove(name)
+    }
+
+    /// Get the names of the middleware, in running order
+    pub fn middleware_names(&self) -> Vec<String> {
+        self.middleware.names()
+    }
+
+    /// Execute one of a plugin's results
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin that returned the result (`PluginResult::plugin_id`)
+    /// * `result` - The result chosen by the user
+    ///
+    /// # Returns
+    /// What the host should do next, or Err if the plugin failed
+    pub async fn execute(&self, plugin_id: &str, result: &PluginResult) -> Result<ExecuteOutcome, String> {
+        if !self.is_enabled(plugin_id) {
+            return Err(format!("Plugin '{}' not found or disabled", plugin_id));
+        }
+        let plugin = locks::read(&self.plugins, "plugin registry")
+            .get(plugin_id)
+            .cloned()
+            .ok_or_else(|| format!("Plugin '{}' not found", plugin_id))?;
+
+        let outcome = dispatch::catch_panic(plugin.execute(result))
+            .await
+            .unwrap_or_else(|| Err("Plugin panicked while executing a result".to_string()));
+        if let Some(usage) = &self.usage {
+            let failed = outcome.as_ref().map_or(true, ExecuteOutcome::is_error);
+            usage.record(plugin_id, UsageEvent::Selection { failed });
+        }
+        if let Ok(outcome) = &outcome {
+            self.update_key_focus(plugin_id, outcome);
+        }
+        outcome
+    }
+
+    // ========== Key Events ==========
+
+    /// Get the plugin owning the current drill-down view, see `crate::keys`
+    pub fn key_focus(&self) -> Option<String> {
+        self.key_focus.lock().unwrap_or_else(|p| p.into_inner()).clone()
+    }
+
+    /// Give key focus back to the launcher
+    ///
+    /// Hosts call this when the view closes: the user pressed Escape or
+    /// left the view, or the window was hidden.
+    pub fn release_key_focus(&self) {
+        *self.key_focus.lock().unwrap_or_else(|p| p.into_inner()) = None;
+    }
+
+    /// Release key focus if a plugin holds it
+    fn release_key_focus_of(&self, plugin_id: &str) {
+        let mut focus = self.key_focus.lock().unwrap_or_else(|p| p.into_inner());
+        if focus.as_deref() == Some(plugin_id) {
+            *focus = None;
+        }
+    }
+
+    /// Move key focus after a result was executed
+    ///
+    /// A plugin capturing keys owns the view its requery opens. Any other
+    /// new view, or closing the window, gives focus back to the launcher;
+    /// outcomes staying in the current view keep its owner.
+    fn update_key_focus(&self, plugin_id: &str, outcome: &ExecuteOutcome) {
+        let mut focus = self.key_focus.lock().unwrap_or_else(|p| p.into_inner());
+        if outcome.capture_keys && outcome.requery.is_some() {
+            *focus = Some(plugin_id.to_string());
+        } else if outcome.close_window || outcome.requery.is_some() {
+            *focus = None;
+        }
+    }
+
+    /// Deliver a key press to the plugin owning the current view
+    ///
+    /// Reserved keys (`KeyEvent::is_reserved`) are never delivered; Escape
+    /// releases focus. A plugin that fails, panics or was disabled loses
+    /// focus and the key is ignored.
+    ///
+    /// # Arguments
+    /// * `event` - The key press
+    /// * `context` - The query shown in the view
+    ///
+    /// # Returns
+    /// What the host should do with the key; `Ignored` when no plugin owns the view
+    pub async fn dispatch_key_event(&self, event: &KeyEvent, context: &QueryContext) -> KeyResponse {
+        let Some(plugin_id) = self.key_focus() else {
+            return KeyResponse::Ignored;
+        };
+        if event.is_reserved() {
+            if event.key == Key::Escape {
+                self.release_key_focus_of(&plugin_id);
+            }
+            return KeyResponse::Ignored;
+        }
+
+        let plugin = locks::read(&self.plugins, "plugin registry").get(&plugin_id).cloned();
+        let Some(plugin) = plugin.filter(|_| self.is_enabled(&plugin_id)) else {
+            self.release_key_focus_of(&plugin_id);
+            return KeyResponse::Ignored;
+        };
+
+        let context = context.scoped_to(&plugin_id);
+        let response = dispatch::catch_panic(plugin.on_key_event(event, &context))
+            .await
+            .unwrap_or_else(|| Err("Plugin panicked while handling a key event".to_string()));
+        match response {
+            Ok(KeyResponse::Release) => {
+                self.release_key_focus_of(&plugin_id);
+                KeyResponse::Release
+            }
+            Ok(response) => response,
+            Err(e) => {
+                self.diagnostics.warn(
+                    "registry",
+                    &format!("Plugin '{}' failed to handle a key event: {}", plugin_id, e),
+                );
+                self.release_key_focus_of(&plugin_id);
+                KeyResponse::Ignored
+            }
+        }
+    }
+
+    // ========== Settings Pages ==========
+
+    /// Get the settings pages a plugin contributes
+    ///
+    /// # Returns
+    /// The pages, empty if the plugin has none, or Err if one is malformed
+    pub fn settings_pages(&self, plugin_id: &str) -> Result<Vec<SettingsPage>, String> {
+        let plugin = locks::read(&self.plugins, "plugin registry")
+            .get(plugin_id)
+            .cloned()
+            .ok_or_else(|| format!("Plugin '{}' not found", plugin_id))?;
+        let Some(provider) = plugin.as_settings_provider() else {
+            return Ok(Vec::new());
+        };
+
+        let pages = panic::catch_unwind(AssertUnwindSafe(|| provider.settings_pages()))
+            .map_err(|_| format!("Plugin '{}' panicked while listing settings pages", plugin_id))?;
+        for page in &pages {
+            page.validate()
+                .map_err(|e| format!("Plugin '{}' has an invalid settings page: {}", plugin_id, e))?;
+        }
+        Ok(pages)
+    }
+
+    /// Get one of a plugin's settings pages
+    pub fn settings_page(&self, plugin_id: &str, page_id: &str) -> Result<SettingsPage, String> {
+        self.settings_pages(plugin_id)?
+            .into_iter()
+            .find(|page| page.id == page_id)
+            .ok_or_else(|| format!("Plugin '{}' has no settings page '{}'", plugin_id, page_id))
+    }
+
+    /// Run the action of a button on a settings page
+    ///
+    /// Only buttons visible with the given values can be clicked.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin owning the page
+    /// * `page_id` - Page holding the button
+    /// * `action` - Action identifier of the button
+    /// * `values` - Unsaved values currently shown on the page
+    ///
+    /// # Returns
+    /// A message to show, if the plugin returned one
+    pub async fn settings_action(
+        &self,
+        plugin_id: &str,
+        page_id: &str,
+        action: &str,
+        values: &serde_json::Value,
+    ) -> Result<Option<Toast>, String> {
+        if !self.settings_page(plugin_id, page_id)?.has_action(action, values) {
+            return Err(format!("Settings page '{}' has no action '{}'", page_id, action));
+        }
+        let plugin = locks::read(&self.plugins, "plugin registry")
+            .get(plugin_id)
+            .cloned()
+            .ok_or_else(|| format!("Plugin '{}' not found", plugin_id))?;
+
+        dispatch::catch_panic(async {
+            match plugin.as_settings_provider() {
+                Some(provider) => provider.settings_action(page_id, action, values).await,
+                None => Err(format!("Plugin '{}' has no settings pages", plugin_id)),
+            }
+        })
+        .await
+        .unwrap_or_else(|| Err("Plugin panicked while running a settings action".to_string()))
+    }
+
+    // ========== Virtual Resources ==========
+
+    /// Get the content behind a `voltres://` URI from its plugin
+    ///
+    /// # Arguments
+    /// * `uri` - The virtual path, see `crate::resources`
+    pub async fn resolve_resource(&self, uri: &str) -> Result<ResolvedResource, String> {
+        let path = VirtualPath::parse(uri)?;
+        if !self.is_enabled(&path.plugin_id) {
+            return Err(format!("Plugin '{}' not found or disabled", path.plugin_id));
+        }
+        let plugin = locks::read(&self.plugins, "plugin registry")
+            .get(&path.plugin_id)
+            .cloned()
+            .ok_or_else(|| format!("Plugin '{}' not found", path.plugin_id))?;
+
+        dispatch::catch_panic(async {
+            match plugin.as_resource_resolver() {
+                Some(resolver) => resolver.resolve(&path).await,
+                None => Err(format!("Plugin '{}' has no virtual resources", path.plugin_id)),
+            }
+        })
+        .await
+        .unwrap_or_else(|| Err("Plugin panicked while resolving a resource".to_string()))
+    }
+
+    /// Get a real file holding the content behind a `voltres://` URI
+    ///
+    /// Content that isn't backed by a file is written below the owning
+    /// plugin's cache directory.
+    pub async fn materialize_resource(&self, api: &VoltPluginAPI, uri: &str) -> Result<PathBuf, String> {
+        let path = VirtualPath::parse(uri)?;
+        let resource = self.resolve_resource(uri).await?;
+        let dir = api.get_plugin_cache_dir(&path.plugin_id)?.join(RESOURCES_CACHE_DIR);
+
+        resources::materialize(&path, resource, &dir)
+    }
+
+    /// Execute a result's intent, materializing virtual paths first
+    ///
+    /// Same as `intents::execute`, except `OpenFile` intents pointing at a
+    /// `voltres://` URI open the materialized file.
+    ///
+    /// # Returns
+    /// None if the result has no intent, otherwise the outcome of running it
+    pub async fn execute_intent(&self, api: &VoltPluginAPI, result: &PluginResult) -> Option<Result<(), String>> {
+        let Some(ResultIntent::OpenFile { path }) = &result.intent else {
+            return crate::intents::execute(api, result);
+        };
+        if !VirtualPath::is_virtual(path) {
+            return crate::intents::execute(api, result);
+        }
+
+        let materialized = match self.materialize_resource(api, path).await {
+            Ok(file) => file,
+            Err(e) => return Some(Err(e)),
+        };
+        let mut result = result.clone();
+        result.intent = Some(ResultIntent::OpenFile {
+            path: materialized.to_string_lossy().to_string(),
         });
+        crate::intents::execute(api, &result)
+    }
 
-        assert!(registry.register(plugin).is_ok());
-        assert!(registry.has_plugin("test"));
-        assert_eq!(registry.count().unwrap(), 1);
+    /// Build the preview of one of a plugin's results
+    ///
+    /// Images pointing at a `voltres://` URI are materialized, so the host
+    /// always gets a file path.
+    ///
+    /// # Returns
+    /// None if the plugin has no previews
+    pub async fn preview(
+        &self,
+        api: &VoltPluginAPI,
+        plugin_id: &str,
+        result: &PluginResult,
+    ) -> Option<Result<Preview, String>> {
+        let plugin = locks::read(&self.plugins, "plugin registry").get(plugin_id).cloned()?;
+
+        let preview = dispatch::catch_panic(async { Some(plugin.as_previewer()?.preview(result).await) })
+            .await
+            .unwrap_or_else(|| Some(Err("Plugin panicked while building a preview".to_string())))?;
+
+        Some(match preview {
+            Ok(Preview::Image { path }) if VirtualPath::is_virtual(&path) => self
+                .materialize_resource(api, &path)
+                .await
+                .map(|file| Preview::Image { path: file.to_string_lossy().to_string() }),
+            preview => preview,
+        })
     }
 
-    #[test]
-    fn test_unregister_plugin() {
-        let registry = PluginRegistry::new();
-        let plugin = Box::new(MockPlugin {
-            id: "test".to_string(),
-            name: "Test Plugin".to_string(),
+    // ========== Fallbacks ==========
+
+    /// Get the user's fallback provider order
+    pub fn fallback_order(&self) -> Vec<String> {
+        locks::read(&self.fallback_order, "fallback order").clone()
+    }
+
+    /// Set the order in which fallback providers are listed
+    ///
+    /// Plugins missing from the list come after the listed ones, by ID.
+    /// Persist the order and restore it with this method at startup.
+    ///
+    /// # Arguments
+    /// * `order` - Plugin IDs, first listed first
+    pub fn set_fallback_order(&self, order: Vec<String>) {
+        *locks::write(&self.fallback_order, "fallback order") = order;
+    }
+
+    /// Collect the fallback results of every enabled plugin for a query
+    ///
+    /// `dispatch_query` calls this when no plugin returned anything. Blank
+    /// queries get no fallbacks, and plugins that panic are left out.
+    ///
+    /// # Arguments
+    /// * `context` - The query that produced no results
+    /// * `aggregator` - Caps and decorates the fallback results
+    pub fn fallback_results(&self, context: &QueryContext, aggregator: &ResultAggregator) -> MergedResults {
+        if context.query.trim().is_empty() {
+            return MergedResults::default();
+        }
+
+        let mut providers: Vec<(String, Arc<dyn Plugin + Send + Sync>)> = {
+            let plugins = locks::read(&self.plugins, "plugin registry");
+            let bundles = locks::read(&self.bundles, "plugin bundles");
+
+            plugins
+                .iter()
+                .filter(|(id, plugin)| plugin.is_enabled() && bundles.is_plugin_enabled(id))
+                .map(|(id, plugin)| (id.clone(), plugin.clone()))
+                .collect()
+        };
+        let order = self.fallback_order();
+        providers.sort_by_cached_key(|(id, _)| {
+            let rank = order.iter().position(|ordered| ordered == id).unwrap_or(order.len());
+            (rank, id.clone())
         });
 
-        registry.register(plugin).unwrap();
-        assert!(registry.unregister("test").is_ok());
-        assert!(!registry.has_plugin("test"));
-        assert_eq!(registry.count().unwrap(), 0);
+        let batches = providers
+            .into_iter()
+            .filter_map(|(plugin_id, plugin)| {
+                let context = context.scoped_to(&plugin_id);
+                match panic::catch_unwind(AssertUnwindSafe(|| plugin.fallback_results(&context))) {
+                    Ok(results) => Some((plugin_id, results)),
+                    Err(_) => {
+                        self.diagnostics.warn(
+                            "registry",
+                            &format!("Plugin '{}' panicked while producing fallback results", plugin_id),
+                        );
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        aggregator.merge_fallbacks(batches)
+    }
+
+    // ========== Annotations ==========
+
+    /// Let enabled annotators decorate merged results
+    ///
+    /// Annotators run concurrently, each on its own thread. Those that
+    /// haven't answered when the budget runs out are reported as timed out
+    /// and their annotations discarded; they finish in the background.
+    /// Annotations are applied in plugin ID order, so the result doesn't
+    /// depend on which annotator answered first.
+    ///
+    /// # Arguments
+    /// * `merged` - Results returned by `dispatch_query`
+    /// * `budget` - Time annotators get, e.g. `annotations::DEFAULT_ANNOTATION_BUDGET`
+    pub fn annotate(&self, merged: &mut MergedResults, budget: Duration) -> AnnotationReport {
+        let mut report = AnnotationReport::default();
+        let annotators: Vec<(String, Arc<dyn Plugin + Send + Sync>)> = {
+            let plugins = locks::read(&self.plugins, "plugin registry");
+            let bundles = locks::read(&self.bundles, "plugin bundles");
+
+            plugins
+                .iter()
+                .filter(|(id, plugin)| plugin.is_enabled() && bundles.is_plugin_enabled(id))
+                .filter(|(_, plugin)| plugin.as_annotator().is_some())
+                .map(|(id, plugin)| (id.clone(), plugin.clone()))
+                .collect()
+        };
+        if annotators.is_empty() || merged.results.is_empty() {
+            return report;
+        }
+
+        let deadline = Instant::now() + budget;
+        // Moved out and back in, so results are only copied when an
+        // annotator is still holding them after the budget ran out
+        let results = Arc::new(std::mem::take(&mut merged.results));
+        let (sender, receiver) = mpsc::channel();
+        let mut pending: Vec<String> = Vec::new();
+        for (plugin_id, plugin) in annotators {
+            let results = results.clone();
+            let sender = sender.clone();
+            pending.push(plugin_id.clone());
+            std::thread::spawn(move || {
+                let annotations = panic::catch_unwind(AssertUnwindSafe(|| {
+                    plugin
+                        .as_annotator()
+                        .map(|annotator| annotator.annotate(&results))
+                        .unwrap_or_default()
+                }));
+                drop(results);
+                // The receiver is gone once the budget ran out
+                let _ = sender.send((plugin_id, annotations.ok()));
+            });
+        }
+        drop(sender);
+
+        let mut answers = Vec::new();
+        while !pending.is_empty() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let Ok((plugin_id, annotations)) = receiver.recv_timeout(remaining) else {
+                break;
+            };
+            pending.retain(|id| *id != plugin_id);
+            match annotations {
+                Some(annotations) => answers.push((plugin_id, annotations)),
+                None => {
+                    self.diagnostics.warn("registry", &format!("Plugin '{}' panicked while annotating", plugin_id));
+                    report.failed.push(plugin_id);
+                }
+            }
+        }
+
+        merged.results = Arc::try_unwrap(results).unwrap_or_else(|shared| shared.as_ref().clone());
+        answers.sort_by(|a, b| a.0.cmp(&b.0));
+        for (plugin_id, annotations) in answers {
+            annotations::apply(&mut merged.results, annotations);
+            report.annotated.push(plugin_id);
+        }
+
+        pending.sort();
+        for plugin_id in &pending {
+            self.diagnostics.warn(
+                "registry",
+                &format!("Plugin '{}' exceeded the annotation budget of {:?}", plugin_id, budget),
+            );
+        }
+        report.timed_out = pending;
+        report.failed.sort();
+        report
+    }
+
+    // ========== Preflight ==========
+
+    /// Check the environment before plugins are discovered and initialized
+    ///
+    /// See `crate::preflight` for what is checked. Packages this platform
+    /// doesn't support aren't checked for interpreters, since they won't be
+    /// loaded. Every issue found is logged as well.
+    ///
+    /// # Arguments
+    /// * `config` - Directories, plugins directory and trust store to check
+    ///
+    /// # Returns
+    /// The issues found, errors first
+    pub fn preflight(&self, config: &PreflightConfig) -> PreflightReport {
+        let mut issues = Vec::new();
+
+        for dir in &config.directories {
+            issues.extend(preflight::check_writable(dir));
+        }
+        if let Some(dir) = config.directories.first() {
+            issues.extend(preflight::check_disk_space(dir, config.min_free_space));
+        }
+        if let Some(plugins_dir) = &config.plugins_dir {
+            issues.extend(self.preflight_packages(plugins_dir, config));
+        }
+        if let Some(trust_store) = &config.trust_store {
+            issues.extend(preflight::check_trust_store(trust_store));
+        }
+
+        // Stable sort keeps the order of checks within each severity
+        issues.sort_by_key(|issue: &PreflightIssue| issue.severity != Severity::Error);
+        for issue in &issues {
+            self.diagnostics.warn("registry", &format!("Preflight: {}", issue.message));
+        }
+        PreflightReport { issues }
+    }
+
+    /// Check the packages of a plugins directory and the interpreters they need
+    fn preflight_packages(&self, plugins_dir: &Path, config: &PreflightConfig) -> Vec<PreflightIssue> {
+        let packages = match discovery::find_packages(plugins_dir) {
+            Ok(packages) => packages,
+            Err(e) => {
+                return vec![PreflightIssue::error(
+                    PreflightCheck::Manifest,
+                    e,
+                    "Create the plugins directory, or give your user read access to it",
+                )
+                .with_path(plugins_dir)];
+            }
+        };
+
+        let mut issues = Vec::new();
+        // Plugins needing each interpreter, by the interpreters able to run them
+        let mut needed: BTreeMap<&'static [&'static str], Vec<String>> = BTreeMap::new();
+        for package_dir in packages {
+            let manifest_path = package_dir.join("manifest.json");
+            let manifest = match PluginManifest::from_file(&manifest_path) {
+                Ok(manifest) => manifest,
+                Err(e) => {
+                    issues.push(
+                        PreflightIssue::error(
+                            PreflightCheck::Manifest,
+                            e,
+                            "Reinstall or remove the plugin",
+                        )
+                        .with_path(manifest_path),
+                    );
+                    continue;
+                }
+            };
+
+            let entry = match manifest.entry_point(&package_dir) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    issues.push(
+                        PreflightIssue::error(
+                            PreflightCheck::Manifest,
+                            e,
+                            "Reinstall or remove the plugin",
+                        )
+                        .with_path(package_dir)
+                        .with_plugins(vec![manifest.id]),
+                    );
+                    continue;
+                }
+            };
+
+            let supported = manifest.check_platform(&self.platform).is_ok();
+            let extension = entry
+                .extension()
+                .map(|extension| extension.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let interpreters = preflight::interpreters(&extension);
+            if supported && PluginBackend::of(&manifest) == Ok(PluginBackend::Script) && !interpreters.is_empty() {
+                needed.entry(interpreters).or_default().push(manifest.id);
+            }
+        }
+
+        for (interpreters, plugin_ids) in needed {
+            if interpreters
+                .iter()
+                .any(|name| preflight::find_program(name, &config.search_path).is_some())
+            {
+                continue;
+            }
+            issues.push(
+                PreflightIssue::error(
+                    PreflightCheck::Interpreter,
+                    format!("{} is not installed, or not on the PATH", interpreters[0]),
+                    format!("Install {} and restart Volt", interpreters[0]),
+                )
+                .with_plugins(plugin_ids),
+            );
+        }
+        issues
+    }
+
+    // ========== Discovery ==========
+
+    /// Load and register every plugin package installed below a directory
+    ///
+    /// Each package's manifest is checked like `register_with_manifest` does,
+    /// binaries are checked for a supported API version, and the plugin is
+    /// built by the loader of its backend (see `crate::discovery`). Packages
+    /// that can't be loaded are reported and skipped; the others still load.
+    /// A plugin ID already registered, or seen earlier in the scan, is skipped.
+    ///
+    /// # Arguments
+    /// * `dir` - Directory the packages are installed in
+    /// * `loaders` - Loaders the host provides, per backend
+    ///
+    /// # Returns
+    /// The outcome of each package, sorted by directory, or Err if the
+    /// directory can't be read
+    pub fn discover_and_register(&self, dir: &Path, loaders: &PluginLoaders) -> Result<Vec<DiscoveredPlugin>, String> {
+        let mut seen = HashMap::new();
+        let found: Vec<DiscoveredPlugin> = discovery::find_packages(dir)?
+            .iter()
+            .map(|package_dir| self.register_package(package_dir, loaders, &mut seen))
+            .inspect(|plugin| {
+                if let DiscoveryOutcome::Failed { error } = &plugin.outcome {
+                    self.diagnostics.warn(
+                        "registry",
+                        &format!("Skipping plugin package {}: {}", plugin.package_dir.display(), error),
+                    );
+                }
+            })
+            .collect();
+
+        let registered = found.iter().filter(|plugin| plugin.is_registered()).count();
+        self.diagnostics.info(
+            "registry",
+            &format!(
+                "Discovered {} plugin packages in {}, {} registered",
+                found.len(),
+                dir.display(),
+                registered
+            ),
+        );
+        Ok(found)
+    }
+
+    /// Load and register a single discovered package
+    fn register_package(
+        &self,
+        package_dir: &Path,
+        loaders: &PluginLoaders,
+        seen: &mut HashMap<String, PathBuf>,
+    ) -> DiscoveredPlugin {
+        let mut found = DiscoveredPlugin::new(package_dir);
+
+        let manifest = match PluginManifest::from_file(&package_dir.join("manifest.json")) {
+            Ok(manifest) => manifest,
+            Err(e) => return found.failed(e),
+        };
+        found.plugin_id = Some(manifest.id.clone());
+        if manifest.id.is_empty() {
+            return found.failed("Manifest has no plugin ID".to_string());
+        }
+        if let Some(first) = seen.get(&manifest.id) {
+            return found.skipped(format!("Plugin '{}' was already found in {}", manifest.id, first.display()));
+        }
+        seen.insert(manifest.id.clone(), package_dir.to_path_buf());
+        if self.has_plugin(&manifest.id) {
+            return found.skipped(format!("Plugin '{}' is already registered", manifest.id));
+        }
+
+        let backend = match PluginBackend::of(&manifest) {
+            Ok(backend) => backend,
+            Err(e) => return found.failed(e),
+        };
+        found.backend = Some(backend);
+
+        if let Err(reason) = self.check_manifest(&manifest) {
+            return found.skipped(reason);
+        }
+        let entry = match manifest.entry_point(package_dir) {
+            Ok(entry) => entry,
+            Err(e) => return found.failed(e),
+        };
+        if backend.is_binary()
+            && let Err(reason) = self.plugin_api_version(&manifest, &entry)
+        {
+            return found.skipped(reason);
+        }
+        let Some(loader) = loaders.get(backend) else {
+            return found.skipped(format!("No loader for {} plugins", backend));
+        };
+
+        let loaded = self.time_startup(&manifest.id, StartupPhase::Load, || {
+            panic::catch_unwind(AssertUnwindSafe(|| loader(package_dir, &manifest)))
+                .unwrap_or_else(|_| Err("Loader panicked".to_string()))
+        });
+        let plugin = match loaded {
+            Ok(plugin) if plugin.id() != manifest.id => {
+                return found.failed(format!(
+                    "Loaded plugin '{}' doesn't match manifest ID '{}'",
+                    plugin.id(),
+                    manifest.id
+                ));
+            }
+            Ok(plugin) => plugin,
+            Err(e) => return found.failed(format!("Failed to load plugin '{}': {}", manifest.id, e)),
+        };
+
+        match self.register_with_manifest(plugin, &manifest) {
+            Ok(()) => found,
+            Err(e) => found.failed(e),
+        }
+    }
+
+    // ========== Startup Profiling ==========
+
+    /// Record time a plugin spent in a startup phase
+    ///
+    /// The host calls this for the phases it drives itself (loading the
+    /// plugin's code and constructing it); `initialize_all` records the
+    /// init phase.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin being started
+    /// * `phase` - Startup phase
+    /// * `duration` - Time spent in the phase
+    pub fn record_startup(&self, plugin_id: &str, phase: StartupPhase, duration: Duration) {
+        locks::write(&self.startup, "startup timings")
+            .entry(plugin_id.to_string())
+            .or_insert_with(|| PluginStartup::new(plugin_id))
+            .add(phase, duration);
+    }
+
+    /// Run a startup phase and record how long it took
+    ///
+    /// # Example
+    /// ```ignore
+    /// let plugin = registry.time_startup("calc", StartupPhase::Construct, || CalculatorPlugin::new());
+    /// ```
+    pub fn time_startup<T>(&self, plugin_id: &str, phase: StartupPhase, f: impl FnOnce() -> T) -> T {
+        let started = Instant::now();
+        let value = f();
+        self.record_startup(plugin_id, phase, started.elapsed());
+        value
+    }
+
+    /// Get per-plugin startup costs, most expensive first
+    pub fn startup_report(&self) -> StartupReport {
+        StartupReport::new(
+            locks::read(&self.startup, "startup timings")
+                .values()
+                .cloned()
+                .collect(),
+        )
+    }
+
+    // ========== Crash Reports ==========
+
+    /// Record a crash of an external plugin
+    ///
+    /// Bridges call this when a plugin process exits unexpectedly or its
+    /// WASM module traps. The report is written to the `crashes` directory
+    /// of the plugin's data dir and becomes the plugin's `last_crash`.
+    ///
+    /// # Arguments
+    /// * `api` - API instance owning the plugin's data directory
+    /// * `report` - Report built by the bridge's `CrashRecorder`
+    ///
+    /// # Returns
+    /// Path of the written report
+    pub fn record_crash(&self, api: &VoltPluginAPI, report: CrashReport) -> Result<PathBuf, String> {
+        let dir = api.get_plugin_data_dir(&report.plugin_id)?.join("crashes");
+        let path = report.write_to(&dir)?;
+
+        self.diagnostics.warn(
+            "registry",
+            &format!("Plugin '{}' crashed, report saved to {}", report.plugin_id, path.display()),
+        );
+
+        let mut crashes = locks::write(&self.crashes, "plugin crashes");
+        let entry = crashes
+            .entry(report.plugin_id.clone())
+            .or_insert_with(|| (0, report.clone()));
+        entry.0 += 1;
+        entry.1 = report;
+        let plugin_id = entry.1.plugin_id.clone();
+        drop(crashes);
+
+        self.emit(RegistryEvent::PluginCrashed {
+            plugin_id,
+            report_path: path.clone(),
+        });
+        Ok(path)
+    }
+
+    /// Get how many times a plugin crashed
+    pub fn crash_count(&self, plugin_id: &str) -> u32 {
+        locks::read(&self.crashes, "plugin crashes")
+            .get(plugin_id)
+            .map_or(0, |(count, _)| *count)
+    }
+
+    /// Get a plugin's most recent crash report
+    pub fn last_crash(&self, plugin_id: &str) -> Option<CrashReport> {
+        locks::read(&self.crashes, "plugin crashes")
+            .get(plugin_id)
+            .map(|(_, report)| report.clone())
+    }
+
+    /// Get the watchdog running legacy plugins' synchronous calls
+    ///
+    /// Pass it to `adapt_v1_watched` so hangs show up in the plugin snapshots.
+    pub fn watchdog(&self) -> Watchdog {
+        self.watchdog.clone()
+    }
+
+    // ========== Bundles ==========
+
+    /// Get the bundle definitions and user-disabled plugins, for persistence
+    pub fn bundle_state(&self) -> BundleState {
+        locks::read(&self.bundles, "plugin bundles").clone()
+    }
+
+    /// Restore previously persisted bundles
+    ///
+    /// No lifecycle hooks run; call this before `initialize_all`.
+    pub fn restore_bundles(&self, state: BundleState) {
+        *locks::write(&self.bundles, "plugin bundles") = state;
+    }
+
+    /// Define or replace a bundle
+    pub fn define_bundle(&self, bundle: PluginBundle) -> Result<(), String> {
+        locks::write(&self.bundles, "plugin bundles").define(bundle)
+    }
+
+    /// Delete a bundle, leaving its members in their current state
+    pub fn remove_bundle(&self, bundle_id: &str) -> Result<(), String> {
+        locks::write(&self.bundles, "plugin bundles")
+            .remove(bundle_id)
+            .map(|_| ())
+    }
+
+    /// Add a plugin to a bundle
+    pub fn add_to_bundle(&self, bundle_id: &str, plugin_id: &str) -> Result<(), String> {
+        locks::write(&self.bundles, "plugin bundles").add_member(bundle_id, plugin_id)
+    }
+
+    /// Remove a plugin from a bundle
+    pub fn remove_from_bundle(&self, bundle_id: &str, plugin_id: &str) -> Result<(), String> {
+        locks::write(&self.bundles, "plugin bundles").remove_member(bundle_id, plugin_id)
+    }
+
+    /// Enable or disable a bundle and its members
+    ///
+    /// # Returns
+    /// The plugins that changed state
+    pub fn set_bundle_enabled(&self, bundle_id: &str, enabled: bool) -> Result<BundleTransition, String> {
+        let transition = locks::write(&self.bundles, "plugin bundles").set_bundle_enabled(bundle_id, enabled)?;
+        Ok(self.run_transition(transition))
+    }
+
+    /// Enable or disable a single plugin
+    pub fn set_plugin_enabled(&self, plugin_id: &str, enabled: bool) -> BundleTransition {
+        let transition = locks::write(&self.bundles, "plugin bundles").set_plugin_enabled(plugin_id, enabled);
+        self.run_transition(transition)
+    }
+
+    /// Switch to a profile, swapping the bundles bound to it
+    pub fn apply_profile(&self, profile: &str) -> BundleTransition {
+        let transition = locks::write(&self.bundles, "plugin bundles").apply_profile(profile);
+        self.run_transition(transition)
+    }
+
+    /// Run the lifecycle hooks of a transition
+    ///
+    /// Every `on_disabled` hook runs before any `on_enabled` hook. A plugin
+    /// failing to enable is disabled again and left out of the returned
+    /// transition.
+    fn run_transition(&self, mut transition: BundleTransition) -> BundleTransition {
+        let plugins = locks::read(&self.plugins, "plugin registry");
+
+        let run = |plugin_id: &str, hook: &dyn Fn(&dyn Plugin) -> Result<(), String>| {
+            let Some(plugin) = plugins.get(plugin_id) else {
+                return Ok(());
+            };
+            panic::catch_unwind(AssertUnwindSafe(|| hook(plugin.as_ref())))
+                .unwrap_or_else(|_| Err("Plugin panicked".to_string()))
+        };
+
+        for plugin_id in &transition.disabled {
+            if let Err(e) = run(plugin_id, &|plugin| plugin.on_disabled()) {
+                self.diagnostics.warn(
+                    "registry",
+                    &format!("Plugin '{}' failed to disable cleanly: {}", plugin_id, e),
+                );
+            }
+            self.stop_tasks(plugin_id);
+        }
+
+        transition.enabled.retain(|plugin_id| match run(plugin_id, &|plugin| plugin.on_enabled()) {
+            Ok(()) => true,
+            Err(e) => {
+                self.diagnostics.warn(
+                    "registry",
+                    &format!("Plugin '{}' failed to enable: {}", plugin_id, e),
+                );
+                locks::write(&self.bundles, "plugin bundles").set_plugin_enabled(plugin_id, false);
+                self.stop_tasks(plugin_id);
+                false
+            }
+        });
+        drop(plugins);
+
+        for plugin_id in &transition.disabled {
+            self.emit(RegistryEvent::PluginDisabled {
+                plugin_id: plugin_id.clone(),
+            });
+        }
+        for plugin_id in &transition.enabled {
+            self.emit(RegistryEvent::PluginEnabled {
+                plugin_id: plugin_id.clone(),
+            });
+        }
+        transition
+    }
+
+    /// Force-stop the tasks a disabled plugin left running
+    fn stop_tasks(&self, plugin_id: &str) {
+        #[cfg(feature = "isolation")]
+        if let Some(tasks) = &self.tasks {
+            let stopped = tasks.shutdown(plugin_id);
+            if stopped.active > 0 {
+                self.diagnostics.warn(
+                    "registry",
+                    &format!("Stopped {} task(s) left running by plugin '{}'", stopped.active, plugin_id),
+                );
+            }
+        }
+        #[cfg(not(feature = "isolation"))]
+        let _ = plugin_id;
+    }
+
+    // ========== Events ==========
+
+    /// Subscribe to registry events
+    pub fn subscribe(&self, listener: RegistryListener) {
+        locks::write(&self.listeners, "registry listeners").push(listener);
+    }
+
+    /// Notify every subscriber of an event
+    fn emit(&self, event: RegistryEvent) {
+        // Callbacks run without holding the lock
+        let listeners = locks::read(&self.listeners, "registry listeners").clone();
+
+        for listener in listeners {
+            if panic::catch_unwind(AssertUnwindSafe(|| listener(&event))).is_err() {
+                self.diagnostics.warn("registry", "A registry event listener panicked");
+            }
+        }
+    }
+
+    // ========== Startup Cleanup ==========
+
+    /// Clean up after a crashed launcher session
+    ///
+    /// Kills orphaned plugin processes and removes stale PID and lock files
+    /// (see `janitor::clean_stale_state`). Call once at startup, before any
+    /// external plugin is started. Subscribers receive a `StaleStateCleaned`
+    /// event if anything was left behind.
+    pub fn clean_stale_state(&self, api: &VoltPluginAPI) -> Result<CleanupReport, String> {
+        let report = crate::janitor::clean_stale_state(&api.get_app_data_dir()?);
+
+        if !report.is_empty() {
+            self.diagnostics.warn(
+                "registry",
+                &format!(
+                    "Cleaned up after a crashed session: {} process(es) killed, {} stale lock(s) removed",
+                    report.killed.len(),
+                    report.removed_locks.len()
+                ),
+            );
+            for error in &report.errors {
+                self.diagnostics.warn("registry", error);
+            }
+            self.emit(RegistryEvent::StaleStateCleaned { report: report.clone() });
+        }
+
+        Ok(report)
+    }
+
+    // ========== Privacy ==========
+
+    /// Notify plugins whenever privacy mode is toggled through the API
+    pub fn route_privacy_changes(&self, api: &VoltPluginAPI) -> Result<(), String> {
+        let registry = self.clone();
+        api.subscribe_privacy_changes(Arc::new(move |enabled| {
+            registry.notify_privacy_mode(enabled);
+        }))
+    }
+
+    /// Tell every plugin that privacy mode was turned on or off
+    ///
+    /// Panics raised by a plugin are contained so every plugin is notified.
+    pub fn notify_privacy_mode(&self, enabled: bool) {
+        let plugins = locks::read(&self.plugins, "plugin registry");
+
+        for (id, plugin) in plugins.iter() {
+            if panic::catch_unwind(AssertUnwindSafe(|| plugin.on_privacy_mode_changed(enabled))).is_err() {
+                self.diagnostics.warn(
+                    "registry",
+                    &format!("Plugin '{}' panicked while switching privacy mode", id),
+                );
+            }
+        }
+    }
+
+    /// Forget everything recorded at or after a time, by the API and every plugin
+    ///
+    /// All plugins are purged even if some fail.
+    ///
+    /// # Arguments
+    /// * `api` - API whose stores are purged as well
+    /// * `since` - Time in milliseconds since the Unix epoch
+    ///
+    /// # Returns
+    /// Err listing the stores that could not be purged
+    pub fn purge_recorded_since(&self, api: &VoltPluginAPI, since: u64) -> Result<(), String> {
+        let mut errors = Vec::new();
+        if let Err(e) = api.purge_recorded_since(since) {
+            errors.push(format!("recent actions: {}", e));
+        }
+
+        let plugins = locks::read(&self.plugins, "plugin registry");
+        let mut ids: Vec<&String> = plugins.keys().collect();
+        ids.sort();
+
+        for id in ids {
+            let plugin = &plugins[id];
+            let outcome = panic::catch_unwind(AssertUnwindSafe(|| plugin.purge_recorded_since(since)))
+                .unwrap_or_else(|_| Err("Plugin panicked while purging".to_string()));
+            if let Err(e) = outcome {
+                errors.push(format!("{}: {}", id, e));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("Failed to purge recorded data: {}", errors.join("; ")))
+        }
+    }
+
+    // ========== Configuration Live-Reload ==========
+
+    /// Deliver configuration changes made through the API to plugins
+    ///
+    /// Changes to the same configuration arriving within `debounce` are
+    /// coalesced, and only the latest one is passed to `on_config_changed`.
+    ///
+    /// # Arguments
+    /// * `api` - API instance whose configuration changes should be routed
+    /// * `debounce` - Quiet period before a change is delivered
+    pub fn route_config_changes(&self, api: &VoltPluginAPI, debounce: Duration) -> Result<(), String> {
+        let registry = self.clone();
+        api.subscribe_config_changes(Arc::new(move |change: &ConfigChange| {
+            registry.schedule_config_change(change, debounce);
+        }))
+    }
+
+    /// Queue a change and start its debounce timer if none is running
+    fn schedule_config_change(&self, change: &ConfigChange, debounce: Duration) {
+        let key = (change.plugin_id.clone(), change.config_name.clone());
+
+        let timer_running = match self.pending_config.lock() {
+            Ok(mut pending) => pending.insert(key.clone(), change.config.clone()).is_some(),
+            Err(_) => return,
+        };
+        if timer_running {
+            return;
+        }
+
+        let registry = self.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(debounce);
+
+            let config = match registry.pending_config.lock() {
+                Ok(mut pending) => pending.remove(&key),
+                Err(_) => None,
+            };
+            if let Some(config) = config {
+                let _ = registry.notify_config_changed(&key.0, &key.1, &config);
+            }
+        });
+    }
+
+    /// Immediately deliver a configuration change to a plugin
+    ///
+    /// Errors and panics raised by the plugin are captured and kept as the
+    /// plugin's last configuration error.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin owning the configuration
+    /// * `config_name` - Name of the configuration file
+    /// * `config` - The new configuration
+    pub fn notify_config_changed(
+        &self,
+        plugin_id: &str,
+        config_name: &str,
+        config: &serde_json::Value,
+    ) -> Result<(), String> {
+        let outcome = {
+            let plugins = locks::read(&self.plugins, "plugin registry");
+
+            let plugin = plugins
+                .get(plugin_id)
+                .ok_or_else(|| format!("Plugin '{}' not found", plugin_id))?;
+
+            panic::catch_unwind(AssertUnwindSafe(|| plugin.on_config_changed(config_name, config)))
+                .unwrap_or_else(|_| Err("Plugin panicked while applying configuration".to_string()))
+        };
+
+        let mut errors = locks::write(&self.config_errors, "plugin config errors");
+
+        match outcome {
+            Ok(()) => {
+                errors.remove(plugin_id);
+                Ok(())
+            }
+            Err(e) => {
+                self.diagnostics.warn(
+                    "registry",
+                    &format!("Plugin '{}' rejected config '{}': {}", plugin_id, config_name, e),
+                );
+                errors.insert(plugin_id.to_string(), e.clone());
+                Err(e)
+            }
+        }
+    }
+
+    /// Get the last error a plugin reported while applying its configuration
+    pub fn last_config_error(&self, plugin_id: &str) -> Option<String> {
+        locks::read(&self.config_errors, "plugin config errors")
+            .get(plugin_id)
+            .cloned()
+    }
+}
+
+impl Default for PluginRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::no_results::NoResultsReason;
+
+    // Mock plugin for testing
+    struct MockPlugin {
+        id: String,
+        name: String,
+    }
+
+    #[async_trait::async_trait]
+    impl Plugin for MockPlugin {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn id(&self) -> &str {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn description(&self) -> &str {
+            "Mock plugin for testing"
+        }
+
+        fn version(&self) -> Option<&str> {
+            Some("1.2.0")
+        }
+
+        fn license(&self) -> Option<&str> {
+            Some("MIT")
+        }
+    }
+
+    #[test]
+    fn test_register_plugin() {
+        let registry = PluginRegistry::new();
+        let plugin = Box::new(MockPlugin {
+            id: "test".to_string(),
+            name: "Test Plugin".to_string(),
+        });
+
+        assert!(registry.register(plugin).is_ok());
+        assert!(registry.has_plugin("test"));
+        assert_eq!(registry.count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_list_plugins_returns_descriptors() {
+        let registry = PluginRegistry::new();
+        for id in ["b", "a"] {
+            registry
+                .register(Box::new(MockPlugin {
+                    id: id.to_string(),
+                    name: id.to_uppercase(),
+                }))
+                .unwrap();
+        }
+
+        let descriptors = registry.list_plugins().unwrap();
+        assert_eq!(descriptors.iter().map(|d| d.id.as_str()).collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(descriptors[0].version.as_deref(), Some("1.2.0"));
+        assert_eq!(descriptors[0].author, None);
+
+        let json = serde_json::to_value(&descriptors[0]).unwrap();
+        assert_eq!(json["license"], "MIT");
+        assert!(json.get("homepage").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_startup_report() {
+        let registry = PluginRegistry::new();
+        let plugin = registry.time_startup("calc", StartupPhase::Construct, || {
+            std::thread::sleep(Duration::from_millis(5));
+            Box::new(MockPlugin {
+                id: "calc".to_string(),
+                name: "Calculator".to_string(),
+            })
+        });
+        registry.register(plugin).unwrap();
+        registry
+            .register(Box::new(MockPlugin {
+                id: "clock".to_string(),
+                name: "Clock".to_string(),
+            }))
+            .unwrap();
+
+        registry.initialize_all().await.unwrap();
+
+        let report = registry.startup_report();
+        assert_eq!(report.plugins.len(), 2);
+        assert_eq!(report.plugins[0].plugin_id, "calc");
+        assert!(report.plugins[0].construct >= Duration::from_millis(5));
+    }
+
+    #[test]
+    fn test_unsupported_platform_is_refused() {
+        use crate::platform::Platform;
+
+        let registry = PluginRegistry::new().with_platform(PlatformInfo {
+            platform: Some(Platform::Linux),
+            os_version: Some("6.1.0".to_string()),
+        });
+        let manifest = PluginManifest {
+            id: "uwp-apps".to_string(),
+            name: "UWP Apps".to_string(),
+            platforms: vec![Platform::Windows],
+            ..Default::default()
+        };
+        let plugin = Box::new(MockPlugin {
+            id: "uwp-apps".to_string(),
+            name: "UWP Apps".to_string(),
+        });
+
+        assert!(registry.register_with_manifest(plugin, &manifest).is_err());
+        assert!(!registry.has_plugin("uwp-apps"));
+
+        registry
+            .register(Box::new(MockPlugin {
+                id: "calc".to_string(),
+                name: "Calculator".to_string(),
+            }))
+            .unwrap();
+
+        let snapshot = registry.snapshot().unwrap();
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot[0].status, PluginStatus::Enabled);
+        assert_eq!(
+            snapshot[1].status,
+            PluginStatus::Unsupported {
+                reason: "Unsupported on linux (supports: windows)".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_plugin_api_versions() {
+        let registry = PluginRegistry::new();
+        let mut manifest = PluginManifest {
+            id: "future".to_string(),
+            name: "Future".to_string(),
+            api_version: Some(3),
+            ..Default::default()
+        };
+        let plugin = Box::new(MockPlugin {
+            id: "future".to_string(),
+            name: "Future".to_string(),
+        });
+        assert!(registry.register_with_manifest(plugin, &manifest).is_err());
+        assert!(!registry.has_plugin("future"));
+
+        let temp_dir = std::env::temp_dir().join("volt_test_registry_api_version");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let binary = temp_dir.join("plugin.wasm");
+        std::fs::write(&binary, b"\0asm without a marker").unwrap();
+
+        let error = registry.plugin_api_version(&manifest, &binary).unwrap_err();
+        assert!(error.contains("supports up to 2"));
+
+        manifest.api_version = None;
+        assert_eq!(registry.plugin_api_version(&manifest, &binary).unwrap(), ApiVersion(1));
+
+        std::fs::write(&binary, crate::compat::VERSION_MARKER).unwrap();
+        assert_eq!(registry.plugin_api_version(&manifest, &binary).unwrap(), ApiVersion::CURRENT);
+
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_discover_and_register() {
+        use crate::discovery::DiscoveryOutcome;
+
+        let temp_dir = std::env::temp_dir().join("volt_test_registry_discovery");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let install = |dir: &str, manifest: &str, main: Option<&str>| {
+            let package_dir = temp_dir.join(dir);
+            std::fs::create_dir_all(&package_dir).unwrap();
+            std::fs::write(package_dir.join("manifest.json"), manifest).unwrap();
+            if let Some(main) = main {
+                std::fs::write(package_dir.join(main), "").unwrap();
+            }
+        };
+        install("broken", "{ not json", None);
+        install("echo", r#"{"id": "echo", "name": "Echo", "version": "1.0.0", "main": "main.py"}"#, Some("main.py"));
+        install("gone", r#"{"id": "gone", "name": "Gone", "version": "1.0.0", "main": "main.py"}"#, None);
+        install("native", r#"{"id": "native", "name": "Native", "version": "1.0.0", "main": "libnative.so"}"#, Some("libnative.so"));
+        install("vendor/echo", r#"{"id": "echo", "name": "Echo", "version": "2.0.0", "main": "main.py"}"#, Some("main.py"));
+
+        let loader: crate::devmode::PluginLoader = Arc::new(|_, manifest| {
+            Ok(Box::new(MockPlugin {
+                id: manifest.id.clone(),
+                name: manifest.name.clone(),
+            }) as Box<dyn Plugin + Send + Sync>)
+        });
+        let registry = PluginRegistry::new();
+        let found = registry
+            .discover_and_register(&temp_dir, &PluginLoaders::new().with(PluginBackend::Script, loader))
+            .unwrap();
+
+        let outcomes: Vec<(&str, &DiscoveryOutcome)> = found
+            .iter()
+            .map(|plugin| (plugin.plugin_id.as_deref().unwrap_or("?"), &plugin.outcome))
+            .collect();
+        assert!(matches!(outcomes[0], ("?", DiscoveryOutcome::Failed { .. })));
+        assert_eq!(outcomes[1], ("echo", &DiscoveryOutcome::Registered));
+        assert!(matches!(outcomes[2], ("gone", DiscoveryOutcome::Failed { error }) if error.contains("not found")));
+        assert_eq!(
+            outcomes[3],
+            (
+                "native",
+                &DiscoveryOutcome::Skipped {
+                    reason: "No loader for native plugins".to_string()
+                }
+            )
+        );
+        assert!(matches!(outcomes[4], ("echo", DiscoveryOutcome::Skipped { reason }) if reason.contains("already found")));
+
+        assert_eq!(registry.plugin_ids(), vec!["echo".to_string()]);
+        assert_eq!(registry.startup_report().plugins[0].plugin_id, "echo");
+        assert!(registry.discover_and_register(&temp_dir.join("missing"), &PluginLoaders::new()).is_err());
+
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_preflight_reports_setup_problems() {
+        use crate::preflight::PreflightCheck;
+
+        let temp_dir = std::env::temp_dir().join("volt_test_registry_preflight");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let plugins_dir = temp_dir.join("plugins");
+        let install = |dir: &str, manifest: &str, main: Option<&str>| {
+            let package_dir = plugins_dir.join(dir);
+            std::fs::create_dir_all(&package_dir).unwrap();
+            std::fs::write(package_dir.join("manifest.json"), manifest).unwrap();
+            if let Some(main) = main {
+                std::fs::write(package_dir.join(main), "").unwrap();
+            }
+        };
+        install("broken", "{ not json", None);
+        install("gone", r#"{"id": "gone", "name": "Gone", "version": "1.0.0", "main": "main.py"}"#, None);
+        install("weather", r#"{"id": "weather", "name": "Weather", "version": "1.0.0", "main": "index.js"}"#, Some("index.js"));
+        install("notes", r#"{"id": "notes", "name": "Notes", "version": "1.0.0", "main": "main.mjs"}"#, Some("main.mjs"));
+        install("echo", r#"{"id": "echo", "name": "Echo", "version": "1.0.0", "main": "main.py"}"#, Some("main.py"));
+        let bin = temp_dir.join("bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        std::fs::write(bin.join("python3"), "").unwrap();
+
+        let api = VoltPluginAPI::new(temp_dir.join("data"));
+        let config = PreflightConfig::new(&api)
+            .with_plugins_dir(&plugins_dir)
+            .with_trust_store(temp_dir.join("trusted_keys.json"))
+            .with_min_free_space(0)
+            .with_search_path(std::env::join_paths([&bin]).unwrap());
+        let report = PluginRegistry::new().preflight(&config);
+
+        assert!(!report.is_ok());
+        assert_eq!(report.of(PreflightCheck::Writable).count(), 0);
+        assert_eq!(report.of(PreflightCheck::Manifest).count(), 2);
+        let interpreters: Vec<&PreflightIssue> = report.of(PreflightCheck::Interpreter).collect();
+        assert_eq!(interpreters.len(), 1);
+        assert_eq!(interpreters[0].plugin_ids, vec!["notes", "weather"]);
+        assert!(interpreters[0].message.starts_with("node "));
+        assert_eq!(report.of(PreflightCheck::TrustStore).count(), 1);
+
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_crashes_are_recorded() {
+        use crate::crash::{CrashCause, CrashRecorder};
+
+        let temp_dir = std::env::temp_dir().join("volt_test_registry_crash");
+        let api = VoltPluginAPI::new(temp_dir.clone());
+        let registry = PluginRegistry::new();
+        registry
+            .register(Box::new(MockPlugin {
+                id: "ext".to_string(),
+                name: "External".to_string(),
+            }))
+            .unwrap();
+
+        let mut recorder = CrashRecorder::new();
+        recorder.stderr_line("thread 'main' panicked");
+        for reason in ["unreachable", "out of bounds memory access"] {
+            let report = recorder.report(
+                "ext",
+                CrashCause::Trap {
+                    reason: reason.to_string(),
+                },
+            );
+            let path = registry.record_crash(&api, report).unwrap();
+            assert!(path.starts_with(api.get_plugin_data_dir("ext").unwrap().join("crashes")));
+        }
+
+        let snapshot = registry.snapshot().unwrap();
+        assert_eq!(snapshot[0].crash_count, 2);
+        assert_eq!(
+            snapshot[0].last_crash.as_ref().map(|report| &report.cause),
+            Some(&CrashCause::Trap {
+                reason: "out of bounds memory access".to_string()
+            })
+        );
+        assert_eq!(registry.crash_count("other"), 0);
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn test_renamed_plugins_keep_their_state() {
+        let registry = PluginRegistry::new();
+        let mut manifest = PluginManifest {
+            id: "clipboard".to_string(),
+            uuid: Some("0b7d2c4e-8f1a-4c3b-9e6d-5a2f1b8c7d90".to_string()),
+            ..Default::default()
+        };
+        let plugin = |id: &str| {
+            Box::new(MockPlugin {
+                id: id.to_string(),
+                name: "Clipboard".to_string(),
+            })
+        };
+        registry.register_with_manifest(plugin("clipboard"), &manifest).unwrap();
+        registry.set_plugin_enabled("clipboard", false);
+        registry.set_fallback_order(vec!["web".to_string(), "clipboard".to_string()]);
+        registry.record_startup("clipboard", StartupPhase::Load, Duration::from_millis(3));
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        registry.subscribe(Arc::new(move |event: &RegistryEvent| sink.lock().unwrap().push(event.clone())));
+
+        manifest.id = "clipboard-history".to_string();
+        registry.register_with_manifest(plugin("clipboard-history"), &manifest).unwrap();
+
+        assert_eq!(registry.plugin_ids(), vec!["clipboard-history".to_string()]);
+        assert!(!registry.is_enabled("clipboard-history"));
+        assert_eq!(registry.fallback_order(), vec!["web", "clipboard-history"]);
+        assert_eq!(registry.startup_report().plugins[0].plugin_id, "clipboard-history");
+        assert!(events.lock().unwrap().contains(&RegistryEvent::PluginRenamed {
+            from: "clipboard".to_string(),
+            to: "clipboard-history".to_string(),
+        }));
+
+        manifest.uuid = Some("not-a-uuid".to_string());
+        assert!(registry.register_with_manifest(plugin("clipboard-history"), &manifest).is_err());
+    }
+
+    #[test]
+    fn test_unregister_plugin() {
+        let registry = PluginRegistry::new();
+        let plugin = Box::new(MockPlugin {
+            id: "test".to_string(),
+            name: "Test Plugin".to_string(),
+        });
+
+        registry.register(plugin).unwrap();
+        assert!(registry.unregister("test").is_ok());
+        assert!(!registry.has_plugin("test"));
+        assert_eq!(registry.count().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_stale_state_cleanup_is_reported() {
+        let temp_dir = std::env::temp_dir().join("volt_test_registry_cleanup");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(temp_dir.join("plugins/notes")).unwrap();
+        std::fs::write(temp_dir.join("plugins/notes/db.lock"), "not a pid").unwrap();
+
+        let api = VoltPluginAPI::new(temp_dir.clone());
+        let registry = PluginRegistry::new();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        registry.subscribe(Arc::new(move |event: &RegistryEvent| sink.lock().unwrap().push(event.clone())));
+
+        let report = registry.clean_stale_state(&api).unwrap();
+        assert_eq!(report.removed_locks, vec![temp_dir.join("plugins/notes/db.lock")]);
+        assert!(registry.clean_stale_state(&api).unwrap().is_empty());
+
+        registry
+            .register(Box::new(MockPlugin {
+                id: "notes".to_string(),
+                name: "Notes".to_string(),
+            }))
+            .unwrap();
+        registry.unregister("notes").unwrap();
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0], RegistryEvent::StaleStateCleaned { report });
+        assert_eq!(
+            serde_json::to_value(&events[2]).unwrap(),
+            serde_json::json!({"type": "pluginUnregistered", "pluginId": "notes"})
+        );
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+
+    // Plugin recording every configuration it receives
+    struct ConfigPlugin {
+        received: Arc<Mutex<Vec<serde_json::Value>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Plugin for ConfigPlugin {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn id(&self) -> &str {
+            "configurable"
+        }
+
+        fn name(&self) -> &str {
+            "Configurable Plugin"
+        }
+
+        fn description(&self) -> &str {
+            "Mock plugin reacting to configuration changes"
+        }
+
+        fn on_config_changed(&self, _config_name: &str, config: &serde_json::Value) -> Result<(), String> {
+            if config.get("invalid").is_some() {
+                return Err("invalid setting".to_string());
+            }
+            self.received.lock().unwrap().push(config.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_config_changes_are_debounced() {
+        let temp_dir = std::env::temp_dir().join("volt_test_registry_config");
+        let api = VoltPluginAPI::new(temp_dir.clone());
+        let registry = PluginRegistry::new();
+        let received = Arc::new(Mutex::new(Vec::new()));
+
+        registry
+            .register(Box::new(ConfigPlugin {
+                received: received.clone(),
+            }))
+            .unwrap();
+        registry
+            .route_config_changes(&api, Duration::from_millis(50))
+            .unwrap();
+
+        for volume in 0..5 {
+            api.save_config("configurable", "settings", &serde_json::json!({ "volume": volume }))
+                .unwrap();
+        }
+        std::thread::sleep(Duration::from_millis(300));
+
+        assert_eq!(*received.lock().unwrap(), vec![serde_json::json!({ "volume": 4 })]);
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn test_config_errors_are_captured() {
+        let registry = PluginRegistry::new();
+        registry
+            .register(Box::new(ConfigPlugin {
+                received: Arc::new(Mutex::new(Vec::new())),
+            }))
+            .unwrap();
+
+        let invalid = serde_json::json!({ "invalid": true });
+        assert!(registry.notify_config_changed("configurable", "settings", &invalid).is_err());
+        assert_eq!(registry.last_config_error("configurable").as_deref(), Some("invalid setting"));
+
+        let valid = serde_json::json!({ "volume": 1 });
+        assert!(registry.notify_config_changed("configurable", "settings", &valid).is_ok());
+        assert!(registry.last_config_error("configurable").is_none());
+        assert!(registry.notify_config_changed("missing", "settings", &valid).is_err());
+    }
+
+    // Plugin keeping a clipboard history, paused in privacy mode
+    struct ClipboardPlugin {
+        paused: Arc<Mutex<bool>>,
+        purged_since: Arc<Mutex<Option<u64>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Plugin for ClipboardPlugin {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn id(&self) -> &str {
+            "clipboard"
+        }
+
+        fn name(&self) -> &str {
+            "Clipboard History"
+        }
+
+        fn description(&self) -> &str {
+            "Mock plugin recording clipboard contents"
+        }
+
+        fn on_privacy_mode_changed(&self, enabled: bool) {
+            *self.paused.lock().unwrap() = enabled;
+        }
+
+        fn purge_recorded_since(&self, since: u64) -> Result<(), String> {
+            *self.purged_since.lock().unwrap() = Some(since);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_privacy_mode_pauses_recording() {
+        let temp_dir = std::env::temp_dir().join("volt_test_registry_privacy");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let api = VoltPluginAPI::new(temp_dir.clone());
+        let registry = PluginRegistry::new();
+        let paused = Arc::new(Mutex::new(false));
+        let purged_since = Arc::new(Mutex::new(None));
+
+        registry
+            .register(Box::new(ClipboardPlugin {
+                paused: paused.clone(),
+                purged_since: purged_since.clone(),
+            }))
+            .unwrap();
+        registry.route_privacy_changes(&api).unwrap();
+
+        let result = crate::result::PluginResult::new("sum", "4");
+        api.record_action("calc", &result).unwrap();
+
+        api.set_privacy_mode(true);
+        assert!(api.privacy_mode());
+        assert!(*paused.lock().unwrap());
+        api.record_action("calc", &crate::result::PluginResult::new("secret", "private")).unwrap();
+        assert_eq!(api.recent_actions(10).unwrap().len(), 1);
+
+        api.set_privacy_mode(false);
+        assert!(!*paused.lock().unwrap());
+
+        registry.purge_recorded_since(&api, 0).unwrap();
+        assert!(api.recent_actions(10).unwrap().is_empty());
+        assert_eq!(*purged_since.lock().unwrap(), Some(0));
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+
+    // Plugin logging its lifecycle hooks into a shared journal
+    struct LifecyclePlugin {
+        id: &'static str,
+        journal: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Plugin for LifecyclePlugin {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn id(&self) -> &str {
+            self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id
+        }
+
+        fn description(&self) -> &str {
+            "Mock plugin logging lifecycle hooks"
+        }
+
+        fn on_enabled(&self) -> Result<(), String> {
+            self.journal.lock().unwrap().push(format!("enable {}", self.id));
+            Ok(())
+        }
+
+        fn on_disabled(&self) -> Result<(), String> {
+            self.journal.lock().unwrap().push(format!("disable {}", self.id));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_profile_switch_disables_before_enabling() {
+        let registry = PluginRegistry::new();
+        let journal = Arc::new(Mutex::new(Vec::new()));
+        for id in ["github", "terminal", "thesaurus"] {
+            registry
+                .register(Box::new(LifecyclePlugin {
+                    id,
+                    journal: journal.clone(),
+                }))
+                .unwrap();
+        }
+
+        registry
+            .define_bundle(
+                PluginBundle::new("dev", "Dev tools")
+                    .with_plugin("github")
+                    .with_plugin("terminal")
+                    .bound_to("work"),
+            )
+            .unwrap();
+        registry
+            .define_bundle(PluginBundle::new("writing", "Writing").bound_to("home"))
+            .unwrap();
+        registry.add_to_bundle("writing", "thesaurus").unwrap();
+
+        registry.apply_profile("work");
+        assert!(!registry.is_enabled("thesaurus"));
+        assert_eq!(registry.enabled_count().unwrap(), 2);
+
+        journal.lock().unwrap().clear();
+        registry.apply_profile("home");
+        assert_eq!(
+            *journal.lock().unwrap(),
+            vec!["disable terminal", "disable github", "enable thesaurus"]
+        );
+        assert_eq!(registry.snapshot().unwrap()[0].status, PluginStatus::Disabled);
+    }
+
+    /// Plugin answering queries that start with, or complete, its keyword
+    struct KeywordPlugin {
+        id: &'static str,
+        panics: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl Plugin for KeywordPlugin {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn id(&self) -> &str {
+            self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id
+        }
+
+        fn description(&self) -> &str {
+            "Keyword plugin for testing"
+        }
+
+        fn can_handle(&self, context: &QueryContext) -> bool {
+            context.query.starts_with(self.id) || self.id.starts_with(context.query.as_str())
+        }
+
+        async fn match_query(&self, context: &QueryContext) -> Result<Vec<PluginResult>, String> {
+            if self.panics {
+                panic!("match_query failed");
+            }
+            Ok(vec![PluginResult::new(context.query.clone(), self.id)])
+        }
+
+        async fn execute(&self, _result: &PluginResult) -> Result<ExecuteOutcome, String> {
+            Err(format!("{} executed", self.id))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_query_and_execute() {
+        let usage = UsageStats::new();
+        let registry = PluginRegistry::new().with_usage_stats(usage.clone());
+        registry.register(Box::new(KeywordPlugin { id: "calc", panics: false })).unwrap();
+        registry.register(Box::new(KeywordPlugin { id: "calendar", panics: true })).unwrap();
+        registry.register(Box::new(KeywordPlugin { id: "files", panics: false })).unwrap();
+        let scheduler = DispatchScheduler::default();
+        let aggregator = ResultAggregator::new();
+
+        // "calendar" also matches but panics, and is left out
+        let merged = registry
+            .dispatch_query(&QueryContext::new("cal"), &scheduler, &aggregator)
+            .await;
+        assert_eq!(merged.results.len(), 1);
+        assert_eq!(merged.results[0].plugin_id.as_deref(), Some("calc"));
+        assert_eq!(scheduler.metrics("calendar").unwrap().in_flight, 0);
+
+        let result = &merged.results[0];
+        assert_eq!(registry.execute("calc", result).await.unwrap_err(), "calc executed");
+        let hour = Duration::from_secs(3600);
+        let calc = usage.stats("calc", hour);
+        assert_eq!((calc.queries, calc.answered, calc.selections, calc.execute_errors), (1, 1, 1, 1));
+        assert_eq!(usage.stats("calendar", hour).query_errors, 1);
+
+        registry.set_plugin_enabled("calc", false);
+        assert!(registry.execute("calc", result).await.is_err());
+        assert!(registry
+            .dispatch_query(&QueryContext::new("calc 2+2"), &scheduler, &aggregator)
+            .await
+            .results
+            .is_empty());
+    }
+
+    /// Download list whose progress changes while it is shown
+    struct Downloads {
+        progress: Arc<std::sync::atomic::AtomicU32>,
+    }
+
+    #[async_trait::async_trait]
+    impl Plugin for Downloads {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn id(&self) -> &str {
+            "downloads"
+        }
+
+        fn name(&self) -> &str {
+            "Downloads"
+        }
+
+        fn description(&self) -> &str {
+            "Download list for testing"
+        }
+
+        fn can_handle(&self, _context: &QueryContext) -> bool {
+            true
+        }
+
+        async fn match_query(&self, _context: &QueryContext) -> Result<Vec<PluginResult>, String> {
+            let progress = self.progress.load(std::sync::atomic::Ordering::SeqCst);
+            if progress > 100 {
+                return Err("download list unavailable".to_string());
+            }
+            Ok(vec![PluginResult {
+                score: 50,
+                ..PluginResult::new("iso", format!("debian.iso {}%", progress))
+            }])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh_plugin_results() {
+        use crate::diff::{DiffDecoder, DiffEncoder, DiffOp};
+
+        let registry = PluginRegistry::new();
+        let progress = Arc::new(std::sync::atomic::AtomicU32::new(10));
+        registry.register(Box::new(Downloads { progress: progress.clone() })).unwrap();
+        registry.register(Box::new(KeywordPlugin { id: "d", panics: false })).unwrap();
+        let aggregator = ResultAggregator::new();
+        let context = QueryContext::new("d");
+        let view = registry
+            .dispatch_query(&context, &DispatchScheduler::default(), &aggregator)
+            .await;
+        assert_eq!(view.results.len(), 2);
+
+        let mut encoder = DiffEncoder::new();
+        let mut decoder = DiffDecoder::new();
+        decoder.apply(&encoder.encode(&view.results)).unwrap();
+
+        // Only the download row is sent again
+        progress.store(60, std::sync::atomic::Ordering::SeqCst);
+        let refreshed = registry.refresh_plugin("downloads", &context, &aggregator, &view).await.unwrap();
+        let diff = encoder.encode(&refreshed.results);
+        assert!(matches!(&diff.ops[..], [DiffOp::Update { result }] if result.title == "debian.iso 60%"));
+        decoder.apply(&diff).unwrap();
+        assert_eq!(decoder.results(), &refreshed.results[..]);
+        assert_eq!(refreshed.results[0].shortcut, view.results[0].shortcut);
+
+        // Failures keep the view, disabled plugins leave it
+        progress.store(101, std::sync::atomic::Ordering::SeqCst);
+        assert!(registry.refresh_plugin("downloads", &context, &aggregator, &refreshed).await.is_err());
+        assert!(registry.refresh_plugin("missing", &context, &aggregator, &refreshed).await.is_err());
+        registry.set_plugin_enabled("downloads", false);
+        let removed = registry.refresh_plugin("downloads", &context, &aggregator, &refreshed).await.unwrap();
+        assert_eq!(removed.results.len(), 1);
+        assert_eq!(removed.results[0].plugin_id.as_deref(), Some("d"));
+    }
+
+    /// Process monitor expanding rows with Right, releasing focus with Left
+    struct ProcessMonitor;
+
+    #[async_trait::async_trait]
+    impl Plugin for ProcessMonitor {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn id(&self) -> &str {
+            "ps"
+        }
+
+        fn name(&self) -> &str {
+            "Processes"
+        }
+
+        fn description(&self) -> &str {
+            "Process monitor for testing"
+        }
+
+        async fn execute(&self, result: &PluginResult) -> Result<ExecuteOutcome, String> {
+            match result.id.as_str() {
+                "monitor" => Ok(ExecuteOutcome::requery("ps ").capture_keys()),
+                _ => Ok(ExecuteOutcome::keep_open()),
+            }
+        }
+
+        async fn on_key_event(&self, event: &KeyEvent, _context: &QueryContext) -> Result<KeyResponse, String> {
+            match event.key {
+                Key::Right => Ok(KeyResponse::Update {
+                    results: vec![PluginResult::new("child", "firefox (content)")],
+                }),
+                Key::Left => Ok(KeyResponse::Release),
+                Key::Tab => panic!("tab"),
+                _ => Ok(KeyResponse::Ignored),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_key_focus_handoff() {
+        let registry = PluginRegistry::new();
+        registry.register(Box::new(ProcessMonitor)).unwrap();
+        let context = QueryContext::new("ps ");
+        let right = KeyEvent::new(Key::Right);
+
+        // Nobody owns the launcher's own views
+        assert_eq!(registry.dispatch_key_event(&right, &context).await, KeyResponse::Ignored);
+
+        let monitor = PluginResult::new("monitor", "Process monitor");
+        registry.execute("ps", &monitor).await.unwrap();
+        assert_eq!(registry.key_focus().as_deref(), Some("ps"));
+        assert!(registry.dispatch_key_event(&right, &context).await.is_handled());
+        assert!(!registry.dispatch_key_event(&KeyEvent::new(Key::Down), &context).await.is_handled());
+
+        // Staying in the view keeps focus, reserved keys never reach the plugin
+        registry.execute("ps", &PluginResult::new("copy", "Copy PID")).await.unwrap();
+        assert_eq!(registry.key_focus().as_deref(), Some("ps"));
+        assert_eq!(
+            registry.dispatch_key_event(&KeyEvent::new(Key::Escape), &context).await,
+            KeyResponse::Ignored
+        );
+        assert_eq!(registry.key_focus(), None);
+
+        registry.execute("ps", &monitor).await.unwrap();
+        assert_eq!(
+            registry.dispatch_key_event(&KeyEvent::new(Key::Left), &context).await,
+            KeyResponse::Release
+        );
+        assert_eq!(registry.key_focus(), None);
+
+        // A panicking plugin loses focus
+        registry.execute("ps", &monitor).await.unwrap();
+        assert_eq!(
+            registry.dispatch_key_event(&KeyEvent::new(Key::Tab), &context).await,
+            KeyResponse::Ignored
+        );
+        assert_eq!(registry.key_focus(), None);
+
+        registry.execute("ps", &monitor).await.unwrap();
+        registry.set_plugin_enabled("ps", false);
+        assert_eq!(registry.dispatch_key_event(&right, &context).await, KeyResponse::Ignored);
+        assert_eq!(registry.key_focus(), None);
+    }
+
+    struct Redactor;
+
+    impl DispatchMiddleware for Redactor {
+        fn name(&self) -> &str {
+            "redactor"
+        }
+
+        fn before_routing(&self, context: &mut QueryContext) -> crate::middleware::BeforeRouting {
+            if context.query.contains("password") {
+                let blocked = PluginResult::new("blocked", "Searching for passwords is blocked");
+                return crate::middleware::BeforeRouting::Respond(vec![blocked]);
+            }
+            context.query = context.query.replace("darn", "");
+            crate::middleware::BeforeRouting::Continue
+        }
+
+        fn after_merge(&self, _context: &QueryContext, merged: &mut MergedResults) {
+            for result in &mut merged.results {
+                result.id = result.id.replace("secret", "******");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_middleware() {
+        let registry = PluginRegistry::new();
+        registry.register(Box::new(KeywordPlugin { id: "calc", panics: false })).unwrap();
+        registry.add_middleware(Arc::new(Redactor), 0);
+        let scheduler = DispatchScheduler::default();
+        let aggregator = ResultAggregator::new();
+
+        let merged = registry
+            .dispatch_query(&QueryContext::new("darncalc secret"), &scheduler, &aggregator)
+            .await;
+        assert_eq!(merged.results.len(), 1);
+        assert_eq!(merged.results[0].id, "calc ******");
+
+        let merged = registry
+            .dispatch_query(&QueryContext::new("calc password"), &scheduler, &aggregator)
+            .await;
+        assert_eq!(merged.results[0].plugin_id.as_deref(), Some("redactor"));
+        assert!(scheduler.metrics("calc").is_some_and(|metrics| metrics.dispatched == 1));
+
+        assert!(registry.remove_middleware("redactor"));
+        assert!(registry.middleware_names().is_empty());
+    }
+
+    /// Never answers, recording when its query is dropped
+    struct StuckPlugin {
+        dropped: Arc<std::sync::atomic::AtomicBool>,
+    }
+
+    struct DropFlag(Arc<std::sync::atomic::AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Plugin for StuckPlugin {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn id(&self) -> &str {
+            "stuck"
+        }
+
+        fn name(&self) -> &str {
+            "Stuck"
+        }
+
+        fn description(&self) -> &str {
+            "Plugin that never answers"
+        }
+
+        fn can_handle(&self, _context: &QueryContext) -> bool {
+            true
+        }
+
+        async fn match_query(&self, _context: &QueryContext) -> Result<Vec<PluginResult>, String> {
+            let _flag = DropFlag(self.dropped.clone());
+            std::future::pending().await
+        }
+
+        async fn execute(&self, _result: &PluginResult) -> Result<ExecuteOutcome, String> {
+            Ok(ExecuteOutcome::default())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_dispatch_drops_plugin_queries() {
+        let registry = PluginRegistry::new();
+        let dropped = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        registry.register(Box::new(StuckPlugin { dropped: dropped.clone() })).unwrap();
+        registry.register(Box::new(KeywordPlugin { id: "calc", panics: false })).unwrap();
+        let scheduler = DispatchScheduler::default();
+        let aggregator = ResultAggregator::new();
+
+        let token = crate::cancel::CancellationToken::new();
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            canceller.cancel();
+        });
+        let context = QueryContext::new("calc").with_cancellation(token);
+        let merged = tokio::time::timeout(
+            Duration::from_secs(5),
+            registry.dispatch_query(&context, &scheduler, &aggregator),
+        )
+        .await
+        .unwrap();
+
+        // Plugins that answered in time are kept
+        assert_eq!(merged.results.len(), 1);
+        assert!(dropped.load(std::sync::atomic::Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_dispatch_leaves_out_late_plugins() {
+        let registry = PluginRegistry::new();
+        let dropped = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        registry.register(Box::new(StuckPlugin { dropped: dropped.clone() })).unwrap();
+        registry.register(Box::new(KeywordPlugin { id: "calc", panics: false })).unwrap();
+        let scheduler = DispatchScheduler::default();
+        let aggregator = ResultAggregator::new();
+        let controller = AdaptiveController::default();
+
+        let budget = controller.budget(crate::dispatch::DispatchPass::Quick);
+        let merged = tokio::time::timeout(
+            Duration::from_secs(5),
+            registry.dispatch_adaptive(&QueryContext::new("calc"), &scheduler, &aggregator, &controller, budget),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(merged.results.len(), 1);
+        assert!(dropped.load(std::sync::atomic::Ordering::SeqCst));
+        let metrics = controller.metrics();
+        assert_eq!(metrics.quick_passes, 1);
+        assert_eq!(metrics.deadline_misses, 1);
+    }
+
+    /// Serves the files of an archive as virtual resources
+    struct ArchivePlugin;
+
+    #[async_trait::async_trait]
+    impl crate::extensions::ResourceResolver for ArchivePlugin {
+        async fn resolve(&self, path: &VirtualPath) -> Result<ResolvedResource, String> {
+            match path.path.as_str() {
+                "backup.zip/cover.png" => Ok(ResolvedResource::Content {
+                    bytes: b"png".to_vec(),
+                    mime: Some("image/png".to_string()),
+                }),
+                _ => Err(format!("No such entry: {}", path.path)),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl crate::extensions::Previewer for ArchivePlugin {
+        async fn preview(&self, _result: &PluginResult) -> Result<Preview, String> {
+            Ok(Preview::Image {
+                path: "voltres://archives/backup.zip/cover.png".to_string(),
+            })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Plugin for ArchivePlugin {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn id(&self) -> &str {
+            "archives"
+        }
+
+        fn name(&self) -> &str {
+            "Archives"
+        }
+
+        fn description(&self) -> &str {
+            "Searches inside archives"
+        }
+
+        fn as_previewer(&self) -> Option<&dyn crate::extensions::Previewer> {
+            Some(self)
+        }
+
+        fn as_resource_resolver(&self) -> Option<&dyn crate::extensions::ResourceResolver> {
+            Some(self)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_virtual_resources() {
+        let temp_dir = std::env::temp_dir().join("volt_test_registry_resources");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let api = VoltPluginAPI::new(temp_dir.clone());
+        let registry = PluginRegistry::new();
+        registry.register(Box::new(ArchivePlugin)).unwrap();
+
+        let file = registry
+            .materialize_resource(&api, "voltres://archives/backup.zip/cover.png")
+            .await
+            .unwrap();
+        assert!(file.starts_with(api.get_plugin_cache_dir("archives").unwrap()));
+        assert!(file.ends_with("backup.zip/cover.png"));
+        assert_eq!(std::fs::read(&file).unwrap(), b"png");
+
+        let result = PluginResult::new("cover", "cover.png");
+        let preview = registry.preview(&api, "archives", &result).await.unwrap().unwrap();
+        assert_eq!(preview, Preview::Image { path: file.to_string_lossy().to_string() });
+
+        assert!(registry.resolve_resource("voltres://archives/missing.txt").await.is_err());
+        assert!(registry.resolve_resource("voltres://unknown/a.txt").await.is_err());
+        let missing = PluginResult::new("missing", "missing.txt").with_intent(ResultIntent::OpenFile {
+            path: "voltres://archives/missing.txt".to_string(),
+        });
+        assert!(registry.execute_intent(&api, &missing).await.unwrap().unwrap_err().contains("No such entry"));
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+
+    struct GitAnnotator {
+        id: &'static str,
+        delay: Duration,
+        panics: bool,
+    }
+
+    impl crate::extensions::Annotator for GitAnnotator {
+        fn annotate(&self, results: &[PluginResult]) -> Vec<crate::annotations::Annotation> {
+            std::thread::sleep(self.delay);
+            if self.panics {
+                panic!("annotate failed");
+            }
+            results
+                .iter()
+                .enumerate()
+                .filter(|(_, result)| result.title == "volt")
+                .map(|(index, _)| {
+                    crate::annotations::Annotation::new(index)
+                        .with_tag(self.id)
+                        .with_accessory(crate::result::Accessory::badge("main"))
+                })
+                .collect()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Plugin for GitAnnotator {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn id(&self) -> &str {
+            self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id
+        }
+
+        fn description(&self) -> &str {
+            "Annotator for testing"
+        }
+
+        fn can_handle(&self, _context: &QueryContext) -> bool {
+            false
+        }
+
+        async fn match_query(&self, _context: &QueryContext) -> Result<Vec<PluginResult>, String> {
+            Ok(Vec::new())
+        }
+
+        async fn execute(&self, _result: &PluginResult) -> Result<ExecuteOutcome, String> {
+            Ok(ExecuteOutcome::default())
+        }
+
+        fn as_annotator(&self) -> Option<&dyn crate::extensions::Annotator> {
+            Some(self)
+        }
+    }
+
+    #[test]
+    fn test_annotate_within_budget() {
+        let registry = PluginRegistry::new();
+        for (id, delay, panics) in [
+            ("git", Duration::ZERO, false),
+            ("broken", Duration::ZERO, true),
+            ("slow", Duration::from_secs(2), false),
+        ] {
+            registry.register(Box::new(GitAnnotator { id, delay, panics })).unwrap();
+        }
+        let mut merged = MergedResults {
+            results: vec![PluginResult::new("home", "home"), PluginResult::new("volt", "volt")],
+            ..Default::default()
+        };
+
+        let started = Instant::now();
+        let report = registry.annotate(&mut merged, Duration::from_millis(200));
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        assert_eq!(report.annotated, vec!["git"]);
+        assert_eq!(report.failed, vec!["broken"]);
+        assert_eq!(report.timed_out, vec!["slow"]);
+        assert!(merged.results[0].tags.is_empty());
+        assert_eq!(merged.results[1].tags, vec!["git"]);
+        assert_eq!(merged.results[1].accessories, vec![crate::result::Accessory::badge("main")]);
+    }
+
+    struct FallbackPlugin {
+        id: &'static str,
+        panics: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl Plugin for FallbackPlugin {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn id(&self) -> &str {
+            self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id
+        }
+
+        fn description(&self) -> &str {
+            "Fallback plugin for testing"
+        }
+
+        fn fallback_results(&self, context: &QueryContext) -> Vec<PluginResult> {
+            if self.panics {
+                panic!("fallback_results failed");
+            }
+            vec![PluginResult::new("fallback", format!("{} '{}'", self.id, context.query))]
+        }
+    }
+
+    /// Explains why it has no results, and has some when `answers`
+    struct WhyNotPlugin {
+        id: &'static str,
+        answers: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl Plugin for WhyNotPlugin {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn id(&self) -> &str {
+            self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id
+        }
+
+        fn description(&self) -> &str {
+            "Plugin explaining its empty answers for testing"
+        }
+
+        fn can_handle(&self, _context: &QueryContext) -> bool {
+            true
+        }
+
+        async fn match_query(&self, context: &QueryContext) -> Result<Vec<PluginResult>, String> {
+            context.why_not(NoResultsReason::AuthExpired, "token expired");
+            if self.answers {
+                return Ok(vec![PluginResult::new("ticket", "VOLT-1")]);
+            }
+            Ok(Vec::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_no_results_notes_reach_dev_mode_only() {
+        let registry = PluginRegistry::new();
+        for (id, answers) in [("jira", false), ("linear", false), ("github", true)] {
+            registry.register(Box::new(WhyNotPlugin { id, answers })).unwrap();
+            registry.set_dev_mode(id, id != "linear");
+        }
+
+        let merged = registry
+            .dispatch_query(&QueryContext::new("bug"), &DispatchScheduler::default(), &ResultAggregator::new())
+            .await;
+
+        // github answered, linear isn't in dev mode
+        assert_eq!(merged.results.len(), 1);
+        assert_eq!(
+            merged.diagnostics,
+            vec![NoResultsNote {
+                plugin_id: "jira".to_string(),
+                reason: NoResultsReason::AuthExpired,
+                detail: "token expired".to_string(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fallbacks_only_on_empty_merges() {
+        let registry = PluginRegistry::new();
+        registry.register(Box::new(KeywordPlugin { id: "calc", panics: false })).unwrap();
+        for (id, panics) in [("web", false), ("notes", false), ("broken", true)] {
+            registry.register(Box::new(FallbackPlugin { id, panics })).unwrap();
+        }
+        let scheduler = DispatchScheduler::default();
+        let aggregator = ResultAggregator::new();
+
+        let merged = registry
+            .dispatch_query(&QueryContext::new("calc 2+2"), &scheduler, &aggregator)
+            .await;
+        assert_eq!(merged.results.len(), 1);
+        assert_eq!(merged.results[0].plugin_id.as_deref(), Some("calc"));
+
+        // Unordered providers are listed by ID
+        let titles = |merged: &MergedResults| -> Vec<String> {
+            merged.results.iter().map(|result| result.title.to_string()).collect()
+        };
+        let merged = registry
+            .dispatch_query(&QueryContext::new("groceries"), &scheduler, &aggregator)
+            .await;
+        assert_eq!(titles(&merged), vec!["notes 'groceries'", "web 'groceries'"]);
+        assert_eq!(merged.results[0].plugin_id.as_deref(), Some("notes"));
+        assert_eq!(merged.results[0].shortcut, Some(1));
+
+        registry.set_fallback_order(vec!["web".to_string()]);
+        assert_eq!(registry.fallback_order(), vec!["web"]);
+        let merged = registry
+            .dispatch_query(&QueryContext::new("groceries"), &scheduler, &aggregator)
+            .await;
+        assert_eq!(titles(&merged), vec!["web 'groceries'", "notes 'groceries'"]);
+
+        registry.set_plugin_enabled("web", false);
+        let merged = registry.fallback_results(&QueryContext::new("groceries"), &aggregator);
+        assert_eq!(titles(&merged), vec!["notes 'groceries'"]);
+        assert!(registry
+            .fallback_results(&QueryContext::new("  "), &aggregator)
+            .results
+            .is_empty());
+    }
+
+    struct SyncPlugin {
+        valid: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl crate::extensions::SettingsProvider for SyncPlugin {
+        fn settings_pages(&self) -> Vec<SettingsPage> {
+            use crate::settings::{SettingsField, SettingsSection};
+
+            let section = SettingsSection::new("server", "Server")
+                .with_field(SettingsField::toggle("enabled", "Sync", false))
+                .with_field(SettingsField::button("test", "Test", "testConnection").visible_when("enabled", true));
+            let section = if self.valid {
+                section
+            } else {
+                section.with_field(SettingsField::toggle("enabled", "Duplicate", true))
+            };
+            vec![SettingsPage::new("sync", "Sync").with_section(section)]
+        }
+
+        async fn settings_action(
+            &self,
+            _page_id: &str,
+            action: &str,
+            _values: &serde_json::Value,
+        ) -> Result<Option<Toast>, String> {
+            Ok(Some(Toast::success(format!("{} succeeded", action))))
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Plugin for SyncPlugin {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn id(&self) -> &str {
+            if self.valid {
+                "sync"
+            } else {
+                "broken-sync"
+            }
+        }
+
+        fn name(&self) -> &str {
+            "Sync"
+        }
+
+        fn description(&self) -> &str {
+            "Settings pages plugin for testing"
+        }
+
+        fn as_settings_provider(&self) -> Option<&dyn crate::extensions::SettingsProvider> {
+            Some(self)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_settings_pages() {
+        let registry = PluginRegistry::new();
+        registry.register(Box::new(SyncPlugin { valid: true })).unwrap();
+        registry.register(Box::new(SyncPlugin { valid: false })).unwrap();
+        registry
+            .register(Box::new(MockPlugin {
+                id: "calc".to_string(),
+                name: "Calculator".to_string(),
+            }))
+            .unwrap();
+
+        assert_eq!(registry.settings_pages("sync").unwrap().len(), 1);
+        assert!(registry.settings_pages("calc").unwrap().is_empty());
+        assert!(registry.settings_pages("broken-sync").is_err());
+        assert!(registry.settings_page("sync", "missing").is_err());
+
+        // The button is hidden until sync is enabled
+        let hidden = registry
+            .settings_action("sync", "sync", "testConnection", &serde_json::json!({}))
+            .await;
+        assert!(hidden.is_err());
+        let toast = registry
+            .settings_action("sync", "sync", "testConnection", &serde_json::json!({"enabled": true}))
+            .await
+            .unwrap();
+        assert_eq!(toast, Some(Toast::success("testConnection succeeded")));
+    }
+
+    #[test]
+    fn test_isolated_registries_side_by_side() {
+        use crate::api::{LogLevel, VoltPluginAPI};
+        use crate::logging::{self, Diagnostic, DiagnosticsSink};
+        use crate::matching::Tokenizer;
+
+        struct Tenant {
+            api: VoltPluginAPI,
+            registry: PluginRegistry,
+            logs: Arc<Mutex<Vec<String>>>,
+            events: Arc<Mutex<Vec<RegistryEvent>>>,
+            refreshed: Arc<Mutex<Vec<String>>>,
+        }
+
+        fn collector(lines: &Arc<Mutex<Vec<String>>>) -> Arc<dyn DiagnosticsSink> {
+            let collected = lines.clone();
+            Arc::new(move |diagnostic: &Diagnostic<'_>| {
+                collected
+                    .lock()
+                    .unwrap()
+                    .push(format!("{}: {}", diagnostic.source, diagnostic.message));
+            })
+        }
+
+        // Anything reaching the process-wide sink escaped a tenant
+        let _settings = logging::tests::PROCESS_SETTINGS
+            .lock()
+            .unwrap_or_else(|p| p.into_inner());
+        let process_wide = Arc::new(Mutex::new(Vec::new()));
+        logging::set_sink(collector(&process_wide));
+
+        // Both hosts start concurrently, registering the same plugin ID
+        let tenants: Vec<Tenant> = std::thread::scope(|scope| {
+            let handles = ["first", "second"].map(|name| {
+                scope.spawn(move || {
+                    let temp_dir = std::env::temp_dir().join(format!("volt_test_tenant_{}", name));
+                    let _ = std::fs::remove_dir_all(&temp_dir);
+
+                    let logs = Arc::new(Mutex::new(Vec::new()));
+                    let tokenizer = Tokenizer::new().with_camel_case(name == "second");
+                    let api = VoltPluginAPI::builder()
+                        .with_app_data_dir(temp_dir)
+                        .with_log_sink(collector(&logs))
+                        .with_quiet_logs(true)
+                        .with_tokenizer(tokenizer)
+                        .with_volt_version(format!("{}.0.0", name.len()))
+                        .build()
+                        .unwrap();
+                    let registry = PluginRegistry::new()
+                        .with_diagnostics(api.diagnostics())
+                        .with_usage_stats(api.usage_stats());
+
+                    let events = Arc::new(Mutex::new(Vec::new()));
+                    let received = events.clone();
+                    registry.subscribe(Arc::new(move |event: &RegistryEvent| {
+                        received.lock().unwrap().push(event.clone());
+                    }));
+                    let refreshed = Arc::new(Mutex::new(Vec::new()));
+                    let requested = refreshed.clone();
+                    api.subscribe_refresh_requests(Arc::new(move |plugin_id: &str| {
+                        requested.lock().unwrap().push(plugin_id.to_string());
+                    }))
+                    .unwrap();
+
+                    registry
+                        .register(Box::new(MockPlugin {
+                            id: "notes".to_string(),
+                            name: format!("Notes ({})", name),
+                        }))
+                        .unwrap();
+                    api.kv_set("notes", "tenant", serde_json::json!(name)).unwrap();
+                    api.log("notes", LogLevel::Info, &format!("hello from {}", name));
+
+                    Tenant {
+                        api,
+                        registry,
+                        logs,
+                        events,
+                        refreshed,
+                    }
+                })
+            });
+            handles.map(|handle| handle.join().unwrap()).into_iter().collect()
+        });
+        let [first, second] = &tenants[..] else {
+            unreachable!()
+        };
+
+        first.registry.set_plugin_enabled("notes", false);
+        second.api.refresh_results("notes").unwrap();
+        second.registry.unregister("notes").unwrap();
+        logging::clear_sink();
+
+        assert!(!first.registry.is_enabled("notes"));
+        assert_eq!(first.registry.list_plugins().unwrap()[0].name, "Notes (first)");
+        assert!(!second.registry.has_plugin("notes"));
+
+        assert_eq!(first.api.kv_get("notes", "tenant").unwrap(), Some(serde_json::json!("first")));
+        assert_eq!(second.api.kv_get("notes", "tenant").unwrap(), Some(serde_json::json!("second")));
+        assert_eq!(first.api.recent_logs("notes", 10).len(), 1);
+        assert!(first.api.recent_logs("notes", 10)[0].ends_with("hello from first"));
+
+        for (tenant, name, other) in [(first, "first", "second"), (second, "second", "first")] {
+            let logs = tenant.logs.lock().unwrap();
+            assert!(logs.contains(&format!("notes: hello from {}", name)), "{:?}", logs);
+            assert!(logs.contains(&format!("registry: Plugin registered: Notes ({}) (notes)", name)), "{:?}", logs);
+            assert!(!logs.iter().any(|line| line.contains(other)), "{:?}", logs);
+        }
+        assert!(second.logs.lock().unwrap().contains(&"registry: Plugin unregistered: notes".to_string()));
+        let escaped: Vec<String> = process_wide
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|line| line.contains("Notes (") || line.contains("hello from"))
+            .cloned()
+            .collect();
+        assert!(escaped.is_empty(), "{:?}", escaped);
+
+        let registered = RegistryEvent::PluginRegistered {
+            plugin_id: "notes".to_string(),
+        };
+        let disabled = RegistryEvent::PluginDisabled {
+            plugin_id: "notes".to_string(),
+        };
+        let unregistered = RegistryEvent::PluginUnregistered {
+            plugin_id: "notes".to_string(),
+        };
+        assert_eq!(*first.events.lock().unwrap(), vec![registered.clone(), disabled]);
+        assert_eq!(*second.events.lock().unwrap(), vec![registered, unregistered]);
+        assert!(first.refreshed.lock().unwrap().is_empty());
+        assert_eq!(*second.refreshed.lock().unwrap(), vec!["notes".to_string()]);
+
+        assert!(!first.api.tokenizer().word_starts("fooBar").contains(&3));
+        assert!(second.api.tokenizer().word_starts("fooBar").contains(&3));
+        assert_eq!(first.api.get_volt_version(), "5.0.0");
+        assert_eq!(second.api.get_volt_version(), "6.0.0");
+
+        for name in ["first", "second"] {
+            let _ = std::fs::remove_dir_all(std::env::temp_dir().join(format!("volt_test_tenant_{}", name)));
+        }
     }
 }
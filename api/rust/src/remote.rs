@@ -0,0 +1,993 @@
+/// Remote plugin hosts over WebSocket
+///
+/// Lets plugins run on another machine (e.g., a work server exposing internal
+/// tools). The launcher connects to a WebSocket endpoint speaking the JSON-RPC
+/// plugin protocol; `wss://` endpoints use TLS and an optional bearer token is
+/// sent during the handshake. Lost connections are re-established in the
+/// background with exponential backoff, and request timeouts adapt to the
+/// measured round-trip latency. Messages are size-capped and, when the host
+/// agrees during the handshake, compressed (see `crate::wire`). Calls
+/// abandoned before their response arrives, because the query was cancelled
+/// or timed out, are cancelled on the host with a `cancel` message.
+///
+/// Once connected, `handshake` negotiates the API version and protocol
+/// features with the host (see `crate::protocol`) and lists its plugins.
+use crate::cancel::{CancellationToken, CANCELLED};
+use crate::logging::Diagnostics;
+use crate::keys::{KeyEvent, KeyResponse};
+use crate::outcome::ExecuteOutcome;
+use crate::plugin::{Plugin, QueryContext};
+use crate::compat::ApiVersion;
+use crate::protocol::{
+    error_codes, methods, negotiate, HandshakeRequest, HandshakeResponse, NegotiatedProtocol, ProtocolFeature,
+    RemotePluginInfo, RpcRequest, RpcResponse,
+};
+use crate::result::PluginResult;
+use crate::wire::{Compression, Frame, WireCodec, WireLimits, COMPRESSION_HEADER};
+use async_trait::async_trait;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, oneshot, Notify};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::header::AUTHORIZATION;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+/// Connection settings for a remote plugin host
+#[derive(Debug, Clone)]
+pub struct RemoteConfig {
+    /// WebSocket endpoint (`ws://` or `wss://`)
+    pub url: String,
+    /// Bearer token sent in the `Authorization` header
+    pub auth_token: Option<String>,
+    /// Maximum time to establish a connection
+    pub connect_timeout: Duration,
+    /// Request timeout before latency is added
+    pub request_timeout: Duration,
+    /// Upper bound for latency-adjusted request timeouts
+    pub max_request_timeout: Duration,
+    /// Delay between reconnection attempts
+    pub backoff: Backoff,
+    /// Size limits of messages
+    pub limits: WireLimits,
+    /// Where connection failures are reported
+    pub diagnostics: Diagnostics,
+}
+
+impl RemoteConfig {
+    /// Create a configuration with default timeouts
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            auth_token: None,
+            connect_timeout: Duration::from_secs(10),
+            request_timeout: Duration::from_millis(500),
+            max_request_timeout: Duration::from_secs(5),
+            backoff: Backoff::default(),
+            limits: WireLimits::default(),
+            diagnostics: Diagnostics::process(),
+        }
+    }
+
+    /// Authenticate with a bearer token
+    pub fn with_auth_token(mut self, token: impl Into<String>) -> Self {
+        self.auth_token = Some(token.into());
+        self
+    }
+
+    /// Use custom message size limits
+    pub fn with_limits(mut self, limits: WireLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Report through an instance's diagnostics
+    pub fn with_diagnostics(mut self, diagnostics: Diagnostics) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+}
+
+/// Exponential backoff between reconnection attempts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Backoff {
+    /// Delay before the first retry
+    pub initial: Duration,
+    /// Longest delay between retries
+    pub max: Duration,
+    /// Growth factor applied after each failed attempt
+    pub multiplier: f64,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(250),
+            max: Duration::from_secs(30),
+            multiplier: 2.0,
+        }
+    }
+}
+
+impl Backoff {
+    /// Get the delay before the given retry (0-based)
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = self.multiplier.max(1.0).powi(attempt.min(64) as i32);
+        self.initial.mul_f64(factor).min(self.max)
+    }
+}
+
+/// Moving average of request round-trip times
+#[derive(Debug, Clone, Copy, Default)]
+struct LatencyTracker {
+    average_ms: Option<f64>,
+}
+
+impl LatencyTracker {
+    /// Weight of the newest sample in the moving average
+    const ALPHA: f64 = 0.2;
+
+    fn record(&mut self, round_trip: Duration) {
+        let sample = round_trip.as_secs_f64() * 1000.0;
+        self.average_ms = Some(match self.average_ms {
+            Some(average) => average + Self::ALPHA * (sample - average),
+            None => sample,
+        });
+    }
+
+    /// Base timeout plus headroom for four average round trips
+    fn timeout(&self, base: Duration, max: Duration) -> Duration {
+        let headroom = Duration::from_secs_f64(self.average_ms.unwrap_or(0.0) * 4.0 / 1000.0);
+        (base + headroom).min(max)
+    }
+}
+
+/// State shared between connection handles and the background task
+struct ConnectionInner {
+    config: RemoteConfig,
+    next_id: AtomicU64,
+    pending: Mutex<HashMap<u64, oneshot::Sender<RpcResponse>>>,
+    outbound: Mutex<Option<mpsc::UnboundedSender<Message>>>,
+    /// Codec of the current connection, set after each handshake
+    codec: Mutex<WireCodec>,
+    latency: Mutex<LatencyTracker>,
+    /// Protocol settled by the last handshake
+    protocol: Mutex<Option<NegotiatedProtocol>>,
+    closed: AtomicBool,
+    shutdown: Notify,
+    connected: Notify,
+}
+
+impl ConnectionInner {
+    /// Route an incoming message to the call waiting for it
+    fn dispatch_response(&self, frame: Frame) {
+        let response = match self.codec().decode_response(frame) {
+            Ok(response) => response,
+            Err(e) => {
+                self.config.diagnostics.warn(
+                    "remote",
+                    &format!("Ignoring message from {}: {}", self.config.url, e),
+                );
+                return;
+            }
+        };
+
+        let waiter = self
+            .pending
+            .lock()
+            .ok()
+            .and_then(|mut pending| pending.remove(&response.id));
+        if let Some(waiter) = waiter {
+            let _ = waiter.send(response);
+        }
+    }
+
+    /// Fail every in-flight call by dropping its response channel
+    fn fail_pending(&self) {
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.clear();
+        }
+    }
+
+    fn codec(&self) -> WireCodec {
+        *self.codec.lock().unwrap_or_else(|p| p.into_inner())
+    }
+
+    fn set_codec(&self, codec: WireCodec) {
+        *self.codec.lock().unwrap_or_else(|p| p.into_inner()) = codec;
+    }
+
+    fn set_outbound(&self, sender: Option<mpsc::UnboundedSender<Message>>) {
+        if let Ok(mut outbound) = self.outbound.lock() {
+            *outbound = sender;
+        }
+    }
+}
+
+/// Handle to a remote plugin host connection
+///
+/// Cheap to clone; all clones share the same underlying connection.
+#[derive(Clone)]
+pub struct RemoteConnection {
+    inner: Arc<ConnectionInner>,
+}
+
+impl RemoteConnection {
+    /// Start connecting to a remote plugin host
+    ///
+    /// Returns immediately; the connection is established, and re-established
+    /// after failures, by a background task until `close` is called. Must be
+    /// called from within a tokio runtime.
+    pub fn connect(config: RemoteConfig) -> Self {
+        let codec = WireCodec::new(config.limits, Compression::None);
+        let inner = Arc::new(ConnectionInner {
+            config,
+            next_id: AtomicU64::new(1),
+            pending: Mutex::new(HashMap::new()),
+            outbound: Mutex::new(None),
+            codec: Mutex::new(codec),
+            latency: Mutex::new(LatencyTracker::default()),
+            protocol: Mutex::new(None),
+            closed: AtomicBool::new(false),
+            shutdown: Notify::new(),
+            connected: Notify::new(),
+        });
+
+        tokio::spawn(Self::run(inner.clone()));
+        Self { inner }
+    }
+
+    /// Check if the connection is currently established
+    pub fn is_connected(&self) -> bool {
+        self.inner
+            .outbound
+            .lock()
+            .map(|outbound| outbound.is_some())
+            .unwrap_or(false)
+    }
+
+    /// Wait until the connection is established
+    pub async fn wait_connected(&self, timeout: Duration) -> Result<(), String> {
+        let connected = self.inner.connected.notified();
+        if self.is_connected() {
+            return Ok(());
+        }
+
+        tokio::time::timeout(timeout, connected)
+            .await
+            .map_err(|_| format!("Timed out connecting to {}", self.inner.config.url))
+    }
+
+    /// Close the connection and stop reconnecting
+    pub fn close(&self) {
+        self.inner.closed.store(true, Ordering::SeqCst);
+        self.inner.shutdown.notify_one();
+    }
+
+    /// Call a method on the remote plugin host
+    ///
+    /// The timeout grows with the observed round-trip latency, bounded by
+    /// `RemoteConfig::max_request_timeout`.
+    pub async fn call(&self, method: &str, params: Value) -> Result<Value, String> {
+        let sender = self
+            .inner
+            .outbound
+            .lock()
+            .map_err(|e| format!("Failed to acquire lock: {}", e))?
+            .clone()
+            .ok_or_else(|| format!("Remote plugin host {} is not connected", self.inner.config.url))?;
+
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.inner
+            .pending
+            .lock()
+            .map_err(|e| format!("Failed to acquire lock: {}", e))?
+            .insert(id, tx);
+
+        let frame = match self.inner.codec().encode_request(&RpcRequest::new(id, method, params)) {
+            Ok(frame) => frame,
+            Err(e) => {
+                self.forget(id);
+                return Err(e);
+            }
+        };
+        if sender.send(to_message(frame)).is_err() {
+            self.forget(id);
+            return Err("Connection to remote plugin host lost".to_string());
+        }
+        // Cancels the request on the host if this future is dropped early
+        let mut in_flight = InFlight {
+            connection: self,
+            id,
+            answered: false,
+        };
+
+        let timeout = self
+            .inner
+            .latency
+            .lock()
+            .map(|latency| {
+                latency.timeout(
+                    self.inner.config.request_timeout,
+                    self.inner.config.max_request_timeout,
+                )
+            })
+            .unwrap_or(self.inner.config.max_request_timeout);
+
+        let started = Instant::now();
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(response)) => {
+                in_flight.answered = true;
+                if let Ok(mut latency) = self.inner.latency.lock() {
+                    latency.record(started.elapsed());
+                }
+                response.into_result()
+            }
+            Ok(Err(_)) => {
+                in_flight.answered = true;
+                Err("Connection to remote plugin host lost".to_string())
+            }
+            // Dropping `in_flight` cancels the request on the host
+            Err(_) => Err(format!(
+                "Remote call '{}' timed out after {} ms",
+                method,
+                timeout.as_millis()
+            )),
+        }
+    }
+
+    /// Call a method on the remote plugin host until the work is cancelled
+    ///
+    /// Once `token` is cancelled the call stops waiting and the host is told
+    /// to stop working on the request.
+    pub async fn call_with_cancellation(
+        &self,
+        method: &str,
+        params: Value,
+        token: &CancellationToken,
+    ) -> Result<Value, String> {
+        token
+            .run(self.call(method, params))
+            .await
+            .unwrap_or_else(|| Err(CANCELLED.to_string()))
+    }
+
+    /// Tell the host to stop working on a request nobody waits for anymore
+    fn cancel(&self, id: u64) {
+        self.forget(id);
+        // Legacy hosts ignore `cancel`; negotiated ones must have agreed to it
+        if self
+            .protocol()
+            .is_some_and(|protocol| !protocol.legacy && !protocol.supports(ProtocolFeature::Cancellation))
+        {
+            return;
+        }
+
+        let Some(sender) = self.inner.outbound.lock().ok().and_then(|outbound| outbound.clone()) else {
+            return;
+        };
+        let cancel_id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        if let Ok(frame) = self.inner.codec().encode_request(&RpcRequest::cancel(cancel_id, id)) {
+            let _ = sender.send(to_message(frame));
+        }
+    }
+
+    /// List the plugins served by the remote host
+    pub async fn list_plugins(&self) -> Result<Vec<RemotePlugin>, String> {
+        let value = self.call(methods::LIST_PLUGINS, Value::Null).await?;
+        let infos: Vec<RemotePluginInfo> = serde_json::from_value(value)
+            .map_err(|e| format!("Failed to parse plugin list: {}", e))?;
+
+        Ok(infos
+            .into_iter()
+            .map(|info| RemotePlugin {
+                info,
+                capabilities: Vec::new(),
+                connection: self.clone(),
+            })
+            .collect())
+    }
+
+    /// Negotiate the protocol with the host and list its plugins
+    ///
+    /// Offers the current API version with cancellation, and compression
+    /// when the connection is compressed. Hosts predating the handshake are
+    /// spoken to with the legacy protocol, and their plugins listed with
+    /// `list_plugins`. Plugins targeting an API version the launcher can't
+    /// load are left out.
+    ///
+    /// # Returns
+    /// The plugins served by the host, or an error if the host targets an
+    /// unsupported API version
+    pub async fn handshake(&self) -> Result<Vec<RemotePlugin>, String> {
+        let mut features = vec![ProtocolFeature::Cancellation];
+        if self.compression() != Compression::None {
+            features.push(ProtocolFeature::Compression);
+        }
+        let request = HandshakeRequest::new(features);
+        let params = serde_json::to_value(&request).map_err(|e| format!("Failed to serialize handshake: {}", e))?;
+
+        let value = match self.call(methods::HANDSHAKE, params).await {
+            Ok(value) => value,
+            Err(e) if e.starts_with(&format!("Plugin host error {}:", error_codes::METHOD_NOT_FOUND)) => {
+                self.set_protocol(NegotiatedProtocol::legacy());
+                return self.list_plugins().await;
+            }
+            Err(e) => return Err(e),
+        };
+        let response: HandshakeResponse =
+            serde_json::from_value(value).map_err(|e| format!("Failed to parse handshake: {}", e))?;
+        let protocol = negotiate(&request, &response)?;
+        self.set_protocol(protocol);
+
+        Ok(response
+            .plugins
+            .into_iter()
+            .filter(|plugin| match plugin.api_version.map(ApiVersion) {
+                Some(version) => match version.check() {
+                    Ok(()) => true,
+                    Err(reason) => {
+                        self.inner.config.diagnostics.warn(
+                            "remote",
+                            &format!("Plugin '{}' from {} not loaded: it {}", plugin.info.id, self.inner.config.url, reason),
+                        );
+                        false
+                    }
+                },
+                None => true,
+            })
+            .map(|plugin| RemotePlugin {
+                info: plugin.info,
+                capabilities: plugin.capabilities,
+                connection: self.clone(),
+            })
+            .collect())
+    }
+
+    /// Get the protocol settled by the last handshake
+    ///
+    /// None until `handshake` succeeds.
+    pub fn protocol(&self) -> Option<NegotiatedProtocol> {
+        self.inner.protocol.lock().unwrap_or_else(|p| p.into_inner()).clone()
+    }
+
+    fn set_protocol(&self, protocol: NegotiatedProtocol) {
+        *self.inner.protocol.lock().unwrap_or_else(|p| p.into_inner()) = Some(protocol);
+    }
+
+    /// Get the compression algorithm negotiated with the host
+    pub fn compression(&self) -> Compression {
+        self.inner.codec().compression()
+    }
+
+    fn forget(&self, id: u64) {
+        if let Ok(mut pending) = self.inner.pending.lock() {
+            pending.remove(&id);
+        }
+    }
+
+    /// Background task keeping the connection alive
+    async fn run(inner: Arc<ConnectionInner>) {
+        let mut attempt = 0;
+
+        while !inner.closed.load(Ordering::SeqCst) {
+            match Self::open(&inner.config).await {
+                Ok((stream, compression)) => {
+                    attempt = 0;
+                    inner.set_codec(WireCodec::new(inner.config.limits, compression));
+                    Self::serve(&inner, stream).await;
+                }
+                Err(e) => {
+                    inner.config.diagnostics.warn(
+                        "remote",
+                        &format!("Remote plugin host {}: {}", inner.config.url, e),
+                    );
+                }
+            }
+
+            inner.set_outbound(None);
+            inner.fail_pending();
+
+            if inner.closed.load(Ordering::SeqCst) {
+                break;
+            }
+
+            let delay = inner.config.backoff.delay(attempt);
+            attempt = attempt.saturating_add(1);
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = inner.shutdown.notified() => break,
+            }
+        }
+    }
+
+    /// Perform the WebSocket (and TLS) handshake
+    ///
+    /// # Returns
+    /// The stream and the compression algorithm picked by the host
+    async fn open(config: &RemoteConfig) -> Result<(WebSocketStream<MaybeTlsStream<TcpStream>>, Compression), String> {
+        let mut request = config
+            .url
+            .as_str()
+            .into_client_request()
+            .map_err(|e| format!("Invalid remote URL: {}", e))?;
+
+        if let Some(token) = &config.auth_token {
+            let value = format!("Bearer {}", token)
+                .parse()
+                .map_err(|_| "Auth token contains invalid characters".to_string())?;
+            request.headers_mut().insert(AUTHORIZATION, value);
+        }
+        if let Some(offer) = Compression::offer() {
+            let value = offer
+                .parse()
+                .map_err(|_| "Compression offer contains invalid characters".to_string())?;
+            request.headers_mut().insert(COMPRESSION_HEADER, value);
+        }
+
+        let (stream, response) =
+            tokio::time::timeout(config.connect_timeout, tokio_tungstenite::connect_async(request))
+                .await
+                .map_err(|_| "Connection timed out".to_string())?
+                .map_err(|e| format!("Failed to connect: {}", e))?;
+
+        let compression = response
+            .headers()
+            .get(COMPRESSION_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(Compression::negotiate)
+            .unwrap_or_default();
+        Ok((stream, compression))
+    }
+
+    /// Pump messages until the connection drops or is closed
+    async fn serve(inner: &ConnectionInner, stream: WebSocketStream<MaybeTlsStream<TcpStream>>) {
+        let (mut sink, mut source) = stream.split();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        inner.set_outbound(Some(tx));
+        inner.connected.notify_waiters();
+
+        loop {
+            tokio::select! {
+                _ = inner.shutdown.notified() => {
+                    let _ = sink.close().await;
+                    break;
+                }
+                outgoing = rx.recv() => match outgoing {
+                    Some(message) => {
+                        if sink.send(message).await.is_err() {
+                            break;
+                        }
+                    }
+                    None => break,
+                },
+                incoming = source.next() => match incoming {
+                    Some(Ok(Message::Text(text))) => inner.dispatch_response(Frame::Text(text.to_string())),
+                    Some(Ok(Message::Binary(bytes))) => inner.dispatch_response(Frame::Binary(bytes.to_vec())),
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+    }
+}
+
+/// A request sent to the host whose response hasn't arrived yet
+struct InFlight<'a> {
+    connection: &'a RemoteConnection,
+    id: u64,
+    answered: bool,
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        if !self.answered {
+            self.connection.cancel(self.id);
+        }
+    }
+}
+
+/// Convert an encoded message to a WebSocket message
+fn to_message(frame: Frame) -> Message {
+    match frame {
+        Frame::Text(text) => Message::text(text),
+        Frame::Binary(bytes) => Message::binary(bytes),
+    }
+}
+
+/// A plugin served by a remote plugin host
+pub struct RemotePlugin {
+    info: RemotePluginInfo,
+    capabilities: Vec<String>,
+    connection: RemoteConnection,
+}
+
+impl RemotePlugin {
+    /// Get the permissions the plugin declared during the handshake
+    pub fn capabilities(&self) -> &[String] {
+        &self.capabilities
+    }
+}
+
+#[async_trait]
+impl Plugin for RemotePlugin {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn id(&self) -> &str {
+        &self.info.id
+    }
+
+    fn name(&self) -> &str {
+        &self.info.name
+    }
+
+    fn description(&self) -> &str {
+        &self.info.description
+    }
+
+    fn version(&self) -> Option<&str> {
+        self.info.version.as_deref()
+    }
+
+    fn author(&self) -> Option<&str> {
+        self.info.author.as_deref()
+    }
+
+    fn homepage(&self) -> Option<&str> {
+        self.info.homepage.as_deref()
+    }
+
+    fn license(&self) -> Option<&str> {
+        self.info.license.as_deref()
+    }
+
+    fn protocol(&self) -> Option<NegotiatedProtocol> {
+        self.connection.protocol()
+    }
+
+    fn can_handle(&self, _context: &QueryContext) -> bool {
+        // Filtering happens remotely; skip the extra round trip
+        self.connection.is_connected()
+    }
+
+    async fn match_query(&self, context: &QueryContext) -> Result<Vec<PluginResult>, String> {
+        let params = serde_json::json!({ "plugin": self.info.id, "context": context });
+        let value = match context.cancellation() {
+            Some(token) => {
+                self.connection
+                    .call_with_cancellation(methods::MATCH_QUERY, params, token)
+                    .await?
+            }
+            None => self.connection.call(methods::MATCH_QUERY, params).await?,
+        };
+
+        serde_json::from_value(value).map_err(|e| format!("Failed to parse results: {}", e))
+    }
+
+    async fn execute(&self, result: &PluginResult) -> Result<ExecuteOutcome, String> {
+        let value = self
+            .connection
+            .call(
+                methods::EXECUTE,
+                serde_json::json!({ "plugin": self.info.id, "result": result }),
+            )
+            .await?;
+
+        // Bridges predating outcomes answer null
+        if value.is_null() {
+            return Ok(ExecuteOutcome::default());
+        }
+        serde_json::from_value(value).map_err(|e| format!("Failed to parse execute outcome: {}", e))
+    }
+
+    async fn on_key_event(&self, event: &KeyEvent, context: &QueryContext) -> Result<KeyResponse, String> {
+        let value = self
+            .connection
+            .call(
+                methods::KEY_EVENT,
+                serde_json::json!({ "plugin": self.info.id, "event": event, "context": context }),
+            )
+            .await?;
+
+        // Bridges without key events answer null
+        if value.is_null() {
+            return Ok(KeyResponse::Ignored);
+        }
+        serde_json::from_value(value).map_err(|e| format!("Failed to parse key response: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+
+    #[test]
+    fn test_backoff_delay() {
+        let backoff = Backoff::default();
+        assert_eq!(backoff.delay(0), Duration::from_millis(250));
+        assert_eq!(backoff.delay(2), Duration::from_secs(1));
+        assert_eq!(backoff.delay(20), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_latency_aware_timeout() {
+        let mut latency = LatencyTracker::default();
+        let base = Duration::from_millis(500);
+        let max = Duration::from_secs(5);
+        assert_eq!(latency.timeout(base, max), base);
+
+        latency.record(Duration::from_millis(100));
+        assert_eq!(latency.timeout(base, max), Duration::from_millis(900));
+
+        latency.record(Duration::from_secs(10));
+        assert_eq!(latency.timeout(base, max), max);
+    }
+
+    /// What the test host saw from the client
+    #[derive(Default)]
+    struct Seen {
+        auth: Option<String>,
+        compression_offer: Option<String>,
+        binary_frames: usize,
+    }
+
+    /// Serve one connection, answering with canned plugin host responses
+    ///
+    /// `match_query` answers with as many results as the query's length.
+    /// Without `handshake`, the host predates it and doesn't know the method.
+    #[allow(clippy::result_large_err)]
+    async fn serve_once(listener: TcpListener, limits: WireLimits, handshake: bool, seen: Arc<Mutex<Seen>>) {
+        let (tcp, _) = listener.accept().await.unwrap();
+        let compression = Arc::new(Mutex::new(Compression::None));
+        let callback = {
+            let seen = seen.clone();
+            let compression = compression.clone();
+            move |request: &Request, mut response: Response| {
+                let header = |name| {
+                    request
+                        .headers()
+                        .get(name)
+                        .map(|value| value.to_str().unwrap().to_string())
+                };
+                let mut seen = seen.lock().unwrap();
+                seen.auth = header(AUTHORIZATION.as_str());
+                seen.compression_offer = header(COMPRESSION_HEADER);
+
+                let picked = Compression::negotiate(seen.compression_offer.as_deref().unwrap_or_default());
+                if picked != Compression::None {
+                    response
+                        .headers_mut()
+                        .insert(COMPRESSION_HEADER, picked.name().parse().unwrap());
+                }
+                *compression.lock().unwrap() = picked;
+                Ok(response)
+            }
+        };
+        let mut ws = tokio_tungstenite::accept_hdr_async(tcp, callback).await.unwrap();
+        let codec = WireCodec::new(limits, *compression.lock().unwrap());
+
+        while let Some(Ok(message)) = ws.next().await {
+            let frame = match message {
+                Message::Text(text) => Frame::Text(text.to_string()),
+                Message::Binary(bytes) => {
+                    seen.lock().unwrap().binary_frames += 1;
+                    Frame::Binary(bytes.to_vec())
+                }
+                _ => break,
+            };
+            let request = codec.decode_request(frame).unwrap();
+            let result = match request.method.as_str() {
+                methods::HANDSHAKE if !handshake => {
+                    let response = RpcResponse::failure(request.id, error_codes::METHOD_NOT_FOUND, "Unknown method");
+                    ws.send(to_message(codec.encode_response(response).unwrap())).await.unwrap();
+                    continue;
+                }
+                methods::HANDSHAKE => serde_json::json!({
+                    "apiVersion": 2,
+                    "features": ["streaming", "cancellation", "telepathy"],
+                    "plugins": [
+                        {"id": "jira", "name": "Jira", "capabilities": ["network"]},
+                        {"id": "hologram", "name": "Hologram", "apiVersion": 99}
+                    ]
+                }),
+                methods::LIST_PLUGINS => serde_json::json!([
+                    {"id": "jira", "name": "Jira", "description": "Search tickets"}
+                ]),
+                methods::MATCH_QUERY => {
+                    let query = request.params["context"]["query"].as_str().unwrap();
+                    (0..query.len())
+                        .map(|i| serde_json::json!({"id": format!("VOLT-{}", i + 1), "title": query, "score": 90}))
+                        .collect()
+                }
+                _ => Value::Null,
+            };
+            let response = codec.encode_response(RpcResponse::success(request.id, result)).unwrap();
+            ws.send(to_message(response)).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_remote_plugin_round_trip() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let seen = Arc::new(Mutex::new(Seen::default()));
+        tokio::spawn(serve_once(listener, WireLimits::default(), false, seen.clone()));
+
+        let connection = RemoteConnection::connect(RemoteConfig::new(url).with_auth_token("s3cret"));
+        connection.wait_connected(Duration::from_secs(5)).await.unwrap();
+        assert_eq!(seen.lock().unwrap().auth.as_deref(), Some("Bearer s3cret"));
+
+        let plugins = connection.list_plugins().await.unwrap();
+        assert_eq!(plugins.len(), 1);
+        assert_eq!(plugins[0].id(), "jira");
+
+        let results = plugins[0]
+            .match_query(&QueryContext::new("login bug"))
+            .await
+            .unwrap();
+        assert_eq!(results[0].id, "VOLT-1");
+        assert_eq!(results[0].title, "login bug");
+
+        connection.close();
+    }
+
+    #[tokio::test]
+    async fn test_handshake_negotiates_protocol() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve_once(listener, WireLimits::default(), true, Arc::default()));
+
+        let connection = RemoteConnection::connect(RemoteConfig::new(url));
+        connection.wait_connected(Duration::from_secs(5)).await.unwrap();
+        assert_eq!(connection.protocol(), None);
+
+        // The plugin targeting a future API is left out
+        let plugins = connection.handshake().await.unwrap();
+        assert_eq!(plugins.len(), 1);
+        assert_eq!(plugins[0].capabilities(), ["network"]);
+
+        // Streaming wasn't offered, telepathy isn't known
+        let protocol = plugins[0].protocol().unwrap();
+        assert_eq!(protocol.api_version, 2);
+        assert_eq!(protocol.features, vec![ProtocolFeature::Cancellation]);
+        assert!(!protocol.legacy);
+        connection.close();
+
+        // Hosts predating the handshake get the legacy protocol
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve_once(listener, WireLimits::default(), false, Arc::default()));
+
+        let connection = RemoteConnection::connect(RemoteConfig::new(url));
+        connection.wait_connected(Duration::from_secs(5)).await.unwrap();
+        let plugins = connection.handshake().await.unwrap();
+        assert_eq!(plugins[0].id(), "jira");
+        assert_eq!(connection.protocol(), Some(NegotiatedProtocol::legacy()));
+        connection.close();
+    }
+
+    #[tokio::test]
+    async fn test_large_messages_are_capped_and_compressed() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let limits = WireLimits {
+            max_message_bytes: 4096,
+            compression_threshold: 512,
+            ..WireLimits::default()
+        };
+        let seen = Arc::new(Mutex::new(Seen::default()));
+        tokio::spawn(serve_once(listener, limits, false, seen.clone()));
+
+        let connection = RemoteConnection::connect(RemoteConfig::new(url).with_limits(limits));
+        connection.wait_connected(Duration::from_secs(5)).await.unwrap();
+        assert_eq!(connection.compression(), Compression::negotiate("zstd"));
+        assert_eq!(seen.lock().unwrap().compression_offer, Compression::offer());
+
+        let plugins = connection.list_plugins().await.unwrap();
+
+        // The host truncates the list to fit, keeping the best results
+        let query = "x".repeat(600);
+        let results = plugins[0].match_query(&QueryContext::new(query.clone())).await.unwrap();
+        assert!(!results.is_empty() && results.len() < query.len());
+        assert_eq!(results[0].id, "VOLT-1");
+
+        // Requests past the limit are refused locally
+        let request = connection
+            .call(methods::EXECUTE, Value::String("x".repeat(8192)))
+            .await;
+        assert!(request.unwrap_err().contains("more than the 4096 allowed"));
+
+        let compressed = connection.compression() == Compression::Zstd;
+        assert_eq!(seen.lock().unwrap().binary_frames > 0, compressed);
+        connection.close();
+    }
+
+    /// Serve one connection whose `match_query` spins until cancelled
+    ///
+    /// Each spin increments `work`; cancelled request IDs are sent on `cancelled`.
+    async fn serve_spinning(listener: TcpListener, work: Arc<AtomicU64>, cancelled: mpsc::UnboundedSender<u64>) {
+        let (tcp, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+        let mut spinning: HashMap<u64, Arc<AtomicBool>> = HashMap::new();
+
+        while let Some(Ok(Message::Text(text))) = ws.next().await {
+            let request = RpcRequest::decode(&text).unwrap();
+            if let Some(id) = request.cancelled_id() {
+                if let Some(stop) = spinning.remove(&id) {
+                    stop.store(true, Ordering::SeqCst);
+                }
+                cancelled.send(id).unwrap();
+                continue;
+            }
+
+            let stop = Arc::new(AtomicBool::new(false));
+            spinning.insert(request.id, stop.clone());
+            let work = work.clone();
+            tokio::spawn(async move {
+                while !stop.load(Ordering::SeqCst) {
+                    work.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                }
+            });
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_query_stops_host_work() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let work = Arc::new(AtomicU64::new(0));
+        let (cancelled_tx, mut cancelled_rx) = mpsc::unbounded_channel();
+        tokio::spawn(serve_spinning(listener, work.clone(), cancelled_tx));
+
+        let connection = RemoteConnection::connect(RemoteConfig::new(url));
+        connection.wait_connected(Duration::from_secs(5)).await.unwrap();
+        let plugin = RemotePlugin {
+            info: serde_json::from_value(serde_json::json!({"id": "slow", "name": "Slow"})).unwrap(),
+            capabilities: Vec::new(),
+            connection: connection.clone(),
+        };
+
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            canceller.cancel();
+        });
+        let context = QueryContext::new("anything").with_cancellation(token);
+        assert_eq!(plugin.match_query(&context).await.unwrap_err(), CANCELLED);
+
+        // The host is told which request to stop, and stops spinning
+        let cancelled = tokio::time::timeout(Duration::from_secs(5), cancelled_rx.recv()).await.unwrap();
+        assert_eq!(cancelled, Some(1));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let after_cancel = work.load(Ordering::SeqCst);
+        assert!(after_cancel > 0);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(work.load(Ordering::SeqCst), after_cancel);
+        assert!(connection.inner.pending.lock().unwrap().is_empty());
+
+        connection.close();
+    }
+
+    #[tokio::test]
+    async fn test_call_fails_when_disconnected() {
+        let mut config = RemoteConfig::new("ws://127.0.0.1:9");
+        config.connect_timeout = Duration::from_millis(100);
+        let connection = RemoteConnection::connect(config);
+
+        assert!(!connection.is_connected());
+        assert!(connection.call(methods::LIST_PLUGINS, Value::Null).await.is_err());
+        connection.close();
+    }
+}
@@ -0,0 +1,330 @@
+/// Headless replay of recorded query traces
+///
+/// Feeds a trace of timestamped keystrokes through the same dispatch steps
+/// the launcher uses (`can_handle`, `match_query` under a timeout, merge)
+/// and reports per-plugin latencies, timeouts and result churn, so plugin
+/// performance regressions show up before release. Used by the
+/// `volt-plugin replay` command.
+///
+/// ```json
+/// {
+///   "name": "open firefox",
+///   "events": [
+///     { "atMs": 0, "query": "f" },
+///     { "atMs": 90, "query": "fi" },
+///     { "atMs": 170, "query": "fir" }
+///   ]
+/// }
+/// ```
+use crate::aggregator::ResultAggregator;
+use crate::plugin::{Plugin, QueryContext};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Default time a plugin gets to answer a query
+pub const DEFAULT_QUERY_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// A recorded sequence of queries
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryTrace {
+    /// Name shown in reports
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Keystrokes in the order they happened
+    pub events: Vec<TraceEvent>,
+}
+
+/// The query text after one keystroke
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceEvent {
+    /// Milliseconds since the start of the trace
+    pub at_ms: u64,
+    /// Full query after the keystroke
+    pub query: String,
+}
+
+impl QueryTrace {
+    /// Parse a trace from JSON
+    pub fn from_json(content: &str) -> Result<Self, String> {
+        serde_json::from_str(content).map_err(|e| format!("Failed to parse trace: {}", e))
+    }
+
+    /// Load a trace from a JSON file
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let content =
+            std::fs::read_to_string(path).map_err(|e| format!("Failed to read trace: {}", e))?;
+        Self::from_json(&content)
+    }
+}
+
+/// How a trace is replayed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayOptions {
+    /// Time a plugin gets to answer each query
+    pub timeout: Duration,
+    /// Wait between keystrokes as recorded instead of replaying flat out
+    pub realtime: bool,
+    /// `QueryContext::max_results` sent with each query
+    pub max_results: Option<usize>,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_QUERY_TIMEOUT,
+            realtime: false,
+            max_results: None,
+        }
+    }
+}
+
+/// Measurements for one plugin over a whole trace
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginReplayStats {
+    /// Plugin identifier
+    pub plugin_id: String,
+    /// Queries the plugin accepted through `can_handle`
+    pub queries: usize,
+    /// Queries that didn't finish within the timeout
+    pub timeouts: usize,
+    /// Queries that returned an error
+    pub errors: usize,
+    /// Median `match_query` latency in milliseconds
+    pub p50_ms: f64,
+    /// 95th percentile latency in milliseconds
+    pub p95_ms: f64,
+    /// Slowest query in milliseconds
+    pub max_ms: f64,
+    /// Average share of results replaced between consecutive keystrokes (0.0-1.0)
+    pub churn: f64,
+}
+
+/// Outcome of replaying a trace
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayReport {
+    /// Trace name
+    pub trace: Option<String>,
+    /// Number of replayed keystrokes
+    pub events: usize,
+    /// Per-plugin measurements, slowest first
+    pub plugins: Vec<PluginReplayStats>,
+    /// Results displayed after the last keystroke
+    pub final_results: usize,
+}
+
+impl fmt::Display for ReplayReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Replayed {} keystrokes{}",
+            self.events,
+            self.trace.as_deref().map(|name| format!(" of '{}'", name)).unwrap_or_default()
+        )?;
+        writeln!(
+            f,
+            "  {:<24} {:>7} {:>9} {:>9} {:>9} {:>8} {:>6} {:>6}",
+            "plugin", "queries", "p50", "p95", "max", "timeouts", "errors", "churn"
+        )?;
+        for stats in &self.plugins {
+            writeln!(
+                f,
+                "  {:<24} {:>7} {:>7.1}ms {:>7.1}ms {:>7.1}ms {:>8} {:>6} {:>5.0}%",
+                stats.plugin_id,
+                stats.queries,
+                stats.p50_ms,
+                stats.p95_ms,
+                stats.max_ms,
+                stats.timeouts,
+                stats.errors,
+                stats.churn * 100.0
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Per-plugin state accumulated during a replay
+#[derive(Default)]
+struct PluginRun {
+    latencies: Vec<Duration>,
+    timeouts: usize,
+    errors: usize,
+    previous_ids: Option<HashSet<String>>,
+    churn: Vec<f64>,
+}
+
+/// Replay a trace against a set of plugins
+///
+/// Each keystroke is dispatched to every plugin accepting it, one plugin at
+/// a time so latencies aren't skewed by contention, and the answers are
+/// merged like in the launcher.
+pub async fn replay(plugins: &[Box<dyn Plugin>], trace: &QueryTrace, options: &ReplayOptions) -> ReplayReport {
+    let mut runs: Vec<PluginRun> = plugins.iter().map(|_| PluginRun::default()).collect();
+    let aggregator = match options.max_results {
+        Some(max) => ResultAggregator::new().with_max_results(max),
+        None => ResultAggregator::new(),
+    };
+    let started = Instant::now();
+    let mut final_results = 0;
+
+    for event in &trace.events {
+        if options.realtime {
+            let due = Duration::from_millis(event.at_ms);
+            tokio::time::sleep(due.saturating_sub(started.elapsed())).await;
+        }
+
+        let mut context = QueryContext::new(event.query.clone());
+        context.max_results = options.max_results;
+
+        let mut batches = Vec::new();
+        for (plugin, run) in plugins.iter().zip(&mut runs) {
+            if !plugin.can_handle(&context) {
+                continue;
+            }
+
+            let query_started = Instant::now();
+            let outcome = tokio::time::timeout(options.timeout, plugin.match_query(&context)).await;
+            run.latencies.push(query_started.elapsed());
+
+            match outcome {
+                Ok(Ok(results)) => {
+                    let ids: HashSet<String> = results.iter().map(|result| result.id.clone()).collect();
+                    if let Some(previous) = run.previous_ids.replace(ids.clone()) {
+                        run.churn.push(churn(&previous, &ids));
+                    }
+                    batches.push((plugin.id().to_string(), results));
+                }
+                Ok(Err(_)) => run.errors += 1,
+                Err(_) => run.timeouts += 1,
+            }
+        }
+
+        final_results = aggregator.merge(batches).results.len();
+    }
+
+    let mut stats: Vec<PluginReplayStats> = plugins
+        .iter()
+        .zip(runs)
+        .map(|(plugin, mut run)| {
+            run.latencies.sort();
+            PluginReplayStats {
+                plugin_id: plugin.id().to_string(),
+                queries: run.latencies.len(),
+                timeouts: run.timeouts,
+                errors: run.errors,
+                p50_ms: percentile_ms(&run.latencies, 0.50),
+                p95_ms: percentile_ms(&run.latencies, 0.95),
+                max_ms: run.latencies.last().map_or(0.0, |max| max.as_secs_f64() * 1000.0),
+                churn: if run.churn.is_empty() {
+                    0.0
+                } else {
+                    run.churn.iter().sum::<f64>() / run.churn.len() as f64
+                },
+            }
+        })
+        .collect();
+
+    stats.sort_by(|a, b| b.p95_ms.total_cmp(&a.p95_ms));
+
+    ReplayReport {
+        trace: trace.name.clone(),
+        events: trace.events.len(),
+        plugins: stats,
+        final_results,
+    }
+}
+
+/// Share of results that differ between two answers (Jaccard distance)
+fn churn(previous: &HashSet<String>, current: &HashSet<String>) -> f64 {
+    let union = previous.union(current).count();
+    if union == 0 {
+        return 0.0;
+    }
+    1.0 - previous.intersection(current).count() as f64 / union as f64
+}
+
+/// Nearest-rank percentile of sorted latencies, in milliseconds
+fn percentile_ms(sorted: &[Duration], percentile: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = ((percentile * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len());
+    sorted[rank - 1].as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result::PluginResult;
+    use async_trait::async_trait;
+
+    /// Answers with one result per query character, stalling on "slow"
+    struct LetterPlugin;
+
+    #[async_trait]
+    impl Plugin for LetterPlugin {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn id(&self) -> &str {
+            "letters"
+        }
+
+        fn name(&self) -> &str {
+            "Letters"
+        }
+
+        fn description(&self) -> &str {
+            "Returns the letters of the query"
+        }
+
+        fn can_handle(&self, context: &QueryContext) -> bool {
+            !context.query.is_empty()
+        }
+
+        async fn match_query(&self, context: &QueryContext) -> Result<Vec<PluginResult>, String> {
+            if context.query == "slow" {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+            }
+            Ok(context
+                .query
+                .chars()
+                .map(|c| PluginResult::new(c.to_string(), c.to_string()))
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_reports_timeouts_and_churn() {
+        let trace = QueryTrace::from_json(
+            r#"{"name": "typing", "events": [
+                {"atMs": 0, "query": "ab"},
+                {"atMs": 50, "query": "abcd"},
+                {"atMs": 90, "query": "slow"},
+                {"atMs": 120, "query": ""}
+            ]}"#,
+        )
+        .unwrap();
+        let plugins: Vec<Box<dyn Plugin>> = vec![Box::new(LetterPlugin)];
+        let options = ReplayOptions {
+            timeout: Duration::from_millis(50),
+            ..Default::default()
+        };
+
+        let report = replay(&plugins, &trace, &options).await;
+        let stats = &report.plugins[0];
+        assert_eq!(report.events, 4);
+        assert_eq!((stats.queries, stats.timeouts, stats.errors), (3, 1, 0));
+        assert_eq!(stats.churn, 0.5);
+        assert!(stats.max_ms >= 50.0);
+        assert!(report.to_string().contains("letters"));
+    }
+}
@@ -0,0 +1,234 @@
+/// Virtual paths to resources owned by plugins
+///
+/// Not every result maps to a real file: an item inside an archive, a note
+/// stored in a database or an attachment of a remote ticket only exist
+/// through the plugin that found them. Such results point at a
+/// `voltres://<plugin id>/<path>` URI instead of a file path, wherever a
+/// path is expected (`ResultIntent::OpenFile`, `Preview::Image`). The path
+/// after the plugin ID means whatever the plugin wants it to.
+///
+/// The owning plugin turns the URI into content through its
+/// `ResourceResolver` (see `crate::extensions`). Hosts that need a real
+/// file, to open it with its default application or show it as an image,
+/// get one from `PluginRegistry::materialize_resource`, which writes the
+/// content below the plugin's cache directory.
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// URI scheme of virtual paths
+pub const RESOURCE_SCHEME: &str = "voltres";
+
+/// Directory of a plugin's cache holding materialized resources
+pub const RESOURCES_CACHE_DIR: &str = "resources";
+
+/// A resource owned by a plugin, addressed as `voltres://<plugin id>/<path>`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VirtualPath {
+    /// Plugin owning the resource
+    pub plugin_id: String,
+    /// Path of the resource within the plugin, without a leading `/`
+    pub path: String,
+}
+
+impl VirtualPath {
+    /// Create a virtual path
+    ///
+    /// # Returns
+    /// Err if the plugin ID or path is malformed, see `parse`
+    pub fn new(plugin_id: impl Into<String>, path: impl Into<String>) -> Result<Self, String> {
+        let plugin_id = plugin_id.into();
+        let path = path.into().trim_start_matches('/').to_string();
+
+        if plugin_id.is_empty()
+            || plugin_id.contains(['/', '\\', ':'])
+            || plugin_id == "."
+            || plugin_id == ".."
+        {
+            return Err(format!(
+                "Invalid plugin ID in resource URI: '{}'",
+                plugin_id
+            ));
+        }
+        if path.is_empty() {
+            return Err("Resource path cannot be empty".to_string());
+        }
+        if path.chars().any(|c| c.is_control() || c == '\\') {
+            return Err(format!(
+                "Resource path contains invalid characters: '{}'",
+                path
+            ));
+        }
+        if path
+            .split('/')
+            .any(|segment| segment.is_empty() || segment == "." || segment == "..")
+        {
+            return Err(format!(
+                "Resource path has an empty or relative segment: '{}'",
+                path
+            ));
+        }
+
+        Ok(Self { plugin_id, path })
+    }
+
+    /// Parse a `voltres://` URI
+    ///
+    /// The path is split into `/`-separated segments, none of which may be
+    /// empty, `.` or `..`.
+    pub fn parse(uri: &str) -> Result<Self, String> {
+        let rest = uri
+            .split_once("://")
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case(RESOURCE_SCHEME))
+            .map(|(_, rest)| rest)
+            .ok_or_else(|| format!("Not a {} URI: '{}'", RESOURCE_SCHEME, uri))?;
+        let (plugin_id, path) = rest
+            .split_once('/')
+            .ok_or_else(|| format!("Resource URI has no path: '{}'", uri))?;
+
+        Self::new(plugin_id, path)
+    }
+
+    /// Check if a path or URI is a virtual path
+    pub fn is_virtual(target: &str) -> bool {
+        target
+            .split_once("://")
+            .is_some_and(|(scheme, _)| scheme.eq_ignore_ascii_case(RESOURCE_SCHEME))
+    }
+
+    /// Get the URI of the resource
+    pub fn to_uri(&self) -> String {
+        self.to_string()
+    }
+
+    /// Get the last segment of the path, e.g. "report.pdf"
+    pub fn file_name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+}
+
+impl fmt::Display for VirtualPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}://{}/{}", RESOURCE_SCHEME, self.plugin_id, self.path)
+    }
+}
+
+impl Serialize for VirtualPath {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_uri())
+    }
+}
+
+impl<'de> Deserialize<'de> for VirtualPath {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let uri = String::deserialize(deserializer)?;
+        Self::parse(&uri).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Content of a resolved virtual path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolvedResource {
+    /// The resource is backed by a real file
+    File(PathBuf),
+    /// The resource's bytes
+    Content {
+        /// The content
+        bytes: Vec<u8>,
+        /// MIME type, e.g. "application/pdf"
+        mime: Option<String>,
+    },
+}
+
+/// Get a real file holding a resolved resource
+///
+/// Content is written to `<dir>/<path>`, so the file keeps the resource's
+/// name and extension and opens with the right application.
+///
+/// # Arguments
+/// * `path` - The virtual path that was resolved
+/// * `resource` - What its plugin resolved it to
+/// * `dir` - Directory materialized resources are written to
+pub fn materialize(
+    path: &VirtualPath,
+    resource: ResolvedResource,
+    dir: &Path,
+) -> Result<PathBuf, String> {
+    match resource {
+        ResolvedResource::File(file) => Ok(file),
+        ResolvedResource::Content { bytes, .. } => {
+            // Segments were validated, so the file stays within `dir`
+            let file = path
+                .path
+                .split('/')
+                .fold(dir.to_path_buf(), |file, segment| file.join(segment));
+            if let Some(parent) = file.parent() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create resource directory: {}", e))?;
+            }
+            std::fs::write(&file, bytes).map_err(|e| format!("Failed to write resource: {}", e))?;
+            Ok(file)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_virtual_path_uris() {
+        let path = VirtualPath::parse("voltres://archives/backup.zip/docs/report.pdf").unwrap();
+        assert_eq!(path.plugin_id, "archives");
+        assert_eq!(path.path, "backup.zip/docs/report.pdf");
+        assert_eq!(path.file_name(), "report.pdf");
+        assert_eq!(
+            path.to_uri(),
+            "voltres://archives/backup.zip/docs/report.pdf"
+        );
+        assert_eq!(
+            serde_json::to_value(&path).unwrap(),
+            serde_json::json!(path.to_uri())
+        );
+
+        assert!(VirtualPath::is_virtual("VOLTRES://notes/1"));
+        assert!(!VirtualPath::is_virtual("/home/user/notes"));
+        for invalid in [
+            "file:///etc/passwd",
+            "voltres://notes",
+            "voltres://notes/",
+            "voltres://notes/a/../../secrets",
+            "voltres://notes/a//b",
+            "voltres://../etc/passwd",
+        ] {
+            assert!(
+                VirtualPath::parse(invalid).is_err(),
+                "{} should be rejected",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_materialize() {
+        let temp_dir = std::env::temp_dir().join("volt_test_resources");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let path = VirtualPath::new("notes", "db/42/meeting.md").unwrap();
+        let content = ResolvedResource::Content {
+            bytes: b"# Meeting".to_vec(),
+            mime: Some("text/markdown".to_string()),
+        };
+        let file = materialize(&path, content, &temp_dir).unwrap();
+        assert_eq!(file, temp_dir.join("db").join("42").join("meeting.md"));
+        assert_eq!(std::fs::read(&file).unwrap(), b"# Meeting");
+
+        let backed = ResolvedResource::File(PathBuf::from("/srv/notes/42.md"));
+        assert_eq!(
+            materialize(&path, backed, &temp_dir).unwrap(),
+            PathBuf::from("/srv/notes/42.md")
+        );
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
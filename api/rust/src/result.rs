@@ -0,0 +1,725 @@
+/// Search results produced by plugins
+///
+/// A `PluginResult` is what a plugin returns for a query and what it receives
+/// back in `execute`. Arbitrary per-result data (file paths, URLs, PIDs, ...)
+/// lives in the `metadata` bag so it survives every trip through the host.
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use smallvec::SmallVec;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Most tags kept on a result
+pub const MAX_TAGS: usize = 4;
+
+/// Most accessories kept on a result
+pub const MAX_ACCESSORIES: usize = 3;
+
+/// Secondary actions of a result, stored inline up to the usual count
+pub type ResultActions = SmallVec<[ResultAction; 2]>;
+
+/// A single result returned by a plugin
+///
+/// Display strings are `Cow<'static, str>`, so results built from string
+/// literals don't allocate for them, and the producing plugin's ID is shared
+/// by all of its results.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct PluginResult {
+    /// Identifier of the result, unique within the producing plugin
+    pub id: String,
+    /// Main line displayed to the user
+    pub title: Cow<'static, str>,
+    /// Secondary line displayed under the title
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subtitle: Option<Cow<'static, str>>,
+    /// Icon path, URL, or emoji
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<Cow<'static, str>>,
+    /// Badge text displayed on the right (e.g., "Game", "App")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub badge: Option<String>,
+    /// Short labels shown after the title, e.g. "main" or "pinned"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Extras shown right-aligned on the result row
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accessories: Vec<Accessory>,
+    /// Relevance score, higher ranks first
+    #[serde(default)]
+    pub score: u32,
+    /// ID of the plugin that created this result
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "ts-bindings", ts(as = "Option<String>"))]
+    pub plugin_id: Option<Arc<str>>,
+    /// Free-form data the plugin needs again when the result is executed
+    ///
+    /// Accepts `data` on input for compatibility with TypeScript plugins.
+    #[serde(default, alias = "data", skip_serializing_if = "Map::is_empty")]
+    pub metadata: Map<String, Value>,
+    /// Quick-select slot (1-9, bound to Cmd/Ctrl+N)
+    ///
+    /// Plugins may set this to request a slot; the aggregator overwrites it
+    /// with the slot actually assigned after merging.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shortcut: Option<u8>,
+    /// Extra keyboard bindings available while this result is selected
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub key_hints: Vec<KeyHint>,
+    /// Secondary actions listed in the result's action menu
+    #[serde(default, skip_serializing_if = "SmallVec::is_empty")]
+    #[cfg_attr(feature = "ts-bindings", ts(as = "Vec<ResultAction>"))]
+    pub actions: ResultActions,
+    /// Query as typed, when the result was produced from a spelling correction
+    ///
+    /// The UI shows "Showing results for ..." next to such results.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub corrected_from: Option<String>,
+    /// Common action the host performs itself instead of calling `execute`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intent: Option<ResultIntent>,
+    /// Age in milliseconds of the cached copy this result was served from
+    ///
+    /// None for results computed fresh for the current query.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_age_ms: Option<u64>,
+    /// What screen readers announce for the result
+    ///
+    /// Filled from the title, decorations and intent by the aggregator when
+    /// the plugin leaves it out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accessibility: Option<Accessibility>,
+}
+
+/// Extra shown right-aligned on a result row
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Accessory {
+    /// Plain text, e.g. "2 min ago"
+    Text {
+        /// The text
+        text: String,
+    },
+    /// Highlighted pill, e.g. "Running" or "3 updates"
+    Badge {
+        /// The text
+        text: String,
+        /// CSS color of the pill, the theme's accent color if None
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        color: Option<String>,
+    },
+    /// Progress bar
+    Progress {
+        /// Completion, from 0.0 to 1.0
+        value: f64,
+    },
+}
+
+impl Accessory {
+    /// Create a text accessory
+    pub fn text(text: impl Into<String>) -> Self {
+        Accessory::Text { text: text.into() }
+    }
+
+    /// Create a badge accessory in the accent color
+    pub fn badge(text: impl Into<String>) -> Self {
+        Accessory::Badge {
+            text: text.into(),
+            color: None,
+        }
+    }
+
+    /// Create a progress accessory
+    pub fn progress(value: f64) -> Self {
+        Accessory::Progress { value }
+    }
+
+    /// Text read out by screen readers
+    pub fn spoken_text(&self) -> String {
+        match self {
+            Accessory::Text { text } | Accessory::Badge { text, .. } => text.clone(),
+            Accessory::Progress { value } => format!("{:.0}% complete", value * 100.0),
+        }
+    }
+}
+
+/// Screen reader metadata of a result
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct Accessibility {
+    /// Short text read when the result is focused, e.g. "Firefox"
+    pub label: String,
+    /// What activating the result does
+    #[serde(default)]
+    pub role: AccessibilityRole,
+    /// Longer text read on request, e.g. "Web browser, running"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl Accessibility {
+    /// Create accessibility metadata with a label
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Set what activating the result does
+    pub fn with_role(mut self, role: AccessibilityRole) -> Self {
+        self.role = role;
+        self
+    }
+
+    /// Set the longer description
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+}
+
+/// What activating a result does, announced by screen readers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub enum AccessibilityRole {
+    /// Plugin-defined action
+    #[default]
+    Item,
+    /// Opens a web page
+    Link,
+    /// Opens a file or folder
+    File,
+    /// Starts a program
+    Command,
+    /// Copies a computed answer, e.g. a calculation
+    Answer,
+}
+
+impl From<IntentKind> for AccessibilityRole {
+    fn from(kind: IntentKind) -> Self {
+        match kind {
+            IntentKind::OpenFile => AccessibilityRole::File,
+            IntentKind::OpenUrl => AccessibilityRole::Link,
+            IntentKind::RunCommand => AccessibilityRole::Command,
+            IntentKind::CopyText => AccessibilityRole::Answer,
+            IntentKind::Share => AccessibilityRole::File,
+        }
+    }
+}
+
+/// Keyboard hint declared by a plugin for one of its results
+///
+/// Rendered next to the selected result; pressing `key` routes `action`
+/// back to the plugin.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct KeyHint {
+    /// Key combination, e.g. "Shift+Enter" or "Mod+C"
+    pub key: String,
+    /// Action identifier passed back to the plugin
+    pub action: String,
+    /// Human-readable label, e.g. "Copy path"
+    pub label: String,
+}
+
+impl KeyHint {
+    /// Create a new keyboard hint
+    pub fn new(key: impl Into<String>, action: impl Into<String>, label: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            action: action.into(),
+            label: label.into(),
+        }
+    }
+}
+
+/// Common action the host can perform for a result
+///
+/// Results carrying an intent are executed by the host (see
+/// `crate::intents`), with the same validation and behavior for every
+/// plugin. Results without one go to the plugin's `execute`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ResultIntent {
+    /// Open a file or folder with its default application
+    OpenFile {
+        /// Absolute path
+        path: String,
+    },
+    /// Open a URL in the default browser or handler
+    OpenUrl {
+        /// URL to open
+        url: String,
+    },
+    /// Start a program
+    RunCommand(CommandSpec),
+    /// Copy text to the clipboard
+    CopyText {
+        /// Text to copy
+        text: String,
+    },
+    /// Offer a file through the platform's share sheet (macOS and Windows)
+    Share {
+        /// Absolute path
+        path: String,
+    },
+}
+
+impl ResultIntent {
+    /// Get the kind of the intent
+    pub fn kind(&self) -> IntentKind {
+        match self {
+            ResultIntent::OpenFile { .. } => IntentKind::OpenFile,
+            ResultIntent::OpenUrl { .. } => IntentKind::OpenUrl,
+            ResultIntent::RunCommand(_) => IntentKind::RunCommand,
+            ResultIntent::CopyText { .. } => IntentKind::CopyText,
+            ResultIntent::Share { .. } => IntentKind::Share,
+        }
+    }
+}
+
+/// Kind of a `ResultIntent`, without its payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub enum IntentKind {
+    /// `ResultIntent::OpenFile`
+    OpenFile,
+    /// `ResultIntent::OpenUrl`
+    OpenUrl,
+    /// `ResultIntent::RunCommand`
+    RunCommand,
+    /// `ResultIntent::CopyText`
+    CopyText,
+    /// `ResultIntent::Share`
+    Share,
+}
+
+/// Program started by a `RunCommand` intent
+///
+/// The program is started directly, never through a shell.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct CommandSpec {
+    /// Program name or path
+    pub program: String,
+    /// Arguments passed to the program
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Directory to start in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<String>,
+}
+
+impl CommandSpec {
+    /// Create a command without arguments
+    pub fn new(program: impl Into<String>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            working_dir: None,
+        }
+    }
+
+    /// Add an argument
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+}
+
+/// Secondary action offered on a result, e.g. "New Private Window"
+///
+/// Listed in the result's action menu; choosing one routes `id` back to
+/// the plugin like a key hint action.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct ResultAction {
+    /// Action identifier passed back to the plugin
+    pub id: String,
+    /// Human-readable label
+    pub label: String,
+    /// Icon path, URL, or emoji
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+}
+
+impl ResultAction {
+    /// Create a new result action
+    pub fn new(id: impl Into<String>, label: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            label: label.into(),
+            icon: None,
+        }
+    }
+
+    /// Set the icon shown next to the action
+    pub fn with_icon(mut self, icon: impl Into<String>) -> Self {
+        self.icon = Some(icon.into());
+        self
+    }
+}
+
+impl PluginResult {
+    /// Create a result with the given ID and title
+    pub fn new(id: impl Into<String>, title: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            id: id.into(),
+            title: title.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Check if this result was served from a cache
+    pub fn is_stale(&self) -> bool {
+        self.cache_age_ms.is_some()
+    }
+
+    // ========== Canonical Form ==========
+
+    /// Serialize the result canonically, see `crate::canonical`
+    pub fn canonical_json(&self) -> String {
+        crate::canonical::value_to_string(&self.to_json_value())
+    }
+
+    /// Hash of the canonical serialization, equal for equal results
+    /// whichever backend produced them
+    pub fn content_hash(&self) -> String {
+        crate::canonical::hash_str(&self.canonical_json())
+    }
+
+    /// Key shared by results with the same content, however they rank
+    ///
+    /// Leaves out what the host assigns per query: the producing plugin,
+    /// score, quick-select slot, cache age, spelling correction and the
+    /// accessibility metadata filled in by the aggregator.
+    pub fn dedup_key(&self) -> String {
+        let mut value = self.to_json_value();
+        if let Value::Object(members) = &mut value {
+            for key in ["pluginId", "score", "shortcut", "cacheAgeMs", "correctedFrom", "accessibility"] {
+                members.remove(key);
+            }
+        }
+        crate::canonical::hash_str(&crate::canonical::value_to_string(&value))
+    }
+
+    fn to_json_value(&self) -> Value {
+        // Results only have string keys, so serializing them can't fail
+        serde_json::to_value(self).unwrap_or(Value::Null)
+    }
+
+    // ========== Metadata ==========
+
+    /// Get a raw metadata value
+    pub fn meta(&self, key: &str) -> Option<&Value> {
+        self.metadata.get(key)
+    }
+
+    /// Get a metadata value as a string
+    pub fn meta_str(&self, key: &str) -> Option<&str> {
+        self.meta(key).and_then(Value::as_str)
+    }
+
+    /// Get a metadata value as a signed integer
+    pub fn meta_i64(&self, key: &str) -> Option<i64> {
+        self.meta(key).and_then(Value::as_i64)
+    }
+
+    /// Get a metadata value as an unsigned integer
+    pub fn meta_u64(&self, key: &str) -> Option<u64> {
+        self.meta(key).and_then(Value::as_u64)
+    }
+
+    /// Get a metadata value as a float
+    pub fn meta_f64(&self, key: &str) -> Option<f64> {
+        self.meta(key).and_then(Value::as_f64)
+    }
+
+    /// Get a metadata value as a boolean
+    pub fn meta_bool(&self, key: &str) -> Option<bool> {
+        self.meta(key).and_then(Value::as_bool)
+    }
+
+    /// Deserialize a metadata value into any type
+    ///
+    /// # Returns
+    /// None if the key is missing, Err if the value has the wrong shape
+    pub fn meta_as<T: DeserializeOwned>(&self, key: &str) -> Option<Result<T, String>> {
+        self.meta(key).map(|value| {
+            serde_json::from_value(value.clone())
+                .map_err(|e| format!("Failed to parse metadata '{}': {}", key, e))
+        })
+    }
+
+    /// Set a metadata value, replacing any previous value for the key
+    pub fn set_meta(&mut self, key: impl Into<String>, value: impl Into<Value>) -> &mut Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    /// Builder-style variant of [`set_meta`](Self::set_meta)
+    pub fn with_meta(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.set_meta(key, value);
+        self
+    }
+
+    /// Remove a metadata value, returning it if present
+    pub fn remove_meta(&mut self, key: &str) -> Option<Value> {
+        self.metadata.remove(key)
+    }
+
+    // ========== Keyboard ==========
+
+    /// Request a quick-select slot (1-9) for this result
+    pub fn with_shortcut(mut self, slot: u8) -> Self {
+        self.shortcut = Some(slot);
+        self
+    }
+
+    /// Add a keyboard hint available while this result is selected
+    pub fn with_key_hint(mut self, hint: KeyHint) -> Self {
+        self.key_hints.push(hint);
+        self
+    }
+
+    /// Let the host perform a common action when the result is executed
+    pub fn with_intent(mut self, intent: ResultIntent) -> Self {
+        self.intent = Some(intent);
+        self
+    }
+
+    /// Add a secondary action to the result's action menu
+    pub fn with_action(mut self, action: ResultAction) -> Self {
+        self.actions.push(action);
+        self
+    }
+
+    // ========== Decorations ==========
+
+    /// Add a tag shown after the title
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Add a right-aligned accessory
+    pub fn with_accessory(mut self, accessory: Accessory) -> Self {
+        self.accessories.push(accessory);
+        self
+    }
+
+    /// Drop empty and duplicate tags and invalid accessories, and enforce
+    /// `MAX_TAGS` and `MAX_ACCESSORIES`
+    ///
+    /// Progress values are clamped to 0.0-1.0.
+    pub fn sanitize_decorations(&mut self) {
+        let mut seen = HashSet::new();
+        self.tags.retain(|tag| {
+            let tag = tag.trim().to_lowercase();
+            !tag.is_empty() && seen.insert(tag)
+        });
+        self.tags.truncate(MAX_TAGS);
+
+        self.accessories.retain_mut(|accessory| match accessory {
+            Accessory::Text { text } | Accessory::Badge { text, .. } => !text.trim().is_empty(),
+            Accessory::Progress { value } => {
+                *value = value.clamp(0.0, 1.0);
+                !value.is_nan()
+            }
+        });
+        self.accessories.truncate(MAX_ACCESSORIES);
+    }
+
+    // ========== Accessibility ==========
+
+    /// Set what screen readers announce for the result
+    pub fn with_accessibility(mut self, accessibility: Accessibility) -> Self {
+        self.accessibility = Some(accessibility);
+        self
+    }
+
+    /// Fill in missing accessibility metadata
+    ///
+    /// The label defaults to the title, the role to the one matching the
+    /// intent, and the description to the subtitle, badge, tags and text of
+    /// the accessories. Plugin-provided values are kept unless blank. Call
+    /// after `sanitize_decorations`, so dropped decorations aren't read out.
+    pub fn fill_accessibility(&mut self) {
+        let intent_role = self.intent.as_ref().map(|intent| AccessibilityRole::from(intent.kind()));
+        let accessibility = self.accessibility.get_or_insert_with(|| Accessibility {
+            role: intent_role.unwrap_or_default(),
+            ..Default::default()
+        });
+
+        if accessibility.label.trim().is_empty() {
+            accessibility.label = self.title.trim().to_string();
+        }
+        accessibility.description = accessibility
+            .description
+            .take()
+            .filter(|description| !description.trim().is_empty())
+            .or_else(|| {
+                let parts: Vec<String> = self
+                    .subtitle
+                    .iter()
+                    .map(|subtitle| subtitle.to_string())
+                    .chain(self.badge.clone())
+                    .chain(self.tags.iter().cloned())
+                    .chain(self.accessories.iter().map(Accessory::spoken_text))
+                    .filter(|part| !part.trim().is_empty())
+                    .collect();
+                (!parts.is_empty()).then(|| parts.join(", "))
+            });
+    }
+
+    /// Append text to the accessibility description
+    ///
+    /// Used for decorations added after `fill_accessibility`, e.g. by
+    /// annotators. Parts already in the description are skipped.
+    pub fn extend_accessibility_description(&mut self, parts: impl IntoIterator<Item = String>) {
+        let Some(accessibility) = &mut self.accessibility else {
+            return;
+        };
+
+        for part in parts {
+            let part = part.trim();
+            match &mut accessibility.description {
+                Some(description) if description.contains(part) => {}
+                Some(description) => {
+                    description.push_str(", ");
+                    description.push_str(part);
+                }
+                None if !part.is_empty() => accessibility.description = Some(part.to_string()),
+                None => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typed_metadata_accessors() {
+        let result = PluginResult::new("proc-42", "firefox")
+            .with_meta("path", "/usr/bin/firefox")
+            .with_meta("pid", 42u32)
+            .with_meta("offset", -3)
+            .with_meta("cpu", 12.5)
+            .with_meta("elevated", false);
+
+        assert_eq!(result.meta_str("path"), Some("/usr/bin/firefox"));
+        assert_eq!(result.meta_u64("pid"), Some(42));
+        assert_eq!(result.meta_i64("offset"), Some(-3));
+        assert_eq!(result.meta_f64("cpu"), Some(12.5));
+        assert_eq!(result.meta_bool("elevated"), Some(false));
+        assert_eq!(result.meta_str("pid"), None);
+        assert!(result.meta("missing").is_none());
+
+        let pid: u32 = result.meta_as("pid").unwrap().unwrap();
+        assert_eq!(pid, 42);
+        assert!(result.meta_as::<u32>("path").unwrap().is_err());
+    }
+
+    #[test]
+    fn test_metadata_round_trip() {
+        let mut result = PluginResult::new("url-1", "Volt");
+        result.score = 80;
+        result
+            .set_meta("url", "https://github.com/VoltLaunchr")
+            .set_meta("size", u64::MAX)
+            .set_meta("tags", serde_json::json!(["a", {"nested": [1, 2.5, null]}]));
+
+        let encoded = serde_json::to_string(&result).unwrap();
+        let decoded: PluginResult = serde_json::from_str(&encoded).unwrap();
+
+        assert_eq!(result, decoded);
+        assert_eq!(decoded.meta_u64("size"), Some(u64::MAX));
+    }
+
+    #[test]
+    fn test_accepts_typescript_data_field() {
+        let decoded: PluginResult = serde_json::from_value(serde_json::json!({
+            "id": "calc",
+            "title": "4",
+            "score": 100,
+            "pluginId": "calculator",
+            "data": {"expression": "2+2"}
+        }))
+        .unwrap();
+
+        assert_eq!(decoded.plugin_id.as_deref(), Some("calculator"));
+        assert_eq!(decoded.meta_str("expression"), Some("2+2"));
+    }
+
+    #[test]
+    fn test_decorations_are_sanitized() {
+        let mut result = PluginResult::new("repo", "volt")
+            .with_tag("main")
+            .with_tag(" MAIN ")
+            .with_tag("")
+            .with_accessory(Accessory::progress(1.7))
+            .with_accessory(Accessory::progress(f64::NAN))
+            .with_accessory(Accessory::text(" "))
+            .with_accessory(Accessory::badge("Running"))
+            .with_accessory(Accessory::text("2 min ago"))
+            .with_accessory(Accessory::text("3 files"));
+
+        result.sanitize_decorations();
+        assert_eq!(result.tags, vec!["main"]);
+        assert_eq!(
+            result.accessories,
+            vec![
+                Accessory::progress(1.0),
+                Accessory::badge("Running"),
+                Accessory::text("2 min ago")
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&result.accessories[1]).unwrap(),
+            serde_json::json!({"kind": "badge", "text": "Running"})
+        );
+    }
+
+    #[test]
+    fn test_accessibility_defaults() {
+        let mut result = PluginResult::new("dl", "ubuntu.iso")
+            .with_tag("iso")
+            .with_accessory(Accessory::progress(0.5))
+            .with_intent(ResultIntent::OpenFile {
+                path: "/tmp/ubuntu.iso".to_string(),
+            });
+        result.subtitle = Some("Downloads".into());
+
+        result.fill_accessibility();
+        assert_eq!(
+            result.accessibility,
+            Some(
+                Accessibility::new("ubuntu.iso")
+                    .with_role(AccessibilityRole::File)
+                    .with_description("Downloads, iso, 50% complete")
+            )
+        );
+
+        let mut result = PluginResult::new("calc", "4")
+            .with_accessibility(Accessibility::new(" ").with_description("Two plus two"));
+        result.fill_accessibility();
+        let accessibility = result.accessibility.unwrap();
+        assert_eq!(accessibility.label, "4");
+        assert_eq!(accessibility.role, AccessibilityRole::Item);
+        assert_eq!(accessibility.description.as_deref(), Some("Two plus two"));
+    }
+}
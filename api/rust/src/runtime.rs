@@ -0,0 +1,383 @@
+/// Per-plugin task isolation
+///
+/// A plugin spawning unbounded tasks on the launcher's runtime can starve
+/// query dispatch. Plugins spawn background work through the
+/// `TaskSupervisor` instead, and each plugin's `SandboxProfile` decides
+/// where it runs: on the shared runtime, on a dedicated current-thread
+/// runtime, or on a dedicated pool with a fixed number of workers. The
+/// supervisor counts tasks, enforces the task budget, and force-stops
+/// everything a plugin spawned when it is disabled or unregistered.
+///
+/// ```ignore
+/// let tasks = api.tasks();
+/// tasks.set_profile("indexer", SandboxProfile::dedicated(RuntimeKind::Workers { threads: 2 }))?;
+/// tasks.spawn("indexer", async move { crawl().await })?;
+/// ```
+use crate::locks;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::task::{AbortHandle, JoinHandle};
+
+/// Time a dedicated runtime gets to finish blocking work when stopped
+pub const TASK_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Where a plugin's tasks run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum RuntimeKind {
+    /// The host's runtime, shared with dispatch
+    #[default]
+    Shared,
+    /// A dedicated single-threaded runtime
+    CurrentThread,
+    /// A dedicated runtime with a fixed number of worker threads
+    Workers {
+        /// Number of worker threads
+        threads: usize,
+    },
+}
+
+/// Isolation settings of a plugin
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SandboxProfile {
+    /// Where the plugin's tasks run
+    #[serde(default)]
+    pub runtime: RuntimeKind,
+    /// Maximum number of tasks running at once; spawning more fails
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tasks: Option<usize>,
+}
+
+impl SandboxProfile {
+    /// Profile running tasks on a dedicated runtime
+    pub fn dedicated(runtime: RuntimeKind) -> Self {
+        Self {
+            runtime,
+            max_tasks: None,
+        }
+    }
+
+    /// Limit the number of tasks running at once
+    pub fn with_max_tasks(mut self, max_tasks: usize) -> Self {
+        self.max_tasks = Some(max_tasks);
+        self
+    }
+}
+
+/// Task counters of a plugin
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskMetrics {
+    /// Tasks spawned since the plugin's profile was set
+    pub spawned: u64,
+    /// Tasks currently running
+    pub active: usize,
+    /// Spawns refused because the task budget was exhausted
+    pub rejected: u64,
+}
+
+/// A dedicated runtime driven by its own thread
+struct DedicatedRuntime {
+    handle: tokio::runtime::Handle,
+    stop: tokio::sync::oneshot::Sender<()>,
+    thread: std::thread::JoinHandle<()>,
+}
+
+impl DedicatedRuntime {
+    fn start(plugin_id: &str, kind: RuntimeKind) -> Result<Option<Self>, String> {
+        let mut builder = match kind {
+            RuntimeKind::Shared => return Ok(None),
+            RuntimeKind::CurrentThread => tokio::runtime::Builder::new_current_thread(),
+            RuntimeKind::Workers { threads } => {
+                let mut builder = tokio::runtime::Builder::new_multi_thread();
+                builder.worker_threads(threads.max(1));
+                builder
+            }
+        };
+
+        let thread_name = format!("volt-plugin-{}", plugin_id);
+        let runtime = builder
+            .enable_all()
+            .thread_name(&thread_name)
+            .build()
+            .map_err(|e| format!("Failed to start runtime for plugin '{}': {}", plugin_id, e))?;
+        let handle = runtime.handle().clone();
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+
+        let thread = std::thread::Builder::new()
+            .name(thread_name)
+            .spawn(move || {
+                runtime.block_on(async {
+                    let _ = stopped.await;
+                });
+                // Pending tasks are dropped; blocking work gets a grace period
+                runtime.shutdown_timeout(TASK_SHUTDOWN_TIMEOUT);
+            })
+            .map_err(|e| format!("Failed to start runtime thread for plugin '{}': {}", plugin_id, e))?;
+
+        Ok(Some(Self { handle, stop, thread }))
+    }
+
+    fn stop(self) {
+        let _ = self.stop.send(());
+        let _ = self.thread.join();
+    }
+}
+
+/// Tasks of one plugin
+struct PluginTasks {
+    profile: SandboxProfile,
+    runtime: Mutex<Option<DedicatedRuntime>>,
+    active: Arc<AtomicUsize>,
+    spawned: AtomicU64,
+    rejected: AtomicU64,
+    handles: Mutex<Vec<AbortHandle>>,
+}
+
+impl PluginTasks {
+    fn new(profile: SandboxProfile, runtime: Option<DedicatedRuntime>) -> Self {
+        Self {
+            profile,
+            runtime: Mutex::new(runtime),
+            active: Arc::default(),
+            spawned: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+            handles: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn metrics(&self) -> TaskMetrics {
+        TaskMetrics {
+            spawned: self.spawned.load(Ordering::Relaxed),
+            active: self.active.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Abort every task and stop the dedicated runtime
+    fn stop(&self) {
+        let handles = std::mem::take(&mut *self.handles.lock().unwrap_or_else(|poisoned| poisoned.into_inner()));
+        for handle in handles {
+            handle.abort();
+        }
+
+        let runtime = self.runtime.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take();
+        if let Some(runtime) = runtime {
+            runtime.stop();
+        }
+    }
+}
+
+/// Decrements a plugin's active task count when its task ends or is dropped
+struct ActiveTask(Arc<AtomicUsize>);
+
+impl Drop for ActiveTask {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Spawns and tracks plugin tasks according to their sandbox profiles
+#[derive(Clone, Default)]
+pub struct TaskSupervisor {
+    plugins: Arc<RwLock<HashMap<String, Arc<PluginTasks>>>>,
+}
+
+impl TaskSupervisor {
+    /// Create a supervisor where every plugin uses the shared runtime
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a plugin's sandbox profile
+    ///
+    /// Tasks spawned under the previous profile are stopped.
+    pub fn set_profile(&self, plugin_id: &str, profile: SandboxProfile) -> Result<(), String> {
+        let runtime = DedicatedRuntime::start(plugin_id, profile.runtime)?;
+        let tasks = Arc::new(PluginTasks::new(profile, runtime));
+
+        let previous = locks::write(&self.plugins, "plugin tasks").insert(plugin_id.to_string(), tasks);
+        if let Some(previous) = previous {
+            previous.stop();
+        }
+        Ok(())
+    }
+
+    /// Get a plugin's sandbox profile
+    pub fn profile(&self, plugin_id: &str) -> SandboxProfile {
+        locks::read(&self.plugins, "plugin tasks")
+            .get(plugin_id)
+            .map(|tasks| tasks.profile)
+            .unwrap_or_default()
+    }
+
+    /// Spawn a task for a plugin
+    ///
+    /// Plugins with the shared profile must call this from within the
+    /// host's runtime.
+    ///
+    /// # Returns
+    /// Err if the plugin's task budget is exhausted or no runtime is available
+    pub fn spawn<F>(&self, plugin_id: &str, future: F) -> Result<JoinHandle<F::Output>, String>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let tasks = {
+            let mut plugins = locks::write(&self.plugins, "plugin tasks");
+            plugins
+                .entry(plugin_id.to_string())
+                .or_insert_with(|| Arc::new(PluginTasks::new(SandboxProfile::default(), None)))
+                .clone()
+        };
+
+        let active = tasks.active.fetch_add(1, Ordering::Relaxed);
+        let guard = ActiveTask(tasks.active.clone());
+        if let Some(max) = tasks.profile.max_tasks
+            && active >= max
+        {
+            tasks.rejected.fetch_add(1, Ordering::Relaxed);
+            return Err(format!("Plugin '{}' exceeded its budget of {} tasks", plugin_id, max));
+        }
+
+        let task = async move {
+            let _guard = guard;
+            future.await
+        };
+
+        let handle = {
+            let mut runtime = tasks.runtime.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            // A dedicated runtime stopped by `shutdown` is restarted on demand
+            if runtime.is_none() {
+                *runtime = DedicatedRuntime::start(plugin_id, tasks.profile.runtime)?;
+            }
+
+            match runtime.as_ref() {
+                Some(runtime) => runtime.handle.spawn(task),
+                None => tokio::runtime::Handle::try_current()
+                    .map_err(|_| format!("No runtime available to spawn tasks of plugin '{}'", plugin_id))?
+                    .spawn(task),
+            }
+        };
+        tasks.spawned.fetch_add(1, Ordering::Relaxed);
+
+        let mut handles = tasks.handles.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        handles.retain(|handle| !handle.is_finished());
+        handles.push(handle.abort_handle());
+
+        Ok(handle)
+    }
+
+    /// Get a plugin's task counters
+    pub fn metrics(&self, plugin_id: &str) -> TaskMetrics {
+        locks::read(&self.plugins, "plugin tasks")
+            .get(plugin_id)
+            .map(|tasks| tasks.metrics())
+            .unwrap_or_default()
+    }
+
+    /// Get the task counters of every plugin that spawned tasks
+    pub fn all_metrics(&self) -> HashMap<String, TaskMetrics> {
+        locks::read(&self.plugins, "plugin tasks")
+            .iter()
+            .map(|(id, tasks)| (id.clone(), tasks.metrics()))
+            .collect()
+    }
+
+    /// Force-stop every task of a plugin
+    ///
+    /// Called by the registry when a plugin is disabled. The profile is
+    /// kept; a dedicated runtime is restarted when the plugin spawns again.
+    ///
+    /// # Returns
+    /// The plugin's counters at the time it was stopped
+    pub fn shutdown(&self, plugin_id: &str) -> TaskMetrics {
+        let tasks = locks::read(&self.plugins, "plugin tasks").get(plugin_id).cloned();
+
+        match tasks {
+            Some(tasks) => {
+                let metrics = tasks.metrics();
+                tasks.stop();
+                metrics
+            }
+            None => TaskMetrics::default(),
+        }
+    }
+
+    /// Force-stop every task of a plugin and forget its profile
+    ///
+    /// Called by the registry when a plugin is unregistered.
+    pub fn remove(&self, plugin_id: &str) -> TaskMetrics {
+        let metrics = self.shutdown(plugin_id);
+        locks::write(&self.plugins, "plugin tasks").remove(plugin_id);
+        metrics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dedicated_runtime_enforces_budget() {
+        let supervisor = TaskSupervisor::new();
+        supervisor
+            .set_profile("heavy", SandboxProfile::dedicated(RuntimeKind::CurrentThread).with_max_tasks(2))
+            .unwrap();
+
+        // Runs without a host runtime, on the plugin's own thread
+        let thread = supervisor
+            .spawn("heavy", async { std::thread::current().name().map(str::to_string) })
+            .unwrap();
+        let name = block_on(thread).unwrap();
+        assert_eq!(name.as_deref(), Some("volt-plugin-heavy"));
+
+        for _ in 0..2 {
+            supervisor
+                .spawn("heavy", async { tokio::time::sleep(Duration::from_secs(60)).await })
+                .unwrap();
+        }
+        assert!(supervisor.spawn("heavy", async {}).is_err());
+        assert_eq!(
+            supervisor.metrics("heavy"),
+            TaskMetrics {
+                spawned: 3,
+                active: 2,
+                rejected: 1
+            }
+        );
+
+        let stopped = supervisor.shutdown("heavy");
+        assert_eq!(stopped.active, 2);
+        assert_eq!(supervisor.metrics("heavy").active, 0);
+
+        // The runtime comes back for the next task
+        assert_eq!(block_on(supervisor.spawn("heavy", async { 7 }).unwrap()).unwrap(), 7);
+        assert_eq!(supervisor.remove("heavy").spawned, 4);
+        assert_eq!(supervisor.profile("heavy"), SandboxProfile::default());
+    }
+
+    #[test]
+    fn test_shared_profile_needs_host_runtime() {
+        let supervisor = TaskSupervisor::new();
+        assert!(supervisor.spawn("light", async {}).is_err());
+
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let value = runtime.block_on(async { supervisor.spawn("light", async { 42 }).unwrap().await });
+        assert_eq!(value.unwrap(), 42);
+        assert_eq!(supervisor.metrics("light").active, 0);
+    }
+
+    /// Wait for a join handle from outside any runtime
+    fn block_on<T>(handle: JoinHandle<T>) -> Result<T, tokio::task::JoinError> {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(handle)
+    }
+}
@@ -0,0 +1,116 @@
+/// Text selected in the application the launcher was opened from
+///
+/// Plugins such as "define word" or "translate selection" act on whatever
+/// the user had selected before summoning the launcher. The host reads it
+/// through the platform's accessibility APIs and installs the reader with
+/// `VoltPluginAPI::set_selection_reader`. For each launcher activation it
+/// takes a fresh `Selection` from `VoltPluginAPI::activation_selection` and
+/// attaches it to every query with `QueryContext::with_selection`; plugins
+/// then call `QueryContext::selection()`.
+///
+/// The selection is read at most once per activation, on first use, and
+/// only plugins declaring the `selection` permission get to see it.
+use std::sync::{Arc, OnceLock};
+
+/// Longest selection handed to plugins, in characters
+pub const MAX_SELECTION_CHARS: usize = 16 * 1024;
+
+/// Callback installed by the host to read the selected text
+///
+/// Must read from the application that was frontmost when the launcher
+/// opened, not from the launcher itself. Returns None when nothing is
+/// selected or the application doesn't expose its selection.
+pub type SelectionReader = Arc<dyn Fn() -> Option<String> + Send + Sync>;
+
+/// Decides whether a plugin may read the selection
+type SelectionGate = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// The selection of one launcher activation
+///
+/// Cheap to clone; all clones share the cached text.
+#[derive(Clone)]
+pub struct Selection {
+    reader: Option<SelectionReader>,
+    allowed: SelectionGate,
+    text: Arc<OnceLock<Option<String>>>,
+}
+
+impl Selection {
+    /// Create the selection of a new activation
+    ///
+    /// # Arguments
+    /// * `reader` - Reads the selected text, None if the host can't
+    /// * `allowed` - Whether a plugin may read the selection
+    pub fn new(reader: Option<SelectionReader>, allowed: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            reader,
+            allowed: Arc::new(allowed),
+            text: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// Get the selected text on behalf of a plugin
+    ///
+    /// # Returns
+    /// None if nothing is selected or the plugin isn't allowed to read it
+    pub fn read(&self, plugin_id: &str) -> Option<String> {
+        if !(self.allowed)(plugin_id) {
+            return None;
+        }
+
+        self.text
+            .get_or_init(|| {
+                let text = (self.reader.as_ref()?)()?;
+                let text = text.trim();
+                (!text.is_empty()).then(|| text.chars().take(MAX_SELECTION_CHARS).collect())
+            })
+            .clone()
+    }
+}
+
+impl std::fmt::Debug for Selection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Selection")
+            .field("read", &self.text.get().is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Two handles are equal when they belong to the same activation
+impl PartialEq for Selection {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.text, &other.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_selection_is_read_once_per_activation() {
+        let reads = Arc::new(AtomicUsize::new(0));
+        let counter = reads.clone();
+        let reader: SelectionReader = Arc::new(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Some("  serendipity \n".to_string())
+        });
+
+        let selection = Selection::new(Some(reader.clone()), |plugin_id| plugin_id == "dictionary");
+        assert_eq!(selection.read("clipboard"), None);
+        assert_eq!(reads.load(Ordering::SeqCst), 0);
+        assert_eq!(selection.read("dictionary").as_deref(), Some("serendipity"));
+        assert_eq!(selection.clone().read("dictionary").as_deref(), Some("serendipity"));
+        assert_eq!(reads.load(Ordering::SeqCst), 1);
+
+        let next = Selection::new(Some(reader), |_| true);
+        assert_ne!(next, selection);
+        next.read("dictionary");
+        assert_eq!(reads.load(Ordering::SeqCst), 2);
+
+        let empty = Selection::new(Some(Arc::new(|| Some(" ".to_string()))), |_| true);
+        assert_eq!(empty.read("dictionary"), None);
+        assert_eq!(Selection::new(None, |_| true).read("dictionary"), None);
+    }
+}
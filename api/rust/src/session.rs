@@ -0,0 +1,320 @@
+/// Ephemeral per-plugin state for multi-keystroke interactions
+///
+/// Drill-down flows ("pick a repository, then a branch") need to remember
+/// earlier steps between keystrokes. The host keeps one `SessionStore` while
+/// the launcher window is open, attaches it to every query with
+/// `QueryContext::with_session`, and clears it when the window closes.
+/// Plugins reach their own share through `QueryContext::session_store()`.
+///
+/// Nothing is written to disk. Entries expire after a TTL, and each plugin's
+/// share is bounded: writing past the limits evicts the plugin's least
+/// recently written entries.
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Bounds of each plugin's session state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionLimits {
+    /// Lifetime of an entry, unless set with `SessionScope::set_with_ttl`
+    pub ttl: Duration,
+    /// Entries kept per plugin
+    pub max_entries: usize,
+    /// Bytes kept per plugin, keys included
+    pub max_bytes: usize,
+    /// Largest single value, as serialized JSON
+    pub max_value_bytes: usize,
+}
+
+impl Default for SessionLimits {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(300),
+            max_entries: 64,
+            max_bytes: 256 * 1024,
+            max_value_bytes: 64 * 1024,
+        }
+    }
+}
+
+/// A stored value
+struct Entry {
+    value: Value,
+    /// Serialized size of the key and value
+    size: usize,
+    expires_at: Instant,
+    /// Write sequence number, lowest is evicted first
+    written: u64,
+}
+
+/// Entries of one plugin
+#[derive(Default)]
+struct PluginSession {
+    entries: HashMap<String, Entry>,
+    bytes: usize,
+}
+
+impl PluginSession {
+    fn remove(&mut self, key: &str) -> Option<Entry> {
+        let entry = self.entries.remove(key)?;
+        self.bytes -= entry.size;
+        Some(entry)
+    }
+
+    fn purge_expired(&mut self, now: Instant) {
+        let expired: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.expires_at <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in expired {
+            self.remove(&key);
+        }
+    }
+
+    /// Evict the oldest writes until the session fits its limits
+    fn enforce(&mut self, limits: &SessionLimits) {
+        while self.entries.len() > limits.max_entries || self.bytes > limits.max_bytes {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.written)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.remove(&oldest);
+        }
+    }
+}
+
+#[derive(Default)]
+struct SessionState {
+    sessions: HashMap<String, PluginSession>,
+    next_write: u64,
+}
+
+/// In-memory state of every plugin, shared by the queries of one launcher
+/// session
+#[derive(Clone, Default)]
+pub struct SessionStore {
+    limits: SessionLimits,
+    state: Arc<Mutex<SessionState>>,
+}
+
+impl SessionStore {
+    /// Create an empty store with the default limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty store with custom limits
+    pub fn with_limits(limits: SessionLimits) -> Self {
+        Self {
+            limits,
+            state: Arc::default(),
+        }
+    }
+
+    /// Get the limits applied to each plugin
+    pub fn limits(&self) -> SessionLimits {
+        self.limits
+    }
+
+    /// Get a plugin's share of the store
+    pub fn scope(&self, plugin_id: impl Into<String>) -> SessionScope {
+        SessionScope {
+            store: self.clone(),
+            plugin_id: plugin_id.into(),
+        }
+    }
+
+    /// Forget the state of every plugin
+    ///
+    /// Call this when the launcher window closes.
+    pub fn clear(&self) {
+        self.state().sessions.clear();
+    }
+
+    /// Get the bytes a plugin currently uses, expired entries included
+    pub fn bytes_used(&self, plugin_id: &str) -> usize {
+        self.state()
+            .sessions
+            .get(plugin_id)
+            .map_or(0, |session| session.bytes)
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, SessionState> {
+        self.state.lock().unwrap_or_else(|p| p.into_inner())
+    }
+}
+
+impl std::fmt::Debug for SessionStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SessionStore").field("limits", &self.limits).finish_non_exhaustive()
+    }
+}
+
+/// Two handles are equal when they share the same state
+impl PartialEq for SessionStore {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+/// One plugin's share of a `SessionStore`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionScope {
+    store: SessionStore,
+    plugin_id: String,
+}
+
+impl SessionScope {
+    /// Get the plugin owning this scope
+    pub fn plugin_id(&self) -> &str {
+        &self.plugin_id
+    }
+
+    /// Get a value, if set, unexpired and of the requested type
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let mut state = self.store.state();
+        let session = state.sessions.get_mut(&self.plugin_id)?;
+        session.purge_expired(Instant::now());
+
+        let entry = session.entries.get(key)?;
+        serde_json::from_value(entry.value.clone()).ok()
+    }
+
+    /// Set a value for the default TTL
+    pub fn set<T: Serialize>(&self, key: &str, value: &T) -> Result<(), String> {
+        self.set_with_ttl(key, value, self.store.limits.ttl)
+    }
+
+    /// Set a value for a specific lifetime
+    ///
+    /// # Returns
+    /// An error if the value can't be serialized or exceeds
+    /// `SessionLimits::max_value_bytes`
+    pub fn set_with_ttl<T: Serialize>(&self, key: &str, value: &T, ttl: Duration) -> Result<(), String> {
+        let value = serde_json::to_value(value).map_err(|e| format!("Failed to serialize session value: {}", e))?;
+        let value_size = serde_json::to_vec(&value)
+            .map_err(|e| format!("Failed to serialize session value: {}", e))?
+            .len();
+        let limits = self.store.limits;
+        if value_size > limits.max_value_bytes {
+            return Err(format!(
+                "Session value '{}' is {} bytes, more than the {} allowed",
+                key, value_size, limits.max_value_bytes
+            ));
+        }
+
+        let now = Instant::now();
+        let mut state = self.store.state();
+        state.next_write += 1;
+        let written = state.next_write;
+
+        let session = state.sessions.entry(self.plugin_id.clone()).or_default();
+        session.purge_expired(now);
+        session.remove(key);
+
+        let size = key.len() + value_size;
+        session.bytes += size;
+        session.entries.insert(
+            key.to_string(),
+            Entry {
+                value,
+                size,
+                expires_at: now + ttl,
+                written,
+            },
+        );
+        session.enforce(&limits);
+        Ok(())
+    }
+
+    /// Remove a value
+    ///
+    /// # Returns
+    /// Whether the key was set
+    pub fn remove(&self, key: &str) -> bool {
+        self.store
+            .state()
+            .sessions
+            .get_mut(&self.plugin_id)
+            .and_then(|session| session.remove(key))
+            .is_some()
+    }
+
+    /// Forget all of the plugin's state
+    pub fn clear(&self) {
+        self.store.state().sessions.remove(&self.plugin_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::QueryContext;
+
+    #[test]
+    fn test_typed_values_are_scoped_per_plugin() {
+        let store = SessionStore::new();
+        let context = QueryContext::new("repo volt").with_session(store.clone());
+        assert!(context.session_store().is_none());
+
+        let git = context.scoped_to("git").session_store().unwrap();
+        git.set("repo", &"volt").unwrap();
+        git.set("depth", &2u32).unwrap();
+        assert_eq!(git.get::<String>("repo").as_deref(), Some("volt"));
+        assert_eq!(git.get::<u32>("depth"), Some(2));
+        assert_eq!(git.get::<u32>("repo"), None);
+        assert_eq!(store.scope("files").get::<String>("repo"), None);
+
+        assert!(git.remove("depth"));
+        assert!(!git.remove("depth"));
+        store.clear();
+        assert_eq!(git.get::<String>("repo"), None);
+        assert_eq!(store.bytes_used("git"), 0);
+    }
+
+    #[test]
+    fn test_entries_expire() {
+        let scope = SessionStore::new().scope("git");
+        scope.set_with_ttl("step", &1, Duration::ZERO).unwrap();
+        scope.set("repo", &"volt").unwrap();
+
+        assert_eq!(scope.get::<u32>("step"), None);
+        assert_eq!(scope.get::<String>("repo").as_deref(), Some("volt"));
+    }
+
+    #[test]
+    fn test_limits_evict_oldest_writes() {
+        let store = SessionStore::with_limits(SessionLimits {
+            max_entries: 2,
+            max_bytes: 32,
+            max_value_bytes: 16,
+            ..SessionLimits::default()
+        });
+        let scope = store.scope("git");
+
+        assert!(scope.set("big", &"x".repeat(20)).is_err());
+
+        scope.set("a", &1).unwrap();
+        scope.set("b", &2).unwrap();
+        scope.set("a", &3).unwrap();
+        scope.set("c", &4).unwrap();
+        assert_eq!(scope.get::<u32>("b"), None);
+        assert_eq!(scope.get::<u32>("a"), Some(3));
+        assert_eq!(scope.get::<u32>("c"), Some(4));
+
+        // 17 bytes each, so only one fits
+        scope.set("d", &"x".repeat(14)).unwrap();
+        scope.set("e", &"y".repeat(14)).unwrap();
+        assert_eq!(scope.get::<String>("d"), None);
+        assert_eq!(store.bytes_used("git"), 17);
+    }
+}
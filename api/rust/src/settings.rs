@@ -0,0 +1,646 @@
+/// Settings pages contributed by plugins
+///
+/// Plugins with more settings than a flat form can hold describe pages of
+/// sections through the `SettingsProvider` extension. Sections and fields
+/// can be shown only when another field has a given value, and buttons call
+/// back into the plugin with `SettingsProvider::settings_action`.
+///
+/// Pages are serialized for the frontend, which renders them; the values it
+/// submits are checked here with `SettingsPage::check_values` before they
+/// are saved, so a buggy or outdated frontend can't store invalid settings.
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+
+/// A page of settings, saved as the plugin configuration named after its ID
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsPage {
+    /// Identifier, unique within the plugin; also the configuration name
+    pub id: String,
+    /// Page title
+    pub title: String,
+    /// Sections, in display order
+    pub sections: Vec<SettingsSection>,
+}
+
+/// A titled group of fields
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsSection {
+    /// Identifier, unique within the page
+    pub id: String,
+    /// Section title
+    pub title: String,
+    /// Help text shown under the title
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Fields, in display order
+    pub fields: Vec<SettingsField>,
+    /// Show the section only when this holds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visible_when: Option<Condition>,
+}
+
+/// A setting, or a button
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsField {
+    /// Key of the value in the saved configuration, unique within the page
+    pub key: String,
+    /// Label shown next to the control
+    pub label: String,
+    /// Help text shown under the control
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// How the value is edited
+    pub control: Control,
+    /// Show the field only when this holds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visible_when: Option<Condition>,
+}
+
+/// Control editing a field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum Control {
+    /// On/off switch
+    Toggle {
+        /// Value until the user changes it
+        default: bool,
+    },
+    /// Single-line text input
+    Text {
+        /// Value until the user changes it
+        default: String,
+        /// Hint shown while the input is empty
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        placeholder: Option<String>,
+        /// Longest accepted value, in characters
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_length: Option<usize>,
+        /// Mask the value, e.g. for API tokens
+        #[serde(default)]
+        secret: bool,
+    },
+    /// Numeric input
+    Number {
+        /// Value until the user changes it
+        default: f64,
+        /// Smallest accepted value
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min: Option<f64>,
+        /// Largest accepted value
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max: Option<f64>,
+    },
+    /// Choice between fixed options
+    Select {
+        /// Value until the user changes it, one of `options`
+        default: String,
+        /// Available options, in display order
+        options: Vec<SelectOption>,
+    },
+    /// Button calling `SettingsProvider::settings_action`; holds no value
+    Button {
+        /// Action identifier passed to the plugin
+        action: String,
+    },
+}
+
+/// An option of a `Control::Select`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct SelectOption {
+    /// Saved value
+    pub value: String,
+    /// Label shown to the user
+    pub label: String,
+}
+
+/// Visibility condition on another field of the same page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-bindings", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct Condition {
+    /// Key of the field the condition depends on
+    pub field: String,
+    /// Value the field must have
+    pub equals: Value,
+}
+
+impl SettingsPage {
+    /// Create an empty page
+    pub fn new(id: impl Into<String>, title: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            title: title.into(),
+            sections: Vec::new(),
+        }
+    }
+
+    /// Add a section
+    pub fn with_section(mut self, section: SettingsSection) -> Self {
+        self.sections.push(section);
+        self
+    }
+
+    /// Iterate over the fields of every section
+    pub fn fields(&self) -> impl Iterator<Item = &SettingsField> {
+        self.sections.iter().flat_map(|section| &section.fields)
+    }
+
+    /// Find a field by key
+    pub fn field(&self, key: &str) -> Option<&SettingsField> {
+        self.fields().find(|field| field.key == key)
+    }
+
+    /// Get the default value of every field holding one
+    pub fn defaults(&self) -> Value {
+        Value::Object(
+            self.fields()
+                .filter_map(|field| Some((field.key.clone(), field.control.default_value()?)))
+                .collect(),
+        )
+    }
+
+    /// Check that the page is well-formed
+    ///
+    /// Keys and IDs must be unique, conditions must refer to a field of the
+    /// page holding a value, and defaults must be accepted by their control.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id.trim().is_empty() {
+            return Err("Settings page ID is empty".to_string());
+        }
+
+        let mut section_ids = HashSet::new();
+        let mut keys = HashSet::new();
+        for section in &self.sections {
+            if !section_ids.insert(section.id.as_str()) {
+                return Err(format!(
+                    "Duplicate section '{}' on page '{}'",
+                    section.id, self.id
+                ));
+            }
+            for field in &section.fields {
+                if field.key.trim().is_empty() {
+                    return Err(format!("Field without key in section '{}'", section.id));
+                }
+                if !keys.insert(field.key.as_str()) {
+                    return Err(format!(
+                        "Duplicate field '{}' on page '{}'",
+                        field.key, self.id
+                    ));
+                }
+                field
+                    .control
+                    .validate()
+                    .map_err(|e| format!("Field '{}': {}", field.key, e))?;
+            }
+        }
+
+        let conditions = self
+            .sections
+            .iter()
+            .filter_map(|section| section.visible_when.as_ref())
+            .chain(
+                self.fields()
+                    .filter_map(|field| field.visible_when.as_ref()),
+            );
+        for condition in conditions {
+            let holds_value = self
+                .field(&condition.field)
+                .is_some_and(|field| field.control.default_value().is_some());
+            if !holds_value {
+                return Err(format!(
+                    "Visibility condition on page '{}' refers to unknown field '{}'",
+                    self.id, condition.field
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check values submitted by the frontend before saving them
+    ///
+    /// Missing values take their default. Values of hidden fields are not
+    /// checked, since the user couldn't see them.
+    ///
+    /// # Arguments
+    /// * `values` - JSON object of values keyed by field key
+    pub fn check_values(&self, values: &Value) -> Result<(), String> {
+        let values = values
+            .as_object()
+            .ok_or_else(|| format!("Settings of page '{}' must be an object", self.id))?;
+        let resolved = self.resolve(values);
+
+        for key in values.keys() {
+            let holds_value = self
+                .field(key)
+                .is_some_and(|field| field.control.default_value().is_some());
+            if !holds_value {
+                return Err(format!("Unknown setting '{}' on page '{}'", key, self.id));
+            }
+        }
+
+        for section in &self.sections {
+            if !Self::holds(section.visible_when.as_ref(), &resolved) {
+                continue;
+            }
+            for field in &section.fields {
+                if !Self::holds(field.visible_when.as_ref(), &resolved) {
+                    continue;
+                }
+                if let Some(value) = values.get(&field.key) {
+                    field
+                        .control
+                        .check(value)
+                        .map_err(|e| format!("Invalid setting '{}': {}", field.label, e))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check if a button is shown for the given values
+    pub fn has_action(&self, action: &str, values: &Value) -> bool {
+        let resolved = self.resolve(values.as_object().unwrap_or(&Map::new()));
+
+        self.sections
+            .iter()
+            .filter(|section| Self::holds(section.visible_when.as_ref(), &resolved))
+            .flat_map(|section| &section.fields)
+            .filter(|field| Self::holds(field.visible_when.as_ref(), &resolved))
+            .any(|field| matches!(&field.control, Control::Button { action: a } if a == action))
+    }
+
+    /// Merge submitted values over the defaults
+    fn resolve(&self, values: &Map<String, Value>) -> Map<String, Value> {
+        let mut resolved = match self.defaults() {
+            Value::Object(defaults) => defaults,
+            _ => Map::new(),
+        };
+        resolved.extend(
+            values
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+        resolved
+    }
+
+    fn holds(condition: Option<&Condition>, values: &Map<String, Value>) -> bool {
+        condition.is_none_or(|condition| values.get(&condition.field) == Some(&condition.equals))
+    }
+}
+
+impl SettingsSection {
+    /// Create an empty section
+    pub fn new(id: impl Into<String>, title: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            title: title.into(),
+            description: None,
+            fields: Vec::new(),
+            visible_when: None,
+        }
+    }
+
+    /// Set the help text
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Add a field
+    pub fn with_field(mut self, field: SettingsField) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    /// Show the section only when a field has a value
+    pub fn visible_when(mut self, field: impl Into<String>, equals: impl Into<Value>) -> Self {
+        self.visible_when = Some(Condition {
+            field: field.into(),
+            equals: equals.into(),
+        });
+        self
+    }
+}
+
+impl SettingsField {
+    /// Create a field with any control
+    pub fn new(key: impl Into<String>, label: impl Into<String>, control: Control) -> Self {
+        Self {
+            key: key.into(),
+            label: label.into(),
+            description: None,
+            control,
+            visible_when: None,
+        }
+    }
+
+    /// Create an on/off switch
+    pub fn toggle(key: impl Into<String>, label: impl Into<String>, default: bool) -> Self {
+        Self::new(key, label, Control::Toggle { default })
+    }
+
+    /// Create a text input
+    pub fn text(
+        key: impl Into<String>,
+        label: impl Into<String>,
+        default: impl Into<String>,
+    ) -> Self {
+        Self::new(
+            key,
+            label,
+            Control::Text {
+                default: default.into(),
+                placeholder: None,
+                max_length: None,
+                secret: false,
+            },
+        )
+    }
+
+    /// Create a numeric input accepting values between `min` and `max`
+    pub fn number(
+        key: impl Into<String>,
+        label: impl Into<String>,
+        default: f64,
+        min: f64,
+        max: f64,
+    ) -> Self {
+        Self::new(
+            key,
+            label,
+            Control::Number {
+                default,
+                min: Some(min),
+                max: Some(max),
+            },
+        )
+    }
+
+    /// Create a choice between `(value, label)` options
+    pub fn select<V: Into<String>, L: Into<String>>(
+        key: impl Into<String>,
+        label: impl Into<String>,
+        default: impl Into<String>,
+        options: impl IntoIterator<Item = (V, L)>,
+    ) -> Self {
+        let options = options
+            .into_iter()
+            .map(|(value, label)| SelectOption {
+                value: value.into(),
+                label: label.into(),
+            })
+            .collect();
+        Self::new(
+            key,
+            label,
+            Control::Select {
+                default: default.into(),
+                options,
+            },
+        )
+    }
+
+    /// Create a button calling the plugin back
+    pub fn button(
+        key: impl Into<String>,
+        label: impl Into<String>,
+        action: impl Into<String>,
+    ) -> Self {
+        Self::new(
+            key,
+            label,
+            Control::Button {
+                action: action.into(),
+            },
+        )
+    }
+
+    /// Set the help text
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Show the field only when another field has a value
+    pub fn visible_when(mut self, field: impl Into<String>, equals: impl Into<Value>) -> Self {
+        self.visible_when = Some(Condition {
+            field: field.into(),
+            equals: equals.into(),
+        });
+        self
+    }
+}
+
+impl Control {
+    /// Get the default value, or None for buttons
+    pub fn default_value(&self) -> Option<Value> {
+        match self {
+            Control::Toggle { default } => Some(Value::Bool(*default)),
+            Control::Text { default, .. } | Control::Select { default, .. } => {
+                Some(Value::String(default.clone()))
+            }
+            Control::Number { default, .. } => Some(Value::from(*default)),
+            Control::Button { .. } => None,
+        }
+    }
+
+    /// Check that the control is consistent and accepts its default
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            Control::Number {
+                min: Some(min),
+                max: Some(max),
+                ..
+            } if min > max => {
+                return Err(format!("minimum {} is above maximum {}", min, max));
+            }
+            Control::Select { options, .. } => {
+                if options.is_empty() {
+                    return Err("select without options".to_string());
+                }
+                let mut values = HashSet::new();
+                if let Some(option) = options.iter().find(|option| !values.insert(&option.value)) {
+                    return Err(format!("duplicate option '{}'", option.value));
+                }
+            }
+            Control::Button { action } if action.trim().is_empty() => {
+                return Err("button without action".to_string());
+            }
+            _ => {}
+        }
+
+        match self.default_value() {
+            Some(default) => self
+                .check(&default)
+                .map_err(|e| format!("invalid default: {}", e)),
+            None => Ok(()),
+        }
+    }
+
+    /// Check a submitted value
+    fn check(&self, value: &Value) -> Result<(), String> {
+        match self {
+            Control::Toggle { .. } => value
+                .as_bool()
+                .map(|_| ())
+                .ok_or_else(|| "expected true or false".to_string()),
+            Control::Text { max_length, .. } => {
+                let text = value.as_str().ok_or("expected text")?;
+                match max_length {
+                    Some(max_length) if text.chars().count() > *max_length => {
+                        Err(format!("longer than {} characters", max_length))
+                    }
+                    _ => Ok(()),
+                }
+            }
+            Control::Number { min, max, .. } => {
+                let number = value.as_f64().ok_or("expected a number")?;
+                if min.is_some_and(|min| number < min) || max.is_some_and(|max| number > max) {
+                    return Err(format!(
+                        "{} is outside {}..={}",
+                        number,
+                        min.map_or("".to_string(), |min| min.to_string()),
+                        max.map_or("".to_string(), |max| max.to_string())
+                    ));
+                }
+                Ok(())
+            }
+            Control::Select { options, .. } => {
+                let selected = value.as_str().ok_or("expected an option")?;
+                if options.iter().any(|option| option.value == selected) {
+                    Ok(())
+                } else {
+                    Err(format!("'{}' is not one of the options", selected))
+                }
+            }
+            Control::Button { .. } => Err("buttons hold no value".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sync_page() -> SettingsPage {
+        SettingsPage::new("sync", "Sync")
+            .with_section(
+                SettingsSection::new("general", "General")
+                    .with_field(SettingsField::toggle("enabled", "Sync notes", false))
+                    .with_field(SettingsField::select(
+                        "provider",
+                        "Provider",
+                        "webdav",
+                        [("webdav", "WebDAV"), ("s3", "Amazon S3")],
+                    )),
+            )
+            .with_section(
+                SettingsSection::new("webdav", "WebDAV")
+                    .visible_when("provider", "webdav")
+                    .with_field(SettingsField::text("url", "Server URL", "https://"))
+                    .with_field(SettingsField::number(
+                        "interval",
+                        "Interval (min)",
+                        15.0,
+                        1.0,
+                        1440.0,
+                    ))
+                    .with_field(
+                        SettingsField::button("test", "Test connection", "testConnection")
+                            .visible_when("enabled", true),
+                    ),
+            )
+    }
+
+    #[test]
+    fn test_page_validation() {
+        let page = sync_page();
+        page.validate().unwrap();
+        assert_eq!(
+            page.defaults(),
+            json!({"enabled": false, "provider": "webdav", "url": "https://", "interval": 15.0})
+        );
+
+        let duplicate = sync_page().with_section(
+            SettingsSection::new("more", "More")
+                .with_field(SettingsField::toggle("enabled", "Again", true)),
+        );
+        assert!(duplicate
+            .validate()
+            .unwrap_err()
+            .contains("Duplicate field 'enabled'"));
+
+        let dangling = sync_page()
+            .with_section(SettingsSection::new("s3", "S3").visible_when("test", "clicked"));
+        assert!(dangling
+            .validate()
+            .unwrap_err()
+            .contains("unknown field 'test'"));
+
+        let bad_default =
+            SettingsPage::new("p", "P").with_section(SettingsSection::new("s", "S").with_field(
+                SettingsField::select("mode", "Mode", "fast", [("slow", "Slow")]),
+            ));
+        assert!(bad_default
+            .validate()
+            .unwrap_err()
+            .contains("invalid default"));
+    }
+
+    #[test]
+    fn test_values_are_checked_when_visible() {
+        let page = sync_page();
+        page.check_values(&json!({"enabled": true, "interval": 30}))
+            .unwrap();
+
+        assert!(page.check_values(&json!({"interval": 0})).is_err());
+        assert!(page.check_values(&json!({"provider": "ftp"})).is_err());
+        assert!(page.check_values(&json!({"enabled": "yes"})).is_err());
+        assert!(page.check_values(&json!({"test": true})).is_err());
+        assert!(page.check_values(&json!({"token": "x"})).is_err());
+        assert!(page.check_values(&json!([1])).is_err());
+
+        // The WebDAV section is hidden for S3, so its values aren't checked
+        page.check_values(&json!({"provider": "s3", "interval": 0}))
+            .unwrap();
+
+        assert!(!page.has_action("testConnection", &json!({})));
+        assert!(page.has_action("testConnection", &json!({"enabled": true})));
+        assert!(!page.has_action(
+            "testConnection",
+            &json!({"enabled": true, "provider": "s3"})
+        ));
+    }
+
+    #[test]
+    fn test_wire_format() {
+        let field =
+            SettingsField::toggle("enabled", "Sync notes", false).visible_when("provider", "s3");
+        assert_eq!(
+            serde_json::to_value(&field).unwrap(),
+            json!({
+                "key": "enabled",
+                "label": "Sync notes",
+                "control": {"kind": "toggle", "default": false},
+                "visibleWhen": {"field": "provider", "equals": "s3"},
+            })
+        );
+    }
+}
@@ -0,0 +1,256 @@
+/// Spelling correction for queries
+///
+/// Generates candidates within a small edit distance (insertions,
+/// deletions, substitutions and adjacent transpositions) from a frequency
+/// dictionary, so plugins and the router can recover from typos like
+/// "chrmoe" → "chrome". The host decides whether correction is enabled
+/// (see `VoltPluginAPI::set_spell_correction`), and results produced from
+/// a corrected query are marked with the original query.
+use crate::plugin::QueryContext;
+use crate::result::PluginResult;
+use std::collections::HashMap;
+
+/// Words every corrector starts with: launcher vocabulary and common app names
+///
+/// Frequencies are relative; higher wins between equally close candidates.
+const BUILTIN_WORDS: &[(&str, u32)] = &[
+    ("app", 60),
+    ("apps", 40),
+    ("brave", 20),
+    ("calculator", 50),
+    ("calendar", 40),
+    ("chrome", 90),
+    ("clipboard", 40),
+    ("code", 70),
+    ("discord", 50),
+    ("docs", 30),
+    ("downloads", 40),
+    ("edge", 30),
+    ("emoji", 40),
+    ("explorer", 40),
+    ("file", 60),
+    ("files", 60),
+    ("finder", 40),
+    ("firefox", 80),
+    ("folder", 40),
+    ("github", 50),
+    ("google", 60),
+    ("mail", 40),
+    ("music", 40),
+    ("notes", 50),
+    ("obsidian", 30),
+    ("outlook", 30),
+    ("password", 30),
+    ("photos", 30),
+    ("restart", 30),
+    ("safari", 40),
+    ("search", 60),
+    ("settings", 70),
+    ("shutdown", 30),
+    ("slack", 50),
+    ("spotify", 60),
+    ("steam", 40),
+    ("teams", 40),
+    ("terminal", 70),
+    ("timer", 40),
+    ("translate", 30),
+    ("weather", 30),
+    ("zoom", 40),
+];
+
+/// A corrected query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Correction {
+    /// Query as typed
+    pub original: String,
+    /// Query with misspelled words replaced
+    pub corrected: String,
+}
+
+impl Correction {
+    /// Build the context for running the corrected query
+    pub fn context(&self, context: &QueryContext) -> QueryContext {
+        let mut corrected = context.clone();
+        corrected.query = self.corrected.clone();
+        corrected.corrected_from = Some(self.original.clone());
+        corrected
+    }
+
+    /// Mark results as produced from the corrected query
+    pub fn mark(&self, results: &mut [PluginResult]) {
+        for result in results {
+            result.corrected_from = Some(self.original.clone());
+        }
+    }
+}
+
+/// Dictionary-based spelling corrector
+#[derive(Debug, Clone)]
+pub struct SpellCorrector {
+    words: HashMap<String, u32>,
+}
+
+impl Default for SpellCorrector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpellCorrector {
+    /// Create a corrector with the built-in dictionary
+    pub fn new() -> Self {
+        Self::empty().with_words(BUILTIN_WORDS.iter().map(|(word, frequency)| (*word, *frequency)))
+    }
+
+    /// Create a corrector without any words
+    pub fn empty() -> Self {
+        Self { words: HashMap::new() }
+    }
+
+    /// Add words, e.g. a plugin's own vocabulary
+    ///
+    /// Frequencies of words already known are added together.
+    pub fn with_words<'a>(mut self, words: impl IntoIterator<Item = (&'a str, u32)>) -> Self {
+        for (word, frequency) in words {
+            self.add_word(word, frequency);
+        }
+        self
+    }
+
+    /// Add a word to the dictionary
+    pub fn add_word(&mut self, word: &str, frequency: u32) {
+        let word = word.trim().to_lowercase();
+        if !word.is_empty() {
+            *self.words.entry(word).or_insert(0) += frequency;
+        }
+    }
+
+    /// Check if a word is in the dictionary
+    pub fn knows(&self, word: &str) -> bool {
+        self.words.contains_key(&word.to_lowercase())
+    }
+
+    /// Get dictionary words close to a word, best first
+    ///
+    /// Up to one edit is allowed for words of 4 characters or less and two
+    /// edits for longer ones. Candidates are ordered by distance, then by
+    /// frequency.
+    pub fn candidates(&self, word: &str) -> Vec<String> {
+        let word = word.to_lowercase();
+        let word_len = word.chars().count();
+        let max_distance = if word_len <= 4 { 1 } else { 2 };
+
+        let mut candidates: Vec<(usize, u32, &String)> = self
+            .words
+            .iter()
+            .filter(|(candidate, _)| candidate.chars().count().abs_diff(word_len) <= max_distance)
+            .map(|(candidate, frequency)| (edit_distance(&word, candidate), *frequency, candidate))
+            .filter(|(distance, _, _)| *distance <= max_distance)
+            .collect();
+
+        candidates.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)).then(a.2.cmp(b.2)));
+        candidates.into_iter().map(|(_, _, candidate)| candidate.clone()).collect()
+    }
+
+    /// Correct every unknown word of a query
+    ///
+    /// Words shorter than 3 characters, words containing digits, and words
+    /// the dictionary knows are kept as typed.
+    ///
+    /// # Returns
+    /// None if nothing was corrected
+    pub fn correct(&self, query: &str) -> Option<Correction> {
+        let mut changed = false;
+
+        let corrected: Vec<String> = query
+            .split_whitespace()
+            .map(|word| {
+                if word.chars().count() < 3 || word.chars().any(|c| c.is_ascii_digit()) || self.knows(word) {
+                    return word.to_string();
+                }
+                match self.candidates(word).into_iter().next() {
+                    Some(candidate) => {
+                        changed = true;
+                        candidate
+                    }
+                    None => word.to_string(),
+                }
+            })
+            .collect();
+
+        changed.then(|| Correction {
+            original: query.to_string(),
+            corrected: corrected.join(" "),
+        })
+    }
+}
+
+/// Optimal string alignment distance between two words
+///
+/// Levenshtein distance where swapping two adjacent characters counts as
+/// a single edit.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut rows = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("chrmoe", "chrome"), 1);
+        assert_eq!(edit_distance("firefx", "firefox"), 1);
+        assert_eq!(edit_distance("termnial", "terminal"), 1);
+        assert_eq!(edit_distance("steam", "teams"), 2);
+        assert_eq!(edit_distance("", "zoom"), 4);
+    }
+
+    #[test]
+    fn test_queries_are_corrected() {
+        let corrector = SpellCorrector::new().with_words([("volt", 10)]);
+
+        let correction = corrector.correct("open chrmoe").unwrap();
+        assert_eq!(correction.corrected, "open chrome");
+        assert_eq!(corrector.correct("Volt 2+2"), None);
+        assert_eq!(corrector.correct("xyzzy"), None);
+
+        // Equally close candidates are ranked by frequency
+        let pets = SpellCorrector::empty().with_words([("cat", 5), ("car", 9)]);
+        assert_eq!(pets.candidates("caz"), vec!["car", "cat"]);
+
+        let context = correction.context(&QueryContext::new("open chrmoe").with_max_results(8));
+        assert_eq!(context.query, "open chrome");
+        assert_eq!(context.corrected_from.as_deref(), Some("open chrmoe"));
+        assert_eq!(context.max_results, Some(8));
+
+        let mut results = vec![PluginResult::new("chrome", "Google Chrome")];
+        correction.mark(&mut results);
+        assert_eq!(results[0].corrected_from.as_deref(), Some("open chrmoe"));
+    }
+}
@@ -0,0 +1,134 @@
+/// Startup profiling
+///
+/// Records how long each plugin takes to load, construct and initialize so
+/// users can find the plugin that makes the launcher slow to appear.
+use serde::Serialize;
+use std::fmt;
+use std::time::Duration;
+
+/// Phase of a plugin's startup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StartupPhase {
+    /// Reading the manifest and loading the plugin's code
+    Load,
+    /// Creating the plugin instance
+    Construct,
+    /// Running the plugin's `initialize` hook
+    Init,
+}
+
+/// Startup cost of a single plugin
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginStartup {
+    /// Plugin identifier
+    pub plugin_id: String,
+    /// Time spent loading
+    pub load: Duration,
+    /// Time spent constructing
+    pub construct: Duration,
+    /// Time spent initializing
+    pub init: Duration,
+}
+
+impl PluginStartup {
+    /// Create an empty record for a plugin
+    pub fn new(plugin_id: impl Into<String>) -> Self {
+        Self {
+            plugin_id: plugin_id.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Add time spent in a phase
+    pub fn add(&mut self, phase: StartupPhase, duration: Duration) {
+        match phase {
+            StartupPhase::Load => self.load += duration,
+            StartupPhase::Construct => self.construct += duration,
+            StartupPhase::Init => self.init += duration,
+        }
+    }
+
+    /// Total time across all phases
+    pub fn total(&self) -> Duration {
+        self.load + self.construct + self.init
+    }
+}
+
+/// Per-plugin startup costs, most expensive first
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupReport {
+    /// Plugins sorted by total cost, most expensive first
+    pub plugins: Vec<PluginStartup>,
+}
+
+impl StartupReport {
+    /// Build a report, sorting plugins by total cost
+    pub fn new(mut plugins: Vec<PluginStartup>) -> Self {
+        plugins.sort_by(|a, b| {
+            b.total()
+                .cmp(&a.total())
+                .then_with(|| a.plugin_id.cmp(&b.plugin_id))
+        });
+        Self { plugins }
+    }
+
+    /// Total startup time spent in plugins
+    pub fn total(&self) -> Duration {
+        self.plugins.iter().map(PluginStartup::total).sum()
+    }
+}
+
+impl fmt::Display for StartupReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Plugin startup: {} plugins in {:.1}ms",
+            self.plugins.len(),
+            millis(self.total())
+        )?;
+
+        for plugin in &self.plugins {
+            writeln!(
+                f,
+                "  {:>8.1}ms  {} (load {:.1}ms, construct {:.1}ms, init {:.1}ms)",
+                millis(plugin.total()),
+                plugin.plugin_id,
+                millis(plugin.load),
+                millis(plugin.construct),
+                millis(plugin.init)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_is_sorted_by_cost() {
+        let mut calc = PluginStartup::new("calc");
+        calc.add(StartupPhase::Construct, Duration::from_millis(2));
+
+        let mut files = PluginStartup::new("files");
+        files.add(StartupPhase::Load, Duration::from_millis(5));
+        files.add(StartupPhase::Init, Duration::from_millis(40));
+
+        let report = StartupReport::new(vec![calc, files]);
+        assert_eq!(report.plugins[0].plugin_id, "files");
+        assert_eq!(report.total(), Duration::from_millis(47));
+
+        let text = report.to_string();
+        assert!(text.starts_with("Plugin startup: 2 plugins in 47.0ms\n"));
+        assert!(text.contains("45.0ms  files (load 5.0ms, construct 0.0ms, init 40.0ms)"));
+    }
+}
@@ -0,0 +1,384 @@
+/// Local usage statistics of plugins
+///
+/// Lets plugin authors see how their plugin performs on their own machine:
+/// how often it is queried and picked, how fast it answers, how often its
+/// cache is hit and how often it fails. The registry records every
+/// dispatched query and executed result, and `VoltPluginAPI::read_cache`
+/// every cache read; plugins read the figures with `VoltPluginAPI::my_stats`.
+///
+/// Events are counted in hourly buckets kept for `RETENTION_MS`, saved to
+/// the application data directory whenever an hour ends. Nothing here ever
+/// leaves the device.
+use crate::actions::now_millis;
+use crate::logging::Diagnostics;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// File of the application data directory holding the statistics
+pub const STATS_FILE: &str = "usage-stats.json";
+
+/// Time covered by one bucket, in milliseconds
+pub const BUCKET_MS: u64 = 60 * 60 * 1000;
+
+/// How long statistics are kept, in milliseconds
+pub const RETENTION_MS: u64 = 30 * 24 * BUCKET_MS;
+
+/// Windows `VoltPluginAPI::my_stats` reports on unless the host configures others
+pub const DEFAULT_WINDOWS: [Duration; 3] = [
+    Duration::from_secs(60 * 60),
+    Duration::from_secs(24 * 60 * 60),
+    Duration::from_secs(7 * 24 * 60 * 60),
+];
+
+/// Something a plugin did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageEvent {
+    /// The plugin answered, or failed to answer, a query
+    Query {
+        /// Time to answer, in milliseconds
+        latency_ms: u64,
+        /// Number of results returned
+        results: usize,
+        /// Whether the plugin returned an error or panicked
+        failed: bool,
+    },
+    /// The user executed one of the plugin's results
+    Selection {
+        /// Whether executing it failed
+        failed: bool,
+    },
+    /// The plugin read its cache
+    CacheRead {
+        /// Whether the entry was there
+        hit: bool,
+    },
+}
+
+/// Counters of one plugin over one hour
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Bucket {
+    start: u64,
+    queries: u64,
+    answered: u64,
+    latency_total_ms: u64,
+    query_errors: u64,
+    selections: u64,
+    execute_errors: u64,
+    cache_hits: u64,
+    cache_misses: u64,
+}
+
+impl Bucket {
+    fn record(&mut self, event: UsageEvent) {
+        match event {
+            UsageEvent::Query {
+                latency_ms,
+                results,
+                failed,
+            } => {
+                self.queries += 1;
+                self.latency_total_ms += latency_ms;
+                self.answered += u64::from(results > 0 && !failed);
+                self.query_errors += u64::from(failed);
+            }
+            UsageEvent::Selection { failed } => {
+                self.selections += 1;
+                self.execute_errors += u64::from(failed);
+            }
+            UsageEvent::CacheRead { hit: true } => self.cache_hits += 1,
+            UsageEvent::CacheRead { hit: false } => self.cache_misses += 1,
+        }
+    }
+}
+
+/// How a plugin performed over a window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginStats {
+    /// Length of the window, in milliseconds
+    pub window_ms: u64,
+    /// Queries the plugin was dispatched
+    pub queries: u64,
+    /// Queries it returned at least one result for
+    pub answered: u64,
+    /// Results of the plugin the user executed
+    pub selections: u64,
+    /// Share of answered queries that ended with one of its results executed
+    pub selection_rate: Option<f64>,
+    /// Average time to answer a query, in milliseconds
+    pub average_latency_ms: Option<u64>,
+    /// Share of cache reads that found the entry
+    pub cache_hit_rate: Option<f64>,
+    /// Queries that failed or panicked
+    pub query_errors: u64,
+    /// Executions that failed or panicked
+    pub execute_errors: u64,
+}
+
+/// Statistics of a plugin over each configured window, shortest first
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageReport {
+    /// Plugin the statistics are about
+    pub plugin_id: String,
+    /// One entry per window
+    pub windows: Vec<PluginStats>,
+}
+
+#[derive(Default)]
+struct UsageState {
+    buckets: HashMap<String, VecDeque<Bucket>>,
+    path: Option<PathBuf>,
+    loaded: bool,
+    diagnostics: Diagnostics,
+}
+
+impl UsageState {
+    /// Load the saved statistics on first use
+    fn ensure_loaded(&mut self) {
+        if self.loaded {
+            return;
+        }
+        self.loaded = true;
+
+        let Some(path) = &self.path else {
+            return;
+        };
+        match load_buckets(path) {
+            Ok(buckets) => self.buckets = buckets,
+            Err(e) => {
+                self.diagnostics.warn("stats", &format!("Starting usage statistics over: {}", e))
+            }
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let content = serde_json::to_string(&self.buckets)
+            .map_err(|e| format!("Failed to serialize usage statistics: {}", e))?;
+
+        std::fs::write(path, content)
+            .map_err(|e| format!("Failed to write usage statistics: {}", e))
+    }
+}
+
+/// Usage statistics of all plugins, shared by the API and the registry
+#[derive(Clone, Default)]
+pub struct UsageStats {
+    state: Arc<Mutex<UsageState>>,
+}
+
+impl UsageStats {
+    /// Create statistics kept in memory only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create statistics saved to a file, loaded on first use
+    pub fn persistent(path: PathBuf) -> Self {
+        let state = UsageState {
+            path: Some(path),
+            ..UsageState::default()
+        };
+        Self {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Report failures to load or save through an instance's diagnostics
+    pub fn with_diagnostics(self, diagnostics: Diagnostics) -> Self {
+        self.state.lock().unwrap_or_else(|p| p.into_inner()).diagnostics = diagnostics;
+        self
+    }
+
+    /// Record an event that happened now
+    pub fn record(&self, plugin_id: &str, event: UsageEvent) {
+        self.record_at(plugin_id, event, now_millis());
+    }
+
+    /// Record an event
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin the event is about
+    /// * `event` - What happened
+    /// * `now` - When it happened, in milliseconds since the Unix epoch
+    pub fn record_at(&self, plugin_id: &str, event: UsageEvent, now: u64) {
+        let mut state = self.state.lock().unwrap_or_else(|p| p.into_inner());
+        state.ensure_loaded();
+
+        let start = now - now % BUCKET_MS;
+        let buckets = state.buckets.entry(plugin_id.to_string()).or_default();
+        let hour_ended = match buckets.back_mut() {
+            Some(bucket) if bucket.start == start => {
+                bucket.record(event);
+                false
+            }
+            _ => {
+                let mut bucket = Bucket {
+                    start,
+                    ..Bucket::default()
+                };
+                bucket.record(event);
+                buckets.push_back(bucket);
+                while buckets
+                    .front()
+                    .is_some_and(|bucket| bucket.start + RETENTION_MS <= start)
+                {
+                    buckets.pop_front();
+                }
+                true
+            }
+        };
+
+        if hour_ended && let Err(e) = state.save() {
+            state.diagnostics.warn("stats", &e);
+        }
+    }
+
+    /// Get how a plugin performed over the last `window`
+    pub fn stats(&self, plugin_id: &str, window: Duration) -> PluginStats {
+        self.stats_at(plugin_id, window, now_millis())
+    }
+
+    /// Get how a plugin performed over the `window` before `now`
+    ///
+    /// Buckets are counted whole, so the window is rounded up to the hour.
+    pub fn stats_at(&self, plugin_id: &str, window: Duration, now: u64) -> PluginStats {
+        let mut state = self.state.lock().unwrap_or_else(|p| p.into_inner());
+        state.ensure_loaded();
+
+        let window_ms = window.as_millis() as u64;
+        let since = now.saturating_sub(window_ms);
+        let mut total = Bucket::default();
+        for bucket in state.buckets.get(plugin_id).into_iter().flatten() {
+            if bucket.start + BUCKET_MS > since && bucket.start <= now {
+                total.queries += bucket.queries;
+                total.answered += bucket.answered;
+                total.latency_total_ms += bucket.latency_total_ms;
+                total.query_errors += bucket.query_errors;
+                total.selections += bucket.selections;
+                total.execute_errors += bucket.execute_errors;
+                total.cache_hits += bucket.cache_hits;
+                total.cache_misses += bucket.cache_misses;
+            }
+        }
+
+        let ratio = |part: u64, whole: u64| (whole > 0).then(|| part as f64 / whole as f64);
+        let cache_reads = total.cache_hits + total.cache_misses;
+        PluginStats {
+            window_ms,
+            queries: total.queries,
+            answered: total.answered,
+            selections: total.selections,
+            selection_rate: ratio(total.selections, total.answered).map(|rate| rate.min(1.0)),
+            average_latency_ms: (total.queries > 0).then(|| total.latency_total_ms / total.queries),
+            cache_hit_rate: ratio(total.cache_hits, cache_reads),
+            query_errors: total.query_errors,
+            execute_errors: total.execute_errors,
+        }
+    }
+
+    /// Move a renamed plugin's statistics to its new ID
+    pub fn rename(&self, from: &str, to: &str) {
+        let mut state = self.state.lock().unwrap_or_else(|p| p.into_inner());
+        state.ensure_loaded();
+
+        if let Some(buckets) = state.buckets.remove(from) {
+            state.buckets.insert(to.to_string(), buckets);
+        }
+    }
+
+    /// Save the statistics now, e.g. when the host shuts down
+    pub fn save(&self) -> Result<(), String> {
+        let state = self.state.lock().unwrap_or_else(|p| p.into_inner());
+        if !state.loaded {
+            return Ok(());
+        }
+        state.save()
+    }
+}
+
+fn load_buckets(path: &Path) -> Result<HashMap<String, VecDeque<Bucket>>, String> {
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read usage statistics: {}", e))?;
+
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse usage statistics: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-01-01 12:00 UTC
+    const NOON: u64 = 1_704_110_400_000;
+
+    #[test]
+    fn test_windows_and_persistence() {
+        let temp_dir = std::env::temp_dir().join("volt_test_usage_stats");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let path = temp_dir.join(STATS_FILE);
+
+        let stats = UsageStats::persistent(path.clone());
+        let query = |latency_ms, results, failed| UsageEvent::Query {
+            latency_ms,
+            results,
+            failed,
+        };
+        stats.record_at("calc", query(30, 2, false), NOON - 2 * 24 * BUCKET_MS);
+        stats.record_at("calc", query(10, 1, false), NOON);
+        stats.record_at("calc", query(20, 0, false), NOON + 1000);
+        stats.record_at("calc", query(60, 0, true), NOON + 2000);
+        stats.record_at("calc", UsageEvent::Selection { failed: false }, NOON + 3000);
+        stats.record_at("calc", UsageEvent::CacheRead { hit: true }, NOON + 4000);
+        stats.record_at("calc", UsageEvent::CacheRead { hit: false }, NOON + 5000);
+
+        let hour = stats.stats_at("calc", DEFAULT_WINDOWS[0], NOON + BUCKET_MS / 2);
+        assert_eq!(hour.queries, 3);
+        assert_eq!(hour.answered, 1);
+        assert_eq!(hour.selection_rate, Some(1.0));
+        assert_eq!(hour.average_latency_ms, Some(30));
+        assert_eq!(hour.cache_hit_rate, Some(0.5));
+        assert_eq!(hour.query_errors, 1);
+        assert_eq!(stats.stats_at("calc", DEFAULT_WINDOWS[2], NOON).queries, 4);
+        assert_eq!(
+            stats.stats_at("other", DEFAULT_WINDOWS[2], NOON),
+            PluginStats {
+                window_ms: DEFAULT_WINDOWS[2].as_millis() as u64,
+                ..PluginStats::default()
+            }
+        );
+
+        // The hour ending saves everything up to then
+        stats.record_at("calc", query(5, 1, false), NOON + BUCKET_MS);
+        let reloaded = UsageStats::persistent(path);
+        assert_eq!(
+            reloaded
+                .stats_at("calc", DEFAULT_WINDOWS[2], NOON + BUCKET_MS)
+                .queries,
+            5
+        );
+
+        // Old buckets are dropped
+        let later = NOON + BUCKET_MS + RETENTION_MS;
+        stats.record_at("calc", query(5, 1, false), later);
+        assert_eq!(
+            stats
+                .stats_at("calc", Duration::from_millis(2 * RETENTION_MS), later)
+                .queries,
+            1
+        );
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
@@ -0,0 +1,417 @@
+/// History-aware keyword suggestions
+///
+/// Learns which keyword prefixes the user types for which kinds of queries
+/// ("f report.pdf", "gh volt-extensions") and suggests the matching keyword
+/// when a raw query looks alike, so the host can hint "press Tab to search
+/// files". Keywords and queries are normalized with the tokenizer of
+/// `crate::matching`, so "Ｆ report" counts as the keyword "f".
+use crate::matching::{self, Tokenizer};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// Minimum number of past uses before a keyword is suggested
+const MIN_USES: u32 = 2;
+
+/// Minimum share of a query kind's history a keyword must account for
+const MIN_CONFIDENCE: f64 = 0.5;
+
+/// Number of recent keyword uses remembered with their time, for purging
+const MAX_LOGGED_USES: usize = 1000;
+
+/// Rough classification of what a query looks like
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QueryShape {
+    /// A file name or path ("report.pdf", "~/notes")
+    FileName,
+    /// A URL or domain ("github.com/volt")
+    Url,
+    /// An arithmetic expression ("12 * 4")
+    Math,
+    /// Anything else
+    Text,
+}
+
+impl QueryShape {
+    /// Classify a query
+    pub fn classify(query: &str) -> Self {
+        let query = query.trim();
+
+        if query.contains("://") || query.starts_with("www.") {
+            return QueryShape::Url;
+        }
+
+        if !query.is_empty()
+            && query.chars().any(|c| c.is_ascii_digit())
+            && query
+                .chars()
+                .all(|c| c.is_ascii_digit() || c.is_whitespace() || "+-*/^%().,".contains(c))
+        {
+            return QueryShape::Math;
+        }
+
+        if query.contains(' ') {
+            return QueryShape::Text;
+        }
+
+        if query.starts_with('/') || query.starts_with("~/") || query.contains('\\') {
+            return QueryShape::FileName;
+        }
+
+        if let Some((stem, extension)) = query.rsplit_once('.')
+            && !stem.is_empty()
+            && (1..=5).contains(&extension.len())
+            && extension.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            return if query.contains('/') || KNOWN_TLDS.contains(&extension) {
+                QueryShape::Url
+            } else {
+                QueryShape::FileName
+            };
+        }
+
+        QueryShape::Text
+    }
+}
+
+/// Top-level domains that make "name.tld" look like a URL rather than a file
+const KNOWN_TLDS: &[&str] = &["com", "org", "net", "io", "dev", "app", "edu", "gov", "co"];
+
+/// A keyword that routes queries to a plugin
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeywordInfo {
+    /// Plugin handling queries prefixed with the keyword
+    pub plugin_id: String,
+    /// Hint shown to the user, e.g. "Search files"
+    pub label: String,
+}
+
+/// A keyword suggested for a raw query
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeywordSuggestion {
+    /// The keyword to prepend
+    pub keyword: String,
+    /// Plugin the keyword routes to
+    pub plugin_id: String,
+    /// Hint shown to the user
+    pub label: String,
+    /// Share of similar past queries that used this keyword (0.0-1.0)
+    pub confidence: f64,
+}
+
+/// Learned keyword usage, serializable so hosts can persist it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KeywordHistory {
+    /// Uses of each keyword, per query shape
+    uses: HashMap<QueryShape, HashMap<String, u32>>,
+    /// Most recent uses with their time, oldest first
+    #[serde(default)]
+    log: VecDeque<KeywordUse>,
+}
+
+/// A single recorded keyword use
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KeywordUse {
+    shape: QueryShape,
+    keyword: String,
+    /// Time of the use, in milliseconds since the Unix epoch
+    at: u64,
+}
+
+impl KeywordHistory {
+    /// Forget the uses recorded at or after a time
+    ///
+    /// Only the last `MAX_LOGGED_USES` uses carry a time; older uses are
+    /// kept.
+    ///
+    /// # Arguments
+    /// * `since` - Time in milliseconds since the Unix epoch
+    pub fn purge_since(&mut self, since: u64) {
+        while self.log.back().is_some_and(|entry| entry.at >= since) {
+            let Some(entry) = self.log.pop_back() else {
+                break;
+            };
+            let Some(keywords) = self.uses.get_mut(&entry.shape) else {
+                continue;
+            };
+            if let Some(count) = keywords.get_mut(&entry.keyword) {
+                *count = count.saturating_sub(1);
+                if *count == 0 {
+                    keywords.remove(&entry.keyword);
+                }
+            }
+            if keywords.is_empty() {
+                self.uses.remove(&entry.shape);
+            }
+        }
+    }
+}
+
+/// Thread-safe keyword suggestion engine
+#[derive(Clone, Default)]
+pub struct KeywordSuggester {
+    keywords: Arc<RwLock<HashMap<String, KeywordInfo>>>,
+    history: Arc<RwLock<KeywordHistory>>,
+    paused: Arc<AtomicBool>,
+    tokenizer: Option<Arc<Tokenizer>>,
+}
+
+impl KeywordSuggester {
+    /// Create a suggester with no keywords and no history
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restore a suggester from previously persisted history
+    pub fn with_history(history: KeywordHistory) -> Self {
+        Self {
+            keywords: Arc::default(),
+            history: Arc::new(RwLock::new(history)),
+            paused: Arc::default(),
+            tokenizer: None,
+        }
+    }
+
+    /// Normalize with a tokenizer instead of the one installed with
+    /// `matching::set_tokenizer`
+    pub fn with_tokenizer(mut self, tokenizer: Tokenizer) -> Self {
+        self.tokenizer = Some(Arc::new(tokenizer));
+        self
+    }
+
+    fn tokenizer(&self) -> Arc<Tokenizer> {
+        self.tokenizer.clone().unwrap_or_else(matching::tokenizer)
+    }
+
+    /// Register a keyword
+    ///
+    /// # Arguments
+    /// * `keyword` - Prefix typed by the user, e.g. "f"
+    /// * `info` - Plugin and hint associated with the keyword
+    pub fn register_keyword(&self, keyword: &str, info: KeywordInfo) -> Result<(), String> {
+        let keyword = self.tokenizer().normalize(keyword.trim());
+        if keyword.is_empty() || keyword.contains(char::is_whitespace) {
+            return Err("Keyword must be a single non-empty word".to_string());
+        }
+
+        let mut keywords = self
+            .keywords
+            .write()
+            .map_err(|e| format!("Failed to acquire write lock: {}", e))?;
+
+        keywords.insert(keyword, info);
+        Ok(())
+    }
+
+    /// Split a query into its registered keyword and the remaining text
+    pub fn split_keyword<'q>(&self, query: &'q str) -> Option<(String, &'q str)> {
+        let (first, rest) = query.trim_start().split_once(char::is_whitespace)?;
+        let keyword = self.tokenizer().normalize(first);
+
+        let keywords = self.keywords.read().ok()?;
+        keywords
+            .contains_key(&keyword)
+            .then(|| (keyword, rest.trim()))
+    }
+
+    /// Learn from a query the user executed
+    ///
+    /// Queries without a registered keyword are ignored, and nothing is
+    /// learned while recording is paused.
+    pub fn record(&self, query: &str) -> Result<(), String> {
+        if self.paused.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        let Some((keyword, rest)) = self.split_keyword(query) else {
+            return Ok(());
+        };
+        if rest.is_empty() {
+            return Ok(());
+        }
+
+        let mut history = self
+            .history
+            .write()
+            .map_err(|e| format!("Failed to acquire write lock: {}", e))?;
+
+        let shape = QueryShape::classify(&self.tokenizer().normalize(rest));
+        *history
+            .uses
+            .entry(shape)
+            .or_default()
+            .entry(keyword.clone())
+            .or_insert(0) += 1;
+
+        history.log.push_back(KeywordUse {
+            shape,
+            keyword,
+            at: crate::actions::now_millis(),
+        });
+        if history.log.len() > MAX_LOGGED_USES {
+            history.log.pop_front();
+        }
+
+        Ok(())
+    }
+
+    /// Pause or resume learning (e.g., while privacy mode is on)
+    pub fn set_recording_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    /// Forget the queries learned at or after a time
+    ///
+    /// # Arguments
+    /// * `since` - Time in milliseconds since the Unix epoch
+    pub fn purge_since(&self, since: u64) -> Result<(), String> {
+        self.history
+            .write()
+            .map(|mut history| history.purge_since(since))
+            .map_err(|e| format!("Failed to acquire write lock: {}", e))
+    }
+
+    /// Suggest a keyword for a raw query
+    ///
+    /// Returns None if the query already starts with a keyword, or if no
+    /// keyword has been used often enough for queries of the same shape.
+    pub fn suggest_keyword(&self, query: &str) -> Option<KeywordSuggestion> {
+        if query.trim().is_empty() || self.split_keyword(query).is_some() {
+            return None;
+        }
+
+        let history = self.history.read().ok()?;
+        let uses = history
+            .uses
+            .get(&QueryShape::classify(&self.tokenizer().normalize(query)))?;
+        let total: u32 = uses.values().sum();
+
+        let (keyword, count) = uses
+            .iter()
+            .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))?;
+        let confidence = *count as f64 / total as f64;
+        if *count < MIN_USES || confidence < MIN_CONFIDENCE {
+            return None;
+        }
+
+        let keywords = self.keywords.read().ok()?;
+        let info = keywords.get(keyword)?;
+
+        Some(KeywordSuggestion {
+            keyword: keyword.clone(),
+            plugin_id: info.plugin_id.clone(),
+            label: info.label.clone(),
+            confidence,
+        })
+    }
+
+    /// Get a copy of the learned history for persistence
+    pub fn history(&self) -> Result<KeywordHistory, String> {
+        self.history
+            .read()
+            .map(|history| history.clone())
+            .map_err(|e| format!("Failed to acquire read lock: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn suggester() -> KeywordSuggester {
+        let suggester = KeywordSuggester::new();
+        suggester
+            .register_keyword(
+                "f",
+                KeywordInfo {
+                    plugin_id: "files".to_string(),
+                    label: "Search files".to_string(),
+                },
+            )
+            .unwrap();
+        suggester
+            .register_keyword(
+                "w",
+                KeywordInfo {
+                    plugin_id: "websearch".to_string(),
+                    label: "Search the web".to_string(),
+                },
+            )
+            .unwrap();
+        suggester
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(QueryShape::classify("report.pdf"), QueryShape::FileName);
+        assert_eq!(QueryShape::classify("~/notes"), QueryShape::FileName);
+        assert_eq!(QueryShape::classify("github.com"), QueryShape::Url);
+        assert_eq!(QueryShape::classify("https://volt.dev"), QueryShape::Url);
+        assert_eq!(QueryShape::classify("12 * (4 + 1)"), QueryShape::Math);
+        assert_eq!(QueryShape::classify("rust borrow checker"), QueryShape::Text);
+    }
+
+    #[test]
+    fn test_suggests_keyword_from_history() {
+        let suggester = suggester();
+        assert!(suggester.suggest_keyword("budget.xlsx").is_none());
+
+        suggester.record("f report.pdf").unwrap();
+        suggester.record("F notes.md").unwrap();
+        suggester.record("ｆ\u{3000}todo.txt").unwrap();
+        suggester.record("w rust borrow checker").unwrap();
+        suggester.record("calendar.ics").unwrap();
+
+        let suggestion = suggester.suggest_keyword("budget.xlsx").unwrap();
+        assert_eq!(suggestion.keyword, "f");
+        assert_eq!(suggestion.plugin_id, "files");
+        assert_eq!(suggestion.confidence, 1.0);
+
+        // Not enough history for text queries, and keyworded queries need no hint
+        assert!(suggester.suggest_keyword("weather tomorrow").is_none());
+        assert!(suggester.suggest_keyword("f budget.xlsx").is_none());
+    }
+
+    #[test]
+    fn test_history_round_trip() {
+        let suggester = suggester();
+        suggester.record("f a.txt").unwrap();
+        suggester.record("f b.txt").unwrap();
+
+        let json = serde_json::to_string(&suggester.history().unwrap()).unwrap();
+        let restored = KeywordSuggester::with_history(serde_json::from_str(&json).unwrap());
+        restored
+            .register_keyword(
+                "f",
+                KeywordInfo {
+                    plugin_id: "files".to_string(),
+                    label: "Search files".to_string(),
+                },
+            )
+            .unwrap();
+
+        assert_eq!(restored.suggest_keyword("c.txt").unwrap().keyword, "f");
+    }
+
+    #[test]
+    fn test_recording_can_be_paused_and_purged() {
+        let suggester = suggester();
+        suggester.set_recording_paused(true);
+        suggester.record("f a.txt").unwrap();
+        suggester.record("f b.txt").unwrap();
+        assert!(suggester.suggest_keyword("c.txt").is_none());
+
+        suggester.set_recording_paused(false);
+        suggester.record("f a.txt").unwrap();
+        suggester.record("f b.txt").unwrap();
+        assert!(suggester.suggest_keyword("c.txt").is_some());
+
+        suggester.purge_since(0).unwrap();
+        assert!(suggester.suggest_keyword("c.txt").is_none());
+        assert!(suggester.history().unwrap().uses.is_empty());
+    }
+}
@@ -0,0 +1,1053 @@
+/// Synchronization of plugin state across devices
+///
+/// Plugins opt in with `VoltPluginAPI::enable_sync`, naming the
+/// configurations and key-value prefixes worth carrying to the user's other
+/// devices. The host then runs a `SyncEngine` over a `SyncBackend`: a
+/// folder (which a file-sync client may mirror), a WebDAV collection or an
+/// S3-compatible bucket.
+///
+/// Everything is encrypted on the device with ChaCha20-Poly1305 before
+/// upload, under a `SyncKey` the host derives from the user's passphrase;
+/// object names are keyed hashes, so the backend learns neither which
+/// plugins are used nor what they store.
+///
+/// Each plugin's data directory keeps a journal of the last synchronized
+/// state of every item. When both sides changed an item since, the
+/// plugin's `ConflictPolicy` decides: the most recent write wins, or a
+/// merge callback combines both versions.
+use crate::actions::now_millis;
+use crate::api::VoltPluginAPI;
+use async_trait::async_trait;
+use chacha20poly1305::ChaCha20Poly1305;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// File of a plugin's data directory holding its sync journal
+pub const JOURNAL_FILE: &str = "sync.json";
+
+/// PBKDF2 rounds used by `SyncKey::derive`
+pub const PBKDF2_ROUNDS: u32 = 600_000;
+
+/// Prefix of every sealed object, followed by the nonce
+const SEALED_MAGIC: &[u8] = b"VSYNC1";
+
+/// Length of a ChaCha20-Poly1305 nonce
+const NONCE_LEN: usize = 12;
+
+type HmacSha256 = Hmac<Sha256>;
+
+// ========== Keys and Encryption ==========
+
+/// Key encrypting synchronized data
+///
+/// Never leaves the device; every device of the user derives the same key.
+#[derive(Clone)]
+pub struct SyncKey([u8; 32]);
+
+impl SyncKey {
+    /// Use raw key bytes, e.g. from the system keychain
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// Derive a key from the user's passphrase
+    ///
+    /// # Arguments
+    /// * `passphrase` - Passphrase the user enters on each device
+    /// * `salt` - Salt stored next to the synchronized data, at least 16 bytes
+    pub fn derive(passphrase: &str, salt: &[u8]) -> Self {
+        let mut key = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, PBKDF2_ROUNDS, &mut key);
+        Self(key)
+    }
+
+    /// Keyed hash hiding the name of an item from the backend
+    fn object_name(&self, plugin_id: &str, item: &str) -> String {
+        let mut mac =
+            <HmacSha256 as Mac>::new_from_slice(&self.0).expect("HMAC accepts any key length");
+        mac.update(b"volt-sync object\0");
+        mac.update(plugin_id.as_bytes());
+        mac.update(b"\0");
+        mac.update(item.as_bytes());
+        format!("{}.vsync", hex(&mac.finalize().into_bytes()[..16]))
+    }
+}
+
+impl std::fmt::Debug for SyncKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SyncKey(..)")
+    }
+}
+
+/// Encrypt data for upload
+///
+/// # Arguments
+/// * `key` - Key of the user
+/// * `name` - Name of the object, authenticated so objects can't be swapped
+/// * `plaintext` - Data to encrypt
+pub fn seal(key: &SyncKey, name: &str, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let cipher = ChaCha20Poly1305::new(&key.0.into());
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad: name.as_bytes(),
+            },
+        )
+        .map_err(|_| "Failed to encrypt sync data".to_string())?;
+
+    let mut sealed = Vec::with_capacity(SEALED_MAGIC.len() + NONCE_LEN + ciphertext.len());
+    sealed.extend_from_slice(SEALED_MAGIC);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+/// Decrypt downloaded data
+///
+/// Fails if the data was encrypted with another key, under another name,
+/// or tampered with.
+pub fn open(key: &SyncKey, name: &str, sealed: &[u8]) -> Result<Vec<u8>, String> {
+    let body = sealed
+        .strip_prefix(SEALED_MAGIC)
+        .filter(|body| body.len() >= NONCE_LEN)
+        .ok_or("Not a Volt sync object")?;
+    let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+
+    ChaCha20Poly1305::new(&key.0.into())
+        .decrypt(
+            nonce.into(),
+            Payload {
+                msg: ciphertext,
+                aad: name.as_bytes(),
+            },
+        )
+        .map_err(|_| {
+            "Failed to decrypt sync data: wrong passphrase or corrupted object".to_string()
+        })
+}
+
+// ========== Backends ==========
+
+/// Remote storage of sealed objects
+///
+/// Objects have flat names made of hex digits and a `.vsync` extension.
+#[async_trait]
+pub trait SyncBackend: Send + Sync {
+    /// Name of the backend, for logs
+    fn name(&self) -> &str;
+
+    /// Read an object
+    ///
+    /// # Returns
+    /// The object, or None if it doesn't exist
+    async fn get(&self, name: &str) -> Result<Option<Vec<u8>>, String>;
+
+    /// Create or replace an object
+    async fn put(&self, name: &str, data: Vec<u8>) -> Result<(), String>;
+
+    /// Delete an object; deleting a missing object succeeds
+    async fn delete(&self, name: &str) -> Result<(), String>;
+}
+
+/// Objects kept as files in a folder
+///
+/// Pointing it at a folder mirrored by Dropbox, Syncthing or a network
+/// share is enough to synchronize devices.
+pub struct FolderBackend {
+    root: PathBuf,
+}
+
+impl FolderBackend {
+    /// Keep objects in a folder, created on first upload
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+#[async_trait]
+impl SyncBackend for FolderBackend {
+    fn name(&self) -> &str {
+        "folder"
+    }
+
+    async fn get(&self, name: &str) -> Result<Option<Vec<u8>>, String> {
+        match std::fs::read(self.root.join(name)) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Failed to read sync object: {}", e)),
+        }
+    }
+
+    async fn put(&self, name: &str, data: Vec<u8>) -> Result<(), String> {
+        std::fs::create_dir_all(&self.root)
+            .map_err(|e| format!("Failed to create sync folder: {}", e))?;
+
+        // Sync clients may pick the file up at any moment; never expose a partial one
+        let path = self.root.join(name);
+        let partial = path.with_extension("partial");
+        std::fs::write(&partial, data)
+            .map_err(|e| format!("Failed to write sync object: {}", e))?;
+        std::fs::rename(&partial, &path).map_err(|e| format!("Failed to write sync object: {}", e))
+    }
+
+    async fn delete(&self, name: &str) -> Result<(), String> {
+        match std::fs::remove_file(self.root.join(name)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("Failed to delete sync object: {}", e))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Objects kept in a WebDAV collection (Nextcloud, ownCloud, Apache mod_dav)
+pub struct WebDavBackend {
+    client: reqwest::Client,
+    url: String,
+    username: String,
+    password: String,
+}
+
+impl WebDavBackend {
+    /// Keep objects in a collection, created on first upload
+    ///
+    /// # Arguments
+    /// * `url` - URL of the collection, e.g. `https://cloud.example.com/remote.php/dav/files/me/Volt`
+    /// * `username` - Account name
+    /// * `password` - Password, usually an app password
+    pub fn new(url: &str, username: &str, password: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: format!("{}/", url.trim_end_matches('/')),
+            username: username.to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    fn request(&self, method: reqwest::Method, url: &str) -> reqwest::RequestBuilder {
+        self.client
+            .request(method, url)
+            .basic_auth(&self.username, Some(&self.password))
+    }
+}
+
+#[async_trait]
+impl SyncBackend for WebDavBackend {
+    fn name(&self) -> &str {
+        "webdav"
+    }
+
+    async fn get(&self, name: &str) -> Result<Option<Vec<u8>>, String> {
+        let response = self
+            .request(reqwest::Method::GET, &format!("{}{}", self.url, name))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach WebDAV server: {}", e))?;
+
+        match response.status() {
+            reqwest::StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => response
+                .bytes()
+                .await
+                .map(|bytes| Some(bytes.to_vec()))
+                .map_err(|e| format!("Failed to read sync object: {}", e)),
+            status => Err(format!("WebDAV server answered {}", status)),
+        }
+    }
+
+    async fn put(&self, name: &str, data: Vec<u8>) -> Result<(), String> {
+        let url = format!("{}{}", self.url, name);
+        let mut status = self.put_once(&url, data.clone()).await?;
+
+        // The collection doesn't exist yet
+        if status == reqwest::StatusCode::CONFLICT || status == reqwest::StatusCode::NOT_FOUND {
+            let mkcol = reqwest::Method::from_bytes(b"MKCOL").expect("MKCOL is a valid method");
+            self.request(mkcol, &self.url)
+                .send()
+                .await
+                .map_err(|e| format!("Failed to create WebDAV collection: {}", e))?;
+            status = self.put_once(&url, data).await?;
+        }
+
+        if !status.is_success() {
+            return Err(format!("WebDAV server answered {}", status));
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, name: &str) -> Result<(), String> {
+        let response = self
+            .request(reqwest::Method::DELETE, &format!("{}{}", self.url, name))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach WebDAV server: {}", e))?;
+
+        let status = response.status();
+        if !status.is_success() && status != reqwest::StatusCode::NOT_FOUND {
+            return Err(format!("WebDAV server answered {}", status));
+        }
+        Ok(())
+    }
+}
+
+impl WebDavBackend {
+    async fn put_once(&self, url: &str, data: Vec<u8>) -> Result<reqwest::StatusCode, String> {
+        self.request(reqwest::Method::PUT, url)
+            .body(data)
+            .send()
+            .await
+            .map(|response| response.status())
+            .map_err(|e| format!("Failed to reach WebDAV server: {}", e))
+    }
+}
+
+/// Objects kept in an S3-compatible bucket (AWS S3, MinIO, R2, B2)
+///
+/// Requests use path-style URLs and AWS Signature Version 4.
+pub struct S3Backend {
+    client: reqwest::Client,
+    endpoint: String,
+    bucket: String,
+    prefix: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+}
+
+impl S3Backend {
+    /// Keep objects in a bucket
+    ///
+    /// # Arguments
+    /// * `endpoint` - Endpoint URL, e.g. `https://s3.eu-west-1.amazonaws.com`
+    /// * `bucket` - Name of the bucket
+    /// * `region` - Region of the bucket; `auto` for Cloudflare R2
+    /// * `access_key` - Access key ID
+    /// * `secret_key` - Secret access key
+    pub fn new(
+        endpoint: &str,
+        bucket: &str,
+        region: &str,
+        access_key: &str,
+        secret_key: &str,
+    ) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            bucket: bucket.to_string(),
+            prefix: String::new(),
+            region: region.to_string(),
+            access_key: access_key.to_string(),
+            secret_key: secret_key.to_string(),
+        }
+    }
+
+    /// Keep objects under a key prefix, e.g. `volt/`
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    async fn send(
+        &self,
+        method: reqwest::Method,
+        name: &str,
+        body: Vec<u8>,
+    ) -> Result<reqwest::Response, String> {
+        let url = reqwest::Url::parse(&format!(
+            "{}/{}/{}{}",
+            self.endpoint, self.bucket, self.prefix, name
+        ))
+        .map_err(|e| format!("Invalid S3 endpoint: {}", e))?;
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+
+        let now = now_millis();
+        let (date, time) = crate::builtins::snippets::local_date_time(now, 0);
+        let date = date.replace('-', "");
+        let amz_date = format!("{}T{}{:02}Z", date, time.replace(':', ""), now / 1000 % 60);
+        let payload_hash = hex(&Sha256::digest(&body));
+
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+            method,
+            url.path(),
+            host,
+            payload_hash,
+            amz_date,
+            payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+        let signing_key = signing_key(&self.secret_key, &date, &self.region, "s3");
+        let signature = hex(&hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+
+        self.client
+            .request(method, url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header(
+                reqwest::header::AUTHORIZATION,
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+                    self.access_key, scope, signature
+                ),
+            )
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach S3 endpoint: {}", e))
+    }
+}
+
+#[async_trait]
+impl SyncBackend for S3Backend {
+    fn name(&self) -> &str {
+        "s3"
+    }
+
+    async fn get(&self, name: &str) -> Result<Option<Vec<u8>>, String> {
+        let response = self.send(reqwest::Method::GET, name, Vec::new()).await?;
+        match response.status() {
+            reqwest::StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => response
+                .bytes()
+                .await
+                .map(|bytes| Some(bytes.to_vec()))
+                .map_err(|e| format!("Failed to read sync object: {}", e)),
+            status => Err(format!("S3 endpoint answered {}", status)),
+        }
+    }
+
+    async fn put(&self, name: &str, data: Vec<u8>) -> Result<(), String> {
+        let status = self.send(reqwest::Method::PUT, name, data).await?.status();
+        if !status.is_success() {
+            return Err(format!("S3 endpoint answered {}", status));
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, name: &str) -> Result<(), String> {
+        let status = self
+            .send(reqwest::Method::DELETE, name, Vec::new())
+            .await?
+            .status();
+        if !status.is_success() && status != reqwest::StatusCode::NOT_FOUND {
+            return Err(format!("S3 endpoint answered {}", status));
+        }
+        Ok(())
+    }
+}
+
+/// AWS Signature Version 4 signing key of a day, region and service
+fn signing_key(secret_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac_sha256(format!("AWS4{}", secret_key).as_bytes(), date.as_bytes());
+    let key = hmac_sha256(&key, region.as_bytes());
+    let key = hmac_sha256(&key, service.as_bytes());
+    hmac_sha256(&key, b"aws4_request")
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// ========== Scopes and Conflicts ==========
+
+/// Both versions of an item changed since the last synchronization
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncConflict {
+    /// Item in conflict, e.g. `config:settings` or `kv:snippet:`
+    pub item: String,
+    /// Version on this device
+    pub local: Value,
+    /// Version uploaded by another device
+    pub remote: Value,
+}
+
+/// Callback combining both versions of an item in conflict
+pub type MergeFn = Arc<dyn Fn(&SyncConflict) -> Value + Send + Sync>;
+
+/// How conflicts between devices are resolved
+#[derive(Clone, Default)]
+pub enum ConflictPolicy {
+    /// Keep the version written most recently
+    #[default]
+    LastWriterWins,
+    /// Keep what the callback returns, on every device
+    Merge(MergeFn),
+}
+
+impl std::fmt::Debug for ConflictPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LastWriterWins => f.write_str("LastWriterWins"),
+            Self::Merge(_) => f.write_str("Merge(..)"),
+        }
+    }
+}
+
+/// What of a plugin's state is synchronized
+///
+/// Everything else (caches, device-specific settings, history) stays on
+/// the device.
+#[derive(Debug, Clone, Default)]
+pub struct SyncScope {
+    /// Names of the synchronized configurations
+    pub configs: Vec<String>,
+    /// Prefixes of the synchronized key-value entries, each synchronized as a whole
+    pub kv_prefixes: Vec<String>,
+    /// How conflicts are resolved
+    pub policy: ConflictPolicy,
+}
+
+impl SyncScope {
+    /// Synchronize nothing yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Synchronize a configuration
+    pub fn with_config(mut self, config_name: &str) -> Self {
+        self.configs.push(config_name.to_string());
+        self
+    }
+
+    /// Synchronize the key-value entries starting with a prefix
+    pub fn with_kv_prefix(mut self, prefix: &str) -> Self {
+        self.kv_prefixes.push(prefix.to_string());
+        self
+    }
+
+    /// Resolve conflicts with a merge callback instead of the most recent write
+    pub fn merge_with(
+        mut self,
+        merge: impl Fn(&SyncConflict) -> Value + Send + Sync + 'static,
+    ) -> Self {
+        self.policy = ConflictPolicy::Merge(Arc::new(merge));
+        self
+    }
+
+    /// Items of the scope, e.g. `config:settings`
+    fn items(&self) -> impl Iterator<Item = Item> + '_ {
+        let configs = self.configs.iter().map(|name| Item::Config(name.clone()));
+        configs.chain(
+            self.kv_prefixes
+                .iter()
+                .map(|prefix| Item::Kv(prefix.clone())),
+        )
+    }
+}
+
+/// A unit of synchronization
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Item {
+    Config(String),
+    Kv(String),
+}
+
+impl Item {
+    fn id(&self) -> String {
+        match self {
+            Self::Config(name) => format!("config:{}", name),
+            Self::Kv(prefix) => format!("kv:{}", prefix),
+        }
+    }
+}
+
+// ========== Engine ==========
+
+/// What synchronizing an item did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncOutcome {
+    /// Both sides were already equal
+    Unchanged,
+    /// The local version was uploaded
+    Uploaded,
+    /// The remote version was applied locally
+    Downloaded,
+    /// Both changed; the local version was more recent and was uploaded
+    LocalWon,
+    /// Both changed; the remote version was more recent and was applied
+    RemoteWon,
+    /// Both changed; the merged version was applied and uploaded
+    Merged,
+}
+
+/// Result of a synchronization
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SyncReport {
+    /// Outcome of each item, e.g. `("clipboard", "kv:pinned:", Uploaded)`
+    pub items: Vec<(String, String, SyncOutcome)>,
+    /// Items that couldn't be synchronized, with the reason
+    pub errors: Vec<(String, String, String)>,
+}
+
+/// Version of an item stored on the backend
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncRecord {
+    /// Device that wrote the version
+    device: String,
+    /// When the version was written on that device, in milliseconds since the Unix epoch
+    modified_ms: u64,
+    /// The configuration, or the key-value entries of the prefix
+    value: Value,
+}
+
+/// Hash of each item's value at its last synchronization
+type Journal = BTreeMap<String, String>;
+
+/// Synchronizes the opted-in state of plugins with a backend
+pub struct SyncEngine {
+    api: VoltPluginAPI,
+    backend: Arc<dyn SyncBackend>,
+    key: SyncKey,
+    device: String,
+}
+
+impl SyncEngine {
+    /// Create an engine
+    ///
+    /// # Arguments
+    /// * `api` - Plugin API holding the opted-in scopes and the local state
+    /// * `backend` - Remote storage
+    /// * `key` - Key encrypting everything uploaded
+    /// * `device` - Name of this device, recorded with each upload
+    pub fn new(
+        api: VoltPluginAPI,
+        backend: Arc<dyn SyncBackend>,
+        key: SyncKey,
+        device: &str,
+    ) -> Self {
+        Self {
+            api,
+            backend,
+            key,
+            device: device.to_string(),
+        }
+    }
+
+    /// Synchronize every plugin that opted in
+    pub async fn sync_all(&self) -> SyncReport {
+        let mut report = SyncReport::default();
+        for (plugin_id, scope) in self.api.sync_scopes() {
+            self.sync_scope(&plugin_id, &scope, &mut report).await;
+        }
+        report
+    }
+
+    /// Synchronize one plugin
+    ///
+    /// # Returns
+    /// The report, or an error if the plugin didn't opt in
+    pub async fn sync_plugin(&self, plugin_id: &str) -> Result<SyncReport, String> {
+        let scope = self
+            .api
+            .sync_scopes()
+            .into_iter()
+            .find(|(id, _)| id == plugin_id)
+            .map(|(_, scope)| scope)
+            .ok_or_else(|| format!("Plugin '{}' doesn't synchronize any state", plugin_id))?;
+
+        let mut report = SyncReport::default();
+        self.sync_scope(plugin_id, &scope, &mut report).await;
+        Ok(report)
+    }
+
+    async fn sync_scope(&self, plugin_id: &str, scope: &SyncScope, report: &mut SyncReport) {
+        let journal_path = match self.api.get_plugin_data_dir(plugin_id) {
+            Ok(dir) => dir.join(JOURNAL_FILE),
+            Err(e) => {
+                report
+                    .errors
+                    .push((plugin_id.to_string(), String::new(), e));
+                return;
+            }
+        };
+        let mut journal = load_journal(&journal_path);
+
+        for item in scope.items() {
+            match self
+                .sync_item(plugin_id, &item, &scope.policy, &mut journal)
+                .await
+            {
+                Ok(outcome) => report
+                    .items
+                    .push((plugin_id.to_string(), item.id(), outcome)),
+                Err(e) => {
+                    self.api.diagnostics().warn(
+                        plugin_id,
+                        &format!(
+                            "Failed to sync {} with {}: {}",
+                            item.id(),
+                            self.backend.name(),
+                            e
+                        ),
+                    );
+                    report.errors.push((plugin_id.to_string(), item.id(), e));
+                }
+            }
+        }
+
+        if let Err(e) = save_journal(&journal_path, &journal) {
+            report
+                .errors
+                .push((plugin_id.to_string(), String::new(), e));
+        }
+    }
+
+    async fn sync_item(
+        &self,
+        plugin_id: &str,
+        item: &Item,
+        policy: &ConflictPolicy,
+        journal: &mut Journal,
+    ) -> Result<SyncOutcome, String> {
+        let id = item.id();
+        let name = self.key.object_name(plugin_id, &id);
+        let local = self.read_local(plugin_id, item)?;
+        let remote = match self.backend.get(&name).await? {
+            Some(sealed) => {
+                let plaintext = open(&self.key, &name, &sealed)?;
+                let record: SyncRecord = serde_json::from_slice(&plaintext)
+                    .map_err(|e| format!("Failed to parse sync record: {}", e))?;
+                Some(record)
+            }
+            None => None,
+        };
+
+        let base = journal.get(&id).cloned();
+        let local_hash = value_hash(&local);
+        let local_changed = match &base {
+            Some(base) => *base != local_hash,
+            None => !is_empty(&local),
+        };
+        let remote_changed = remote
+            .as_ref()
+            .is_some_and(|record| base.as_ref() != Some(&value_hash(&record.value)));
+
+        let (outcome, value) = match remote {
+            Some(record) if value_hash(&record.value) == local_hash => {
+                (SyncOutcome::Unchanged, local)
+            }
+            None if is_empty(&local) => (SyncOutcome::Unchanged, local),
+            None => (SyncOutcome::Uploaded, local),
+            Some(_) if local_changed && !remote_changed => (SyncOutcome::Uploaded, local),
+            Some(record) if !local_changed => (SyncOutcome::Downloaded, record.value),
+            Some(record) => match policy {
+                ConflictPolicy::LastWriterWins => {
+                    if self.local_modified_ms(plugin_id, item) >= record.modified_ms {
+                        (SyncOutcome::LocalWon, local)
+                    } else {
+                        (SyncOutcome::RemoteWon, record.value)
+                    }
+                }
+                ConflictPolicy::Merge(merge) => {
+                    let conflict = SyncConflict {
+                        item: id.clone(),
+                        local,
+                        remote: record.value,
+                    };
+                    (SyncOutcome::Merged, merge(&conflict))
+                }
+            },
+        };
+
+        if matches!(
+            outcome,
+            SyncOutcome::Downloaded | SyncOutcome::RemoteWon | SyncOutcome::Merged
+        ) {
+            self.write_local(plugin_id, item, &value)?;
+        }
+        if matches!(
+            outcome,
+            SyncOutcome::Uploaded | SyncOutcome::LocalWon | SyncOutcome::Merged
+        ) {
+            let record = SyncRecord {
+                device: self.device.clone(),
+                modified_ms: now_millis(),
+                value: value.clone(),
+            };
+            let plaintext = serde_json::to_vec(&record)
+                .map_err(|e| format!("Failed to serialize sync record: {}", e))?;
+            self.backend
+                .put(&name, seal(&self.key, &name, &plaintext)?)
+                .await?;
+        }
+
+        journal.insert(id, value_hash(&value));
+        Ok(outcome)
+    }
+
+    /// Current local value of an item
+    fn read_local(&self, plugin_id: &str, item: &Item) -> Result<Value, String> {
+        match item {
+            Item::Config(name) => self.api.load_config(plugin_id, name),
+            Item::Kv(prefix) => {
+                let mut entries = serde_json::Map::new();
+                for key in self.api.kv_keys(plugin_id, prefix)? {
+                    if let Some(value) = self.api.kv_get(plugin_id, &key)? {
+                        entries.insert(key, value);
+                    }
+                }
+                Ok(Value::Object(entries))
+            }
+        }
+    }
+
+    /// Replace the local value of an item
+    fn write_local(&self, plugin_id: &str, item: &Item, value: &Value) -> Result<(), String> {
+        match item {
+            Item::Config(name) => self.api.save_config(plugin_id, name, value),
+            Item::Kv(prefix) => {
+                let entries = value
+                    .as_object()
+                    .ok_or("Synchronized entries must be an object")?;
+                for key in self.api.kv_keys(plugin_id, prefix)? {
+                    if !entries.contains_key(&key) {
+                        self.api.kv_remove(plugin_id, &key)?;
+                    }
+                }
+                for (key, value) in entries
+                    .iter()
+                    .filter(|(key, _)| key.starts_with(prefix.as_str()))
+                {
+                    self.api.kv_set(plugin_id, key, value.clone())?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// When the file holding an item was last written, in milliseconds since the Unix epoch
+    fn local_modified_ms(&self, plugin_id: &str, item: &Item) -> u64 {
+        let path = match item {
+            Item::Config(name) => self
+                .api
+                .get_plugin_config_dir(plugin_id)
+                .map(|dir| dir.join(format!("{}.json", name))),
+            Item::Kv(_) => self
+                .api
+                .get_plugin_data_dir(plugin_id)
+                .map(|dir| dir.join(crate::kv::KV_FILE)),
+        };
+
+        path.ok()
+            .and_then(|path| std::fs::metadata(path).ok())
+            .and_then(|metadata| metadata.modified().ok())
+            .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |age| age.as_millis() as u64)
+    }
+}
+
+/// Whether a value holds nothing worth uploading
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Object(entries) => entries.is_empty(),
+        _ => false,
+    }
+}
+
+fn value_hash(value: &Value) -> String {
+    hex(&Sha256::digest(value.to_string().as_bytes()))
+}
+
+fn load_journal(path: &Path) -> Journal {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_journal(path: &Path, journal: &Journal) -> Result<(), String> {
+    let content = serde_json::to_string(journal)
+        .map_err(|e| format!("Failed to serialize sync journal: {}", e))?;
+    std::fs::write(path, content).map_err(|e| format!("Failed to write sync journal: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sealed_objects_are_bound_to_key_and_name() {
+        let key = SyncKey::from_bytes([7; 32]);
+        let sealed = seal(&key, "a.vsync", b"secret snippets").unwrap();
+        assert!(!sealed.windows(7).any(|window| window == b"snippet"));
+
+        assert_eq!(open(&key, "a.vsync", &sealed).unwrap(), b"secret snippets");
+        assert!(open(&key, "b.vsync", &sealed).is_err());
+        assert!(open(&SyncKey::from_bytes([8; 32]), "a.vsync", &sealed).is_err());
+
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(open(&key, "a.vsync", &tampered).is_err());
+    }
+
+    #[test]
+    fn test_signing_key_matches_aws_example() {
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        );
+        assert_eq!(
+            hex(&key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_devices_sync_through_a_folder() {
+        let temp_dir = std::env::temp_dir().join("volt_test_sync");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let backend: Arc<dyn SyncBackend> = Arc::new(FolderBackend::new(temp_dir.join("remote")));
+        let key = SyncKey::from_bytes([1; 32]);
+        let laptop = VoltPluginAPI::new(temp_dir.join("laptop"));
+        let desktop = VoltPluginAPI::new(temp_dir.join("desktop"));
+        for api in [&laptop, &desktop] {
+            let scope = SyncScope::new()
+                .with_config("settings")
+                .with_kv_prefix("snippet:");
+            api.enable_sync("snippets", scope).unwrap();
+        }
+        let laptop_sync = SyncEngine::new(laptop.clone(), backend.clone(), key.clone(), "laptop");
+        let desktop_sync = SyncEngine::new(desktop.clone(), backend.clone(), key, "desktop");
+
+        laptop
+            .save_config("snippets", "settings", &serde_json::json!({"trigger": ";"}))
+            .unwrap();
+        laptop
+            .kv_set("snippets", "snippet:sig", serde_json::json!("Regards"))
+            .unwrap();
+        laptop
+            .kv_set("snippets", "usage:sig", serde_json::json!(4))
+            .unwrap();
+        let report = laptop_sync.sync_all().await;
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert!(
+            report
+                .items
+                .iter()
+                .all(|(_, _, outcome)| *outcome == SyncOutcome::Uploaded)
+        );
+
+        // Names and contents are opaque to the backend
+        for entry in std::fs::read_dir(temp_dir.join("remote")).unwrap() {
+            let entry = entry.unwrap();
+            assert!(entry.file_name().to_string_lossy().ends_with(".vsync"));
+            let data = std::fs::read(entry.path()).unwrap();
+            assert!(!data.windows(7).any(|window| window == b"Regards"));
+        }
+
+        let report = desktop_sync.sync_plugin("snippets").await.unwrap();
+        assert!(
+            report
+                .items
+                .iter()
+                .all(|(_, _, outcome)| *outcome == SyncOutcome::Downloaded)
+        );
+        assert_eq!(
+            desktop.load_config("snippets", "settings").unwrap()["trigger"],
+            ";"
+        );
+        assert_eq!(
+            desktop.kv_get("snippets", "snippet:sig").unwrap(),
+            Some(serde_json::json!("Regards"))
+        );
+        assert_eq!(desktop.kv_get("snippets", "usage:sig").unwrap(), None);
+
+        // Removing an entry on one device removes it on the other
+        desktop.kv_remove("snippets", "snippet:sig").unwrap();
+        desktop
+            .kv_set("snippets", "snippet:addr", serde_json::json!("1 Main St"))
+            .unwrap();
+        desktop_sync.sync_all().await;
+        laptop_sync.sync_all().await;
+        assert_eq!(
+            laptop.kv_keys("snippets", "snippet:").unwrap(),
+            vec!["snippet:addr"]
+        );
+        assert!(
+            laptop_sync
+                .sync_all()
+                .await
+                .items
+                .iter()
+                .all(|(_, _, outcome)| *outcome == SyncOutcome::Unchanged)
+        );
+
+        // Concurrent edits: the most recent write wins
+        desktop
+            .save_config("snippets", "settings", &serde_json::json!({"trigger": "!"}))
+            .unwrap();
+        desktop_sync.sync_all().await;
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        laptop
+            .save_config("snippets", "settings", &serde_json::json!({"trigger": "#"}))
+            .unwrap();
+        let report = laptop_sync.sync_all().await;
+        assert!(report.items.contains(&(
+            "snippets".into(),
+            "config:settings".into(),
+            SyncOutcome::LocalWon
+        )));
+        desktop_sync.sync_all().await;
+        assert_eq!(
+            desktop.load_config("snippets", "settings").unwrap()["trigger"],
+            "#"
+        );
+
+        // Or the plugin merges both versions
+        let merging = SyncScope::new()
+            .with_kv_prefix("snippet:")
+            .merge_with(|conflict| {
+                let mut merged = conflict.remote.as_object().cloned().unwrap_or_default();
+                merged.extend(conflict.local.as_object().cloned().unwrap_or_default());
+                Value::Object(merged)
+            });
+        laptop.enable_sync("snippets", merging).unwrap();
+        desktop
+            .kv_set("snippets", "snippet:desk", serde_json::json!("desk"))
+            .unwrap();
+        desktop_sync.sync_all().await;
+        laptop
+            .kv_set("snippets", "snippet:lap", serde_json::json!("lap"))
+            .unwrap();
+        let report = laptop_sync.sync_all().await;
+        assert_eq!(
+            report.items,
+            vec![("snippets".into(), "kv:snippet:".into(), SyncOutcome::Merged)]
+        );
+        assert_eq!(
+            laptop.kv_keys("snippets", "snippet:").unwrap(),
+            vec!["snippet:addr", "snippet:desk", "snippet:lap"]
+        );
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
@@ -0,0 +1,295 @@
+/// Tauri command bindings
+///
+/// Ready-made `#[tauri::command]` functions exposing the plugin system to a
+/// Tauri frontend. The host puts a `VoltState` in Tauri's managed state and
+/// registers every command with a single `invoke_handler` call:
+///
+/// ```ignore
+/// tauri::Builder::default()
+///     .manage(VoltState::new(registry, api))
+///     .invoke_handler(volt_plugin_api::tauri_bindings::handler())
+///     .run(tauri::generate_context!())?;
+/// ```
+///
+/// ```ts
+/// const merged = await invoke("dispatch_query", { query: "calc 2+2" });
+/// await invoke("execute_action", { result: merged.results[0] });
+/// ```
+use crate::aggregator::{MergedResults, ResultAggregator};
+use crate::annotations::DEFAULT_ANNOTATION_BUDGET;
+use crate::api::VoltPluginAPI;
+use crate::bundles::BundleTransition;
+use crate::dispatch::DispatchScheduler;
+use crate::export::{ExportDestination, ExportFormat};
+use crate::keys::{KeyEvent, KeyResponse};
+use crate::open_with::OpenWithApp;
+use crate::outcome::{ExecuteOutcome, Toast};
+use crate::plugin::QueryContext;
+use crate::registry::{PluginDescriptor, PluginRegistry};
+use crate::result::PluginResult;
+use crate::selection::Selection;
+use crate::session::SessionStore;
+use crate::settings::SettingsPage;
+use std::path::PathBuf;
+use tauri::ipc::Invoke;
+use tauri::{Runtime, State};
+
+/// Number of log lines returned by `get_logs` when no limit is given
+pub const DEFAULT_LOG_LIMIT: usize = 200;
+
+/// Plugin system state shared by the commands
+pub struct VoltState {
+    /// Registered plugins
+    pub registry: PluginRegistry,
+    /// API handed to the plugins
+    pub api: VoltPluginAPI,
+    /// Fairness scheduler shared by all queries
+    pub scheduler: DispatchScheduler,
+    /// Merges and ranks the results of each query
+    pub aggregator: ResultAggregator,
+    /// Plugin state kept between the queries of a launcher session
+    pub session: SessionStore,
+    /// Selection of the current launcher session, taken on its first query
+    selection: std::sync::Mutex<Option<Selection>>,
+}
+
+impl VoltState {
+    /// Create the state with the default scheduler and aggregator
+    pub fn new(registry: PluginRegistry, api: VoltPluginAPI) -> Self {
+        Self {
+            registry,
+            api,
+            scheduler: DispatchScheduler::default(),
+            aggregator: ResultAggregator::new(),
+            session: SessionStore::new(),
+            selection: std::sync::Mutex::new(None),
+        }
+    }
+
+    /// Use a configured scheduler
+    pub fn with_scheduler(mut self, scheduler: DispatchScheduler) -> Self {
+        self.scheduler = scheduler;
+        self
+    }
+
+    /// Use a configured aggregator
+    pub fn with_aggregator(mut self, aggregator: ResultAggregator) -> Self {
+        self.aggregator = aggregator;
+        self
+    }
+}
+
+/// Get a handler registering every command, for `Builder::invoke_handler`
+///
+/// Hosts registering commands of their own list these functions in their
+/// `generate_handler!` call instead.
+pub fn handler<R: Runtime>() -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static {
+    tauri::generate_handler![
+        dispatch_query,
+        execute_action,
+        export_results,
+        list_open_with_apps,
+        open_with,
+        dispatch_key_event,
+        release_key_focus,
+        list_plugins,
+        set_enabled,
+        get_fallback_order,
+        set_fallback_order,
+        get_logs,
+        end_session,
+        get_settings_pages,
+        load_settings,
+        save_settings,
+        run_settings_action
+    ]
+}
+
+/// Run a query through the plugins and return the merged, annotated results
+#[tauri::command]
+pub async fn dispatch_query(
+    state: State<'_, VoltState>,
+    query: String,
+    max_results: Option<usize>,
+) -> Result<MergedResults, String> {
+    let selection = state
+        .selection
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .get_or_insert_with(|| state.api.activation_selection())
+        .clone();
+    let mut context = QueryContext::new(query)
+        .with_session(state.session.clone())
+        .with_selection(selection);
+    if let Some(max_results) = max_results {
+        context = context.with_max_results(max_results);
+    }
+
+    let mut merged = state
+        .registry
+        .dispatch_query(&context, &state.scheduler, &state.aggregator)
+        .await;
+    state.registry.annotate(&mut merged, DEFAULT_ANNOTATION_BUDGET);
+    Ok(merged)
+}
+
+/// Execute a result returned by `dispatch_query`
+///
+/// Plugin failures are returned as a failed outcome, so the frontend always
+/// learns what to do with the window.
+#[tauri::command]
+pub async fn execute_action(state: State<'_, VoltState>, result: PluginResult) -> Result<ExecuteOutcome, String> {
+    let plugin_id = result
+        .plugin_id
+        .clone()
+        .ok_or("Result has no plugin ID; pass a result returned by dispatch_query")?;
+
+    Ok(state
+        .registry
+        .execute(&plugin_id, &result)
+        .await
+        .unwrap_or_else(ExecuteOutcome::failed))
+}
+
+/// Export results to a file or the share sheet, returning the file written
+///
+/// Pass the merged results, or the ones the user selected, in display order.
+#[tauri::command]
+pub fn export_results(
+    state: State<'_, VoltState>,
+    results: Vec<PluginResult>,
+    format: ExportFormat,
+    destination: ExportDestination,
+) -> Result<PathBuf, String> {
+    state.api.export_results(&results, format, &destination)
+}
+
+/// List the applications able to open a file result, for its "Open with…" action
+#[tauri::command]
+pub fn list_open_with_apps(result: PluginResult) -> Result<Vec<OpenWithApp>, String> {
+    crate::intents::open_with_apps(&result)
+}
+
+/// Open a file result with an application returned by `list_open_with_apps`
+#[tauri::command]
+pub fn open_with(result: PluginResult, app_id: String) -> Result<(), String> {
+    crate::intents::open_with(&result, &app_id)
+}
+
+/// Deliver a key press to the plugin owning the current view
+///
+/// Keys answered with `ignored` or `release` get the launcher's usual
+/// handling, see `crate::keys`.
+#[tauri::command]
+pub async fn dispatch_key_event(state: State<'_, VoltState>, query: String, event: KeyEvent) -> KeyResponse {
+    let context = QueryContext::new(query).with_session(state.session.clone());
+    state.registry.dispatch_key_event(&event, &context).await
+}
+
+/// Give key focus back to the launcher, when the user leaves the current view
+#[tauri::command]
+pub fn release_key_focus(state: State<'_, VoltState>) {
+    state.registry.release_key_focus();
+}
+
+/// List the registered plugins
+#[tauri::command]
+pub fn list_plugins(state: State<'_, VoltState>) -> Result<Vec<PluginDescriptor>, String> {
+    state.registry.list_plugins()
+}
+
+/// Enable or disable a plugin
+///
+/// # Returns
+/// The plugins whose state changed
+#[tauri::command]
+pub fn set_enabled(state: State<'_, VoltState>, plugin_id: String, enabled: bool) -> Result<BundleTransition, String> {
+    if !state.registry.has_plugin(&plugin_id) {
+        return Err(format!("Plugin '{}' not found", plugin_id));
+    }
+
+    Ok(state.registry.set_plugin_enabled(&plugin_id, enabled))
+}
+
+/// Get the order in which fallback providers are listed
+#[tauri::command]
+pub fn get_fallback_order(state: State<'_, VoltState>) -> Vec<String> {
+    state.registry.fallback_order()
+}
+
+/// Set the order in which fallback providers are listed
+///
+/// The host is responsible for persisting it.
+#[tauri::command]
+pub fn set_fallback_order(state: State<'_, VoltState>, order: Vec<String>) {
+    state.registry.set_fallback_order(order);
+}
+
+/// Get a plugin's most recent log lines, oldest first
+#[tauri::command]
+pub fn get_logs(state: State<'_, VoltState>, plugin_id: String, limit: Option<usize>) -> Vec<String> {
+    state
+        .api
+        .recent_logs(&plugin_id, limit.unwrap_or(DEFAULT_LOG_LIMIT))
+}
+
+/// Forget the plugins' session state and selection, when the launcher window closes
+#[tauri::command]
+pub fn end_session(state: State<'_, VoltState>) {
+    state.session.clear();
+    state.registry.release_key_focus();
+    *state.selection.lock().unwrap_or_else(|p| p.into_inner()) = None;
+}
+
+/// Get the settings pages a plugin contributes
+#[tauri::command]
+pub fn get_settings_pages(state: State<'_, VoltState>, plugin_id: String) -> Result<Vec<SettingsPage>, String> {
+    state.registry.settings_pages(&plugin_id)
+}
+
+/// Get the saved values of a settings page, defaults included
+#[tauri::command]
+pub fn load_settings(
+    state: State<'_, VoltState>,
+    plugin_id: String,
+    page_id: String,
+) -> Result<serde_json::Value, String> {
+    let page = state.registry.settings_page(&plugin_id, &page_id)?;
+
+    let mut values = page.defaults();
+    if let (Some(values), serde_json::Value::Object(saved)) =
+        (values.as_object_mut(), state.api.load_config(&plugin_id, &page_id)?)
+    {
+        values.extend(saved);
+    }
+    Ok(values)
+}
+
+/// Check the values of a settings page and save them
+#[tauri::command]
+pub fn save_settings(
+    state: State<'_, VoltState>,
+    plugin_id: String,
+    page_id: String,
+    values: serde_json::Value,
+) -> Result<(), String> {
+    let page = state.registry.settings_page(&plugin_id, &page_id)?;
+    page.check_values(&values)?;
+
+    state.api.save_config(&plugin_id, &page_id, &values)
+}
+
+/// Run the action of a button on a settings page
+#[tauri::command]
+pub async fn run_settings_action(
+    state: State<'_, VoltState>,
+    plugin_id: String,
+    page_id: String,
+    action: String,
+    values: serde_json::Value,
+) -> Result<Option<Toast>, String> {
+    state
+        .registry
+        .settings_action(&plugin_id, &page_id, &action, &values)
+        .await
+}
@@ -0,0 +1,411 @@
+/// Fixture-driven test runner for plugins
+///
+/// Feeds a plugin a scripted set of queries from a YAML fixture, checks the
+/// results against the fixture's expectations, and reports the outcome in a
+/// cargo-style summary and as JUnit XML for CI systems. Used by the
+/// `volt-plugin test` command.
+///
+/// ```yaml
+/// plugin: calculator
+/// cases:
+///   - name: adds numbers
+///     query: "2+2"
+///     expect:
+///       count: 1
+///       firstTitle: "4"
+///       minTopScore: 80
+/// ```
+use crate::plugin::{Plugin, QueryContext};
+use crate::result::PluginResult;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// A set of scripted queries and expectations
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Fixture {
+    /// Plugin the fixture targets, used to pick a plugin from multi-plugin hosts
+    #[serde(default)]
+    pub plugin: Option<String>,
+    /// Test cases, run in order
+    pub cases: Vec<TestCase>,
+}
+
+/// A single scripted query
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestCase {
+    /// Name shown in reports
+    pub name: String,
+    /// Query fed to the plugin
+    pub query: String,
+    /// Assertions on the results
+    #[serde(default)]
+    pub expect: Expectation,
+}
+
+/// Assertions on the results of a query
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Expectation {
+    /// Exact number of results
+    pub count: Option<usize>,
+    /// Minimum number of results
+    pub min_count: Option<usize>,
+    /// Maximum number of results
+    pub max_count: Option<usize>,
+    /// Title of the highest scored result
+    pub first_title: Option<String>,
+    /// Titles that must appear somewhere in the results
+    #[serde(default)]
+    pub titles: Vec<String>,
+    /// Minimum score of the highest scored result
+    pub min_top_score: Option<u32>,
+}
+
+impl Fixture {
+    /// Parse a fixture from YAML
+    pub fn from_yaml(content: &str) -> Result<Self, String> {
+        serde_yaml::from_str(content).map_err(|e| format!("Failed to parse fixture: {}", e))
+    }
+
+    /// Read and parse a fixture file
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read fixture: {}", e))?;
+
+        Self::from_yaml(&content)
+    }
+}
+
+impl Expectation {
+    /// Check results against the expectation
+    ///
+    /// # Returns
+    /// Every violated assertion, empty if the results pass
+    pub fn check(&self, results: &[PluginResult]) -> Vec<String> {
+        let mut failures = Vec::new();
+        let mut ranked: Vec<&PluginResult> = results.iter().collect();
+        ranked.sort_by_key(|result| std::cmp::Reverse(result.score));
+
+        if let Some(count) = self.count
+            && results.len() != count
+        {
+            failures.push(format!("expected {} results, got {}", count, results.len()));
+        }
+        if let Some(min) = self.min_count
+            && results.len() < min
+        {
+            failures.push(format!("expected at least {} results, got {}", min, results.len()));
+        }
+        if let Some(max) = self.max_count
+            && results.len() > max
+        {
+            failures.push(format!("expected at most {} results, got {}", max, results.len()));
+        }
+
+        if let Some(expected) = &self.first_title {
+            match ranked.first() {
+                Some(top) if &top.title == expected => {}
+                Some(top) => failures.push(format!(
+                    "expected first title '{}', got '{}'",
+                    expected, top.title
+                )),
+                None => failures.push(format!("expected first title '{}', got no results", expected)),
+            }
+        }
+
+        for title in &self.titles {
+            if !results.iter().any(|result| &result.title == title) {
+                failures.push(format!("missing result titled '{}'", title));
+            }
+        }
+
+        if let Some(min_score) = self.min_top_score {
+            let top_score = ranked.first().map(|result| result.score).unwrap_or(0);
+            if top_score < min_score {
+                failures.push(format!(
+                    "expected top score of at least {}, got {}",
+                    min_score, top_score
+                ));
+            }
+        }
+
+        failures
+    }
+}
+
+/// Outcome of a single test case
+#[derive(Debug, Clone)]
+pub struct CaseReport {
+    /// Test case name
+    pub name: String,
+    /// Time spent in the plugin
+    pub duration: Duration,
+    /// Violated assertions, empty if the case passed
+    pub failures: Vec<String>,
+}
+
+impl CaseReport {
+    /// Check if the case passed
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Outcome of a whole fixture
+#[derive(Debug, Clone)]
+pub struct TestReport {
+    /// Plugin under test
+    pub plugin_id: String,
+    /// Per-case outcomes, in fixture order
+    pub cases: Vec<CaseReport>,
+}
+
+impl TestReport {
+    /// Number of failed cases
+    pub fn failed(&self) -> usize {
+        self.cases.iter().filter(|case| !case.passed()).count()
+    }
+
+    /// Render the report as JUnit XML
+    pub fn to_junit_xml(&self) -> String {
+        let total: Duration = self.cases.iter().map(|case| case.duration).sum();
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+
+        xml.push_str(&format!(
+            "<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+            xml_escape(&self.plugin_id),
+            self.cases.len(),
+            self.failed(),
+            total.as_secs_f64()
+        ));
+
+        for case in &self.cases {
+            xml.push_str(&format!(
+                "  <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+                xml_escape(&case.name),
+                xml_escape(&self.plugin_id),
+                case.duration.as_secs_f64()
+            ));
+
+            if case.passed() {
+                xml.push_str("/>\n");
+            } else {
+                xml.push_str(&format!(
+                    ">\n    <failure message=\"{}\">{}</failure>\n  </testcase>\n",
+                    xml_escape(&case.failures[0]),
+                    xml_escape(&case.failures.join("\n"))
+                ));
+            }
+        }
+
+        xml.push_str("</testsuite>\n");
+        xml
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Run every case of a fixture against a plugin
+pub async fn run_fixture(plugin: &dyn Plugin, fixture: &Fixture) -> TestReport {
+    let mut cases = Vec::with_capacity(fixture.cases.len());
+
+    for case in &fixture.cases {
+        let context = QueryContext::new(case.query.clone());
+        let started = Instant::now();
+
+        let results = if plugin.can_handle(&context) {
+            plugin.match_query(&context).await
+        } else {
+            Ok(Vec::new())
+        };
+
+        let duration = started.elapsed();
+        let failures = match results {
+            Ok(results) => case.expect.check(&results),
+            Err(e) => vec![format!("plugin returned an error: {}", e)],
+        };
+
+        cases.push(CaseReport {
+            name: case.name.clone(),
+            duration,
+            failures,
+        });
+    }
+
+    TestReport {
+        plugin_id: plugin.id().to_string(),
+        cases,
+    }
+}
+
+/// Where a plugin under test is loaded from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PluginSource {
+    /// Native dynamic library (`.so`, `.dll`, `.dylib`)
+    Native(PathBuf),
+    /// WebAssembly module
+    Wasm(PathBuf),
+    /// Executable speaking the JSON-RPC plugin protocol over stdio
+    Process(PathBuf),
+    /// Remote plugin host WebSocket URL
+    Remote(String),
+}
+
+impl PluginSource {
+    /// Infer the plugin kind from a path or URL
+    pub fn parse(source: &str) -> Self {
+        if source.starts_with("ws://") || source.starts_with("wss://") {
+            return PluginSource::Remote(source.to_string());
+        }
+
+        let path = PathBuf::from(source);
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("so") | Some("dll") | Some("dylib") => PluginSource::Native(path),
+            Some("wasm") => PluginSource::Wasm(path),
+            _ => PluginSource::Process(path),
+        }
+    }
+
+    /// Get a short name for the kind of plugin
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PluginSource::Native(_) => "native",
+            PluginSource::Wasm(_) => "wasm",
+            PluginSource::Process(_) => "process",
+            PluginSource::Remote(_) => "remote",
+        }
+    }
+
+    /// Load the plugin
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin to pick when the source serves several
+    pub async fn load(&self, plugin_id: Option<&str>) -> Result<Box<dyn Plugin>, String> {
+        match self {
+            #[cfg(feature = "remote")]
+            PluginSource::Remote(url) => {
+                use crate::remote::{RemoteConfig, RemoteConnection};
+
+                let connection = RemoteConnection::connect(RemoteConfig::new(url.clone()));
+                connection.wait_connected(Duration::from_secs(10)).await?;
+
+                let plugin = connection
+                    .list_plugins()
+                    .await?
+                    .into_iter()
+                    .find(|plugin| plugin_id.is_none_or(|id| plugin.id() == id))
+                    .ok_or_else(|| format!("No matching plugin served by {}", url))?;
+
+                Ok(Box::new(plugin))
+            }
+            other => {
+                let _ = plugin_id;
+                Err(format!("Loading {} plugins is not supported by this build", other.kind()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+
+    struct EchoPlugin;
+
+    #[async_trait]
+    impl Plugin for EchoPlugin {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn id(&self) -> &str {
+            "echo"
+        }
+
+        fn name(&self) -> &str {
+            "Echo"
+        }
+
+        fn description(&self) -> &str {
+            "Echoes the query back"
+        }
+
+        fn can_handle(&self, context: &QueryContext) -> bool {
+            !context.query.is_empty()
+        }
+
+        async fn match_query(&self, context: &QueryContext) -> Result<Vec<PluginResult>, String> {
+            let mut result = PluginResult::new("echo", context.query.clone());
+            result.score = 90;
+            Ok(vec![result])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_fixture() {
+        let fixture = Fixture::from_yaml(
+            r#"
+plugin: echo
+cases:
+  - name: echoes query
+    query: hello
+    expect:
+      count: 1
+      firstTitle: hello
+      minTopScore: 80
+  - name: ignores empty <query>
+    query: ""
+    expect:
+      minCount: 1
+      titles: ["nothing"]
+"#,
+        )
+        .unwrap();
+
+        let report = run_fixture(&EchoPlugin, &fixture).await;
+        assert_eq!(report.plugin_id, "echo");
+        assert!(report.cases[0].passed());
+        assert_eq!(
+            report.cases[1].failures,
+            vec![
+                "expected at least 1 results, got 0".to_string(),
+                "missing result titled 'nothing'".to_string(),
+            ]
+        );
+
+        let xml = report.to_junit_xml();
+        assert!(xml.contains("<testsuite name=\"echo\" tests=\"2\" failures=\"1\""));
+        assert!(xml.contains("name=\"ignores empty &lt;query&gt;\""));
+        assert!(xml.contains("<failure message=\"expected at least 1 results, got 0\">"));
+    }
+
+    #[test]
+    fn test_plugin_source_parse() {
+        assert_eq!(
+            PluginSource::parse("target/release/libnotes.so"),
+            PluginSource::Native(PathBuf::from("target/release/libnotes.so"))
+        );
+        assert_eq!(
+            PluginSource::parse("notes.wasm"),
+            PluginSource::Wasm(PathBuf::from("notes.wasm"))
+        );
+        assert_eq!(
+            PluginSource::parse("wss://tools.example.com/volt"),
+            PluginSource::Remote("wss://tools.example.com/volt".to_string())
+        );
+        assert_eq!(
+            PluginSource::parse("./notes-plugin"),
+            PluginSource::Process(PathBuf::from("./notes-plugin"))
+        );
+    }
+}
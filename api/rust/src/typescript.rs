@@ -0,0 +1,120 @@
+/// TypeScript declarations of the wire types
+///
+/// Frontends and JavaScript plugins consume the JSON produced by these
+/// types. Their TypeScript declarations are generated from the Rust
+/// definitions, serde attributes included, and checked in at
+/// `api/typescript/src/bindings.d.ts`; a test fails when the checked-in file
+/// no longer matches, so the wire format can't drift silently. Regenerate it
+/// with:
+///
+/// ```sh
+/// VOLT_UPDATE_BINDINGS=1 cargo test --features ts-bindings bindings
+/// ```
+use crate::aggregator::MergedResults;
+use crate::bundles::BundleTransition;
+use crate::janitor::{CleanupReport, OrphanedProcess};
+use crate::keys::{Key, KeyEvent, KeyResponse, Modifiers};
+use crate::no_results::{NoResultsNote, NoResultsReason};
+use crate::outcome::{ExecuteOutcome, Toast, ToastStyle};
+use crate::plugin::QueryContext;
+use crate::protocol::{HandshakePlugin, HandshakeRequest, HandshakeResponse, ProtocolFeature, RemotePluginInfo, RpcError, RpcRequest, RpcResponse};
+use crate::registry::{PluginDescriptor, RegistryEvent};
+use crate::result::{Accessibility, AccessibilityRole, Accessory, CommandSpec, IntentKind, KeyHint, PluginResult, ResultAction, ResultIntent};
+use crate::settings::{Condition, Control, SelectOption, SettingsField, SettingsPage, SettingsSection};
+use std::path::Path;
+use ts_rs::{Config, TS};
+
+/// Header of the generated file
+const HEADER: &str = "// Generated by volt-plugin-api from the Rust wire types. Do not edit.\n";
+
+/// Generate the `.d.ts` bundle declaring every wire type
+pub fn generate_bindings() -> String {
+    // JSON numbers are read as `number`, whatever their Rust width
+    let config = Config::new().with_large_int("number");
+
+    let declarations = [
+        serde_json::Value::decl(&config),
+        QueryContext::decl(&config),
+        PluginResult::decl(&config),
+        Accessory::decl(&config),
+        Accessibility::decl(&config),
+        AccessibilityRole::decl(&config),
+        KeyHint::decl(&config),
+        ResultAction::decl(&config),
+        ResultIntent::decl(&config),
+        IntentKind::decl(&config),
+        CommandSpec::decl(&config),
+        ExecuteOutcome::decl(&config),
+        Toast::decl(&config),
+        ToastStyle::decl(&config),
+        KeyEvent::decl(&config),
+        Key::decl(&config),
+        Modifiers::decl(&config),
+        KeyResponse::decl(&config),
+        SettingsPage::decl(&config),
+        SettingsSection::decl(&config),
+        SettingsField::decl(&config),
+        Control::decl(&config),
+        SelectOption::decl(&config),
+        Condition::decl(&config),
+        MergedResults::decl(&config),
+        NoResultsNote::decl(&config),
+        NoResultsReason::decl(&config),
+        PluginDescriptor::decl(&config),
+        BundleTransition::decl(&config),
+        RegistryEvent::decl(&config),
+        CleanupReport::decl(&config),
+        OrphanedProcess::decl(&config),
+        RpcRequest::decl(&config),
+        RpcResponse::decl(&config),
+        RpcError::decl(&config),
+        RemotePluginInfo::decl(&config),
+        ProtocolFeature::decl(&config),
+        HandshakeRequest::decl(&config),
+        HandshakeResponse::decl(&config),
+        HandshakePlugin::decl(&config),
+    ];
+
+    let mut bindings = HEADER.to_string();
+    for declaration in declarations {
+        bindings.push_str("\nexport ");
+        bindings.push_str(&declaration);
+        bindings.push('\n');
+    }
+    bindings
+}
+
+/// Write the `.d.ts` bundle to a file
+pub fn write_bindings(path: &Path) -> Result<(), String> {
+    std::fs::write(path, generate_bindings()).map_err(|e| format!("Failed to write bindings: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_in_bindings_are_up_to_date() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../typescript/src/bindings.d.ts");
+        if std::env::var_os("VOLT_UPDATE_BINDINGS").is_some() {
+            write_bindings(&path).unwrap();
+        }
+
+        let checked_in = std::fs::read_to_string(&path).unwrap_or_default().replace("\r\n", "\n");
+        assert!(
+            checked_in == generate_bindings(),
+            "{} is out of date; regenerate it with VOLT_UPDATE_BINDINGS=1 cargo test --features ts-bindings bindings",
+            path.display()
+        );
+    }
+
+    #[test]
+    fn test_bindings_follow_serde_attributes() {
+        let bindings = generate_bindings();
+
+        assert!(bindings.contains("export type RegistryEvent = { \"type\": \"pluginRegistered\", "));
+        assert!(bindings.contains("reportPath: string"));
+        assert!(bindings.contains("cacheAgeMs?: number"));
+        assert!(!bindings.contains("bigint"));
+    }
+}
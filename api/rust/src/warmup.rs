@@ -0,0 +1,364 @@
+/// Scheduled cache warmups
+///
+/// A plugin whose first query reads a cold cache answers slowly, or not at
+/// all, right after the launcher starts. Plugins declare warmup tasks in
+/// their manifest instead:
+///
+/// ```json
+/// "warmup": [
+///   { "id": "frecent-files", "onStartup": true, "every": "1h", "params": { "limit": 100 } }
+/// ]
+/// ```
+///
+/// and do the work in `Plugin::warm_up`. The host's `WarmupScheduler` runs
+/// due tasks at low priority: one at a time, on a thread of their own, and
+/// not while the host reports it is busy (e.g. while the user types).
+/// Intervals follow the `PowerPolicy` like data feeds do: they are stretched
+/// on battery, and no warmup runs while background refreshes are paused.
+///
+/// The registry tracks the status of each task (`PluginRegistry::warmup_status`,
+/// `PluginSnapshot::warmup`), so hosts can show an "indexing…" state while
+/// `PluginRegistry::is_warming_up` for a plugin whose startup warmup hasn't
+/// completed yet.
+use crate::api::VoltPluginAPI;
+use crate::builtins::timer::parse_duration;
+use crate::registry::PluginRegistry;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Time before a failed warmup is attempted again
+pub const WARMUP_RETRY_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// A warmup task declared in a plugin manifest
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WarmupTask {
+    /// Identifier, unique within the plugin
+    pub id: String,
+    /// Run as soon as the plugin is registered
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub on_startup: bool,
+    /// Run again this long after the previous run, e.g. "1h" or "30m"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub every: Option<String>,
+    /// Parameters handed to the plugin, e.g. `{"limit": 100}`
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub params: serde_json::Value,
+}
+
+impl WarmupTask {
+    /// Get the time between two runs
+    ///
+    /// # Returns
+    /// None if the task only runs at startup or `every` is invalid
+    pub fn interval(&self) -> Option<Duration> {
+        self.every
+            .as_deref()
+            .and_then(parse_duration)
+            .map(Duration::from_millis)
+    }
+
+    /// Check that the task is well formed
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id.trim().is_empty() {
+            return Err("Warmup task ID cannot be empty".to_string());
+        }
+        if let Some(every) = &self.every
+            && parse_duration(every).is_none()
+        {
+            return Err(format!(
+                "Warmup task '{}' has an invalid interval '{}'",
+                self.id, every
+            ));
+        }
+        if !self.on_startup && self.every.is_none() {
+            return Err(format!(
+                "Warmup task '{}' never runs: set onStartup or every",
+                self.id
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Status of a warmup task
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(
+    tag = "state",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum WarmupStatus {
+    /// The task hasn't run yet
+    Pending,
+    /// The task is running
+    Running,
+    /// The last run succeeded
+    Ready {
+        /// End of the run, in milliseconds since the Unix epoch
+        at_ms: u64,
+    },
+    /// The last run failed
+    Failed {
+        /// End of the run, in milliseconds since the Unix epoch
+        at_ms: u64,
+        /// Why it failed
+        error: String,
+    },
+}
+
+/// Status of one of a plugin's warmup tasks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WarmupTaskStatus {
+    /// ID of the task
+    pub task_id: String,
+    /// Its status
+    pub status: WarmupStatus,
+}
+
+/// A declared task and its status, kept by the registry
+#[derive(Debug, Clone)]
+pub(crate) struct WarmupEntry {
+    pub(crate) task: WarmupTask,
+    pub(crate) status: WarmupStatus,
+    /// Whether a run has succeeded since the plugin was registered
+    pub(crate) warmed: bool,
+}
+
+/// Outcome of running one warmup task
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WarmupRun {
+    /// Plugin declaring the task
+    pub plugin_id: String,
+    /// The task that ran
+    pub task_id: String,
+    /// Whether it succeeded
+    pub outcome: Result<(), String>,
+}
+
+/// Reports whether the host is busy, e.g. while the user types
+pub type BusyCheck = Arc<dyn Fn() -> bool + Send + Sync>;
+
+/// Runs the warmup tasks declared by registered plugins
+#[derive(Clone)]
+pub struct WarmupScheduler {
+    api: VoltPluginAPI,
+    registry: PluginRegistry,
+    busy: Option<BusyCheck>,
+    /// Time each task not running at startup was first seen
+    first_seen: Arc<Mutex<HashMap<(String, String), u64>>>,
+}
+
+impl WarmupScheduler {
+    /// Create a scheduler for the plugins of a registry
+    pub fn new(api: VoltPluginAPI, registry: PluginRegistry) -> Self {
+        Self {
+            api,
+            registry,
+            busy: None,
+            first_seen: Default::default(),
+        }
+    }
+
+    /// Hold warmups back while the host is busy
+    pub fn yield_to(mut self, busy: BusyCheck) -> Self {
+        self.busy = Some(busy);
+        self
+    }
+
+    /// Run the tasks that are due, one after another
+    ///
+    /// Startup tasks are due right away and other tasks `every` after their
+    /// previous run, or after they were first seen. Failed tasks are retried
+    /// after `WARMUP_RETRY_INTERVAL` at the latest. Stops early when the
+    /// host becomes busy.
+    pub fn run_due(&self) -> Vec<WarmupRun> {
+        let now = self.api.clock().now_millis();
+        let policy = self.api.power_policy();
+        let mode = self.api.power_mode();
+        // Startup warmups wait while background refreshes are paused
+        if policy.refresh_interval(Duration::ZERO, mode).is_none() {
+            return Vec::new();
+        }
+
+        let mut runs = Vec::new();
+        for (plugin_id, task, status) in self.registry.warmup_tasks() {
+            if self.busy.as_ref().is_some_and(|busy| busy()) {
+                break;
+            }
+
+            let (since, interval) = match status {
+                WarmupStatus::Running => continue,
+                WarmupStatus::Pending if task.on_startup => (now, Some(Duration::ZERO)),
+                WarmupStatus::Pending => {
+                    let mut first_seen = self.first_seen.lock().unwrap_or_else(|p| p.into_inner());
+                    let seen = *first_seen
+                        .entry((plugin_id.clone(), task.id.clone()))
+                        .or_insert(now);
+                    (seen, task.interval())
+                }
+                WarmupStatus::Ready { at_ms } => (at_ms, task.interval()),
+                WarmupStatus::Failed { at_ms, .. } => (
+                    at_ms,
+                    Some(task.interval().map_or(WARMUP_RETRY_INTERVAL, |interval| {
+                        interval.min(WARMUP_RETRY_INTERVAL)
+                    })),
+                ),
+            };
+            let Some(interval) =
+                interval.and_then(|interval| policy.refresh_interval(interval, mode))
+            else {
+                continue;
+            };
+            if now.saturating_sub(since) < interval.as_millis() as u64 {
+                continue;
+            }
+
+            let outcome = self.run(&plugin_id, &task.id);
+            runs.push(WarmupRun {
+                plugin_id,
+                task_id: task.id,
+                outcome,
+            });
+        }
+
+        runs
+    }
+
+    /// Run one task now, whether it is due or not
+    pub fn run(&self, plugin_id: &str, task_id: &str) -> Result<(), String> {
+        let clock = self.api.clock();
+        self.registry
+            .run_warmup(plugin_id, task_id, &|| clock.now_millis())
+    }
+
+    /// Run due tasks forever on a thread of their own, checking every `tick`
+    ///
+    /// Start this after plugins are registered.
+    pub fn spawn(self, tick: Duration) -> Result<std::thread::JoinHandle<()>, String> {
+        std::thread::Builder::new()
+            .name("volt-warmup".to_string())
+            .spawn(move || {
+                loop {
+                    for run in self.run_due() {
+                        if let Err(e) = &run.outcome {
+                            self.registry.diagnostics().warn(
+                                "warmup",
+                                &format!(
+                                    "Warmup '{}' of plugin '{}' failed: {}",
+                                    run.task_id, run.plugin_id, e
+                                ),
+                            );
+                        }
+                    }
+                    std::thread::sleep(tick);
+                }
+            })
+            .map_err(|e| format!("Failed to start warmup thread: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::manifest::PluginManifest;
+    use crate::plugin::Plugin;
+    use crate::power::PowerState;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Prefetcher {
+        runs: Arc<AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl Plugin for Prefetcher {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn id(&self) -> &str {
+            "prefetcher"
+        }
+
+        fn name(&self) -> &str {
+            "Prefetcher"
+        }
+
+        fn description(&self) -> &str {
+            "Warms its cache"
+        }
+
+        fn warm_up(&self, task: &WarmupTask) -> Result<(), String> {
+            assert_eq!(task.params["limit"], 100);
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_warmups_run_on_schedule() {
+        let manifest: PluginManifest = serde_json::from_str(
+            r#"{"id": "prefetcher", "name": "Prefetcher", "version": "1.0.0",
+                "warmup": [{"id": "frecent", "onStartup": true, "every": "1h", "params": {"limit": 100}}]}"#,
+        )
+        .unwrap();
+        assert!(
+            WarmupTask {
+                id: "never".to_string(),
+                ..Default::default()
+            }
+            .validate()
+            .is_err()
+        );
+
+        let temp_dir = std::env::temp_dir().join("volt_test_warmup");
+        let clock = ManualClock::new(1_000_000);
+        let api = VoltPluginAPI::builder()
+            .with_app_data_dir(temp_dir.clone())
+            .with_clock(Arc::new(clock.clone()))
+            .build()
+            .unwrap();
+        let registry = PluginRegistry::new();
+        let runs = Arc::new(AtomicUsize::new(0));
+        registry
+            .register_with_manifest(Box::new(Prefetcher { runs: runs.clone() }), &manifest)
+            .unwrap();
+        assert!(registry.is_warming_up("prefetcher"));
+
+        let busy = Arc::new(std::sync::atomic::AtomicBool::new(true));
+        let flag = busy.clone();
+        let scheduler = WarmupScheduler::new(api.clone(), registry.clone())
+            .yield_to(Arc::new(move || flag.load(Ordering::SeqCst)));
+        assert!(scheduler.run_due().is_empty());
+
+        busy.store(false, Ordering::SeqCst);
+        assert_eq!(scheduler.run_due().len(), 1);
+        assert!(!registry.is_warming_up("prefetcher"));
+        assert_eq!(
+            registry.warmup_status("prefetcher"),
+            vec![WarmupTaskStatus {
+                task_id: "frecent".to_string(),
+                status: WarmupStatus::Ready { at_ms: 1_000_000 },
+            }]
+        );
+
+        // Refreshed hourly on AC power, every four hours on battery
+        clock.advance(Duration::from_secs(3600));
+        api.set_power_state(PowerState {
+            on_battery: true,
+            battery_percent: Some(80),
+            low_power_mode: false,
+        });
+        assert!(scheduler.run_due().is_empty());
+        clock.advance(Duration::from_secs(3 * 3600));
+        assert_eq!(scheduler.run_due().len(), 1);
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
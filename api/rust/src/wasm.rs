@@ -0,0 +1,1016 @@
+/// Plugins compiled to WebAssembly, run in a WASI sandbox
+///
+/// Plugins nobody vouches for can ship as a `.wasm` module (built for
+/// `wasm32-wasip1`) instead of native code. The host runs each module in a
+/// store of its own with bounded memory and a fuel budget per call, and
+/// gives it exactly one directory: the plugin's data directory
+/// (`VoltPluginAPI::get_plugin_data_dir`), preopened as `/data`. WASI
+/// resolves every path against the preopens, so the module can't open any
+/// file outside that directory, and has no sockets, environment or
+/// arguments either.
+///
+/// Calls run on blocking threads. A call whose query is cancelled (see
+/// `QueryContext::cancellation`) is interrupted at the module's next epoch
+/// check instead of burning the rest of its fuel. A module that trapped or
+/// was interrupted may be left in any state, so it is instantiated again
+/// before its next call.
+///
+/// The module talks to the host like native plugins do (see
+/// `crate::native`): the host sends JSON-RPC requests of the plugin protocol
+/// to its `volt_call` export. In the other direction the host imports the
+/// `HOST_FUNCTIONS` under the `volt` module, each taking JSON parameters and
+/// answering a JSON-RPC response, always on behalf of the plugin the module
+/// was loaded as. Buffers cross the boundary through the module's
+/// `volt_alloc` and `volt_free` exports, and a call returns its buffer as
+/// `pointer << 32 | length`.
+///
+/// Plugin crates export their plugin with a macro:
+///
+/// ```ignore
+/// volt_plugin_api::export_wasm_plugin!(MyPlugin::new);
+/// ```
+///
+/// Hosts built with the `wasm` feature load modules during discovery:
+///
+/// ```ignore
+/// let runtime = WasmRuntime::new(api.clone())?;
+/// let loaders = PluginLoaders::new().with(PluginBackend::Wasm, runtime.loader());
+/// registry.discover_and_register(&plugins_dir, &loaders)?;
+/// ```
+use crate::plugin::Plugin;
+use std::sync::OnceLock;
+
+/// Path of the plugin's data directory inside the sandbox
+pub const DATA_DIR: &str = "/data";
+
+/// Module name of the host imports
+pub const HOST_MODULE: &str = "volt";
+
+/// Host functions imported by modules, each taking JSON parameters
+///
+/// - `log` - `{"level": "info" | "warn" | "error" | "debug", "message": ...}`
+/// - `load_config`, `save_config` - `{"name": ..., "config": ...}`
+/// - `kv_get`, `kv_set`, `kv_remove` - `{"key": ..., "value": ...}`
+/// - `kv_keys` - `{"prefix": ...}`
+/// - `get_volt_version` - no parameters
+pub const HOST_FUNCTIONS: &[&str] = &[
+    "log",
+    "load_config",
+    "save_config",
+    "kv_get",
+    "kv_set",
+    "kv_remove",
+    "kv_keys",
+    "get_volt_version",
+    "refresh_results",
+];
+
+/// Memory a module may grow to by default, in bytes
+pub const DEFAULT_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+
+/// Fuel a module may burn per call by default, roughly one per instruction
+pub const DEFAULT_CALL_FUEL: u64 = 5_000_000_000;
+
+/// Fuel `can_handle` may burn by default; it runs on the querying thread
+pub const DEFAULT_CAN_HANDLE_FUEL: u64 = 10_000_000;
+
+/// Export a plugin from a crate built for `wasm32-wasip1`
+///
+/// Defines the `volt_alloc`, `volt_free` and `volt_call` exports and
+/// declares the API version. Takes a function creating the plugin, called
+/// on the first request:
+///
+/// ```ignore
+/// volt_plugin_api::export_wasm_plugin!(MyPlugin::new);
+/// ```
+#[macro_export]
+macro_rules! export_wasm_plugin {
+    ($constructor:path) => {
+        $crate::declare_api_version!();
+
+        static VOLT_PLUGIN: ::std::sync::OnceLock<
+            ::std::boxed::Box<dyn $crate::plugin::Plugin + Send + Sync>,
+        > = ::std::sync::OnceLock::new();
+
+        /// Allocate a buffer for the host, see `volt_plugin_api::wasm`
+        #[unsafe(no_mangle)]
+        pub extern "C" fn volt_alloc(len: usize) -> *mut u8 {
+            $crate::wasm::guest::alloc(len)
+        }
+
+        /// Free a buffer allocated with `volt_alloc`
+        #[unsafe(no_mangle)]
+        pub unsafe extern "C" fn volt_free(ptr: *mut u8, len: usize) {
+            unsafe { $crate::wasm::guest::free(ptr, len) }
+        }
+
+        /// Answer a request of the host
+        #[unsafe(no_mangle)]
+        pub unsafe extern "C" fn volt_call(ptr: *const u8, len: usize) -> u64 {
+            unsafe {
+                $crate::wasm::guest::call(
+                    &VOLT_PLUGIN,
+                    || ::std::boxed::Box::new($constructor()),
+                    ptr,
+                    len,
+                )
+            }
+        }
+    };
+}
+
+/// Module side of the interface, used by `export_wasm_plugin!`
+pub mod guest {
+    use super::*;
+
+    /// Allocate a zeroed buffer handed to the host
+    pub fn alloc(len: usize) -> *mut u8 {
+        Box::into_raw(vec![0u8; len].into_boxed_slice()) as *mut u8
+    }
+
+    /// Free a buffer returned by `alloc`
+    ///
+    /// # Safety
+    /// `ptr` and `len` must come from one `alloc` call, freed once
+    pub unsafe fn free(ptr: *mut u8, len: usize) {
+        if !ptr.is_null() {
+            // SAFETY: guaranteed by the caller
+            drop(unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len)) });
+        }
+    }
+
+    /// Answer a JSON-RPC request of the host
+    ///
+    /// # Returns
+    /// The response buffer, as `pointer << 32 | length`; the host frees it
+    ///
+    /// # Safety
+    /// `ptr` must be valid for reads of `len` bytes
+    pub unsafe fn call(
+        plugin: &OnceLock<Box<dyn Plugin + Send + Sync>>,
+        create: impl FnOnce() -> Box<dyn Plugin + Send + Sync>,
+        ptr: *const u8,
+        len: usize,
+    ) -> u64 {
+        let request = if ptr.is_null() {
+            &[][..]
+        } else {
+            // SAFETY: guaranteed by the caller
+            unsafe { std::slice::from_raw_parts(ptr, len) }
+        };
+
+        let plugin = plugin.get_or_init(create);
+        let response = crate::native::answer(plugin.as_ref(), request);
+        let bytes = serde_json::to_vec(&response).unwrap_or_default();
+        let len = bytes.len();
+        let ptr = Box::into_raw(bytes.into_boxed_slice()) as *mut u8;
+        ((ptr as usize as u64) << 32) | len as u64
+    }
+
+    /// Call a host function, see `HOST_FUNCTIONS`
+    #[cfg(target_family = "wasm")]
+    pub fn call_host(
+        function: &str,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value, String> {
+        #[link(wasm_import_module = "volt")]
+        unsafe extern "C" {
+            fn log(ptr: *const u8, len: usize) -> u64;
+            fn load_config(ptr: *const u8, len: usize) -> u64;
+            fn save_config(ptr: *const u8, len: usize) -> u64;
+            fn kv_get(ptr: *const u8, len: usize) -> u64;
+            fn kv_set(ptr: *const u8, len: usize) -> u64;
+            fn kv_remove(ptr: *const u8, len: usize) -> u64;
+            fn kv_keys(ptr: *const u8, len: usize) -> u64;
+            fn get_volt_version(ptr: *const u8, len: usize) -> u64;
+            fn refresh_results(ptr: *const u8, len: usize) -> u64;
+        }
+
+        let import = match function {
+            "log" => log,
+            "load_config" => load_config,
+            "save_config" => save_config,
+            "kv_get" => kv_get,
+            "kv_set" => kv_set,
+            "kv_remove" => kv_remove,
+            "kv_keys" => kv_keys,
+            "get_volt_version" => get_volt_version,
+            "refresh_results" => refresh_results,
+            other => return Err(format!("Unknown host function '{}'", other)),
+        };
+
+        let params = serde_json::to_vec(&params)
+            .map_err(|e| format!("Failed to serialize parameters: {}", e))?;
+        // SAFETY: the host reads the parameters and answers with a buffer from `volt_alloc`
+        let response = unsafe {
+            let packed = import(params.as_ptr(), params.len());
+            let (ptr, len) = (
+                (packed >> 32) as usize as *mut u8,
+                (packed & 0xFFFF_FFFF) as usize,
+            );
+            let response =
+                String::from_utf8_lossy(std::slice::from_raw_parts(ptr, len)).into_owned();
+            free(ptr, len);
+            response
+        };
+        crate::protocol::RpcResponse::decode(&response)?.into_result()
+    }
+}
+
+#[cfg(feature = "wasm")]
+pub use host::{WasmPlugin, WasmRuntime};
+
+#[cfg(feature = "wasm")]
+mod host {
+    use super::*;
+    use crate::api::{LogLevel, VoltPluginAPI};
+    use crate::cancel::{CANCELLED, CancellationToken};
+    use crate::devmode::PluginLoader;
+    use crate::keys::{KeyEvent, KeyResponse};
+    use crate::outcome::ExecuteOutcome;
+    use crate::plugin::QueryContext;
+    use crate::protocol::{RemotePluginInfo, RpcRequest, RpcResponse, error_codes, methods};
+    use crate::result::PluginResult;
+    use async_trait::async_trait;
+    use serde::de::DeserializeOwned;
+    use serde_json::Value;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+    use wasmtime::{
+        Caller, Config, Engine, Extern, Linker, Memory, Module, Store, StoreContextMut, StoreLimits,
+        StoreLimitsBuilder, TypedFunc, UpdateDeadline,
+    };
+    use wasmtime_wasi::preview1::WasiP1Ctx;
+    use wasmtime_wasi::{DirPerms, FilePerms, WasiCtxBuilder};
+
+    /// Compiles and instantiates WASM plugins
+    ///
+    /// Cheap to clone; clones share the compiled code cache.
+    #[derive(Clone)]
+    pub struct WasmRuntime {
+        engine: Engine,
+        api: VoltPluginAPI,
+        memory_limit: usize,
+        call_fuel: u64,
+        can_handle_fuel: u64,
+    }
+
+    impl WasmRuntime {
+        /// Create a runtime whose modules use an API
+        pub fn new(api: VoltPluginAPI) -> Result<Self, String> {
+            let mut config = Config::new();
+            config.consume_fuel(true);
+            config.epoch_interruption(true);
+            let engine =
+                Engine::new(&config).map_err(|e| format!("Failed to create WASM engine: {}", e))?;
+
+            Ok(Self {
+                engine,
+                api,
+                memory_limit: DEFAULT_MEMORY_LIMIT,
+                call_fuel: DEFAULT_CALL_FUEL,
+                can_handle_fuel: DEFAULT_CAN_HANDLE_FUEL,
+            })
+        }
+
+        /// Limit the memory of each module, in bytes
+        pub fn with_memory_limit(mut self, bytes: usize) -> Self {
+            self.memory_limit = bytes;
+            self
+        }
+
+        /// Limit the fuel each call may burn; calls running out of it fail
+        pub fn with_call_fuel(mut self, fuel: u64) -> Self {
+            self.call_fuel = fuel;
+            self
+        }
+
+        /// Limit the fuel `can_handle` may burn; modules running out of it
+        /// can't handle the query
+        pub fn with_can_handle_fuel(mut self, fuel: u64) -> Self {
+            self.can_handle_fuel = fuel;
+            self
+        }
+
+        /// Load a module as a plugin
+        ///
+        /// # Arguments
+        /// * `path` - Module exporting `volt_call`, `volt_alloc` and `volt_free`
+        /// * `plugin_id` - Plugin the module is loaded as, from its manifest;
+        ///   it only ever sees this plugin's data directory
+        pub fn load(&self, path: &Path, plugin_id: &str) -> Result<WasmPlugin, String> {
+            let module = Module::from_file(&self.engine, path)
+                .map_err(|e| format!("Failed to compile WASM plugin {}: {}", path.display(), e))?;
+            let guest = self.instantiate(&module, plugin_id)?;
+            let mut sandbox = Sandbox {
+                runtime: self.clone(),
+                module,
+                plugin_id: plugin_id.to_string(),
+                guest: Some(guest),
+            };
+
+            let plugins: Vec<RemotePluginInfo> =
+                serde_json::from_value(sandbox.call(methods::LIST_PLUGINS, Value::Null, None, self.call_fuel)?)
+                    .map_err(|e| format!("Failed to parse WASM plugin info: {}", e))?;
+            let info = plugins
+                .into_iter()
+                .next()
+                .ok_or_else(|| "WASM plugin describes no plugin".to_string())?;
+
+            Ok(WasmPlugin {
+                info,
+                runtime: self.clone(),
+                sandbox: Arc::new(Mutex::new(sandbox)),
+            })
+        }
+
+        /// Instantiate a compiled module in a store of its own
+        fn instantiate(&self, module: &Module, plugin_id: &str) -> Result<Guest, String> {
+            let data_dir = self.api.get_plugin_data_dir(plugin_id)?;
+            let wasi = WasiCtxBuilder::new()
+                .preopened_dir(&data_dir, DATA_DIR, DirPerms::all(), FilePerms::all())
+                .map_err(|e| format!("Failed to open sandbox directory: {}", e))?
+                .build_p1();
+
+            let mut store = Store::new(
+                &self.engine,
+                HostState {
+                    wasi,
+                    api: self.api.clone(),
+                    plugin_id: plugin_id.to_string(),
+                    limits: StoreLimitsBuilder::new()
+                        .memory_size(self.memory_limit)
+                        .build(),
+                    cancellation: None,
+                },
+            );
+            store.limiter(|state| &mut state.limits);
+            // The epoch is shared by every store of the engine; only the
+            // store whose query was cancelled stops
+            store.epoch_deadline_callback(|store: StoreContextMut<'_, HostState>| {
+                match &store.data().cancellation {
+                    Some(token) if token.is_cancelled() => Err(wasmtime::Error::msg(CANCELLED)),
+                    _ => Ok(UpdateDeadline::Continue(1)),
+                }
+            });
+            store.set_epoch_deadline(1);
+            store
+                .set_fuel(self.call_fuel)
+                .map_err(|e| format!("Failed to set WASM fuel: {}", e))?;
+
+            let mut linker = Linker::new(&self.engine);
+            wasmtime_wasi::preview1::add_to_linker_sync(&mut linker, |state: &mut HostState| {
+                &mut state.wasi
+            })
+            .map_err(|e| format!("Failed to link WASI: {}", e))?;
+            for function in HOST_FUNCTIONS {
+                linker
+                    .func_wrap(
+                        HOST_MODULE,
+                        function,
+                        move |caller: Caller<'_, HostState>, ptr: u32, len: u32| {
+                            host_import(caller, function, ptr, len)
+                        },
+                    )
+                    .map_err(|e| format!("Failed to link host function '{}': {}", function, e))?;
+            }
+
+            let instance = linker
+                .instantiate(&mut store, module)
+                .map_err(|e| format!("Failed to instantiate WASM plugin: {}", e))?;
+            // Reactor modules set up their runtime in `_initialize`
+            if let Ok(initialize) = instance.get_typed_func::<(), ()>(&mut store, "_initialize") {
+                initialize
+                    .call(&mut store, ())
+                    .map_err(|e| format!("Failed to initialize WASM plugin: {}", e))?;
+            }
+
+            Ok(Guest {
+                memory: instance
+                    .get_memory(&mut store, "memory")
+                    .ok_or_else(|| "WASM plugin doesn't export its memory".to_string())?,
+                alloc: instance
+                    .get_typed_func(&mut store, "volt_alloc")
+                    .map_err(|e| missing_export("volt_alloc", e))?,
+                free: instance
+                    .get_typed_func(&mut store, "volt_free")
+                    .map_err(|e| missing_export("volt_free", e))?,
+                call: instance
+                    .get_typed_func(&mut store, "volt_call")
+                    .map_err(|e| missing_export("volt_call", e))?,
+                store,
+                next_id: 1,
+                poisoned: false,
+            })
+        }
+
+        /// Loader of WASM plugin packages, for `PluginBackend::Wasm`
+        ///
+        /// Loads the module named by the manifest's `main` as the manifest's plugin.
+        pub fn loader(&self) -> PluginLoader {
+            let runtime = self.clone();
+            Arc::new(
+                move |package_dir: &Path, manifest: &crate::manifest::PluginManifest| {
+                    let plugin = runtime.load(&manifest.entry_point(package_dir)?, &manifest.id)?;
+                    Ok(Box::new(plugin) as Box<dyn Plugin + Send + Sync>)
+                },
+            )
+        }
+    }
+
+    /// State of a module's store
+    struct HostState {
+        wasi: WasiP1Ctx,
+        api: VoltPluginAPI,
+        plugin_id: String,
+        limits: StoreLimits,
+        /// Token of the query the current call serves
+        cancellation: Option<CancellationToken>,
+    }
+
+    /// An instantiated module
+    struct Guest {
+        store: Store<HostState>,
+        memory: Memory,
+        alloc: TypedFunc<u32, u32>,
+        free: TypedFunc<(u32, u32), ()>,
+        call: TypedFunc<(u32, u32), u64>,
+        next_id: u64,
+        /// Set once a call trapped or was interrupted
+        poisoned: bool,
+    }
+
+    impl Guest {
+        /// Send a request to the module and wait for its answer
+        ///
+        /// The module is interrupted once `token` is cancelled and the
+        /// engine's epoch bumped.
+        fn call(
+            &mut self,
+            method: &str,
+            params: Value,
+            token: Option<&CancellationToken>,
+            fuel: u64,
+        ) -> Result<Value, String> {
+            let request = serde_json::to_vec(&RpcRequest::new(self.next_id, method, params))
+                .map_err(|e| format!("Failed to serialize request: {}", e))?;
+            self.next_id += 1;
+            self.store
+                .set_fuel(fuel)
+                .map_err(|e| format!("Failed to set WASM fuel: {}", e))?;
+            self.store.data_mut().cancellation = token.cloned();
+            self.store.set_epoch_deadline(1);
+            // Checked after the deadline is set, so a cancellation after
+            // this point bumps the epoch past it
+            if let Some(token) = token {
+                token.check()?;
+            }
+
+            let response = self.exchange(&request).inspect_err(|_| self.poisoned = true)?;
+            RpcResponse::decode(&response)?.into_result()
+        }
+
+        /// Hand a request to `volt_call` and read its response
+        fn exchange(&mut self, request: &[u8]) -> Result<String, String> {
+            let trapped = |e: wasmtime::Error| format!("WASM plugin trapped: {}", e);
+            let len = u32::try_from(request.len())
+                .map_err(|_| "Request is too large for a WASM plugin".to_string())?;
+            let ptr = self.alloc.call(&mut self.store, len).map_err(trapped)?;
+            self.memory
+                .write(&mut self.store, ptr as usize, request)
+                .map_err(|e| format!("Failed to write WASM plugin memory: {}", e))?;
+            let packed = self.call.call(&mut self.store, (ptr, len));
+            self.free
+                .call(&mut self.store, (ptr, len))
+                .map_err(trapped)?;
+
+            let (ptr, len) = unpack(packed.map_err(trapped)?);
+            let response =
+                String::from_utf8_lossy(guest_buffer(self.memory.data(&self.store), ptr, len)?)
+                    .into_owned();
+            self.free
+                .call(&mut self.store, (ptr, len))
+                .map_err(trapped)?;
+
+            Ok(response)
+        }
+    }
+
+    /// A compiled module and its current instance
+    struct Sandbox {
+        runtime: WasmRuntime,
+        module: Module,
+        plugin_id: String,
+        /// None after the instance was poisoned, until the next call
+        guest: Option<Guest>,
+    }
+
+    impl Sandbox {
+        /// Call the module, instantiating it again if its last call was
+        /// cut short
+        fn call(
+            &mut self,
+            method: &str,
+            params: Value,
+            token: Option<&CancellationToken>,
+            fuel: u64,
+        ) -> Result<Value, String> {
+            let guest = match &mut self.guest {
+                Some(guest) => guest,
+                None => self
+                    .guest
+                    .insert(self.runtime.instantiate(&self.module, &self.plugin_id)?),
+            };
+
+            let result = guest.call(method, params, token, fuel);
+            if guest.poisoned {
+                self.guest = None;
+            }
+            result
+        }
+    }
+
+    /// A plugin running in a WASM module
+    pub struct WasmPlugin {
+        info: RemotePluginInfo,
+        /// Its engine's epoch is bumped to interrupt a cancelled call
+        runtime: WasmRuntime,
+        /// Shared with the blocking threads calls run on
+        sandbox: Arc<Mutex<Sandbox>>,
+    }
+
+    impl WasmPlugin {
+        fn sandbox(&self) -> std::sync::MutexGuard<'_, Sandbox> {
+            self.sandbox.lock().unwrap_or_else(|p| p.into_inner())
+        }
+
+        /// Call the module on a blocking thread, so a busy module doesn't
+        /// stall the runtime
+        ///
+        /// Once the query's token is cancelled the call stops waiting and
+        /// the module is interrupted.
+        async fn call_blocking(
+            &self,
+            method: &'static str,
+            params: Value,
+            context: Option<&QueryContext>,
+        ) -> Result<Value, String> {
+            let sandbox = Arc::clone(&self.sandbox);
+            let fuel = self.runtime.call_fuel;
+            let token = context.and_then(QueryContext::cancellation).cloned();
+            let call = {
+                let token = token.clone();
+                async move {
+                    tokio::task::spawn_blocking(move || {
+                        sandbox
+                            .lock()
+                            .unwrap_or_else(|p| p.into_inner())
+                            .call(method, params, token.as_ref(), fuel)
+                    })
+                    .await
+                    .map_err(|e| format!("WASM plugin call failed: {}", e))?
+                }
+            };
+
+            let Some(token) = token else {
+                return call.await;
+            };
+            match token.run(call).await {
+                Some(result) => result,
+                None => {
+                    self.runtime.engine.increment_epoch();
+                    Err(CANCELLED.to_string())
+                }
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Plugin for WasmPlugin {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn id(&self) -> &str {
+            &self.info.id
+        }
+
+        fn name(&self) -> &str {
+            &self.info.name
+        }
+
+        fn description(&self) -> &str {
+            &self.info.description
+        }
+
+        fn version(&self) -> Option<&str> {
+            self.info.version.as_deref()
+        }
+
+        fn author(&self) -> Option<&str> {
+            self.info.author.as_deref()
+        }
+
+        fn homepage(&self) -> Option<&str> {
+            self.info.homepage.as_deref()
+        }
+
+        fn license(&self) -> Option<&str> {
+            self.info.license.as_deref()
+        }
+
+        fn can_handle(&self, context: &QueryContext) -> bool {
+            // Runs on the querying thread, so only with a small budget
+            self.sandbox()
+                .call(
+                    methods::CAN_HANDLE,
+                    serde_json::json!({ "context": context }),
+                    context.cancellation(),
+                    self.runtime.can_handle_fuel,
+                )
+                .is_ok_and(|value| value == Value::Bool(true))
+        }
+
+        async fn match_query(&self, context: &QueryContext) -> Result<Vec<PluginResult>, String> {
+            let value = self
+                .call_blocking(
+                    methods::MATCH_QUERY,
+                    serde_json::json!({ "context": context }),
+                    Some(context),
+                )
+                .await?;
+            serde_json::from_value(value).map_err(|e| format!("Failed to parse results: {}", e))
+        }
+
+        async fn execute(&self, result: &PluginResult) -> Result<ExecuteOutcome, String> {
+            let value = self
+                .call_blocking(methods::EXECUTE, serde_json::json!({ "result": result }), None)
+                .await?;
+            serde_json::from_value(value)
+                .map_err(|e| format!("Failed to parse execute outcome: {}", e))
+        }
+
+        async fn on_key_event(
+            &self,
+            event: &KeyEvent,
+            context: &QueryContext,
+        ) -> Result<KeyResponse, String> {
+            let value = self
+                .call_blocking(
+                    methods::KEY_EVENT,
+                    serde_json::json!({ "event": event, "context": context }),
+                    Some(context),
+                )
+                .await?;
+            serde_json::from_value(value)
+                .map_err(|e| format!("Failed to parse key response: {}", e))
+        }
+    }
+
+    fn missing_export(name: &str, e: wasmtime::Error) -> String {
+        format!("WASM plugin doesn't export '{}': {}", name, e)
+    }
+
+    /// Split a buffer returned as `pointer << 32 | length`
+    fn unpack(packed: u64) -> (u32, u32) {
+        ((packed >> 32) as u32, packed as u32)
+    }
+
+    /// Get a buffer the module points to in its memory
+    ///
+    /// Both ends come from the module; a buffer not inside its memory is
+    /// refused before the host allocates anything for it.
+    fn guest_buffer(memory: &[u8], ptr: u32, len: u32) -> Result<&[u8], String> {
+        let start = ptr as usize;
+        memory
+            .get(start..start.saturating_add(len as usize))
+            .ok_or_else(|| format!("WASM plugin buffer of {} bytes at {} is outside its memory", len, ptr))
+    }
+
+    /// Run a host function called by a module
+    fn host_import(
+        mut caller: Caller<'_, HostState>,
+        function: &str,
+        ptr: u32,
+        len: u32,
+    ) -> wasmtime::Result<u64> {
+        let memory = caller
+            .get_export("memory")
+            .and_then(Extern::into_memory)
+            .ok_or_else(|| wasmtime::Error::msg("WASM plugin doesn't export its memory"))?;
+        let params = guest_buffer(memory.data(&caller), ptr, len).map_err(wasmtime::Error::msg)?;
+
+        let state = caller.data();
+        let response = match serde_json::from_slice(params) {
+            Ok(params) => match call_host(&state.api, &state.plugin_id, function, &params) {
+                Ok(value) => RpcResponse::success(0, value),
+                Err(e) => RpcResponse::failure(0, error_codes::INTERNAL_ERROR, e),
+            },
+            Err(e) => RpcResponse::failure(
+                0,
+                error_codes::PARSE_ERROR,
+                format!("Failed to parse parameters: {}", e),
+            ),
+        };
+        let response = serde_json::to_vec(&response)?;
+
+        let alloc = caller
+            .get_export("volt_alloc")
+            .and_then(Extern::into_func)
+            .ok_or_else(|| wasmtime::Error::msg("WASM plugin doesn't export 'volt_alloc'"))?
+            .typed::<u32, u32>(&caller)?;
+        let len = u32::try_from(response.len())
+            .map_err(|_| wasmtime::Error::msg("Response is too large"))?;
+        let out = alloc.call(&mut caller, len)?;
+        memory.write(&mut caller, out as usize, &response)?;
+
+        Ok((u64::from(out) << 32) | u64::from(len))
+    }
+
+    /// Run a host function for a plugin, see `HOST_FUNCTIONS`
+    pub(super) fn call_host(
+        api: &VoltPluginAPI,
+        plugin_id: &str,
+        function: &str,
+        params: &Value,
+    ) -> Result<Value, String> {
+        match function {
+            "log" => {
+                let level = match param::<String>(params, "level")?.as_str() {
+                    "debug" => LogLevel::Debug,
+                    "warn" => LogLevel::Warn,
+                    "error" => LogLevel::Error,
+                    _ => LogLevel::Info,
+                };
+                api.log(plugin_id, level, &param::<String>(params, "message")?);
+                Ok(Value::Null)
+            }
+            "load_config" => api.load_config(plugin_id, &param::<String>(params, "name")?),
+            "save_config" => api
+                .save_config(
+                    plugin_id,
+                    &param::<String>(params, "name")?,
+                    &param::<Value>(params, "config")?,
+                )
+                .map(|()| Value::Null),
+            "kv_get" => api
+                .kv_get(plugin_id, &param::<String>(params, "key")?)
+                .map(Option::unwrap_or_default),
+            "kv_set" => api
+                .kv_set(
+                    plugin_id,
+                    &param::<String>(params, "key")?,
+                    param(params, "value")?,
+                )
+                .map(|()| Value::Null),
+            "kv_remove" => api
+                .kv_remove(plugin_id, &param::<String>(params, "key")?)
+                .map(Option::unwrap_or_default),
+            "kv_keys" => api
+                .kv_keys(
+                    plugin_id,
+                    &param::<String>(params, "prefix").unwrap_or_default(),
+                )
+                .map(|keys| keys.into()),
+            "get_volt_version" => Ok(api.get_volt_version().into()),
+            "refresh_results" => api.refresh_results(plugin_id).map(|()| Value::Null),
+            other => Err(format!("Unknown host function '{}'", other)),
+        }
+    }
+
+    /// Read a named parameter of a host function
+    fn param<T: DeserializeOwned>(params: &Value, name: &str) -> Result<T, String> {
+        serde_json::from_value(params.get(name).cloned().unwrap_or(Value::Null))
+            .map_err(|e| format!("Invalid parameter '{}': {}", name, e))
+    }
+}
+
+#[cfg(all(test, feature = "wasm"))]
+mod tests {
+    use super::host::call_host;
+    use super::{WasmPlugin, WasmRuntime};
+    use crate::api::VoltPluginAPI;
+    use crate::plugin::{Plugin, QueryContext};
+    use serde_json::json;
+    use std::time::{Duration, Instant};
+
+    /// Module answering by the first marker found in the request: plugins
+    /// for `list_plugins`, an endless loop for `spin`, a trap for `trap`, a
+    /// 4 GiB response for `huge`, a 4 GiB `log` call for `flood`, a result
+    /// titled with the calls made to the instance for `count`, and no
+    /// results otherwise
+    const TEST_MODULE: &str = r#"(module
+        (import "volt" "log" (func $log (param i32 i32) (result i64)))
+        (memory (export "memory") 2)
+        (global $heap (mut i32) (i32.const 4096))
+        (global $calls (mut i32) (i32.const 0))
+        (data (i32.const 0) "list_plugins")
+        (data (i32.const 16) "spin")
+        (data (i32.const 24) "trap")
+        (data (i32.const 32) "huge")
+        (data (i32.const 40) "flood")
+        (data (i32.const 48) "count")
+        (data (i32.const 64) "PLUGINS")
+        (data (i32.const 1024) "RESULTS")
+        (data (i32.const 2048) "COUNT")
+        (func (export "volt_alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $heap))
+            (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+            (local.get $ptr))
+        (func (export "volt_free") (param i32 i32))
+        (func $contains (param $ptr i32) (param $len i32) (param $needle i32) (param $needle_len i32) (result i32)
+            (local $i i32)
+            (local $j i32)
+            (block $done
+                (loop $start
+                    (br_if $done (i32.gt_u (i32.add (local.get $i) (local.get $needle_len)) (local.get $len)))
+                    (local.set $j (i32.const 0))
+                    (block $mismatch
+                        (loop $compare
+                            (if (i32.eq (local.get $j) (local.get $needle_len)) (then (return (i32.const 1))))
+                            (br_if $mismatch (i32.ne
+                                (i32.load8_u (i32.add (local.get $ptr) (i32.add (local.get $i) (local.get $j))))
+                                (i32.load8_u (i32.add (local.get $needle) (local.get $j)))))
+                            (local.set $j (i32.add (local.get $j) (i32.const 1)))
+                            (br $compare)))
+                    (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                    (br $start)))
+            (i32.const 0))
+        (func $buffer (param $ptr i32) (param $len i32) (result i64)
+            (i64.or
+                (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+                (i64.extend_i32_u (local.get $len))))
+        (func (export "volt_call") (param $ptr i32) (param $len i32) (result i64)
+            (global.set $calls (i32.add (global.get $calls) (i32.const 1)))
+            (if (call $contains (local.get $ptr) (local.get $len) (i32.const 0) (i32.const 12))
+                (then (return (call $buffer (i32.const 64) (i32.const PLUGINS_LEN)))))
+            (if (call $contains (local.get $ptr) (local.get $len) (i32.const 16) (i32.const 4))
+                (then (loop $spin (br $spin))))
+            (if (call $contains (local.get $ptr) (local.get $len) (i32.const 24) (i32.const 4))
+                (then unreachable))
+            (if (call $contains (local.get $ptr) (local.get $len) (i32.const 32) (i32.const 4))
+                (then (return (call $buffer (i32.const 0) (i32.const -1)))))
+            (if (call $contains (local.get $ptr) (local.get $len) (i32.const 40) (i32.const 5))
+                (then (return (call $log (i32.const 0) (i32.const -16)))))
+            (if (call $contains (local.get $ptr) (local.get $len) (i32.const 48) (i32.const 5))
+                (then
+                    (i32.store8 (i32.const COUNT_DIGIT) (i32.add (i32.const 48) (global.get $calls)))
+                    (return (call $buffer (i32.const 2048) (i32.const COUNT_LEN)))))
+            (call $buffer (i32.const 1024) (i32.const RESULTS_LEN)))
+    )"#;
+
+    /// Load `TEST_MODULE` with enough fuel to spin for minutes
+    fn load_test_module(name: &str) -> WasmPlugin {
+        let temp_dir = std::env::temp_dir().join(format!("volt_test_wasm_{}", name));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(&temp_dir).unwrap();
+
+        let plugins = r#"{"jsonrpc":"2.0","id":0,"result":[{"id":"wat","name":"Wat"}]}"#;
+        let results = r#"{"jsonrpc":"2.0","id":0,"result":[]}"#;
+        let count = r#"{"jsonrpc":"2.0","id":0,"result":[{"id":"count","title":"0"}]}"#;
+        let module = TEST_MODULE
+            .replace("PLUGINS_LEN", &plugins.len().to_string())
+            .replace("RESULTS_LEN", &results.len().to_string())
+            .replace("COUNT_LEN", &count.len().to_string())
+            .replace("COUNT_DIGIT", &(2048 + count.rfind('0').unwrap()).to_string())
+            .replace("PLUGINS", &plugins.replace('"', "\\\""))
+            .replace("RESULTS", &results.replace('"', "\\\""))
+            .replace("COUNT", &count.replace('"', "\\\""));
+        let path = temp_dir.join("plugin.wat");
+        std::fs::write(&path, module).unwrap();
+
+        WasmRuntime::new(VoltPluginAPI::new(temp_dir.join("data")))
+            .unwrap()
+            .with_call_fuel(1 << 40)
+            .load(&path, "wat")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_wasm_plugin_roundtrip() {
+        let plugin = load_test_module("roundtrip");
+        assert_eq!(plugin.id(), "wat");
+        assert_eq!(plugin.name(), "Wat");
+
+        let results = plugin.match_query(&QueryContext::new("hello")).await.unwrap();
+        assert!(results.is_empty());
+        let trapped = plugin.match_query(&QueryContext::new("trap")).await.unwrap_err();
+        assert!(trapped.contains("trapped"), "{}", trapped);
+    }
+
+    #[tokio::test]
+    async fn test_wasm_plugin_is_instantiated_again_after_a_trap() {
+        let plugin = load_test_module("poisoned");
+        let calls = async || plugin.match_query(&QueryContext::new("count")).await.unwrap()[0].title.to_string();
+
+        // Listing its plugins was the first call
+        assert_eq!(calls().await, "2");
+        assert!(plugin.match_query(&QueryContext::new("trap")).await.is_err());
+        assert_eq!(calls().await, "1");
+
+        // A busy `can_handle` runs out of its own, smaller budget
+        let started = Instant::now();
+        assert!(!plugin.can_handle(&QueryContext::new("spin")));
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(calls().await, "1");
+    }
+
+    #[tokio::test]
+    async fn test_wasm_plugin_buffers_stay_in_its_memory() {
+        let plugin = load_test_module("buffers");
+
+        let huge = plugin.match_query(&QueryContext::new("huge")).await.unwrap_err();
+        assert!(huge.contains("outside its memory"), "{}", huge);
+        let flood = plugin.match_query(&QueryContext::new("flood")).await.unwrap_err();
+        assert!(flood.contains("trapped"), "{}", flood);
+        assert!(plugin.match_query(&QueryContext::new("hello")).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_wasm_plugin_is_interrupted_when_cancelled() {
+        let plugin = load_test_module("cancel");
+
+        let token = crate::cancel::CancellationToken::new();
+        token.cancel_after(Duration::from_millis(20));
+        let started = Instant::now();
+        let cancelled = plugin
+            .match_query(&QueryContext::new("spin").with_cancellation(token))
+            .await
+            .unwrap_err();
+        assert_eq!(cancelled, crate::cancel::CANCELLED);
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        // The module stopped spinning and answers again
+        let next = tokio::time::timeout(Duration::from_secs(5), plugin.match_query(&QueryContext::new("hello")))
+            .await
+            .expect("the cancelled call is still running");
+        assert!(next.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_host_functions_act_for_the_loaded_plugin() {
+        let temp_dir = std::env::temp_dir().join("volt_test_wasm_host");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let api = VoltPluginAPI::new(temp_dir.clone());
+
+        call_host(
+            &api,
+            "sandboxed",
+            "kv_set",
+            &json!({"key": "count", "value": 3}),
+        )
+        .unwrap();
+        assert_eq!(
+            call_host(&api, "sandboxed", "kv_get", &json!({"key": "count"})).unwrap(),
+            3
+        );
+        assert_eq!(
+            call_host(&api, "other", "kv_get", &json!({"key": "count"})).unwrap(),
+            json!(null)
+        );
+        assert_eq!(
+            call_host(&api, "sandboxed", "kv_keys", &json!({})).unwrap(),
+            json!(["count"])
+        );
+
+        call_host(
+            &api,
+            "sandboxed",
+            "save_config",
+            &json!({"name": "settings", "config": {"a": 1}}),
+        )
+        .unwrap();
+        assert_eq!(
+            call_host(
+                &api,
+                "sandboxed",
+                "load_config",
+                &json!({"name": "settings"})
+            )
+            .unwrap()["a"],
+            1
+        );
+        assert!(
+            call_host(
+                &api,
+                "sandboxed",
+                "load_config",
+                &json!({"name": "../escape"})
+            )
+            .is_err()
+        );
+
+        call_host(
+            &api,
+            "sandboxed",
+            "log",
+            &json!({"level": "warn", "message": "hello"}),
+        )
+        .unwrap();
+        assert!(api.recent_logs("sandboxed", 1)[0].ends_with("WARN: hello"));
+        assert!(call_host(&api, "sandboxed", "read_file", &json!({})).is_err());
+
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+}
@@ -0,0 +1,419 @@
+/// Watchdog for synchronous plugin calls
+///
+/// Legacy plugins answer synchronously (see `crate::compat::PluginV1`) and
+/// can block forever on a lock or a network call, which would stall the
+/// async dispatcher with them. The `Watchdog` runs such calls on dedicated
+/// worker threads instead. A monitor thread flags calls exceeding the hang
+/// timeout: the caller gets an error right away, and the worker thread is
+/// abandoned (it can't be killed safely) and left to finish on its own.
+///
+/// While an abandoned call is still running its plugin is degraded: further
+/// calls fail fast instead of piling up more stuck threads, and the
+/// registry's snapshot reports the plugin as degraded along with the hang
+/// report. The degradation lifts once the abandoned call returns. On Linux,
+/// the report includes where the thread is blocked, read from `/proc`.
+use crate::logging::Diagnostics;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, Weak};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Default time a synchronous call may run before it is abandoned
+pub const DEFAULT_HANG_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest the monitor sleeps, so it notices the watchdog was dropped
+const MONITOR_IDLE: Duration = Duration::from_secs(1);
+
+/// A call that exceeded the hang timeout
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HangReport {
+    /// Plugin whose call hung
+    pub plugin_id: String,
+    /// Name of the call, e.g. "match_query"
+    pub operation: String,
+    /// When the hang was detected, in milliseconds since the Unix epoch
+    pub detected_at: u64,
+    /// Timeout the call exceeded, in milliseconds
+    pub timeout_ms: u64,
+    /// Name of the abandoned worker thread
+    pub thread: String,
+    /// Where the thread is blocked, if the platform tells
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stack: Option<String>,
+}
+
+/// Result slot shared by a worker thread and the future awaiting it
+struct CallSlot<T> {
+    outcome: Option<thread::Result<T>>,
+    hung: Option<HangReport>,
+    waker: Option<Waker>,
+}
+
+/// A running call, as seen by the monitor
+struct ActiveCall {
+    plugin_id: String,
+    operation: String,
+    thread: String,
+    deadline: Instant,
+    done: Arc<AtomicBool>,
+    /// `/proc` entry of the worker thread, relative to `/proc`
+    task: Arc<Mutex<Option<String>>>,
+    /// Mark the call hung and wake its caller; false if it just finished
+    expire: Box<dyn Fn(&HangReport) -> bool + Send>,
+}
+
+#[derive(Default)]
+struct WatchdogState {
+    active: Vec<ActiveCall>,
+    /// Abandoned calls still running, by plugin
+    abandoned: HashMap<String, usize>,
+    /// Latest hang of each plugin
+    hangs: HashMap<String, HangReport>,
+    monitor_running: bool,
+    /// Where hangs are reported
+    diagnostics: Diagnostics,
+}
+
+struct Shared {
+    timeout: Duration,
+    state: Mutex<WatchdogState>,
+    changed: Condvar,
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|p| p.into_inner())
+}
+
+/// Runs synchronous plugin calls on watched worker threads
+#[derive(Clone)]
+pub struct Watchdog {
+    shared: Arc<Shared>,
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Self::new(DEFAULT_HANG_TIMEOUT)
+    }
+}
+
+impl Watchdog {
+    /// Create a watchdog abandoning calls that run longer than `timeout`
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                timeout,
+                state: Mutex::new(WatchdogState::default()),
+                changed: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Report hangs through an instance's diagnostics
+    pub fn with_diagnostics(self, diagnostics: Diagnostics) -> Self {
+        lock(&self.shared.state).diagnostics = diagnostics;
+        self
+    }
+
+    /// Get the hang timeout
+    pub fn timeout(&self) -> Duration {
+        self.shared.timeout
+    }
+
+    /// Run a synchronous call on a watched worker thread
+    ///
+    /// A panic in the call is resumed in the awaiting task, so callers catch
+    /// it like one raised in place.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin making the call
+    /// * `operation` - Name of the call, for reports
+    /// * `call` - The call
+    ///
+    /// # Returns
+    /// The call's value, or Err if it hung or the plugin is degraded
+    pub async fn run<T, F>(&self, plugin_id: &str, operation: &str, call: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        if self.is_degraded(plugin_id) {
+            return Err(format!(
+                "Plugin '{}' is degraded: an earlier call is still hung",
+                plugin_id
+            ));
+        }
+
+        let slot = Arc::new(Mutex::new(CallSlot {
+            outcome: None,
+            hung: None,
+            waker: None,
+        }));
+        let done = Arc::new(AtomicBool::new(false));
+        let task = Arc::new(Mutex::new(None));
+        let thread_name = format!("volt-{}-{}", plugin_id, operation);
+
+        let worker = {
+            let slot = slot.clone();
+            let done = done.clone();
+            let task = task.clone();
+            let shared = self.shared.clone();
+            let plugin_id = plugin_id.to_string();
+            move || {
+                *lock(&task) = current_task();
+                let outcome = panic::catch_unwind(AssertUnwindSafe(call));
+                done.store(true, Ordering::SeqCst);
+
+                let mut call = lock(&slot);
+                if call.hung.is_some() {
+                    drop(call);
+                    shared.finish_abandoned(&plugin_id);
+                    return;
+                }
+                call.outcome = Some(outcome);
+                if let Some(waker) = call.waker.take() {
+                    waker.wake();
+                }
+            }
+        };
+        thread::Builder::new()
+            .name(thread_name.clone())
+            .spawn(worker)
+            .map_err(|e| format!("Failed to start worker thread: {}", e))?;
+
+        let expiring = slot.clone();
+        self.shared.watch(ActiveCall {
+            plugin_id: plugin_id.to_string(),
+            operation: operation.to_string(),
+            thread: thread_name,
+            deadline: Instant::now() + self.shared.timeout,
+            done,
+            task,
+            expire: Box::new(move |report| {
+                let mut call = lock(&expiring);
+                if call.outcome.is_some() {
+                    return false;
+                }
+                call.hung = Some(report.clone());
+                if let Some(waker) = call.waker.take() {
+                    waker.wake();
+                }
+                true
+            }),
+        });
+
+        WatchedCall { slot }.await
+    }
+
+    /// Check if a plugin has an abandoned call still running
+    pub fn is_degraded(&self, plugin_id: &str) -> bool {
+        lock(&self.shared.state)
+            .abandoned
+            .get(plugin_id)
+            .is_some_and(|count| *count > 0)
+    }
+
+    /// Get the latest hang of a plugin
+    pub fn last_hang(&self, plugin_id: &str) -> Option<HangReport> {
+        lock(&self.shared.state).hangs.get(plugin_id).cloned()
+    }
+}
+
+impl Shared {
+    /// Hand a call to the monitor, starting it if needed
+    fn watch(self: &Arc<Self>, call: ActiveCall) {
+        let mut state = lock(&self.state);
+        state.active.push(call);
+
+        if !state.monitor_running {
+            let weak = Arc::downgrade(self);
+            let started = thread::Builder::new()
+                .name("volt-watchdog".to_string())
+                .spawn(move || monitor(weak))
+                .is_ok();
+            state.monitor_running = started;
+        }
+        self.changed.notify_all();
+    }
+
+    fn finish_abandoned(&self, plugin_id: &str) {
+        let mut state = lock(&self.state);
+        if let Some(count) = state.abandoned.get_mut(plugin_id) {
+            *count = count.saturating_sub(1);
+        }
+        state.diagnostics.info(
+            "watchdog",
+            &format!("Hung call of plugin '{}' returned", plugin_id),
+        );
+    }
+}
+
+/// Flag calls past their deadline until the watchdog is dropped
+fn monitor(shared: Weak<Shared>) {
+    loop {
+        let Some(shared) = shared.upgrade() else {
+            return;
+        };
+        let mut state = lock(&shared.state);
+        let now = Instant::now();
+
+        state
+            .active
+            .retain(|call| !call.done.load(Ordering::SeqCst));
+        let (expired, active): (Vec<_>, Vec<_>) = std::mem::take(&mut state.active)
+            .into_iter()
+            .partition(|call| call.deadline <= now);
+        state.active = active;
+
+        for call in expired {
+            let report = HangReport {
+                plugin_id: call.plugin_id.clone(),
+                operation: call.operation.clone(),
+                detected_at: crate::actions::now_millis(),
+                timeout_ms: shared.timeout.as_millis() as u64,
+                thread: call.thread.clone(),
+                stack: lock(&call.task).as_deref().and_then(blocked_stack),
+            };
+            if !(call.expire)(&report) {
+                continue;
+            }
+
+            state.diagnostics.warn(
+                "watchdog",
+                &format!(
+                    "Plugin '{}' hung in {} for more than {}ms, abandoned thread {}",
+                    report.plugin_id, report.operation, report.timeout_ms, report.thread
+                ),
+            );
+            *state.abandoned.entry(report.plugin_id.clone()).or_insert(0) += 1;
+            state.hangs.insert(report.plugin_id.clone(), report);
+        }
+
+        let wait = state
+            .active
+            .iter()
+            .map(|call| call.deadline.saturating_duration_since(now))
+            .min()
+            .unwrap_or(MONITOR_IDLE)
+            .min(MONITOR_IDLE);
+        drop(shared.changed.wait_timeout(state, wait));
+    }
+}
+
+/// Future resolving when a worker thread finishes or is abandoned
+struct WatchedCall<T> {
+    slot: Arc<Mutex<CallSlot<T>>>,
+}
+
+impl<T> Future for WatchedCall<T> {
+    type Output = Result<T, String>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut call = lock(&self.slot);
+
+        if let Some(outcome) = call.outcome.take() {
+            drop(call);
+            return match outcome {
+                Ok(value) => Poll::Ready(Ok(value)),
+                Err(payload) => panic::resume_unwind(payload),
+            };
+        }
+        if let Some(report) = &call.hung {
+            return Poll::Ready(Err(format!(
+                "Plugin '{}' hung in {} for more than {}ms",
+                report.plugin_id, report.operation, report.timeout_ms
+            )));
+        }
+
+        call.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// `/proc` entry of the current thread, e.g. "1234/task/1240"
+fn current_task() -> Option<String> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    let link = std::fs::read_link("/proc/thread-self").ok()?;
+    Some(link.to_string_lossy().to_string())
+}
+
+/// Where a thread is blocked: its wait channel, and its kernel stack when
+/// readable (it usually needs elevated rights)
+fn blocked_stack(task: &str) -> Option<String> {
+    let dir = std::path::Path::new("/proc").join(task);
+    let read = |name: &str| {
+        std::fs::read_to_string(dir.join(name))
+            .ok()
+            .map(|content| content.trim().to_string())
+            .filter(|content| !content.is_empty() && content != "0")
+    };
+
+    match (read("wchan"), read("stack")) {
+        (Some(wchan), Some(stack)) => Some(format!("blocked in {}\n{}", wchan, stack)),
+        (Some(wchan), None) => Some(format!("blocked in {}", wchan)),
+        (None, stack) => stack,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[tokio::test]
+    async fn test_hung_calls_are_abandoned() {
+        let watchdog = Watchdog::new(Duration::from_millis(50));
+        assert_eq!(watchdog.run("quick", "match_query", || 42).await, Ok(42));
+
+        let (release, blocked) = mpsc::channel::<()>();
+        let error = watchdog
+            .run("stuck", "match_query", move || blocked.recv().is_ok())
+            .await
+            .unwrap_err();
+        assert!(error.contains("hung in match_query"));
+        assert!(watchdog.is_degraded("stuck"));
+        assert!(!watchdog.is_degraded("quick"));
+
+        let report = watchdog.last_hang("stuck").unwrap();
+        assert_eq!(report.timeout_ms, 50);
+        assert_eq!(report.thread, "volt-stuck-match_query");
+        if cfg!(target_os = "linux") {
+            assert!(report.stack.is_some());
+        }
+
+        // Further calls fail fast until the abandoned one returns
+        let error = watchdog.run("stuck", "execute", || ()).await.unwrap_err();
+        assert!(error.contains("degraded"));
+
+        release.send(()).unwrap();
+        for _ in 0..100 {
+            if !watchdog.is_degraded("stuck") {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(!watchdog.is_degraded("stuck"));
+        assert_eq!(watchdog.run("stuck", "execute", || "ok").await, Ok("ok"));
+    }
+
+    #[tokio::test]
+    async fn test_panics_reach_the_caller() {
+        let watchdog = Watchdog::default();
+        let caught = tokio::spawn(async move {
+            watchdog
+                .run("faulty", "execute", || -> u32 { panic!("plugin bug") })
+                .await
+        })
+        .await;
+
+        assert!(caught.unwrap_err().is_panic());
+    }
+}
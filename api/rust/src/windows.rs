@@ -0,0 +1,169 @@
+/// Window management
+///
+/// The host implements `WindowManager` on top of the platform's window APIs
+/// and installs it on `VoltPluginAPI` along with declaring
+/// `HostFeature::WindowManagement` available. Window switchers and tiling
+/// plugins list, focus, close and move windows through it. When windows
+/// open, close, change or gain focus, the host reports a `WindowEvent`
+/// through `VoltPluginAPI::notify_window_event`, so plugins keep their view
+/// of the desktop current without polling.
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Application windows, implemented by the host
+pub trait WindowManager: Send + Sync {
+    /// List the open windows, most recently focused first
+    fn list_windows(&self) -> Result<Vec<WindowInfo>, String>;
+
+    /// Bring a window to the front and focus it
+    fn focus(&self, window_id: &str) -> Result<(), String>;
+
+    /// Ask a window to close, as if the user clicked its close button
+    fn close(&self, window_id: &str) -> Result<(), String>;
+
+    /// Number of workspaces (virtual desktops), 1 if unsupported
+    fn workspace_count(&self) -> u32 {
+        1
+    }
+
+    /// Move a window to another workspace
+    ///
+    /// # Arguments
+    /// * `window_id` - The window
+    /// * `workspace` - Zero-based index of the workspace
+    fn move_to_workspace(&self, _window_id: &str, _workspace: u32) -> Result<(), String> {
+        Err("Workspaces are not supported".to_string())
+    }
+
+    /// Capture a small PNG preview of a window, if the platform allows it
+    fn thumbnail(&self, _window_id: &str) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+/// An application window
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowInfo {
+    /// Identifier of the window, opaque to plugins
+    pub id: String,
+    /// Window title
+    pub title: String,
+    /// Name of the application owning the window
+    pub app_name: String,
+    /// Identifier of the application, e.g. a bundle ID or executable path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_id: Option<String>,
+    /// Zero-based index of the window's workspace, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<u32>,
+    /// Whether the window is minimized
+    #[serde(default)]
+    pub minimized: bool,
+}
+
+/// Something that happened to a window
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum WindowEvent {
+    /// A window opened
+    Opened {
+        /// The new window
+        window: WindowInfo,
+    },
+    /// A window's title, workspace or state changed
+    Changed {
+        /// The window as it is now
+        window: WindowInfo,
+    },
+    /// A window was focused
+    Focused {
+        /// Identifier of the window
+        window_id: String,
+    },
+    /// A window closed
+    Closed {
+        /// Identifier of the window
+        window_id: String,
+    },
+}
+
+/// Callback invoked for each window event
+pub type WindowListener = Arc<dyn Fn(&WindowEvent) + Send + Sync>;
+
+/// Apply an event to a list of windows kept most recently focused first
+///
+/// Plugins keeping their own copy of `list_windows` call this from their
+/// window listener.
+pub fn apply_event(windows: &mut Vec<WindowInfo>, event: &WindowEvent) {
+    match event {
+        WindowEvent::Opened { window } => {
+            windows.retain(|known| known.id != window.id);
+            windows.insert(0, window.clone());
+        }
+        WindowEvent::Changed { window } => {
+            match windows.iter_mut().find(|known| known.id == window.id) {
+                Some(known) => *known = window.clone(),
+                None => windows.push(window.clone()),
+            }
+        }
+        WindowEvent::Focused { window_id } => {
+            if let Some(index) = windows.iter().position(|known| known.id == *window_id) {
+                let window = windows.remove(index);
+                windows.insert(0, window);
+            }
+        }
+        WindowEvent::Closed { window_id } => windows.retain(|known| known.id != *window_id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(id: &str, title: &str) -> WindowInfo {
+        WindowInfo {
+            id: id.to_string(),
+            title: title.to_string(),
+            app_name: "Editor".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_events_keep_the_focus_order() {
+        let mut windows = vec![window("1", "notes.md"), window("2", "todo.md")];
+
+        apply_event(
+            &mut windows,
+            &WindowEvent::Opened {
+                window: window("3", "draft.md"),
+            },
+        );
+        apply_event(
+            &mut windows,
+            &WindowEvent::Focused {
+                window_id: "2".to_string(),
+            },
+        );
+        apply_event(
+            &mut windows,
+            &WindowEvent::Changed {
+                window: window("1", "notes.md *"),
+            },
+        );
+        apply_event(
+            &mut windows,
+            &WindowEvent::Closed {
+                window_id: "3".to_string(),
+            },
+        );
+
+        let titles: Vec<&str> = windows.iter().map(|window| window.title.as_str()).collect();
+        assert_eq!(titles, vec!["todo.md", "notes.md *"]);
+    }
+}
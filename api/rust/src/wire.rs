@@ -0,0 +1,385 @@
+/// Size caps and compression of bridge messages
+///
+/// Large result lists and previews can grow bridge messages past what either
+/// end should buffer. Bridges encode and decode messages through a
+/// `WireCodec`, which enforces `WireLimits` on the uncompressed size and
+/// compresses large messages when both ends agreed on an algorithm during the
+/// handshake (see `COMPRESSION_HEADER`). Compressed messages travel as binary
+/// frames, everything else as text.
+///
+/// Oversized responses whose result is a list, such as `match_query` results
+/// ranked best first, are truncated to the longest prefix that fits. Any other
+/// oversized response is replaced by a `PAYLOAD_TOO_LARGE` error, and oversized
+/// requests are refused before being sent.
+use crate::logging;
+use crate::protocol::{error_codes, RpcRequest, RpcResponse};
+use serde_json::Value;
+
+/// Handshake header listing the compression algorithms a peer accepts
+///
+/// The client sends the algorithms it supports, preferred first; the host
+/// answers with the one it picked, or leaves the header out to disable
+/// compression.
+pub const COMPRESSION_HEADER: &str = "x-volt-compression";
+
+/// Compression algorithm of a connection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Compression {
+    /// Messages are sent as plain JSON text
+    #[default]
+    None,
+    /// Large messages are compressed with Zstandard
+    Zstd,
+}
+
+impl Compression {
+    /// Name used in the handshake header
+    pub fn name(&self) -> &'static str {
+        match self {
+            Compression::None => "none",
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    /// Algorithms supported by this build, preferred first
+    ///
+    /// Zstandard requires the `compression` feature.
+    pub fn supported() -> Vec<Compression> {
+        if cfg!(feature = "compression") {
+            vec![Compression::Zstd]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Value of `COMPRESSION_HEADER` offering the supported algorithms
+    pub fn offer() -> Option<String> {
+        let supported = Self::supported();
+        if supported.is_empty() {
+            return None;
+        }
+
+        Some(supported.iter().map(Compression::name).collect::<Vec<_>>().join(", "))
+    }
+
+    /// Pick the algorithm to use from a peer's `COMPRESSION_HEADER`
+    ///
+    /// # Returns
+    /// The first offered algorithm this build supports, or `None`
+    pub fn negotiate(header: &str) -> Compression {
+        header
+            .split(',')
+            .map(str::trim)
+            .find_map(|offered| {
+                Self::supported()
+                    .into_iter()
+                    .find(|supported| supported.name().eq_ignore_ascii_case(offered))
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Size limits of bridge messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WireLimits {
+    /// Largest message sent or accepted, uncompressed
+    pub max_message_bytes: usize,
+    /// Smallest message worth compressing
+    pub compression_threshold: usize,
+    /// Zstandard level, 1 (fastest) to 22
+    pub compression_level: i32,
+}
+
+impl Default for WireLimits {
+    fn default() -> Self {
+        Self {
+            max_message_bytes: 8 * 1024 * 1024,
+            compression_threshold: 16 * 1024,
+            compression_level: 3,
+        }
+    }
+}
+
+/// An encoded message, as sent over the transport
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    /// JSON text
+    Text(String),
+    /// Compressed JSON
+    Binary(Vec<u8>),
+}
+
+impl Frame {
+    /// Size of the frame on the wire
+    pub fn size(&self) -> usize {
+        match self {
+            Frame::Text(text) => text.len(),
+            Frame::Binary(bytes) => bytes.len(),
+        }
+    }
+}
+
+/// Encodes and decodes the messages of one connection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WireCodec {
+    limits: WireLimits,
+    compression: Compression,
+}
+
+impl WireCodec {
+    /// Create a codec for a connection
+    ///
+    /// # Arguments
+    /// * `limits` - Size limits of the connection
+    /// * `compression` - Algorithm negotiated during the handshake
+    pub fn new(limits: WireLimits, compression: Compression) -> Self {
+        Self { limits, compression }
+    }
+
+    /// Get the size limits
+    pub fn limits(&self) -> WireLimits {
+        self.limits
+    }
+
+    /// Get the negotiated compression algorithm
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// Encode a request
+    ///
+    /// # Returns
+    /// An error if the request exceeds `WireLimits::max_message_bytes`
+    pub fn encode_request(&self, request: &RpcRequest) -> Result<Frame, String> {
+        let text = serde_json::to_string(request).map_err(|e| format!("Failed to serialize request: {}", e))?;
+        if text.len() > self.limits.max_message_bytes {
+            return Err(format!(
+                "Request '{}' is {} bytes, more than the {} allowed",
+                request.method,
+                text.len(),
+                self.limits.max_message_bytes
+            ));
+        }
+
+        self.frame(text)
+    }
+
+    /// Encode a response, truncating or replacing it if it's too large
+    pub fn encode_response(&self, response: RpcResponse) -> Result<Frame, String> {
+        let text = serde_json::to_string(&response).map_err(|e| format!("Failed to serialize response: {}", e))?;
+        if text.len() <= self.limits.max_message_bytes {
+            return self.frame(text);
+        }
+
+        let size = text.len();
+        let id = response.id;
+        let text = match response.result {
+            Some(Value::Array(items)) => self.truncate(id, items)?,
+            _ => None,
+        };
+        let text = match text {
+            Some(text) => text,
+            None => {
+                let failure = RpcResponse::failure(
+                    id,
+                    error_codes::PAYLOAD_TOO_LARGE,
+                    format!(
+                        "Response is {} bytes, more than the {} allowed",
+                        size, self.limits.max_message_bytes
+                    ),
+                );
+                serde_json::to_string(&failure).map_err(|e| format!("Failed to serialize response: {}", e))?
+            }
+        };
+
+        self.frame(text)
+    }
+
+    /// Decode a request
+    pub fn decode_request(&self, frame: Frame) -> Result<RpcRequest, String> {
+        RpcRequest::decode(&self.open(frame)?)
+    }
+
+    /// Decode a response
+    pub fn decode_response(&self, frame: Frame) -> Result<RpcResponse, String> {
+        RpcResponse::decode(&self.open(frame)?)
+    }
+
+    /// Encode the longest prefix of a list result that fits
+    ///
+    /// # Returns
+    /// None if not even an empty list fits
+    fn truncate(&self, id: u64, mut items: Vec<Value>) -> Result<Option<String>, String> {
+        let total = items.len();
+        let encode = |items: &[Value]| {
+            serde_json::to_string(&RpcResponse::success(id, Value::Array(items.to_vec())))
+                .map_err(|e| format!("Failed to serialize response: {}", e))
+        };
+
+        // Binary search on the number of kept items
+        let (mut low, mut high) = (0, total);
+        while low < high {
+            let middle = (low + high).div_ceil(2);
+            if encode(&items[..middle])?.len() <= self.limits.max_message_bytes {
+                low = middle;
+            } else {
+                high = middle - 1;
+            }
+        }
+
+        items.truncate(low);
+        let text = encode(&items)?;
+        if text.len() > self.limits.max_message_bytes {
+            return Ok(None);
+        }
+
+        logging::warn(
+            "wire",
+            &format!("Truncated response {} from {} to {} items to fit the size limit", id, total, low),
+        );
+        Ok(Some(text))
+    }
+
+    /// Wrap an encoded message, compressing it if it's worth it
+    fn frame(&self, text: String) -> Result<Frame, String> {
+        if self.compression == Compression::None || text.len() < self.limits.compression_threshold {
+            return Ok(Frame::Text(text));
+        }
+
+        self.compress(text.as_bytes()).map(Frame::Binary)
+    }
+
+    /// Unwrap a received message, enforcing the size limit
+    fn open(&self, frame: Frame) -> Result<String, String> {
+        let text = match frame {
+            Frame::Text(text) => text,
+            Frame::Binary(bytes) => {
+                if self.compression == Compression::None {
+                    return Err("Received a compressed message without negotiating compression".to_string());
+                }
+                let bytes = self.decompress(&bytes)?;
+                String::from_utf8(bytes).map_err(|e| format!("Failed to decode message: {}", e))?
+            }
+        };
+
+        if text.len() > self.limits.max_message_bytes {
+            return Err(format!(
+                "Message is {} bytes, more than the {} allowed",
+                text.len(),
+                self.limits.max_message_bytes
+            ));
+        }
+        Ok(text)
+    }
+
+    #[cfg(feature = "compression")]
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        zstd::bulk::compress(data, self.limits.compression_level)
+            .map_err(|e| format!("Failed to compress message: {}", e))
+    }
+
+    #[cfg(not(feature = "compression"))]
+    fn compress(&self, _data: &[u8]) -> Result<Vec<u8>, String> {
+        Err("Compression requires the `compression` feature".to_string())
+    }
+
+    /// Decompress, refusing output past the size limit
+    #[cfg(feature = "compression")]
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        zstd::bulk::decompress(data, self.limits.max_message_bytes)
+            .map_err(|e| format!("Failed to decompress message: {}", e))
+    }
+
+    #[cfg(not(feature = "compression"))]
+    fn decompress(&self, _data: &[u8]) -> Result<Vec<u8>, String> {
+        Err("Compression requires the `compression` feature".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::methods;
+    use crate::result::PluginResult;
+
+    fn results(count: usize) -> Value {
+        let results: Vec<PluginResult> = (0..count)
+            .map(|i| PluginResult::new(format!("result-{}", i), "A result with a reasonably long title"))
+            .collect();
+        serde_json::to_value(results).unwrap()
+    }
+
+    fn limits(max_message_bytes: usize) -> WireLimits {
+        WireLimits {
+            max_message_bytes,
+            compression_threshold: 256,
+            ..WireLimits::default()
+        }
+    }
+
+    #[test]
+    fn test_small_messages_round_trip_as_text() {
+        let codec = WireCodec::new(limits(4096), Compression::None);
+        let request = RpcRequest::new(1, methods::MATCH_QUERY, serde_json::json!({"query": "calc"}));
+
+        let frame = codec.encode_request(&request).unwrap();
+        assert!(matches!(frame, Frame::Text(_)));
+        assert_eq!(codec.decode_request(frame).unwrap(), request);
+        assert!(codec.decode_response(Frame::Binary(vec![1, 2, 3])).is_err());
+    }
+
+    #[test]
+    fn test_oversized_messages() {
+        let codec = WireCodec::new(limits(1024), Compression::None);
+        let request = RpcRequest::new(1, methods::EXECUTE, Value::String("x".repeat(2048)));
+        assert!(codec.encode_request(&request).is_err());
+        assert!(codec
+            .decode_request(Frame::Text(serde_json::to_string(&request).unwrap()))
+            .is_err());
+
+        // Lists keep their best items
+        let frame = codec.encode_response(RpcResponse::success(2, results(50))).unwrap();
+        assert!(frame.size() <= 1024);
+        let kept = codec.decode_response(frame).unwrap().into_result().unwrap();
+        let kept = kept.as_array().unwrap();
+        assert!(!kept.is_empty() && kept.len() < 50);
+        assert_eq!(kept[0]["id"], "result-0");
+
+        // Anything else becomes an error
+        let frame = codec
+            .encode_response(RpcResponse::success(3, Value::String("x".repeat(2048))))
+            .unwrap();
+        let error = codec.decode_response(frame).unwrap().error.unwrap();
+        assert_eq!(error.code, error_codes::PAYLOAD_TOO_LARGE);
+    }
+
+    #[test]
+    fn test_negotiation() {
+        assert_eq!(Compression::negotiate("brotli"), Compression::None);
+        if cfg!(feature = "compression") {
+            assert_eq!(Compression::offer().as_deref(), Some("zstd"));
+            assert_eq!(Compression::negotiate("brotli, ZSTD"), Compression::Zstd);
+        } else {
+            assert_eq!(Compression::offer(), None);
+            assert_eq!(Compression::negotiate("zstd"), Compression::None);
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_large_messages_are_compressed() {
+        let codec = WireCodec::new(limits(64 * 1024), Compression::Zstd);
+        let response = RpcResponse::success(1, results(100));
+
+        let frame = codec.encode_response(response.clone()).unwrap();
+        let Frame::Binary(bytes) = frame.clone() else {
+            panic!("expected a compressed frame");
+        };
+        assert!(bytes.len() < serde_json::to_string(&response).unwrap().len() / 4);
+        assert_eq!(codec.decode_response(frame).unwrap(), response);
+
+        // Decompressing past the limit fails instead of allocating
+        let small = WireCodec::new(limits(1024), Compression::Zstd);
+        assert!(small.decode_response(Frame::Binary(bytes)).is_err());
+    }
+}
@@ -0,0 +1,671 @@
+// Generated by volt-plugin-api from the Rust wire types. Do not edit.
+
+export type JsonValue = number | string | boolean | Array<JsonValue> | { [key in string]: JsonValue } | null;
+
+export type QueryContext = { 
+/**
+ * Raw query typed by the user
+ */
+query: string, 
+/**
+ * Number of results the host will actually display, if limited
+ *
+ * Plugins should stop producing results once they have this many;
+ * anything beyond it is dropped before merging.
+ */
+maxResults?: number | null, 
+/**
+ * Query as typed, when `query` is a spelling correction of it
+ */
+correctedFrom?: string | null, };
+
+export type PluginResult = { 
+/**
+ * Identifier of the result, unique within the producing plugin
+ */
+id: string, 
+/**
+ * Main line displayed to the user
+ */
+title: string, 
+/**
+ * Secondary line displayed under the title
+ */
+subtitle?: string | null, 
+/**
+ * Icon path, URL, or emoji
+ */
+icon?: string | null, 
+/**
+ * Badge text displayed on the right (e.g., "Game", "App")
+ */
+badge?: string | null, 
+/**
+ * Short labels shown after the title, e.g. "main" or "pinned"
+ */
+tags?: Array<string>, 
+/**
+ * Extras shown right-aligned on the result row
+ */
+accessories?: Array<Accessory>, 
+/**
+ * Relevance score, higher ranks first
+ */
+score: number, 
+/**
+ * ID of the plugin that created this result
+ */
+pluginId?: string | null, 
+/**
+ * Free-form data the plugin needs again when the result is executed
+ *
+ * Accepts `data` on input for compatibility with TypeScript plugins.
+ */
+metadata?: { [key in string]: JsonValue }, 
+/**
+ * Quick-select slot (1-9, bound to Cmd/Ctrl+N)
+ *
+ * Plugins may set this to request a slot; the aggregator overwrites it
+ * with the slot actually assigned after merging.
+ */
+shortcut?: number | null, 
+/**
+ * Extra keyboard bindings available while this result is selected
+ */
+keyHints?: Array<KeyHint>, 
+/**
+ * Secondary actions listed in the result's action menu
+ */
+actions?: Array<ResultAction>, 
+/**
+ * Query as typed, when the result was produced from a spelling correction
+ *
+ * The UI shows "Showing results for ..." next to such results.
+ */
+correctedFrom?: string | null, 
+/**
+ * Common action the host performs itself instead of calling `execute`
+ */
+intent?: ResultIntent | null, 
+/**
+ * Age in milliseconds of the cached copy this result was served from
+ *
+ * None for results computed fresh for the current query.
+ */
+cacheAgeMs?: number | null, 
+/**
+ * What screen readers announce for the result
+ *
+ * Filled from the title, decorations and intent by the aggregator when
+ * the plugin leaves it out.
+ */
+accessibility?: Accessibility | null, };
+
+export type Accessory = { "kind": "text", 
+/**
+ * The text
+ */
+text: string, } | { "kind": "badge", 
+/**
+ * The text
+ */
+text: string, 
+/**
+ * CSS color of the pill, the theme's accent color if None
+ */
+color?: string | null, } | { "kind": "progress", 
+/**
+ * Completion, from 0.0 to 1.0
+ */
+value: number, };
+
+export type Accessibility = { 
+/**
+ * Short text read when the result is focused, e.g. "Firefox"
+ */
+label: string, 
+/**
+ * What activating the result does
+ */
+role: AccessibilityRole, 
+/**
+ * Longer text read on request, e.g. "Web browser, running"
+ */
+description?: string | null, };
+
+export type AccessibilityRole = "item" | "link" | "file" | "command" | "answer";
+
+export type KeyHint = { 
+/**
+ * Key combination, e.g. "Shift+Enter" or "Mod+C"
+ */
+key: string, 
+/**
+ * Action identifier passed back to the plugin
+ */
+action: string, 
+/**
+ * Human-readable label, e.g. "Copy path"
+ */
+label: string, };
+
+export type ResultAction = { 
+/**
+ * Action identifier passed back to the plugin
+ */
+id: string, 
+/**
+ * Human-readable label
+ */
+label: string, 
+/**
+ * Icon path, URL, or emoji
+ */
+icon?: string | null, };
+
+export type ResultIntent = { "type": "openFile", 
+/**
+ * Absolute path
+ */
+path: string, } | { "type": "openUrl", 
+/**
+ * URL to open
+ */
+url: string, } | { "type": "runCommand" } & CommandSpec | { "type": "copyText", 
+/**
+ * Text to copy
+ */
+text: string, } | { "type": "share", 
+/**
+ * Absolute path
+ */
+path: string, };
+
+export type IntentKind = "openFile" | "openUrl" | "runCommand" | "copyText" | "share";
+
+export type CommandSpec = { 
+/**
+ * Program name or path
+ */
+program: string, 
+/**
+ * Arguments passed to the program
+ */
+args?: Array<string>, 
+/**
+ * Directory to start in
+ */
+workingDir?: string | null, };
+
+export type ExecuteOutcome = { 
+/**
+ * Hide the launcher window
+ */
+closeWindow: boolean, 
+/**
+ * Query to run next in place of the current one
+ */
+requery?: string | null, 
+/**
+ * Message shown briefly in the launcher
+ */
+message?: Toast | null, 
+/**
+ * Why the action failed, shown to the user
+ */
+error?: string | null, 
+/**
+ * Route key presses to the plugin while the view opened by `requery`
+ * is shown, see `crate::keys`
+ */
+captureKeys?: boolean, };
+
+export type Toast = { 
+/**
+ * Message text
+ */
+text: string, 
+/**
+ * How the message is styled
+ */
+style: ToastStyle, };
+
+export type ToastStyle = "info" | "success" | "warning";
+
+export type KeyEvent = { 
+/**
+ * The key pressed
+ */
+key: Key, 
+/**
+ * Modifier keys held
+ */
+modifiers: Modifiers, 
+/**
+ * Generated by holding the key down
+ */
+repeat: boolean, 
+/**
+ * ID of the selected result, if any
+ */
+selected?: string | null, };
+
+export type Key = "up" | "down" | "left" | "right" | "tab" | "enter" | "escape" | "backspace" | "delete" | "home" | "end" | "pageUp" | "pageDown" | { "char": string } | { "function": number };
+
+export type Modifiers = { shift: boolean, ctrl: boolean, alt: boolean, 
+/**
+ * Command on macOS, the Windows key elsewhere
+ */
+meta: boolean, };
+
+export type KeyResponse = { "type": "ignored" } | { "type": "handled" } | { "type": "update", results: Array<PluginResult>, } | { "type": "requery", query: string, } | { "type": "release" };
+
+export type SettingsPage = { 
+/**
+ * Identifier, unique within the plugin; also the configuration name
+ */
+id: string, 
+/**
+ * Page title
+ */
+title: string, 
+/**
+ * Sections, in display order
+ */
+sections: Array<SettingsSection>, };
+
+export type SettingsSection = { 
+/**
+ * Identifier, unique within the page
+ */
+id: string, 
+/**
+ * Section title
+ */
+title: string, 
+/**
+ * Help text shown under the title
+ */
+description?: string | null, 
+/**
+ * Fields, in display order
+ */
+fields: Array<SettingsField>, 
+/**
+ * Show the section only when this holds
+ */
+visibleWhen?: Condition | null, };
+
+export type SettingsField = { 
+/**
+ * Key of the value in the saved configuration, unique within the page
+ */
+key: string, 
+/**
+ * Label shown next to the control
+ */
+label: string, 
+/**
+ * Help text shown under the control
+ */
+description?: string | null, 
+/**
+ * How the value is edited
+ */
+control: Control, 
+/**
+ * Show the field only when this holds
+ */
+visibleWhen?: Condition | null, };
+
+export type Control = { "kind": "toggle", 
+/**
+ * Value until the user changes it
+ */
+default: boolean, } | { "kind": "text", 
+/**
+ * Value until the user changes it
+ */
+default: string, 
+/**
+ * Hint shown while the input is empty
+ */
+placeholder?: string | null, 
+/**
+ * Longest accepted value, in characters
+ */
+maxLength?: number | null, 
+/**
+ * Mask the value, e.g. for API tokens
+ */
+secret: boolean, } | { "kind": "number", 
+/**
+ * Value until the user changes it
+ */
+default: number, 
+/**
+ * Smallest accepted value
+ */
+min?: number | null, 
+/**
+ * Largest accepted value
+ */
+max?: number | null, } | { "kind": "select", 
+/**
+ * Value until the user changes it, one of `options`
+ */
+default: string, 
+/**
+ * Available options, in display order
+ */
+options: Array<SelectOption>, } | { "kind": "button", 
+/**
+ * Action identifier passed to the plugin
+ */
+action: string, };
+
+export type SelectOption = { 
+/**
+ * Saved value
+ */
+value: string, 
+/**
+ * Label shown to the user
+ */
+label: string, };
+
+export type Condition = { 
+/**
+ * Key of the field the condition depends on
+ */
+field: string, 
+/**
+ * Value the field must have
+ */
+equals: JsonValue, };
+
+export type MergedResults = { 
+/**
+ * Results in display order
+ */
+results: Array<PluginResult>, 
+/**
+ * Why dev mode plugins returned no results, see `crate::no_results`
+ */
+diagnostics: Array<NoResultsNote>, };
+
+export type NoResultsNote = { 
+/**
+ * Plugin that wrote the note
+ */
+pluginId: string, 
+/**
+ * Why it returned no results
+ */
+reason: NoResultsReason, 
+/**
+ * Explanation for the plugin's author
+ */
+detail: string, };
+
+export type NoResultsReason = "keywordMismatch" | "indexEmpty" | "authExpired" | "offline" | "rateLimited" | "notConfigured" | "other";
+
+export type PluginDescriptor = { 
+/**
+ * Plugin identifier
+ */
+id: string, 
+/**
+ * Human-readable name
+ */
+name: string, 
+/**
+ * Short description
+ */
+description: string, 
+/**
+ * Version of the plugin
+ */
+version: string | null, 
+/**
+ * Author of the plugin
+ */
+author: string | null, 
+/**
+ * Project homepage
+ */
+homepage: string | null, 
+/**
+ * SPDX license identifier
+ */
+license: string | null, };
+
+export type BundleTransition = { 
+/**
+ * Plugins to disable, in order
+ */
+disabled: Array<string>, 
+/**
+ * Plugins to enable, in order
+ */
+enabled: Array<string>, };
+
+export type RegistryEvent = { "type": "pluginRegistered", 
+/**
+ * Plugin identifier
+ */
+pluginId: string, } | { "type": "pluginUnregistered", 
+/**
+ * Plugin identifier
+ */
+pluginId: string, } | { "type": "pluginReloaded", 
+/**
+ * Plugin identifier
+ */
+pluginId: string, } | { "type": "pluginRenamed", 
+/**
+ * Previous plugin identifier
+ */
+from: string, 
+/**
+ * New plugin identifier
+ */
+to: string, } | { "type": "pluginEnabled", 
+/**
+ * Plugin identifier
+ */
+pluginId: string, } | { "type": "pluginDisabled", 
+/**
+ * Plugin identifier
+ */
+pluginId: string, } | { "type": "pluginCrashed", 
+/**
+ * Plugin identifier
+ */
+pluginId: string, 
+/**
+ * Where the crash report was saved
+ */
+reportPath: string, } | { "type": "staleStateCleaned", 
+/**
+ * What was cleaned
+ */
+report: CleanupReport, };
+
+export type CleanupReport = { 
+/**
+ * Plugin processes killed
+ */
+killed: Array<OrphanedProcess>, 
+/**
+ * PID files deleted
+ */
+removedPidFiles: Array<string>, 
+/**
+ * Stale lock files deleted
+ */
+removedLocks: Array<string>, 
+/**
+ * Problems that prevented part of the cleanup
+ */
+errors: Array<string>, };
+
+export type OrphanedProcess = { 
+/**
+ * Plugin run by the process
+ */
+pluginId: string, 
+/**
+ * Process ID
+ */
+pid: number, 
+/**
+ * Program the process ran
+ */
+program: string, };
+
+export type RpcRequest = { 
+/**
+ * Always "2.0"
+ */
+jsonrpc: string, 
+/**
+ * Identifier echoed back in the response
+ */
+id: number, 
+/**
+ * Method to invoke
+ */
+method: string, 
+/**
+ * Method parameters
+ */
+params: JsonValue, };
+
+export type RpcResponse = { 
+/**
+ * Always "2.0"
+ */
+jsonrpc: string, 
+/**
+ * Identifier of the request being answered
+ */
+id: number, 
+/**
+ * Result of a successful call
+ */
+result?: JsonValue | null, 
+/**
+ * Error of a failed call
+ */
+error?: RpcError | null, };
+
+export type RpcError = { 
+/**
+ * Error code, see [`error_codes`]
+ */
+code: number, 
+/**
+ * Human-readable message
+ */
+message: string, 
+/**
+ * Additional error data
+ */
+data?: JsonValue | null, };
+
+export type RemotePluginInfo = { 
+/**
+ * Unique identifier of the plugin
+ */
+id: string, 
+/**
+ * Human-readable name
+ */
+name: string, 
+/**
+ * Short description
+ */
+description: string, 
+/**
+ * Version of the plugin
+ */
+version?: string | null, 
+/**
+ * Author of the plugin
+ */
+author?: string | null, 
+/**
+ * Project homepage
+ */
+homepage?: string | null, 
+/**
+ * SPDX license identifier
+ */
+license?: string | null, };
+
+export type ProtocolFeature = "streaming" | "diffs" | "compression" | "cancellation";
+
+export type HandshakeRequest = { 
+/**
+ * API version the launcher implements
+ */
+apiVersion: number, 
+/**
+ * Features the launcher can use
+ */
+features: Array<ProtocolFeature>, };
+
+export type HandshakeResponse = { 
+/**
+ * API version the host targets
+ */
+apiVersion: number, 
+/**
+ * Features the host agrees to use
+ *
+ * Names the launcher doesn't know, from newer hosts, are ignored.
+ */
+features: Array<string>, 
+/**
+ * Plugins served by the host
+ */
+plugins: Array<HandshakePlugin>, };
+
+export type HandshakePlugin = { 
+/**
+ * Permissions the plugin needs, as declared in manifests
+ */
+capabilities: Array<string>, 
+/**
+ * API version the plugin targets, when it differs from the host's
+ */
+apiVersion?: number | null, 
+/**
+ * Unique identifier of the plugin
+ */
+id: string, 
+/**
+ * Human-readable name
+ */
+name: string, 
+/**
+ * Short description
+ */
+description: string, 
+/**
+ * Version of the plugin
+ */
+version?: string | null, 
+/**
+ * Author of the plugin
+ */
+author?: string | null, 
+/**
+ * Project homepage
+ */
+homepage?: string | null, 
+/**
+ * SPDX license identifier
+ */
+license?: string | null, };
@@ -25,8 +25,52 @@ export interface PluginResult {
   score: number;
   data?: Record<string, unknown>;
   pluginId?: string; // ID of the plugin that created this result
+  accessibility?: ResultAccessibility; // Filled from the title and subtitle if omitted
 }
 
+// What screen readers announce for a result
+export interface ResultAccessibility {
+  label: string; // Read when the result is focused
+  role?: 'item' | 'link' | 'file' | 'command' | 'answer';
+  description?: string; // Longer text read on request
+}
+
+export interface Toast {
+  text: string;
+  style?: 'info' | 'success' | 'warning';
+}
+
+// What the launcher does after a result is executed; returning nothing
+// closes the window
+export interface ExecuteOutcome {
+  closeWindow?: boolean; // Defaults to true
+  requery?: string; // Query to run next in place of the current one
+  message?: Toast;
+  error?: string;
+  captureKeys?: boolean; // Receive key presses in the view opened by requery
+}
+
+// Key pressed while a plugin owns the drill-down view
+export interface KeyEvent {
+  key:
+    | 'up' | 'down' | 'left' | 'right' | 'tab' | 'enter' | 'escape'
+    | 'backspace' | 'delete' | 'home' | 'end' | 'pageUp' | 'pageDown'
+    | { char: string }
+    | { function: number };
+  modifiers?: { shift?: boolean; ctrl?: boolean; alt?: boolean; meta?: boolean };
+  repeat?: boolean;
+  selected?: string; // ID of the selected result
+}
+
+// What the launcher does with a key event; 'ignored' and 'release' get the
+// launcher's usual handling
+export type KeyResponse =
+  | { type: 'ignored' }
+  | { type: 'handled' }
+  | { type: 'update'; results: PluginResult[] }
+  | { type: 'requery'; query: string }
+  | { type: 'release' };
+
 export interface PluginContext {
   query: string;
   settings?: Record<string, unknown>;
@@ -53,7 +97,13 @@ export interface Plugin {
   /**
    * Execute the action for a plugin result
    */
-  execute(result: PluginResult): Promise<void> | void;
+  execute(result: PluginResult): Promise<ExecuteOutcome | void> | ExecuteOutcome | void;
+
+  /**
+   * Handle a key pressed while the plugin owns the drill-down view, after
+   * opting in with `captureKeys`
+   */
+  onKeyEvent?(event: KeyEvent, context: PluginContext): Promise<KeyResponse | void> | KeyResponse | void;
 }
 
 export interface PluginRegistry {
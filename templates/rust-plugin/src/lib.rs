@@ -31,8 +31,8 @@ impl VoltPluginAPI for MyPlugin {
         vec![
             PluginResult {
                 id: "result-1".to_string(),
-                title: "My Result".to_string(),
-                subtitle: Some(format!("You searched for: {}", query)),
+                title: "My Result".into(),
+                subtitle: Some(format!("You searched for: {}", query).into()),
                 score: 100,
                 ..Default::default()
             }